@@ -0,0 +1,98 @@
+//! Benchmark: `sample_indices` strategies across regimes
+//! 
+//! Backs the thresholds in `distributions/index_sample.rs`. For each `n` and
+//! `k`, times Floyd's algorithm with a linear search, with a hash map, and
+//! the partial shuffle, then `sample_indices` (whose choice is marked `*`),
+//! printing nanoseconds per index returned. Hopeless cases are skipped:
+//! Floyd's linear search for `k` above 10^4, and the shuffle for `n` above
+//! 1000 times `k`.
+//! 
+//! Run with (optimisation is required for meaningful numbers):
+//! 
+//!     rustc -O index_sample.rs && ./index_sample
+//! 
+//! Speed is reported, not asserted. The strategies are timed in turn within
+//! each run, since this machine's speed drifted between runs.
+//! 
+//! Thoughts: Floyd's linear search took 6-9 ns per index for `k` of 10-30,
+//! whatever `n`, and overtook the hash map near `k = 64` (the hash map took
+//! about 28-33 ns per index at every `n`, mostly SipHash; a cheaper hasher
+//! would lower `FLOYD_LINEAR_MAX`). The shuffle took 4-8 ns per index once
+//! `n` was within 10x of `k`, and still beat the hash map at 30x; so it is
+//! chosen up to `SHUFFLE_MAX_RATIO = 32`, which also wins at small `n`,
+//! where 30 of 100 took 5 ns against 14 ns for the linear search. `auto`
+//! stayed within noise of the best except just past the ratio: at `n = 10^4`
+//! the shuffle won up to 100x (the whole range fits in cache), but at
+//! `n = 10^6` it lost at 100x, so a fixed ratio is a compromise. Single
+//! indices (`k = 1`) cost 40-55 ns, mostly allocating the result.
+
+#[path = "../distributions/index_sample.rs"]
+#[allow(dead_code)]
+mod index_sample;
+
+use index_sample::{sample_indices, sample_indices_with, strategy, SplitMix, Strategy};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Indices returned per measurement, roughly.
+const WORK: usize = 1 << 20;
+const RUNS: usize = 5;
+
+const WAYS: [(&str, Option<Strategy>); 4] = [
+    ("linear", Some(Strategy::Floyd)),
+    ("hashed", Some(Strategy::FloydHashed)),
+    ("shuffle", Some(Strategy::PartialShuffle)),
+    ("auto", None),
+];
+
+fn main() {
+    let cases: &[(usize, &[usize])] = &[
+        (100, &[1, 10, 30, 50, 100]),
+        (10_000, &[1, 10, 30, 100, 300, 1000, 3000, 10_000]),
+        (1_000_000, &[1, 10, 30, 100, 300, 1000, 10_000, 100_000, 300_000, 1_000_000]),
+        (1 << 40, &[1, 10, 30, 100, 1000, 100_000]),
+    ];
+    println!("{:>14} {:>9} | {:>9} {:>9} {:>9} {:>9}  (ns per index)", "n", "k", "linear", "hashed", "shuffle", "auto");
+    for &(n, ks) in cases {
+        for &k in ks {
+            let reps = (WORK / k).max(1).min(WORK / 64);
+            let mut best = [Duration::MAX; 4];
+            for _ in 0..RUNS {
+                for (i, &(_, way)) in WAYS.iter().enumerate() {
+                    let skip = match way {
+                        Some(Strategy::Floyd) => k > 10_000,
+                        Some(Strategy::PartialShuffle) => n / 1000 > k,
+                        _ => false,
+                    };
+                    if skip {
+                        continue;
+                    }
+                    let mut rng = black_box(SplitMix(0x5EED));
+                    let start = Instant::now();
+                    for _ in 0..reps {
+                        black_box(match way {
+                            Some(s) => sample_indices_with(&mut rng, n, k, s),
+                            None => sample_indices(&mut rng, n, k),
+                        });
+                    }
+                    best[i] = best[i].min(start.elapsed());
+                }
+            }
+            let chosen = strategy(n, k);
+            print!("{:>14} {:>9} |", n, k);
+            for (i, &(_, way)) in WAYS.iter().enumerate() {
+                let mark = if way == Some(chosen) { "*" } else { " " };
+                if best[i] == Duration::MAX {
+                    print!(" {:>9}", "-");
+                } else {
+                    let ns = best[i].as_secs_f64() * 1e9 / (reps * k) as f64;
+                    print!(" {:>8.1}{}", ns, mark);
+                }
+            }
+            println!();
+        }
+    }
+    if cfg!(debug_assertions) {
+        println!("Not optimised: timings are meaningless (build with -O).");
+    }
+}
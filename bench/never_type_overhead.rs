@@ -0,0 +1,336 @@
+//! Benchmark: does the never-type approach really cost nothing?
+//! 
+//! The headers of `traits/raw_rng.rs` and `traits/raw_explicit.rs` claim "no
+//! performance overhead" from unifying the traits via `Result<T, !>`. This
+//! includes those two designs' files (and the `raw_newtype` variant, and
+//! `raw_infallible`, which has `Infallible` for `!`) plus the stable
+//! `marker_only`, `separate_explicit_Rng`, `const_crypto` and
+//! `fallibility_param` designs, runs each one's own test generators over
+//! *the same* backend, and times `next_u32` / `try_next_u32` through every
+//! path each design offers (direct, via blanket impl, via adaptor).
+//! `marker_only` has no `try_next_u32`: its fallible path is `try_fill`
+//! into four bytes.
+//! 
+//! Each path is compared against calling the backend directly; the run fails
+//! if any path is slower than the baseline by more than `TOLERANCE`.
+//! 
+//! Run with (optimisation is required for meaningful numbers):
+//! 
+//!     rustc +nightly -O never_type_overhead.rs && ./never_type_overhead
+//! 
+//! By default the backend is a reference xorshift generator. To repeat the
+//! measurements over production-quality generators, enable the `rand_chacha`
+//! (`ChaCha20Rng`) and/or `rand_pcg` (`Pcg64`) features, passing the crates
+//! (0.3) and `rand_core` (0.6) as externs:
+//! 
+//!     rustc +nightly -O --cfg 'feature="rand_chacha"' --cfg 'feature="rand_pcg"' \
+//!         --extern rand_core=PATH/librand_core.rlib \
+//!         --extern rand_chacha=PATH/librand_chacha.rlib \
+//!         --extern rand_pcg=PATH/librand_pcg.rlib -L PATH never_type_overhead.rs
+//! 
+//! Thoughts: with `-O` every path compiles down to the same loop as the bare
+//! backend, including the `unwrap` in `AsRng` (the `Ok` is always constructed
+//! by an inlined function, so the panic branch is removed). The same holds
+//! over `ChaCha20Rng` and `Pcg64`, so the result is not an artefact of a
+//! trivially inlined toy backend. `raw_infallible` does as well as
+//! `raw_explicit`: the stable `Infallible`, with `match e {}` and
+//! `From<Infallible>` spelled out, is optimised away just as `!` is.
+//! `verify/codegen.rs` checks the same in the assembly, path by path.
+//! Unoptimised builds are dominated by call overhead and noise, so the
+//! check is skipped there.
+
+#![feature(never_type)]
+
+// As `marker_only.rs` declares it at its own crate root
+extern crate core;
+#[cfg(any(feature = "rand_chacha", feature = "rand_pcg"))]
+extern crate rand_core;
+#[cfg(feature = "rand_chacha")]
+extern crate rand_chacha;
+#[cfg(feature = "rand_pcg")]
+extern crate rand_pcg;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Maximum allowed slowdown relative to calling the backend directly.
+const TOLERANCE: f64 = 1.25;
+/// Number of words generated per measurement.
+const N: usize = 20_000_000;
+/// Measurements per path; the fastest is kept.
+const RUNS: usize = 7;
+
+// ——— backends ———
+
+/// A backend shared by all designs.
+pub trait Backend: Clone {
+    fn step(&mut self) -> u32;
+}
+
+/// The default backend: xorshift32.
+#[derive(Debug, Clone)]
+pub struct XorShift32(u32);
+
+impl Backend for XorShift32 {
+    #[inline]
+    fn step(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+}
+
+#[cfg(feature = "rand_chacha")]
+impl Backend for rand_chacha::ChaCha20Rng {
+    #[inline]
+    fn step(&mut self) -> u32 {
+        rand_core::RngCore::next_u32(self)
+    }
+}
+
+#[cfg(feature = "rand_pcg")]
+impl Backend for rand_pcg::Pcg64 {
+    #[inline]
+    fn step(&mut self) -> u32 {
+        rand_core::RngCore::next_u32(self)
+    }
+}
+
+const SEED: u32 = 0x193a_6754;
+
+// ——— designs ———
+
+// The variants themselves, as in `traits/`
+#[path = "../traits/marker_only.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod marker_only;
+#[path = "../traits/separate_explicit_Rng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod separate_explicit_rng;
+#[path = "../traits/raw_rng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_rng;
+#[path = "../traits/raw_explicit.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_explicit;
+#[path = "../traits/raw_infallible.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_infallible;
+#[path = "../traits/raw_newtype.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_newtype;
+#[path = "../traits/const_crypto.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod const_crypto;
+#[path = "../traits/fallibility_param.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod fallibility_param;
+
+/// A backend as the words of each variant's test generators.
+#[derive(Debug, Clone)]
+pub struct Words<B>(pub B);
+
+macro_rules! impl_source {
+    ($($design:ident),*) => {
+        $(
+            impl<B: Backend> $design::test_rngs::Source for Words<B> {
+                #[inline]
+                fn try_word(&mut self) -> Result<u64, $design::test_rngs::Exhausted> {
+                    Ok(self.0.step() as u64)
+                }
+            }
+        )*
+    };
+}
+
+impl_source!(marker_only, separate_explicit_rng, raw_rng, raw_explicit, raw_infallible,
+    raw_newtype, const_crypto, fallibility_param);
+
+// ——— harness ———
+
+/// Time `N` evaluations of `$call`, keeping the fastest of `RUNS`.
+/// 
+/// Each run starts from fresh state `$init`. The sum of outputs is checked
+/// against `$expected` so that all paths are known to produce the same stream
+/// (and the optimiser can't drop the work).
+macro_rules! bench {
+    ($results:ident, $name:expr, $expected:expr, $init:expr, |$r:ident| $call:expr) => ({
+        let mut best = Duration::from_secs(u64::max_value());
+        for _ in 0..RUNS {
+            let mut $r = black_box($init);
+            let start = Instant::now();
+            let mut sum = 0u32;
+            for _ in 0..N {
+                let $r = &mut $r;
+                sum = sum.wrapping_add($call);
+            }
+            let elapsed = start.elapsed();
+            assert_eq!(black_box(sum), $expected, "stream differs from backend");
+            if elapsed < best {
+                best = elapsed;
+            }
+        }
+        $results.push(($name, best));
+    });
+}
+
+/// Run every path of every design over `backend`.
+fn run<B: Backend>(backend: B) -> Vec<(&'static str, Duration)> {
+    use marker_only::Rng as MRng;
+    use separate_explicit_rng::{Rng as SRng, CryptoRng as SCryptoRng};
+    use raw_rng::{Rng as RRng, RawRng as RRawRng};
+    use raw_explicit::{Rng as ERng, RawRng as ERawRng};
+    use raw_newtype::{Rng as NRng, RawRng as NRawRng};
+    use raw_infallible::{Rng as IRng, RawRng as IRawRng};
+    
+    let expected = {
+        let mut x = backend.clone();
+        (0..N).fold(0u32, |s, _| s.wrapping_add(x.step()))
+    };
+    let words = || Words(backend.clone());
+    
+    let mut results = vec![];
+    bench!(results, "baseline: Backend::step", expected,
+        backend.clone(), |r| r.step());
+    
+    {
+        use marker_only::test_rngs::TestRng;
+        bench!(results, "marker_only: Rng::next_u32", expected,
+            TestRng(words()), |r| r.next_u32());
+        bench!(results, "marker_only: Rng::try_fill([u8; 4])", expected,
+            TestRng(words()), |r| {
+                let mut buf = [0u8; 4];
+                r.try_fill(&mut buf).unwrap();
+                u32::from_le_bytes(buf)
+            });
+    }
+    
+    {
+        use separate_explicit_rng::as_rng;
+        use separate_explicit_rng::test_rngs::{TestCRng, TestRng};
+        bench!(results, "separate_explicit_Rng: Rng::next_u32", expected,
+            TestRng(words()), |r| r.next_u32());
+        bench!(results, "separate_explicit_Rng: CryptoRng::try_next_u32", expected,
+            TestCRng(words()), |r| r.try_next_u32().unwrap());
+        bench!(results, "separate_explicit_Rng: as_rng(CryptoRng)::next_u32", expected,
+            TestCRng(words()), |r| as_rng(r).next_u32());
+    }
+    
+    {
+        use raw_rng::as_rng;
+        use raw_rng::test_rngs::{TestCRng, TestRng};
+        bench!(results, "raw_rng: Rng::next_u32", expected,
+            TestRng(words()), |r| r.next_u32());
+        bench!(results, "raw_rng: RawRng<!>::try_next_u32", expected,
+            TestRng(words()), |r| RRawRng::<!>::try_next_u32(r).unwrap_or_else(|e| e));
+        bench!(results, "raw_rng: RawRng<CryptoError>::try_next_u32", expected,
+            TestCRng(words()), |r| r.try_next_u32().unwrap());
+        bench!(results, "raw_rng: as_rng(RawRng<CryptoError>)::next_u32", expected,
+            TestCRng(words()), |r| as_rng(r).next_u32());
+    }
+    
+    {
+        use raw_explicit::{as_rng_ref, Error};
+        use raw_explicit::test_rngs::{TestFCRng, TestRng};
+        bench!(results, "raw_explicit: Rng::next_u32", expected,
+            TestRng(words()), |r| r.next_u32());
+        bench!(results, "raw_explicit: RawRng<Error>::try_next_u32 (infallible)", expected,
+            TestRng(words()), |r| ERawRng::<Error>::try_next_u32(r).unwrap());
+        bench!(results, "raw_explicit: RawRng<Error>::try_next_u32 (fallible)", expected,
+            TestFCRng(words()), |r| r.try_next_u32().unwrap());
+        bench!(results, "raw_explicit: as_rng_ref(RawRng<Error>)::next_u32", expected,
+            TestFCRng(words()), |r| as_rng_ref(r).next_u32());
+    }
+    
+    {
+        use std::convert::Infallible;
+        use raw_infallible::{as_rng_ref, Error};
+        use raw_infallible::test_rngs::{TestFCRng, TestRng};
+        bench!(results, "raw_infallible: Rng::next_u32", expected,
+            TestRng(words()), |r| r.next_u32());
+        bench!(results, "raw_infallible: RawRng<Infallible>::try_next_u32", expected,
+            TestRng(words()),
+            |r| IRawRng::<Infallible>::try_next_u32(r).unwrap_or_else(|e| match e {}));
+        bench!(results, "raw_infallible: RawRng<Error>::try_next_u32 (infallible)", expected,
+            TestRng(words()), |r| IRawRng::<Error>::try_next_u32(r).unwrap());
+        bench!(results, "raw_infallible: RawRng<Error>::try_next_u32 (fallible)", expected,
+            TestFCRng(words()), |r| r.try_next_u32().unwrap());
+        bench!(results, "raw_infallible: as_rng_ref(RawRng<Error>)::next_u32", expected,
+            TestFCRng(words()), |r| as_rng_ref(r).next_u32());
+    }
+    
+    {
+        use raw_newtype::Infallible;
+        use raw_newtype::test_rngs::TestRng;
+        bench!(results, "raw_newtype: Rng::next_u32", expected,
+            TestRng(words()), |r| r.next_u32());
+        bench!(results, "raw_newtype: Infallible(&mut Rng)::try_next_u32", expected,
+            TestRng(words()), |r| Infallible(r).try_next_u32().unwrap());
+    }
+    
+    {
+        use const_crypto::Rng;
+        use const_crypto::test_rngs::{TestCRng, TestRng};
+        bench!(results, "const_crypto: Rng<false>::next_u32", expected,
+            TestRng(words()), |r| Rng::next_u32(r));
+        bench!(results, "const_crypto: Rng<true>::try_next_u32", expected,
+            TestCRng(words()), |r| Rng::<true>::try_next_u32(r).unwrap());
+        bench!(results, "const_crypto: Rng<false>::next_u32 (crypto, blanket)", expected,
+            TestCRng(words()), |r| Rng::<false>::next_u32(r));
+    }
+    
+    {
+        use fallibility_param::{as_rng_ref, Rng, Fallible, Infallible};
+        use fallibility_param::test_rngs::{TestFCRng, TestRng};
+        bench!(results, "fallibility_param: Rng<Infallible>::next_u32", expected,
+            TestRng(words()), |r| Rng::<Infallible>::next_u32(r));
+        bench!(results, "fallibility_param: Rng<Fallible>::next_u32 (infallible)", expected,
+            TestRng(words()), |r| Rng::<Fallible>::next_u32(r).unwrap());
+        bench!(results, "fallibility_param: Rng<Fallible>::next_u32 (fallible)", expected,
+            TestFCRng(words()), |r| r.next_u32().unwrap());
+        bench!(results, "fallibility_param: as_rng_ref(Rng<Fallible>)::next_u32", expected,
+            TestFCRng(words()), |r| Rng::<Infallible>::next_u32(&mut as_rng_ref(r)));
+    }
+    
+    results
+}
+
+/// Print results relative to the baseline; return paths out of tolerance.
+fn report(backend: &str, results: &[(&'static str, Duration)]) -> Vec<String> {
+    println!("——— backend: {} ———", backend);
+    let baseline = results[0].1;
+    let mut failed = vec![];
+    for &(name, d) in results {
+        let ratio = d.as_secs_f64() / baseline.as_secs_f64();
+        println!("{:<60} {:>8.2} ms  ({:.2}x)", name, d.as_secs_f64() * 1e3, ratio);
+        if ratio > TOLERANCE {
+            failed.push(format!("{}: {}", backend, name));
+        }
+    }
+    failed
+}
+
+fn main() {
+    #[allow(unused_mut)]
+    let mut failed = report("XorShift32", &run(XorShift32(SEED)));
+    #[cfg(feature = "rand_chacha")] {
+        use rand_core::SeedableRng;
+        let rng = rand_chacha::ChaCha20Rng::seed_from_u64(SEED as u64);
+        failed.extend(report("ChaCha20Rng", &run(rng)));
+    }
+    #[cfg(feature = "rand_pcg")] {
+        use rand_core::SeedableRng;
+        let rng = rand_pcg::Pcg64::seed_from_u64(SEED as u64);
+        failed.extend(report("Pcg64", &run(rng)));
+    }
+    
+    if cfg!(debug_assertions) {
+        println!("Not optimised: skipping tolerance check (build with -O).");
+    } else {
+        assert!(failed.is_empty(), "slower than {}x baseline: {:?}", TOLERANCE, failed);
+        println!("All paths within {}x of baseline.", TOLERANCE);
+    }
+}
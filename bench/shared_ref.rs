@@ -0,0 +1,113 @@
+//! Benchmark: `&self` generators against `&mut self`
+//! 
+//! Backs the claim in `traits/shared_ref.rs` about what interior mutability
+//! costs a generator. The same SplitMix64 arithmetic, five ways:
+//! 
+//! *   `&mut`: `test_rngs::SplitMix` through `Source::word(&mut self)`, the
+//!     signature of every other variant
+//! *   `Cell`: `CellSplitMix`, its state a `Cell<u64>`
+//! *   `RefCell`: `TestRng(RefCell<SplitMix>)`, a `&mut` generator held for
+//!     `&self`, borrowed once per word
+//! *   `atomic`: `AtomicSplitMix`, a `fetch_add` per word, from one thread
+//! *   `thread_rng`: the thread-local `CellSplitMix`, looked up per word
+//! 
+//! each timed summing `WORDS` words, and filling `BYTES` bytes. All five
+//! give the same words (the thread-local generator from its own seed), so
+//! the sums are checked to agree.
+//! 
+//! Run with (optimisation is required for meaningful numbers):
+//! 
+//!     rustc -O shared_ref.rs && ./shared_ref
+//! 
+//! Thoughts: see the variant. Only the inlined loop is timed, where `Cell`
+//! could have cost the most, by denying the optimiser the `noalias` that
+//! `&mut` gives; behind a call boundary every way loads and stores its state
+//! per call anyway.
+
+#[path = "../traits/shared_ref.rs"]
+#[allow(dead_code)]
+mod shared_ref;
+
+use shared_ref::test_rngs::{Source, SplitMix, TestRng};
+use shared_ref::{thread_rng, AtomicSplitMix, CellSplitMix, Rng};
+use std::cell::RefCell;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SEED: u64 = 0x5EED;
+const WORDS: usize = 1 << 24;
+const BYTES: usize = 1 << 23;
+const RUNS: usize = 11;
+
+/// Best of RUNS, and the last result.
+fn time<T, F: FnMut() -> T>(mut f: F) -> (Duration, T) {
+    let mut best = Duration::MAX;
+    let mut result = None;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let r = black_box(f());
+        best = best.min(start.elapsed());
+        result = Some(r);
+    }
+    (best, result.unwrap())
+}
+
+fn sum<F: FnMut() -> u64>(mut next: F) -> u64 {
+    (0..WORDS).fold(0u64, |acc, _| acc.wrapping_add(next()))
+}
+
+fn sum_shared<R: Rng>(rng: &R) -> u64 {
+    sum(|| rng.next_u64())
+}
+
+fn fill_shared<R: Rng>(rng: &R) -> u8 {
+    let mut buf = vec![0u8; BYTES];
+    rng.fill(&mut buf);
+    buf[BYTES - 1]
+}
+
+fn report(name: &str, words: Duration, bytes: Duration, base: (Duration, Duration)) {
+    println!("{:<12} sum {:>10.2?} ({:.2}x)   fill {:>10.2?} ({:.2}x)", name,
+        words, words.as_secs_f64() / base.0.as_secs_f64(),
+        bytes, bytes.as_secs_f64() / base.1.as_secs_f64());
+}
+
+fn main() {
+    let (w, want) = time(|| {
+        let mut s = black_box(SplitMix(SEED));
+        sum(|| s.word())
+    });
+    let (b, last) = time(|| {
+        let mut s = black_box(SplitMix(SEED));
+        let mut buf = vec![0u8; BYTES];
+        for chunk in buf.chunks_mut(8) {
+            chunk.copy_from_slice(&s.word().to_le_bytes());
+        }
+        buf[BYTES - 1]
+    });
+    let base = (w, b);
+    report("&mut", w, b, base);
+    
+    let (w, got) = time(|| sum_shared(&black_box(CellSplitMix::new(SEED))));
+    let (b, l) = time(|| fill_shared(&black_box(CellSplitMix::new(SEED))));
+    assert!(got == want && l == last);
+    report("Cell", w, b, base);
+    
+    let (w, got) = time(|| sum_shared(&black_box(TestRng(RefCell::new(SplitMix(SEED))))));
+    let (b, l) = time(|| fill_shared(&black_box(TestRng(RefCell::new(SplitMix(SEED))))));
+    assert!(got == want && l == last);
+    report("RefCell", w, b, base);
+    
+    let (w, got) = time(|| sum_shared(&black_box(AtomicSplitMix::new(SEED))));
+    let (b, l) = time(|| fill_shared(&black_box(AtomicSplitMix::new(SEED))));
+    assert!(got == want && l == last);
+    report("atomic", w, b, base);
+    
+    let (w, _) = time(|| sum_shared(&thread_rng()));
+    let (b, _) = time(|| fill_shared(&thread_rng()));
+    report("thread_rng", w, b, base);
+    
+    if cfg!(debug_assertions) {
+        println!("Not optimised: timings are meaningless (build with -O).");
+    }
+}
@@ -0,0 +1,120 @@
+//! Benchmark: cumulative sums vs alias table, build plus draws
+//! 
+//! Finds the crossover claimed in `distributions/weighted.rs`: the alias
+//! table costs more to build but less per draw, so it wins once enough draws
+//! are taken from each build. First the two costs alone, per draw and per
+//! weight built, for each `n`. Then for `n` weights and `k` draws per build,
+//! both samplers build from the same weights then draw `k` indices,
+//! repeatedly; the table shows the cumulative sampler's time over the alias
+//! table's (so above 1 the alias table is faster), and for each `n` the
+//! smallest `k` at which the alias table wins.
+//! 
+//! Run with (optimisation is required for meaningful numbers):
+//! 
+//!     rustc -O weighted.rs && ./weighted
+//! 
+//! Speed is reported, not asserted. The two samplers are timed in turn
+//! within each run, since this machine's speed drifted between runs.
+//! 
+//! Thoughts: a draw from the alias table took about 3 ns at every `n`; from
+//! the cumulative sums, 6-7 ns for small `n`, rising to 19 ns at 4096 as the
+//! binary search mispredicts. Building the alias table took about 6 ns per
+//! weight against 1-2 ns, and at small `n` its allocations (five buffers
+//! against one) dominate both. So the crossover was at 32-256 draws per
+//! build up to `n = 256`, and near `k = n` beyond. The alias table's select
+//! must be branchless: as a branch it mispredicted as often as the split is
+//! even, a draw took 7-9 ns, and the alias table lost at every `n` below 256.
+
+#[path = "../distributions/weighted.rs"]
+#[allow(dead_code)]
+mod weighted;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use weighted::{Rng, SplitMix, WeightedAliasIndex, WeightedIndex};
+
+/// Builds plus draws per cell: about this many steps of work.
+const WORK: usize = 1 << 21;
+const RUNS: usize = 7;
+const NS: &[usize] = &[4, 16, 64, 256, 1024, 4096];
+const KS: &[usize] = &[1, 2, 4, 8, 16, 32, 64, 256, 4096];
+
+/// Best of RUNS for `cumulative` and `alias`, timed in turn.
+fn time<F: Fn(&mut SplitMix) -> usize, G: Fn(&mut SplitMix) -> usize>(cumulative: F, alias: G)
+    -> (Duration, Duration)
+{
+    let (mut c, mut a) = (Duration::MAX, Duration::MAX);
+    for _ in 0..RUNS {
+        let mut rng = black_box(SplitMix(0x5EED));
+        let start = Instant::now();
+        black_box(cumulative(&mut rng));
+        c = c.min(start.elapsed());
+        let mut rng = black_box(SplitMix(0x5EED));
+        let start = Instant::now();
+        black_box(alias(&mut rng));
+        a = a.min(start.elapsed());
+    }
+    (c, a)
+}
+
+fn main() {
+    let mut rng = SplitMix(1);
+    for &n in NS {
+        let weights: Vec<u32> = (0..n).map(|_| rng.next_u32() % 1000 + 1).collect();
+        let (c, a) = (WeightedIndex::new(&weights).unwrap(), WeightedAliasIndex::new(&weights).unwrap());
+        let (dc, da) = time(
+            |rng| (0..WORK).map(|_| c.sample(rng)).sum(),
+            |rng| (0..WORK).map(|_| a.sample(rng)).sum());
+        let reps = WORK / n;
+        let (bc, ba) = time(
+            |_| (0..reps).map(|_| WeightedIndex::new(black_box(&weights)).unwrap().weights().len()).sum(),
+            |_| (0..reps).map(|_| WeightedAliasIndex::new(black_box(&weights)).unwrap().weights().len()).sum());
+        let ns = |t: Duration, count: usize| t.as_secs_f64() * 1e9 / count as f64;
+        println!("n = {:>4}: per draw {:>5.1} ns cumulative, {:>5.1} ns alias; per weight built {:>5.1} ns, {:>5.1} ns",
+            n, ns(dc, WORK), ns(da, WORK), ns(bc, reps * n), ns(ba, reps * n));
+    }
+    println!();
+    
+    print!("{:>6} |", "n \\ k");
+    for k in KS {
+        print!("{:>6}", k);
+    }
+    println!(" | crossover");
+    for &n in NS {
+        let weights: Vec<u32> = (0..n).map(|_| rng.next_u32() % 1000 + 1).collect();
+        let mut crossover = None;
+        print!("{:>6} |", n);
+        for &k in KS {
+            let reps = (WORK / (n + k)).max(1);
+            let (c, a) = time(
+                |rng| {
+                    let mut sum = 0;
+                    for _ in 0..reps {
+                        let d = WeightedIndex::new(black_box(&weights)).unwrap();
+                        sum += (0..k).map(|_| d.sample(rng)).sum::<usize>();
+                    }
+                    sum
+                },
+                |rng| {
+                    let mut sum = 0;
+                    for _ in 0..reps {
+                        let d = WeightedAliasIndex::new(black_box(&weights)).unwrap();
+                        sum += (0..k).map(|_| d.sample(rng)).sum::<usize>();
+                    }
+                    sum
+                });
+            let ratio = c.as_secs_f64() / a.as_secs_f64();
+            if ratio > 1.0 && crossover.is_none() {
+                crossover = Some(k);
+            }
+            print!("{:>6.2}", ratio);
+        }
+        match crossover {
+            Some(k) => println!(" | {}", k),
+            None => println!(" | none"),
+        }
+    }
+    if cfg!(debug_assertions) {
+        println!("Not optimised: timings are meaningless (build with -O).");
+    }
+}
@@ -0,0 +1,68 @@
+//! `impl_fill_from_u64`, safe against the pointer copy it replaced
+//! 
+//! `traits/marker_only.rs` fills with `chunks_exact_mut`, shifting each
+//! word's bytes out in turn (not `to_le_bytes`, whose array an unoptimised
+//! build copies where `secure-helpers` can't wipe it); the old body, with
+//! `copy_nonoverlapping` from the word's address, is kept there as
+//! `impl_fill_from_u64_ptr` behind the `unsafe-fill` feature. Both fill from
+//! `marker_only`'s `TestRng` over the `SplitMix` source, for lengths of less
+//! than a word, a word-multiple, and a multiple plus a tail. Before
+//! measuring, `main` checks that the two write the same bytes and use the
+//! same words.
+//! 
+//! Run with:
+//! 
+//!     cargo bench --features unsafe-fill --bench fill_helpers
+//! 
+//! Thoughts: better than parity. Over two runs the safe version took
+//! 285-340 ns for 1 KiB against 420-580 ns for the pointer copy, and 21 ns
+//! for 64 bytes against 30-35 ns; 7 bytes, one partial word, is 3-4.5 ns for
+//! either. The difference is likely the loop, not the copy: the pointer
+//! version takes `min(len - pos, 8)` each step, a copy of variable length,
+//! where `chunks_exact_mut` gives every chunk but the tail a fixed eight
+//! bytes, whose shifts the optimiser can merge into one store. So the
+//! `unsafe` cost speed as well as review, and the feature can go once the
+//! comparison isn't wanted.
+
+extern crate criterion;
+extern crate rand_design;
+
+use std::time::Duration;
+
+use criterion::{black_box, Criterion};
+use rand_design::marker_only::test_rngs::{SplitMix, TestRng};
+use rand_design::marker_only::{impl_fill_from_u64, impl_fill_from_u64_ptr};
+
+const SEED: u64 = 0x0123_4567_89ab_cdef;
+const LENS: [usize; 4] = [7, 64, 1024, 1027];
+
+fn main() {
+    // Parity of output first: the same bytes, and the same words drawn
+    for &len in LENS.iter() {
+        let (mut a, mut b) = (TestRng(SplitMix(SEED)), TestRng(SplitMix(SEED)));
+        let (mut x, mut y) = (vec![0u8; len], vec![0u8; len]);
+        impl_fill_from_u64(&mut a, &mut x);
+        impl_fill_from_u64_ptr(&mut b, &mut y);
+        assert!(x == y && a.0 == b.0, "impl_fill_from_u64: differs at length {}", len);
+    }
+    
+    let mut c = Criterion::default()
+        .warm_up_time(Duration::from_millis(300))
+        .measurement_time(Duration::from_secs(1))
+        .configure_from_args();
+    let mut g = c.benchmark_group("fill_from_u64");
+    for &len in LENS.iter() {
+        let mut rng = TestRng(SplitMix(SEED));
+        let mut buf = vec![0u8; len];
+        g.bench_function(format!("safe/{}", len), |b| b.iter(|| {
+            impl_fill_from_u64(&mut rng, &mut buf);
+            black_box(&mut buf);
+        }));
+        g.bench_function(format!("ptr/{}", len), |b| b.iter(|| {
+            impl_fill_from_u64_ptr(&mut rng, &mut buf);
+            black_box(&mut buf);
+        }));
+    }
+    g.finish();
+    c.final_summary();
+}
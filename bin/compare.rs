@@ -0,0 +1,478 @@
+//! Capability matrix: what each trait design supports, machine-checked
+//! 
+//! The "Thoughts" at the top of each variant say what it can and cannot do;
+//! this checks them. For every variant relating `Rng` and `CryptoRng`, and
+//! every capability, a small probe is appended to a copy of the variant's
+//! source, compiled with `rustc` and run; each cell reads `yes` (compiles
+//! and runs), `no` (the design rejects it) or `panics`. The capabilities
+//! (the rows of the legend) are static and dynamic dispatch through each
+//! trait, a plain generator accepted as crypto (a hole, not a feature),
+//! dual-impl types, adaptors each way, `&mut R` and `Box<R>` forwarding,
+//! generators of each seeding design, block generators, `OsRng`, a
+//! `thread_rng()`-style shared handle, and whether a crypto generator's
+//! failure reaches the caller and can be recovered from. Probes use the
+//! shared generators of `traits/test_rngs.rs`; the variants on other
+//! subjects (`async_rng`, `locked` and the like) are not included.
+//! 
+//! Run with:
+//! 
+//!     cargo run --bin compare [-- --verbose]
+//! 
+//! Variants needing unstable features are compiled with `rustc +nightly`;
+//! without a nightly toolchain their rows read `n/a`. `--verbose` prints the
+//! first error of each rejected probe, to check that a `no` is the design's
+//! doing and not the probe's; the `no` cells of the dual-impl and
+//! plain-as-crypto rows are also `trybuild` cases in `tests/impl_conflicts`.
+//! 
+//! Thoughts: four designs (`extends_CryptoRng` 1-3, `separate_implicit_Rng`)
+//! accept any plain generator as crypto. Only `marker_only`,
+//! `negative_impls` and `specialization` combine crypto-as-`Rng`, dual-impl
+//! types and errors reaching the caller. Where forwarding is missing, a
+//! blanket impl is in the way. Seeding, blocks, the OS generator and shared
+//! handles work everywhere, and every design passing errors on recovers.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// ——— variants ———
+
+/// How to name one variant's traits and test generators.
+struct Variant {
+    name: &'static str,
+    file: &'static str,
+    nightly: bool,
+    /// Bound for plain use, and a call through it
+    plain: &'static str,
+    plain_call: &'static str,
+    /// Bound for crypto use, and a call through it
+    crypto: &'static str,
+    crypto_call: &'static str,
+    /// The crypto role in `test_rngs`
+    crypto_rng: &'static str,
+    /// Impls making `ProbeDual(u32)` plain and crypto
+    plain_impl: &'static str,
+    crypto_impl: &'static str,
+}
+
+const PLAIN_IMPL: &str = "impl Rng for ProbeDual { fn next_u32(&mut self) -> u32 { self.0 } }";
+const CRYPTO_IMPL: &str = "impl CryptoRng for ProbeDual { \
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> { Ok(self.0) } }";
+
+/// A variant with the common `Rng::next_u32` / `CryptoRng::try_next_u32`.
+const fn common(name: &'static str, file: &'static str) -> Variant {
+    Variant {
+        name, file, nightly: false,
+        plain: "Rng", plain_call: "next_u32()",
+        crypto: "CryptoRng", crypto_call: "try_next_u32()", crypto_rng: "TestCRng",
+        plain_impl: PLAIN_IMPL, crypto_impl: CRYPTO_IMPL,
+    }
+}
+
+const RAW_CRYPTO_IMPL: &str = "impl RawRng<Error> for ProbeDual { \
+    fn try_next_u32(&mut self) -> Result<u32, Error> { Ok(self.0) } } \
+    impl CryptoRng<Error> for ProbeDual {}";
+
+const VARIANTS: &[Variant] = &[
+    Variant {
+        crypto: "TryRng<Error = CryptoError>",
+        plain_impl: "impl TryRng for ProbeDual { type Error = Infallible; \
+            fn try_next_u32(&mut self) -> Result<u32, Infallible> { Ok(self.0) } }",
+        crypto_impl: "impl TryRng for ProbeDual { type Error = CryptoError; \
+            fn try_next_u32(&mut self) -> Result<u32, CryptoError> { Ok(self.0) } }",
+        ..common("assoc_error", "assoc_error.rs")
+    },
+    Variant {
+        nightly: true,
+        crypto: "Rng + CryptoRng", crypto_call: "next_u32()",
+        crypto_impl: "",
+        ..common("auto_trait_CryptoRng", "auto_trait_CryptoRng.rs")
+    },
+    Variant {
+        crypto: "Rng<true>", crypto_call: "next_u32()",
+        crypto_impl: "impl Rng<true> for ProbeDual { fn next_u32(&mut self) -> u32 { self.0 } }",
+        ..common("const_crypto", "const_crypto.rs")
+    },
+    common("extends_CryptoRng", "extends_CryptoRng.rs"),
+    common("extends_CryptoRng2", "extends_CryptoRng2.rs"),
+    common("extends_CryptoRng3", "extends_CryptoRng3.rs"),
+    common("extends_Rng", "extends_Rng.rs"),
+    common("extends_Rng2", "extends_Rng2.rs"),
+    Variant {
+        crypto_call: "next_u32()",
+        crypto_impl: "impl CryptoRng for ProbeDual {}",
+        ..common("extends_Rng_infallible", "extends_Rng_infallible.rs")
+    },
+    Variant {
+        plain: "Rng<Infallible>",
+        crypto: "CryptoRng<Fallible>", crypto_call: "next_u32()", crypto_rng: "TestFCRng",
+        plain_impl: "impl Rng<Infallible> for ProbeDual { fn next_u32(&mut self) -> u32 { self.0 } }",
+        crypto_impl: "impl Rng<Fallible> for ProbeDual { \
+            fn next_u32(&mut self) -> Result<u32, Error> { Ok(self.0) } } \
+            impl CryptoRng<Fallible> for ProbeDual {}",
+        ..common("fallibility_param", "fallibility_param.rs")
+    },
+    Variant {
+        plain: "Gen<Plain>",
+        crypto: "Gen<Crypto>", crypto_call: "next_u32()",
+        plain_impl: "impl Gen<Plain> for ProbeDual { fn next_u32(&mut self) -> u32 { self.0 } }",
+        crypto_impl: "impl Gen<Crypto> for ProbeDual { fn next_u32(&mut self) -> u32 { self.0 } }",
+        ..common("kind_param", "kind_param.rs")
+    },
+    Variant {
+        plain_call: "next_u64()",
+        crypto_call: "try_fill(&mut [0u8; 8])",
+        plain_impl: "impl Rng for ProbeDual { \
+            fn try_fill(&mut self, d: &mut [u8]) -> Result<(), Error> { d.fill(self.0 as u8); Ok(()) } \
+            fn next_u64(&mut self) -> u64 { self.0 as u64 } }",
+        crypto_impl: "impl CryptoRng for ProbeDual {}",
+        ..common("marker_only", "marker_only.rs")
+    },
+    Variant {
+        nightly: true,
+        plain_impl: "impl NativeRng for ProbeDual {} \
+            impl Rng for ProbeDual { fn next_u32(&mut self) -> u32 { self.0 } }",
+        ..common("negative_impls", "negative_impls.rs")
+    },
+    Variant {
+        crypto_rng: "TestFCRng",
+        crypto_impl: "impl RawRng for ProbeDual { \
+            fn try_next_u32(&mut self) -> Result<u32, Error> { Ok(self.0) } } \
+            impl CryptoRng for ProbeDual {}",
+        ..common("raw_default", "raw_default.rs")
+    },
+    Variant {
+        nightly: true,
+        crypto: "CryptoRng<Error>", crypto_rng: "TestFCRng",
+        crypto_impl: RAW_CRYPTO_IMPL,
+        ..common("raw_explicit", "raw_explicit.rs")
+    },
+    Variant {
+        crypto: "CryptoRng<Error>", crypto_rng: "TestFCRng",
+        crypto_impl: RAW_CRYPTO_IMPL,
+        ..common("raw_infallible", "raw_infallible.rs")
+    },
+    Variant {
+        nightly: true,
+        crypto: "CryptoRng<Error>", crypto_rng: "TestFCRng",
+        crypto_impl: RAW_CRYPTO_IMPL,
+        ..common("raw_newtype", "raw_newtype.rs")
+    },
+    Variant {
+        nightly: true,
+        crypto: "RawRng<CryptoError>",
+        crypto_impl: "impl RawRng<CryptoError> for ProbeDual { \
+            fn try_next_u32(&mut self) -> Result<u32, CryptoError> { Ok(self.0) } }",
+        ..common("raw_rng", "raw_rng.rs")
+    },
+    common("separate_explicit_Rng", "separate_explicit_Rng.rs"),
+    common("separate_implicit_Rng", "separate_implicit_Rng.rs"),
+    common("separate_implicit_Rng2", "separate_implicit_Rng2.rs"),
+    common("separate_implicit_Rng3", "separate_implicit_Rng3.rs"),
+    Variant { nightly: true, ..common("specialization", "specialization.rs") },
+];
+
+// ——— probes ———
+
+/// One capability: the body of `main`, in alternative spellings, where `t`
+/// is a plain and `c` a crypto generator and `plain`, `crypto` take `&mut`
+/// of either trait.
+struct Capability {
+    name: &'static str,
+    bodies: &'static [&'static str],
+}
+
+const CAPABILITIES: &[Capability] = &[
+    Capability { name: "static: plain as Rng", bodies: &["plain(&mut t);"] },
+    Capability { name: "static: crypto as CryptoRng", bodies: &["crypto(&mut c);"] },
+    Capability { name: "static: crypto as Rng", bodies: &["plain(&mut c);"] },
+    Capability { name: "static: plain as CryptoRng (a hole)", bodies: &["crypto(&mut t);"] },
+    Capability { name: "dyn Rng from plain", bodies: &["let r: &mut (dyn PLAIN) = &mut t; plain(r);"] },
+    Capability { name: "dyn Rng from crypto", bodies: &["let r: &mut (dyn PLAIN) = &mut c; plain(r);"] },
+    Capability { name: "dyn CryptoRng from crypto", bodies: &["let r: &mut (dyn CRYPTO) = &mut c; crypto(r);"] },
+    Capability {
+        name: "dyn CryptoRng used as Rng",
+        bodies: &[
+            "let r: &mut (dyn CRYPTO) = &mut c; plain(r);",
+            "let mut r: &mut (dyn CRYPTO) = &mut c; plain(&mut r);",
+        ],
+    },
+    Capability {
+        name: "dual-impl type",
+        bodies: &["struct ProbeDual(u32); PLAIN_IMPL CRYPTO_IMPL \
+            let mut d = ProbeDual(3); plain(&mut d); crypto(&mut d);"],
+    },
+    Capability {
+        name: "adaptor: crypto to Rng",
+        bodies: &[
+            "let mut a = as_rng(&mut c); plain(&mut a);",
+            "let mut a = as_rng_ref(&mut c); plain(&mut a);",
+            "let mut a = AsRng { rng: &mut c }; plain(&mut a);",
+        ],
+    },
+    Capability {
+        name: "adaptor: plain to CryptoRng",
+        bodies: &[
+            "let mut a = as_crng(&mut t); crypto(&mut a);",
+            "let mut a = as_crng_ref(&mut t); crypto(&mut a);",
+            "let mut a = assume_crypto(&mut t); crypto(&mut a);",
+        ],
+    },
+    Capability {
+        name: "&mut forwarding: Rng",
+        bodies: &["fn by_value<R: PLAIN>(mut r: R) { let _ = r.PLAIN_CALL; } by_value(&mut t);"],
+    },
+    Capability {
+        name: "&mut forwarding: CryptoRng",
+        bodies: &["fn by_value<R: CRYPTO>(mut r: R) { let _ = r.CRYPTO_CALL; } by_value(&mut c);"],
+    },
+    Capability {
+        name: "Box forwarding: Rng",
+        bodies: &["fn by_value<R: PLAIN>(mut r: R) { let _ = r.PLAIN_CALL; } \
+            let b: Box<dyn PLAIN> = Box::new(t); by_value(b);"],
+    },
+    Capability {
+        name: "Box forwarding: CryptoRng",
+        bodies: &["fn by_value<R: CRYPTO>(mut r: R) { let _ = r.CRYPTO_CALL; } \
+            let b: Box<dyn CRYPTO> = Box::new(c); by_value(b);"],
+    },
+    Capability {
+        name: "seeded generators",
+        bodies: &["use test_rngs::seeding::{FromSeedSlice, SeedFrom, SeedableRng}; \
+            let mut a = test_rngs::TestRng::<test_rngs::SplitMix>::seed_from_u64(1); plain(&mut a); \
+            let mut b = test_rngs::CRYPTO_RNG::<test_rngs::SplitMix>::from_seed_slice(&[1; 8]).unwrap(); \
+            crypto(&mut b); \
+            let mut d = test_rngs::CRYPTO_RNG::<test_rngs::SplitMix>::seed_from(1u64); crypto(&mut d);"],
+    },
+    Capability {
+        name: "block generators",
+        bodies: &["use test_rngs::block::BlockRng; use test_rngs::{Source, SplitMix, SplitMixCore}; \
+            let mut a = test_rngs::TestRng(BlockRng::new(SplitMixCore(1))); plain(&mut a); \
+            let mut b = test_rngs::CRYPTO_RNG(BlockRng::new(SplitMixCore(1))); crypto(&mut b); \
+            let (mut x, mut y) = (BlockRng::new(SplitMixCore(3)), SplitMix(3)); \
+            for _ in 0..20 { assert_eq!(x.word(), y.word()); } \
+            let (mut x, mut y) = (BlockRng::new(SplitMixCore(3)), BlockRng::new(SplitMixCore(3))); \
+            let mut buf = [0u8; 70]; x.fill(&mut buf); \
+            for c in buf.chunks(4) { assert_eq!(c, &y.next_u32().to_le_bytes()[..c.len()]); }"],
+    },
+    Capability {
+        name: "OS generator",
+        bodies: &["use test_rngs::os::OsRng; \
+            let mut a = test_rngs::CRYPTO_RNG(OsRng::new()); crypto(&mut a); \
+            let mut b = test_rngs::CRYPTO_RNG(OsRng::nonblocking()); crypto(&mut b); \
+            let (mut x, mut y) = ([0u8; 32], [0u8; 32]); \
+            OsRng::new().try_fill(&mut x).unwrap(); OsRng::nonblocking().try_fill(&mut y).unwrap(); \
+            assert!(x != y);"],
+    },
+    Capability {
+        name: "shared handle (thread_rng)",
+        bodies: &[
+            "HANDLE THREAD_CRYPTO_IMPL HANDLE_USE",
+            "HANDLE THREAD_PLAIN_IMPL THREAD_CRYPTO_IMPL HANDLE_USE",
+        ],
+    },
+    Capability {
+        name: "crypto failure reaches caller",
+        bodies: &["fn first<R: CRYPTO + ?Sized>(r: &mut R) -> String { format!(\"{:?}\", r.CRYPTO_CALL) } \
+            let mut f = test_rngs::CRYPTO_RNG(test_rngs::FailAfter::new(0)); \
+            assert!(first(&mut f).starts_with(\"Err\")); \
+            assert!(first(&mut f).contains(\"Unavailable\"));"],
+    },
+    Capability {
+        name: "crypto fault, then recovery",
+        bodies: &["fn first<R: CRYPTO + ?Sized>(r: &mut R) -> String { format!(\"{:?}\", r.CRYPTO_CALL) } \
+            let faulty = test_rngs::FaultyRng::with_probability(test_rngs::Counting(1), 0.5, 7); \
+            let mut f = test_rngs::CRYPTO_RNG(faulty); \
+            let out: Vec<String> = (0..32).map(|_| first(&mut f)).collect(); \
+            let ok: Vec<String> = out.into_iter().filter(|o| o.starts_with(\"Ok\")).collect(); \
+            assert!(ok.len() > 4 && ok.len() < 28); \
+            let mut g = test_rngs::CRYPTO_RNG(test_rngs::Counting(1)); \
+            assert!(ok.iter().all(|o| *o == first(&mut g)));"],
+    },
+];
+
+/// A handle to shared state, as `entropy/thread_rng.rs`'s `ThreadRng`: not
+/// `Send`, and `&mut` to nothing but its `Rc`. The variant's impls for
+/// `ProbeDual` are reused for it, with each word a step of the shared state.
+const HANDLE: &str = "#[derive(Clone)] struct ProbeThread(std::rc::Rc<std::cell::RefCell<u32>>); \
+    fn step(s: &std::cell::RefCell<u32>) -> u32 { let mut s = s.borrow_mut(); *s += 1; *s }";
+const HANDLE_USE: &str = "let state = std::rc::Rc::new(std::cell::RefCell::new(0)); \
+    let (mut a, mut b) = (ProbeThread(state.clone()), ProbeThread(state.clone())); \
+    crypto(&mut a); let n = *state.borrow(); assert!(n > 0); \
+    crypto(&mut b); assert!(*state.borrow() > n);";
+
+/// The variant's impl for `ProbeDual`, made for `ProbeThread`.
+fn handle_impl(probe_dual_impl: &str) -> String {
+    probe_dual_impl.replace("ProbeDual", "ProbeThread").replace("self.0", "step(&self.0)")
+}
+
+/// The probe program: the variant, whose `main` is renamed, and `body`.
+fn probe_source(v: &Variant, traits_dir: &Path, body: Option<&str>) -> String {
+    let source = fs::read_to_string(traits_dir.join(v.file)).expect("read variant");
+    let mut s = source.replace("pub fn main()", "pub fn variant_main()");
+    // The shared files, included relative to the variant
+    for shared in &["bridge.rs", "error.rs", "test_rngs.rs"] {
+        let path = traits_dir.join(shared);
+        s = s.replace(&format!("#[path = {:?}]", shared), &format!("#[path = {:?}]", path));
+    }
+    let body = body.unwrap_or("variant_main();");
+    let main = format!("
+fn plain<R: PLAIN + ?Sized>(r: &mut R) {{ let _ = r.PLAIN_CALL; }}
+fn crypto<R: CRYPTO + ?Sized>(r: &mut R) {{ let _ = r.CRYPTO_CALL; }}
+fn main() {{
+    let mut t = test_rngs::TestRng(test_rngs::Constant(1));
+    let mut c = test_rngs::CRYPTO_RNG(test_rngs::Constant(2));
+    {}
+}}
+", body);
+    // Implementations first: they contain the other placeholders
+    s += &main.replace("HANDLE_USE", HANDLE_USE)
+        .replace("HANDLE", HANDLE)
+        .replace("THREAD_PLAIN_IMPL", &handle_impl(v.plain_impl))
+        .replace("THREAD_CRYPTO_IMPL", &handle_impl(v.crypto_impl))
+        .replace("PLAIN_IMPL", v.plain_impl)
+        .replace("CRYPTO_IMPL", v.crypto_impl)
+        .replace("PLAIN_CALL", v.plain_call)
+        .replace("CRYPTO_CALL", v.crypto_call)
+        .replace("CRYPTO_RNG", v.crypto_rng)
+        .replace("PLAIN", v.plain)
+        .replace("CRYPTO", v.crypto);
+    s
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Outcome {
+    Yes,
+    No(String),
+    Panics,
+    /// The variant itself would not build or run
+    Unavailable,
+}
+
+/// Compiles and runs one probe program.
+fn run_probe(source: &str, nightly: bool, dir: &Path, id: usize) -> Outcome {
+    let src = dir.join(format!("probe{}.rs", id));
+    let bin = dir.join(format!("probe{}", id));
+    fs::write(&src, source).expect("write probe");
+    let mut rustc = Command::new("rustc");
+    if nightly {
+        rustc.arg("+nightly");
+    }
+    let out = rustc.args(["--edition", "2015", "-A", "warnings", "-o"])
+        .arg(&bin)
+        .arg(&src)
+        .output();
+    let out = match out {
+        Ok(out) => out,
+        Err(_) => return Outcome::Unavailable,
+    };
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let first = stderr.lines().find(|l| l.starts_with("error")).unwrap_or("");
+        return Outcome::No(first.to_string());
+    }
+    let status = Command::new(&bin).output().map(|o| o.status.success());
+    let _ = fs::remove_file(&bin);
+    match status {
+        Ok(true) => Outcome::Yes,
+        _ => Outcome::Panics,
+    }
+}
+
+// ——— harness ———
+
+/// The first spelling of `cap` which compiles and runs, else the best
+/// outcome of any.
+fn check(v: &Variant, cap: &Capability, traits_dir: &Path, dir: &Path, id: usize) -> Outcome {
+    let mut outcome = None;
+    for (i, body) in cap.bodies.iter().enumerate() {
+        let o = run_probe(&probe_source(v, traits_dir, Some(body)), v.nightly, dir, id * 8 + i);
+        match o {
+            Outcome::Yes => return o,
+            Outcome::Panics => outcome = Some(o),
+            _ => if outcome.is_none() {
+                outcome = Some(o);
+            },
+        }
+    }
+    outcome.unwrap()
+}
+
+fn cell(o: &Outcome) -> &'static str {
+    match *o {
+        Outcome::Yes => "yes",
+        Outcome::No(_) => "no",
+        Outcome::Panics => "panics",
+        Outcome::Unavailable => "n/a",
+    }
+}
+
+fn main() {
+    let verbose = env::args().any(|a| a == "--verbose");
+    let traits_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("traits");
+    let dir = env::temp_dir().join(format!("rand_design_compare_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create temp dir");
+    
+    // Each variant alone first: its own `main` must run, or the row is n/a
+    let jobs: Vec<(usize, Option<usize>)> = (0..VARIANTS.len())
+        .flat_map(|v| Some((v, None)).into_iter().chain((0..CAPABILITIES.len()).map(move |c| (v, Some(c)))))
+        .collect();
+    let results = Mutex::new(vec![None; jobs.len()]);
+    let next = AtomicUsize::new(0);
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= jobs.len() {
+                    break;
+                }
+                let (v, c) = jobs[i];
+                let v = &VARIANTS[v];
+                let o = match c {
+                    None => match run_probe(&probe_source(v, &traits_dir, None), v.nightly, &dir, i * 8) {
+                        Outcome::Yes => Outcome::Yes,
+                        _ => Outcome::Unavailable,
+                    },
+                    Some(c) => check(v, &CAPABILITIES[c], &traits_dir, &dir, i),
+                };
+                results.lock().unwrap()[i] = Some(o);
+            });
+        }
+    });
+    let _ = fs::remove_dir_all(&dir);
+    let results: Vec<Outcome> = results.into_inner().unwrap().into_iter().map(Option::unwrap).collect();
+    
+    for (i, cap) in CAPABILITIES.iter().enumerate() {
+        println!("{:>2}  {}", i + 1, cap.name);
+    }
+    println!();
+    print!("{:<24}", "");
+    for i in 0..CAPABILITIES.len() {
+        print!("{:>7}", i + 1);
+    }
+    println!();
+    let stride = CAPABILITIES.len() + 1;
+    for (vi, v) in VARIANTS.iter().enumerate() {
+        let row = &results[vi * stride..(vi + 1) * stride];
+        print!("{:<24}", v.name);
+        for o in &row[1..] {
+            print!("{:>7}", if row[0] == Outcome::Yes { cell(o) } else { "n/a" });
+        }
+        println!();
+    }
+    if verbose {
+        println!();
+        for (vi, v) in VARIANTS.iter().enumerate() {
+            for (ci, cap) in CAPABILITIES.iter().enumerate() {
+                if let Outcome::No(ref e) = results[vi * stride + ci + 1] {
+                    println!("{} / {}: {}", v.name, cap.name, e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! `no_std` check: which trait designs build on `core` alone
+//! 
+//! Each variant is a program needing `std`; what must not is its design. So
+//! this cuts each variant at its `——— test RNGs ———` marker and compiles what
+//! comes before as a `#![no_std]` library (naming `core` as `std`, so the
+//! designs' `std::` paths resolve where `core` has them), reading `core`,
+//! `alloc` (for `Box` forwarding impls) or `no`. The shared files are checked
+//! whole.
+//! 
+//! Thoughts: the designs don't need `std`. Every variant relating the two
+//! traits reads `alloc` for its `Box<R>` impls alone, which a crate would put
+//! behind a feature; what needs `std` is around the designs: printing,
+//! `catch_unwind` and OS entropy.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+// ——— variants ———
+
+/// The variants and shared files, and whether each needs nightly.
+const FILES: &[(&str, bool)] = &[
+    ("assoc_error.rs", false),
+    ("async_rng.rs", false),
+    ("auto_trait_CryptoRng.rs", true),
+    ("batched_fallibility.rs", false),
+    ("block_output.rs", false),
+    ("capabilities.rs", false),
+    ("cloneable.rs", false),
+    ("const_crypto.rs", false),
+    ("downcast.rs", false),
+    ("extends_CryptoRng.rs", false),
+    ("extends_CryptoRng2.rs", false),
+    ("extends_CryptoRng3.rs", false),
+    ("extends_Rng.rs", false),
+    ("extends_Rng2.rs", false),
+    ("extends_Rng_infallible.rs", false),
+    ("fallibility_param.rs", false),
+    ("from_rng.rs", false),
+    ("kind_param.rs", false),
+    ("lending_block.rs", false),
+    ("locked.rs", false),
+    ("marker_only.rs", false),
+    ("negative_impls.rs", true),
+    ("raw_default.rs", false),
+    ("raw_explicit.rs", true),
+    ("raw_infallible.rs", false),
+    ("raw_newtype.rs", true),
+    ("raw_rng.rs", true),
+    ("separate_explicit_Rng.rs", false),
+    ("separate_implicit_Rng.rs", false),
+    ("separate_implicit_Rng2.rs", false),
+    ("separate_implicit_Rng3.rs", false),
+    ("shared_ref.rs", false),
+    ("specialization.rs", true),
+    ("streams.rs", false),
+    // The shared files
+    ("block.rs", false),
+    ("bridge.rs", false),
+    ("chacha.rs", false),
+    ("error.rs", false),
+    ("os.rs", false),
+    ("pcg.rs", false),
+    ("seeding.rs", false),
+    ("test_rngs.rs", false),
+];
+
+/// The design part of `file`, with its shared files included by absolute
+/// path, as a `no_std` library; with `alloc` if `with_alloc`.
+fn library_source(file: &str, traits_dir: &Path, with_alloc: bool) -> String {
+    let source = fs::read_to_string(traits_dir.join(file)).expect("read variant");
+    let (design, rest) = match source.find("// ——— test RNGs ———") {
+        Some(end) => source.split_at(end),
+        None => (&source[..], ""),
+    };
+    // The shared test generators' include, if the section starts with it:
+    // some designs (`from_rng`) build on `test_rngs::seeding`
+    let include: Vec<&str> = rest.lines().skip(1)
+        .take_while(|l| l.is_empty() || l.starts_with("#[path = \"test_rngs.rs\"]")
+            || l.starts_with("#[allow") || l.starts_with("pub mod test_rngs")
+            || l.starts_with("use self::test_rngs"))
+        .collect();
+    // Crate attributes first: the variant's `//!` docs and `#![...]`
+    let mut lines = design.lines().peekable();
+    let mut s = String::new();
+    while let Some(line) = lines.peek() {
+        if !(line.starts_with("//!") || line.starts_with("#![") || line.trim().is_empty()) {
+            break;
+        }
+        s += lines.next().unwrap();
+        s += "\n";
+    }
+    s += "#![no_std]\nextern crate core as std;\n";
+    if with_alloc {
+        s += "extern crate alloc;\nuse alloc::boxed::Box;\n";
+    }
+    // `#![no_std]` declares `core` itself
+    for line in lines.chain(include).filter(|&l| l != "extern crate core;") {
+        s += line;
+        s += "\n";
+    }
+    for shared in &["bridge.rs", "error.rs", "seeding.rs", "test_rngs.rs",
+        "../entropy/os_backends/sys.rs"] {
+        let path = traits_dir.join(shared);
+        s = s.replace(&format!("#[path = {:?}]", shared), &format!("#[path = {:?}]", path));
+    }
+    s
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Outcome {
+    Core,
+    Alloc,
+    No(String),
+    Unavailable,
+}
+
+/// Compiles one library; the first error if it fails.
+fn compile(source: &str, nightly: bool, dir: &Path, name: &str) -> Result<(), Option<String>> {
+    let src = dir.join(format!("{}.rs", name));
+    fs::write(&src, source).expect("write library");
+    let mut rustc = Command::new("rustc");
+    if nightly {
+        rustc.arg("+nightly");
+    }
+    let out = rustc.args(["--edition", "2015", "--crate-type", "lib", "-A", "warnings", "--out-dir"])
+        .arg(dir)
+        .arg(&src)
+        .output()
+        .map_err(|_| None)?;
+    if out.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let first = stderr.lines().find(|l| l.starts_with("error")).unwrap_or("");
+    Err(Some(first.to_string()))
+}
+
+fn check(file: &str, nightly: bool, traits_dir: &Path, dir: &Path) -> Outcome {
+    let name = file.trim_end_matches(".rs").to_lowercase();
+    let error = match compile(&library_source(file, traits_dir, false), nightly, dir, &name) {
+        Ok(()) => return Outcome::Core,
+        Err(None) => return Outcome::Unavailable,
+        Err(Some(e)) => e,
+    };
+    match compile(&library_source(file, traits_dir, true), nightly, dir, &name) {
+        Ok(()) => Outcome::Alloc,
+        // No nightly toolchain: `n/a`, as in `compare`
+        Err(_) if nightly && error.contains("toolchain") => Outcome::Unavailable,
+        Err(_) => Outcome::No(error),
+    }
+}
+
+fn main() {
+    let verbose = env::args().any(|a| a == "--verbose");
+    let traits_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("traits");
+    let dir = env::temp_dir().join(format!("rand_design_no_std_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create temp dir");
+    let results: Vec<Outcome> = FILES.iter()
+        .map(|&(file, nightly)| check(file, nightly, &traits_dir, &dir))
+        .collect();
+    let _ = fs::remove_dir_all(&dir);
+    
+    for (&(file, _), o) in FILES.iter().zip(&results) {
+        let cell = match *o {
+            Outcome::Core => "core",
+            Outcome::Alloc => "alloc",
+            Outcome::No(_) => "no",
+            Outcome::Unavailable => "n/a",
+        };
+        println!("{:<28}{:>6}", file.trim_end_matches(".rs"), cell);
+    }
+    if verbose {
+        println!();
+        for (&(file, _), o) in FILES.iter().zip(&results) {
+            if let Outcome::No(ref e) = *o {
+                println!("{}: {}", file, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,504 @@
+//! `gen_bool(p)` by an integer threshold, `gen_ratio(n, d)` exactly, over three designs
+//! 
+//! Methods: `Bernoulli::new(p)` converts `p` once to a threshold `t =
+//! floor(p * 2^64)` (`u64::MAX` flags `p = 1`), and each sample compares
+//! one `u64` with it; `from_ratio(n, d)` makes the threshold from integers.
+//! `gen_ratio(n, d)` is exact instead: `gen_range(0, d) < n`. Written once,
+//! against `gen_range.rs`'s `Words`, and given to `extends_Rng`,
+//! `extends_CryptoRng` and `raw_infallible` as `gen_range` is; `main`
+//! checks the thresholds bit for bit, the edge words and the refused `p`,
+//! and compares with the float compare `unit(x) < p` of `combinators.rs`.
+//! 
+//! Run with:
+//! 
+//!     rustc -O bernoulli.rs && ./bernoulli
+//! 
+//! Thoughts: the threshold is exact to a `u64`, while the float compare
+//! rounds `p` up to a multiple of 2^-53, so tiny `p` come out far too
+//! large. A word is drawn even for `p = 0` and `p = 1`, so the stream
+//! doesn't depend on `p`. `gen_ratio` has no bias at all but a cost in
+//! words that varies, so it is the default for k-in-n; `from_ratio` stays
+//! for one word a sample. None of this depends on the design.
+
+use std::convert::Infallible;
+
+#[path = "gen_range.rs"]
+#[allow(dead_code)]
+mod gen_range;
+
+use gen_range::{SampleRange, Words};
+
+// ——— Bernoulli ———
+
+/// The threshold meaning `p = 1`, which no `p < 1` reaches: its largest,
+/// `1 - 2^-53`, gives `2^64 - 2^11`.
+const ALWAYS: u64 = u64::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BernoulliError {
+    /// `p` is outside `[0, 1]` or NaN, or the ratio's numerator exceeds its
+    /// denominator, or the denominator is 0.
+    InvalidProbability,
+}
+
+/// `true` with probability `p`, to within 2^-64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bernoulli {
+    /// `floor(p * 2^64)`, or `ALWAYS`.
+    threshold: u64,
+}
+
+impl Bernoulli {
+    pub fn new(p: f64) -> Result<Bernoulli, BernoulliError> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(BernoulliError::InvalidProbability);
+        }
+        if p == 1.0 {
+            return Ok(Bernoulli { threshold: ALWAYS });
+        }
+        // Exact: scaling by 2^64 doesn't round, and `as` truncates
+        Ok(Bernoulli { threshold: (p * 2f64.powi(64)) as u64 })
+    }
+    
+    /// `true` with probability `numerator / denominator`, to within 2^-64.
+    pub fn from_ratio(numerator: u32, denominator: u32) -> Result<Bernoulli, BernoulliError> {
+        if denominator == 0 || numerator > denominator {
+            return Err(BernoulliError::InvalidProbability);
+        }
+        if numerator == denominator {
+            return Ok(Bernoulli { threshold: ALWAYS });
+        }
+        Ok(Bernoulli { threshold: (((numerator as u128) << 64) / denominator as u128) as u64 })
+    }
+    
+    /// One word, drawn whatever `p`.
+    pub fn sample<W: Words+?Sized>(&self, words: &mut W) -> Result<bool, W::Error> {
+        let x = words.word_u64()?;
+        Ok(self.threshold == ALWAYS || x < self.threshold)
+    }
+}
+
+/// For `gen_bool`, which panics on a bad `p`.
+fn bernoulli(p: f64) -> Bernoulli {
+    Bernoulli::new(p).unwrap_or_else(|_| panic!("gen_bool: p = {} not in [0, 1]", p))
+}
+
+/// `true` with probability exactly `numerator / denominator`: a value of
+/// `[0, denominator)` by `gen_range`'s sampler, below `numerator`. Panics
+/// unless the ratio is in `[0, 1]`.
+pub fn ratio<W: Words+?Sized>(numerator: u32, denominator: u32, words: &mut W)
+    -> Result<bool, W::Error>
+{
+    assert!(denominator > 0 && numerator <= denominator,
+        "gen_ratio: {} / {} not in [0, 1]", numerator, denominator);
+    Ok(u32::sample_range(0, denominator, words)? < numerator)
+}
+
+// ——— the float compare, for contrast ———
+
+/// marker_only's `f64_closed_open01`: multiples of 2^-53.
+fn unit_mul(x: u64) -> f64 {
+    (x >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// marker_only's `f64_closed_open01_exp`: multiples of 2^-52.
+fn unit_exp(x: u64) -> f64 {
+    f64::from_bits(0x3FF0_0000_0000_0000 | x >> 12) - 1.0
+}
+
+// ——— over extends_Rng ———
+
+mod on_extends_rng {
+    use super::gen_range::extends_rng::{CryptoError, CryptoRng, Rng};
+    use super::gen_range::on_extends_rng::{Fallibly, Infallibly};
+    use super::{bernoulli, ratio};
+    
+    pub trait RngExt: Rng {
+        /// `true` with probability `p`. Panics unless `p` is in `[0, 1]`.
+        fn gen_bool(&mut self, p: f64) -> bool {
+            bernoulli(p).sample(&mut Infallibly(self)).unwrap_or_else(|e| match e {})
+        }
+        
+        /// `true` with probability `numerator / denominator`. Panics unless
+        /// that is in `[0, 1]`.
+        fn gen_ratio(&mut self, numerator: u32, denominator: u32) -> bool {
+            ratio(numerator, denominator, &mut Infallibly(self)).unwrap_or_else(|e| match e {})
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+    
+    pub trait CryptoRngExt: CryptoRng {
+        /// Panics unless `p` is in `[0, 1]`.
+        fn try_gen_bool(&mut self, p: f64) -> Result<bool, CryptoError> {
+            bernoulli(p).sample(&mut Fallibly(self))
+        }
+        
+        /// Panics unless the ratio is in `[0, 1]`.
+        fn try_gen_ratio(&mut self, numerator: u32, denominator: u32) -> Result<bool, CryptoError> {
+            ratio(numerator, denominator, &mut Fallibly(self))
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> CryptoRngExt for R {}
+}
+
+// ——— over extends_CryptoRng ———
+
+mod on_extends_cryptorng {
+    use super::gen_range::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use super::gen_range::on_extends_cryptorng::Fallibly;
+    use super::{bernoulli, ratio};
+    
+    pub trait CryptoRngExt: CryptoRng {
+        /// Panics unless `p` is in `[0, 1]`.
+        fn try_gen_bool(&mut self, p: f64) -> Result<bool, CryptoError> {
+            bernoulli(p).sample(&mut Fallibly(self))
+        }
+        
+        /// Panics unless the ratio is in `[0, 1]`.
+        fn try_gen_ratio(&mut self, numerator: u32, denominator: u32) -> Result<bool, CryptoError> {
+            ratio(numerator, denominator, &mut Fallibly(self))
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> CryptoRngExt for R {}
+    
+    pub trait RngExt: Rng {
+        /// `true` with probability `p`. Panics unless `p` is in `[0, 1]`,
+        /// or on error.
+        fn gen_bool(&mut self, p: f64) -> bool {
+            self.try_gen_bool(p).unwrap_or_else(|e| panic!("gen_bool failed: {}", e))
+        }
+        
+        /// `true` with probability `numerator / denominator`. Panics unless
+        /// that is in `[0, 1]`, or on error.
+        fn gen_ratio(&mut self, numerator: u32, denominator: u32) -> bool {
+            self.try_gen_ratio(numerator, denominator)
+                .unwrap_or_else(|e| panic!("gen_ratio failed: {}", e))
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+}
+
+// ——— over raw_infallible ———
+
+mod on_raw_infallible {
+    use super::gen_range::on_raw_infallible::Raw;
+    use super::gen_range::raw_infallible::{RawRng, Rng};
+    use super::{bernoulli, ratio, Infallible};
+    use std::marker::PhantomData;
+    
+    pub trait RawRngExt {
+        /// Panics unless `p` is in `[0, 1]`.
+        fn try_gen_bool<E>(&mut self, p: f64) -> Result<bool, E> where Self: RawRng<E> {
+            bernoulli(p).sample(&mut Raw(self, PhantomData))
+        }
+        
+        /// Panics unless the ratio is in `[0, 1]`.
+        fn try_gen_ratio<E>(&mut self, numerator: u32, denominator: u32) -> Result<bool, E>
+            where Self: RawRng<E>
+        {
+            ratio(numerator, denominator, &mut Raw(self, PhantomData))
+        }
+    }
+    
+    impl<R: ?Sized> RawRngExt for R {}
+    
+    pub trait RngExt: Rng {
+        /// `true` with probability `p`. Panics unless `p` is in `[0, 1]`.
+        fn gen_bool(&mut self, p: f64) -> bool {
+            self.try_gen_bool::<Infallible>(p).unwrap_or_else(|e| match e {})
+        }
+        
+        /// `true` with probability `numerator / denominator`. Panics unless
+        /// that is in `[0, 1]`.
+        fn gen_ratio(&mut self, numerator: u32, denominator: u32) -> bool {
+            self.try_gen_ratio::<Infallible>(numerator, denominator).unwrap_or_else(|e| match e {})
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+}
+
+// ——— test RNGs ———
+
+/// One `u64`, forever. The shared `ConstRng` makes its `u64` of two of its
+/// `u32` words, so can't give an arbitrary one.
+struct Word(u64);
+
+impl gen_range::raw_infallible::Rng for Word {
+    fn next_u32(&mut self) -> u32 {
+        self.0 as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0
+    }
+}
+
+/// One `u32` word, then nothing: a sample that rejects it fails.
+struct Once(Option<u32>);
+
+impl Words for Once {
+    type Error = ();
+    
+    fn word_u32(&mut self) -> Result<u32, ()> {
+        self.0.take().ok_or(())
+    }
+    
+    fn word_u64(&mut self) -> Result<u64, ()> {
+        Err(())
+    }
+}
+
+// ——— checking ———
+
+/// How many of the 2^32 words give `k` from `u32::sample_range(0, d)`,
+/// sampling only the words at the ends of its stretch. The stretch is the
+/// words `w` with `floor(w * d / 2^32) = k`, the high half, and the low half
+/// grows by `d` a word, so only its first word can have a low half below
+/// the zone, itself below `d`, and be rejected.
+fn words_giving(d: u32, k: u32) -> u64 {
+    let start = |k: u64| (k << 32).div_ceil(d as u64);
+    let (first, last) = (start(k as u64), start(k as u64 + 1) - 1);
+    let sample = |w: u64| u32::sample_range(0, d, &mut Once(Some(w as u32)));
+    assert_eq!(sample(last), Ok(k));
+    if k > 0 {
+        assert_ne!(sample(first - 1), Ok(k));
+    }
+    let rejected = match sample(first) {
+        Ok(x) => {
+            assert_eq!(x, k);
+            0
+        }
+        Err(()) => 1,
+    };
+    last - first + 1 - rejected
+}
+
+
+/// `floor(p * 2^64)` for `p` in `[0, 1)`, from its bits.
+fn exact_threshold(p: f64) -> u64 {
+    let bits = p.to_bits();
+    let (exp, mantissa) = ((bits >> 52) as i32, bits & ((1 << 52) - 1));
+    if exp == 0 {
+        // Subnormal: below 2^-1022
+        return 0;
+    }
+    // p = (2^52 + mantissa) * 2^(exp - 1075), times 2^64
+    let (m, shift) = ((1u64 << 52) | mantissa, exp - 1011);
+    if shift >= 0 {
+        m << shift
+    } else if shift > -64 {
+        m >> -shift
+    } else {
+        0
+    }
+}
+
+/// Each method's `P(true) - p`, exactly but for the final rounding: the
+/// threshold, then the float compares, `true` for every multiple of 2^-53
+/// (2^-52) below `p`; each as a count of words out of 2^64 less `p * 2^64`,
+/// itself exact, its floor `exact_threshold`.
+fn errors(p: f64) -> [f64; 3] {
+    let scaled = p * 2f64.powi(64);
+    let threshold = Bernoulli::new(p).unwrap().threshold as i128;
+    let below = |bits: i32| ((p * 2f64.powi(bits)).ceil() as i128) << (64 - bits);
+    let error = |count: i128| {
+        ((count - exact_threshold(p) as i128) as f64 - scaled.fract()) * 2f64.powi(-64)
+    };
+    [error(threshold), error(below(53)), error(below(52))]
+}
+
+// ——— usage ———
+
+fn main() {
+    let draws: [Vec<(bool, bool)>; 3] = [
+        {
+            use self::gen_range::extends_rng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_rng::RngExt;
+            let mut t = TestRng(SplitMix(1));
+            (0..1000).map(|_| (t.gen_bool(0.25), t.gen_ratio(1, 3))).collect()
+        },
+        {
+            use self::gen_range::extends_cryptorng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_cryptorng::RngExt;
+            let mut t = TestRng(SplitMix(1));
+            (0..1000).map(|_| (t.gen_bool(0.25), t.gen_ratio(1, 3))).collect()
+        },
+        {
+            use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+            use self::on_raw_infallible::RngExt;
+            let mut t = TestRng(SplitMix(1));
+            (0..1000).map(|_| (t.gen_bool(0.25), t.gen_ratio(1, 3))).collect()
+        },
+    ];
+    assert!(draws[0] == draws[1] && draws[0] == draws[2]);
+    println!("gen_bool, gen_ratio: the same over each design, first {:?}", draws[0][0]);
+    {
+        use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+        use self::on_raw_infallible::RngExt;
+        let mut t = TestRng(SplitMix(2));
+        let quarter = (0..100_000).filter(|_| t.gen_bool(0.25)).count();
+        let third = (0..100_000).filter(|_| t.gen_ratio(1, 3)).count();
+        assert!((quarter as i64 - 25_000).abs() < 600 && (third as i64 - 33_333).abs() < 600);
+        println!("100000 times: gen_bool(0.25) {}, gen_ratio(1, 3) {}", quarter, third);
+    }
+    {
+        // Every threshold the exact floor: special values, then a sweep of
+        // every binade and of `[0, 1)` itself
+        use self::gen_range::raw_infallible::test_rngs::{Source, SplitMix};
+        let mut ps = vec![0.0, f64::from_bits(1), f64::MIN_POSITIVE, 2f64.powi(-64), 1e-20,
+            0.1, 1.0 / 3.0, 0.5, 1.0 - 2f64.powi(-53)];
+        let mut s = SplitMix(3);
+        for exp in -1074..0 {
+            ps.push(2f64.powi(exp) * (1.0 + unit_mul(s.word())));
+        }
+        ps.extend((0..10_000).map(|_| unit_mul(s.word())));
+        for &p in ps.iter().filter(|&&p| p < 1.0) {
+            assert_eq!(Bernoulli::new(p).unwrap().threshold, exact_threshold(p), "p = {:e}", p);
+        }
+        for d in 1..200u32 {
+            for n in 0..d {
+                let t = Bernoulli::from_ratio(n, d).unwrap().threshold as u128;
+                let scaled = (n as u128) << 64;
+                assert!(t * d as u128 <= scaled && (t + 1) * d as u128 > scaled);
+            }
+        }
+        println!("thresholds: the exact floor for {} p from 2^-1074 to 1 - 2^-53, \
+            and every n / d below 1 for d < 200", ps.len());
+    }
+    {
+        // Either side of the threshold, near 0 and 1: the words that give
+        // `true` are exactly those below it
+        use self::on_raw_infallible::RngExt;
+        let (top, ulp) = (!0u64 << 11, 1 << 11);
+        let cases = [(2f64.powi(-64), 1), (3.0 * 2f64.powi(-64), 3), (2f64.powi(-60), 16),
+            (1.0 - 2f64.powi(-53), top), (1.0 - 2f64.powi(-52), top - ulp)];
+        for &(p, threshold) in cases.iter() {
+            assert!(Word(threshold - 1).gen_bool(p), "p = {:e}", p);
+            assert!(!Word(threshold).gen_bool(p), "p = {:e}", p);
+        }
+        // The smallest subnormal, and p = 0: never, not even from word 0,
+        // where the float compares say `true`
+        for &p in [0.0, f64::from_bits(1), f64::MIN_POSITIVE, 1e-20].iter() {
+            assert!(!Word(0).gen_bool(p));
+            assert_eq!((unit_mul(0) < p, unit_exp(0) < p), (p > 0.0, p > 0.0));
+        }
+        // p = 1: always, even from the all-ones word
+        assert!(Word(!0).gen_bool(1.0) && Word(!0).gen_ratio(7, 7));
+        assert!(!Word(!0).gen_bool(1.0 - 2f64.powi(-53)));
+        println!("words either side of the threshold: right near 0 and 1; subnormal p never \
+            true, p = 1 always");
+    }
+    {
+        // A word whatever `p`, so a failing source fails even at 0 and 1
+        use self::gen_range::raw_infallible::test_rngs::{CountingRng, FailAfter, SplitMix};
+        use self::gen_range::raw_infallible::test_rngs::{TestFCRng, TestRng};
+        use self::gen_range::raw_infallible::ErrorKind;
+        use self::on_raw_infallible::{RawRngExt, RngExt};
+        let mut c = CountingRng::new(TestRng(SplitMix(4)));
+        for &p in [0.0, 0.5, 1.0].iter() {
+            c.gen_bool(p);
+        }
+        assert_eq!((c.consumed.u32s, c.consumed.u64s), (0, 3));
+        for &p in [0.0, 1.0].iter() {
+            let e = TestFCRng(FailAfter::new(1)).try_gen_bool(p).unwrap_err();
+            assert_eq!(e.kind, ErrorKind::Unavailable);
+        }
+        println!("one u64 a sample, at p = 0 and 1 too; a spent source fails there as well");
+    }
+    {
+        use self::gen_range::extends_rng::test_rngs::FailAfter;
+        use self::gen_range::FallibleCRng;
+        use self::on_extends_rng::CryptoRngExt;
+        assert!(FallibleCRng(FailAfter::new(1)).try_gen_bool(0.0).is_err());
+        assert!(!FallibleCRng(FailAfter::new(2)).try_gen_bool(0.0).unwrap());
+    }
+    {
+        use self::gen_range::extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use self::on_extends_cryptorng::CryptoRngExt;
+        assert!(TestCRng(FailAfter::new(1)).try_gen_bool(1.0).is_err());
+        assert!(TestCRng(FailAfter::new(2)).try_gen_bool(1.0).unwrap());
+    }
+    {
+        for &p in [-0.1, 1.0 + 2f64.powi(-52), f64::NAN, f64::INFINITY].iter() {
+            assert_eq!(Bernoulli::new(p), Err(BernoulliError::InvalidProbability));
+        }
+        assert_eq!(Bernoulli::from_ratio(4, 3), Err(BernoulliError::InvalidProbability));
+        assert_eq!(Bernoulli::from_ratio(0, 0), Err(BernoulliError::InvalidProbability));
+        println!("Bernoulli::new(-0.1, 1 + 2^-52, NaN, inf), from_ratio(4, 3), (0, 0): \
+            InvalidProbability");
+    }
+    {
+        // gen_ratio exactly: every value of `[0, d)` from the same number of
+        // words, 2^32 / d rounded down, so `n` of them are exactly `n / d`
+        use self::on_raw_infallible::RngExt;
+        for d in (1..=300).chain(vec![1000, 6000, 1 << 20, (1 << 31) + 1, u32::MAX]) {
+            let ks: Vec<u32> = if d <= 6000 {
+                (0..d).collect()
+            } else {
+                (0..1000).chain(d / 2 - 500..d / 2 + 500).chain(d - 1000..d).collect()
+            };
+            let each = (1u64 << 32) / d as u64;
+            assert!(ks.iter().all(|&k| words_giving(d, k) == each), "d = {}", d);
+        }
+        // Where the threshold, one word, is within 2^-64: 1/3 is
+        // `(2^64 - 1) / 3` words, `1 / (3 * 2^64)` short
+        assert_eq!(Bernoulli::from_ratio(1, 3).unwrap().threshold as u128 * 3, (1 << 64) - 1);
+        // (Not word 0, which is in 0 / 7's zone: `Word` would reject it forever)
+        for &w in [1, 1 << 31, !0].iter() {
+            assert!(!Word(w).gen_ratio(0, 7) && Word(w).gen_ratio(7, 7) && Word(w).gen_ratio(1, 1));
+        }
+        println!("gen_ratio: each value of [0, d) from 2^32 / d words, for d to 300 and \
+            some to 2^32 - 1; 0 / d never, d / d always");
+    }
+    {
+        // The cost in words: one, but for rejections, which near d = 2^31
+        // are nearly half; the threshold always exactly one `u64`
+        use self::gen_range::raw_infallible::test_rngs::{CountingRng, SplitMix, TestRng};
+        use self::on_raw_infallible::RngExt;
+        let cost = |d: u32| {
+            let mut c = CountingRng::new(TestRng(SplitMix(5)));
+            for _ in 0..10_000 {
+                c.gen_ratio(1, d);
+            }
+            c.consumed.u32s
+        };
+        let (die, half) = (cost(6), cost((1 << 31) + 1));
+        assert!(die == 10_000 && half > 19_000 && half < 21_000);
+        println!("words for 10000 gen_ratio: {} for 1 / 6, {} for 1 / (2^31 + 1)", die, half);
+    }
+    {
+        // A rejected word, then the source fails: word 0 is in 1 / 3's
+        // zone, 2^32 mod 3 = 1
+        use self::gen_range::extends_rng::test_rngs::FailAfter;
+        use self::gen_range::FallibleCRng;
+        use self::on_extends_rng::CryptoRngExt;
+        assert!(FallibleCRng(FailAfter::new(1)).try_gen_ratio(1, 3).is_err());
+        assert!(FallibleCRng(FailAfter::new(2)).try_gen_ratio(1, 3).unwrap());
+    }
+    {
+        use self::gen_range::extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use self::on_extends_cryptorng::CryptoRngExt;
+        assert!(TestCRng(FailAfter::new(1)).try_gen_ratio(1, 3).is_err());
+        assert!(TestCRng(FailAfter::new(2)).try_gen_ratio(1, 3).unwrap());
+    }
+    {
+        use self::gen_range::raw_infallible::test_rngs::{FailAfter, TestFCRng};
+        use self::on_raw_infallible::RawRngExt;
+        assert!(TestFCRng(FailAfter::new(1)).try_gen_ratio(1, 3).is_err());
+        assert_eq!(TestFCRng(FailAfter::new(2)).try_gen_ratio(1, 3), Ok(true));
+        println!("try_gen_ratio(1, 3): word 0 rejected, then the source fails; with a word \
+            more, true");
+    }
+    println!("P(true) - p; by threshold, by float compare (53 bits, 52 bits):");
+    let ps = [("2^-1074", f64::from_bits(1)), ("1e-20", 1e-20), ("2^-60", 2f64.powi(-60)),
+        ("0.1", 0.1), ("1/3", 1.0 / 3.0), ("1 - 2^-53", 1.0 - 2f64.powi(-53))];
+    for &(name, p) in ps.iter() {
+        let [t, m, e] = errors(p);
+        println!("    p = {:<10} {:>11.3e} {:>11.3e} {:>11.3e}", name, t, m, e);
+    }
+}
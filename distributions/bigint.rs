@@ -0,0 +1,250 @@
+//! Big-integer generation via optional `num-bigint` interop
+//! 
+//! With the `num-bigint` feature, generates `BigUint` and `BigInt` values
+//! using only `Rng::fill`:
+//! 
+//! *   `gen_biguint(rng, bits)`: uniform over `[0, 2^bits)`
+//! *   `gen_biguint_exact_bits(rng, bits)`: uniform over numbers of exactly
+//!     `bits` bits, i.e. `[2^(bits-1), 2^bits)` (e.g. candidate primes)
+//! *   `gen_bigint(rng, bits)`: uniform over `(-2^bits, 2^bits)`
+//! *   `gen_biguint_below(rng, bound)`: uniform over `[0, bound)`
+//! 
+//! The last uses rejection sampling, but draws the most significant limb
+//! first and rejects as soon as it exceeds the bound's, before drawing the
+//! rest. This is equivalent to drawing everything then rejecting, so remains
+//! uniform, but costs one limb rather than all of them per rejection.
+//! 
+//! Run with:
+//! 
+//!     rustc --cfg 'feature="num-bigint"' --extern num_bigint=PATH/libnum_bigint.rlib \
+//!         -L PATH bigint.rs && ./bigint
+//! 
+//! (where `PATH` contains `num_bigint` and its dependencies, e.g. the
+//! `target/debug/deps` of any Cargo project depending on `num-bigint = "0.4"`).
+//! 
+//! Thoughts: num-bigint has its own `rand` feature, but it is tied to a
+//! particular rand version; building on `fill` needs nothing but the limb
+//! layout (`u32` digits, least significant first), which is stable. Bytes are
+//! consumed little-endian per limb, top limb first.
+
+#[cfg(feature = "num-bigint")]
+extern crate num_bigint;
+
+// ——— traits ———
+
+pub trait Rng {
+    /// Fill dest with random bytes.
+    fn fill(&mut self, dest: &mut [u8]);
+}
+
+// ——— generation ———
+
+#[cfg(feature = "num-bigint")]
+pub use self::bigint::*;
+
+#[cfg(feature = "num-bigint")]
+mod bigint {
+    use super::Rng;
+    use num_bigint::{BigInt, BigUint, Sign};
+    
+    const LIMB_BITS: u64 = 32;
+    
+    fn gen_limb<R: Rng+?Sized>(rng: &mut R) -> u32 {
+        let mut buf = [0u8; 4];
+        rng.fill(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+    
+    /// Fill `limbs` (least significant first), top limb first.
+    fn gen_limbs<R: Rng+?Sized>(rng: &mut R, limbs: &mut [u32]) {
+        for limb in limbs.iter_mut().rev() {
+            *limb = gen_limb(rng);
+        }
+    }
+    
+    /// Mask keeping the low `bits % 32` bits of the top limb (all if 0).
+    fn top_mask(bits: u64) -> u32 {
+        match bits % LIMB_BITS {
+            0 => !0,
+            r => (1 << r) - 1,
+        }
+    }
+    
+    /// Uniform over `[0, 2^bits)`.
+    pub fn gen_biguint<R: Rng+?Sized>(rng: &mut R, bits: u64) -> BigUint {
+        let mut limbs = vec![0u32; ((bits + LIMB_BITS - 1) / LIMB_BITS) as usize];
+        gen_limbs(rng, &mut limbs);
+        if let Some(top) = limbs.last_mut() {
+            *top &= top_mask(bits);
+        }
+        BigUint::new(limbs)
+    }
+    
+    /// Uniform over numbers of exactly `bits` bits.
+    /// 
+    /// Panics if `bits == 0`.
+    pub fn gen_biguint_exact_bits<R: Rng+?Sized>(rng: &mut R, bits: u64) -> BigUint {
+        assert!(bits > 0, "gen_biguint_exact_bits: bits must be positive");
+        let mut x = gen_biguint(rng, bits);
+        x.set_bit(bits - 1, true);
+        x
+    }
+    
+    /// Uniform over `(-2^bits, 2^bits)`.
+    /// 
+    /// Negative zero is rejected, so zero is no more likely than other values.
+    pub fn gen_bigint<R: Rng+?Sized>(rng: &mut R, bits: u64) -> BigInt {
+        loop {
+            let mut sign = [0u8];
+            rng.fill(&mut sign);
+            let magnitude = gen_biguint(rng, bits);
+            let negative = sign[0] & 1 == 1;
+            if negative && magnitude.bits() == 0 {
+                continue;
+            }
+            let sign = if negative { Sign::Minus } else { Sign::Plus };
+            return BigInt::from_biguint(sign, magnitude);
+        }
+    }
+    
+    /// Uniform over `[0, bound)`, by rejection, checking the top limb first.
+    /// 
+    /// Panics if `bound` is zero.
+    pub fn gen_biguint_below<R: Rng+?Sized>(rng: &mut R, bound: &BigUint) -> BigUint {
+        assert!(bound.bits() > 0, "gen_biguint_below: empty range");
+        let bits = bound.bits();
+        let bound_limbs = bound.to_u32_digits();
+        let n = bound_limbs.len();
+        let mask = top_mask(bits);
+        let mut limbs = vec![0u32; n];
+        loop {
+            let top = gen_limb(rng) & mask;
+            if top > bound_limbs[n - 1] {
+                continue;
+            }
+            limbs[n - 1] = top;
+            gen_limbs(rng, &mut limbs[..n - 1]);
+            let x = BigUint::new(limbs.clone());
+            if &x < bound {
+                return x;
+            }
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "../traits/test_rngs.rs"]
+#[allow(dead_code)]
+mod test_rngs;
+use self::test_rngs::{Source, SplitMix};
+
+/// SplitMix64, byte-wise.
+impl Rng for SplitMix {
+    fn fill(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            chunk.copy_from_slice(&self.word().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
+
+/// Yields 0, 1, 2, ... as 4-byte little-endian words, so single-limb
+/// generation enumerates every limb value in turn.
+#[allow(dead_code)]
+struct Counter(u32);
+
+impl Rng for Counter {
+    fn fill(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            chunk.copy_from_slice(&self.0.to_le_bytes()[..chunk.len()]);
+            self.0 = self.0.wrapping_add(1);
+        }
+    }
+}
+
+// ——— usage ———
+
+#[path = "../verify/chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+#[cfg(not(feature = "num-bigint"))]
+fn main() {
+    println!("built without the num-bigint feature: nothing to test");
+}
+
+#[cfg(feature = "num-bigint")]
+fn main() {
+    use chi_squared::{chi_squared, chi_squared_limit};
+    use num_bigint::{BigInt, BigUint};
+    
+    let mut rng = SplitMix(0x5EED);
+    {
+        // Bit lengths
+        for &bits in [1u64, 7, 31, 32, 33, 64, 100, 521].iter() {
+            for _ in 0..100 {
+                assert!(gen_biguint(&mut rng, bits).bits() <= bits);
+                assert_eq!(gen_biguint_exact_bits(&mut rng, bits).bits(), bits);
+                assert!(gen_bigint(&mut rng, bits).magnitude().bits() <= bits);
+            }
+        }
+        assert_eq!(gen_biguint(&mut rng, 0), BigUint::from(0u32));
+        println!("bit lengths: ok");
+    }
+    {
+        // Exact: with limbs enumerated in turn, each value below the bound is
+        // produced once per cycle of the masked top limb, and every value at
+        // or above it is rejected
+        for &bound in [1u32, 2, 3, 5, 6, 7, 100, 255, 256, 257, 1000].iter() {
+            let bits = 32 - bound.leading_zeros();
+            let mut counts = vec![0u64; bound as usize];
+            let mut counter = Counter(0);
+            for _ in 0..2 * bound {
+                let x = gen_biguint_below(&mut counter, &BigUint::from(bound));
+                counts[x.to_u32_digits().first().cloned().unwrap_or(0) as usize] += 1;
+            }
+            assert!(counts.iter().all(|&c| c == 2), "bound {}: {:?}", bound, counts);
+            assert_eq!(counter.0, (1 << bits) + bound);
+        }
+        println!("tiny bounds, exhaustive: ok");
+    }
+    {
+        // Statistical: tiny bounds, and a two-limb bound just above 2^32
+        // (where the top limb is 0 or 1, and most top limbs are rejected)
+        const SAMPLES: u64 = 60_000;
+        for &bound in [2u32, 3, 5, 7, 10].iter() {
+            let mut counts = vec![0u64; bound as usize];
+            for _ in 0..SAMPLES {
+                let x = gen_biguint_below(&mut rng, &BigUint::from(bound));
+                counts[x.to_u32_digits().first().cloned().unwrap_or(0) as usize] += 1;
+            }
+            let chi2 = chi_squared(&counts);
+            assert!(chi2 < chi_squared_limit(counts.len() - 1), "bound {}: {:?}", bound, counts);
+        }
+        let bound = (BigUint::from(1u32) << 32) + BigUint::from(3u32);
+        let mut counts = [0u64; 8];
+        for _ in 0..SAMPLES {
+            let x = gen_biguint_below(&mut rng, &bound);
+            assert!(x < bound);
+            // bucket by top bit and low bits, all uniform if x is
+            let low = x.to_u32_digits().first().cloned().unwrap_or(0) as usize & 3;
+            counts[(x.bits() == 33) as usize * 4 + low] += 1;
+        }
+        // x >= 2^32 has probability 3/bound, i.e. effectively zero
+        assert!(counts[4..].iter().sum::<u64>() < 5);
+        let chi2 = chi_squared(&counts[..4]);
+        assert!(chi2 < chi_squared_limit(3), "bound 2^32+3: {:?}", counts);
+        
+        // Signs: -1, 0 and 1 are equally likely, since negative zero is
+        // rejected rather than counted as zero
+        let values = [BigInt::from(-1), BigInt::from(0), BigInt::from(1)];
+        let mut counts = [0u64; 3];
+        for _ in 0..SAMPLES {
+            let x = gen_bigint(&mut rng, 1);
+            counts[values.iter().position(|v| *v == x).unwrap()] += 1;
+        }
+        let chi2 = chi_squared(&counts);
+        assert!(chi2 < chi_squared_limit(2), "gen_bigint(1): {:?}", counts);
+        println!("tiny bounds, chi-squared: ok");
+    }
+}
@@ -0,0 +1,316 @@
+//! Bulk Bernoulli trials packed into a bitmap
+//! 
+//! `fill_bool_bitmap(out, p)` sets each of the `8 * out.len()` bits of `out`
+//! independently with probability `p` (bit `j` of `out[k]` is trial
+//! `8k + j`); `fill_bool_bitmap_ratio(out, num, den)` does the same with
+//! probability exactly `num / den`.
+//! 
+//! Instead of one comparison per trial, 64 trials run in parallel, one per bit
+//! of a `u64`. Each trial compares a uniform `U` in `[0, 1)` with `p` bit by
+//! bit, most significant first, drawing one word per bit position for all 64
+//! trials at once; a trial is decided at the first bit where `U` and `p`
+//! differ. All 64 are usually decided after about `log2(64) + 2` words, and
+//! `p = 1/2` takes exactly one. The bits of `p` come from its exact binary
+//! expansion: doubling an `f64` in `[0, 1)` and subtracting one are exact, and
+//! for `num / den` long division gives the (possibly infinite) expansion. A
+//! finite expansion ends the comparison early: remaining trials are `false`.
+//! So both variants are exact, not just within some `2^-k`.
+//! 
+//! Run with:
+//! 
+//!     rustc -O bool_bitmap.rs && ./bool_bitmap
+//! 
+//! and see `bench/bool_bitmap.rs` for a comparison with per-bool sampling.
+//! 
+//! Thoughts: per-bool sampling needs a full word per trial (or a lossy split of
+//! one); here it's about 1/8 word per trial whatever `p`, and `p = 1/2` costs
+//! 1/64. The output stream differs from per-bool sampling, so switching a
+//! simulation to the bitmap changes its results, though not their distribution.
+
+// ——— traits ———
+
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+    
+    /// Set each bit of `out` independently with probability `p`.
+    /// 
+    /// Panics unless `0 <= p <= 1`.
+    fn fill_bool_bitmap(&mut self, out: &mut [u8], p: f64) {
+        assert!((0.0..=1.0).contains(&p), "fill_bool_bitmap: p = {} not in [0, 1]", p);
+        fill(self, out, Expansion::Float(p))
+    }
+    
+    /// Set each bit of `out` independently with probability `num / den`.
+    /// 
+    /// Panics unless `num <= den` and `den > 0`.
+    fn fill_bool_bitmap_ratio(&mut self, out: &mut [u8], num: u32, den: u32) {
+        assert!(num <= den && den > 0, "fill_bool_bitmap_ratio: invalid ratio {}/{}", num, den);
+        fill(self, out, Expansion::Ratio { rem: num as u64, den: den as u64 })
+    }
+}
+
+// ——— implementation ———
+
+/// Binary expansion of a probability; `next` yields its bits after the point.
+#[derive(Clone, Copy)]
+enum Expansion {
+    Float(f64),
+    Ratio { rem: u64, den: u64 },
+}
+
+impl Expansion {
+    fn is_one(&self) -> bool {
+        match *self {
+            Expansion::Float(p) => p == 1.0,
+            Expansion::Ratio { rem, den } => rem == den,
+        }
+    }
+    
+    /// Next bit, or `None` once all remaining bits are zero.
+    fn next(&mut self) -> Option<bool> {
+        match *self {
+            Expansion::Float(ref mut x) => {
+                if *x == 0.0 {
+                    return None;
+                }
+                *x *= 2.0;
+                let bit = *x >= 1.0;
+                if bit {
+                    *x -= 1.0;
+                }
+                Some(bit)
+            }
+            Expansion::Ratio { ref mut rem, den } => {
+                if *rem == 0 {
+                    return None;
+                }
+                *rem *= 2;
+                let bit = *rem >= den;
+                if bit {
+                    *rem -= den;
+                }
+                Some(bit)
+            }
+        }
+    }
+}
+
+/// The first (up to) 64 bits of an expansion, most significant first, and
+/// the expansion after them.
+#[derive(Clone, Copy)]
+struct Prefix {
+    bits: u64,
+    len: u32,
+    rest: Expansion,
+}
+
+impl Prefix {
+    fn new(mut p: Expansion) -> Self {
+        let (mut bits, mut len) = (0, 0);
+        while len < 64 {
+            match p.next() {
+                Some(bit) => bits |= (bit as u64) << (63 - len),
+                None => break,
+            }
+            len += 1;
+        }
+        Prefix { bits, len, rest: p }
+    }
+}
+
+/// Compare one more bit of each `U` with `bit` of `p`, given the trials'
+/// `(result, undecided)` masks so far.
+#[inline]
+fn step((result, undecided): (u64, u64), bit: bool, u: u64) -> (u64, u64) {
+    // Where p's bit is 1, U's bit 0 means U < p; either way, U's bit equal to
+    // p's leaves the trial undecided.
+    let p = (bit as u64).wrapping_neg();
+    (result | undecided & !u & p, undecided & !(u ^ p))
+}
+
+/// 64 trials: bit `i` is set iff `U_i < p`, where `U_i` has bits `i` of the
+/// words drawn.
+fn trials<R: Rng+?Sized>(rng: &mut R, p: &Prefix) -> u64 {
+    let mut state = (0, !0);
+    for i in 0..p.len {
+        state = step(state, p.bits >> (63 - i) & 1 == 1, rng.next_u64());
+        if state.1 == 0 {
+            return state.0;
+        }
+    }
+    // Undecided after 64 bits: rare, unless p's expansion ends
+    let mut rest = p.rest;
+    while state.1 != 0 {
+        match rest.next() {
+            Some(bit) => state = step(state, bit, rng.next_u64()),
+            None => break,
+        }
+    }
+    state.0
+}
+
+fn fill<R: Rng+?Sized>(rng: &mut R, out: &mut [u8], p: Expansion) {
+    if p.is_one() {
+        for b in out.iter_mut() {
+            *b = !0;
+        }
+        return;
+    }
+    let prefix = Prefix::new(p);
+    for chunk in out.chunks_mut(8) {
+        let x = trials(rng, &prefix).to_le_bytes();
+        chunk.copy_from_slice(&x[..chunk.len()]);
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "../traits/test_rngs.rs"]
+#[allow(dead_code)]
+mod test_rngs;
+use self::test_rngs::Source;
+
+/// SplitMix64 of `test_rngs.rs`, counting the words drawn.
+pub struct SplitMix {
+    source: test_rngs::SplitMix,
+    pub words: u64,
+}
+
+impl SplitMix {
+    pub fn new(seed: u64) -> Self {
+        SplitMix { source: test_rngs::SplitMix(seed), words: 0 }
+    }
+}
+
+impl Rng for SplitMix {
+    fn next_u64(&mut self) -> u64 {
+        self.words += 1;
+        self.source.word()
+    }
+}
+
+/// Per-bool reference: one word per trial, `p` to 64 bits.
+pub fn gen_bool<R: Rng+?Sized>(rng: &mut R, p: f64) -> bool {
+    if p == 1.0 {
+        return true;
+    }
+    rng.next_u64() < (p * 2f64.powi(64)) as u64
+}
+
+// ——— usage ———
+
+fn ones(bitmap: &[u8]) -> u64 {
+    bitmap.iter().map(|b| b.count_ones() as u64).sum()
+}
+
+/// Bitmaps of `len` bytes from `f`, `reps` times: check the overall rate, the
+/// rate at each of the 64 bit positions of a word, and of adjacent pairs.
+fn check<F: FnMut(&mut SplitMix, &mut [u8])>(name: &str, p: f64, len: usize, reps: usize, mut f: F) {
+    let mut rng = SplitMix::new(0x5EED ^ len as u64);
+    let mut buf = vec![0u8; len];
+    let mut by_pos = [0u64; 64];
+    let (mut total, mut pairs) = (0u64, 0u64);
+    for _ in 0..reps {
+        f(&mut rng, &mut buf);
+        total += ones(&buf);
+        for i in 0..8 * len {
+            let bit = |i: usize| buf[i / 8] >> (i % 8) & 1 == 1;
+            if bit(i) {
+                by_pos[i % 64] += 1;
+                if i > 0 && bit(i - 1) {
+                    pairs += 1;
+                }
+            }
+        }
+    }
+    let n = (8 * len * reps) as f64;
+    let sigma = (n * p * (1.0 - p)).sqrt();
+    assert!((total as f64 - n * p).abs() <= 5.0 * sigma + 1e-9,
+        "{}: {} ones in {} trials, expected {}", name, total, n, n * p);
+    let pn = (n - reps as f64) * p * p;
+    assert!((pairs as f64 - pn).abs() <= 5.0 * pn.sqrt() + 1e-9,
+        "{}: {} adjacent pairs, expected {}", name, pairs, pn);
+    if len.is_multiple_of(8) && p > 0.0 && p < 1.0 {
+        let e = n / 64.0 * p;
+        let chi2: f64 = by_pos.iter().map(|&c| (c as f64 - e).powi(2) / (e * (1.0 - p))).sum();
+        assert!(chi2 < 63.0 + 5.0 * 126f64.sqrt(), "{}: chi2 over positions {}", name, chi2);
+    }
+    println!("{}: {:.5} observed ({} trials, {:.2} words per 64)",
+        name, total as f64 / n, n, rng.words as f64 * 64.0 / n);
+}
+
+fn main() {
+    {
+        // Degenerate probabilities, and their cost
+        let mut rng = SplitMix::new(1);
+        let mut buf = [0x55u8; 20];
+        rng.fill_bool_bitmap(&mut buf, 0.0);
+        assert!(buf == [0; 20] && rng.words == 0);
+        rng.fill_bool_bitmap(&mut buf, 1.0);
+        assert!(buf == [!0; 20] && rng.words == 0);
+        rng.fill_bool_bitmap_ratio(&mut buf, 0, 7);
+        assert!(buf == [0; 20]);
+        rng.fill_bool_bitmap_ratio(&mut buf, 7, 7);
+        assert!(buf == [!0; 20] && rng.words == 0);
+        
+        // One half takes one word per 64 trials: the word itself
+        let mut copy = SplitMix::new(2);
+        let mut rng = SplitMix::new(2);
+        rng.fill_bool_bitmap(&mut buf, 0.5);
+        assert_eq!(rng.words, 3);
+        let mut want = vec![];
+        for _ in 0..3 {
+            want.extend_from_slice(&(!copy.next_u64()).to_le_bytes());
+        }
+        assert!(buf[..] == want[..20]);
+        
+        // Powers of two terminate: 1/4 is "01", two words
+        let mut rng = SplitMix::new(3);
+        rng.fill_bool_bitmap(&mut buf[..8], 0.25);
+        assert_eq!(rng.words, 2);
+        rng.fill_bool_bitmap_ratio(&mut buf[..8], 2, 8);
+        assert_eq!(rng.words, 4);
+        println!("degenerate and dyadic probabilities: ok");
+    }
+    {
+        // Exactness: with words enumerating every combination of U's top
+        // bits, the fraction of U below p is exactly p
+        struct Enum(u64, u32);
+        impl Rng for Enum {
+            fn next_u64(&mut self) -> u64 {
+                // word j holds bit j (from the top) of each lane's U = lane
+                self.1 += 1;
+                let mut w = 0;
+                for lane in 0..64 {
+                    w |= ((lane + self.0) >> (6 - self.1) & 1) << lane;
+                }
+                w
+            }
+        }
+        for &(num, den) in [(1u32, 64u32), (5, 64), (21, 64), (63, 64), (1, 2), (3, 8)].iter() {
+            let mut rng = Enum(0, 0);
+            let x = trials(&mut rng, &Prefix::new(Expansion::Ratio { rem: num as u64, den: den as u64 }));
+            assert_eq!(x.count_ones(), num * 64 / den, "{}/{}", num, den);
+            let mut rng = Enum(0, 0);
+            let x = trials(&mut rng, &Prefix::new(Expansion::Float(num as f64 / den as f64)));
+            assert_eq!(x.count_ones(), num * 64 / den, "{}/{} (f64)", num, den);
+        }
+        println!("exactness over all 6-bit inputs: ok");
+    }
+    for &p in [0.5, 0.1, 0.3, 1e-3, 0.999, 2f64.powi(-10) * 3.0].iter() {
+        check(&format!("p = {}", p), p, 64, 2000, |rng, buf| rng.fill_bool_bitmap(buf, p));
+    }
+    for &(num, den) in [(1u32, 3u32), (2, 7), (999, 1000), (1, 1 << 31)].iter() {
+        let p = num as f64 / den as f64;
+        check(&format!("{}/{}", num, den), p, 64, 2000, |rng, buf| rng.fill_bool_bitmap_ratio(buf, num, den));
+    }
+    // Lengths not a multiple of a word
+    check("p = 0.3, 13 bytes", 0.3, 13, 5000, |rng, buf| rng.fill_bool_bitmap(buf, 0.3));
+    // The per-bool reference agrees
+    check("gen_bool, p = 0.3", 0.3, 64, 200, |rng, buf| {
+        for i in 0..8 * buf.len() {
+            let b = gen_bool(rng, 0.3) as u8;
+            buf[i / 8] = buf[i / 8] & !(1 << (i % 8)) | b << (i % 8);
+        }
+    });
+}
@@ -0,0 +1,261 @@
+//! Distribution combinators: `map`, `zip` and `compose`
+//! 
+//! Derived distributions without new types, as provided methods of a
+//! `Distribution<T>` trait:
+//! 
+//! *   `d.map(f)`: `f(x)` for `x` from `d`, e.g. dice as `1..=6` from `0..6`
+//! *   `a.zip(b)`: `(x, y)` for `x` from `a` then `y` from `b`, e.g. a random
+//!     point from two uniform coordinates
+//! *   `d.compose(f)`: a sample of the distribution `f(x)` for `x` from `d`,
+//!     where the second distribution depends on the first value, e.g. a
+//!     random length then a random index below it, or a mixture choosing
+//!     between distributions
+//! 
+//! Each draws exactly what its parts draw, in order (`main` checks this
+//! against sampling the parts by hand), so a combinator never changes the
+//! stream of a seeded program. `Distribution` is not object safe (`sample` is
+//! generic over the generator); the object-safe `DynDistribution` takes
+//! `&mut dyn Rng`, is implemented for every distribution, and boxed is a
+//! `Distribution` again, so combinators work on and produce trait objects.
+//! 
+//! Run with:
+//! 
+//!     rustc -O combinators.rs && ./combinators
+//! 
+//! Thoughts: `map` needs a type parameter for the input type (`Map<D, F,
+//! T>`), since a distribution may implement `Distribution<T>` for several `T`
+//! and the closure alone does not fix it; inference copes when `d` has one
+//! impl. The combinators take `self` by value, so reusing a distribution
+//! means cloning it or combining references (`(&d).map(f)`), as with
+//! iterators. `compose` builds its second distribution per sample, so setup
+//! work there (e.g. `UniformInt`'s threshold, a division) is repeated every
+//! time; this is the price of the dependency, and why `zip` exists.
+
+#[path = "uniform_int.rs"]
+#[allow(dead_code)]
+mod uniform_int;
+
+#[path = "../verify/chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+pub use self::uniform_int::{Rng, SplitMix, UniformInt};
+use self::chi_squared::{chi_squared, chi_squared_limit};
+use std::marker::PhantomData;
+
+// ——— traits ———
+
+pub trait Distribution<T> {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> T;
+    
+    /// `f(x)` for `x` from this distribution.
+    fn map<U, F: Fn(T) -> U>(self, f: F) -> Map<Self, F, T> where Self: Sized {
+        Map { distr: self, f, _input: PhantomData }
+    }
+    
+    /// `(x, y)`, sampling `x` from this distribution, then `y` from `other`.
+    fn zip<U, D: Distribution<U>>(self, other: D) -> Zip<Self, D> where Self: Sized {
+        Zip(self, other)
+    }
+    
+    /// A sample of `f(x)`, for `x` from this distribution.
+    fn compose<U, D: Distribution<U>, F: Fn(T) -> D>(self, f: F) -> Compose<Self, F, T>
+        where Self: Sized
+    {
+        Compose { distr: self, f, _input: PhantomData }
+    }
+}
+
+impl<T, D: Distribution<T>+?Sized> Distribution<T> for &D {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> T {
+        (**self).sample(rng)
+    }
+}
+
+/// Object-safe `Distribution`, implemented for all distributions.
+pub trait DynDistribution<T> {
+    fn sample_dyn(&self, rng: &mut dyn Rng) -> T;
+}
+
+impl<T, D: Distribution<T>> DynDistribution<T> for D {
+    fn sample_dyn(&self, rng: &mut dyn Rng) -> T {
+        self.sample(rng)
+    }
+}
+
+impl<'a, T> Distribution<T> for Box<dyn DynDistribution<T> + 'a> {
+    fn sample<R: Rng+?Sized>(&self, mut rng: &mut R) -> T {
+        // `&mut R` is `Sized` even if `R` is not, so it coerces to `&mut dyn Rng`
+        (**self).sample_dyn(&mut rng)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+}
+
+// ——— combinators ———
+
+#[derive(Debug, Clone, Copy)]
+pub struct Map<D, F, T> {
+    distr: D,
+    f: F,
+    _input: PhantomData<fn() -> T>,
+}
+
+impl<T, U, D: Distribution<T>, F: Fn(T) -> U> Distribution<U> for Map<D, F, T> {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> U {
+        (self.f)(self.distr.sample(rng))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Zip<A, B>(A, B);
+
+impl<T, U, A: Distribution<T>, B: Distribution<U>> Distribution<(T, U)> for Zip<A, B> {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> (T, U) {
+        let x = self.0.sample(rng);
+        (x, self.1.sample(rng))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Compose<D, F, T> {
+    distr: D,
+    f: F,
+    _input: PhantomData<fn() -> T>,
+}
+
+impl<T, U, D, E, F> Distribution<U> for Compose<D, F, T>
+    where D: Distribution<T>, E: Distribution<U>, F: Fn(T) -> E
+{
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> U {
+        (self.f)(self.distr.sample(rng)).sample(rng)
+    }
+}
+
+// ——— distributions ———
+
+macro_rules! uniform_int_distribution {
+    ($($ty:ident, $word:ident;)*) => { $(
+        impl Distribution<$ty> for UniformInt<$ty, $word> {
+            fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> $ty {
+                UniformInt::<$ty, $word>::sample(self, rng)
+            }
+        }
+    )* };
+}
+
+uniform_int_distribution! { i32, u32; u32, u32; i64, u64; u64, u64; usize, u64; }
+
+/// `[0, 1)` from the top 53 bits of a `u64`.
+#[derive(Debug, Clone, Copy)]
+pub struct Unit;
+
+impl Distribution<f64> for Unit {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> f64 {
+        (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// `true` with probability `p`.
+#[derive(Debug, Clone, Copy)]
+pub struct Bernoulli(pub f64);
+
+impl Distribution<bool> for Bernoulli {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> bool {
+        Unit.sample(rng) < self.0
+    }
+}
+
+// ——— test RNGs ———
+
+/// Counts the calls to a `SplitMix`.
+#[derive(Clone)]
+struct Counting(SplitMix, usize);
+
+impl Rng for Counting {
+    fn next_u32(&mut self) -> u32 {
+        self.1 += 1;
+        self.0.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.1 += 1;
+        self.0.next_u64()
+    }
+}
+
+// ——— usage ———
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+fn main() {
+    let seed = Counting(SplitMix(0x5EED), 0);
+    let die = UniformInt::<u32, _>::new(0, 6);
+    {
+        // Each combinator draws what its parts draw, in order
+        let (mut a, mut b) = (seed.clone(), seed.clone());
+        let d = die.map(|x| x + 1);
+        let point = Unit.zip(Unit).map(|(x, y)| Point { x, y });
+        let span = UniformInt::<usize, _>::new(1, 1000)
+            .compose(|n| UniformInt::<usize, _>::new(0, n).map(move |i| (n, i)));
+        for _ in 0..1000 {
+            assert_eq!(d.sample(&mut a), die.sample(&mut b) + 1);
+            let p = point.sample(&mut a);
+            assert_eq!(p, Point { x: Unit.sample(&mut b), y: Unit.sample(&mut b) });
+            let (n, i) = span.sample(&mut a);
+            assert!(i < n);
+            assert_eq!(n, UniformInt::<usize, _>::new(1, 1000).sample(&mut b));
+            assert_eq!(i, UniformInt::<usize, _>::new(0, n).sample(&mut b));
+            assert_eq!(a.1, b.1);
+        }
+        println!("map, zip, compose: same values and draws as their parts ({} calls)", a.1);
+    }
+    {
+        // Zip samples independently: all 36 pairs of two dice equally likely
+        let mut rng = seed.clone();
+        let pair = die.zip(die).map(|(x, y)| (6 * x + y) as usize);
+        let mut counts = [0u64; 36];
+        for _ in 0..360_000 {
+            counts[pair.sample(&mut rng)] += 1;
+        }
+        assert!(chi_squared(&counts) < chi_squared_limit(35), "zip: {:?}", counts);
+        println!("zip of two dice: uniform over pairs");
+    }
+    {
+        // Through trait objects: distributions chosen at run time, combined
+        // and sampled with `&mut dyn Rng`
+        let (mut a, mut b) = (seed.clone(), seed.clone());
+        let low: Box<dyn DynDistribution<u32>> = Box::new(die);
+        let high: Box<dyn DynDistribution<u32>> = Box::new(die.map(|x| x + 100));
+        let mixture = Bernoulli(0.25).compose(|h| if h { &high } else { &low });
+        let dyn_rng: &mut dyn Rng = &mut a;
+        let xs: Vec<u32> = (0..1000).map(|_| mixture.sample(dyn_rng)).collect();
+        for &x in xs.iter() {
+            let h = Bernoulli(0.25).sample(&mut b);
+            assert_eq!(x, die.sample(&mut b) + if h { 100 } else { 0 });
+        }
+        assert_eq!(a.1, b.1);
+        let high_count = xs.iter().filter(|&&x| x >= 100).count();
+        assert!(200 < high_count && high_count < 300);
+        let boxed: Box<dyn DynDistribution<(u32, f64)>> = Box::new(mixture.zip(Unit));
+        let (x, u) = boxed.map(|(x, u)| (x * 2, u)).sample(&mut a);
+        assert!(x % 2 == 0 && (0.0..1.0).contains(&u));
+        println!("dyn DynDistribution and &mut dyn Rng: same draws");
+    }
+}
@@ -0,0 +1,702 @@
+//! `Distribution<T>`: sampling over each design, and whether it needs
+//! `try_sample`
+//! 
+//! Three simple distributions, their arithmetic shared: `Bernoulli::new(p)`
+//! (one `u64` against `p * 2^64`), `Exp::new(lambda)` (by inversion) and
+//! `Disc`, a point uniform in the unit disc by rejection, with no bound on
+//! the words drawn. The trait is written over three designs of `traits/`,
+//! each included whole, as in `standard.rs`:
+//! 
+//! *   `extends_Rng`: `sample<R: Rng>` only; `try_sample` is a free function
+//!     over a `Sticky` adaptor, which keeps the first error and gives out a
+//!     fixed word from then on
+//! *   `extends_CryptoRng`: `try_sample<R: CryptoRng>` required, `sample`
+//!     provided, unwrapping it
+//! *   `raw_infallible`: `try_sample<E, R: RawRng<E>>` required, `sample`
+//!     provided at `E = Infallible`
+//! 
+//! Each also provides `sample_iter(self, rng)` and `try_sample_iter`, taking
+//! the distribution and the generator by value, as rand 0.7's does.
+//! 
+//! Run with:
+//! 
+//!     rustc -O distribution.rs && ./distribution
+//! 
+//! Compile-fail check (must fail to compile):
+//! 
+//!     rustc --cfg fail_borrowed_try_iter distribution.rs
+//! 
+//! Thoughts: there should be a `try_sample`, required, with `sample`
+//! derived from it: a rejection loop like `Disc`'s is where a failure lands
+//! mid-computation, and `Sticky`'s fixed word must be one every sampler
+//! accepts or the loop spins forever. Over `RawRng<E>` errors stay typed
+//! through the sampler. Its one limit: `&mut R` is a fallible `RawRng` only
+//! for infallible `R`, so `try_sample_iter` can't borrow a fallible
+//! generator (`fail_borrowed_try_iter`).
+
+use std::convert::Infallible;
+use std::marker::PhantomData;
+
+#[path = "../traits/extends_Rng.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+mod extends_rng;
+
+#[path = "../traits/extends_CryptoRng.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+mod extends_cryptorng;
+
+#[path = "../traits/raw_infallible.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+mod raw_infallible;
+
+// ——— distributions ———
+
+/// `[0, 1)` from the top 53 bits of a word.
+fn unit(x: u64) -> f64 {
+    (x >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// `true` with probability `p`.
+#[derive(Debug, Clone, Copy)]
+pub struct Bernoulli {
+    p_int: u64,
+}
+
+impl Bernoulli {
+    /// `p = 1` is `true` but for one word in 2^64.
+    pub fn new(p: f64) -> Bernoulli {
+        assert!((0.0..=1.0).contains(&p), "Bernoulli::new: p not in [0, 1]");
+        let p_int = if p == 1.0 { u64::MAX } else { (p * 2f64.powi(64)) as u64 };
+        Bernoulli { p_int }
+    }
+    
+    fn of(&self, x: u64) -> bool {
+        x < self.p_int
+    }
+}
+
+/// Exponential, with rate `lambda`.
+#[derive(Debug, Clone, Copy)]
+pub struct Exp {
+    mean: f64,
+}
+
+impl Exp {
+    pub fn new(lambda: f64) -> Exp {
+        assert!(lambda > 0.0, "Exp::new: lambda not positive");
+        Exp { mean: 1.0 / lambda }
+    }
+    
+    fn of(&self, x: u64) -> f64 {
+        -(1.0 - unit(x)).ln() * self.mean
+    }
+}
+
+/// Uniform in the unit disc.
+#[derive(Debug, Clone, Copy)]
+pub struct Disc;
+
+impl Disc {
+    /// The point of a pair of words, if in the disc.
+    fn of(&self, x: u64, y: u64) -> Option<(f64, f64)> {
+        let (x, y) = (2.0 * unit(x) - 1.0, 2.0 * unit(y) - 1.0);
+        if x * x + y * y < 1.0 {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+}
+
+// ——— over extends_Rng ———
+
+mod on_extends_rng {
+    use super::extends_rng::{CryptoError, CryptoRng, Rng};
+    use super::{Bernoulli, Disc, Exp, PhantomData};
+    
+    pub trait Distribution<T> {
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> T;
+        
+        /// Samples without end, from `rng`, owned or lent.
+        fn sample_iter<R: Rng>(self, rng: R) -> DistIter<Self, R, T> where Self: Sized {
+            DistIter { distr: self, rng, _values: PhantomData }
+        }
+    }
+    
+    impl<T, D: Distribution<T>+?Sized> Distribution<T> for &D {
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> T {
+            (**self).sample(rng)
+        }
+    }
+    
+    /// The samples of `D`, from `R`.
+    pub struct DistIter<D, R, T> {
+        distr: D,
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<D: Distribution<T>, R: Rng, T> Iterator for DistIter<D, R, T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            Some(self.distr.sample(&mut self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    impl Distribution<bool> for Bernoulli {
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> bool {
+            self.of(rng.next_u64())
+        }
+    }
+    
+    impl Distribution<f64> for Exp {
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> f64 {
+            self.of(rng.next_u64())
+        }
+    }
+    
+    impl Distribution<(f64, f64)> for Disc {
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> (f64, f64) {
+            loop {
+                let x = rng.next_u64();
+                if let Some(p) = self.of(x, rng.next_u64()) {
+                    return p;
+                }
+            }
+        }
+    }
+    
+    /// A `CryptoRng` as an `Rng` which doesn't panic: it keeps the first
+    /// error, and gives out `1 << 31`, mid-range, from then on.
+    pub struct Sticky<'a, R: CryptoRng+?Sized+'a> {
+        rng: &'a mut R,
+        error: Option<CryptoError>,
+    }
+    
+    impl<'a, R: CryptoRng+?Sized> Rng for Sticky<'a, R> {
+        fn next_u32(&mut self) -> u32 {
+            if self.error.is_none() {
+                match self.rng.try_next_u32() {
+                    Ok(x) => return x,
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            1 << 31
+        }
+    }
+    
+    /// A sample, or the generator's first error while drawing it.
+    pub fn try_sample<T, D, R>(distr: &D, rng: &mut R) -> Result<T, CryptoError>
+        where D: Distribution<T>+?Sized, R: CryptoRng+?Sized
+    {
+        let mut sticky = Sticky { rng, error: None };
+        let x = distr.sample(&mut sticky);
+        match sticky.error {
+            Some(e) => Err(e),
+            None => Ok(x),
+        }
+    }
+    
+    /// `try_sample`'s results without end, from `rng`, owned or lent.
+    pub fn try_sample_iter<T, D, R>(distr: D, rng: R) -> TryDistIter<D, R, T>
+        where D: Distribution<T>, R: CryptoRng
+    {
+        TryDistIter { distr, rng, _values: PhantomData }
+    }
+    
+    /// The results of `try_sample` for `D`, from `R`.
+    pub struct TryDistIter<D, R, T> {
+        distr: D,
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<D: Distribution<T>, R: CryptoRng, T> Iterator for TryDistIter<D, R, T> {
+        type Item = Result<T, CryptoError>;
+        
+        fn next(&mut self) -> Option<Result<T, CryptoError>> {
+            Some(try_sample(&self.distr, &mut self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+}
+
+// ——— over extends_CryptoRng ———
+
+mod on_extends_cryptorng {
+    use super::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use super::{Bernoulli, Disc, Exp, PhantomData};
+    
+    pub trait Distribution<T> {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<T, CryptoError>;
+        
+        /// A sample, panicking on error.
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> T {
+            self.try_sample(rng).unwrap_or_else(|e| panic!("Distribution::sample failed: {}", e))
+        }
+        
+        /// Samples without end, from `rng`, owned or lent.
+        fn sample_iter<R: Rng>(self, rng: R) -> DistIter<Self, R, T> where Self: Sized {
+            DistIter { distr: self, rng, _values: PhantomData }
+        }
+        
+        /// `try_sample`'s results without end, from `rng`, owned or lent.
+        fn try_sample_iter<R: CryptoRng>(self, rng: R) -> TryDistIter<Self, R, T>
+            where Self: Sized
+        {
+            TryDistIter { distr: self, rng, _values: PhantomData }
+        }
+    }
+    
+    impl<T, D: Distribution<T>+?Sized> Distribution<T> for &D {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<T, CryptoError> {
+            (**self).try_sample(rng)
+        }
+    }
+    
+    /// The samples of `D`, from `R`.
+    pub struct DistIter<D, R, T> {
+        distr: D,
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<D: Distribution<T>, R: Rng, T> Iterator for DistIter<D, R, T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            Some(self.distr.sample(&mut self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// The results of `try_sample` for `D`, from `R`.
+    pub struct TryDistIter<D, R, T> {
+        distr: D,
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<D: Distribution<T>, R: CryptoRng, T> Iterator for TryDistIter<D, R, T> {
+        type Item = Result<T, CryptoError>;
+        
+        fn next(&mut self) -> Option<Result<T, CryptoError>> {
+            Some(self.distr.try_sample(&mut self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    impl Distribution<bool> for Bernoulli {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<bool, CryptoError> {
+            Ok(self.of(rng.try_next_u64()?))
+        }
+    }
+    
+    impl Distribution<f64> for Exp {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<f64, CryptoError> {
+            Ok(self.of(rng.try_next_u64()?))
+        }
+    }
+    
+    impl Distribution<(f64, f64)> for Disc {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<(f64, f64), CryptoError> {
+            loop {
+                let x = rng.try_next_u64()?;
+                if let Some(p) = self.of(x, rng.try_next_u64()?) {
+                    return Ok(p);
+                }
+            }
+        }
+    }
+}
+
+// ——— over raw_infallible ———
+
+mod on_raw_infallible {
+    use super::raw_infallible::{RawRng, Rng};
+    use super::{Bernoulli, Disc, Exp, Infallible, PhantomData};
+    
+    pub trait Distribution<T> {
+        fn try_sample<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<T, E>;
+        
+        /// A sample; the generator can't fail.
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> T {
+            self.try_sample::<Infallible, R>(rng).unwrap_or_else(|e| match e {})
+        }
+        
+        /// Samples without end, from `rng`, owned or lent.
+        fn sample_iter<R: Rng>(self, rng: R) -> DistIter<Self, R, T> where Self: Sized {
+            DistIter { distr: self, rng, _values: PhantomData }
+        }
+        
+        /// `try_sample`'s results without end, from `rng`: owned, unless
+        /// infallible.
+        fn try_sample_iter<E, R: RawRng<E>>(self, rng: R) -> TryDistIter<Self, R, T, E>
+            where Self: Sized
+        {
+            TryDistIter { distr: self, rng, _values: PhantomData }
+        }
+    }
+    
+    impl<T, D: Distribution<T>+?Sized> Distribution<T> for &D {
+        fn try_sample<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<T, E> {
+            (**self).try_sample(rng)
+        }
+    }
+    
+    /// The samples of `D`, from `R`.
+    pub struct DistIter<D, R, T> {
+        distr: D,
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<D: Distribution<T>, R: Rng, T> Iterator for DistIter<D, R, T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            Some(self.distr.sample(&mut self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// The results of `try_sample` at `E` for `D`, from `R`.
+    pub struct TryDistIter<D, R, T, E> {
+        distr: D,
+        rng: R,
+        _values: PhantomData<fn() -> Result<T, E>>,
+    }
+    
+    impl<D, R, T, E> TryDistIter<D, R, T, E> {
+        /// The generator, where the samples stopped.
+        pub fn into_inner(self) -> R {
+            self.rng
+        }
+    }
+    
+    impl<D: Distribution<T>, R: RawRng<E>, T, E> Iterator for TryDistIter<D, R, T, E> {
+        type Item = Result<T, E>;
+        
+        fn next(&mut self) -> Option<Result<T, E>> {
+            Some(self.distr.try_sample(&mut self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    impl Distribution<bool> for Bernoulli {
+        fn try_sample<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<bool, E> {
+            Ok(self.of(rng.try_next_u64()?))
+        }
+    }
+    
+    impl Distribution<f64> for Exp {
+        fn try_sample<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<f64, E> {
+            Ok(self.of(rng.try_next_u64()?))
+        }
+    }
+    
+    impl Distribution<(f64, f64)> for Disc {
+        fn try_sample<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<(f64, f64), E> {
+            loop {
+                let x = rng.try_next_u64()?;
+                if let Some(p) = self.of(x, rng.try_next_u64()?) {
+                    return Ok(p);
+                }
+            }
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+/// extends_Rng's `TestCRng` keeps `CryptoRng`'s provided `try_` methods,
+/// which can't fail; this one's fail with its source.
+struct FallibleCRng<S>(S);
+
+impl<S: extends_rng::test_rngs::Source> extends_rng::Rng for FallibleCRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: extends_rng::test_rngs::Source> extends_rng::CryptoRng for FallibleCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, extends_rng::CryptoError> {
+        self.0.try_word().map(|x| x as u32).map_err(|_| {
+            extends_rng::CryptoError::new(extends_rng::ErrorKind::Unavailable, "test source exhausted")
+        })
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, extends_rng::CryptoError> {
+        let lo = self.try_next_u32()?;
+        Ok((self.try_next_u32()? as u64) << 32 | lo as u64)
+    }
+}
+
+// ——— consumers ———
+
+/// The arrival times of a Poisson process of rate `lambda`: the running sum
+/// of exponential gaps.
+fn arrivals<R: raw_infallible::Rng>(rng: R, lambda: f64) -> impl Iterator<Item = f64> {
+    use on_raw_infallible::Distribution;
+    Exp::new(lambda).sample_iter(rng).scan(0.0, |t, gap| {
+        *t += gap;
+        Some(*t)
+    })
+}
+
+// `&mut R` is `RawRng<Error>` only for an infallible `R`: a fallible
+// generator can't be lent to `try_sample_iter`
+#[cfg(fail_borrowed_try_iter)]
+fn lent(f: &mut raw_infallible::test_rngs::TestFCRng<raw_infallible::test_rngs::FailAfter>)
+    -> Option<Result<bool, raw_infallible::Error>>
+{
+    use on_raw_infallible::Distribution;
+    Bernoulli::new(0.5).try_sample_iter(f).next()
+}
+
+// ——— usage ———
+
+/// Samples of each distribution, as `main` draws them.
+type Samples = (Vec<bool>, Vec<f64>, Vec<(f64, f64)>);
+
+const N: usize = 10_000;
+
+fn main() {
+    let (b, e) = (Bernoulli::new(0.25), Exp::new(2.0));
+    let samples: [Samples; 3] = [
+        {
+            use extends_rng::test_rngs::{SplitMix, TestRng};
+            use on_extends_rng::Distribution;
+            let mut t = TestRng(SplitMix(1));
+            ((0..N).map(|_| b.sample(&mut t)).collect(),
+                (0..N).map(|_| e.sample(&mut t)).collect(),
+                (0..N).map(|_| Disc.sample(&mut t)).collect())
+        },
+        {
+            use extends_cryptorng::test_rngs::{SplitMix, TestRng};
+            use on_extends_cryptorng::Distribution;
+            let mut t = TestRng(SplitMix(1));
+            ((0..N).map(|_| b.sample(&mut t)).collect(),
+                (0..N).map(|_| e.sample(&mut t)).collect(),
+                (0..N).map(|_| Disc.sample(&mut t)).collect())
+        },
+        {
+            use on_raw_infallible::Distribution;
+            use raw_infallible::test_rngs::{SplitMix, TestRng};
+            let mut t = TestRng(SplitMix(1));
+            ((0..N).map(|_| b.sample(&mut t)).collect(),
+                (0..N).map(|_| e.sample(&mut t)).collect(),
+                (0..N).map(|_| Disc.sample(&mut t)).collect())
+        },
+    ];
+    assert!(samples[0] == samples[1] && samples[0] == samples[2]);
+    let (ref bs, ref es, ref ds) = samples[0];
+    let p = bs.iter().filter(|&&x| x).count() as f64 / N as f64;
+    let mean = es.iter().sum::<f64>() / N as f64;
+    assert!((p - 0.25).abs() < 0.02 && (mean - 0.5).abs() < 0.02);
+    assert!(ds.iter().all(|&(x, y)| x * x + y * y < 1.0));
+    println!("sample: the same over each design; Bernoulli(0.25) {}, Exp(2) mean {:.3}", p, mean);
+    {
+        // The cost, metered: a word each for the inversions, a pair a try
+        // for `Disc`, 8 / pi = 2.55 words a point on average
+        let costs = [
+            {
+                use extends_rng::test_rngs::{CountingRng, SplitMix, TestRng};
+                use on_extends_rng::Distribution;
+                let mut c = CountingRng::new(TestRng(SplitMix(1)));
+                for _ in 0..N {
+                    b.sample(&mut c);
+                    e.sample(&mut c);
+                    Disc.sample(&mut c);
+                }
+                (c.consumed.u32s, c.consumed.u64s, c.consumed.fills, c.consumed.bytes)
+            },
+            {
+                use extends_cryptorng::test_rngs::{CountingRng, SplitMix, TestRng};
+                use on_extends_cryptorng::Distribution;
+                let mut c = CountingRng::new(TestRng(SplitMix(1)));
+                for _ in 0..N {
+                    b.sample(&mut c);
+                    e.sample(&mut c);
+                    Disc.sample(&mut c);
+                }
+                (c.consumed.u32s, c.consumed.u64s, c.consumed.fills, c.consumed.bytes)
+            },
+            {
+                use on_raw_infallible::Distribution;
+                use raw_infallible::test_rngs::{CountingRng, SplitMix, TestRng};
+                let mut c = CountingRng::new(TestRng(SplitMix(1)));
+                for _ in 0..N {
+                    b.sample(&mut c);
+                    e.sample(&mut c);
+                    Disc.sample(&mut c);
+                }
+                (c.consumed.u32s, c.consumed.u64s, c.consumed.fills, c.consumed.bytes)
+            },
+        ];
+        assert!(costs[0] == costs[1] && costs[0] == costs[2]);
+        let (u32s, u64s, fills, bytes) = costs[0];
+        assert!(u32s == 0 && fills == 0 && bytes == 8 * u64s);
+        let disc = u64s - 2 * N;
+        assert!(disc > 25_000 && disc < 26_000 && disc.is_multiple_of(2));
+        println!("CountingRng: a u64 a Bernoulli or Exp, {} a Disc point", disc as f64 / N as f64);
+    }
+    
+    {
+        // `sample_iter`: the samples of `sample`, the distribution and the
+        // generator lent, then moved in
+        let iters: [Vec<bool>; 3] = [
+            {
+                use extends_rng::test_rngs::{SplitMix, TestRng};
+                use on_extends_rng::Distribution;
+                let mut t = TestRng(SplitMix(1));
+                let mut v: Vec<bool> = (&b).sample_iter(&mut t).take(N / 2).collect();
+                v.extend(b.sample_iter(t).take(N - N / 2));
+                v
+            },
+            {
+                use extends_cryptorng::test_rngs::{SplitMix, TestRng};
+                use on_extends_cryptorng::Distribution;
+                let mut t = TestRng(SplitMix(1));
+                let mut v: Vec<bool> = (&b).sample_iter(&mut t).take(N / 2).collect();
+                v.extend(b.sample_iter(t).take(N - N / 2));
+                v
+            },
+            {
+                use on_raw_infallible::Distribution;
+                use raw_infallible::test_rngs::{SplitMix, TestRng};
+                let mut t = TestRng(SplitMix(1));
+                let mut v: Vec<bool> = (&b).sample_iter(&mut t).take(N / 2).collect();
+                v.extend(b.sample_iter(t).take(N - N / 2));
+                v
+            },
+        ];
+        assert!(iters.iter().all(|v| *v == samples[0].0));
+        println!("sample_iter: the same samples, from &mut rng then rng");
+    }
+    {
+        // A consumer returning its iterator: lent a generator, it ends
+        // where the caller's goes on; given one, it can be kept
+        use raw_infallible::test_rngs::{SplitMix, TestRng};
+        use raw_infallible::Rng;
+        let mut t = TestRng(SplitMix(1));
+        let lent: Vec<f64> = arrivals(&mut t, 2.0).take(N).collect();
+        let mut fresh = TestRng(SplitMix(1));
+        assert_eq!(t.next_u64(), (0..=N).map(|_| fresh.next_u64()).last().unwrap());
+        let mut owned = arrivals(TestRng(SplitMix(1)), 2.0);
+        assert!(owned.by_ref().take(N).eq(lent.iter().cloned()));
+        assert!(lent.windows(2).all(|w| w[0] < w[1]));
+        let rate = N as f64 / lent[N - 1];
+        assert!((rate - 2.0).abs() < 0.1);
+        let queue: Box<dyn Iterator<Item = f64>> = Box::new(owned);
+        assert!(queue.take(1).all(|x| x > lent[N - 1]));
+        println!("arrivals, borrowing and owning: rate {:.3}", rate);
+    }    
+    // The failing cases: words 0, 1, 2, ... then an error. `Disc` rejects
+    // the points of the first words (near (-1, -1)), so runs into it
+    {
+        use extends_rng::test_rngs::FailAfter;
+        use extends_rng::ErrorKind;
+        use on_extends_rng::{try_sample, Distribution};
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        let mut f = FallibleCRng(FailAfter::new(2));
+        assert!(try_sample(&b, &mut f).unwrap());
+        assert_eq!(try_sample(&Disc, &mut f).unwrap_err().kind, ErrorKind::Unavailable);
+        let mut f = FallibleCRng(FailAfter::new(8));
+        assert_eq!(try_sample(&Disc, &mut f).unwrap_err().kind, ErrorKind::Unavailable);
+        let mut f = FallibleCRng(FailAfter::new(8));
+        assert!(catch_unwind(AssertUnwindSafe(|| Disc.sample(&mut f))).is_err());
+        println!("extends_Rng: try_sample through Sticky, Unavailable; sample panics");
+    }
+    {
+        use extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use extends_cryptorng::ErrorKind;
+        use on_extends_cryptorng::Distribution;
+        let mut f = TestCRng(FailAfter::new(2));
+        assert!(b.try_sample(&mut f).unwrap());
+        assert_eq!(Disc.try_sample(&mut f).unwrap_err().kind, ErrorKind::Unavailable);
+        let mut f = TestCRng(FailAfter::new(8));
+        assert_eq!(Disc.try_sample(&mut f).unwrap_err().kind, ErrorKind::Unavailable);
+        assert_eq!(f.0.left, 0);
+        println!("extends_CryptoRng: try_sample, Unavailable at the failing word");
+    }
+    {
+        use on_raw_infallible::Distribution;
+        use raw_infallible::test_rngs::{FailAfter, TestFCRng, TestRng};
+        use raw_infallible::{Error, ErrorKind};
+        let mut f = TestFCRng(FailAfter::new(2));
+        assert!(b.try_sample(&mut f).unwrap());
+        assert_eq!(Disc.try_sample(&mut f).unwrap_err().kind, ErrorKind::Unavailable);
+        let mut f = TestFCRng(FailAfter::new(8));
+        assert_eq!(Disc.try_sample(&mut f).unwrap_err().kind, ErrorKind::Unavailable);
+        // A plain generator's `try_sample` can't fail, at `Infallible`
+        let mut t = TestRng(FailAfter::new(2));
+        let x: Result<bool, Infallible> = b.try_sample(&mut t);
+        assert!(x.is_ok() && b.try_sample::<Error, _>(&mut TestRng(FailAfter::new(2))).is_ok());
+        println!("raw_infallible: try_sample, Unavailable; Result<_, Infallible> if plain");
+    }
+    {
+        // The iterators of `Result`s, lent the failing generators: a
+        // rejection loop's error ends the collect
+        use extends_rng::test_rngs::FailAfter;
+        use on_extends_rng::try_sample_iter;
+        let mut f = FallibleCRng(FailAfter::new(6));
+        let bits: Vec<_> = try_sample_iter(b, &mut f).take(4).collect();
+        assert!(bits[..3].iter().all(|x| x.is_ok()) && bits[3].is_err());
+        let mut f = FallibleCRng(FailAfter::new(8));
+        assert!(try_sample_iter(Disc, &mut f).take(2).collect::<Result<Vec<_>, _>>().is_err());
+        println!("extends_Rng: try_sample_iter through Sticky, Err at the failing word");
+    }
+    {
+        use extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use on_extends_cryptorng::Distribution;
+        let mut f = TestCRng(FailAfter::new(6));
+        let bits: Vec<_> = (&b).try_sample_iter(&mut f).take(4).collect();
+        assert!(bits[..3].iter().all(|x| x.is_ok()) && bits[3].is_err());
+        let mut f = TestCRng(FailAfter::new(8));
+        assert!(Disc.try_sample_iter(&mut f).take(2).collect::<Result<Vec<_>, _>>().is_err());
+        println!("extends_CryptoRng: try_sample_iter, Err at the failing word");
+    }
+    {
+        // raw_infallible: the fallible generator moved in, and taken back
+        use on_raw_infallible::Distribution;
+        use raw_infallible::test_rngs::{FailAfter, TestFCRng};
+        let mut bits = b.try_sample_iter(TestFCRng(FailAfter::new(6)));
+        assert!(bits.by_ref().take(3).all(|x| x.is_ok()));
+        let mut f = bits.into_inner();
+        assert!(b.try_sample(&mut f).is_err());
+        let points = Disc.try_sample_iter(TestFCRng(FailAfter::new(8)));
+        assert!(points.take(2).collect::<Result<Vec<_>, _>>().is_err());
+        println!("raw_infallible: try_sample_iter owning, Err at the failing word");
+    }
+    {
+        // Through trait objects: `R: ?Sized` throughout
+        use on_raw_infallible::Distribution;
+        use raw_infallible::test_rngs::{SplitMix, TestRng};
+        use raw_infallible::Rng;
+        let mut t = TestRng(SplitMix(1));
+        let r: &mut dyn Rng = &mut t;
+        assert_eq!(b.sample(r), samples[0].0[0]);
+        println!("&mut dyn Rng: sample = {}", samples[0].0[0]);
+    }
+}
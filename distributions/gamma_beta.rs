@@ -0,0 +1,300 @@
+//! `Gamma` and `Beta`: distributions built from other distributions
+//! 
+//! Checks that `Distribution` (from `combinators.rs`) composes when one
+//! distribution is implemented by sampling others, several layers deep:
+//! 
+//! *   `Normal`: Marsaglia's polar method, from pairs of `Unit` samples
+//! *   `Gamma`: Marsaglia and Tsang's method, one `Normal` and one `Open01`
+//!     sample per try (about 1.02-1.05 tries for shapes of 1 and above, from
+//!     the squeeze alone for most); shapes below 1 sample `Gamma(shape + 1)`
+//!     and multiply by `U^(1/shape)`
+//! *   `Beta`: `x / (x + y)` for `x` from `Gamma(a)` and `y` from `Gamma(b)`
+//! 
+//! Each holds its parts as fields and calls their `sample` with the same
+//! generator, which needs nothing beyond `Distribution` itself: no
+//! `&mut dyn Rng` conversion, no boxing. The same `Beta` also falls out of
+//! the combinators, `Gamma(a).zip(Gamma(b)).map(..)`, with the same draws.
+//! Constructors return `Err(ParamError)` for parameters which are not
+//! finite and positive.
+//! 
+//! `main` compares the mean and variance of 200 000 samples with the exact
+//! moments, for shapes from 0.1 to 100, allowing five standard errors (the
+//! variance's from the fourth moment), and checks the combinator `Beta`
+//! and a boxed `Beta` against the struct.
+//! 
+//! Run with:
+//! 
+//!     rustc -O gamma_beta.rs && ./gamma_beta
+//! 
+//! Thoughts: `sample(&self)` leaves nowhere to keep the polar method's second
+//! value, so `Normal` throws it away, drawing twice the words it needs; a
+//! Ziggurat (one word, usually) avoids the question, and is what rand uses.
+//! Otherwise layering was free: `Gamma` never names the generator type, and
+//! `Beta` is three lines. Composition does not compose accuracy, though:
+//! for small shapes both gammas can underflow to 0, and `Beta` returns NaN:
+//! none in 10^6 samples of `Beta(0.01, 0.01)`, but one in 1800 at 0.005 and
+//! one in 4 at 0.001. rand switched to Cheng's algorithms for `Beta` for
+//! that reason.
+
+#[path = "combinators.rs"]
+#[allow(dead_code)]
+mod combinators;
+
+pub use self::combinators::{Distribution, DynDistribution, Rng, Unit};
+use self::combinators::SplitMix;
+
+// ——— errors ———
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamError {
+    /// The standard deviation is not finite and positive (or the mean not
+    /// finite).
+    StdDevInvalid,
+    /// A shape is not finite and positive.
+    ShapeInvalid,
+    /// The scale is not finite and positive.
+    ScaleInvalid,
+}
+
+fn finite_positive(x: f64) -> bool {
+    x.is_finite() && x > 0.0
+}
+
+// ——— distributions ———
+
+/// `(0, 1)`: 52 random bits, plus a half so that 0 never occurs.
+#[derive(Debug, Clone, Copy)]
+pub struct Open01;
+
+impl Distribution<f64> for Open01 {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> f64 {
+        ((rng.next_u64() >> 12) as f64 + 0.5) / (1u64 << 52) as f64
+    }
+}
+
+/// The normal distribution with mean 0 and standard deviation 1.
+#[derive(Debug, Clone, Copy)]
+pub struct StandardNormal;
+
+impl Distribution<f64> for StandardNormal {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> f64 {
+        loop {
+            let x = 2.0 * Unit.sample(rng) - 1.0;
+            let y = 2.0 * Unit.sample(rng) - 1.0;
+            let s = x * x + y * y;
+            if s < 1.0 && s > 0.0 {
+                // `y * ..` would be a second, independent value
+                return x * (-2.0 * s.ln() / s).sqrt();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Normal {
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Normal {
+    pub fn new(mean: f64, std_dev: f64) -> Result<Self, ParamError> {
+        if !mean.is_finite() || !finite_positive(std_dev) {
+            return Err(ParamError::StdDevInvalid);
+        }
+        Ok(Normal { mean, std_dev })
+    }
+}
+
+impl Distribution<f64> for Normal {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> f64 {
+        self.mean + self.std_dev * StandardNormal.sample(rng)
+    }
+}
+
+/// The gamma distribution with density proportional to
+/// `x^(shape - 1) e^(-x / scale)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Gamma {
+    scale: f64,
+    /// `shape - 1/3`, with 1 added to shapes below 1.
+    d: f64,
+    /// `1 / sqrt(9 d)`.
+    c: f64,
+    /// `1 / shape` for shapes below 1, whose samples are multiplied by
+    /// `U^(1 / shape)`.
+    boost: Option<f64>,
+    normal: StandardNormal,
+    uniform: Open01,
+}
+
+impl Gamma {
+    pub fn new(shape: f64, scale: f64) -> Result<Self, ParamError> {
+        if !finite_positive(shape) {
+            return Err(ParamError::ShapeInvalid);
+        }
+        if !finite_positive(scale) {
+            return Err(ParamError::ScaleInvalid);
+        }
+        let (base, boost) = if shape < 1.0 { (shape + 1.0, Some(1.0 / shape)) } else { (shape, None) };
+        let d = base - 1.0 / 3.0;
+        Ok(Gamma { scale, d, c: 1.0 / (9.0 * d).sqrt(), boost, normal: StandardNormal, uniform: Open01 })
+    }
+}
+
+impl Distribution<f64> for Gamma {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> f64 {
+        let x = loop {
+            let z = self.normal.sample(rng);
+            let v = 1.0 + self.c * z;
+            if v <= 0.0 {
+                continue;
+            }
+            let v = v * v * v;
+            let u = self.uniform.sample(rng);
+            let z2 = z * z;
+            // The squeeze accepts most tries without a logarithm
+            if u < 1.0 - 0.0331 * z2 * z2
+                || u.ln() < 0.5 * z2 + self.d * (1.0 - v + v.ln())
+            {
+                break self.d * v;
+            }
+        };
+        match self.boost {
+            Some(inv_shape) => x * self.uniform.sample(rng).powf(inv_shape) * self.scale,
+            None => x * self.scale,
+        }
+    }
+}
+
+/// The beta distribution on `[0, 1]`, with density proportional to
+/// `x^(a - 1) (1 - x)^(b - 1)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Beta {
+    a: Gamma,
+    b: Gamma,
+}
+
+impl Beta {
+    pub fn new(a: f64, b: f64) -> Result<Self, ParamError> {
+        Ok(Beta { a: Gamma::new(a, 1.0)?, b: Gamma::new(b, 1.0)? })
+    }
+}
+
+impl Distribution<f64> for Beta {
+    fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> f64 {
+        let x = self.a.sample(rng);
+        x / (x + self.b.sample(rng))
+    }
+}
+
+// ——— test RNGs ———
+
+/// Counts the calls to a `SplitMix`.
+#[derive(Clone)]
+struct Counting(SplitMix, usize);
+
+impl Rng for Counting {
+    fn next_u32(&mut self) -> u32 {
+        self.1 += 1;
+        self.0.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.1 += 1;
+        self.0.next_u64()
+    }
+}
+
+// ——— usage ———
+
+const SAMPLES: usize = 200_000;
+
+/// Exact mean, variance and excess kurtosis of a distribution.
+struct Moments {
+    mean: f64,
+    var: f64,
+    kurtosis: f64,
+}
+
+fn gamma_moments(shape: f64, scale: f64) -> Moments {
+    Moments { mean: shape * scale, var: shape * scale * scale, kurtosis: 6.0 / shape }
+}
+
+fn beta_moments(a: f64, b: f64) -> Moments {
+    let s = a + b;
+    Moments {
+        mean: a / s,
+        var: a * b / (s * s * (s + 1.0)),
+        kurtosis: 6.0 * ((a - b).powi(2) * (s + 1.0) - a * b * (s + 2.0))
+            / (a * b * (s + 2.0) * (s + 3.0)),
+    }
+}
+
+/// Checks the sample mean and variance of `SAMPLES` values of `d` against
+/// `m`, within five standard errors; returns the words drawn per sample.
+fn check_moments<D: Distribution<f64>>(name: &str, d: &D, m: Moments, rng: &mut Counting) -> f64 {
+    let start = rng.1;
+    let xs: Vec<f64> = (0..SAMPLES).map(|_| d.sample(rng)).collect();
+    assert!(xs.iter().all(|x| x.is_finite()), "{}: not finite", name);
+    let n = SAMPLES as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    let var = xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    // The sample variance's variance is (mu_4 - var^2) / n
+    let (mean_se, var_se) = ((m.var / n).sqrt(), (m.var * m.var * (m.kurtosis + 2.0) / n).sqrt());
+    assert!((mean - m.mean).abs() < 5.0 * mean_se, "{}: mean {} not {}", name, mean, m.mean);
+    assert!((var - m.var).abs() < 5.0 * var_se, "{}: variance {} not {}", name, var, m.var);
+    (rng.1 - start) as f64 / n
+}
+
+fn main() {
+    let mut rng = Counting(SplitMix(0x5EED), 0);
+    {
+        let d = Normal::new(3.0, 2.0).unwrap();
+        let words = check_moments("Normal(3, 2)", &d, Moments { mean: 3.0, var: 4.0, kurtosis: 0.0 }, &mut rng);
+        println!("Normal(3, 2): mean and variance ok, {:.2} words per sample", words);
+    }
+    {
+        for &(shape, scale) in &[(0.1, 1.0), (0.5, 2.0), (1.0, 1.0), (2.5, 0.5), (10.0, 3.0), (100.0, 0.01)] {
+            let name = format!("Gamma({}, {})", shape, scale);
+            let d = Gamma::new(shape, scale).unwrap();
+            let words = check_moments(&name, &d, gamma_moments(shape, scale), &mut rng);
+            println!("{}: mean and variance ok, {:.2} words per sample", name, words);
+        }
+    }
+    {
+        for &(a, b) in &[(0.1, 0.1), (0.5, 0.5), (1.0, 1.0), (2.0, 5.0), (0.2, 3.0), (50.0, 50.0)] {
+            let name = format!("Beta({}, {})", a, b);
+            let d = Beta::new(a, b).unwrap();
+            let words = check_moments(&name, &d, beta_moments(a, b), &mut rng);
+            println!("{}: mean and variance ok, {:.2} words per sample", name, words);
+        }
+    }
+    {
+        // The same Beta from combinators, draw for draw; and through a box
+        let (mut a, mut b) = (rng.clone(), rng.clone());
+        let beta = Beta::new(2.0, 0.5).unwrap();
+        let zipped = Gamma::new(2.0, 1.0).unwrap().zip(Gamma::new(0.5, 1.0).unwrap())
+            .map(|(x, y)| x / (x + y));
+        let boxed: Box<dyn DynDistribution<f64>> = Box::new(beta);
+        for _ in 0..1000 {
+            let x = beta.sample(&mut a);
+            assert_eq!(x, zipped.sample(&mut b));
+            assert!((0.0..=1.0).contains(&x));
+        }
+        assert_eq!(a.1, b.1);
+        for _ in 0..1000 {
+            assert_eq!(boxed.sample(&mut a), beta.sample(&mut b));
+        }
+        assert_eq!(a.1, b.1);
+        println!("Beta == Gamma.zip(Gamma).map(..) == boxed Beta, same draws");
+    }
+    {
+        assert_eq!(Normal::new(0.0, 0.0).unwrap_err(), ParamError::StdDevInvalid);
+        assert_eq!(Normal::new(f64::NAN, 1.0).unwrap_err(), ParamError::StdDevInvalid);
+        assert_eq!(Gamma::new(0.0, 1.0).unwrap_err(), ParamError::ShapeInvalid);
+        assert_eq!(Gamma::new(f64::INFINITY, 1.0).unwrap_err(), ParamError::ShapeInvalid);
+        assert_eq!(Gamma::new(1.0, -1.0).unwrap_err(), ParamError::ScaleInvalid);
+        assert_eq!(Beta::new(1.0, f64::NAN).unwrap_err(), ParamError::ShapeInvalid);
+        println!("invalid parameters: errors");
+    }
+}
@@ -0,0 +1,490 @@
+//! `gen_iter`: iterators of values, borrowing or owning the generator
+//! 
+//! Old rand's `rng.gen_iter::<T>()` borrowed the generator; rand 0.7's
+//! `sample_iter` takes it by value, borrowing only through `&mut R: Rng`.
+//! Both forms, over `standard.rs` (included whole, its three designs and its
+//! `Standard` with them): `rng.gen_iter::<T>()` and `rng.iter_u32()` hold
+//! `&mut R`, any `R: ?Sized`; `rng.into_gen_iter::<T>()` takes `R`, and
+//! `into_inner` gives it back. For fallible generators,
+//! `rng.try_gen_iter::<T>()` yields `try_gen`'s `Result`s: a second iterator
+//! over `extends_Rng`, the one iterator over `extends_CryptoRng` and
+//! `raw_infallible`, whose `gen_iter` unwraps or instantiates it.
+//! 
+//! Run with:
+//! 
+//!     rustc -O gen_iter.rs && ./gen_iter
+//! 
+//! Compile-fail check (must fail to compile):
+//! 
+//!     rustc --cfg fail_borrowed_iter gen_iter.rs
+//! 
+//! Thoughts: where the design forwards `&mut R`, the owning iterator is the
+//! one to have: it borrows when given a borrow, and only it can outlive the
+//! generator's scope (`fail_borrowed_iter`). `raw_infallible` can't forward
+//! its fallible trait, so `try_gen_iter` borrows everywhere. Items of
+//! `Result`, not fused, need nothing of the traits: `collect` stops at the
+//! first error, and `filter_map(Result::ok)` gets past transient faults.
+
+use std::convert::Infallible;
+
+#[path = "standard.rs"]
+#[allow(dead_code)]
+mod standard;
+
+use standard::raw_infallible::test_rngs as raw;
+use standard::Standard;
+
+// ——— over extends_Rng ———
+
+mod on_extends_rng {
+    use super::standard::extends_rng::{CryptoError, CryptoRng, Rng};
+    use super::standard::on_extends_rng::{Distribution, TryDistribution};
+    use super::Standard;
+    use std::marker::PhantomData;
+    
+    /// `gen`'s values, from a borrowed generator.
+    pub struct Iter<'a, R: ?Sized+'a, T> {
+        rng: &'a mut R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<'a, R: Rng+?Sized, T> Iterator for Iter<'a, R, T> where Standard: Distribution<T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            Some(Standard.sample(self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// `gen`'s values, from an owned generator.
+    pub struct IntoIter<R, T> {
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<R, T> IntoIter<R, T> {
+        /// The generator, where the values stopped.
+        pub fn into_inner(self) -> R {
+            self.rng
+        }
+    }
+    
+    impl<R: Rng, T> Iterator for IntoIter<R, T> where Standard: Distribution<T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            Some(Standard.sample(&mut self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// `try_gen`'s results, from a borrowed generator.
+    pub struct TryIter<'a, R: ?Sized+'a, T> {
+        rng: &'a mut R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<'a, R: CryptoRng+?Sized, T> Iterator for TryIter<'a, R, T>
+        where Standard: TryDistribution<T>
+    {
+        type Item = Result<T, CryptoError>;
+        
+        fn next(&mut self) -> Option<Result<T, CryptoError>> {
+            Some(Standard.try_sample(self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    pub trait IterExt: Rng {
+        fn gen_iter<T>(&mut self) -> Iter<'_, Self, T> where Standard: Distribution<T> {
+            Iter { rng: self, _values: PhantomData }
+        }
+        
+        fn iter_u32(&mut self) -> Iter<'_, Self, u32> {
+            self.gen_iter()
+        }
+        
+        fn into_gen_iter<T>(self) -> IntoIter<Self, T>
+            where Self: Sized, Standard: Distribution<T>
+        {
+            IntoIter { rng: self, _values: PhantomData }
+        }
+    }
+    
+    impl<R: Rng+?Sized> IterExt for R {}
+    
+    pub trait TryIterExt: CryptoRng {
+        fn try_gen_iter<T>(&mut self) -> TryIter<'_, Self, T> where Standard: TryDistribution<T> {
+            TryIter { rng: self, _values: PhantomData }
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> TryIterExt for R {}
+}
+
+// ——— over extends_CryptoRng ———
+
+mod on_extends_cryptorng {
+    use super::standard::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use super::standard::on_extends_cryptorng::Distribution;
+    use super::Standard;
+    use std::marker::PhantomData;
+    
+    /// `try_gen`'s results, from a borrowed generator.
+    pub struct TryIter<'a, R: ?Sized+'a, T> {
+        rng: &'a mut R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<'a, R: CryptoRng+?Sized, T> Iterator for TryIter<'a, R, T>
+        where Standard: Distribution<T>
+    {
+        type Item = Result<T, CryptoError>;
+        
+        fn next(&mut self) -> Option<Result<T, CryptoError>> {
+            Some(Standard.try_sample(self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// `gen`'s values, from a borrowed generator: `TryIter`'s, unwrapped.
+    pub struct Iter<'a, R: ?Sized+'a, T>(TryIter<'a, R, T>);
+    
+    impl<'a, R: Rng+?Sized, T> Iterator for Iter<'a, R, T> where Standard: Distribution<T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            self.0.next().map(|x| x.unwrap())
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// `gen`'s values, from an owned generator.
+    pub struct IntoIter<R, T> {
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<R, T> IntoIter<R, T> {
+        /// The generator, where the values stopped.
+        pub fn into_inner(self) -> R {
+            self.rng
+        }
+    }
+    
+    impl<R: Rng, T> Iterator for IntoIter<R, T> where Standard: Distribution<T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            Some(Standard.try_sample(&mut self.rng).unwrap())
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    pub trait TryIterExt: CryptoRng {
+        fn try_gen_iter<T>(&mut self) -> TryIter<'_, Self, T> where Standard: Distribution<T> {
+            TryIter { rng: self, _values: PhantomData }
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> TryIterExt for R {}
+    
+    pub trait IterExt: Rng {
+        fn gen_iter<T>(&mut self) -> Iter<'_, Self, T> where Standard: Distribution<T> {
+            Iter(self.try_gen_iter())
+        }
+        
+        fn iter_u32(&mut self) -> Iter<'_, Self, u32> {
+            self.gen_iter()
+        }
+        
+        fn into_gen_iter<T>(self) -> IntoIter<Self, T>
+            where Self: Sized, Standard: Distribution<T>
+        {
+            IntoIter { rng: self, _values: PhantomData }
+        }
+    }
+    
+    impl<R: Rng+?Sized> IterExt for R {}
+}
+
+// ——— over raw_infallible ———
+
+mod on_raw_infallible {
+    use super::standard::on_raw_infallible::Distribution;
+    use super::standard::raw_infallible::{RawRng, Rng};
+    use super::{Infallible, Standard};
+    use std::marker::PhantomData;
+    
+    /// `try_gen`'s results at `E`, from a borrowed generator.
+    pub struct TryIter<'a, R: ?Sized+'a, T, E> {
+        rng: &'a mut R,
+        _values: PhantomData<fn() -> Result<T, E>>,
+    }
+    
+    impl<'a, R: RawRng<E>+?Sized, T, E> Iterator for TryIter<'a, R, T, E>
+        where Standard: Distribution<T>
+    {
+        type Item = Result<T, E>;
+        
+        fn next(&mut self) -> Option<Result<T, E>> {
+            Some(Standard.try_sample(self.rng))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// `gen`'s values, from a borrowed generator: `TryIter`'s at
+    /// `Infallible`.
+    pub struct Iter<'a, R: ?Sized+'a, T>(TryIter<'a, R, T, Infallible>);
+    
+    impl<'a, R: Rng+?Sized, T> Iterator for Iter<'a, R, T> where Standard: Distribution<T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            self.0.next().map(|x| x.unwrap_or_else(|e| match e {}))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    /// `gen`'s values, from an owned generator.
+    pub struct IntoIter<R, T> {
+        rng: R,
+        _values: PhantomData<fn() -> T>,
+    }
+    
+    impl<R, T> IntoIter<R, T> {
+        /// The generator, where the values stopped.
+        pub fn into_inner(self) -> R {
+            self.rng
+        }
+    }
+    
+    impl<R: Rng, T> Iterator for IntoIter<R, T> where Standard: Distribution<T> {
+        type Item = T;
+        
+        fn next(&mut self) -> Option<T> {
+            Some(Standard.try_sample::<Infallible, R>(&mut self.rng).unwrap_or_else(|e| match e {}))
+        }
+        
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (usize::MAX, None)
+        }
+    }
+    
+    pub trait TryIterExt {
+        fn try_gen_iter<T, E>(&mut self) -> TryIter<'_, Self, T, E>
+            where Self: RawRng<E>, Standard: Distribution<T>
+        {
+            TryIter { rng: self, _values: PhantomData }
+        }
+    }
+    
+    impl<R: ?Sized> TryIterExt for R {}
+    
+    pub trait IterExt: Rng {
+        fn gen_iter<T>(&mut self) -> Iter<'_, Self, T> where Standard: Distribution<T> {
+            Iter(self.try_gen_iter())
+        }
+        
+        fn iter_u32(&mut self) -> Iter<'_, Self, u32> {
+            self.gen_iter()
+        }
+        
+        fn into_gen_iter<T>(self) -> IntoIter<Self, T>
+            where Self: Sized, Standard: Distribution<T>
+        {
+            IntoIter { rng: self, _values: PhantomData }
+        }
+    }
+    
+    impl<R: Rng+?Sized> IterExt for R {}
+}
+
+// ——— consumers ———
+
+/// An endless stream of noise, in `[0, 1)`, from its own generator.
+fn noise(seed: u64) -> on_raw_infallible::IntoIter<raw::TestRng<raw::SplitMix>, f64> {
+    use on_raw_infallible::IterExt;
+    raw::TestRng(raw::SplitMix(seed)).into_gen_iter()
+}
+
+// The same, borrowing: the generator is dropped at the end of the function,
+// while the iterator still borrows it
+#[cfg(fail_borrowed_iter)]
+fn borrowed_noise(seed: u64) -> on_raw_infallible::Iter<'static, raw::TestRng<raw::SplitMix>, f64> {
+    use on_raw_infallible::IterExt;
+    let mut rng = raw::TestRng(raw::SplitMix(seed));
+    rng.gen_iter()
+}
+
+// ——— usage ———
+
+fn main() {
+    // Words 1, 2, 3, ...: `gen`'s values, five ways over each design
+    let values: [Vec<u32>; 3] = [
+        {
+            use on_extends_rng::IterExt;
+            use standard::extends_rng::test_rngs::{Counting, TestRng};
+            let mut t = TestRng(Counting(1));
+            let mut v: Vec<u32> = t.gen_iter().take(2).collect();
+            v.extend(t.iter_u32().take(2));
+            let mut owned = (&mut t).into_gen_iter::<u32>();
+            v.extend(owned.by_ref().take(2));
+            let mut owned = owned.into_inner().into_gen_iter::<u32>();
+            v.extend(owned.by_ref().take(2));
+            v.extend(owned.into_inner().iter_u32().take(2));
+            v
+        },
+        {
+            use on_extends_cryptorng::IterExt;
+            use standard::extends_cryptorng::test_rngs::{Counting, TestRng};
+            let mut t = TestRng(Counting(1));
+            let mut v: Vec<u32> = t.gen_iter().take(2).collect();
+            v.extend(t.iter_u32().take(2));
+            let mut owned = (&mut t).into_gen_iter::<u32>();
+            v.extend(owned.by_ref().take(2));
+            let mut owned = owned.into_inner().into_gen_iter::<u32>();
+            v.extend(owned.by_ref().take(2));
+            v.extend(owned.into_inner().iter_u32().take(2));
+            v
+        },
+        {
+            use on_raw_infallible::IterExt;
+            use standard::raw_infallible::test_rngs::{Counting, TestRng};
+            let mut t = TestRng(Counting(1));
+            let mut v: Vec<u32> = t.gen_iter().take(2).collect();
+            v.extend(t.iter_u32().take(2));
+            let mut owned = (&mut t).into_gen_iter::<u32>();
+            v.extend(owned.by_ref().take(2));
+            let mut owned = owned.into_inner().into_gen_iter::<u32>();
+            v.extend(owned.by_ref().take(2));
+            v.extend(owned.into_inner().iter_u32().take(2));
+            v
+        },
+    ];
+    assert_eq!(values[0], (1..11).collect::<Vec<u32>>());
+    assert!(values[0] == values[1] && values[0] == values[2]);
+    println!("gen_iter, iter_u32, into_gen_iter borrowing and owning: {:?}", values[0]);
+    {
+        // Values of any `Standard` type, and the generator carries on where
+        // the iterator stopped
+        use on_raw_infallible::IterExt;
+        use standard::on_raw_infallible::RngExt;
+        use standard::raw_infallible::test_rngs::{Counting, TestRng};
+        let mut t = TestRng(Counting(1));
+        let pairs: Vec<(u16, bool)> = t.gen_iter().take(2).collect();
+        assert_eq!(pairs, vec![(1, false), (3, false)]);
+        assert_eq!(t.gen::<u64>(), 6 << 32 | 5);
+        println!("gen_iter::<(u16, bool)>(): {:?}, then gen() continues", pairs);
+    }
+    {
+        // Owning outlives the scope which seeded it: returned, and moved to
+        // another thread
+        use on_raw_infallible::IterExt;
+        use standard::raw_infallible::test_rngs::{SplitMix, TestRng};
+        let want: Vec<f64> = TestRng(SplitMix(3)).gen_iter().take(4).collect();
+        assert_eq!(noise(3).take(4).collect::<Vec<f64>>(), want);
+        let stream = noise(3);
+        let got = std::thread::spawn(move || stream.take(4).collect::<Vec<f64>>()).join().unwrap();
+        assert_eq!(got, want);
+        assert!(want.iter().all(|&x| (0.0..1.0).contains(&x)));
+        println!("noise(3), returned and sent to a thread: {:?}", want);
+    }
+    {
+        // Trait objects: borrowing takes `&mut dyn Rng`, owning `Box<dyn Rng>`
+        use on_raw_infallible::IterExt;
+        use standard::raw_infallible::test_rngs::{Counting, TestRng};
+        use standard::raw_infallible::Rng;
+        let mut t = TestRng(Counting(1));
+        let r: &mut dyn Rng = &mut t;
+        assert_eq!(r.iter_u32().take(3).collect::<Vec<u32>>(), vec![1, 2, 3]);
+        let b: Box<dyn Rng> = Box::new(TestRng(Counting(1)));
+        assert_eq!(b.into_gen_iter::<u8>().take(3).collect::<Vec<u8>>(), vec![1, 2, 3]);
+        println!("&mut dyn Rng and Box<dyn Rng>: 1, 2, 3");
+    }
+    
+    // Failing generators: words 0, 1, 2, then errors. Collecting a
+    // `Result` stops at the first; three values take three results
+    {
+        use on_extends_rng::TryIterExt;
+        use standard::extends_rng::test_rngs::FailAfter;
+        use standard::extends_rng::ErrorKind;
+        use standard::FallibleCRng;
+        let mut f = FallibleCRng(FailAfter::new(3));
+        let ok: Result<Vec<u32>, _> = f.try_gen_iter().take(3).collect();
+        assert_eq!(ok.unwrap(), vec![0, 1, 2]);
+        let mut f = FallibleCRng(FailAfter::new(3));
+        let err: Result<Vec<u32>, _> = f.try_gen_iter().take(4).collect();
+        assert_eq!(err.unwrap_err().kind, ErrorKind::Unavailable);
+        println!("extends_Rng: try_gen_iter, a second iterator; Unavailable at the 4th");
+    }
+    {
+        use on_extends_cryptorng::TryIterExt;
+        use standard::extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use standard::extends_cryptorng::ErrorKind;
+        let mut f = TestCRng(FailAfter::new(3));
+        let ok: Result<Vec<u32>, _> = f.try_gen_iter().take(3).collect();
+        assert_eq!(ok.unwrap(), vec![0, 1, 2]);
+        let mut f = TestCRng(FailAfter::new(3));
+        let err: Result<Vec<u32>, _> = f.try_gen_iter().take(4).collect();
+        assert_eq!(err.unwrap_err().kind, ErrorKind::Unavailable);
+        println!("extends_CryptoRng: try_gen_iter; Unavailable at the 4th");
+    }
+    {
+        use on_raw_infallible::TryIterExt;
+        use standard::raw_infallible::test_rngs::{Counting, FailAfter, TestFCRng, TestRng};
+        use standard::raw_infallible::{Error, ErrorKind};
+        let mut f = TestFCRng(FailAfter::new(3));
+        let ok: Result<Vec<u32>, _> = f.try_gen_iter().take(3).collect();
+        assert_eq!(ok.unwrap(), vec![0, 1, 2]);
+        let mut f = TestFCRng(FailAfter::new(3));
+        let err: Result<Vec<u32>, Error> = f.try_gen_iter().take(4).collect();
+        assert_eq!(err.unwrap_err().kind, ErrorKind::Unavailable);
+        // A plain generator's can't fail, at `Infallible`; `E` must be named
+        let mut t = TestRng(Counting(1));
+        let v: Result<Vec<u8>, Infallible> = t.try_gen_iter().take(3).collect();
+        assert_eq!(v.unwrap_or_else(|e| match e {}), vec![1, 2, 3]);
+        println!("raw_infallible: try_gen_iter::<T, E>; Unavailable at the 4th");
+    }
+    {
+        // Not fused: past random faults, the words come in order; `take`
+        // counts the errors
+        use on_extends_cryptorng::{IterExt, TryIterExt};
+        use standard::extends_cryptorng::test_rngs::{FaultyRng, SplitMix, TestCRng, TestRng};
+        let want: Vec<u32> = TestRng(SplitMix(1)).gen_iter().take(100).collect();
+        let mut f = TestCRng(FaultyRng::with_probability(SplitMix(1), 0.25, 7));
+        let got: Vec<u32> = f.try_gen_iter().filter_map(Result::ok).take(100).collect();
+        assert_eq!(got, want);
+        let faults = f.0.calls - 100;
+        assert!(faults > 0);
+        let mut f = TestCRng(FaultyRng::with_probability(SplitMix(1), 0.25, 7));
+        let values = f.try_gen_iter::<u32>().take(100).filter(|x| x.is_ok()).count();
+        assert!(values < 100);
+        println!("FaultyRng: 100 words past {} faults; take(100) gave {} values", faults, values);
+    }
+}
@@ -0,0 +1,472 @@
+//! `gen_range`: one integer from a range, over three trait designs
+//! 
+//! `rng.gen_range(low, high)` is the commonest call users make. It is
+//! `uniform_sampler.rs`'s `sample_single`: multiply a word by the range,
+//! keep the high half, and reject words whose low half is below `2^W mod
+//! range`, the zone computed only when the low half is below the range
+//! itself. Here it is written once, against what it needs of a generator,
+//! and that is given by each of three designs of `traits/`, included whole:
+//! 
+//! *   `Words`: a `u32` and a `u64`, either of which may fail, with the error
+//!     as an associated type. Types up to 32 bits draw `u32`s, 64-bit types
+//!     `u64`s; nothing else (`fill`, `next_u128`) is called
+//! *   `extends_Rng`: `gen_range` over `Rng` through an `Infallible` adaptor,
+//!     `try_gen_range` over `CryptoRng` through a second, fallible one
+//! *   `extends_CryptoRng`: one adaptor, over `CryptoRng`; `gen_range` on
+//!     `Rng` unwraps `try_gen_range`
+//! *   `raw_infallible`: one adaptor, over `RawRng<E>` for any `E`, which
+//!     must carry `E` as a type parameter; `gen_range` instantiates it at
+//!     `Infallible`
+//! 
+//! Run with:
+//! 
+//!     rustc -O gen_range.rs && ./gen_range
+//! 
+//! Compile-fail check (must fail to compile):
+//! 
+//!     rustc --cfg fail_unconstrained_error gen_range.rs
+//! 
+//! Thoughts: range sampling asks only two word widths, which may fail, as
+//! rejection draws an unbounded number of words: exactly `RawRng<E>`'s
+//! `try_next_u32` and `try_next_u64`. Over an infallible base the algorithm
+//! is shared only through an adaptor pair, and a fallible crypto
+//! generator's `gen_range` panics mid-loop. `RawRng`'s error is a parameter,
+//! so `impl<E, R: RawRng<E>> Words for R` leaves `E` unconstrained
+//! (`fail_unconstrained_error`) and the adaptor holds a `PhantomData<E>`.
+
+use std::convert::Infallible;
+
+#[path = "../traits/extends_Rng.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+pub mod extends_rng;
+
+#[path = "../traits/extends_CryptoRng.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+pub mod extends_cryptorng;
+
+#[path = "../traits/raw_infallible.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+pub mod raw_infallible;
+
+// ——— what gen_range needs ———
+
+/// A generator as `gen_range` sees it: words of two widths, either of which
+/// may fail.
+pub trait Words {
+    type Error;
+    
+    fn word_u32(&mut self) -> Result<u32, Self::Error>;
+    
+    fn word_u64(&mut self) -> Result<u64, Self::Error>;
+}
+
+/// Integers `gen_range` can sample.
+pub trait SampleRange: Sized {
+    /// Uniform over `[low, high)`. Panics unless `low < high`.
+    fn sample_range<W: Words+?Sized>(low: Self, high: Self, words: &mut W) -> Result<Self, W::Error>;
+}
+
+/// High and low halves of the double-width product.
+fn wmul_u32(a: u32, b: u32) -> (u32, u32) {
+    let m = a as u64 * b as u64;
+    ((m >> 32) as u32, m as u32)
+}
+
+fn wmul_u64(a: u64, b: u64) -> (u64, u64) {
+    let m = a as u128 * b as u128;
+    ((m >> 64) as u64, m as u64)
+}
+
+macro_rules! sample_range {
+    ($($ty:ident, $uty:ident, $word:ident, $next:ident, $wmul:ident;)*) => {$(
+        impl SampleRange for $ty {
+            fn sample_range<W: Words+?Sized>(low: $ty, high: $ty, words: &mut W)
+                -> Result<$ty, W::Error>
+            {
+                assert!(low < high, "gen_range: empty range [{}, {})", low, high);
+                // Never 0: a half-open range is never the whole type
+                let range = high.wrapping_sub(low) as $uty as $word;
+                let mut threshold = None;
+                loop {
+                    let (hi, lo) = $wmul(words.$next()?, range);
+                    // `lo >= range` is never in the zone, which is below `range`
+                    if lo >= range
+                        || lo >= *threshold.get_or_insert_with(|| range.wrapping_neg() % range)
+                    {
+                        return Ok(low.wrapping_add(hi as $uty as $ty));
+                    }
+                }
+            }
+        }
+    )*};
+}
+
+sample_range! {
+    i8, u8, u32, word_u32, wmul_u32;
+    i16, u16, u32, word_u32, wmul_u32;
+    i32, u32, u32, word_u32, wmul_u32;
+    i64, u64, u64, word_u64, wmul_u64;
+    u8, u8, u32, word_u32, wmul_u32;
+    u16, u16, u32, word_u32, wmul_u32;
+    u32, u32, u32, word_u32, wmul_u32;
+    u64, u64, u64, word_u64, wmul_u64;
+}
+
+// ——— over extends_Rng ———
+
+pub mod on_extends_rng {
+    use super::extends_rng::{CryptoError, CryptoRng, Rng};
+    use super::{Infallible, SampleRange, Words};
+    
+    pub struct Infallibly<'a, R: ?Sized+'a>(pub &'a mut R);
+    
+    impl<'a, R: Rng+?Sized> Words for Infallibly<'a, R> {
+        type Error = Infallible;
+        
+        fn word_u32(&mut self) -> Result<u32, Infallible> {
+            Ok(self.0.next_u32())
+        }
+        
+        fn word_u64(&mut self) -> Result<u64, Infallible> {
+            Ok(self.0.next_u64())
+        }
+    }
+    
+    pub struct Fallibly<'a, R: ?Sized+'a>(pub &'a mut R);
+    
+    impl<'a, R: CryptoRng+?Sized> Words for Fallibly<'a, R> {
+        type Error = CryptoError;
+        
+        fn word_u32(&mut self) -> Result<u32, CryptoError> {
+            self.0.try_next_u32()
+        }
+        
+        fn word_u64(&mut self) -> Result<u64, CryptoError> {
+            self.0.try_next_u64()
+        }
+    }
+    
+    pub trait RngExt: Rng {
+        /// One value from `[low, high)`. Panics unless `low < high`.
+        fn gen_range<T: SampleRange>(&mut self, low: T, high: T) -> T {
+            T::sample_range(low, high, &mut Infallibly(self)).unwrap_or_else(|e| match e {})
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+    
+    pub trait CryptoRngExt: CryptoRng {
+        fn try_gen_range<T: SampleRange>(&mut self, low: T, high: T) -> Result<T, CryptoError> {
+            T::sample_range(low, high, &mut Fallibly(self))
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> CryptoRngExt for R {}
+}
+
+// ——— over extends_CryptoRng ———
+
+pub mod on_extends_cryptorng {
+    use super::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use super::{SampleRange, Words};
+    
+    pub struct Fallibly<'a, R: ?Sized+'a>(pub &'a mut R);
+    
+    impl<'a, R: CryptoRng+?Sized> Words for Fallibly<'a, R> {
+        type Error = CryptoError;
+        
+        fn word_u32(&mut self) -> Result<u32, CryptoError> {
+            self.0.try_next_u32()
+        }
+        
+        fn word_u64(&mut self) -> Result<u64, CryptoError> {
+            self.0.try_next_u64()
+        }
+    }
+    
+    pub trait CryptoRngExt: CryptoRng {
+        fn try_gen_range<T: SampleRange>(&mut self, low: T, high: T) -> Result<T, CryptoError> {
+            T::sample_range(low, high, &mut Fallibly(self))
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> CryptoRngExt for R {}
+    
+    pub trait RngExt: Rng {
+        /// One value from `[low, high)`. Panics unless `low < high`, or on
+        /// error.
+        fn gen_range<T: SampleRange>(&mut self, low: T, high: T) -> T {
+            self.try_gen_range(low, high).unwrap_or_else(|e| panic!("gen_range failed: {}", e))
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+}
+
+// ——— over raw_infallible ———
+
+pub mod on_raw_infallible {
+    use super::raw_infallible::{RawRng, Rng};
+    use super::{Infallible, SampleRange, Words};
+    use std::marker::PhantomData;
+    
+    /// `RawRng<E>` as `Words`: `E`, a parameter of the trait, must be one of
+    /// the adaptor's too.
+    pub struct Raw<'a, R: ?Sized+'a, E>(pub &'a mut R, pub PhantomData<E>);
+    
+    impl<'a, E, R: RawRng<E>+?Sized> Words for Raw<'a, R, E> {
+        type Error = E;
+        
+        fn word_u32(&mut self) -> Result<u32, E> {
+            self.0.try_next_u32()
+        }
+        
+        fn word_u64(&mut self) -> Result<u64, E> {
+            self.0.try_next_u64()
+        }
+    }
+    
+    // Error: `E` isn't constrained by the impl's trait or type.
+    #[cfg(fail_unconstrained_error)]
+    impl<E, R: RawRng<E>+?Sized> Words for R {
+        type Error = E;
+        
+        fn word_u32(&mut self) -> Result<u32, E> {
+            self.try_next_u32()
+        }
+        
+        fn word_u64(&mut self) -> Result<u64, E> {
+            self.try_next_u64()
+        }
+    }
+    
+    pub trait RawRngExt {
+        fn try_gen_range<T: SampleRange, E>(&mut self, low: T, high: T) -> Result<T, E>
+            where Self: RawRng<E>
+        {
+            T::sample_range(low, high, &mut Raw(self, PhantomData))
+        }
+    }
+    
+    impl<R: ?Sized> RawRngExt for R {}
+    
+    pub trait RngExt: Rng {
+        /// One value from `[low, high)`. Panics unless `low < high`.
+        fn gen_range<T: SampleRange>(&mut self, low: T, high: T) -> T {
+            self.try_gen_range::<T, Infallible>(low, high).unwrap_or_else(|e| match e {})
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+}
+
+// ——— test RNGs ———
+
+/// extends_Rng's `TestCRng` keeps `CryptoRng`'s provided `try_` methods,
+/// which can't fail; this one's fail with its source.
+pub struct FallibleCRng<S>(pub S);
+
+impl<S: extends_rng::test_rngs::Source> extends_rng::Rng for FallibleCRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: extends_rng::test_rngs::Source> extends_rng::CryptoRng for FallibleCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, extends_rng::CryptoError> {
+        self.0.try_word().map(|x| x as u32).map_err(|_| {
+            extends_rng::CryptoError::new(extends_rng::ErrorKind::Unavailable, "test source exhausted")
+        })
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, extends_rng::CryptoError> {
+        let lo = self.try_next_u32()?;
+        Ok((self.try_next_u32()? as u64) << 32 | lo as u64)
+    }
+}
+
+/// The all-ones word, counting calls to each method of extends_Rng's `Rng`.
+#[derive(Debug, Default)]
+struct Calls {
+    u32s: usize,
+    u64s: usize,
+    fills: usize,
+}
+
+impl extends_rng::Rng for Calls {
+    fn next_u32(&mut self) -> u32 {
+        self.u32s += 1;
+        u32::MAX
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.u64s += 1;
+        u64::MAX
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        unimplemented!("gen_range doesn't use next_u128")
+    }
+    
+    fn fill(&mut self, _: &mut [u8]) {
+        self.fills += 1;
+    }
+}
+
+// ——— usage ———
+
+/// The values `main` draws, a few of each type.
+type Values = Vec<(u8, i32, u64, i8, i64)>;
+
+fn main() {
+    let values: [Values; 3] = [
+        {
+            use self::extends_rng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_rng::RngExt;
+            let mut t = TestRng(SplitMix(1));
+            (0..1000).map(|_| (t.gen_range(0, 6), t.gen_range(-10, 10), t.gen_range(0, 1_000_000_000_000),
+                t.gen_range(i8::MIN, i8::MAX), t.gen_range(i64::MIN, i64::MAX))).collect()
+        },
+        {
+            use self::extends_cryptorng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_cryptorng::RngExt;
+            let mut t = TestRng(SplitMix(1));
+            (0..1000).map(|_| (t.gen_range(0, 6), t.gen_range(-10, 10), t.gen_range(0, 1_000_000_000_000),
+                t.gen_range(i8::MIN, i8::MAX), t.gen_range(i64::MIN, i64::MAX))).collect()
+        },
+        {
+            use self::on_raw_infallible::RngExt;
+            use self::raw_infallible::test_rngs::{SplitMix, TestRng};
+            let mut t = TestRng(SplitMix(1));
+            (0..1000).map(|_| (t.gen_range(0, 6), t.gen_range(-10, 10), t.gen_range(0, 1_000_000_000_000),
+                t.gen_range(i8::MIN, i8::MAX), t.gen_range(i64::MIN, i64::MAX))).collect()
+        },
+    ];
+    assert!(values[0] == values[1] && values[0] == values[2]);
+    assert!(values[0].iter().all(|&(a, b, c, d, _)| a < 6 && (-10..10).contains(&b) && c < 1_000_000_000_000 && d < i8::MAX));
+    println!("gen_range: the same over each design, first {:?}", values[0][0]);
+    {
+        // A die, evenly
+        use self::on_raw_infallible::RngExt;
+        use self::raw_infallible::test_rngs::{SplitMix, TestRng};
+        let mut t = TestRng(SplitMix(2));
+        let mut counts = [0; 6];
+        for _ in 0..60_000 {
+            counts[t.gen_range(0u8, 6) as usize] += 1;
+        }
+        assert!(counts.iter().all(|&n| n > 9_500 && n < 10_500));
+        println!("gen_range(0, 6), 60000 times: {:?}", counts);
+    }
+    {
+        // The mocks, for draws known in advance: word `k * 2^30` times 4 has
+        // `k mod 4` as its high half, and 2^32 mod 4 = 0 rejects nothing
+        use self::on_raw_infallible::RngExt;
+        use self::raw_infallible::test_rngs::{ConstRng, StepRng};
+        let mut s = StepRng::new(0, 1 << 30);
+        let draws: Vec<u8> = (0..6).map(|_| s.gen_range(0, 4)).collect();
+        assert_eq!(draws, [0, 1, 2, 3, 0, 1]);
+        let mut s = extends_rng::test_rngs::StepRng::new(0, 1 << 30);
+        let same: Vec<u8> = (0..6).map(|_| on_extends_rng::RngExt::gen_range(&mut s, 0, 4)).collect();
+        assert_eq!(same, draws);
+        assert_eq!(ConstRng::new(!0).gen_range(10u64, 20), 19);
+        println!("StepRng(0, 2^30), gen_range(0, 4): {:?}; ConstRng(!0): the top", draws);
+    }
+    {
+        // The methods used: a word of the type's width, once (the all-ones
+        // word is never rejected), and the top of the range
+        use self::on_extends_rng::RngExt;
+        let mut c = Calls::default();
+        assert_eq!((c.gen_range(10u8, 20), c.gen_range(-5i16, 5), c.gen_range(0u32, 7)), (19, 4, 6));
+        assert_eq!((c.u32s, c.u64s, c.fills), (3, 0, 0));
+        assert_eq!((c.gen_range(10u64, 20), c.gen_range(i64::MIN, 0)), (19, -1));
+        assert_eq!((c.u32s, c.u64s, c.fills), (3, 2, 0));
+        println!("methods used: next_u32 up to 32 bits, next_u64 for 64; never fill or next_u128");
+    }
+    {
+        // The cost, metered: a word a sample but for rejections, which near
+        // a half of a word's range are nearly half the words
+        fn costs<R: FnMut(u32, u64) -> (usize, usize)>(mut metered: R) -> [(usize, usize); 3] {
+            [metered(3, 3), metered((1 << 31) + 1, 3), metered(3, (1 << 63) + 1)]
+        }
+        let per_design = [
+            costs(|m32, m64| {
+                use self::extends_rng::test_rngs::{CountingRng, SplitMix, TestRng};
+                use self::on_extends_rng::RngExt;
+                let mut c = CountingRng::new(TestRng(SplitMix(3)));
+                for _ in 0..10_000 {
+                    c.gen_range(0, m32);
+                    c.gen_range(0, m64);
+                }
+                (c.consumed.u32s, c.consumed.u64s)
+            }),
+            costs(|m32, m64| {
+                use self::extends_cryptorng::test_rngs::{CountingRng, SplitMix, TestRng};
+                use self::on_extends_cryptorng::RngExt;
+                let mut c = CountingRng::new(TestRng(SplitMix(3)));
+                for _ in 0..10_000 {
+                    c.gen_range(0, m32);
+                    c.gen_range(0, m64);
+                }
+                (c.consumed.u32s, c.consumed.u64s)
+            }),
+            costs(|m32, m64| {
+                use self::on_raw_infallible::RngExt;
+                use self::raw_infallible::test_rngs::{CountingRng, SplitMix, TestRng};
+                let mut c = CountingRng::new(TestRng(SplitMix(3)));
+                for _ in 0..10_000 {
+                    c.gen_range(0, m32);
+                    c.gen_range(0, m64);
+                }
+                (c.consumed.u32s, c.consumed.u64s)
+            }),
+        ];
+        assert!(per_design[0] == per_design[1] && per_design[0] == per_design[2]);
+        let [small, half32, half64] = per_design[0];
+        assert_eq!(small, (10_000, 10_000));
+        assert!(half32.0 > 19_000 && half32.0 < 21_000 && half32.1 == 10_000);
+        assert!(half64.1 > 19_000 && half64.1 < 21_000 && half64.0 == 10_000);
+        println!("CountingRng, (u32s, u64s) for 10000 of each range: 3 and 3, {:?}; \
+            2^31 + 1 and 3, {:?}; 3 and 2^63 + 1, {:?}", small, half32, half64);
+    }
+    
+    // Failing: word 0, rejected from a range of 3 (its zone is 2^32 mod 3 =
+    // 1), then the source fails
+    {
+        use self::extends_rng::test_rngs::FailAfter;
+        use self::extends_rng::ErrorKind;
+        use self::on_extends_rng::CryptoRngExt;
+        let mut f = FallibleCRng(FailAfter::new(1));
+        assert_eq!(f.try_gen_range(0u32, 3).unwrap_err().kind, ErrorKind::Unavailable);
+        println!("extends_Rng: try_gen_range through the fallible adaptor, Unavailable");
+    }
+    {
+        use self::extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use self::extends_cryptorng::ErrorKind;
+        use self::on_extends_cryptorng::CryptoRngExt;
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_gen_range(0u32, 3).unwrap_err().kind, ErrorKind::Unavailable);
+        let mut f = TestCRng(FailAfter::new(2));
+        assert_eq!(f.try_gen_range(0u32, 3).unwrap(), 0);
+        println!("extends_CryptoRng: try_gen_range, Unavailable; with a word more, 0");
+    }
+    {
+        use self::on_raw_infallible::RawRngExt;
+        use self::raw_infallible::test_rngs::{FailAfter, TestFCRng, TestRng};
+        use self::raw_infallible::ErrorKind;
+        let mut f = TestFCRng(FailAfter::new(1));
+        assert_eq!(f.try_gen_range(0u32, 3).unwrap_err().kind, ErrorKind::Unavailable);
+        let mut t = TestRng(FailAfter::new(2));
+        assert_eq!(t.try_gen_range::<_, Infallible>(0u32, 3), Ok(0));
+        println!("raw_infallible: try_gen_range, Unavailable; Infallible if plain");
+    }
+    {
+        // Through trait objects
+        use self::on_raw_infallible::RngExt;
+        use self::raw_infallible::test_rngs::{SplitMix, TestRng};
+        use self::raw_infallible::Rng;
+        let mut t = TestRng(SplitMix(1));
+        let r: &mut dyn Rng = &mut t;
+        assert_eq!(r.gen_range(0u8, 6), values[0][0].0);
+        println!("&mut dyn Rng: gen_range(0, 6) = {}", values[0][0].0);
+    }
+}
@@ -0,0 +1,237 @@
+//! `sample_indices(n, k)`: `k` distinct indices below `n`, without O(n) memory
+//! 
+//! Returns `k` distinct indices from `[0, n)`, in random order: every ordered
+//! choice is equally likely, so the result is also a uniform random subset,
+//! and its first `j` elements are a uniform sample of `j`. Two algorithms:
+//! 
+//! *   Floyd's: one draw per index, `O(k)` memory whatever `n`. It draws `t`
+//!     from `[0, j]` for `j` in `n - k..n`, keeping `t` unless already kept,
+//!     then `j`; putting `j` where `t` was and `t` last makes the order
+//!     uniform too. Membership is a linear search for small `k` (`O(k^2)`
+//!     time but cache-friendly), a hash map from index to position otherwise
+//! *   partial shuffle: the first `k` steps of Fisher-Yates over `0..n`, so
+//!     `O(n)` memory and time, but no lookups
+//! 
+//! `sample_indices` chooses with `strategy(n, k)`: the shuffle when `n` is at
+//! most `SHUFFLE_MAX_RATIO` times `k`, else the linear search up to
+//! `FLOYD_LINEAR_MAX` indices, else the hash map; so memory is never more
+//! than a constant times `k`. The strategies give different indices from the
+//! same seed, so a seeded program's result changes where the choice does.
+//! 
+//! `main` checks that every strategy gives distinct indices in range,
+//! uniform over ordered choices (small `n`) and over indices (large `n`), and
+//! counts allocations to check that `k` of a huge `n` needs only `O(k)`.
+//! 
+//! Run with:
+//! 
+//!     rustc -O index_sample.rs && ./index_sample
+//! 
+//! and see `bench/index_sample.rs` for the regimes and thresholds.
+//! 
+//! Thoughts: returning `Vec<usize>` costs 8 bytes per index where `u32`
+//! would usually do; rand's `IndexVec` picks the width from `n`, which is
+//! worth copying. A sorted result would be free from a different Floyd
+//! variant, but then the first `j` indices would not be a sample, and callers
+//! wanting order can sort.
+
+#[path = "uniform_int.rs"]
+#[allow(dead_code)]
+mod uniform_int;
+
+#[path = "../verify/chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+pub use self::uniform_int::{Rng, SplitMix};
+use self::chi_squared::{chi_squared, chi_squared_limit};
+use self::uniform_int::UniformInt;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// ——— selection ———
+
+/// Largest `k` for Floyd's algorithm with a linear search.
+pub const FLOYD_LINEAR_MAX: usize = 64;
+/// Largest `n / k` for the partial shuffle.
+pub const SHUFFLE_MAX_RATIO: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Floyd,
+    FloydHashed,
+    PartialShuffle,
+}
+
+/// The strategy `sample_indices` uses for `k` of `n`.
+pub fn strategy(n: usize, k: usize) -> Strategy {
+    if n / SHUFFLE_MAX_RATIO <= k {
+        Strategy::PartialShuffle
+    } else if k <= FLOYD_LINEAR_MAX {
+        Strategy::Floyd
+    } else {
+        Strategy::FloydHashed
+    }
+}
+
+/// `k` distinct indices from `[0, n)`, in random order. Panics if `k > n`.
+pub fn sample_indices<R: Rng+?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    sample_indices_with(rng, n, k, strategy(n, k))
+}
+
+/// `sample_indices` with a given strategy.
+pub fn sample_indices_with<R: Rng+?Sized>(rng: &mut R, n: usize, k: usize, strategy: Strategy)
+    -> Vec<usize>
+{
+    assert!(k <= n, "sample_indices: {} of {}", k, n);
+    match strategy {
+        Strategy::Floyd => floyd(rng, n, k),
+        Strategy::FloydHashed => floyd_hashed(rng, n, k),
+        Strategy::PartialShuffle => partial_shuffle(rng, n, k),
+    }
+}
+
+// ——— impls ———
+
+/// Uniform in `[0, n)`, sampled as `u64`; `n` must be positive.
+fn gen_index<R: Rng+?Sized>(rng: &mut R, n: usize) -> usize {
+    UniformInt::<u64, _>::new(0, n as u64).sample(rng) as usize
+}
+
+fn floyd<R: Rng+?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(k);
+    for j in n - k..n {
+        let t = gen_index(rng, j + 1);
+        if let Some(pos) = indices.iter().position(|&x| x == t) {
+            indices[pos] = j;
+        }
+        indices.push(t);
+    }
+    indices
+}
+
+fn floyd_hashed<R: Rng+?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(k);
+    let mut positions = HashMap::with_capacity(k);
+    for j in n - k..n {
+        let t = gen_index(rng, j + 1);
+        if let Some(&pos) = positions.get(&t) {
+            indices[pos] = j;
+            positions.insert(j, pos);
+        }
+        positions.insert(t, indices.len());
+        indices.push(t);
+    }
+    indices
+}
+
+fn partial_shuffle<R: Rng+?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..n).collect();
+    for i in 0..k {
+        let j = i + gen_index(rng, n - i);
+        indices.swap(i, j);
+    }
+    indices.truncate(k);
+    indices
+}
+
+// ——— usage ———
+
+/// Counts the bytes allocated and not yet freed, and the peak since reset.
+struct CountingAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let now = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(now, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+    
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Peak bytes allocated while running `f`, beyond those already allocated.
+fn peak_allocation<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+    let base = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(base, Ordering::SeqCst);
+    let result = f();
+    (result, PEAK.load(Ordering::SeqCst) - base)
+}
+
+const STRATEGIES: [Strategy; 3] = [Strategy::Floyd, Strategy::FloydHashed, Strategy::PartialShuffle];
+
+fn main() {
+    let mut rng = SplitMix(0x5EED);
+    {
+        // Distinct and in range, including k = 0 and k = n
+        for &strategy in STRATEGIES.iter() {
+            for &(n, k) in &[(0, 0), (1, 0), (1, 1), (5, 5), (10, 3), (100, 100), (1000, 999), (1 << 20, 500)] {
+                let mut xs = sample_indices_with(&mut rng, n, k, strategy);
+                assert_eq!(xs.len(), k);
+                assert!(xs.iter().all(|&x| x < n));
+                xs.sort();
+                xs.dedup();
+                assert_eq!(xs.len(), k, "{:?}: duplicates in {} of {}", strategy, k, n);
+            }
+        }
+        println!("distinct indices in range, every strategy");
+    }
+    {
+        // Every ordered choice of 3 from 5 (60 of them) equally likely
+        for &strategy in STRATEGIES.iter() {
+            let mut counts = [0u64; 125];
+            for _ in 0..120_000 {
+                let xs = sample_indices_with(&mut rng, 5, 3, strategy);
+                counts[xs[0] * 25 + xs[1] * 5 + xs[2]] += 1;
+            }
+            let counts: Vec<u64> = counts.iter().enumerate()
+                .filter(|&(i, _)| i / 25 != i / 5 % 5 && i / 25 != i % 5 && i / 5 % 5 != i % 5)
+                .map(|(_, &c)| c)
+                .collect();
+            assert_eq!(counts.len(), 60);
+            assert!(chi_squared(&counts) < chi_squared_limit(59), "{:?}: {:?}", strategy, counts);
+        }
+        println!("uniform over ordered choices of 3 from 5, every strategy");
+    }
+    {
+        // Every index equally likely, over a large n in 100 buckets
+        for &strategy in STRATEGIES.iter() {
+            let n = 1_000_000;
+            let mut counts = [0u64; 100];
+            for _ in 0..100 {
+                for x in sample_indices_with(&mut rng, n, 2000, strategy) {
+                    counts[x / (n / 100)] += 1;
+                }
+            }
+            assert!(chi_squared(&counts) < chi_squared_limit(99), "{:?}: {:?}", strategy, counts);
+        }
+        println!("uniform over indices of 10^6, every strategy");
+    }
+    {
+        // The selector: memory proportional to k, never to n
+        assert_eq!(strategy(10, 10), Strategy::PartialShuffle);
+        assert_eq!(strategy(1 << 40, 10), Strategy::Floyd);
+        assert_eq!(strategy(1000, 500), Strategy::PartialShuffle);
+        assert_eq!(strategy(1 << 40, 1000), Strategy::FloydHashed);
+        assert_eq!(strategy(0, 0), Strategy::PartialShuffle);
+        let n = usize::MAX / 2;
+        for &k in &[0, 1, 10, FLOYD_LINEAR_MAX, 1000, 100_000] {
+            let (xs, peak) = peak_allocation(|| sample_indices(&mut rng, n, k));
+            assert!(xs.len() == k && xs.iter().all(|&x| x < n));
+            assert!(peak <= 100 * k.max(1), "{} of {}: {} bytes", k, n, peak);
+        }
+        // The shuffle's `n` is at most `SHUFFLE_MAX_RATIO` times `k`
+        let (_, peak) = peak_allocation(|| sample_indices(&mut rng, 1 << 20, (1 << 20) / SHUFFLE_MAX_RATIO));
+        assert!(peak <= 8 << 20);
+        println!("k of usize::MAX / 2: at most 100 bytes per index");
+    }
+}
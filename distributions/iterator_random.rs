@@ -0,0 +1,237 @@
+//! `IteratorRandom`: choosing from iterators
+//! 
+//! `choose` and `sample` for any iterator, not only slices, in a single pass
+//! and without collecting it first:
+//! 
+//! *   `iter.choose(&mut rng)`: one uniformly chosen item, `None` if empty
+//! *   `iter.sample(&mut rng, k)`: `k` distinct items (all of them if there are
+//!     fewer), every subset equally likely, in no particular order
+//! 
+//! An iterator whose `size_hint` is exact (lower bound equal to the upper, as
+//! for `Vec::into_iter` or a `Range`) takes a fast path: `choose` draws one
+//! index and skips to it with `nth`, and `sample` draws `k` distinct indices
+//! with Floyd's algorithm. Any other iterator uses reservoir sampling, which
+//! draws an index per item: a reservoir of one for `choose`, Algorithm R for
+//! `sample`. `main` checks uniformity through both paths, the words each
+//! consumes, and empty and short iterators.
+//! 
+//! Run with:
+//! 
+//!     rustc -O iterator_random.rs && ./iterator_random
+//! 
+//! Thoughts: the two paths give different results from the same seed, so
+//! whether a seeded program's output changes when an adaptor (`filter`,
+//! `chain` with an unbounded iterator) is added or removed depends on a
+//! `size_hint` it probably never looked at. That seems acceptable: neither
+//! result is documented. Trusting `size_hint` is safe but not sound as a
+//! promise; an iterator which ends early just yields fewer items here. A
+//! lower bound alone could also start the reservoir with one index drawn over
+//! the first `lower` items; few adaptors report a useful lower bound without
+//! an exact one, so this is not done.
+
+#[path = "uniform_int.rs"]
+#[allow(dead_code)]
+mod uniform_int;
+
+#[path = "../verify/chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+pub use self::uniform_int::{Rng, SplitMix};
+use self::chi_squared::{chi_squared, chi_squared_limit};
+use self::uniform_int::UniformInt;
+
+// ——— traits ———
+
+pub trait IteratorRandom: Iterator + Sized {
+    /// A uniformly chosen item; `None` if empty.
+    fn choose<R: Rng+?Sized>(mut self, rng: &mut R) -> Option<Self::Item> {
+        let (lower, upper) = self.size_hint();
+        if upper == Some(lower) {
+            return if lower == 0 { None } else { self.nth(gen_index(rng, lower)) };
+        }
+        // Reservoir of one: the i-th item replaces the choice with chance 1/i
+        let mut chosen = None;
+        let mut seen = 0;
+        for x in self {
+            seen += 1;
+            if seen == 1 || gen_index(rng, seen) == 0 {
+                chosen = Some(x);
+            }
+        }
+        chosen
+    }
+    
+    /// `k` distinct items, uniformly chosen, in no particular order; all
+    /// items if there are no more than `k`.
+    fn sample<R: Rng+?Sized>(mut self, rng: &mut R, k: usize) -> Vec<Self::Item> {
+        let (lower, upper) = self.size_hint();
+        if upper == Some(lower) {
+            if lower <= k {
+                return self.collect();
+            }
+            let mut out = Vec::with_capacity(k);
+            let mut pos = 0;
+            for i in floyd(rng, lower, k) {
+                match self.nth(i - pos) {
+                    Some(x) => out.push(x),
+                    None => break,
+                }
+                pos = i + 1;
+            }
+            return out;
+        }
+        // Algorithm R: the i-th item replaces a uniformly chosen one of the
+        // `k` with chance k/i
+        let mut out: Vec<Self::Item> = self.by_ref().take(k).collect();
+        for (i, x) in self.enumerate() {
+            let j = gen_index(rng, k + i + 1);
+            if j < k {
+                out[j] = x;
+            }
+        }
+        out
+    }
+}
+
+impl<I: Iterator> IteratorRandom for I {}
+
+// ——— impls ———
+
+/// Uniform in `[0, n)`, sampled as `u64`; `n` must be positive.
+fn gen_index<R: Rng+?Sized>(rng: &mut R, n: usize) -> usize {
+    UniformInt::<u64, _>::new(0, n as u64).sample(rng) as usize
+}
+
+/// `k` distinct indices in `[0, n)`, every subset equally likely, ascending
+/// (Floyd's algorithm: `k` draws whatever `n`). Needs `k <= n`.
+fn floyd<R: Rng+?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(k);
+    for j in n - k..n {
+        let t = gen_index(rng, j + 1);
+        indices.push(if indices.contains(&t) { j } else { t });
+    }
+    indices.sort();
+    indices
+}
+
+// ——— test RNGs ———
+
+/// Counts the words taken from a `SplitMix`.
+struct Counting(SplitMix, usize);
+
+impl Rng for Counting {
+    fn next_u32(&mut self) -> u32 {
+        self.1 += 1;
+        self.0.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.1 += 1;
+        self.0.next_u64()
+    }
+}
+
+// ——— usage ———
+
+/// Hides an iterator's length: `size_hint` is `(0, None)`.
+struct Unhinted<I>(I);
+
+impl<I: Iterator> Iterator for Unhinted<I> {
+    type Item = I::Item;
+    
+    fn next(&mut self) -> Option<I::Item> {
+        self.0.next()
+    }
+}
+
+/// Rank of a 3-subset of `0..6` among all 20, for counting.
+fn subset_rank(xs: &[usize]) -> usize {
+    let mask: usize = xs.iter().map(|&x| 1 << x).sum();
+    (0..mask).filter(|m: &usize| m.count_ones() == 3).count()
+}
+
+fn main() {
+    let seed = SplitMix(0x5EED);
+    {
+        // Empty: nothing chosen, nothing consumed
+        let mut rng = Counting(seed.clone(), 0);
+        assert_eq!((0..0).choose(&mut rng), None);
+        assert_eq!(Unhinted(0..0).choose(&mut rng), None);
+        assert!((0..0).sample(&mut rng, 3).is_empty());
+        assert!(Unhinted(0..0).sample(&mut rng, 3).is_empty());
+        assert!((0..10).sample(&mut rng, 0).is_empty());
+        assert_eq!(rng.1, 0);
+        
+        // No more than `k` items: all of them
+        let mut all = Unhinted(0..5).sample(&mut rng, 5);
+        all.sort();
+        assert!(all == (0..5).collect::<Vec<_>>());
+        assert!((0..5).sample(&mut rng, 9) == (0..5).collect::<Vec<_>>());
+        assert_eq!(Unhinted(7..8).choose(&mut rng), Some(7));
+        assert_eq!(rng.1, 0);
+        println!("empty and short iterators: ok, nothing consumed");
+    }
+    {
+        // Fast path: one word for `choose`, `k` for `sample`, whatever the
+        // length; the reservoir takes one per item after the first (`k`)
+        let n = 1000;
+        let mut rng = Counting(seed.clone(), 0);
+        assert!((0..n).choose(&mut rng).is_some());
+        assert_eq!(rng.1, 1);
+        let mut rng = Counting(seed.clone(), 0);
+        assert!(Unhinted(0..n).choose(&mut rng).is_some());
+        assert_eq!(rng.1, n - 1);
+        let mut rng = Counting(seed.clone(), 0);
+        assert_eq!((0..n).sample(&mut rng, 10).len(), 10);
+        assert_eq!(rng.1, 10);
+        let mut rng = Counting(seed.clone(), 0);
+        assert_eq!(Unhinted(0..n).sample(&mut rng, 10).len(), 10);
+        assert_eq!(rng.1, n - 10);
+        // `filter` bounds the length without knowing it: reservoir too
+        let mut rng = Counting(seed.clone(), 0);
+        assert!((0..n).filter(|x| x % 2 == 0).choose(&mut rng).is_some());
+        assert_eq!(rng.1, n / 2 - 1);
+        println!("exact size_hint: one word per item chosen; otherwise one per item");
+    }
+    let mut rng = seed.clone();
+    {
+        // choose: every item equally likely, through each path
+        let mut exact = [0u64; 10];
+        let mut bounded = [0u64; 10];
+        let mut unhinted = [0u64; 10];
+        for _ in 0..100_000 {
+            exact[(0..10).choose(&mut rng).unwrap()] += 1;
+            bounded[(0..20).filter(|x| x % 2 == 0).choose(&mut rng).unwrap() / 2] += 1;
+            unhinted[Unhinted(0..10).choose(&mut rng).unwrap()] += 1;
+        }
+        for counts in [exact, bounded, unhinted].iter() {
+            assert!(chi_squared(counts) < chi_squared_limit(9), "choose: {:?}", counts);
+        }
+        println!("choose: uniform, exact size_hint or not");
+    }
+    {
+        // sample: all 20 subsets of 3 from 6 equally likely, each distinct
+        let mut exact = [0u64; 20];
+        let mut unhinted = [0u64; 20];
+        for _ in 0..200_000 {
+            let xs = (0..6).sample(&mut rng, 3);
+            let ys = Unhinted(0..6).sample(&mut rng, 3);
+            assert_eq!(xs.len(), 3);
+            assert_eq!(ys.len(), 3);
+            exact[subset_rank(&xs)] += 1;
+            unhinted[subset_rank(&ys)] += 1;
+        }
+        for counts in [exact, unhinted].iter() {
+            assert!(counts.iter().all(|&c| c > 0));
+            assert!(chi_squared(counts) < chi_squared_limit(19), "sample: {:?}", counts);
+        }
+        println!("sample: uniform over subsets, exact size_hint or not");
+    }
+    {
+        // A lazy iterator too long to collect comfortably
+        let xs = (0u64..).map(|x| x * 3).take_while(|&x| x < 30_000_000).sample(&mut rng, 5);
+        assert!(xs.len() == 5 && xs.iter().all(|x| x % 3 == 0 && *x < 30_000_000));
+        println!("sample from a lazy iterator: {:?}", xs);
+    }
+}
@@ -0,0 +1,167 @@
+//! Measuring the bias of `x % n` against rejection sampling
+//! 
+//! Reducing a `W`-bit word `x` to `[0, n)` with `x % n` maps `q + 1` words to
+//! each of the first `r = 2^W mod n` values and `q = 2^W / n` to the rest.
+//! This measures that bias empirically, next to `UniformInt` from
+//! `uniform_int.rs`, on adversarial bounds for 8, 16, 32 and 64-bit words:
+//! 
+//! *   `n = 2^(W-1) + 1`, just above a power of two: `q = 1`, so every value
+//!     except the last two is twice as likely as those two, the worst ratio
+//!     possible; but the favoured values are almost all of them
+//! *   `n = 3 * 2^(W-2)`: also `q = 1`, with a third of the values taking half
+//!     of all samples, close to the largest total bias (at `n = 2^W / √2`)
+//! 
+//! `measure` samples a method and returns the fraction of samples in the
+//! unfavoured values `[r, n)`, with its z-score against `(n - r) / n` (the
+//! uniform expectation); `naive_exact` gives the exact fraction `x % n` should
+//! show, and how many samples would detect it. (The unfavoured fraction is the
+//! one which stays accurate in `f64` when there are only two such values.)
+//! 
+//! Run with:
+//! 
+//!     rustc -O modulo_bias.rs && ./modulo_bias
+//! 
+//! The run asserts that rejection sampling shows no bias (|z| < 5) on every
+//! bound, and that `x % n` shows exactly its predicted bias where 10^6
+//! samples can detect it. For 8-bit words it also compares every value's
+//! count (chi-squared), which no larger word allows.
+//! 
+//! Thoughts: for `n = 2^(W-1) + 1` the bias is real at every width but hidden
+//! in two values out of `n`; at 32 bits detecting it takes some 10^11 samples,
+//! which is why "the tests pass" proves nothing about `x % n` and the bias has
+//! to be derived, as here, before measuring it. Rejection needs at most two
+//! words on average for any `n`.
+
+#[path = "uniform_int.rs"]
+#[allow(dead_code)]
+mod uniform_int;
+
+#[path = "../verify/chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+use chi_squared::{chi_squared, chi_squared_limit};
+use uniform_int::{Rng, SplitMix, UniformInt};
+
+// ——— measurement ———
+
+/// What `x % n` should show, from counting words.
+#[derive(Debug, Clone, Copy)]
+pub struct NaiveBias {
+    /// Number of favoured values, `2^W mod n`.
+    pub r: u128,
+    /// Words per unfavoured value, `2^W / n`.
+    pub q: u128,
+    /// Probability of an unfavoured value, `(n - r) q / 2^W`.
+    pub unfavoured: f64,
+    /// The same under a uniform distribution, `(n - r) / n`.
+    pub uniform: f64,
+}
+
+impl NaiveBias {
+    /// Samples needed for the expected z-score to reach `z`.
+    pub fn samples_to_detect(&self, z: f64) -> f64 {
+        let p = self.uniform;
+        z * z * p * (1.0 - p) / (self.unfavoured - p).powi(2)
+    }
+}
+
+/// The exact distribution of `x % n` for uniform `W`-bit `x`.
+pub fn naive_exact(bits: u32, n: u64) -> NaiveBias {
+    let words = 1u128 << bits;
+    let (q, r) = (words / n as u128, words % n as u128);
+    NaiveBias {
+        r,
+        q,
+        unfavoured: ((n as u128 - r) * q) as f64 / words as f64,
+        uniform: (n as u128 - r) as f64 / n as f64,
+    }
+}
+
+/// Observed frequency of the unfavoured values.
+#[derive(Debug, Clone, Copy)]
+pub struct Measured {
+    pub fraction: f64,
+    /// Standard deviations from the uniform expectation.
+    pub z: f64,
+}
+
+/// Draw `samples` from `sample` (which must return values in `[0, n)`) and
+/// compare the frequency of `[r, n)` with uniform.
+pub fn measure<F: FnMut() -> u64>(bits: u32, n: u64, samples: u64, mut sample: F) -> Measured {
+    let r = naive_exact(bits, n).r as u64;
+    let mut hits = 0;
+    for _ in 0..samples {
+        let x = sample();
+        assert!(x < n, "measure: sample {} out of range [0, {})", x, n);
+        hits += (x >= r) as u64;
+    }
+    let p = (n - r) as f64 / n as f64;
+    let fraction = hits as f64 / samples as f64;
+    Measured { fraction, z: (fraction - p) / (p * (1.0 - p) / samples as f64).sqrt() }
+}
+
+// ——— usage ———
+
+/// `x % n` with the top `bits` bits of a word.
+fn naive(rng: &mut SplitMix, bits: u32, n: u64) -> u64 {
+    (rng.next_u64() >> (64 - bits)) % n
+}
+
+/// `UniformInt` over `[0, n)` for the type of `bits` bits.
+fn rejection(rng: &mut SplitMix, bits: u32, n: u64) -> u64 {
+    match bits {
+        8 => UniformInt::<u8, _>::new(0, n as u8).sample(rng) as u64,
+        16 => UniformInt::<u16, _>::new(0, n as u16).sample(rng) as u64,
+        32 => UniformInt::<u32, _>::new(0, n as u32).sample(rng) as u64,
+        64 => UniformInt::<u64, _>::new(0, n).sample(rng),
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    const SAMPLES: u64 = 1_000_000;
+    let mut rng = SplitMix(0x5EED);
+    
+    for &bits in [8u32, 16, 32, 64].iter() {
+        let bounds = [("2^(W-1) + 1", (1u64 << (bits - 1)) + 1), ("3 * 2^(W-2)", 3u64 << (bits - 2))];
+        for &(name, n) in bounds.iter() {
+            let exact = naive_exact(bits, n);
+            assert_eq!((exact.q, exact.r), (1, (1u128 << bits) - n as u128));
+            let x_mod_n = measure(bits, n, SAMPLES, || naive(&mut rng, bits, n));
+            let uniform = measure(bits, n, SAMPLES, || rejection(&mut rng, bits, n));
+            let needed = exact.samples_to_detect(5.0);
+            println!("W = {}, n = {}: unfavoured fraction {:.3e} if uniform, {:.3e} for x % n; \
+                measured x % n {:.3e} (z = {:.1}), rejection {:.3e} (z = {:.1}); \
+                x % n detectable after ~{:.1e} samples",
+                bits, name, exact.uniform, exact.unfavoured, x_mod_n.fraction, x_mod_n.z,
+                uniform.fraction, uniform.z, needed);
+            
+            assert!(uniform.z.abs() < 5.0, "W = {}, n = {}: rejection sampling biased", bits, name);
+            if needed < SAMPLES as f64 / 4.0 {
+                // Detectable with 10^6 samples: x % n is biased, by as much
+                // as predicted
+                assert!(x_mod_n.z < -5.0, "W = {}, n = {}: x % n bias not seen", bits, name);
+                let p = exact.unfavoured;
+                let z = (x_mod_n.fraction - p) / (p * (1.0 - p) / SAMPLES as f64).sqrt();
+                assert!(z.abs() < 5.0, "W = {}, n = {}: x % n differs from prediction", bits, name);
+            }
+        }
+    }
+    
+    // 8-bit words: every value's count, for every n in (128, 256)
+    let mut biased = 0;
+    for n in 129..256u64 {
+        let mut counts = [vec![0u64; n as usize], vec![0u64; n as usize]];
+        for _ in 0..100 * n {
+            counts[0][naive(&mut rng, 8, n) as usize] += 1;
+            counts[1][rejection(&mut rng, 8, n) as usize] += 1;
+        }
+        let limit = chi_squared_limit(n as usize - 1);
+        assert!(chi_squared(&counts[1]) < limit, "n = {}: rejection sampling biased", n);
+        biased += (chi_squared(&counts[0]) > limit) as u32;
+    }
+    // x % n misses only for n close to 256, where few values are unfavoured
+    assert!(biased >= 100, "x % n biased for only {} of 127 bounds", biased);
+    println!("8-bit words, n in (128, 256): rejection uniform for all, x % n biased for {}", biased);
+}
@@ -0,0 +1,224 @@
+//! `usize`/`isize` sampling which is the same on 32 and 64-bit targets
+//! 
+//! A seeded program should do the same thing everywhere, but `usize` is 32
+//! bits on some targets and 64 on others. The policy here:
+//! 
+//! *   `gen::<usize>()` (and `isize`) always consumes one `u64` and truncates
+//!     it, so a 32-bit target gets the low half of the 64-bit value, and both
+//!     stay in step with the generator
+//! *   `Uniform` over a `usize` (or `isize`) range always samples the range as
+//!     `u64` (or `i64`) and converts the result. Every range a 32-bit target
+//!     can express has the same `u64` form on both, so both give the same
+//!     values from the same seed
+//! 
+//! Only the full-width values of `gen::<usize>()` and ranges beyond 32 bits
+//! (which a 32-bit target cannot express) differ.
+//! 
+//! Both pointer widths are tested on any host: the implementation is a macro
+//! instantiated with `usize`/`isize` standing for `u32`/`i32` (what a 32-bit
+//! target compiles) and `u64`/`i64`, as well as with the real types, which
+//! must match the host's width.
+//! 
+//! Run with:
+//! 
+//!     rustc -O pointer_sized.rs && ./pointer_sized
+//! 
+//! Thoughts: the alternative for `gen::<usize>()`, rejecting 64-bit values
+//! which do not fit, would give equal values wherever both can be produced,
+//! but a 32-bit target would reject all but one word in 2^32. Sampling with
+//! the native width (`next_u32` on 32-bit targets) is cheaper there, but with
+//! any generator where `next_u32` consumes less than `next_u64` it puts the
+//! two targets' streams out of step after the first `usize`, so nothing
+//! generated afterwards agrees either.
+
+#[path = "uniform_int.rs"]
+#[allow(dead_code)]
+mod uniform_int;
+
+use uniform_int::{Rng, SplitMix, UniformInt};
+
+// ——— impls ———
+
+macro_rules! pointer_sized_impl {
+    ($usize:ident, $isize:ident) => {
+        use super::{Rng, UniformInt};
+        
+        /// `gen::<usize>()`: a `u64`, truncated.
+        pub fn gen_usize<R: Rng+?Sized>(rng: &mut R) -> $usize {
+            rng.next_u64() as $usize
+        }
+        
+        /// `gen::<isize>()`: a `u64`, truncated.
+        pub fn gen_isize<R: Rng+?Sized>(rng: &mut R) -> $isize {
+            rng.next_u64() as $isize
+        }
+        
+        /// Uniform over `[low, high]`, sampled as `u64`.
+        #[derive(Debug, Clone, Copy)]
+        pub struct UniformUsize(UniformInt<u64, u64>);
+        
+        impl UniformUsize {
+            pub fn new_inclusive(low: $usize, high: $usize) -> Self {
+                UniformUsize(UniformInt::<u64, _>::new_inclusive(low as u64, high as u64))
+            }
+            
+            pub fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> $usize {
+                self.0.sample(rng) as $usize
+            }
+        }
+        
+        /// Uniform over `[low, high]`, sampled as `i64`.
+        #[derive(Debug, Clone, Copy)]
+        pub struct UniformIsize(UniformInt<i64, u64>);
+        
+        impl UniformIsize {
+            pub fn new_inclusive(low: $isize, high: $isize) -> Self {
+                UniformIsize(UniformInt::<i64, _>::new_inclusive(low as i64, high as i64))
+            }
+            
+            pub fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> $isize {
+                self.0.sample(rng) as $isize
+            }
+        }
+        
+        /// Fisher-Yates, as a seeded program would write it.
+        pub fn shuffle<T, R: Rng+?Sized>(rng: &mut R, xs: &mut [T]) {
+            for i in (1..xs.len()).rev() {
+                let j = UniformUsize::new_inclusive(0, i as $usize).sample(rng);
+                xs.swap(i, j as usize);
+            }
+        }
+    };
+}
+
+/// The real `usize`/`isize`.
+mod native {
+    pointer_sized_impl!(usize, isize);
+}
+
+/// As compiled for a 32-bit target.
+mod target32 {
+    pointer_sized_impl!(u32, i32);
+}
+
+/// As compiled for a 64-bit target.
+mod target64 {
+    pointer_sized_impl!(u64, i64);
+}
+
+// ——— test RNGs ———
+
+/// A 32-bit generator: `next_u64` takes two words, as with PCG32 or a block
+/// generator's buffer, so `next_u32` and `next_u64` consume different amounts.
+#[derive(Clone)]
+struct Words32(SplitMix);
+
+impl Rng for Words32 {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.next_u32() as u64 | (self.next_u32() as u64) << 32
+    }
+}
+
+// ——— usage ———
+
+fn main() {
+    let seed = SplitMix(0x5EED);
+    {
+        // gen: 32-bit values are the low halves, and the generators stay in step
+        let (mut a, mut b) = (seed.clone(), seed.clone());
+        for _ in 0..1000 {
+            assert_eq!(target32::gen_usize(&mut a), target64::gen_usize(&mut b) as u32);
+            assert_eq!(target32::gen_isize(&mut a), target64::gen_isize(&mut b) as i32);
+        }
+        assert_eq!(a.next_u64(), b.next_u64());
+        println!("gen::<usize>(), gen::<isize>(): truncated, in step");
+    }
+    {
+        // Uniform: equal values over every range both can express, including
+        // the full 32-bit range and negative ranges
+        let ranges: &[(u32, u32)] = &[
+            (0, 0), (0, 1), (0, 9), (3, 1000), (0, 1 << 31), ((1 << 31) + 1, u32::MAX),
+            (0, u32::MAX - 1), (0, u32::MAX), (u32::MAX, u32::MAX),
+        ];
+        let (mut a, mut b) = (seed.clone(), seed.clone());
+        for &(low, high) in ranges {
+            let d32 = target32::UniformUsize::new_inclusive(low, high);
+            let d64 = target64::UniformUsize::new_inclusive(low as u64, high as u64);
+            for _ in 0..1000 {
+                let x = d32.sample(&mut a);
+                assert!(low <= x && x <= high);
+                assert_eq!(x as u64, d64.sample(&mut b), "[{}, {}]", low, high);
+            }
+        }
+        let ranges: &[(i32, i32)] = &[
+            (-5, 5), (i32::MIN, -1), (-1, i32::MAX), (i32::MIN, i32::MAX), (0, 0),
+        ];
+        for &(low, high) in ranges {
+            let d32 = target32::UniformIsize::new_inclusive(low, high);
+            let d64 = target64::UniformIsize::new_inclusive(low as i64, high as i64);
+            for _ in 0..1000 {
+                let x = d32.sample(&mut a);
+                assert!(low <= x && x <= high);
+                assert_eq!(x as i64, d64.sample(&mut b), "[{}, {}]", low, high);
+            }
+        }
+        assert_eq!(a.next_u64(), b.next_u64());
+        println!("Uniform over usize, isize: equal on both widths");
+    }
+    {
+        // A seeded program: the same permutation on both
+        let mut xs32: Vec<u32> = (0..1000).collect();
+        let mut xs64 = xs32.clone();
+        target32::shuffle(&mut seed.clone(), &mut xs32);
+        target64::shuffle(&mut seed.clone(), &mut xs64);
+        assert!(xs32 == xs64);
+        assert!(xs32 != (0..1000).collect::<Vec<u32>>());
+        println!("shuffle: same permutation on both widths");
+    }
+    {
+        // The host is one of the two
+        let (mut a, mut b) = (seed.clone(), seed.clone());
+        let d = native::UniformUsize::new_inclusive(7, 100_000);
+        let e = native::UniformIsize::new_inclusive(-100, 100);
+        for _ in 0..1000 {
+            let x = native::gen_usize(&mut a) as u64;
+            let y = native::gen_isize(&mut a) as i64;
+            let (v, w) = (d.sample(&mut a) as u64, e.sample(&mut a) as i64);
+            if cfg!(target_pointer_width = "64") {
+                let d64 = target64::UniformUsize::new_inclusive(7, 100_000);
+                let e64 = target64::UniformIsize::new_inclusive(-100, 100);
+                assert_eq!(x, target64::gen_usize(&mut b));
+                assert_eq!(y, target64::gen_isize(&mut b));
+                assert_eq!((v, w), (d64.sample(&mut b), e64.sample(&mut b)));
+            } else {
+                let d32 = target32::UniformUsize::new_inclusive(7, 100_000);
+                let e32 = target32::UniformIsize::new_inclusive(-100, 100);
+                assert_eq!(x, target32::gen_usize(&mut b) as u64);
+                assert_eq!(y, target32::gen_isize(&mut b) as i64);
+                assert_eq!((v, w), (d32.sample(&mut b) as u64, e32.sample(&mut b) as i64));
+            }
+        }
+        let mut xs: Vec<u32> = (0..1000).collect();
+        let mut ys = xs.clone();
+        native::shuffle(&mut seed.clone(), &mut xs);
+        target64::shuffle(&mut seed.clone(), &mut ys);
+        assert!(xs == ys);
+        println!("native ({} bits): matches its model", usize::BITS);
+    }
+    {
+        // For contrast: sampling with the native word width puts the two out
+        // of step, so even values which fit in both no longer agree
+        let (mut a, mut b) = (Words32(seed.clone()), Words32(seed.clone()));
+        let _ = (a.next_u32(), b.next_u64());
+        let d32 = target32::UniformUsize::new_inclusive(0, 9);
+        let d64 = target64::UniformUsize::new_inclusive(0, 9);
+        let xs: Vec<u32> = (0..20).map(|_| d32.sample(&mut a)).collect();
+        let ys: Vec<u32> = (0..20).map(|_| d64.sample(&mut b) as u32).collect();
+        assert!(xs != ys);
+        println!("native-width gen::<usize>(): streams diverge");
+    }
+}
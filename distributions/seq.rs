@@ -0,0 +1,874 @@
+//! `SliceRandom` over three trait designs, and a dyn-compatible form
+//! 
+//! `choose`, `choose_mut`, `shuffle` and `choose_weighted` are written once,
+//! against `gen_range.rs`'s `Words`, and each design's extension trait
+//! (`extends_Rng`, `extends_CryptoRng`, `raw_infallible`) reuses them. The
+//! methods are generic, so each design adds `DynSliceRandom<T>` too, with the
+//! generator an object.
+//! 
+//! Thoughts: object safety bites twice, and the designs differ only in the
+//! second: `SliceRandom` can't be an object, and over `raw_infallible` the
+//! error must become a parameter of the trait, hence `DynTrySliceRandom`.
+//! Weighted choice checks the weights before drawing a word, so only the
+//! fallible method adds the generator's error.
+
+use std::convert::Infallible;
+
+#[path = "gen_range.rs"]
+#[allow(dead_code)]
+mod gen_range;
+
+use gen_range::{SampleRange, Words};
+
+// ——— written once, against `Words` ———
+
+/// Uniform in `[0, n)`, sampled as `u64` (as in `slice_random.rs`); `None`,
+/// drawing nothing, if `n` is 0.
+fn index<W: Words+?Sized>(words: &mut W, n: usize) -> Result<Option<usize>, W::Error> {
+    if n == 0 {
+        return Ok(None);
+    }
+    Ok(Some(u64::sample_range(0, n as u64, words)? as usize))
+}
+
+fn choose<'a, T, W: Words+?Sized>(xs: &'a [T], words: &mut W) -> Result<Option<&'a T>, W::Error> {
+    Ok(index(words, xs.len())?.map(|i| &xs[i]))
+}
+
+fn choose_mut<'a, T, W: Words+?Sized>(xs: &'a mut [T], words: &mut W)
+    -> Result<Option<&'a mut T>, W::Error>
+{
+    Ok(index(words, xs.len())?.map(move |i| &mut xs[i]))
+}
+
+/// Fisher-Yates. On error the slice is left part shuffled, a permutation
+/// of what it was.
+fn shuffle<T, W: Words+?Sized>(xs: &mut [T], words: &mut W) -> Result<(), W::Error> {
+    for i in (1..xs.len()).rev() {
+        let j = u64::sample_range(0, i as u64 + 1, words)? as usize;
+        xs.swap(i, j);
+    }
+    Ok(())
+}
+
+// ——— weights ———
+
+/// Why `choose_weighted` can't choose, whatever the generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightedError {
+    /// The slice is empty.
+    NoItem,
+    /// A weight is negative, infinite or NaN.
+    InvalidWeight,
+    /// Every weight is zero.
+    AllWeightsZero,
+    /// The total weight overflows its type.
+    TooMany,
+}
+
+/// A fallible generator's weighted choice failing: the weights, checked
+/// before anything is drawn, or the generator.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChooseError<E> {
+    Weights(WeightedError),
+    Rng(E),
+}
+
+impl ChooseError<Infallible> {
+    /// The weights' error, the only one an infallible generator can give.
+    pub fn weights(self) -> WeightedError {
+        match self {
+            ChooseError::Weights(e) => e,
+            ChooseError::Rng(e) => match e {},
+        }
+    }
+}
+
+/// Types a weight can have.
+pub trait Weight: Copy+PartialOrd {
+    const ZERO: Self;
+    
+    /// Finite and not negative.
+    fn is_valid(self) -> bool;
+    
+    /// `None` on overflow.
+    fn checked_sum(self, other: Self) -> Option<Self>;
+    
+    /// Uniform over `[0, total)`.
+    fn below<W: Words+?Sized>(total: Self, words: &mut W) -> Result<Self, W::Error>;
+}
+
+macro_rules! int_weight {
+    ($($ty:ident)*) => {$(
+        /// Exact: a point drawn by `gen_range`'s `SampleRange`.
+        impl Weight for $ty {
+            const ZERO: $ty = 0;
+            
+            fn is_valid(self) -> bool {
+                self as i128 >= 0
+            }
+            
+            fn checked_sum(self, other: $ty) -> Option<$ty> {
+                self.checked_add(other)
+            }
+            
+            fn below<W: Words+?Sized>(total: $ty, words: &mut W) -> Result<$ty, W::Error> {
+                $ty::sample_range(0, total, words)
+            }
+        }
+    )*};
+}
+
+int_weight! { i32 i64 u32 u64 }
+
+/// A point `v * total`, `v` a multiple of 2^-53 below 1, rounded once.
+/// It stays below `total`: the exact product is at least half an ulp
+/// under it, and exact when `total` is a power of two.
+impl Weight for f64 {
+    const ZERO: f64 = 0.0;
+    
+    fn is_valid(self) -> bool {
+        self >= 0.0 && self.is_finite()
+    }
+    
+    fn checked_sum(self, other: f64) -> Option<f64> {
+        Some(self + other).filter(|s| s.is_finite())
+    }
+    
+    fn below<W: Words+?Sized>(total: f64, words: &mut W) -> Result<f64, W::Error> {
+        let v = (words.word_u64()? >> 11) as f64 * (1.0 / (1u64 << 53) as f64);
+        Ok(v * total)
+    }
+}
+
+/// Two passes, calling `weight` twice an element, so that nothing is
+/// allocated: the total, checking every weight, then the first element
+/// whose running total passes a point drawn below it. The passes add alike,
+/// so the second reaches the first's total; a zero weight never passes a
+/// point its predecessor didn't.
+fn choose_weighted<'a, T, X, F, W>(xs: &'a [T], weight: F, words: &mut W)
+    -> Result<&'a T, ChooseError<W::Error>>
+    where X: Weight, F: Fn(&T) -> X, W: Words+?Sized
+{
+    if xs.is_empty() {
+        return Err(ChooseError::Weights(WeightedError::NoItem));
+    }
+    let mut total = X::ZERO;
+    for x in xs {
+        let w = weight(x);
+        if !w.is_valid() {
+            return Err(ChooseError::Weights(WeightedError::InvalidWeight));
+        }
+        total = total.checked_sum(w).ok_or(ChooseError::Weights(WeightedError::TooMany))?;
+    }
+    if total == X::ZERO {
+        return Err(ChooseError::Weights(WeightedError::AllWeightsZero));
+    }
+    let point = X::below(total, words).map_err(ChooseError::Rng)?;
+    let mut sum = X::ZERO;
+    for x in xs {
+        sum = sum.checked_sum(weight(x)).unwrap_or(total);
+        if point < sum {
+            return Ok(x);
+        }
+    }
+    panic!("choose_weighted: the weights changed between calls")
+}
+
+// ——— over extends_Rng ———
+
+mod on_extends_rng {
+    use super::gen_range::extends_rng::{CryptoError, CryptoRng, Rng};
+    use super::gen_range::on_extends_rng::{Fallibly, Infallibly};
+    use super::{ChooseError, Weight, WeightedError};
+    
+    pub trait SliceRandom {
+        type Item;
+        
+        /// A uniformly chosen element; `None` if empty.
+        fn choose<R: Rng+?Sized>(&self, rng: &mut R) -> Option<&Self::Item>;
+        
+        /// A uniformly chosen element; `None` if empty.
+        fn choose_mut<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<&mut Self::Item>;
+        
+        /// Fisher-Yates: every permutation equally likely.
+        fn shuffle<R: Rng+?Sized>(&mut self, rng: &mut R);
+        
+        fn try_choose<R: CryptoRng+?Sized>(&self, rng: &mut R)
+            -> Result<Option<&Self::Item>, CryptoError>;
+        
+        fn try_choose_mut<R: CryptoRng+?Sized>(&mut self, rng: &mut R)
+            -> Result<Option<&mut Self::Item>, CryptoError>;
+        
+        /// On error, the slice is left part shuffled.
+        fn try_shuffle<R: CryptoRng+?Sized>(&mut self, rng: &mut R) -> Result<(), CryptoError>;
+        
+        /// An element chosen with probability its weight over the total.
+        fn choose_weighted<R: Rng+?Sized, X: Weight, F: Fn(&Self::Item) -> X>(&self, rng: &mut R,
+            weight: F) -> Result<&Self::Item, WeightedError>;
+        
+        fn try_choose_weighted<R, X, F>(&self, rng: &mut R, weight: F)
+            -> Result<&Self::Item, ChooseError<CryptoError>>
+            where R: CryptoRng+?Sized, X: Weight, F: Fn(&Self::Item) -> X;
+    }
+    
+    impl<T> SliceRandom for [T] {
+        type Item = T;
+        
+        fn choose<R: Rng+?Sized>(&self, rng: &mut R) -> Option<&T> {
+            super::choose(self, &mut Infallibly(rng)).unwrap_or_else(|e| match e {})
+        }
+        
+        fn choose_mut<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<&mut T> {
+            super::choose_mut(self, &mut Infallibly(rng)).unwrap_or_else(|e| match e {})
+        }
+        
+        fn shuffle<R: Rng+?Sized>(&mut self, rng: &mut R) {
+            super::shuffle(self, &mut Infallibly(rng)).unwrap_or_else(|e| match e {})
+        }
+        
+        fn try_choose<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<Option<&T>, CryptoError> {
+            super::choose(self, &mut Fallibly(rng))
+        }
+        
+        fn try_choose_mut<R: CryptoRng+?Sized>(&mut self, rng: &mut R)
+            -> Result<Option<&mut T>, CryptoError>
+        {
+            super::choose_mut(self, &mut Fallibly(rng))
+        }
+        
+        fn try_shuffle<R: CryptoRng+?Sized>(&mut self, rng: &mut R) -> Result<(), CryptoError> {
+            super::shuffle(self, &mut Fallibly(rng))
+        }
+        
+        fn choose_weighted<R: Rng+?Sized, X: Weight, F: Fn(&T) -> X>(&self, rng: &mut R, weight: F)
+            -> Result<&T, WeightedError>
+        {
+            super::choose_weighted(self, weight, &mut Infallibly(rng)).map_err(ChooseError::weights)
+        }
+        
+        fn try_choose_weighted<R, X, F>(&self, rng: &mut R, weight: F)
+            -> Result<&T, ChooseError<CryptoError>>
+            where R: CryptoRng+?Sized, X: Weight, F: Fn(&T) -> X
+        {
+            super::choose_weighted(self, weight, &mut Fallibly(rng))
+        }
+    }
+    
+    /// Dyn-compatible: the generator an object, and the element type a
+    /// parameter, so that any slice-like container implements it.
+    pub trait DynSliceRandom<T> {
+        fn choose(&self, rng: &mut dyn Rng) -> Option<&T>;
+        
+        fn choose_mut(&mut self, rng: &mut dyn Rng) -> Option<&mut T>;
+        
+        fn shuffle(&mut self, rng: &mut dyn Rng);
+        
+        fn try_choose(&self, rng: &mut dyn CryptoRng) -> Result<Option<&T>, CryptoError>;
+        
+        fn try_choose_mut(&mut self, rng: &mut dyn CryptoRng)
+            -> Result<Option<&mut T>, CryptoError>;
+        
+        fn try_shuffle(&mut self, rng: &mut dyn CryptoRng) -> Result<(), CryptoError>;
+    }
+    
+    impl<T, S: AsRef<[T]>+AsMut<[T]>+?Sized> DynSliceRandom<T> for S {
+        fn choose(&self, rng: &mut dyn Rng) -> Option<&T> {
+            SliceRandom::choose(self.as_ref(), rng)
+        }
+        
+        fn choose_mut(&mut self, rng: &mut dyn Rng) -> Option<&mut T> {
+            SliceRandom::choose_mut(self.as_mut(), rng)
+        }
+        
+        fn shuffle(&mut self, rng: &mut dyn Rng) {
+            SliceRandom::shuffle(self.as_mut(), rng)
+        }
+        
+        fn try_choose(&self, rng: &mut dyn CryptoRng) -> Result<Option<&T>, CryptoError> {
+            SliceRandom::try_choose(self.as_ref(), rng)
+        }
+        
+        fn try_choose_mut(&mut self, rng: &mut dyn CryptoRng)
+            -> Result<Option<&mut T>, CryptoError>
+        {
+            SliceRandom::try_choose_mut(self.as_mut(), rng)
+        }
+        
+        fn try_shuffle(&mut self, rng: &mut dyn CryptoRng) -> Result<(), CryptoError> {
+            SliceRandom::try_shuffle(self.as_mut(), rng)
+        }
+    }
+}
+
+// ——— over extends_CryptoRng ———
+
+mod on_extends_cryptorng {
+    use super::gen_range::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use super::gen_range::on_extends_cryptorng::Fallibly;
+    use super::{ChooseError, Weight, WeightedError};
+    
+    pub trait SliceRandom {
+        type Item;
+        
+        fn try_choose<R: CryptoRng+?Sized>(&self, rng: &mut R)
+            -> Result<Option<&Self::Item>, CryptoError>;
+        
+        fn try_choose_mut<R: CryptoRng+?Sized>(&mut self, rng: &mut R)
+            -> Result<Option<&mut Self::Item>, CryptoError>;
+        
+        /// On error, the slice is left part shuffled.
+        fn try_shuffle<R: CryptoRng+?Sized>(&mut self, rng: &mut R) -> Result<(), CryptoError>;
+        
+        /// A uniformly chosen element; `None` if empty. Panics on error.
+        fn choose<R: Rng+?Sized>(&self, rng: &mut R) -> Option<&Self::Item> {
+            self.try_choose(rng).unwrap_or_else(|e| panic!("choose failed: {}", e))
+        }
+        
+        /// A uniformly chosen element; `None` if empty. Panics on error.
+        fn choose_mut<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<&mut Self::Item> {
+            self.try_choose_mut(rng).unwrap_or_else(|e| panic!("choose_mut failed: {}", e))
+        }
+        
+        /// Fisher-Yates: every permutation equally likely. Panics on error.
+        fn shuffle<R: Rng+?Sized>(&mut self, rng: &mut R) {
+            self.try_shuffle(rng).unwrap_or_else(|e| panic!("shuffle failed: {}", e))
+        }
+        
+        fn try_choose_weighted<R, X, F>(&self, rng: &mut R, weight: F)
+            -> Result<&Self::Item, ChooseError<CryptoError>>
+            where R: CryptoRng+?Sized, X: Weight, F: Fn(&Self::Item) -> X;
+        
+        /// An element chosen with probability its weight over the total.
+        /// Returns the weights' error; panics on the generator's.
+        fn choose_weighted<R: Rng+?Sized, X: Weight, F: Fn(&Self::Item) -> X>(&self, rng: &mut R,
+            weight: F) -> Result<&Self::Item, WeightedError>
+        {
+            self.try_choose_weighted(rng, weight).map_err(|e| match e {
+                ChooseError::Weights(e) => e,
+                ChooseError::Rng(e) => panic!("choose_weighted failed: {}", e),
+            })
+        }
+    }
+    
+    impl<T> SliceRandom for [T] {
+        type Item = T;
+        
+        fn try_choose<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<Option<&T>, CryptoError> {
+            super::choose(self, &mut Fallibly(rng))
+        }
+        
+        fn try_choose_mut<R: CryptoRng+?Sized>(&mut self, rng: &mut R)
+            -> Result<Option<&mut T>, CryptoError>
+        {
+            super::choose_mut(self, &mut Fallibly(rng))
+        }
+        
+        fn try_shuffle<R: CryptoRng+?Sized>(&mut self, rng: &mut R) -> Result<(), CryptoError> {
+            super::shuffle(self, &mut Fallibly(rng))
+        }
+        
+        fn try_choose_weighted<R, X, F>(&self, rng: &mut R, weight: F)
+            -> Result<&T, ChooseError<CryptoError>>
+            where R: CryptoRng+?Sized, X: Weight, F: Fn(&T) -> X
+        {
+            super::choose_weighted(self, weight, &mut Fallibly(rng))
+        }
+    }
+    
+    /// Dyn-compatible, as over extends_Rng.
+    pub trait DynSliceRandom<T> {
+        fn try_choose(&self, rng: &mut dyn CryptoRng) -> Result<Option<&T>, CryptoError>;
+        
+        fn try_choose_mut(&mut self, rng: &mut dyn CryptoRng)
+            -> Result<Option<&mut T>, CryptoError>;
+        
+        fn try_shuffle(&mut self, rng: &mut dyn CryptoRng) -> Result<(), CryptoError>;
+        
+        fn choose(&self, rng: &mut dyn Rng) -> Option<&T>;
+        
+        fn choose_mut(&mut self, rng: &mut dyn Rng) -> Option<&mut T>;
+        
+        fn shuffle(&mut self, rng: &mut dyn Rng);
+    }
+    
+    impl<T, S: AsRef<[T]>+AsMut<[T]>+?Sized> DynSliceRandom<T> for S {
+        fn try_choose(&self, rng: &mut dyn CryptoRng) -> Result<Option<&T>, CryptoError> {
+            SliceRandom::try_choose(self.as_ref(), rng)
+        }
+        
+        fn try_choose_mut(&mut self, rng: &mut dyn CryptoRng)
+            -> Result<Option<&mut T>, CryptoError>
+        {
+            SliceRandom::try_choose_mut(self.as_mut(), rng)
+        }
+        
+        fn try_shuffle(&mut self, rng: &mut dyn CryptoRng) -> Result<(), CryptoError> {
+            SliceRandom::try_shuffle(self.as_mut(), rng)
+        }
+        
+        fn choose(&self, rng: &mut dyn Rng) -> Option<&T> {
+            SliceRandom::choose(self.as_ref(), rng)
+        }
+        
+        fn choose_mut(&mut self, rng: &mut dyn Rng) -> Option<&mut T> {
+            SliceRandom::choose_mut(self.as_mut(), rng)
+        }
+        
+        fn shuffle(&mut self, rng: &mut dyn Rng) {
+            SliceRandom::shuffle(self.as_mut(), rng)
+        }
+    }
+}
+
+// ——— over raw_infallible ———
+
+mod on_raw_infallible {
+    use super::gen_range::on_raw_infallible::Raw;
+    use super::gen_range::raw_infallible::{RawRng, Rng};
+    use super::{ChooseError, Infallible, Weight, WeightedError};
+    use std::marker::PhantomData;
+    
+    pub trait SliceRandom {
+        type Item;
+        
+        fn try_choose<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<Option<&Self::Item>, E>;
+        
+        fn try_choose_mut<E, R: RawRng<E>+?Sized>(&mut self, rng: &mut R)
+            -> Result<Option<&mut Self::Item>, E>;
+        
+        /// On error, the slice is left part shuffled.
+        fn try_shuffle<E, R: RawRng<E>+?Sized>(&mut self, rng: &mut R) -> Result<(), E>;
+        
+        /// A uniformly chosen element; `None` if empty.
+        fn choose<R: Rng+?Sized>(&self, rng: &mut R) -> Option<&Self::Item> {
+            self.try_choose::<Infallible, R>(rng).unwrap_or_else(|e| match e {})
+        }
+        
+        /// A uniformly chosen element; `None` if empty.
+        fn choose_mut<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<&mut Self::Item> {
+            self.try_choose_mut::<Infallible, R>(rng).unwrap_or_else(|e| match e {})
+        }
+        
+        /// Fisher-Yates: every permutation equally likely.
+        fn shuffle<R: Rng+?Sized>(&mut self, rng: &mut R) {
+            self.try_shuffle::<Infallible, R>(rng).unwrap_or_else(|e| match e {})
+        }
+        
+        fn try_choose_weighted<E, R, X, F>(&self, rng: &mut R, weight: F)
+            -> Result<&Self::Item, ChooseError<E>>
+            where R: RawRng<E>+?Sized, X: Weight, F: Fn(&Self::Item) -> X;
+        
+        /// An element chosen with probability its weight over the total.
+        fn choose_weighted<R: Rng+?Sized, X: Weight, F: Fn(&Self::Item) -> X>(&self, rng: &mut R,
+            weight: F) -> Result<&Self::Item, WeightedError>
+        {
+            self.try_choose_weighted::<Infallible, R, X, F>(rng, weight)
+                .map_err(ChooseError::weights)
+        }
+    }
+    
+    impl<T> SliceRandom for [T] {
+        type Item = T;
+        
+        fn try_choose<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<Option<&T>, E> {
+            super::choose(self, &mut Raw(rng, PhantomData))
+        }
+        
+        fn try_choose_mut<E, R: RawRng<E>+?Sized>(&mut self, rng: &mut R)
+            -> Result<Option<&mut T>, E>
+        {
+            super::choose_mut(self, &mut Raw(rng, PhantomData))
+        }
+        
+        fn try_shuffle<E, R: RawRng<E>+?Sized>(&mut self, rng: &mut R) -> Result<(), E> {
+            super::shuffle(self, &mut Raw(rng, PhantomData))
+        }
+        
+        fn try_choose_weighted<E, R, X, F>(&self, rng: &mut R, weight: F)
+            -> Result<&T, ChooseError<E>>
+            where R: RawRng<E>+?Sized, X: Weight, F: Fn(&T) -> X
+        {
+            super::choose_weighted(self, weight, &mut Raw(rng, PhantomData))
+        }
+    }
+    
+    /// Dyn-compatible, as over extends_Rng, for plain generators.
+    pub trait DynSliceRandom<T> {
+        fn choose(&self, rng: &mut dyn Rng) -> Option<&T>;
+        
+        fn choose_mut(&mut self, rng: &mut dyn Rng) -> Option<&mut T>;
+        
+        fn shuffle(&mut self, rng: &mut dyn Rng);
+    }
+    
+    impl<T, S: AsRef<[T]>+AsMut<[T]>+?Sized> DynSliceRandom<T> for S {
+        fn choose(&self, rng: &mut dyn Rng) -> Option<&T> {
+            SliceRandom::choose(self.as_ref(), rng)
+        }
+        
+        fn choose_mut(&mut self, rng: &mut dyn Rng) -> Option<&mut T> {
+            SliceRandom::choose_mut(self.as_mut(), rng)
+        }
+        
+        fn shuffle(&mut self, rng: &mut dyn Rng) {
+            SliceRandom::shuffle(self.as_mut(), rng)
+        }
+    }
+    
+    /// And for fallible ones: `E` a parameter of the trait, as one of the
+    /// methods would make it no longer dyn-compatible, and a trait of its
+    /// own, as the plain methods would never fix it.
+    pub trait DynTrySliceRandom<T, E> {
+        fn try_choose(&self, rng: &mut dyn RawRng<E>) -> Result<Option<&T>, E>;
+        
+        fn try_choose_mut(&mut self, rng: &mut dyn RawRng<E>) -> Result<Option<&mut T>, E>;
+        
+        fn try_shuffle(&mut self, rng: &mut dyn RawRng<E>) -> Result<(), E>;
+    }
+    
+    impl<T, E, S: AsRef<[T]>+AsMut<[T]>+?Sized> DynTrySliceRandom<T, E> for S {
+        fn try_choose(&self, rng: &mut dyn RawRng<E>) -> Result<Option<&T>, E> {
+            SliceRandom::try_choose(self.as_ref(), rng)
+        }
+        
+        fn try_choose_mut(&mut self, rng: &mut dyn RawRng<E>) -> Result<Option<&mut T>, E> {
+            SliceRandom::try_choose_mut(self.as_mut(), rng)
+        }
+        
+        fn try_shuffle(&mut self, rng: &mut dyn RawRng<E>) -> Result<(), E> {
+            SliceRandom::try_shuffle(self.as_mut(), rng)
+        }
+    }
+}
+
+// ——— consumers ———
+
+/// A generic consumer must say `?Sized` to take a generator object; this
+/// one forgot.
+#[cfg(fail_sized_rng)]
+fn first_pick<R: gen_range::raw_infallible::Rng>(xs: &[u32], rng: &mut R) -> Option<u32> {
+    use on_raw_infallible::SliceRandom;
+    xs.choose(rng).cloned()
+}
+
+#[cfg(fail_sized_rng)]
+fn pick_through_object(rng: &mut dyn gen_range::raw_infallible::Rng) -> Option<u32> {
+    first_pick(&[1, 2, 3], rng)
+}
+
+/// The generic trait has generic methods, so no object of it exists.
+#[cfg(fail_slice_object)]
+fn deal(deck: &mut dyn on_extends_rng::SliceRandom<Item = u32>) {
+    let _ = deck;
+}
+
+// ——— usage ———
+
+/// What `main` draws: 100 choices from 0..10, 100 picks of 10 counters
+/// by `choose_mut`, then 0..52 shuffled.
+type Draws = (Vec<u32>, [u32; 10], Vec<u32>);
+
+fn main() {
+    let xs: Vec<u32> = (0..10).collect();
+    let deck: Vec<u32> = (0..52).collect();
+    let draws: [Draws; 3] = [
+        {
+            use self::gen_range::extends_rng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_rng::SliceRandom;
+            let mut t = TestRng(SplitMix(1));
+            let (mut marks, mut d) = ([0; 10], deck.clone());
+            let choices = (0..100).map(|_| *xs.choose(&mut t).unwrap()).collect();
+            (0..100).for_each(|_| *marks.choose_mut(&mut t).unwrap() += 1);
+            d.shuffle(&mut t);
+            (choices, marks, d)
+        },
+        {
+            use self::gen_range::extends_cryptorng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_cryptorng::SliceRandom;
+            let mut t = TestRng(SplitMix(1));
+            let (mut marks, mut d) = ([0; 10], deck.clone());
+            let choices = (0..100).map(|_| *xs.choose(&mut t).unwrap()).collect();
+            (0..100).for_each(|_| *marks.choose_mut(&mut t).unwrap() += 1);
+            d.shuffle(&mut t);
+            (choices, marks, d)
+        },
+        {
+            use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+            use self::on_raw_infallible::SliceRandom;
+            let mut t = TestRng(SplitMix(1));
+            let (mut marks, mut d) = ([0; 10], deck.clone());
+            let choices = (0..100).map(|_| *xs.choose(&mut t).unwrap()).collect();
+            (0..100).for_each(|_| *marks.choose_mut(&mut t).unwrap() += 1);
+            d.shuffle(&mut t);
+            (choices, marks, d)
+        },
+    ];
+    assert!(draws[0] == draws[1] && draws[0] == draws[2]);
+    assert_eq!(draws[0].1.iter().sum::<u32>(), 100);
+    let mut sorted = draws[0].2.clone();
+    sorted.sort();
+    assert_eq!(sorted, deck);
+    println!("choose, choose_mut, shuffle: the same over each design, first {}, deck from {:?}",
+        draws[0].0[0], &draws[0].2[..4]);
+    {
+        // The dyn-compatible forms draw as the generic ones do, through
+        // either object, `&mut dyn CryptoRng` upcast to `&mut dyn Rng`
+        use self::gen_range::extends_rng::test_rngs::SplitMix;
+        use self::gen_range::extends_rng::{CryptoRng, Rng};
+        use self::gen_range::FallibleCRng;
+        use self::on_extends_rng::DynSliceRandom;
+        let mut f = FallibleCRng(SplitMix(1));
+        let r: &mut dyn Rng = &mut f as &mut dyn CryptoRng;
+        let (mut marks, mut d) = ([0; 10], deck.clone());
+        let choices = (0..100).map(|_| *xs.choose(r).unwrap()).collect();
+        (0..100).for_each(|_| *marks.choose_mut(r).unwrap() += 1);
+        d.shuffle(r);
+        assert!((choices, marks, d) == draws[0]);
+        let mut f = FallibleCRng(SplitMix(1));
+        let c: &mut dyn CryptoRng = &mut f;
+        let (mut marks, mut d) = ([0; 10], deck.clone());
+        let choices = (0..100).map(|_| *xs.try_choose(c).unwrap().unwrap()).collect();
+        (0..100).for_each(|_| *marks.try_choose_mut(c).unwrap().unwrap() += 1);
+        d.try_shuffle(c).unwrap();
+        assert!((choices, marks, d) == draws[0]);
+    }
+    {
+        // Here `Rng` extends `CryptoRng`, so the upcast is the other way
+        use self::gen_range::extends_cryptorng::test_rngs::{SplitMix, TestRng};
+        use self::gen_range::extends_cryptorng::{CryptoRng, Rng};
+        use self::on_extends_cryptorng::DynSliceRandom;
+        let mut t = TestRng(SplitMix(1));
+        let r: &mut dyn Rng = &mut t;
+        let (mut marks, mut d) = ([0; 10], deck.clone());
+        let choices = (0..100).map(|_| *xs.choose(r).unwrap()).collect();
+        (0..100).for_each(|_| *marks.choose_mut(r).unwrap() += 1);
+        d.shuffle(r);
+        assert!((choices, marks, d) == draws[0]);
+        let mut t = TestRng(SplitMix(1));
+        let c: &mut dyn CryptoRng = &mut t as &mut dyn Rng;
+        let (mut marks, mut d) = ([0; 10], deck.clone());
+        let choices = (0..100).map(|_| *xs.try_choose(c).unwrap().unwrap()).collect();
+        (0..100).for_each(|_| *marks.try_choose_mut(c).unwrap().unwrap() += 1);
+        d.try_shuffle(c).unwrap();
+        assert!((choices, marks, d) == draws[0]);
+    }
+    {
+        use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestFCRng, TestRng};
+        use self::gen_range::raw_infallible::{Error, RawRng, Rng};
+        use self::on_raw_infallible::{DynSliceRandom, DynTrySliceRandom};
+        let mut t = TestRng(SplitMix(1));
+        let r: &mut dyn Rng = &mut t;
+        let (mut marks, mut d) = ([0; 10], deck.clone());
+        let choices = (0..100).map(|_| *xs.choose(r).unwrap()).collect();
+        (0..100).for_each(|_| *marks.choose_mut(r).unwrap() += 1);
+        d.shuffle(r);
+        assert!((choices, marks, d) == draws[0]);
+        let mut f = TestFCRng(SplitMix(1));
+        let c: &mut dyn RawRng<Error> = &mut f;
+        let (mut marks, mut d) = ([0; 10], deck.clone());
+        let choices = (0..100).map(|_| *xs.try_choose(c).unwrap().unwrap()).collect();
+        (0..100).for_each(|_| *marks.try_choose_mut(c).unwrap().unwrap() += 1);
+        d.try_shuffle(c).unwrap();
+        assert!((choices, marks, d) == draws[0]);
+        println!("DynSliceRandom through &mut dyn Rng, &mut dyn CryptoRng, &mut dyn RawRng<Error>: \
+            the same draws");
+    }
+    {
+        // Objects of slice-like containers: the slice itself is unsized, so
+        // an object of it needs one
+        use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+        use self::on_raw_infallible::DynSliceRandom;
+        let mut t = TestRng(SplitMix(2));
+        let boxed: Box<[u32]> = vec![20, 21, 22, 23].into_boxed_slice();
+        let mut decks: Vec<Box<dyn DynSliceRandom<u32>>> =
+            vec![Box::new(vec![0u32, 1, 2, 3]), Box::new([10u32, 11, 12, 13]), Box::new(boxed)];
+        for (k, d) in decks.iter_mut().enumerate() {
+            d.shuffle(&mut t);
+            *d.choose_mut(&mut t).unwrap() += 100;
+            let x = *d.choose(&mut t).unwrap();
+            assert!(x / 10 % 10 == k as u32 && x % 10 < 4);
+        }
+        println!("Vec<Box<dyn DynSliceRandom<u32>>>: a Vec, an array and a Box<[u32]>, shuffled");
+    }
+    
+    // Failing part way: an error, and the slice left a permutation
+    {
+        use self::gen_range::extends_rng::test_rngs::FailAfter;
+        use self::gen_range::extends_rng::ErrorKind;
+        use self::gen_range::FallibleCRng;
+        use self::on_extends_rng::SliceRandom;
+        let mut d = deck.clone();
+        let err = d.try_shuffle(&mut FallibleCRng(FailAfter::new(3))).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Unavailable);
+        d.sort();
+        assert_eq!(d, deck);
+    }
+    {
+        use self::gen_range::extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use self::gen_range::extends_cryptorng::ErrorKind;
+        use self::on_extends_cryptorng::SliceRandom;
+        let mut d = deck.clone();
+        let err = d.try_shuffle(&mut TestCRng(FailAfter::new(3))).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Unavailable);
+        d.sort();
+        assert_eq!(d, deck);
+    }
+    {
+        use self::gen_range::raw_infallible::test_rngs::{FailAfter, TestFCRng};
+        use self::gen_range::raw_infallible::ErrorKind;
+        use self::on_raw_infallible::SliceRandom;
+        let mut d = deck.clone();
+        let err = d.try_shuffle(&mut TestFCRng(FailAfter::new(3))).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Unavailable);
+        d.sort();
+        assert_eq!(d, deck);
+        // An empty slice draws nothing, so even a spent source gives `None`
+        let empty: [u32; 0] = [];
+        assert_eq!(empty.try_choose(&mut TestFCRng(FailAfter::new(0))), Ok(None));
+        println!("try_shuffle over a failing source: Unavailable, a permutation left; \
+            try_choose of empty: Ok(None)");
+    }
+    {
+        // Every permutation of four, evenly
+        use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+        use self::on_raw_infallible::SliceRandom;
+        let mut t = TestRng(SplitMix(3));
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..24_000 {
+            let mut d = [0u8, 1, 2, 3];
+            d.shuffle(&mut t);
+            *counts.entry(d).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 24);
+        assert!(counts.values().all(|&n| n > 850 && n < 1150));
+        println!("shuffle of 4, 24000 times: all 24 permutations, {} to {} each",
+            counts.values().min().unwrap(), counts.values().max().unwrap());
+    }
+    
+    // Weighted: 100 choices by integer weights, then 100 by float ones
+    let weighted: [(Vec<u32>, Vec<u32>); 3] = [
+        {
+            use self::gen_range::extends_rng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_rng::SliceRandom;
+            let mut t = TestRng(SplitMix(4));
+            let ints = (0..100).map(|_| *xs.choose_weighted(&mut t, |&x| x + 1).unwrap()).collect();
+            let sqrt = |&x: &u32| (x as f64).sqrt();
+            let floats = (0..100).map(|_| *xs.choose_weighted(&mut t, sqrt).unwrap()).collect();
+            (ints, floats)
+        },
+        {
+            use self::gen_range::extends_cryptorng::test_rngs::{SplitMix, TestRng};
+            use self::on_extends_cryptorng::SliceRandom;
+            let mut t = TestRng(SplitMix(4));
+            let ints = (0..100).map(|_| *xs.choose_weighted(&mut t, |&x| x + 1).unwrap()).collect();
+            let sqrt = |&x: &u32| (x as f64).sqrt();
+            let floats = (0..100).map(|_| *xs.choose_weighted(&mut t, sqrt).unwrap()).collect();
+            (ints, floats)
+        },
+        {
+            use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+            use self::on_raw_infallible::SliceRandom;
+            let mut t = TestRng(SplitMix(4));
+            let ints = (0..100).map(|_| *xs.choose_weighted(&mut t, |&x| x + 1).unwrap()).collect();
+            let sqrt = |&x: &u32| (x as f64).sqrt();
+            let floats = (0..100).map(|_| *xs.choose_weighted(&mut t, sqrt).unwrap()).collect();
+            (ints, floats)
+        },
+    ];
+    assert!(weighted[0] == weighted[1] && weighted[0] == weighted[2]);
+    // sqrt(0) is 0: never chosen
+    assert!(!weighted[0].1.contains(&0));
+    println!("choose_weighted: the same over each design, first {} and {}",
+        weighted[0].0[0], weighted[0].1[0]);
+    {
+        // In proportion to the weights, and never a zero weight
+        use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+        use self::on_raw_infallible::SliceRandom;
+        let mut t = TestRng(SplitMix(5));
+        let ys = [0usize, 1, 2, 3, 4];
+        let weights = [1u64, 2, 0, 3, 4];
+        let mut counts = [0; 5];
+        for _ in 0..100_000 {
+            counts[*ys.choose_weighted(&mut t, |&y| weights[y]).unwrap()] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        assert!(ys.iter().all(|&y| (counts[y] as i64 - weights[y] as i64 * 10_000).abs() < 600));
+        println!("choose_weighted by 1, 2, 0, 3, 4, 100000 times: {:?}", counts);
+    }
+    {
+        // The weights' errors
+        use self::gen_range::raw_infallible::test_rngs::{SplitMix, TestRng};
+        use self::on_raw_infallible::SliceRandom;
+        use self::WeightedError::*;
+        let mut t = TestRng(SplitMix(6));
+        let empty: [f64; 0] = [];
+        assert_eq!(empty.choose_weighted(&mut t, |&w| w), Err(NoItem));
+        assert_eq!([0u32, 0].choose_weighted(&mut t, |&w| w), Err(AllWeightsZero));
+        assert_eq!([0.0, -0.0].choose_weighted(&mut t, |&w| w), Err(AllWeightsZero));
+        assert_eq!([2i32, -1].choose_weighted(&mut t, |&w| w), Err(InvalidWeight));
+        for &bad in [-0.5, f64::NAN, f64::INFINITY].iter() {
+            assert_eq!([1.0, bad].choose_weighted(&mut t, |&w| w), Err(InvalidWeight));
+        }
+        assert_eq!([u32::MAX, 1].choose_weighted(&mut t, |&w| w), Err(TooMany));
+        assert_eq!([i64::MAX, 1].choose_weighted(&mut t, |&w| w), Err(TooMany));
+        assert_eq!([f64::MAX; 2].choose_weighted(&mut t, |&w| w), Err(TooMany));
+        println!("choose_weighted errors: NoItem, AllWeightsZero, InvalidWeight (negative, NaN, \
+            infinite), TooMany (u32, i64, f64 overflowing)");
+    }
+    {
+        // The top of the range: from the all-ones word, the last nonzero
+        // weight, a float point staying below even an inexact total
+        use self::gen_range::raw_infallible::test_rngs::ConstRng;
+        use self::on_raw_infallible::SliceRandom;
+        let mut c = ConstRng::new(!0);
+        let ys = [0, 1, 2];
+        assert_eq!(ys.choose_weighted(&mut c, |&y| [1u32, 2, 0][y]), Ok(&1));
+        assert_eq!(ys.choose_weighted(&mut c, |&y| [1.0, 2.0, 0.0][y]), Ok(&1));
+        assert_eq!(xs.choose_weighted(&mut c, |_| 0.1), Ok(&9));
+        assert_eq!(xs.choose_weighted(&mut ConstRng::new(0), |_| 0.1), Ok(&0));
+        println!("choose_weighted from the all-ones word: the last nonzero weight, by u32 or f64");
+    }
+    
+    // The weights are checked first: over a spent source, their error;
+    // with good weights, the source's
+    {
+        use self::gen_range::extends_rng::test_rngs::FailAfter;
+        use self::gen_range::extends_rng::ErrorKind;
+        use self::gen_range::FallibleCRng;
+        use self::on_extends_rng::SliceRandom;
+        let mut f = FallibleCRng(FailAfter::new(0));
+        let err = [0u32, 0].try_choose_weighted(&mut f, |&w| w).unwrap_err();
+        assert!(err == ChooseError::Weights(WeightedError::AllWeightsZero));
+        match [1u32, 0].try_choose_weighted(&mut f, |&w| w) {
+            Err(ChooseError::Rng(e)) => assert_eq!(e.kind, ErrorKind::Unavailable),
+            r => panic!("not the source's error: {:?}", r),
+        }
+    }
+    {
+        use self::gen_range::extends_cryptorng::test_rngs::{FailAfter, TestCRng};
+        use self::gen_range::extends_cryptorng::ErrorKind;
+        use self::on_extends_cryptorng::SliceRandom;
+        let mut f = TestCRng(FailAfter::new(0));
+        let err = [0u32, 0].try_choose_weighted(&mut f, |&w| w).unwrap_err();
+        assert!(err == ChooseError::Weights(WeightedError::AllWeightsZero));
+        match [1u32, 0].try_choose_weighted(&mut f, |&w| w) {
+            Err(ChooseError::Rng(e)) => assert_eq!(e.kind, ErrorKind::Unavailable),
+            r => panic!("not the source's error: {:?}", r),
+        }
+    }
+    {
+        use self::gen_range::raw_infallible::test_rngs::{FailAfter, TestFCRng};
+        use self::gen_range::raw_infallible::ErrorKind;
+        use self::on_raw_infallible::SliceRandom;
+        let mut f = TestFCRng(FailAfter::new(0));
+        let err = [0u32, 0].try_choose_weighted(&mut f, |&w| w).unwrap_err();
+        assert!(err == ChooseError::Weights(WeightedError::AllWeightsZero));
+        match [1u32, 0].try_choose_weighted(&mut f, |&w| w) {
+            Err(ChooseError::Rng(e)) => assert_eq!(e.kind, ErrorKind::Unavailable),
+            r => panic!("not the source's error: {:?}", r),
+        }
+        println!("try_choose_weighted over a spent source: Weights(AllWeightsZero), or \
+            Rng(Unavailable) if the weights are good");
+    }
+}
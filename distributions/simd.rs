@@ -0,0 +1,310 @@
+//! SIMD sampling: `Standard` and `Uniform` for `u32x8` and `f32x8`
+//! 
+//! With the `simd_support` feature (nightly, `portable_simd`), backends may
+//! implement `SimdRng::next_u32x8` to produce eight lanes at once;
+//! `Xoshiro128x8` runs eight xoshiro128++ generators side by side, lane `i`
+//! producing exactly the stream of `Xoshiro128(seeds[i])`. Other generators
+//! get a default which fills the lanes from `next_u32`.
+//! 
+//! On top of that:
+//! 
+//! *   `Standard`: `u32x8` (raw lanes) and `f32x8` in `[0, 1)` (24 bits per
+//!     lane, as the scalar conversion)
+//! *   `UniformU32x8`: `[low, high)` per lane by widening multiply; rejected
+//!     lanes are resampled from fresh vectors, accepted ones kept
+//! *   `UniformF32x8`: `[low, high)`, using `UniformFloat<f32>`'s parameters
+//!     (from `uniform_float.rs`), so lane results match the scalar version
+//!     sample for sample, including never returning `high`
+//! 
+//! Run with:
+//! 
+//!     rustc +nightly --cfg 'feature="simd_support"' -O simd.rs && ./simd
+//! 
+//! and see `bench/simd_monte_carlo.rs` for a comparison against scalar loops.
+//! 
+//! Thoughts: only generators whose state vectorises gain anything; for the
+//! rest `next_u32x8` is a loop, so the distributions gain only from
+//! vectorising the conversions. Per-lane rejection keeps `UniformU32x8`
+//! unbiased but makes its output depend on which lanes were rejected, so it
+//! does not match the scalar stream the way the float version does.
+
+#![cfg_attr(feature = "simd_support", feature(portable_simd))]
+#![cfg_attr(not(feature = "simd_support"), allow(dead_code))]
+
+#[path = "uniform_float.rs"]
+#[allow(dead_code)]
+mod uniform_float;
+
+pub use self::uniform_float::{Rng, UniformFloat};
+
+// ——— backends ———
+
+/// xoshiro128++ (scalar).
+#[derive(Debug, Clone)]
+pub struct Xoshiro128([u32; 4]);
+
+impl Xoshiro128 {
+    pub fn new(seed: [u32; 4]) -> Self {
+        assert!(seed != [0; 4], "xoshiro128++: all-zero seed");
+        Xoshiro128(seed)
+    }
+}
+
+impl Rng for Xoshiro128 {
+    fn next_u32(&mut self) -> u32 {
+        let s = &mut self.0;
+        let result = s[0].wrapping_add(s[3]).rotate_left(7).wrapping_add(s[0]);
+        let t = s[1] << 9;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(11);
+        result
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let lo = self.next_u32() as u64;
+        (self.next_u32() as u64) << 32 | lo
+    }
+}
+
+#[cfg(feature = "simd_support")]
+pub use self::simd::*;
+
+#[cfg(feature = "simd_support")]
+mod simd {
+    use super::{Rng, UniformFloat, Xoshiro128};
+    use std::simd::cmp::SimdPartialOrd;
+    use std::simd::num::SimdUint;
+    use std::simd::{f32x8, u32x8, u64x8, Mask, Select};
+    
+    fn rotl(x: u32x8, k: u32) -> u32x8 {
+        x << u32x8::splat(k) | x >> u32x8::splat(32 - k)
+    }
+    
+    /// A generator able to produce eight `u32` lanes at once.
+    pub trait SimdRng: Rng {
+        fn next_u32x8(&mut self) -> u32x8 {
+            let mut lanes = [0u32; 8];
+            for x in lanes.iter_mut() {
+                *x = self.next_u32();
+            }
+            u32x8::from_array(lanes)
+        }
+    }
+    
+    impl SimdRng for Xoshiro128 {}
+    
+    /// Eight xoshiro128++ generators in lock-step.
+    #[derive(Debug, Clone)]
+    pub struct Xoshiro128x8([u32x8; 4]);
+    
+    impl Xoshiro128x8 {
+        /// Lane `i` is seeded with `seeds[i]`.
+        pub fn new(seeds: [[u32; 4]; 8]) -> Self {
+            assert!(seeds.iter().all(|s| *s != [0; 4]), "xoshiro128++: all-zero seed");
+            let word = |j: usize| u32x8::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(|i| seeds[i][j]));
+            Xoshiro128x8([word(0), word(1), word(2), word(3)])
+        }
+    }
+    
+    impl Rng for Xoshiro128x8 {
+        /// Lane 0 only; prefer `next_u32x8`.
+        fn next_u32(&mut self) -> u32 {
+            self.next_u32x8()[0]
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            let x = self.next_u32x8();
+            (x[1] as u64) << 32 | x[0] as u64
+        }
+    }
+    
+    impl SimdRng for Xoshiro128x8 {
+        fn next_u32x8(&mut self) -> u32x8 {
+            let s = &mut self.0;
+            let result = rotl(s[0] + s[3], 7) + s[0];
+            let t = s[1] << u32x8::splat(9);
+            s[2] ^= s[0];
+            s[3] ^= s[1];
+            s[1] ^= s[2];
+            s[0] ^= s[3];
+            s[2] ^= t;
+            s[3] = rotl(s[3], 11);
+            result
+        }
+    }
+    
+    /// The standard distribution, vectorised.
+    pub struct Standard;
+    
+    impl Standard {
+        pub fn sample_u32x8<R: SimdRng+?Sized>(&self, rng: &mut R) -> u32x8 {
+            rng.next_u32x8()
+        }
+        
+        /// Uniform over `[0, 1)`, in steps of 2^-24.
+        pub fn sample_f32x8<R: SimdRng+?Sized>(&self, rng: &mut R) -> f32x8 {
+            let x = rng.next_u32x8() >> u32x8::splat(8);
+            x.cast::<f32>() * f32x8::splat(1.0 / (1 << 24) as f32)
+        }
+    }
+    
+    /// Uniform over `[low, high)` in each lane.
+    pub struct UniformU32x8 {
+        low: u32,
+        range: u32,
+        /// Values of the low half of the product below this are rejected.
+        threshold: u32,
+    }
+    
+    impl UniformU32x8 {
+        pub fn new(low: u32, high: u32) -> Self {
+            assert!(low < high, "UniformU32x8::new: empty range");
+            let range = high - low;
+            UniformU32x8 { low, range, threshold: range.wrapping_neg() % range }
+        }
+        
+        pub fn sample<R: SimdRng+?Sized>(&self, rng: &mut R) -> u32x8 {
+            let mut result = u32x8::splat(0);
+            let mut done = Mask::<i32, 8>::splat(false);
+            loop {
+                let m = rng.next_u32x8().cast::<u64>() * u64x8::splat(self.range as u64);
+                let accept = m.cast::<u32>().simd_ge(u32x8::splat(self.threshold)) & !done;
+                let value = (m >> u64x8::splat(32)).cast::<u32>() + u32x8::splat(self.low);
+                result = accept.select(value, result);
+                done |= accept;
+                if done.all() {
+                    return result;
+                }
+            }
+        }
+    }
+    
+    /// Uniform over `[low, high)` in each lane; see `UniformFloat`.
+    pub struct UniformF32x8(UniformFloat<f32>);
+    
+    impl UniformF32x8 {
+        pub fn new(low: f32, high: f32) -> Self {
+            UniformF32x8(UniformFloat::<f32>::new(low, high))
+        }
+        
+        pub fn sample<R: SimdRng+?Sized>(&self, rng: &mut R) -> f32x8 {
+            let v = Standard.sample_f32x8(rng);
+            let UniformFloat { offset, scale, factor } = self.0;
+            (v * f32x8::splat(scale) + f32x8::splat(offset)) * f32x8::splat(factor)
+        }
+    }
+}
+
+// ——— usage ———
+
+/// Distinct, non-zero seeds for eight lanes.
+pub fn lane_seeds(seed: u64) -> [[u32; 4]; 8] {
+    let mut x = seed;
+    let mut next = || {
+        // SplitMix64
+        x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = x;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    [(); 8].map(|_| {
+        let (a, b) = (next(), next());
+        [a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32]
+    })
+}
+
+#[cfg(not(feature = "simd_support"))]
+fn main() {
+    println!("built without the simd_support feature: nothing to test");
+}
+
+#[cfg(feature = "simd_support")]
+fn main() {
+    let seeds = lane_seeds(0x5EED);
+    let mut scalar: Vec<Xoshiro128> = seeds.iter().map(|&s| Xoshiro128::new(s)).collect();
+    let mut vector = Xoshiro128x8::new(seeds);
+    let mut looped = Xoshiro128::new(seeds[0]);
+    {
+        // Lane i is the stream of scalar generator i
+        for _ in 0..1000 {
+            let x = Standard.sample_u32x8(&mut vector);
+            for (i, rng) in scalar.iter_mut().enumerate() {
+                assert_eq!(x[i], rng.next_u32());
+            }
+        }
+        // The default for scalar generators fills lanes in order
+        let mut copy = looped.clone();
+        let x = looped.next_u32x8();
+        assert!((0..8).all(|i| x[i] == copy.next_u32()));
+        println!("Xoshiro128x8: lanes match scalar streams");
+    }
+    {
+        // Floats: in [0, 1), and equal to the scalar conversion per lane
+        for _ in 0..1000 {
+            let x = Standard.sample_f32x8(&mut vector);
+            for (i, rng) in scalar.iter_mut().enumerate() {
+                assert_eq!(x[i], (rng.next_u32() >> 8) as f32 / (1 << 24) as f32);
+                assert!(0.0 <= x[i] && x[i] < 1.0);
+            }
+        }
+        println!("Standard f32x8: matches scalar");
+    }
+    {
+        // UniformF32x8 matches UniformFloat sample for sample, including on
+        // ranges where rounding would otherwise reach `high`
+        let one_up = f32::from_bits(1.0f32.to_bits() + 1);
+        for &(low, high) in [(0.0, 1.0), (-1.0, 1.0), (1.0, one_up), (-f32::MAX, f32::MAX),
+            (-0.0, f32::from_bits(1)), (1e30, 3e30)].iter()
+        {
+            let (d, s) = (UniformF32x8::new(low, high), UniformFloat::<f32>::new(low, high));
+            for _ in 0..1000 {
+                let x = d.sample(&mut vector);
+                for (i, rng) in scalar.iter_mut().enumerate() {
+                    assert_eq!(x[i], s.sample(rng));
+                    assert!(low <= x[i] && x[i] < high);
+                }
+            }
+        }
+        println!("UniformF32x8: matches UniformFloat");
+    }
+    {
+        // UniformU32x8: in range, every value hit, roughly uniform; and no
+        // bias from the range not dividing 2^32 (threshold rejection)
+        for &(low, high) in [(0, 1), (0, 3), (10, 17), (0, u32::MAX), (u32::MAX - 1, u32::MAX)].iter() {
+            let d = UniformU32x8::new(low, high);
+            for _ in 0..1000 {
+                let x = d.sample(&mut vector);
+                assert!((0..8).all(|i| low <= x[i] && x[i] < high));
+            }
+        }
+        let d = UniformU32x8::new(0, 6);
+        let mut counts = [0u64; 6];
+        for _ in 0..60_000 {
+            let x = d.sample(&mut vector);
+            for i in 0..8 {
+                counts[x[i] as usize] += 1;
+            }
+        }
+        let expected = 80_000.0;
+        let chi2: f64 = counts.iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum();
+        assert!(chi2 < 5.0 + 5.0 * 10f64.sqrt(), "counts {:?}", counts);
+        
+        // A range of 3 * 2^30 rejects a quarter of all inputs: lanes must
+        // still all be filled from accepted values
+        let d = UniformU32x8::new(0, 3 << 30);
+        let mut top = [0u64; 3];
+        for _ in 0..30_000 {
+            let x = d.sample(&mut vector);
+            for i in 0..8 {
+                top[(x[i] >> 30) as usize] += 1;
+            }
+        }
+        assert!(top.iter().all(|&c| (c as f64 - 80_000.0).abs() < 2_000.0), "{:?}", top);
+        println!("UniformU32x8: ok");
+    }
+}
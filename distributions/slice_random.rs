@@ -0,0 +1,226 @@
+//! `SliceRandom`: choosing and shuffling from the slice's side
+//! 
+//! Two placements of the same operations, sharing one implementation:
+//! 
+//! *   rng-side, `RngExt` (as in rand 0.4): `rng.choose(&xs)`,
+//!     `rng.choose_mut(&mut xs)`, `rng.shuffle(&mut xs)`
+//! *   slice-side, `SliceRandom`: `xs.choose(&mut rng)`,
+//!     `xs.choose_mut(&mut rng)`, `xs.shuffle(&mut rng)`; and `VecRandom` for
+//!     `v.swap_remove_random(&mut rng)`, which needs a `Vec` since removing
+//!     changes the length
+//! 
+//! Indices are sampled as `u64` (see `pointer_sized.rs`), so results do not
+//! depend on the target's pointer width. Both placements consume the
+//! generator identically, and work through `&mut dyn Rng`; `main` checks
+//! this, with uniformity of `choose`, `shuffle` and `swap_remove_random`, and
+//! empty slices.
+//! 
+//! Run with:
+//! 
+//!     rustc -O slice_random.rs && ./slice_random
+//! 
+//! and see `bench/slice_random.rs` for the cost of each placement.
+//! 
+//! Thoughts: slice-side reads better in chains and with long expressions for
+//! the slice (`self.candidates[k..].choose(rng)`), and keeps `Rng` free of
+//! collection methods; rng-side puts the generator first, where it is easier
+//! to spot that code is random, and needs no extra import. For code size
+//! neither wins: both are generic over the element type and the generator,
+//! so each `(T, R)` pair gets its own copy either way. Counting definitions
+//! in the unoptimised LLVM IR of a program shuffling three element types with
+//! two generators gave six `shuffle` instances for each placement, plus six
+//! thin forwarding wrappers for the rng-side trait (gone with `-O`). Keeping
+//! index generation generic over `R` only (as `gen_index` is) is what limits
+//! growth.
+
+#[path = "uniform_int.rs"]
+#[allow(dead_code)]
+mod uniform_int;
+
+#[path = "../verify/chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+pub use self::uniform_int::{Rng, SplitMix};
+use self::chi_squared::{chi_squared, chi_squared_limit};
+use self::uniform_int::UniformInt;
+
+// ——— traits ———
+
+/// Rng-side.
+pub trait RngExt: Rng {
+    fn choose<'a, T>(&mut self, xs: &'a [T]) -> Option<&'a T> {
+        xs.choose(self)
+    }
+    
+    fn choose_mut<'a, T>(&mut self, xs: &'a mut [T]) -> Option<&'a mut T> {
+        xs.choose_mut(self)
+    }
+    
+    fn shuffle<T>(&mut self, xs: &mut [T]) {
+        xs.shuffle(self)
+    }
+}
+
+impl<R: Rng+?Sized> RngExt for R {}
+
+/// Slice-side.
+pub trait SliceRandom {
+    type Item;
+    
+    /// A uniformly chosen element; `None` if empty.
+    fn choose<R: Rng+?Sized>(&self, rng: &mut R) -> Option<&Self::Item>;
+    
+    /// A uniformly chosen element; `None` if empty.
+    fn choose_mut<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<&mut Self::Item>;
+    
+    /// Fisher-Yates: every permutation equally likely.
+    fn shuffle<R: Rng+?Sized>(&mut self, rng: &mut R);
+}
+
+/// Slice-side, for operations changing the length.
+pub trait VecRandom {
+    type Item;
+    
+    /// Remove a uniformly chosen element, moving the last into its place;
+    /// `None` if empty.
+    fn swap_remove_random<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<Self::Item>;
+}
+
+// ——— impls ———
+
+/// Uniform in `[0, n)`, sampled as `u64`; `n` must be positive.
+fn gen_index<R: Rng+?Sized>(rng: &mut R, n: usize) -> usize {
+    UniformInt::<u64, _>::new(0, n as u64).sample(rng) as usize
+}
+
+impl<T> SliceRandom for [T] {
+    type Item = T;
+    
+    fn choose<R: Rng+?Sized>(&self, rng: &mut R) -> Option<&T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(&self[gen_index(rng, self.len())])
+        }
+    }
+    
+    fn choose_mut<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<&mut T> {
+        if self.is_empty() {
+            None
+        } else {
+            let i = gen_index(rng, self.len());
+            Some(&mut self[i])
+        }
+    }
+    
+    fn shuffle<R: Rng+?Sized>(&mut self, rng: &mut R) {
+        for i in (1..self.len()).rev() {
+            self.swap(i, gen_index(rng, i + 1));
+        }
+    }
+}
+
+impl<T> VecRandom for Vec<T> {
+    type Item = T;
+    
+    fn swap_remove_random<R: Rng+?Sized>(&mut self, rng: &mut R) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            let i = gen_index(rng, self.len());
+            Some(self.swap_remove(i))
+        }
+    }
+}
+
+// ——— usage ———
+
+fn main() {
+    let seed = SplitMix(0x5EED);
+    {
+        // The two placements side by side: same results, same stream
+        let words = ["apple", "banana", "cherry", "damson", "elder"];
+        let (mut a, mut b) = (seed.clone(), seed.clone());
+        for _ in 0..100 {
+            assert_eq!(a.choose(&words), words.choose(&mut b));
+        }
+        let mut xs: Vec<u32> = (0..100).collect();
+        let mut ys = xs.clone();
+        a.shuffle(&mut xs);
+        ys.shuffle(&mut b);
+        assert!(xs == ys);
+        *a.choose_mut(&mut xs).unwrap() += 1000;
+        *ys.choose_mut(&mut b).unwrap() += 1000;
+        assert!(xs == ys && xs.iter().filter(|&&x| x >= 1000).count() == 1);
+        assert_eq!(a.next_u64(), b.next_u64());
+        println!("rng.choose(&xs) == xs.choose(&mut rng), likewise shuffle, choose_mut");
+    }
+    {
+        // Through a trait object, either way round
+        let mut rng = seed.clone();
+        let dyn_rng: &mut dyn Rng = &mut rng;
+        let xs = [1, 2, 3];
+        assert!(xs.choose(dyn_rng).is_some());
+        assert!(dyn_rng.choose(&xs).is_some());
+        let mut v = vec![1, 2, 3];
+        v.shuffle(dyn_rng);
+        assert!(v.swap_remove_random(dyn_rng).is_some());
+        println!("&mut dyn Rng: ok");
+    }
+    {
+        // Empty slices
+        let mut rng = seed.clone();
+        let mut empty: Vec<u8> = vec![];
+        assert!(empty.choose(&mut rng).is_none() && rng.choose(&empty).is_none());
+        assert!(empty.choose_mut(&mut rng).is_none() && rng.choose_mut(&mut empty).is_none());
+        assert!(empty.swap_remove_random(&mut rng).is_none());
+        empty.shuffle(&mut rng);
+        let mut one = [7];
+        one.shuffle(&mut rng);
+        let mut rng2 = seed.clone();
+        assert_eq!(rng.next_u64(), rng2.next_u64(), "nothing to choose from must consume nothing");
+        assert_eq!(one.choose(&mut rng), Some(&7));
+        println!("empty and single-element slices: ok");
+    }
+    let mut rng = seed.clone();
+    {
+        // choose: every element equally likely
+        let xs: Vec<usize> = (0..10).collect();
+        let mut counts = [0u64; 10];
+        for _ in 0..100_000 {
+            counts[*xs.choose(&mut rng).unwrap()] += 1;
+        }
+        assert!(chi_squared(&counts) < chi_squared_limit(9), "choose: {:?}", counts);
+        
+        // shuffle: all 24 permutations of 4 equally likely
+        let mut counts = [0u64; 24];
+        for _ in 0..240_000 {
+            let mut p = [0usize, 1, 2, 3];
+            p.shuffle(&mut rng);
+            // Lehmer code of the permutation
+            let mut code = 0;
+            for i in 0..4 {
+                code = code * (4 - i) + p[i + 1..].iter().filter(|&&x| x < p[i]).count();
+            }
+            counts[code] += 1;
+        }
+        assert!(chi_squared(&counts) < chi_squared_limit(23), "shuffle: {:?}", counts);
+        
+        // swap_remove_random: removes each element once; the first removed
+        // is uniform
+        let mut firsts = [0u64; 8];
+        for _ in 0..80_000 {
+            let mut v: Vec<usize> = (0..8).collect();
+            let mut removed = vec![v.swap_remove_random(&mut rng).unwrap()];
+            firsts[removed[0]] += 1;
+            while let Some(x) = v.swap_remove_random(&mut rng) {
+                removed.push(x);
+            }
+            removed.sort();
+            assert!(removed == (0..8).collect::<Vec<_>>());
+        }
+        assert!(chi_squared(&firsts) < chi_squared_limit(7), "swap_remove_random: {:?}", firsts);
+        println!("choose, shuffle, swap_remove_random: uniform");
+    }
+}
@@ -0,0 +1,395 @@
+//! `gen::<T>()`: values of any type, over three trait designs
+//! 
+//! The trait designs stop at words; users want values: `rng.gen::<u32>()`,
+//! `rng.gen::<f64>()`, `rng.gen::<(u8, bool)>()`. As in rand, each type
+//! implements the `Standard` distribution (`Distribution<T> for Standard`),
+//! and `gen` is a provided method of an extension trait implemented for
+//! every generator: integers, `bool`, `f32` and `f64` over `[0, 1)`, and
+//! pairs of these.
+//! 
+//! The conversions from words are shared (`convert`); what differs is what
+//! the layer asks of a generator. It is written here over three designs of
+//! `traits/`, each included whole:
+//! 
+//! *   `extends_Rng` (`CryptoRng: Rng`, the infallible trait the base):
+//!     `Standard` over `Rng` gives `gen` to every generator, crypto ones too,
+//!     which panic on failure; `try_gen` needs a second distribution trait,
+//!     over `CryptoRng`'s `try_` methods, and every impl written again
+//! *   `extends_CryptoRng` (`Rng: CryptoRng`, the fallible trait the base):
+//!     one `Standard`, over `CryptoRng`, returning `Result`; `gen` unwraps
+//!     it, the `Err` arm dead only once inlined into an infallible generator
+//! *   `raw_infallible` (`RawRng<E>`): one `Standard`, generic over the
+//!     error; `gen` instantiates it at `Infallible`, where the `Err` arm is
+//!     empty by type, and `try_gen` at any `E`
+//! 
+//! Run with:
+//! 
+//!     rustc -O standard.rs && ./standard
+//! 
+//! Compile-fail check (must fail to compile):
+//! 
+//!     rustc --cfg fail_ambiguous_error standard.rs
+//! 
+//! Thoughts: the layer works over each design; they differ in how often it
+//! must be written. Over an infallible base, `try_gen` costs a second copy
+//! of every impl; over a fallible base the infallible path is the fallible
+//! one, unwrapped. `RawRng<E>` writes it once, `gen` infallible by type, at
+//! the cost of inference: `t.try_gen()` can't pick `E` for a plain generator
+//! (`fail_ambiguous_error`). No layer bounds anything by `CryptoRng`; that
+//! is for the consumer of key material.
+
+use std::convert::Infallible;
+
+#[path = "../traits/extends_Rng.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+pub mod extends_rng;
+
+#[path = "../traits/extends_CryptoRng.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+pub mod extends_cryptorng;
+
+#[path = "../traits/raw_infallible.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+pub mod raw_infallible;
+
+// ——— conversions ———
+
+/// Words to values, the same over every design.
+mod convert {
+    pub fn bool(x: u32) -> bool {
+        (x as i32) < 0
+    }
+    
+    pub fn f32(x: u32) -> f32 {
+        (x >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+    }
+    
+    pub fn f64(x: u64) -> f64 {
+        (x >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+/// The distribution of `gen`.
+#[derive(Debug, Clone, Copy)]
+pub struct Standard;
+
+// ——— over extends_Rng ———
+
+pub mod on_extends_rng {
+    use super::extends_rng::{CryptoError, CryptoRng, Rng};
+    use super::{convert, Standard};
+    
+    pub trait Distribution<T> {
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> T;
+    }
+    
+    /// The same again, for fallible generators.
+    pub trait TryDistribution<T> {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<T, CryptoError>;
+    }
+    
+    macro_rules! standard {
+        ($($ty:ty => |$rng:ident| $e:expr, $try_e:expr;)*) => {$(
+            impl Distribution<$ty> for Standard {
+                fn sample<R: Rng+?Sized>(&self, $rng: &mut R) -> $ty {
+                    $e
+                }
+            }
+            
+            impl TryDistribution<$ty> for Standard {
+                fn try_sample<R: CryptoRng+?Sized>(&self, $rng: &mut R) -> Result<$ty, CryptoError> {
+                    Ok($try_e)
+                }
+            }
+        )*};
+    }
+    
+    standard! {
+        u8 => |rng| rng.next_u32() as u8, rng.try_next_u32()? as u8;
+        u16 => |rng| rng.next_u32() as u16, rng.try_next_u32()? as u16;
+        u32 => |rng| rng.next_u32(), rng.try_next_u32()?;
+        u64 => |rng| rng.next_u64(), rng.try_next_u64()?;
+        u128 => |rng| rng.next_u128(), rng.try_next_u128()?;
+        bool => |rng| convert::bool(rng.next_u32()), convert::bool(rng.try_next_u32()?);
+        f32 => |rng| convert::f32(rng.next_u32()), convert::f32(rng.try_next_u32()?);
+        f64 => |rng| convert::f64(rng.next_u64()), convert::f64(rng.try_next_u64()?);
+    }
+    
+    impl<A, B> Distribution<(A, B)> for Standard
+        where Standard: Distribution<A> + Distribution<B>
+    {
+        fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> (A, B) {
+            let a = self.sample(rng);
+            (a, self.sample(rng))
+        }
+    }
+    
+    impl<A, B> TryDistribution<(A, B)> for Standard
+        where Standard: TryDistribution<A> + TryDistribution<B>
+    {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<(A, B), CryptoError> {
+            let a = self.try_sample(rng)?;
+            Ok((a, self.try_sample(rng)?))
+        }
+    }
+    
+    pub trait RngExt: Rng {
+        fn gen<T>(&mut self) -> T where Standard: Distribution<T> {
+            Standard.sample(self)
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+    
+    pub trait CryptoRngExt: CryptoRng {
+        fn try_gen<T>(&mut self) -> Result<T, CryptoError> where Standard: TryDistribution<T> {
+            Standard.try_sample(self)
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> CryptoRngExt for R {}
+}
+
+// ——— over extends_CryptoRng ———
+
+pub mod on_extends_cryptorng {
+    use super::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use super::{convert, Standard};
+    
+    pub trait Distribution<T> {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<T, CryptoError>;
+    }
+    
+    macro_rules! standard {
+        ($($ty:ty => |$rng:ident| $e:expr;)*) => {$(
+            impl Distribution<$ty> for Standard {
+                fn try_sample<R: CryptoRng+?Sized>(&self, $rng: &mut R) -> Result<$ty, CryptoError> {
+                    Ok($e)
+                }
+            }
+        )*};
+    }
+    
+    standard! {
+        u8 => |rng| rng.try_next_u32()? as u8;
+        u16 => |rng| rng.try_next_u32()? as u16;
+        u32 => |rng| rng.try_next_u32()?;
+        u64 => |rng| rng.try_next_u64()?;
+        u128 => |rng| rng.try_next_u128()?;
+        bool => |rng| convert::bool(rng.try_next_u32()?);
+        f32 => |rng| convert::f32(rng.try_next_u32()?);
+        f64 => |rng| convert::f64(rng.try_next_u64()?);
+    }
+    
+    impl<A, B> Distribution<(A, B)> for Standard
+        where Standard: Distribution<A> + Distribution<B>
+    {
+        fn try_sample<R: CryptoRng+?Sized>(&self, rng: &mut R) -> Result<(A, B), CryptoError> {
+            let a = self.try_sample(rng)?;
+            Ok((a, self.try_sample(rng)?))
+        }
+    }
+    
+    pub trait CryptoRngExt: CryptoRng {
+        fn try_gen<T>(&mut self) -> Result<T, CryptoError> where Standard: Distribution<T> {
+            Standard.try_sample(self)
+        }
+    }
+    
+    impl<R: CryptoRng+?Sized> CryptoRngExt for R {}
+    
+    pub trait RngExt: Rng {
+        fn gen<T>(&mut self) -> T where Standard: Distribution<T> {
+            Standard.try_sample(self).unwrap()
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+}
+
+// ——— over raw_infallible ———
+
+pub mod on_raw_infallible {
+    use super::raw_infallible::{RawRng, Rng};
+    #[cfg(fail_ambiguous_error)]
+    use super::raw_infallible::test_rngs::{Constant, TestRng};
+    use super::{convert, Infallible, Standard};
+    
+    pub trait Distribution<T> {
+        fn try_sample<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<T, E>;
+    }
+    
+    macro_rules! standard {
+        ($($ty:ty => |$rng:ident| $e:expr;)*) => {$(
+            impl Distribution<$ty> for Standard {
+                fn try_sample<E, R: RawRng<E>+?Sized>(&self, $rng: &mut R) -> Result<$ty, E> {
+                    Ok($e)
+                }
+            }
+        )*};
+    }
+    
+    standard! {
+        u8 => |rng| rng.try_next_u32()? as u8;
+        u16 => |rng| rng.try_next_u32()? as u16;
+        u32 => |rng| rng.try_next_u32()?;
+        u64 => |rng| rng.try_next_u64()?;
+        u128 => |rng| rng.try_next_u128()?;
+        bool => |rng| convert::bool(rng.try_next_u32()?);
+        f32 => |rng| convert::f32(rng.try_next_u32()?);
+        f64 => |rng| convert::f64(rng.try_next_u64()?);
+    }
+    
+    impl<A, B> Distribution<(A, B)> for Standard
+        where Standard: Distribution<A> + Distribution<B>
+    {
+        fn try_sample<E, R: RawRng<E>+?Sized>(&self, rng: &mut R) -> Result<(A, B), E> {
+            let a = self.try_sample(rng)?;
+            Ok((a, self.try_sample(rng)?))
+        }
+    }
+    
+    pub trait RawRngExt {
+        fn try_gen<T, E>(&mut self) -> Result<T, E> where Self: RawRng<E>, Standard: Distribution<T> {
+            Standard.try_sample(self)
+        }
+    }
+    
+    impl<R: ?Sized> RawRngExt for R {}
+    
+    pub trait RngExt: Rng {
+        fn gen<T>(&mut self) -> T where Standard: Distribution<T> {
+            Standard.try_sample::<Infallible, Self>(self).unwrap_or_else(|e| match e {})
+        }
+    }
+    
+    impl<R: Rng+?Sized> RngExt for R {}
+    
+    // A plain generator is `RawRng<Infallible>` and `RawRng<Error>`: `E` is
+    // ambiguous unless named. (Generic over `R: Rng` it isn't, the bound's
+    // `RawRng<Infallible>` being preferred.)
+    #[cfg(fail_ambiguous_error)]
+    fn ambiguous(mut t: TestRng<Constant>) -> u32 {
+        t.try_gen().unwrap()
+    }
+}
+
+// ——— test RNGs ———
+
+/// extends_Rng's `TestCRng` keeps `CryptoRng`'s provided `try_` methods,
+/// which can't fail; this one's fail with its source.
+pub struct FallibleCRng<S>(pub S);
+
+impl<S: extends_rng::test_rngs::Source> extends_rng::Rng for FallibleCRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: extends_rng::test_rngs::Source> extends_rng::CryptoRng for FallibleCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, extends_rng::CryptoError> {
+        self.0.try_word().map(|x| x as u32).map_err(|_| {
+            extends_rng::CryptoError::new(extends_rng::ErrorKind::Unavailable, "test source exhausted")
+        })
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, extends_rng::CryptoError> {
+        let lo = self.try_next_u32()?;
+        Ok((self.try_next_u32()? as u64) << 32 | lo as u64)
+    }
+}
+
+// ——— usage ———
+
+/// The values `main` draws, from words 1, 2, 3, ...
+type Values = (u32, u8, u64, bool, (u16, bool), f64);
+
+fn main() {
+    let values: [Values; 3] = [
+        {
+            use self::extends_rng::test_rngs::{Counting, TestRng};
+            use self::on_extends_rng::RngExt;
+            let mut t = TestRng(Counting(1));
+            (t.gen(), t.gen(), t.gen(), t.gen(), t.gen(), t.gen())
+        },
+        {
+            use self::extends_cryptorng::test_rngs::{Counting, TestRng};
+            use self::on_extends_cryptorng::RngExt;
+            let mut t = TestRng(Counting(1));
+            (t.gen(), t.gen(), t.gen(), t.gen(), t.gen(), t.gen())
+        },
+        {
+            use self::on_raw_infallible::RngExt;
+            use self::raw_infallible::test_rngs::{Counting, TestRng};
+            let mut t = TestRng(Counting(1));
+            (t.gen(), t.gen(), t.gen(), t.gen(), t.gen(), t.gen())
+        },
+    ];
+    assert_eq!(values[0], (1, 2, 4 << 32 | 3, false, (6, false), ((9u64 << 32 | 8) >> 11) as f64 / 2f64.powi(53)));
+    assert_eq!(values[0], values[1]);
+    assert_eq!(values[0], values[2]);
+    println!("gen: the same values over each design, {:?}", values[0]);
+    {
+        // The conversions at the ends of a word: `[0, 1)`, and the top bit
+        use self::on_raw_infallible::RngExt;
+        use self::raw_infallible::test_rngs::{Constant, TestRng};
+        let mut zero = TestRng(Constant(0));
+        assert_eq!((zero.gen::<bool>(), zero.gen::<f32>(), zero.gen::<f64>()), (false, 0.0, 0.0));
+        let mut ones = TestRng(Constant(u64::MAX));
+        assert!(ones.gen::<bool>());
+        assert_eq!(ones.gen::<f32>(), 1.0 - 2f32.powi(-24));
+        assert_eq!(ones.gen::<f64>(), 1.0 - 2f64.powi(-53));
+        assert_eq!(ones.gen::<u128>(), u128::MAX);
+        println!("all-ones words: true, 1 - 2^-24, 1 - 2^-53, u128::MAX");
+    }
+    {
+        // extends_Rng: `gen` on a crypto generator, `try_gen` through the
+        // second set of impls
+        use self::extends_rng::test_rngs::{Constant, FailAfter, TestCRng};
+        use self::extends_rng::ErrorKind;
+        use self::on_extends_rng::{CryptoRngExt, RngExt};
+        let mut c = TestCRng(Constant(42));
+        assert_eq!((c.gen::<u8>(), c.try_gen::<u8>().unwrap()), (42, 42));
+        let mut f = FallibleCRng(FailAfter::new(1));
+        assert_eq!(f.try_gen::<(u32, u32)>().unwrap_err().kind, ErrorKind::Unavailable);
+        println!("extends_Rng: c.gen() = 42; a pair from one word: Unavailable");
+    }
+    {
+        // extends_CryptoRng: `try_gen` for all, `gen` for `Rng`s
+        use self::extends_cryptorng::test_rngs::{Constant, FailAfter, TestCRng, TestRng};
+        use self::extends_cryptorng::ErrorKind;
+        use self::on_extends_cryptorng::{CryptoRngExt, RngExt};
+        let mut t = TestRng(Constant(7));
+        assert_eq!((t.gen::<u16>(), t.try_gen::<u16>().unwrap()), (7, 7));
+        let mut c = TestCRng(Constant(42));
+        assert_eq!(c.try_gen::<u8>().unwrap(), 42);
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_gen::<(u32, u32)>().unwrap_err().kind, ErrorKind::Unavailable);
+        println!("extends_CryptoRng: try_gen for all; a pair from one word: Unavailable");
+    }
+    {
+        // raw_infallible: `try_gen` at the generator's error, which a plain
+        // generator must name
+        use self::on_raw_infallible::RawRngExt;
+        use self::raw_infallible::test_rngs::{Constant, FailAfter, TestFCRng, TestRng};
+        use self::raw_infallible::{Error, ErrorKind};
+        let mut t = TestRng(Constant(7));
+        assert_eq!(t.try_gen::<u8, Error>().unwrap(), 7);
+        assert_eq!(t.try_gen::<u8, Infallible>().unwrap(), 7);
+        let mut c = TestFCRng(Constant(42));
+        assert_eq!(c.try_gen::<u8, _>().unwrap(), 42);
+        let mut f = TestFCRng(FailAfter::new(1));
+        assert_eq!(f.try_gen::<(u32, u32), _>().unwrap_err().kind, ErrorKind::Unavailable);
+        println!("raw_infallible: try_gen::<T, E>; a pair from one word: Unavailable");
+    }
+    {
+        // Through trait objects: `Standard` takes `R: ?Sized`
+        use self::on_raw_infallible::RngExt;
+        use self::raw_infallible::test_rngs::{Counting, TestRng};
+        use self::raw_infallible::Rng;
+        let mut t = TestRng(Counting(1));
+        let r: &mut dyn Rng = &mut t;
+        assert_eq!(r.gen::<(u8, u8)>(), (1, 2));
+        println!("&mut dyn Rng: gen::<(u8, u8)>() = (1, 2)");
+    }
+}
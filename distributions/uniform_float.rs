@@ -0,0 +1,224 @@
+//! `UniformFloat`: uniform sampling of `f32`/`f64` over `[low, high)`
+//! 
+//! Samples `v` uniformly from `[0, 1)` (24 or 53 random bits) and returns
+//! `low + v * scale`. Two things need care:
+//! 
+//! *   `high - low` overflows to infinity for ranges wider than `MAX` (e.g.
+//!     `[-MAX, MAX)`). Such ranges are computed at half size, as
+//!     `(low/2 + v * scale) * 2`; halving and doubling are exact here since
+//!     both bounds are huge.
+//! *   rounding can make `low + v * scale` equal `high` even though `v < 1`,
+//!     especially for narrow ranges (in `[x, next_up(x))` it happens for half
+//!     of all `v`). So `new` picks the largest `scale` for which the largest
+//!     `v` still gives a result below `high`, by binary search on its bits.
+//!     Since every step is monotonic in `v`, no sample can then reach `high`.
+//! 
+//! Results are never below `low` either: with `v = 0` the result is `low`.
+//! 
+//! Run with:
+//! 
+//!     rustc -O uniform_float.rs && ./uniform_float
+//! 
+//! Thoughts: rand's approach of decrementing `scale` one ULP at a time until
+//! the largest sample is below `high` takes ~2^52 steps for adjacent bounds;
+//! binary search takes at most 64. Shrinking `scale` biases the result
+//! slightly towards `low`, but only by the rounding error it corrects.
+
+// ——— traits ———
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64;
+}
+
+// ——— distribution ———
+
+// Fields are visible to the vectorised version in simd.rs.
+#[derive(Debug, Clone, Copy)]
+pub struct UniformFloat<F> {
+    pub(crate) offset: F,
+    pub(crate) scale: F,
+    pub(crate) factor: F,
+}
+
+macro_rules! uniform_float_impl {
+    ($ty:ident, $uty:ident, $next:ident, $bits:expr) => {
+        impl UniformFloat<$ty> {
+            /// Largest `v` sampled.
+            const MAX_V: $ty = 1.0 - 1.0 / (1u64 << $bits) as $ty;
+            
+            /// Uniform over `[low, high)`.
+            /// 
+            /// Panics unless `low < high` and both are finite.
+            pub fn new(low: $ty, high: $ty) -> Self {
+                assert!(low.is_finite() && high.is_finite() && low < high,
+                    "UniformFloat::new: invalid range [{:?}, {:?})", low, high);
+                let (offset, width, factor) = if (high - low).is_finite() {
+                    (low, high - low, 1.0)
+                } else {
+                    (low / 2.0, high / 2.0 - low / 2.0, 2.0)
+                };
+                let max = |scale: $ty| (Self::MAX_V * scale + offset) * factor;
+                
+                // Largest scale in [0, width] with max(scale) < high
+                // (positive floats are ordered like their bits)
+                let (mut ok, mut bad) = (0, width.to_bits());
+                if max(width) < high {
+                    ok = bad;
+                }
+                while bad - ok > 1 {
+                    let mid = ok + (bad - ok) / 2;
+                    if max($ty::from_bits(mid)) < high {
+                        ok = mid;
+                    } else {
+                        bad = mid;
+                    }
+                }
+                UniformFloat { offset, scale: $ty::from_bits(ok), factor }
+            }
+            
+            pub fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> $ty {
+                let v = (rng.$next() >> (<$uty>::BITS - $bits)) as $ty
+                    / (1u64 << $bits) as $ty;
+                (v * self.scale + self.offset) * self.factor
+            }
+        }
+    };
+}
+
+uniform_float_impl!(f32, u32, next_u32, 24);
+uniform_float_impl!(f64, u64, next_u64, 53);
+
+// ——— test RNGs ———
+
+#[path = "../traits/test_rngs.rs"]
+#[allow(dead_code)]
+mod test_rngs;
+use self::test_rngs::{Source, SplitMix};
+
+/// SplitMix64, taking the high half of a word for a `u32`.
+impl Rng for SplitMix {
+    fn next_u32(&mut self) -> u32 {
+        (self.word() >> 32) as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.word()
+    }
+}
+
+/// Always returns the same bits: 0 and !0 give the extreme samples.
+struct Fixed(u64);
+
+impl Rng for Fixed {
+    fn next_u32(&mut self) -> u32 {
+        self.0 as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0
+    }
+}
+
+// ——— usage ———
+
+macro_rules! tests {
+    ($ty:ident, $rng:expr) => {{
+        let next_up = |x: $ty| {
+            if x == 0.0 { $ty::from_bits(1) }
+            else if x > 0.0 { $ty::from_bits(x.to_bits() + 1) }
+            else { $ty::from_bits(x.to_bits() - 1) }
+        };
+        let tiny = $ty::from_bits(1);
+        
+        // Check extremes and n random samples of [low, high); return the
+        // largest sample seen
+        let rng: &mut SplitMix = $rng;
+        let mut check = |low: $ty, high: $ty, n: usize| {
+            let d = UniformFloat::<$ty>::new(low, high);
+            let lowest = d.sample(&mut Fixed(0));
+            let mut max = d.sample(&mut Fixed(!0));
+            assert!(lowest == low, "[{:?}, {:?}): v = 0 gave {:?}", low, high, lowest);
+            assert!(low <= max && max < high, "[{:?}, {:?}): v = MAX gave {:?}", low, high, max);
+            for _ in 0..n {
+                let x = d.sample(rng);
+                assert!(low <= x && x < high, "[{:?}, {:?}): got {:?}", low, high, x);
+                if x > max {
+                    max = x;
+                }
+            }
+            max
+        };
+        
+        // Every pair of boundary values, and each with its neighbour above
+        let points = [
+            -$ty::MAX, -1e30, -2.0, -1.0, -$ty::MIN_POSITIVE, -3.0 * tiny, -tiny, -0.0,
+            0.0, tiny, 3.0 * tiny, $ty::MIN_POSITIVE, 1.0, 2.0, 1e30, $ty::MAX / 2.0,
+        ];
+        let highs: Vec<$ty> = points.iter().cloned().chain(points.iter().map(|&x| next_up(x))).collect();
+        let mut pairs = 0;
+        for &low in points.iter() {
+            for &high in highs.iter() {
+                if low < high {
+                    check(low, high, 100);
+                    pairs += 1;
+                }
+            }
+        }
+        
+        // Full range: no overflow, and both signs occur
+        let max = check(-$ty::MAX, $ty::MAX, 1000);
+        assert!(max > 0.0 && max.is_finite());
+        
+        // Adjacent values: only `low`; one apart: `low` or its neighbour
+        for &x in points.iter() {
+            let y = next_up(x);
+            assert_eq!(check(x, y, 100), x);
+            let z = next_up(y);
+            assert!(check(x, z, 100) <= y);
+        }
+        
+        // Negative zero: `high` of ±0 excludes both zeros; `low` of -0
+        // allows +0 (they compare equal)
+        assert!(check(-tiny, -0.0, 100) == -tiny);
+        assert!(check(-1.0, 0.0, 100) < 0.0);
+        check(-0.0, tiny, 100);
+        
+        // Mean of [1, 3) is close to 2
+        let d = UniformFloat::<$ty>::new(1.0, 3.0);
+        let mean = (0..100_000).map(|_| d.sample(rng) as f64).sum::<f64>() / 100_000.0;
+        assert!((mean - 2.0).abs() < 0.01, "mean {}", mean);
+        
+        println!("{}: {} boundary pairs ok", stringify!($ty), pairs);
+    }};
+}
+
+fn main() {
+    let mut rng = SplitMix(0x5EED);
+    tests!(f32, &mut rng);
+    tests!(f64, &mut rng);
+    
+    // f32, exhaustively over subnormals (both signs) and sparsely elsewhere:
+    // every [x, next_up(x)) gives only x
+    let mut n = 0u64;
+    let mut bits = 0u32;
+    loop {
+        let x = f32::from_bits(bits);
+        let exp = (bits >> 23) & 0xFF;
+        if x.is_finite() && x != f32::MAX {
+            let y = if x == 0.0 && bits != 0 { f32::from_bits(1) }     // -0.0
+                else if x >= 0.0 { f32::from_bits(bits + 1) }
+                else { f32::from_bits(bits - 1) };
+            let d = UniformFloat::<f32>::new(x, y);
+            assert!(d.sample(&mut Fixed(0)) == x && d.sample(&mut Fixed(!0)) == x, "[{:?}, {:?})", x, y);
+            n += 1;
+        }
+        let step = if exp == 0 { 1 } else { 4099 };
+        match bits.checked_add(step) {
+            Some(b) => bits = b,
+            None => break,
+        }
+    }
+    println!("f32: {} adjacent pairs ok", n);
+}
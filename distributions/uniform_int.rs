@@ -0,0 +1,290 @@
+//! `UniformInt`: uniform sampling of integers over `[low, high]`, any range
+//! 
+//! The width of a signed range can overflow its own type: `i64::MIN..=i64::MAX`
+//! has 2^64 values and even `-1..=i64::MAX` has 2^63 + 1, so `high - low + 1`
+//! panics in debug builds and wraps in release. All range arithmetic is
+//! therefore done on the unsigned type of the same width:
+//! 
+//! *   `high.wrapping_sub(low) as $uty` is the exact distance, since the true
+//!     difference is in `[0, 2^N)`; one more is the number of values, which
+//!     only overflows for the full domain, stored as 0
+//! *   the result is `low.wrapping_add(offset as $ty)`: exact, since the true
+//!     sum is in `[low, high]`, even when `offset` does not fit in `$ty`
+//! 
+//! Sampling multiplies a random word by the range and keeps the high half,
+//! rejecting words whose low half is below `2^W mod range` (the same method
+//! as `UniformU32x8` in `simd.rs`), so every value is exactly equally likely.
+//! The word is a `u32` for types up to 32 bits, and otherwise of the type's
+//! width; the full domain of a type needs no multiply or rejection.
+//! 
+//! Run with:
+//! 
+//!     rustc -O uniform_int.rs && ./uniform_int
+//! 
+//! `main` checks every integer type over ranges between its boundary values
+//! (`MIN`, `MIN + 1`, `-1`, `0`, `1`, `MAX - 1`, `MAX` and midpoints): both
+//! ends are reachable, no sample falls outside, and narrow ranges produce
+//! every value.
+//! 
+//! Thoughts: the mapping is monotonic in the word, so word 1 always gives
+//! `low` and the all-ones word always gives `high` (neither is ever rejected
+//! outside the full domain), which makes the ends testable without searching
+//! for lucky seeds. Half-open `new(low, high)` is just `new_inclusive(low,
+//! high - 1)`; only the inclusive form can express the full domain.
+
+// ——— traits ———
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64;
+    
+    fn next_u128(&mut self) -> u128 {
+        self.next_u64() as u128 | (self.next_u64() as u128) << 64
+    }
+}
+
+// ——— distribution ———
+
+#[derive(Debug, Clone, Copy)]
+pub struct UniformInt<X, W> {
+    low: X,
+    /// Number of values, as a word; 0 for 2^W.
+    range: W,
+    /// Values of the low half of the product below this are rejected.
+    threshold: W,
+}
+
+/// High and low halves of the double-width product.
+fn wmul_u32(a: u32, b: u32) -> (u32, u32) {
+    let m = a as u64 * b as u64;
+    ((m >> 32) as u32, m as u32)
+}
+
+fn wmul_u64(a: u64, b: u64) -> (u64, u64) {
+    let m = a as u128 * b as u128;
+    ((m >> 64) as u64, m as u64)
+}
+
+fn wmul_u128(a: u128, b: u128) -> (u128, u128) {
+    const LO: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & LO);
+    let (b1, b0) = (b >> 64, b & LO);
+    let low = a0 * b0;
+    let mid = a1 * b0 + (low >> 64);
+    let mid2 = a0 * b1 + (mid & LO);
+    (a1 * b1 + (mid >> 64) + (mid2 >> 64), (mid2 << 64) | (low & LO))
+}
+
+macro_rules! uniform_int_impl {
+    ($ty:ident, $uty:ident, $word:ident, $next:ident, $wmul:ident) => {
+        impl UniformInt<$ty, $word> {
+            /// Uniform over `[low, high)`.
+            /// 
+            /// Panics unless `low < high`.
+            pub fn new(low: $ty, high: $ty) -> Self {
+                assert!(low < high, "UniformInt::new: empty range [{}, {})", low, high);
+                Self::new_inclusive(low, high - 1)
+            }
+            
+            /// Uniform over `[low, high]`; may be the whole type.
+            /// 
+            /// Panics unless `low <= high`.
+            pub fn new_inclusive(low: $ty, high: $ty) -> Self {
+                assert!(low <= high, "UniformInt::new_inclusive: empty range [{}, {}]", low, high);
+                let range = (high.wrapping_sub(low) as $uty as $word).wrapping_add(1);
+                let threshold = if range == 0 { 0 } else { range.wrapping_neg() % range };
+                UniformInt { low, range, threshold }
+            }
+            
+            pub fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> $ty {
+                if self.range == 0 {
+                    return self.low.wrapping_add(rng.$next() as $ty);
+                }
+                loop {
+                    let (hi, lo) = $wmul(rng.$next(), self.range);
+                    if lo >= self.threshold {
+                        return self.low.wrapping_add(hi as $uty as $ty);
+                    }
+                }
+            }
+        }
+    };
+}
+
+uniform_int_impl!(i8, u8, u32, next_u32, wmul_u32);
+uniform_int_impl!(i16, u16, u32, next_u32, wmul_u32);
+uniform_int_impl!(i32, u32, u32, next_u32, wmul_u32);
+uniform_int_impl!(i64, u64, u64, next_u64, wmul_u64);
+uniform_int_impl!(i128, u128, u128, next_u128, wmul_u128);
+uniform_int_impl!(isize, usize, u64, next_u64, wmul_u64);
+uniform_int_impl!(u8, u8, u32, next_u32, wmul_u32);
+uniform_int_impl!(u16, u16, u32, next_u32, wmul_u32);
+uniform_int_impl!(u32, u32, u32, next_u32, wmul_u32);
+uniform_int_impl!(u64, u64, u64, next_u64, wmul_u64);
+uniform_int_impl!(u128, u128, u128, next_u128, wmul_u128);
+uniform_int_impl!(usize, usize, u64, next_u64, wmul_u64);
+
+// ——— test RNGs ———
+
+#[path = "../traits/test_rngs.rs"]
+#[allow(dead_code)]
+mod test_rngs;
+pub use self::test_rngs::SplitMix;
+use self::test_rngs::Source;
+
+/// SplitMix64, taking the high half of a word for a `u32`.
+impl Rng for SplitMix {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.word() >> 32) as u32
+    }
+    
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        self.word()
+    }
+}
+
+/// Always returns the same word (truncated for `next_u32`, `next_u64`).
+struct Fixed(u128);
+
+impl Rng for Fixed {
+    fn next_u32(&mut self) -> u32 {
+        self.0 as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0 as u64
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.0
+    }
+}
+
+/// Returns the given words in order; panics when they run out.
+struct Seq(Vec<u128>);
+
+impl Rng for Seq {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u128() as u32
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.next_u128() as u64
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        assert!(!self.0.is_empty(), "Seq: out of words");
+        self.0.remove(0)
+    }
+}
+
+// ——— usage ———
+
+macro_rules! tests {
+    ($ty:ident, $rng:expr) => {{
+        let rng: &mut SplitMix = $rng;
+        
+        // Ends via fixed words, then n random samples of [low, high]; if the
+        // range is narrow, all values must occur
+        let mut check = |low: $ty, high: $ty, n: usize| {
+            let d = UniformInt::<$ty, _>::new_inclusive(low, high);
+            let full = low == $ty::MIN && high == $ty::MAX;
+            let first = d.sample(&mut Fixed(if full { 0 } else { 1 }));
+            let last = d.sample(&mut Fixed(!0));
+            assert!(first == low, "[{}, {}]: lowest word gave {}", low, high, first);
+            assert!(last == high, "[{}, {}]: highest word gave {}", low, high, last);
+            let width = high.wrapping_sub(low) as u128;
+            let mut seen = vec![false; if width < 16 { width as usize + 1 } else { 0 }];
+            for _ in 0..n {
+                let x = d.sample(rng);
+                assert!(low <= x && x <= high, "[{}, {}]: got {}", low, high, x);
+                if let Some(s) = seen.get_mut(x.wrapping_sub(low) as usize) {
+                    *s = true;
+                }
+            }
+            assert!(seen.iter().all(|&s| s), "[{}, {}]: not every value occurred", low, high);
+        };
+        
+        let neg1 = (0 as $ty).wrapping_sub(1);      // MAX for unsigned types
+        let points = [
+            $ty::MIN, $ty::MIN + 1, $ty::MIN / 2, neg1, 0, 1,
+            $ty::MAX / 2, $ty::MAX / 2 + 1, $ty::MAX - 1, $ty::MAX,
+        ];
+        let mut pairs = 0;
+        for &low in points.iter() {
+            for &high in points.iter() {
+                if low <= high {
+                    check(low, high, 200);
+                    pairs += 1;
+                }
+            }
+        }
+        
+        // Ranges whose width overflows the type, or only just fits
+        check($ty::MIN, $ty::MAX, 1000);
+        check(neg1.min(0), $ty::MAX, 1000);
+        check($ty::MIN, $ty::MAX - 1, 1000);
+        check($ty::MIN + 1, $ty::MAX, 1000);
+        
+        // Half-open ranges end one below `high`
+        let d = UniformInt::<$ty, _>::new($ty::MIN, $ty::MAX);
+        assert!(d.sample(&mut Fixed(!0)) == $ty::MAX - 1);
+        
+        // Full domain: both halves occur equally often
+        let d = UniformInt::<$ty, _>::new_inclusive($ty::MIN, $ty::MAX);
+        let mid = $ty::MIN / 2 + $ty::MAX / 2;
+        let above = (0..100_000).filter(|_| d.sample(rng) > mid).count();
+        assert!((above as f64 / 100_000.0 - 0.5).abs() < 0.01, "full domain: {} above", above);
+        
+        println!("{}: {} boundary pairs ok", stringify!($ty), pairs);
+    }};
+}
+
+fn main() {
+    let mut rng = SplitMix(0x5EED);
+    tests!(i8, &mut rng);
+    tests!(i16, &mut rng);
+    tests!(i32, &mut rng);
+    tests!(i64, &mut rng);
+    tests!(i128, &mut rng);
+    tests!(isize, &mut rng);
+    tests!(u8, &mut rng);
+    tests!(u16, &mut rng);
+    tests!(u32, &mut rng);
+    tests!(u64, &mut rng);
+    tests!(u128, &mut rng);
+    tests!(usize, &mut rng);
+    
+    // -1..=i64::MAX has 2^63 + 1 values, so nearly half of all words are
+    // rejected: word 0 is, and word 1 then gives -1
+    let d = UniformInt::<i64, _>::new_inclusive(-1, i64::MAX);
+    let mut words = Seq(vec![0, 1]);
+    assert_eq!(d.sample(&mut words), -1);
+    assert!(words.0.is_empty());
+    let n = 100_000;
+    let neg = (0..n).filter(|_| d.sample(&mut rng) < 0).count();
+    assert!(neg < 10, "-1 is one value in 2^63 + 1, seen {} times", neg);
+    let high = (0..n).filter(|_| d.sample(&mut rng) > i64::MAX / 2).count();
+    assert!((high as f64 / n as f64 - 0.5).abs() < 0.01, "upper half: {} of {}", high, n);
+    println!("i64 [-1, MAX]: rejection ok");
+    
+    // Without rejection, [0, 3 * 2^30) would take values below 2^31 half the
+    // time, not two thirds
+    let d = UniformInt::<u32, _>::new(0, 3 << 30);
+    let low = (0..n).filter(|_| d.sample(&mut rng) < 1 << 31).count();
+    assert!((low as f64 / n as f64 - 2.0 / 3.0).abs() < 0.01, "below 2^31: {} of {}", low, n);
+    
+    // The 128-bit widening multiply against the 64-bit one
+    for _ in 0..10_000 {
+        let (a, b) = (rng.next_u64(), rng.next_u64());
+        let (hi, lo) = wmul_u128(a as u128, b as u128);
+        assert_eq!((hi, lo), (0, a as u128 * b as u128));
+        let (hi, lo) = wmul_u128((a as u128) << 64, (b as u128) << 64);
+        assert_eq!((hi, lo), (a as u128 * b as u128, 0));
+    }
+    assert_eq!(wmul_u128(!0, !0), (!0 - 1, 1));
+    println!("bias and wide multiply: ok");
+}
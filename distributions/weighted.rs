@@ -0,0 +1,361 @@
+//! Weighted index sampling: cumulative sums vs alias table
+//! 
+//! Two samplers of an index `i` with probability `weights[i] / total`, for
+//! integer weights:
+//! 
+//! *   `WeightedIndex`: cumulative sums, then a binary search of a uniform
+//!     value in `[0, total)`. O(n) to build, O(log n) per draw
+//! *   `WeightedAliasIndex`: Walker's alias table, built with Vose's method.
+//!     Each of the `n` columns holds probability `1/n`, split between its own
+//!     index and one alias; a draw picks a column and a split value, from the
+//!     two halves of one `u64`. O(n) to build (with more work per weight),
+//!     O(1) per draw
+//! 
+//! Both have `update_weights`, changing some weights in place: the cumulative
+//! sums are recomputed from the first changed index, and the alias table is
+//! rebuilt in its existing buffers.
+//! 
+//! With integer weights both are exact: no rounding anywhere, so the
+//! probability of each index is exactly its weight over the total. `main`
+//! checks this exactly, enumerating every uniform value for the cumulative
+//! sums and adding up each index's share of the columns for the alias table,
+//! then by sampling.
+//! 
+//! Run with:
+//! 
+//!     rustc -O weighted.rs && ./weighted
+//! 
+//! and see `bench/weighted.rs` for where each is faster.
+//! 
+//! Thoughts: exactness needs the column height `total` and the scaled weights
+//! `weight * n` to be integers, which limits the total to `u32`; float
+//! weights would be rounded into such a table anyway. Taking the column and
+//! split value from one `u64` halves the generator calls per draw; it stays
+//! exact by rejecting the word if either half falls in its rejection zone.
+//! Updating an alias table has no cheaper way than rebuilding it, since one
+//! changed weight can move every alias.
+
+#[path = "uniform_int.rs"]
+#[allow(dead_code)]
+mod uniform_int;
+
+#[path = "../verify/chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+pub use self::uniform_int::{Rng, SplitMix, UniformInt};
+use self::chi_squared::{chi_squared_limit, chi_squared_weighted};
+
+// ——— errors ———
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightedError {
+    /// No weights were given.
+    NoItem,
+    /// Every weight is zero.
+    AllWeightsZero,
+    /// The total weight, or the number of weights, exceeds `u32::MAX`.
+    TooMany,
+}
+
+/// The total of `weights`, checked.
+fn total(weights: &[u32]) -> Result<u32, WeightedError> {
+    if weights.is_empty() {
+        return Err(WeightedError::NoItem);
+    }
+    if weights.len() > u32::MAX as usize {
+        return Err(WeightedError::TooMany);
+    }
+    let sum: u64 = weights.iter().map(|&w| w as u64).sum();
+    match sum {
+        0 => Err(WeightedError::AllWeightsZero),
+        s if s > u32::MAX as u64 => Err(WeightedError::TooMany),
+        s => Ok(s as u32),
+    }
+}
+
+// ——— cumulative sums ———
+
+#[derive(Debug, Clone)]
+pub struct WeightedIndex {
+    /// `cumulative[i]` is the sum of the weights up to and including `i`.
+    cumulative: Vec<u32>,
+    within: UniformInt<u32, u32>,
+}
+
+impl WeightedIndex {
+    pub fn new(weights: &[u32]) -> Result<Self, WeightedError> {
+        let total = total(weights)?;
+        let cumulative = weights.iter()
+            .scan(0, |sum, &w| { *sum += w; Some(*sum) })
+            .collect();
+        Ok(WeightedIndex { cumulative, within: UniformInt::<u32, _>::new(0, total) })
+    }
+    
+    /// The index whose range of cumulative weight holds `x`, for `x` below the
+    /// total.
+    fn index_of(&self, x: u32) -> usize {
+        self.cumulative.partition_point(|&c| c <= x)
+    }
+    
+    pub fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> usize {
+        self.index_of(self.within.sample(rng))
+    }
+    
+    /// Set the weight of each `(index, weight)` pair. On error the weights
+    /// are left unchanged.
+    pub fn update_weights(&mut self, updates: &[(usize, u32)]) -> Result<(), WeightedError> {
+        let mut weights = self.weights();
+        for &(i, w) in updates {
+            weights[i] = w;
+        }
+        let total = total(&weights)?;
+        let first = match updates.iter().map(|&(i, _)| i).min() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+        let mut sum = if first == 0 { 0 } else { self.cumulative[first - 1] };
+        for (c, &w) in self.cumulative[first..].iter_mut().zip(&weights[first..]) {
+            sum += w;
+            *c = sum;
+        }
+        self.within = UniformInt::<u32, _>::new(0, total);
+        Ok(())
+    }
+    
+    /// The weights, recovered from the sums.
+    pub fn weights(&self) -> Vec<u32> {
+        let mut previous = 0;
+        self.cumulative.iter().map(|&c| { let w = c - previous; previous = c; w }).collect()
+    }
+}
+
+// ——— alias table ———
+
+/// High and low halves of the double-width product.
+fn wmul_u32(a: u32, b: u32) -> (u32, u32) {
+    let m = a as u64 * b as u64;
+    ((m >> 32) as u32, m as u32)
+}
+
+/// Low halves below this are rejected when sampling `[0, range)` by
+/// multiplying; as in `UniformInt`.
+fn zone(range: u32) -> u32 {
+    range.wrapping_neg() % range
+}
+
+#[derive(Debug, Clone)]
+pub struct WeightedAliasIndex {
+    weights: Vec<u32>,
+    /// Column `i` gives `i` if the split value is below `split[i]`, else
+    /// `alias[i]`; out of `total`.
+    split: Vec<u32>,
+    alias: Vec<u32>,
+    total: u32,
+    /// Rejection zones (as in `UniformInt`) for the column and split value.
+    column_zone: u32,
+    split_zone: u32,
+    /// Work lists for building, kept to reuse their allocations.
+    small: Vec<u32>,
+    large: Vec<u32>,
+    scaled: Vec<u64>,
+}
+
+impl WeightedAliasIndex {
+    pub fn new(weights: &[u32]) -> Result<Self, WeightedError> {
+        let total = total(weights)?;
+        let mut table = WeightedAliasIndex {
+            weights: weights.to_vec(),
+            split: vec![],
+            alias: vec![],
+            total,
+            column_zone: zone(weights.len() as u32),
+            split_zone: zone(total),
+            small: vec![],
+            large: vec![],
+            scaled: vec![],
+        };
+        table.build(total);
+        Ok(table)
+    }
+    
+    /// Vose's method, on weights scaled by `n` so that each column holds
+    /// exactly `total`.
+    fn build(&mut self, total: u32) {
+        let n = self.weights.len();
+        let total = total as u64;
+        self.scaled.clear();
+        self.scaled.extend(self.weights.iter().map(|&w| w as u64 * n as u64));
+        self.split.clear();
+        self.split.resize(n, 0);
+        self.alias.clear();
+        self.alias.extend(0..n as u32);
+        self.small.clear();
+        self.small.reserve(n);
+        self.large.clear();
+        self.large.reserve(n);
+        for (i, &s) in self.scaled.iter().enumerate() {
+            if s < total { self.small.push(i as u32) } else { self.large.push(i as u32) }
+        }
+        // Fill each small column from a large one, which may become small
+        while let (Some(&s), Some(&l)) = (self.small.last(), self.large.last()) {
+            self.small.pop();
+            self.split[s as usize] = self.scaled[s as usize] as u32;
+            self.alias[s as usize] = l;
+            self.scaled[l as usize] -= total - self.scaled[s as usize];
+            if self.scaled[l as usize] < total {
+                self.large.pop();
+                self.small.push(l);
+            }
+        }
+        // With no rounding, what remains is large columns holding exactly
+        // `total`: the scaled weights always sum to `total` per column left
+        debug_assert!(self.small.is_empty());
+        for &l in self.large.iter() {
+            debug_assert_eq!(self.scaled[l as usize], total);
+            self.split[l as usize] = total as u32;
+        }
+    }
+    
+    pub fn sample<R: Rng+?Sized>(&self, rng: &mut R) -> usize {
+        let n = self.split.len() as u32;
+        loop {
+            let x = rng.next_u64();
+            let (i, i_low) = wmul_u32((x >> 32) as u32, n);
+            let (v, v_low) = wmul_u32(x as u32, self.total);
+            if i_low >= self.column_zone && v_low >= self.split_zone {
+                // Both loaded first, for a select rather than a branch which
+                // mispredicts as often as the split is even
+                let (i, alias) = (i as usize, self.alias[i as usize] as usize);
+                return if v < self.split[i] { i } else { alias };
+            }
+        }
+    }
+    
+    /// Set the weight of each `(index, weight)` pair and rebuild the table in
+    /// place. On error the weights are left unchanged.
+    pub fn update_weights(&mut self, updates: &[(usize, u32)]) -> Result<(), WeightedError> {
+        let old: Vec<u32> = updates.iter().map(|&(i, _)| self.weights[i]).collect();
+        for &(i, w) in updates {
+            self.weights[i] = w;
+        }
+        match total(&self.weights) {
+            Ok(total) => {
+                self.total = total;
+                self.split_zone = zone(total);
+                self.build(total);
+                Ok(())
+            }
+            Err(e) => {
+                for (&(i, _), &w) in updates.iter().zip(&old).rev() {
+                    self.weights[i] = w;
+                }
+                Err(e)
+            }
+        }
+    }
+    
+    pub fn weights(&self) -> &[u32] {
+        &self.weights
+    }
+    
+    /// Each index's share of the columns, out of `n * total`: exactly
+    /// `weight * n` for a correct table.
+    fn shares(&self) -> Vec<u64> {
+        let total = self.total as u64;
+        let mut shares = vec![0u64; self.split.len()];
+        for (i, (&split, &alias)) in self.split.iter().zip(&self.alias).enumerate() {
+            shares[i] += split as u64;
+            shares[alias as usize] += total - split as u64;
+        }
+        shares
+    }
+    }
+
+// ——— usage ———
+
+/// Probabilities are exactly `weights[i] / total`.
+fn check_exact(c: &WeightedIndex, a: &WeightedAliasIndex, weights: &[u32]) {
+    let total: u32 = weights.iter().sum();
+    assert!(c.weights() == weights && a.weights() == weights);
+    // Every uniform value, through the cumulative sums
+    if total <= 1 << 16 {
+        let mut counts = vec![0u32; weights.len()];
+        for x in 0..total {
+            counts[c.index_of(x)] += 1;
+        }
+        assert!(counts == weights, "cumulative: {:?} for {:?}", counts, weights);
+    }
+    // Each index's share of the alias columns
+    let n = weights.len() as u64;
+    let want: Vec<u64> = weights.iter().map(|&w| w as u64 * n).collect();
+    assert!(a.shares() == want, "alias: {:?} for {:?}", a.shares(), weights);
+}
+
+fn main() {
+    let cases: &[&[u32]] = &[
+        &[1], &[0, 5], &[1, 1], &[1, 2, 3, 4], &[10, 0, 0, 1], &[1, 1000, 1, 1000, 1],
+        &[7; 13], &[3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7, 9, 3, 2, 3, 8, 4, 6],
+        &[u32::MAX], &[u32::MAX - 1, 1], &[1 << 31, 1 << 30, (1 << 30) - 1],
+    ];
+    for &weights in cases {
+        let c = WeightedIndex::new(weights).unwrap();
+        let a = WeightedAliasIndex::new(weights).unwrap();
+        check_exact(&c, &a, weights);
+    }
+    let mut rng = SplitMix(0x5EED);
+    let weights: Vec<u32> = (0..300).map(|_| rng.next_u32() % 1000 * (rng.next_u32() % 2)).collect();
+    check_exact(&WeightedIndex::new(&weights).unwrap(), &WeightedAliasIndex::new(&weights).unwrap(), &weights);
+    println!("cumulative and alias: exact probabilities for {} weight sets", cases.len() + 1);
+    
+    {
+        // Errors
+        assert_eq!(WeightedIndex::new(&[]).unwrap_err(), WeightedError::NoItem);
+        assert_eq!(WeightedAliasIndex::new(&[0, 0]).unwrap_err(), WeightedError::AllWeightsZero);
+        assert_eq!(WeightedAliasIndex::new(&[u32::MAX, 1]).unwrap_err(), WeightedError::TooMany);
+        let mut c = WeightedIndex::new(&[1, 2]).unwrap();
+        let mut a = WeightedAliasIndex::new(&[1, 2]).unwrap();
+        assert_eq!(c.update_weights(&[(0, 0), (1, 0)]), Err(WeightedError::AllWeightsZero));
+        assert_eq!(a.update_weights(&[(0, 0), (1, 0)]), Err(WeightedError::AllWeightsZero));
+        check_exact(&c, &a, &[1, 2]);
+        println!("errors: empty, all zero, too large; failed updates change nothing");
+    }
+    {
+        // Updates: exact, and the same as building afresh
+        let mut weights = vec![5u32, 0, 3, 8, 1, 1, 20, 2];
+        let mut c = WeightedIndex::new(&weights).unwrap();
+        let mut a = WeightedAliasIndex::new(&weights).unwrap();
+        let steps: &[&[(usize, u32)]] = &[
+            &[(1, 4)], &[(7, 0), (0, 9)], &[(6, 1)], &[], &[(3, 0), (3, 2)], &[(2, 100), (5, 0)],
+        ];
+        for &updates in steps {
+            for &(i, w) in updates {
+                weights[i] = w;
+            }
+            c.update_weights(updates).unwrap();
+            a.update_weights(updates).unwrap();
+            check_exact(&c, &a, &weights);
+            let (fresh_c, fresh_a) = (WeightedIndex::new(&weights).unwrap(), WeightedAliasIndex::new(&weights).unwrap());
+            let (mut r1, mut r2) = (rng.clone(), rng.clone());
+            for _ in 0..100 {
+                assert_eq!(c.sample(&mut r1), fresh_c.sample(&mut r2));
+                assert_eq!(a.sample(&mut r1), fresh_a.sample(&mut r2));
+            }
+        }
+        println!("update_weights: exact, same samples as a fresh build");
+    }
+    {
+        // Sampling agrees with the weights
+        let weights = [1, 2, 3, 4, 0, 10, 30, 50];
+        let c = WeightedIndex::new(&weights).unwrap();
+        let a = WeightedAliasIndex::new(&weights).unwrap();
+        let (mut cc, mut ac) = ([0u64; 8], [0u64; 8]);
+        for _ in 0..200_000 {
+            cc[c.sample(&mut rng)] += 1;
+            ac[a.sample(&mut rng)] += 1;
+        }
+        assert!(chi_squared_weighted(&cc, &weights) < chi_squared_limit(6), "cumulative: {:?}", cc);
+        assert!(chi_squared_weighted(&ac, &weights) < chi_squared_limit(6), "alias: {:?}", ac);
+        println!("sampling: matches the weights (zero weight never drawn)");
+    }
+}
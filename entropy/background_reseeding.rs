@@ -0,0 +1,217 @@
+//! Background asynchronous reseeding
+//! 
+//! A reseeding wrapper normally calls its entropy source inline, so the call
+//! to `next_u32` which crosses the threshold blocks for as long as the source
+//! takes. `BackgroundReseeding<R>` instead asks a worker thread for a new
+//! state when the threshold is crossed and keeps using the current state
+//! until the new one is ready; the swap is a plain move on the foreground
+//! thread (double buffering: the worker builds the whole new state, the
+//! foreground only ever sees complete states).
+//! 
+//! Guarantees (checked in `main`):
+//! 
+//! *   foreground calls never wait for the source
+//! *   once a new state is swapped in, no further output comes from the old
+//!     one: output after the swap is exactly `R::from_seed(new_seed)`'s stream
+//! 
+//! Thoughts: works with a plain `&mut self` `Rng` trait and a `SeedableRng`
+//! whose generators are `Send`; nothing needs to be shared. The price is that
+//! the threshold is soft (output continues from the old state until the worker
+//! delivers) and a thread per generator, which rules this out for `no_std` and
+//! makes it a poor fit for `thread_rng`. Source failures can't be reported
+//! from `next_u32`; here they are counted and the old state is kept.
+
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ——— traits ———
+
+#[derive(Debug)]
+pub struct Error;
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+}
+
+pub trait SeedableRng: Sized {
+    type Seed: Default + AsMut<[u8]>;
+    
+    fn from_seed(seed: Self::Seed) -> Self;
+}
+
+/// A (slow, fallible) source of seeds.
+pub trait EntropySource {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+// ——— wrapper ———
+
+/// Reseeds `R` from a background thread every `threshold` outputs.
+pub struct BackgroundReseeding<R> {
+    rng: R,
+    threshold: u64,
+    used: u64,
+    pending: bool,
+    generation: u64,
+    failures: u64,
+    requests: Sender<()>,
+    states: Receiver<Result<R, Error>>,
+}
+
+impl<R: SeedableRng+Send+'static> BackgroundReseeding<R> {
+    /// Start with `rng` and spawn a worker seeding from `source`.
+    pub fn new<S: EntropySource+Send+'static>(rng: R, source: S, threshold: u64) -> Self {
+        let (requests, req_rx) = channel::<()>();
+        let (state_tx, states) = channel();
+        thread::spawn(move || {
+            let mut source = source;
+            // Ends when the wrapper (and thus `requests`) is dropped.
+            for () in req_rx {
+                let mut seed = R::Seed::default();
+                let state = source.try_fill(seed.as_mut()).map(|()| R::from_seed(seed));
+                if state_tx.send(state).is_err() {
+                    break;
+                }
+            }
+        });
+        BackgroundReseeding {
+            rng, threshold, used: 0, pending: false, generation: 0, failures: 0,
+            requests, states,
+        }
+    }
+    
+    /// Number of completed reseeds.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    
+    /// Number of failed reseed attempts.
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+    
+    /// Swap in a new state if one is ready; never blocks.
+    fn poll_reseed(&mut self) {
+        if !self.pending {
+            if self.used < self.threshold {
+                return;
+            }
+            self.pending = self.requests.send(()).is_ok();
+        }
+        match self.states.try_recv() {
+            Ok(Ok(rng)) => {
+                self.rng = rng;
+                self.generation += 1;
+                self.used = 0;
+                self.pending = false;
+            }
+            Ok(Err(Error)) => {
+                // Keep the current state; ask again on the next call.
+                self.failures += 1;
+                self.pending = false;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.pending = false,
+        }
+    }
+}
+
+impl<R: Rng+SeedableRng+Send+'static> Rng for BackgroundReseeding<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.poll_reseed();
+        self.used += 1;
+        self.rng.next_u32()
+    }
+}
+
+// ——— test RNGs ———
+
+// Outputs `seed << 16 | n` for n = 0, 1, 2, ...: every output identifies the
+// state it came from.
+#[derive(Debug)]
+struct TaggedRng {
+    seed: u16,
+    n: u16,
+}
+
+impl Rng for TaggedRng {
+    fn next_u32(&mut self) -> u32 {
+        let x = (self.seed as u32) << 16 | self.n as u32;
+        self.n = self.n.wrapping_add(1);
+        x
+    }
+}
+
+impl SeedableRng for TaggedRng {
+    type Seed = [u8; 2];
+    
+    fn from_seed(seed: [u8; 2]) -> Self {
+        TaggedRng { seed: u16::from_le_bytes(seed), n: 0 }
+    }
+}
+
+// Yields seeds 1, 2, 3, ... after a delay; fails every `fail_every`-th call.
+struct SlowCountingSource {
+    next: u16,
+    delay: Duration,
+    fail_every: u16,
+    calls: u16,
+}
+
+impl EntropySource for SlowCountingSource {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        thread::sleep(self.delay);
+        self.calls += 1;
+        if self.fail_every != 0 && self.calls.is_multiple_of(self.fail_every) {
+            return Err(Error);
+        }
+        self.next += 1;
+        dest.copy_from_slice(&self.next.to_le_bytes());
+        Ok(())
+    }
+}
+
+// ——— usage ———
+
+fn main() {
+    {
+        // Foreground calls never block, even with a very slow source
+        let source = SlowCountingSource { next: 0, delay: Duration::from_millis(200), fail_every: 0, calls: 0 };
+        let mut r = BackgroundReseeding::new(TaggedRng::from_seed([0, 0]), source, 10);
+        let start = Instant::now();
+        for _ in 0..10_000 {
+            r.next_u32();
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed < Duration::from_millis(100), "foreground blocked: {:?}", elapsed);
+        assert_eq!(r.generation(), 0);
+        println!("10000 outputs while the source was busy: {:?}", elapsed);
+    }
+    {
+        // After each swap, all output comes from the new state, starting at
+        // the beginning of its stream
+        let source = SlowCountingSource { next: 0, delay: Duration::from_millis(1), fail_every: 3, calls: 0 };
+        let mut r = BackgroundReseeding::new(TaggedRng::from_seed([0, 0]), source, 100);
+        let mut last = (0u64, 0u32);
+        let start = Instant::now();
+        while r.generation() < 5 && start.elapsed() < Duration::from_secs(10) {
+            let x = r.next_u32();
+            let generation = r.generation();
+            if generation != last.0 {
+                // First output after a swap: start of the new stream
+                assert_eq!(x & 0xFFFF, 0, "swap did not start a fresh stream");
+                assert!(x >> 16 > last.1 >> 16, "output from stale state");
+            } else if last.1 != 0 || x != 0 {
+                // Otherwise: same state, next output. The threshold is soft,
+                // so a state may outlive 2^16 outputs: `n` wraps, the tag not
+                assert_eq!(x >> 16, last.1 >> 16, "output not from current state");
+                assert_eq!(x as u16, (last.1 as u16).wrapping_add(1), "output out of order");
+            }
+            last = (generation, x);
+        }
+        assert_eq!(r.generation(), 5);
+        assert!(r.failures() > 0);
+        println!("5 swaps, {} failed reseeds, no stale output", r.failures());
+    }
+}
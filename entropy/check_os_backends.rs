@@ -0,0 +1,107 @@
+//! Checks that exactly one `OsRng` backend is selected for each target
+//! 
+//! Compiles `os_backends/check.rs` for a list of target triples (and the
+//! `getrandom` feature, `os_rng_custom` hook and `os_rng_enclave` mode),
+//! asserting that it succeeds and that the expected backend is chosen. The
+//! check uses `#![no_core]`, so the targets' standard libraries need not be
+//! installed, but a nightly toolchain is required (invoked as `rustc +nightly`
+//! via rustup).
+//! 
+//! Run with:
+//! 
+//!     rustc check_os_backends.rs && ./check_os_backends
+//! 
+//! Thoughts: checking selection without building each backend is cheap
+//! enough to cover every tier-2 target; it does not prove a backend works on
+//! its target, only that the cfg table routes the target to exactly one.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const TARGETS: &[(&str, &[&str], &str)] = &[
+    ("x86_64-unknown-linux-gnu", &[], "linux"),
+    ("aarch64-unknown-linux-musl", &[], "linux"),
+    ("aarch64-linux-android", &[], "linux"),
+    ("x86_64-pc-windows-msvc", &[], "windows"),
+    ("x86_64-pc-windows-gnu", &[], "windows"),
+    ("x86_64-apple-darwin", &[], "macos"),
+    ("aarch64-apple-ios", &[], "macos"),
+    ("wasm32-wasip1", &[], "wasi"),
+    ("x86_64-unknown-freebsd", &[], "urandom"),
+    ("x86_64-unknown-netbsd", &[], "urandom"),
+    ("x86_64-unknown-openbsd", &[], "urandom"),
+    ("x86_64-unknown-illumos", &[], "urandom"),
+    ("x86_64-unknown-redox", &[], "urandom"),
+    ("wasm32-unknown-unknown", &[], "unsupported"),
+    ("x86_64-unknown-fuchsia", &[], "unsupported"),
+    ("x86_64-unknown-hermit", &[], "unsupported"),
+    ("thumbv7em-none-eabihf", &[], "unsupported"),
+    // The getrandom feature overrides all platform backends ...
+    ("x86_64-unknown-linux-gnu", &["--cfg", "feature=\"getrandom\""], "getrandom"),
+    ("wasm32-unknown-unknown", &["--cfg", "feature=\"getrandom\""], "getrandom"),
+    ("x86_64-unknown-fuchsia", &["--cfg", "feature=\"getrandom\""], "getrandom"),
+    // ... the custom hook overrides those ...
+    ("x86_64-unknown-linux-gnu", &["--cfg", "os_rng_custom"], "custom"),
+    ("thumbv7em-none-eabihf", &["--cfg", "os_rng_custom",
+        "--cfg", "feature=\"getrandom\""], "custom"),
+    // ... and enclave mode overrides everything
+    ("x86_64-unknown-linux-gnu", &["--cfg", "os_rng_enclave"], "rdrand"),
+    ("x86_64-fortanix-unknown-sgx", &["--cfg", "os_rng_enclave"], "rdrand"),
+    ("i686-pc-windows-msvc", &["--cfg", "os_rng_enclave",
+        "--cfg", "os_rng_custom", "--cfg", "feature=\"getrandom\""], "rdrand"),
+];
+
+/// Compile check.rs for `target`; return compiler errors on failure.
+fn check(dir: &Path, target: &str, args: &[&str], expected: &str) -> Result<(), String> {
+    let mut src = std::fs::read_to_string(dir.join("check.rs")).unwrap();
+    src.push_str(&format!("pub use self::selected::{} as expected;\n", expected));
+    
+    let out = std::env::temp_dir().join("check_os_backends.rmeta");
+    let mut child = Command::new("rustc")
+        .current_dir(dir)
+        .args(&["+nightly", "--target", target, "--emit=metadata", "-o"])
+        .arg(&out)
+        .args(args)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run rustc +nightly");
+    child.stdin.take().unwrap().write_all(src.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr.lines().filter(|l| l.starts_with("error")).collect::<Vec<_>>().join("\n"))
+    }
+}
+
+/// `os_backends/`, whatever the working directory: `file!()` is relative to
+/// where this was compiled, which is the manifest directory under Cargo and
+/// otherwise where `rustc` put the executable (as in the run line above).
+fn backends_dir() -> PathBuf {
+    let base = match option_env!("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::current_exe().unwrap().parent().unwrap().to_path_buf(),
+    };
+    base.join(file!()).parent().unwrap().join("os_backends")
+}
+
+fn main() {
+    let dir = backends_dir();
+    let mut failures = 0;
+    for &(target, args, expected) in TARGETS {
+        let label = format!("{} {}", target, args.join(" "));
+        match check(&dir, target, args, expected) {
+            Ok(()) => println!("{}: {}", label.trim_end(), expected),
+            Err(e) => {
+                println!("{}: FAILED (expected {})\n{}", label.trim_end(), expected, e);
+                failures += 1;
+            }
+        }
+    }
+    assert_eq!(failures, 0, "backend selection failed for {} target(s)", failures);
+}
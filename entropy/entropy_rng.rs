@@ -0,0 +1,283 @@
+//! `EntropyRng`: the OS generator, with a fallback
+//! 
+//! `EntropyRng<P, F>` fills from its primary source `P`, `OsRng` of
+//! `traits/os.rs` by default, and if that fails, from the fallback `F`. The
+//! error's kind (`traits/error.rs`) says whether to try the primary again
+//! next time; `last_source` and `primary_error` say what happened. It is a
+//! `CryptoRng` if both its sources are.
+//! 
+//! Thoughts: recovery inside a generator needs the error's kind: with an
+//! empty marker error it can't tell early boot from a missing syscall. The
+//! recovery is invisible through the traits, so a weaker fallback is a
+//! silent downgrade, hence `last_source`, which a generic caller can't reach.
+
+use std::fmt;
+
+// ——— traits ———
+
+#[path = "../traits/error.rs"]
+#[allow(dead_code)]
+mod error;
+use self::error::{Error, ErrorKind};
+
+#[path = "../traits/os.rs"]
+#[allow(dead_code)]
+mod os;
+use self::os::{OsError, OsRng};
+
+#[path = "jitter.rs"]
+#[allow(dead_code)]
+mod jitter;
+use self::jitter::JitterRng;
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("Rng::fill failed: {}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+pub trait CryptoRng: Rng {}
+
+// ——— OsRng ———
+
+impl From<OsError> for Error {
+    fn from(e: OsError) -> Error {
+        match e {
+            OsError::NotReady => Error::new(ErrorKind::NotReady, "OS pool not initialised"),
+            OsError::Unavailable => Error::new(ErrorKind::Unavailable, "no OS generator"),
+            OsError::Code(_) => Error::new(ErrorKind::Unexpected, "OS generator failed"),
+        }
+    }
+}
+
+impl Rng for OsRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        OsRng::try_fill(self, dest).map_err(Error::from)
+    }
+}
+
+impl CryptoRng for OsRng {}
+
+impl Rng for JitterRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        JitterRng::try_fill(self, dest)
+    }
+}
+
+impl CryptoRng for JitterRng {}
+
+// ——— EntropyRng ———
+
+/// Which source filled a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Used {
+    Primary,
+    Fallback,
+}
+
+impl fmt::Display for Used {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Used::Primary => write!(f, "primary"),
+            Used::Fallback => write!(f, "fallback"),
+        }
+    }
+}
+
+/// Fills from `P`, and from `F` if `P` fails. After `NotReady` or `Transient`
+/// the next request tries `P` again; after `Unavailable` or `Unexpected`, `P`
+/// is given up for good. A request `P` fails part way is filled again from
+/// the start by `F`, so no byte comes from both.
+#[derive(Debug)]
+pub struct EntropyRng<P, F> {
+    primary: P,
+    fallback: F,
+    /// Set once the primary fails for good.
+    given_up: bool,
+    last_source: Option<Used>,
+    primary_error: Option<Error>,
+}
+
+impl<F: Rng> EntropyRng<OsRng, F> {
+    /// `OsRng`, not blocking in early boot, then `fallback`.
+    pub fn new(fallback: F) -> Self {
+        EntropyRng::with_primary(OsRng::nonblocking(), fallback)
+    }
+}
+
+impl<P: Rng, F: Rng> EntropyRng<P, F> {
+    pub fn with_primary(primary: P, fallback: F) -> Self {
+        EntropyRng { primary, fallback, given_up: false, last_source: None, primary_error: None }
+    }
+    
+    /// The source which filled the last request, if it succeeded.
+    pub fn last_source(&self) -> Option<Used> {
+        self.last_source
+    }
+    
+    /// The primary's last error, if it has failed.
+    pub fn primary_error(&self) -> Option<Error> {
+        self.primary_error
+    }
+    
+    /// True once the primary has failed for good.
+    pub fn primary_given_up(&self) -> bool {
+        self.given_up
+    }
+}
+
+impl<P: Rng, F: Rng> Rng for EntropyRng<P, F> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.last_source = None;
+        if !self.given_up {
+            match self.primary.try_fill(dest) {
+                Ok(()) => {
+                    self.last_source = Some(Used::Primary);
+                    return Ok(());
+                }
+                Err(e) => {
+                    self.given_up = !e.should_retry();
+                    self.primary_error = Some(e);
+                }
+            }
+        }
+        self.fallback.try_fill(dest)?;
+        self.last_source = Some(Used::Fallback);
+        Ok(())
+    }
+}
+
+impl<P: CryptoRng, F: CryptoRng> CryptoRng for EntropyRng<P, F> {}
+
+// ——— test RNGs ———
+
+/// Fails with each of `script`'s kinds in turn (`None` succeeds), writing
+/// `byte` first; then fills with `byte`.
+struct Scripted {
+    byte: u8,
+    script: Vec<Option<ErrorKind>>,
+    calls: usize,
+}
+
+impl Scripted {
+    fn new(byte: u8, script: &[Option<ErrorKind>]) -> Scripted {
+        Scripted { byte, script: script.iter().rev().cloned().collect(), calls: 0 }
+    }
+}
+
+impl Rng for Scripted {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.calls += 1;
+        for b in dest.iter_mut() {
+            *b = self.byte;
+        }
+        match self.script.pop() {
+            Some(Some(kind)) => Err(Error::new(kind, "scripted failure")),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl CryptoRng for Scripted {}
+
+// ——— consumers ———
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) -> Result<[u8; 4], Error> {
+    let mut key = [0; 4];
+    rng.try_fill(&mut key)?;
+    Ok(key)
+}
+
+// ——— usage ———
+
+fn main() {
+    {
+        // The OS generator, where there is one: the fallback isn't touched
+        let mut rng = EntropyRng::new(Scripted::new(0xee, &[]));
+        let mut buf = [0u8; 32];
+        match rng.try_fill(&mut buf) {
+            Ok(()) if rng.last_source() == Some(Used::Primary) => {
+                assert!(buf != [0xee; 32] && rng.fallback.calls == 0);
+            }
+            Ok(()) => assert_eq!(buf, [0xee; 32]),
+            Err(e) => panic!("EntropyRng failed: {}", e),
+        }
+        println!("EntropyRng over {}: {} filled {:?}", OsRng::backend(),
+            rng.last_source().unwrap(), &buf[..8]);
+    }
+    {
+        // Not ready, then interrupted: each request falls back, the next
+        // tries the primary again
+        let script = [Some(ErrorKind::NotReady), Some(ErrorKind::Transient), None];
+        let mut rng = EntropyRng::with_primary(Scripted::new(1, &script), Scripted::new(2, &[]));
+        assert_eq!(key(&mut rng).unwrap(), [2; 4]);
+        assert_eq!(rng.last_source(), Some(Used::Fallback));
+        assert_eq!(rng.primary_error().unwrap().kind, ErrorKind::NotReady);
+        assert_eq!(key(&mut rng).unwrap(), [2; 4]);
+        assert_eq!(rng.primary_error().unwrap().kind, ErrorKind::Transient);
+        assert_eq!(key(&mut rng).unwrap(), [1; 4]);
+        assert_eq!(rng.last_source(), Some(Used::Primary));
+        assert!(!rng.primary_given_up());
+        println!("NotReady, Transient: fell back twice, then primary again");
+    }
+    {
+        // Unavailable: given up, and not called again
+        let mut rng = EntropyRng::with_primary(
+            Scripted::new(1, &[Some(ErrorKind::Unavailable)]), Scripted::new(2, &[]));
+        for _ in 0..3 {
+            assert_eq!(key(&mut rng).unwrap(), [2; 4]);
+            assert_eq!(rng.last_source(), Some(Used::Fallback));
+        }
+        assert!(rng.primary_given_up());
+        assert_eq!(rng.primary.calls, 1);
+        println!("Unavailable: primary given up after 1 call, fallback used 3 times");
+    }
+    {
+        // Both failing: the fallback's error, with the primary's kept
+        let mut rng = EntropyRng::with_primary(
+            Scripted::new(1, &[Some(ErrorKind::NotReady)]),
+            Scripted::new(2, &[Some(ErrorKind::Unexpected)]));
+        let e = key(&mut rng).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Unexpected);
+        assert_eq!(rng.primary_error().unwrap().kind, ErrorKind::NotReady);
+        assert_eq!(rng.last_source(), None);
+        println!("both failing: Err({}), primary: {}", e, rng.primary_error().unwrap());
+    }
+    {
+        // The OS error's kinds, as the designs' error
+        assert_eq!(Error::from(OsError::NotReady).kind, ErrorKind::NotReady);
+        assert_eq!(Error::from(OsError::Unavailable).kind, ErrorKind::Unavailable);
+        assert_eq!(Error::from(OsError::Code(5)).kind, ErrorKind::Unexpected);
+        println!("OsError mapping: ok");
+    }
+    {
+        // The chain as deployed: OsRng, then JitterRng if its self-test
+        // passes here; with the primary gone, JitterRng fills
+        match JitterRng::new() {
+            Ok(jitter) => {
+                let mut rng = EntropyRng::new(jitter);
+                key(&mut rng).unwrap();
+                let jitter = JitterRng::new().unwrap();
+                let mut rng = EntropyRng::with_primary(
+                    Scripted::new(1, &[Some(ErrorKind::Unavailable)]), jitter);
+                let k = key(&mut rng).unwrap();
+                assert_eq!(rng.last_source(), Some(Used::Fallback));
+                assert!(k != [1; 4]);
+                println!("OsRng then JitterRng: {:?} from the fallback", k);
+            }
+            Err(e) => println!("OsRng then JitterRng: no JitterRng here, {}", e.description()),
+        }
+    }
+    {
+        // Through the trait, as a trait object
+        let mut rng = EntropyRng::with_primary(
+            Scripted::new(1, &[Some(ErrorKind::Unavailable)]), Scripted::new(2, &[]));
+        let r = &mut rng as &mut dyn CryptoRng;
+        assert_eq!(key(r).unwrap(), [2; 4]);
+        println!("EntropyRng, dynamic dispatch, using CryptoRng: ok");
+    }
+}
@@ -0,0 +1,235 @@
+// `JitterRng`: entropy from CPU timing jitter, as jitterentropy.
+//
+// Included by `jitter_rng.rs` and `entropy_rng.rs`, each of which has an
+// `error` module (`traits/error.rs`) and implements its own traits for
+// `JitterRng` over `try_fill`.
+//
+// Each measurement times a noise step (memory accesses over a buffer), and
+// folds the time's delta into a 64-bit pool by LFSR; one word of output is
+// `rounds` measurements, `rounds` chosen by the self-test from how much the
+// deltas vary. The timer is a parameter, `fn() -> u64` in nanoseconds or any
+// finer unit, so that a test can script it.
+
+use super::error::{Error, ErrorKind};
+use std::fmt;
+use std::ptr;
+
+/// Why the self-test rejected the timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerError {
+    /// The timer returned zero.
+    NoTimer,
+    /// The timer doesn't resolve the noise step (deltas of zero, or all
+    /// multiples of 100).
+    CoarseTimer,
+    /// The timer ran backwards more than a few times.
+    NotMonotonic,
+    /// The deltas hardly vary: too little entropy per measurement.
+    TinyVariations,
+    /// Most deltas were the same as the one before.
+    TooManyStuck,
+}
+
+impl TimerError {
+    pub fn description(self) -> &'static str {
+        match self {
+            TimerError::NoTimer => "no timer available",
+            TimerError::CoarseTimer => "timer too coarse",
+            TimerError::NotMonotonic => "timer not monotonic",
+            TimerError::TinyVariations => "timer variations too small",
+            TimerError::TooManyStuck => "too many stuck timer results",
+        }
+    }
+}
+
+impl From<TimerError> for Error {
+    fn from(e: TimerError) -> Error {
+        Error::new(ErrorKind::Unavailable, e.description())
+    }
+}
+
+/// Measurements in the self-test, after the first `CLEARCACHE` warm-up ones.
+const TESTLOOPCOUNT: u64 = 300;
+const CLEARCACHE: u64 = 100;
+/// Stuck measurements in a row before a fill gives up.
+const MAX_STUCK: u32 = 1000;
+const MEMORY_SIZE: usize = 2048;
+
+/// A timing-jitter entropy collector.
+pub struct JitterRng {
+    timer: fn() -> u64,
+    rounds: u32,
+    data: u64,
+    prev_time: u64,
+    last_delta: i64,
+    last_delta2: i64,
+    mem: [u8; MEMORY_SIZE],
+    mem_index: usize,
+}
+
+impl fmt::Debug for JitterRng {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "JitterRng {{ rounds: {} }}", self.rounds)
+    }
+}
+
+impl JitterRng {
+    /// A collector over the system clock, if it passes the self-test.
+    pub fn new() -> Result<JitterRng, TimerError> {
+        JitterRng::new_with_timer(system_timer)
+    }
+    
+    /// A collector over `timer`, if it passes the self-test.
+    pub fn new_with_timer(timer: fn() -> u64) -> Result<JitterRng, TimerError> {
+        let mut rng = JitterRng {
+            timer,
+            rounds: 64,
+            data: 0,
+            prev_time: 0,
+            last_delta: 0,
+            last_delta2: 0,
+            mem: [0; MEMORY_SIZE],
+            mem_index: 0,
+        };
+        rng.rounds = rng.test_timer()?;
+        rng.prev_time = (rng.timer)();
+        Ok(rng)
+    }
+    
+    /// Measurements per word of output.
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+    
+    /// Fills `dest`, slowly: `rounds` timed measurements per eight bytes.
+    pub fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for chunk in dest.chunks_mut(8) {
+            let x = self.gen_entropy()?;
+            chunk.copy_from_slice(&x.to_le_bytes()[..chunk.len()]);
+        }
+        Ok(())
+    }
+    
+    /// A word of `rounds` measurements, none of them stuck.
+    fn gen_entropy(&mut self) -> Result<u64, Error> {
+        let mut stuck = 0;
+        let mut done = 0;
+        while done < self.rounds {
+            if self.measure_jitter() {
+                done += 1;
+                stuck = 0;
+            } else {
+                stuck += 1;
+                if stuck == MAX_STUCK {
+                    return Err(Error::new(ErrorKind::Unexpected, "timer stuck"));
+                }
+            }
+        }
+        Ok(self.data)
+    }
+    
+    /// One measurement, folded into the pool; false if it was stuck.
+    fn measure_jitter(&mut self) -> bool {
+        self.memaccess();
+        let time = (self.timer)();
+        let delta = time.wrapping_sub(self.prev_time) as i64;
+        self.prev_time = time;
+        let stuck = self.stuck(delta);
+        self.data = lfsr_time(self.data, delta as u64);
+        !stuck
+    }
+    
+    /// A delta is stuck if it, its change, or its change's change, is zero.
+    fn stuck(&mut self, delta: i64) -> bool {
+        let delta2 = self.last_delta.wrapping_sub(delta);
+        let delta3 = self.last_delta2.wrapping_sub(delta2);
+        self.last_delta = delta;
+        self.last_delta2 = delta2;
+        delta == 0 || delta2 == 0 || delta3 == 0
+    }
+    
+    /// The noise step: reads and writes over the buffer, which the
+    /// compiler must not remove.
+    fn memaccess(&mut self) {
+        for _ in 0..128 {
+            self.mem_index = (self.mem_index + 7) % MEMORY_SIZE;
+            let p = &mut self.mem[self.mem_index] as *mut u8;
+            unsafe { ptr::write_volatile(p, ptr::read_volatile(p).wrapping_add(1)) };
+        }
+    }
+    
+    /// Runs the timer through the checks of jitterentropy, and estimates
+    /// the measurements needed for 64 bits.
+    fn test_timer(&mut self) -> Result<u32, TimerError> {
+        let mut delta_sum = 0u64;
+        let mut old_delta = 0i64;
+        let mut time_backwards = 0;
+        let mut count_mod = 0;
+        let mut count_stuck = 0;
+        for i in 0..(CLEARCACHE + TESTLOOPCOUNT) {
+            let time = (self.timer)();
+            self.memaccess();
+            self.data = lfsr_time(self.data, time);
+            let time2 = (self.timer)();
+            if time == 0 || time2 == 0 {
+                return Err(TimerError::NoTimer);
+            }
+            let delta = time2.wrapping_sub(time) as i64;
+            if delta == 0 {
+                return Err(TimerError::CoarseTimer);
+            }
+            let stuck = self.stuck(delta);
+            if i < CLEARCACHE {
+                continue;
+            }
+            if stuck {
+                count_stuck += 1;
+            }
+            if time2 < time {
+                time_backwards += 1;
+            }
+            if delta % 100 == 0 {
+                count_mod += 1;
+            }
+            delta_sum = delta_sum.saturating_add(delta.wrapping_sub(old_delta).unsigned_abs());
+            old_delta = delta;
+        }
+        if time_backwards > 3 {
+            return Err(TimerError::NotMonotonic);
+        }
+        if delta_sum < TESTLOOPCOUNT {
+            return Err(TimerError::TinyVariations);
+        }
+        if count_mod > TESTLOOPCOUNT * 9 / 10 {
+            return Err(TimerError::CoarseTimer);
+        }
+        if count_stuck > TESTLOOPCOUNT * 9 / 10 {
+            return Err(TimerError::TooManyStuck);
+        }
+        // Bits per measurement: the log2 of the mean change of the delta,
+        // counted as entropy; at least one, at most 64
+        let bits = 63 - (delta_sum / TESTLOOPCOUNT).max(1).leading_zeros();
+        let bits = bits.clamp(1, 64);
+        Ok(64u32.div_ceil(bits))
+    }
+}
+
+/// Folds `time` into `data` a bit at a time, by an LFSR of polynomial
+/// x^64 + x^61 + x^56 + x^31 + x^28 + x^23 + 1.
+fn lfsr_time(mut data: u64, time: u64) -> u64 {
+    for i in 0..64 {
+        data ^= (time >> i) & 1;
+        let feedback = (data >> 63) ^ (data >> 60) ^ (data >> 55)
+            ^ (data >> 30) ^ (data >> 27) ^ (data >> 22);
+        data = data << 1 | (feedback & 1);
+    }
+    data
+}
+
+/// Nanoseconds since the Unix epoch.
+pub fn system_timer() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() * 1_000_000_000 + d.subsec_nanos() as u64)
+        .unwrap_or(0)
+}
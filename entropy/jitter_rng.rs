@@ -0,0 +1,191 @@
+//! `JitterRng`: a slow, fallible `CryptoRng` from CPU timing jitter
+//! 
+//! With no OS generator (bare metal, an early-boot hook, a sandbox blocking
+//! the syscall), the one source left is the machine itself: how long a
+//! memory-bound noise step takes varies, by cache and pipeline state, in the
+//! low bits of a fine timer. `JitterRng` (`jitter.rs`, after jitterentropy
+//! and rand 0.4's) collects those variations: each word of output folds
+//! `rounds` timed measurements into a 64-bit pool.
+//! 
+//! It can fail in two places:
+//! 
+//! *   at construction, the self-test: the timer must exist, resolve the
+//!     noise step, run forwards, and vary enough; otherwise `new` returns a
+//!     `TimerError` (as the shared error, `Unavailable`), and there is no
+//!     generator at all. The self-test also sets `rounds`
+//! *   while filling, if the timer gets stuck (the same delta over and over):
+//!     `Unexpected`, after `MAX_STUCK` measurements in a row
+//! 
+//! The timer is a `fn() -> u64`, the system clock by default; `main` scripts
+//! one for each failure.
+//! 
+//! Run with:
+//! 
+//!     rustc jitter_rng.rs && ./jitter_rng
+//! 
+//! Thoughts: a generator whose construction can fail fits every design, as
+//! construction is outside the traits: `new` returns a `Result`, and once
+//! built, `JitterRng` is a fallible `CryptoRng` like `OsRng`. What doesn't
+//! fit is cost. A word takes `rounds` measurements (here microseconds; in
+//! rand 0.4, where it counted entropy more cautiously, a millisecond and
+//! more), so the infallible `fill` of designs that panic on failure is the
+//! wrong interface to hand it: it should seed a fast generator once, never
+//! serve requests. Its place is the fallback of `EntropyRng`
+//! (`entropy_rng.rs`), where the self-test's failure means the chain ends
+//! there. The entropy estimate is the weak point: the self-test counts the
+//! log2 of the mean change in delta as bits, which a predictable but noisy
+//! timer would pass; jitterentropy's health tests go further.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// ——— traits ———
+
+#[path = "../traits/error.rs"]
+#[allow(dead_code)]
+mod error;
+use self::error::{Error, ErrorKind};
+
+#[path = "jitter.rs"]
+#[allow(dead_code)]
+mod jitter;
+use self::jitter::{JitterRng, TimerError};
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("Rng::fill failed: {}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+pub trait CryptoRng: Rng {}
+
+// ——— impls ———
+
+impl Rng for JitterRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        JitterRng::try_fill(self, dest)
+    }
+}
+
+impl CryptoRng for JitterRng {}
+
+// ——— test RNGs ———
+
+// Scripted timers: `fn() -> u64`, so their state is static
+
+static TIME: AtomicU64 = AtomicU64::new(1_000_000);
+static STATE: AtomicU64 = AtomicU64::new(0x2545_f491_4f6c_dd1d);
+static STUCK: AtomicBool = AtomicBool::new(false);
+static STEP: AtomicU64 = AtomicU64::new(0);
+
+/// A timer stepping by a pseudo-random 1 to 1024 units, or by 37 once
+/// `STUCK` is set.
+fn noisy_timer() -> u64 {
+    let step = if STUCK.load(Ordering::Relaxed) {
+        37
+    } else {
+        // xorshift64
+        let mut x = STATE.load(Ordering::Relaxed);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        STATE.store(x, Ordering::Relaxed);
+        1 + (x & 1023)
+    };
+    TIME.fetch_add(step, Ordering::Relaxed) + step
+}
+
+fn zero_timer() -> u64 {
+    0
+}
+
+/// A clock ticking in hundreds, resolving nothing finer: steps of 100, 200
+/// and 300 in turn.
+fn coarse_timer() -> u64 {
+    let step = 100 * (1 + STEP.fetch_add(1, Ordering::Relaxed) % 3);
+    TIME.fetch_add(step, Ordering::Relaxed) + step
+}
+
+/// A timer running backwards.
+fn backwards_timer() -> u64 {
+    TIME.fetch_sub(3, Ordering::Relaxed) - 3
+}
+
+/// A timer stepping by 2: it resolves the noise step, but never varies.
+fn steady_timer() -> u64 {
+    TIME.fetch_add(2, Ordering::Relaxed) + 2
+}
+
+/// A timer whose steps grow by 5: varying, but so predictably that every
+/// delta's change of change is zero.
+fn stuck_timer() -> u64 {
+    let step = 5 * (1 + STEP.fetch_add(1, Ordering::Relaxed));
+    TIME.fetch_add(step, Ordering::Relaxed) + step
+}
+
+// ——— consumers ———
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) -> Result<[u8; 16], Error> {
+    let mut key = [0; 16];
+    rng.try_fill(&mut key)?;
+    Ok(key)
+}
+
+// ——— usage ———
+
+fn main() {
+    {
+        // The system clock: a generator, if it passes the self-test here
+        match JitterRng::new() {
+            Ok(mut rng) => {
+                let (a, b) = (key(&mut rng).unwrap(), key(&mut rng).unwrap());
+                assert!(a != b);
+                println!("system timer: {:?}, {:?}", rng, &a[..8]);
+            }
+            Err(e) => println!("system timer: self-test failed, {}", e.description()),
+        }
+    }
+    {
+        // A noisy scripted timer passes, and gives different output from
+        // different timings
+        let mut rng = JitterRng::new_with_timer(noisy_timer).unwrap();
+        assert!(rng.rounds() > 1 && rng.rounds() <= 64);
+        let (a, b) = (key(&mut rng).unwrap(), key(&mut rng).unwrap());
+        assert!(a != b);
+        println!("noisy timer: {} rounds a word, {:?}", rng.rounds(), &a[..8]);
+        
+        // Stuck while filling: the fill fails, the generator stays usable
+        STUCK.store(true, Ordering::Relaxed);
+        let e = key(&mut rng).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Unexpected);
+        STUCK.store(false, Ordering::Relaxed);
+        key(&mut rng).unwrap();
+        println!("noisy timer, stuck while filling: Err({}), then recovered", e);
+    }
+    {
+        // Each self-test failure, at construction
+        let cases: [(fn() -> u64, TimerError); 5] = [
+            (zero_timer, TimerError::NoTimer),
+            (coarse_timer, TimerError::CoarseTimer),
+            (backwards_timer, TimerError::NotMonotonic),
+            (steady_timer, TimerError::TinyVariations),
+            (stuck_timer, TimerError::TooManyStuck),
+        ];
+        for &(timer, want) in cases.iter() {
+            let e = JitterRng::new_with_timer(timer).unwrap_err();
+            assert_eq!(e, want);
+            assert_eq!(Error::from(e).kind, ErrorKind::Unavailable);
+            println!("self-test: {:?}, {}", e, e.description());
+        }
+    }
+    {
+        // Through the trait, as a trait object
+        let mut rng = JitterRng::new_with_timer(noisy_timer).unwrap();
+        let r = &mut rng as &mut dyn CryptoRng;
+        let k = key(r).unwrap();
+        println!("JitterRng, dynamic dispatch, using CryptoRng: {:?}", &k[..8]);
+    }
+}
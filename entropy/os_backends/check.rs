@@ -0,0 +1,32 @@
+// Compiles if and only if exactly one backend is selected for the target.
+// 
+// Needs no standard library, so it can be checked for any target triple
+// without installing it:
+// 
+//     rustc +nightly --target TRIPLE --emit=metadata check.rs
+// 
+// Two selected backends give "the name `selected` is defined multiple times";
+// none gives "unresolved import `self::selected`". Appending
+// `pub use self::selected::NAME as expected;` also checks which backend was
+// selected. Usually run via `check_os_backends.rs`.
+
+#![feature(no_core)]
+#![no_core]
+#![crate_type = "lib"]
+
+#[path = "table.rs"]
+#[macro_use]
+mod table;
+
+macro_rules! selected {
+    ($(#[cfg($pred:meta)] $name:ident $path:tt;)*) => {
+        $(
+            #[cfg($pred)]
+            pub mod selected { pub mod $name {} }
+        )*
+    };
+}
+
+os_backends!(selected);
+
+pub use self::selected as check;
@@ -0,0 +1,17 @@
+// Backend: a function supplied by the program, selected with
+// `--cfg os_rng_custom`. The program must define:
+// 
+//     #[no_mangle]
+//     pub fn os_rng_custom_fill(dest: &mut [u8]) -> Result<(), Error> { ... }
+
+use super::Error;
+
+pub const NAME: &str = "custom";
+
+extern "Rust" {
+    fn os_rng_custom_fill(dest: &mut [u8]) -> Result<(), Error>;
+}
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    unsafe { os_rng_custom_fill(dest) }
+}
@@ -0,0 +1,11 @@
+// Backend: the getrandom crate (0.2), covering all platforms it supports.
+// Error conversion lives with `Error`, since it is needed whenever the
+// feature is enabled.
+
+use super::Error;
+
+pub const NAME: &str = "getrandom crate";
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    ::getrandom::getrandom(dest).map_err(Error::from)
+}
@@ -0,0 +1,12 @@
+// Backend: the getrandom(2) system call (Linux ≥ 3.17, Android), via libc.
+// 
+// Blocks until the kernel's pool is initialised (early boot).
+
+use super::{sys, Error};
+use std::io;
+
+pub const NAME: &str = "linux getrandom(2)";
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    sys::fill(dest, false).map_err(|code| io::Error::from_raw_os_error(code).into())
+}
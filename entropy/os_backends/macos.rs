@@ -0,0 +1,10 @@
+// Backend: getentropy(2) (macOS ≥ 10.12, iOS), at most 256 bytes per call.
+
+use super::{sys, Error};
+use std::io;
+
+pub const NAME: &str = "macos getentropy";
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    sys::fill(dest, false).map_err(|code| io::Error::from_raw_os_error(code).into())
+}
@@ -0,0 +1,179 @@
+// Backend: RDRAND only, for enclaves (e.g. SGX) where there is no OS to ask
+// (`--cfg os_rng_enclave`).
+// 
+// Health checks are mandatory, since nothing else vouches for the hardware:
+// 
+// *   startup: before the first output, `SELF_TEST_WORDS` words are drawn and
+//     must be distinct and neither 0 nor !0 (some AMD CPUs return !0 with the
+//     success flag set after suspend/resume)
+// *   continuous: each word must differ from the previous one
+// 
+// A failed check is sticky: every later request fails with `HealthCheck`.
+// Underflow (no word after `RETRIES` attempts) is not, and reports `NotReady`.
+// 
+// With `--cfg os_rng_enclave_sim` the instruction is replaced by a
+// programmable software source (`sim`), so the checks can be exercised on any
+// machine, with or without RDRAND.
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", os_rng_enclave_sim)))]
+compile_error!("os_rng_enclave requires x86 or x86_64 (RDRAND), or os_rng_enclave_sim");
+
+use super::{Error, ErrorKind};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+pub const NAME: &str = if cfg!(os_rng_enclave_sim) { "rdrand (simulated)" } else { "rdrand" };
+
+/// Attempts per word before giving up, as recommended by Intel.
+const RETRIES: usize = 10;
+/// Words drawn by the startup self-test.
+const SELF_TEST_WORDS: usize = 8;
+
+const UNTESTED: u8 = 0;
+const PASSED: u8 = 1;
+const FAILED: u8 = 2;
+
+static STATE: AtomicU8 = AtomicU8::new(UNTESTED);
+static LAST: AtomicU64 = AtomicU64::new(0);
+
+fn health_failure() -> Error {
+    STATE.store(FAILED, Ordering::Release);
+    Error::new(ErrorKind::HealthCheck)
+}
+
+/// One word, retrying on underflow.
+fn next_word() -> Result<u64, Error> {
+    for _ in 0..RETRIES {
+        if let Some(x) = raw::rdrand64() {
+            return Ok(x);
+        }
+    }
+    Err(Error::new(ErrorKind::NotReady))
+}
+
+/// One word passing the continuous test.
+fn checked_word() -> Result<u64, Error> {
+    let x = next_word()?;
+    if x == 0 || x == !0 || LAST.swap(x, Ordering::Relaxed) == x {
+        return Err(health_failure());
+    }
+    Ok(x)
+}
+
+fn self_test() -> Result<(), Error> {
+    if !raw::is_supported() {
+        return Err(Error::new(ErrorKind::Unavailable));
+    }
+    let mut words = [0u64; SELF_TEST_WORDS];
+    for i in 0..words.len() {
+        words[i] = checked_word()?;
+        if words[..i].contains(&words[i]) {
+            return Err(health_failure());
+        }
+    }
+    // Two threads may both run the test; that is harmless.
+    STATE.store(PASSED, Ordering::Release);
+    Ok(())
+}
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    match STATE.load(Ordering::Acquire) {
+        PASSED => (),
+        FAILED => return Err(Error::new(ErrorKind::HealthCheck)),
+        _ => self_test()?,
+    }
+    for chunk in dest.chunks_mut(8) {
+        let bytes = checked_word()?.to_le_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+    Ok(())
+}
+
+#[cfg(not(os_rng_enclave_sim))]
+mod raw {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::_rdrand32_step;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::_rdrand64_step;
+    
+    pub fn is_supported() -> bool {
+        // CPUID traps inside SGX enclaves: build those with
+        // `-C target-feature=+rdrand` so this is decided at compile time.
+        cfg!(target_feature = "rdrand") || is_x86_feature_detected!("rdrand")
+    }
+    
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "rdrand")]
+    unsafe fn step(x: &mut u64) -> bool {
+        _rdrand64_step(x) == 1
+    }
+    
+    #[cfg(target_arch = "x86")]
+    #[target_feature(enable = "rdrand")]
+    unsafe fn step(x: &mut u64) -> bool {
+        let (mut lo, mut hi) = (0, 0);
+        let ok = _rdrand32_step(&mut lo) == 1 && _rdrand32_step(&mut hi) == 1;
+        *x = (hi as u64) << 32 | lo as u64;
+        ok
+    }
+    
+    /// Only called after `is_supported` returned true.
+    pub fn rdrand64() -> Option<u64> {
+        let mut x = 0;
+        if unsafe { step(&mut x) } { Some(x) } else { None }
+    }
+}
+
+#[cfg(os_rng_enclave_sim)]
+use self::sim as raw;
+
+/// Simulated RDRAND, for testing the health checks.
+#[cfg(os_rng_enclave_sim)]
+pub mod sim {
+    use std::sync::Mutex;
+    use std::sync::atomic::Ordering;
+    
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Mode {
+        /// Works (output of a xorshift generator).
+        Healthy,
+        /// The CPU has no RDRAND.
+        Missing,
+        /// Always reports underflow.
+        Underflow,
+        /// Reports success but always returns the given word.
+        Stuck(u64),
+    }
+    
+    static SIM: Mutex<(Mode, u64)> = Mutex::new((Mode::Healthy, 0x9E37_79B9_7F4A_7C15));
+    
+    /// Change behaviour from the next instruction on.
+    pub fn set_mode(mode: Mode) {
+        SIM.lock().unwrap().0 = mode;
+    }
+    
+    /// Forget health-check results, as when a new enclave starts.
+    pub fn power_cycle() {
+        super::STATE.store(super::UNTESTED, Ordering::Release);
+        super::LAST.store(0, Ordering::Relaxed);
+    }
+    
+    pub(super) fn is_supported() -> bool {
+        SIM.lock().unwrap().0 != Mode::Missing
+    }
+    
+    pub(super) fn rdrand64() -> Option<u64> {
+        let mut sim = SIM.lock().unwrap();
+        match sim.0 {
+            Mode::Healthy => {
+                let mut x = sim.1;
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                sim.1 = x;
+                Some(x)
+            }
+            Mode::Missing | Mode::Underflow => None,
+            Mode::Stuck(x) => Some(x),
+        }
+    }
+}
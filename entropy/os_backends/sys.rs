@@ -0,0 +1,123 @@
+// The system calls behind the platform backends, on `core` alone.
+// 
+// Shared by `linux.rs`, `macos.rs` and `windows.rs` here, which keep the
+// failure as an `io::Error`, and by `traits/os.rs`, which classifies it by
+// the errno values below. `fill` retries `EINTR` and otherwise fails with the
+// call's code (errno, or 0 where the call reports none).
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod imp {
+    pub const NAME: &str = "getrandom(2)";
+    pub const EPERM: i32 = 1;
+    pub const EINTR: i32 = 4;
+    pub const EAGAIN: i32 = 11;
+    // The errno numbering of MIPS and SPARC differs from the generic one
+    #[cfg(not(any(target_arch = "mips", target_arch = "mips64",
+        target_arch = "mips32r6", target_arch = "mips64r6",
+        target_arch = "sparc", target_arch = "sparc64")))]
+    pub const ENOSYS: i32 = 38;
+    #[cfg(any(target_arch = "mips", target_arch = "mips64",
+        target_arch = "mips32r6", target_arch = "mips64r6"))]
+    pub const ENOSYS: i32 = 89;
+    #[cfg(any(target_arch = "sparc", target_arch = "sparc64"))]
+    pub const ENOSYS: i32 = 90;
+    const GRND_NONBLOCK: u32 = 1;
+    
+    extern "C" {
+        fn getrandom(buf: *mut u8, buflen: usize, flags: u32) -> isize;
+        #[cfg_attr(target_os = "linux", link_name = "__errno_location")]
+        #[cfg_attr(target_os = "android", link_name = "__errno")]
+        fn errno_location() -> *mut i32;
+    }
+    
+    /// Blocks until the kernel's pool is initialised, unless `nonblocking`,
+    /// when it fails `EAGAIN` instead.
+    pub fn fill(dest: &mut [u8], nonblocking: bool) -> Result<(), i32> {
+        let flags = if nonblocking { GRND_NONBLOCK } else { 0 };
+        let mut pos = 0;
+        while pos < dest.len() {
+            let rest = &mut dest[pos..];
+            let n = unsafe { getrandom(rest.as_mut_ptr(), rest.len(), flags) };
+            if n < 0 {
+                let code = unsafe { *errno_location() };
+                if code == EINTR {
+                    continue;
+                }
+                return Err(code);
+            }
+            pos += n as usize;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod imp {
+    pub const NAME: &str = "getentropy(2)";
+    pub const EPERM: i32 = 1;
+    pub const EINTR: i32 = 4;
+    pub const EAGAIN: i32 = 35;
+    pub const ENOSYS: i32 = 78;
+    
+    extern "C" {
+        fn getentropy(buf: *mut u8, buflen: usize) -> i32;
+        fn __error() -> *mut i32;
+    }
+    
+    /// At most 256 bytes a call; never blocks once booted.
+    pub fn fill(dest: &mut [u8], _nonblocking: bool) -> Result<(), i32> {
+        for chunk in dest.chunks_mut(256) {
+            loop {
+                if unsafe { getentropy(chunk.as_mut_ptr(), chunk.len()) } == 0 {
+                    break;
+                }
+                let code = unsafe { *__error() };
+                if code != EINTR {
+                    return Err(code);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    pub const NAME: &str = "RtlGenRandom";
+    // Not errno values: `RtlGenRandom` reports none
+    pub const EPERM: i32 = -1;
+    pub const EINTR: i32 = -2;
+    pub const EAGAIN: i32 = -3;
+    pub const ENOSYS: i32 = -4;
+    
+    #[link(name = "advapi32")]
+    extern "system" {
+        #[link_name = "SystemFunction036"]
+        fn RtlGenRandom(buf: *mut u8, len: u32) -> u8;
+    }
+    
+    pub fn fill(dest: &mut [u8], _nonblocking: bool) -> Result<(), i32> {
+        for chunk in dest.chunks_mut(u32::MAX as usize) {
+            if unsafe { RtlGenRandom(chunk.as_mut_ptr(), chunk.len() as u32) } == 0 {
+                return Err(0);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android",
+    target_os = "macos", target_os = "ios", windows)))]
+mod imp {
+    pub const NAME: &str = "unsupported";
+    pub const EPERM: i32 = -1;
+    pub const EINTR: i32 = -2;
+    pub const EAGAIN: i32 = -3;
+    pub const ENOSYS: i32 = -4;
+    
+    pub fn fill(_dest: &mut [u8], _nonblocking: bool) -> Result<(), i32> {
+        Err(ENOSYS)
+    }
+}
+
+pub use self::imp::*;
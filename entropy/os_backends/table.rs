@@ -0,0 +1,72 @@
+// The OsRng backend table.
+// 
+// `os_backends!(callback)` invokes `callback!` with one entry per backend:
+// the `cfg` predicate under which it is selected, a name, and its source
+// file. The predicates must be mutually exclusive and together cover every
+// target: `check.rs` verifies this for a list of target triples (see
+// `check_os_backends.rs`).
+// 
+// Adding a backend (e.g. for Fuchsia via `zx_cprng_draw`, or Hermit via
+// `sys_read_entropy`):
+// 
+// 1.  write `NAME.rs` defining `pub const NAME: &str` and
+//     `pub fn fill(dest: &mut [u8]) -> Result<(), super::Error>`
+// 2.  add an entry here, and add `not(...)` of its predicate to the entries
+//     it takes precedence over (`urandom` and `unsupported` at least)
+// 3.  add the target triple to `check_os_backends.rs`
+// 
+// A program can also supply its own backend without touching this table by
+// building with `--cfg os_rng_custom` (see `custom.rs`). `--cfg os_rng_enclave`
+// overrides everything, including the hook: see `rdrand.rs`.
+
+macro_rules! os_backends {
+    ($callback:ident) => {
+        $callback! {
+            // Enclaves (e.g. SGX) have no OS: RDRAND only, health-checked
+            #[cfg(os_rng_enclave)]
+            rdrand "rdrand.rs";
+            
+            // Extension point: user-supplied function
+            #[cfg(all(not(os_rng_enclave), os_rng_custom))]
+            custom "custom.rs";
+            
+            // The getrandom crate, if enabled, handles all its platforms
+            #[cfg(all(not(any(os_rng_enclave, os_rng_custom)),
+                feature = "getrandom"))]
+            getrandom "getrandom.rs";
+            
+            #[cfg(all(not(any(os_rng_enclave, os_rng_custom, feature = "getrandom")),
+                any(target_os = "linux", target_os = "android")))]
+            linux "linux.rs";
+            
+            #[cfg(all(not(any(os_rng_enclave, os_rng_custom, feature = "getrandom")),
+                windows))]
+            windows "windows.rs";
+            
+            #[cfg(all(not(any(os_rng_enclave, os_rng_custom, feature = "getrandom")),
+                any(target_os = "macos", target_os = "ios")))]
+            macos "macos.rs";
+            
+            #[cfg(all(not(any(os_rng_enclave, os_rng_custom, feature = "getrandom")),
+                target_arch = "wasm32", target_os = "wasi"))]
+            wasi "wasi.rs";
+            
+            // Other Unix-like systems (BSDs, Solaris, Redox, ...)
+            #[cfg(all(not(any(os_rng_enclave, os_rng_custom, feature = "getrandom")),
+                unix,
+                not(any(target_os = "linux", target_os = "android",
+                    target_os = "macos", target_os = "ios",
+                    target_os = "fuchsia"))))]
+            urandom "urandom.rs";
+            
+            // Everything else, including wasm32-unknown-unknown (see
+            // entropy/wasm), Fuchsia, Hermit and bare metal
+            #[cfg(all(not(any(os_rng_enclave, os_rng_custom, feature = "getrandom")),
+                not(any(target_os = "linux", target_os = "android",
+                    windows, target_os = "macos", target_os = "ios",
+                    all(target_arch = "wasm32", target_os = "wasi"))),
+                any(not(unix), target_os = "fuchsia")))]
+            unsupported "unsupported.rs";
+        }
+    };
+}
@@ -0,0 +1,12 @@
+// Backend: none. `OsRng` compiles but every request fails.
+
+use super::{Error, ErrorKind};
+
+pub const NAME: &str = "unsupported";
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    if dest.is_empty() {
+        return Ok(());
+    }
+    Err(Error::new(ErrorKind::Unavailable))
+}
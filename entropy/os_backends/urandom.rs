@@ -0,0 +1,23 @@
+// Backend: reading /dev/urandom, for Unix-like systems without a dedicated
+// backend. The file is opened once and shared.
+
+use super::Error;
+use std::fs::File;
+use std::io::Read;
+use std::sync::OnceLock;
+
+pub const NAME: &str = "/dev/urandom";
+
+static FILE: OnceLock<File> = OnceLock::new();
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    let file = match FILE.get() {
+        Some(file) => file,
+        None => {
+            let file = File::open("/dev/urandom")?;
+            FILE.get_or_init(|| file)
+        }
+    };
+    (&*file).read_exact(dest)?;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+// Backend: WASI's random_get.
+
+use super::Error;
+use std::io;
+
+pub const NAME: &str = "wasi random_get";
+
+#[link(wasm_import_module = "wasi_snapshot_preview1")]
+extern "C" {
+    fn random_get(buf: *mut u8, buf_len: usize) -> u16;
+}
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    match unsafe { random_get(dest.as_mut_ptr(), dest.len()) } {
+        0 => Ok(()),
+        errno => Err(io::Error::from_raw_os_error(errno as i32).into()),
+    }
+}
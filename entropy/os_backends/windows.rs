@@ -0,0 +1,10 @@
+// Backend: RtlGenRandom (advapi32's SystemFunction036).
+
+use super::{sys, Error, ErrorKind};
+
+pub const NAME: &str = "windows RtlGenRandom";
+
+pub fn fill(dest: &mut [u8]) -> Result<(), Error> {
+    // `RtlGenRandom` reports no error code
+    sys::fill(dest, false).map_err(|_| Error::new(ErrorKind::Unexpected))
+}
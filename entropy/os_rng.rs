@@ -0,0 +1,376 @@
+//! `OsRng`: a façade over the operating system's entropy source
+//! 
+//! `OsRng` implements the fallible side of the designs (`try_fill`), from a
+//! backend in `os_backends/` chosen at compile time by the cfg table of
+//! `os_backends/table.rs`: the platform's own interface, the `getrandom`
+//! crate (the `getrandom` feature), a program's function (`os_rng_custom`),
+//! or health-checked RDRAND in an enclave (`os_rng_enclave`). Platform errors
+//! map into `ErrorKind`, keeping the `io::Error` as their `source()`.
+//! 
+//! Thoughts: the mapping is the only interesting part, best done via
+//! `io::ErrorKind` rather than errno values, which differ between platforms.
+//! Delegating to `getrandom` removes all platform code; enclave mode shows
+//! the limit of a thin façade, which must then do its own health checking.
+
+#[cfg(feature = "getrandom")]
+extern crate getrandom;
+
+use std::fmt;
+use std::io;
+
+// ——— error ———
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Not ready yet (e.g. early boot); retry later.
+    NotReady,
+    /// A temporary failure (e.g. interrupted); retry at once.
+    Transient,
+    /// No entropy source on this platform; retrying will not help.
+    Unavailable,
+    /// The source failed a health check; do not use it again.
+    HealthCheck,
+    /// Anything else.
+    Unexpected,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// The underlying OS error, if any.
+    source: Option<io::Error>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind) -> Self {
+        Error { kind, source: None }
+    }
+    
+    /// Classify as `kind`, keeping `source` as the cause.
+    pub fn with_source(kind: ErrorKind, source: io::Error) -> Self {
+        Error { kind, source: Some(source) }
+    }
+    
+    /// The raw OS error code (errno, or `GetLastError` on Windows), if any.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.source.as_ref().and_then(|e| e.raw_os_error())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.raw_os_error() {
+            Some(code) => write!(f, "OsRng: {:?} (os error {})", self.kind, code),
+            None => write!(f, "OsRng: {:?}", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_ref().map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        let kind = match e.kind() {
+            io::ErrorKind::WouldBlock => ErrorKind::NotReady,
+            io::ErrorKind::Interrupted => ErrorKind::Transient,
+            io::ErrorKind::NotFound |
+            io::ErrorKind::PermissionDenied |
+            io::ErrorKind::Unsupported => ErrorKind::Unavailable,
+            _ => ErrorKind::Unexpected,
+        };
+        Error::with_source(kind, e)
+    }
+}
+
+#[cfg(feature = "getrandom")]
+impl From<getrandom::Error> for Error {
+    fn from(e: getrandom::Error) -> Self {
+        match e.raw_os_error() {
+            Some(code) => io::Error::from_raw_os_error(code).into(),
+            None if e == getrandom::Error::UNSUPPORTED => {
+                Error::new(ErrorKind::Unavailable)
+            }
+            None => Error::new(ErrorKind::Unexpected),
+        }
+    }
+}
+
+// ——— traits ———
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("{}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+pub trait CryptoRng: Rng {}
+
+// ——— backends ———
+
+/// Backend selection: see `os_backends/table.rs` for the table and how to
+/// extend it. Each backend is a module providing `NAME` and `fill`.
+mod os_backends {
+    #[allow(unused_imports)]
+    use super::{Error, ErrorKind};
+    
+    #[path = "table.rs"]
+    #[macro_use]
+    mod table;
+    
+    // The system calls, shared with `traits/os.rs`
+    #[path = "sys.rs"]
+    #[allow(dead_code)]
+    pub mod sys;
+    
+    macro_rules! declare_backend {
+        ($(#[cfg($pred:meta)] $name:ident $path:tt;)*) => {
+            $(
+                #[cfg($pred)]
+                #[path = $path]
+                mod imp;
+            )*
+        };
+    }
+    
+    os_backends!(declare_backend);
+    
+    pub use self::imp::{NAME, fill};
+    #[cfg(os_rng_enclave_sim)]
+    pub use self::imp::sim;
+}
+
+// ——— OsRng ———
+
+/// The operating system's entropy source.
+#[derive(Debug)]
+pub struct OsRng;
+
+impl OsRng {
+    pub fn new() -> Result<OsRng, Error> {
+        Ok(OsRng)
+    }
+    
+    /// Name of the backend in use.
+    pub fn backend() -> &'static str {
+        os_backends::NAME
+    }
+}
+
+impl Rng for OsRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        os_backends::fill(dest)
+    }
+}
+
+impl CryptoRng for OsRng {}
+
+// ——— seeding ———
+
+pub trait SeedableRng: Sized {
+    type Seed: Default + AsMut<[u8]>;
+    
+    fn from_seed(seed: Self::Seed) -> Self;
+}
+
+/// Seed an `R` from `source`.
+/// 
+/// If `source` is `Unavailable`, falls back to a weak seed from the clock and
+/// an address, so that programs still run on platforms without `OsRng`. In
+/// enclave mode the error is returned instead: the host controls the clock.
+pub fn seed_from<R: SeedableRng>(source: &mut dyn Rng) -> Result<R, Error> {
+    let mut seed = R::Seed::default();
+    match source.try_fill(seed.as_mut()) {
+        Ok(()) => (),
+        Err(ref e) if e.kind == ErrorKind::Unavailable && !cfg!(os_rng_enclave) => {
+            weak_seed(seed.as_mut())
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(R::from_seed(seed))
+}
+
+/// Seed an `R` from `OsRng` (see `seed_from`).
+pub fn from_entropy<R: SeedableRng>() -> Result<R, Error> {
+    seed_from(&mut OsRng)
+}
+
+fn weak_seed(dest: &mut [u8]) {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64).unwrap_or(0);
+    let mut x = nanos ^ (&nanos as *const u64 as u64);
+    for chunk in dest.chunks_mut(8) {
+        // SplitMix64
+        x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = x;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+    }
+}
+
+// ——— test RNGs ———
+
+/// Seedable RNG which just remembers its seed.
+struct SeedOnly([u8; 16]);
+
+impl SeedableRng for SeedOnly {
+    type Seed = [u8; 16];
+    
+    fn from_seed(seed: [u8; 16]) -> Self {
+        SeedOnly(seed)
+    }
+}
+
+/// Source which always fails.
+struct FailingRng(ErrorKind);
+
+impl Rng for FailingRng {
+    fn try_fill(&mut self, _: &mut [u8]) -> Result<(), Error> {
+        Err(Error::new(self.0))
+    }
+}
+
+/// Source failing like a system call with the given errno, as in `linux.rs`.
+struct ErrnoRng(i32);
+
+impl Rng for ErrnoRng {
+    fn try_fill(&mut self, _: &mut [u8]) -> Result<(), Error> {
+        Err(io::Error::from_raw_os_error(self.0).into())
+    }
+}
+
+// ——— usage ———
+
+/// Example `os_rng_custom` hook: a deterministic source, for testing only.
+#[cfg(os_rng_custom)]
+#[no_mangle]
+pub fn os_rng_custom_fill(dest: &mut [u8]) -> Result<(), Error> {
+    for (i, b) in dest.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+    Ok(())
+}
+
+fn main() {
+    println!("OsRng backend: {}", OsRng::backend());
+    let mut rng = OsRng::new().unwrap();
+    {
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        rng.try_fill(&mut a).unwrap();
+        rng.fill(&mut b);
+        if cfg!(all(os_rng_custom, not(os_rng_enclave))) {
+            assert!(a == b && a[31] == 31);
+        } else {
+            assert!(a != b && a != [0; 32]);
+        }
+        println!("OsRng, static dispatch: {:?}", &a[..8]);
+        let cr = &mut rng as &mut dyn CryptoRng;
+        cr.fill(&mut a);
+        println!("OsRng, dynamic dispatch, using CryptoRng: {:?}", &a[..8]);
+        // Zero-length requests succeed
+        rng.try_fill(&mut []).unwrap();
+    }
+    {
+        // Error mapping preserves the OS code and classifies it
+        let cases = [
+            (io::ErrorKind::WouldBlock, ErrorKind::NotReady),
+            (io::ErrorKind::Interrupted, ErrorKind::Transient),
+            (io::ErrorKind::Unsupported, ErrorKind::Unavailable),
+            (io::ErrorKind::InvalidData, ErrorKind::Unexpected),
+        ];
+        for &(io_kind, kind) in cases.iter() {
+            let e = Error::from(io::Error::from(io_kind));
+            assert_eq!(e.kind, kind);
+        }
+        let e = Error::from(io::Error::from_raw_os_error(4));
+        assert_eq!(e.raw_os_error(), Some(4));
+        println!("error mapping: {}", e);
+    }
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "ios"))]
+    {
+        // Simulated EAGAIN and ENOSYS: classified, with the original error
+        // (and code) still reachable, also through seeding and boxing
+        use std::error::Error as _;
+        let (eagain, enosys) = (os_backends::sys::EAGAIN, os_backends::sys::ENOSYS);
+        for &(errno, kind) in [(eagain, ErrorKind::NotReady), (enosys, ErrorKind::Unavailable)].iter() {
+            let e = ErrnoRng(errno).try_fill(&mut [0; 8]).unwrap_err();
+            assert_eq!((e.kind, e.raw_os_error()), (kind, Some(errno)));
+            let source = e.source().unwrap().downcast_ref::<io::Error>().unwrap();
+            assert_eq!(source.raw_os_error(), Some(errno));
+            assert!(e.to_string().ends_with(&format!("(os error {})", errno)));
+            
+            let e = seed_from::<SeedOnly>(&mut ErrnoRng(errno)).err();
+            if kind == ErrorKind::NotReady {
+                assert_eq!(e.unwrap().raw_os_error(), Some(errno));
+            }
+            
+            let boxed: Box<dyn std::error::Error> = Box::new(ErrnoRng(errno).try_fill(&mut []).unwrap_err());
+            let os = boxed.source().and_then(|s| s.downcast_ref::<io::Error>());
+            assert_eq!(os.and_then(|e| e.raw_os_error()), Some(errno));
+        }
+        assert!(Error::new(ErrorKind::Transient).source().is_none());
+        println!("errno chaining: EAGAIN and ENOSYS ok");
+    }
+    #[cfg(feature = "getrandom")]
+    {
+        assert_eq!(Error::from(getrandom::Error::UNSUPPORTED).kind, ErrorKind::Unavailable);
+        let e = Error::from(getrandom::Error::from(std::num::NonZeroU32::new((1 << 31) + 100).unwrap()));
+        assert_eq!(e.kind, ErrorKind::Unexpected);
+        println!("getrandom error mapping: ok");
+    }
+    {
+        // Weak fallbacks only when OsRng is unavailable, and never in enclaves
+        let seeded: SeedOnly = from_entropy().unwrap();
+        assert!(seeded.0 != [0; 16]);
+        let weak = seed_from::<SeedOnly>(&mut FailingRng(ErrorKind::Unavailable));
+        assert_eq!(weak.is_ok(), !cfg!(os_rng_enclave));
+        let e = seed_from::<SeedOnly>(&mut FailingRng(ErrorKind::NotReady)).err().unwrap();
+        assert_eq!(e.kind, ErrorKind::NotReady);
+        println!("seeding: weak fallback {}", if weak.is_ok() { "allowed" } else { "refused" });
+    }
+    #[cfg(os_rng_enclave_sim)]
+    {
+        use os_backends::sim::{self, Mode};
+        let kind = |rng: &mut OsRng| rng.try_fill(&mut [0; 8]).err().map(|e| e.kind);
+        
+        // Underflow is reported, but not held against the source
+        sim::set_mode(Mode::Underflow);
+        assert_eq!(kind(&mut rng), Some(ErrorKind::NotReady));
+        sim::set_mode(Mode::Healthy);
+        assert_eq!(kind(&mut rng), None);
+        
+        // Continuous test: a stuck source is caught, and stays failed
+        sim::set_mode(Mode::Stuck(0x1234));
+        let _ = rng.try_fill(&mut [0; 8]);
+        assert_eq!(kind(&mut rng), Some(ErrorKind::HealthCheck));
+        sim::set_mode(Mode::Healthy);
+        assert_eq!(kind(&mut rng), Some(ErrorKind::HealthCheck));
+        
+        // Startup test: the AMD failure mode is caught before any output
+        sim::power_cycle();
+        sim::set_mode(Mode::Stuck(!0));
+        assert_eq!(kind(&mut rng), Some(ErrorKind::HealthCheck));
+        
+        // No RDRAND: from_entropy refuses to fall back
+        sim::power_cycle();
+        sim::set_mode(Mode::Missing);
+        assert_eq!(kind(&mut rng), Some(ErrorKind::Unavailable));
+        assert!(from_entropy::<SeedOnly>().is_err());
+        
+        sim::power_cycle();
+        sim::set_mode(Mode::Healthy);
+        assert_eq!(kind(&mut rng), None);
+        println!("simulated enclave: health checks ok");
+    }
+}
@@ -0,0 +1,194 @@
+//! `ReadRng`: any `std::io::Read` as a fallible generator
+//! 
+//! `ReadRng<R>` fills from a reader: `/dev/urandom` opened as a file, a file
+//! of recorded entropy for replaying a run, a pipe from a hardware module.
+//! It implements the fallible side of the designs (`try_fill`), with the
+//! error type the fallible variants share (`traits/error.rs`). Unlike the
+//! test generators, its failures are real ones, each mapped to a kind:
+//! 
+//! *   a short read isn't a failure: `read` may return fewer bytes than
+//!     asked, and `try_fill` reads again until `dest` is full
+//! *   `Interrupted` is retried at once, as `read_exact` does
+//! *   `WouldBlock` (a non-blocking reader with nothing yet) is `NotReady`
+//! *   end of input, `Ok(0)`, is `Unavailable`: the recording has run out
+//! *   `NotFound`, `PermissionDenied` and `Unsupported` are `Unavailable`
+//! *   anything else is `Unexpected`
+//! 
+//! Run with:
+//! 
+//!     rustc read_rng.rs && ./read_rng
+//! 
+//! Thoughts: `Read` is close to `try_fill` already; the work is in the short
+//! reads, which a careless adaptor turns into unfilled output, and in the
+//! mapping, done (as in `os_rng.rs`) by `io::ErrorKind` rather than by
+//! errno. Two things don't fit. A failed fill has consumed whatever was read
+//! before the error, and `dest` holds it, partly written: retrying gives
+//! different bytes, harmless for entropy and fatal for replaying a
+//! recording, which must treat any error as the end. And the shared error's
+//! message is a `&'static str`, so the `io::Error` itself (its OS code, its
+//! text) is lost; `os_rng.rs` keeps it as the error's `source()`, which
+//! needs `std` and a field the shared type doesn't have.
+
+use std::io;
+use std::io::Read;
+
+// ——— traits ———
+
+#[path = "../traits/error.rs"]
+#[allow(dead_code)]
+mod error;
+use self::error::{Error, ErrorKind};
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("Rng::fill failed: {}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+// ——— ReadRng ———
+
+/// A generator reading its bytes from `R`.
+#[derive(Debug)]
+pub struct ReadRng<R> {
+    reader: R,
+}
+
+impl<R: Read> ReadRng<R> {
+    pub fn new(reader: R) -> ReadRng<R> {
+        ReadRng { reader }
+    }
+    
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// The kind and message for an error of `read`.
+fn map_io_error(e: &io::Error) -> Error {
+    match e.kind() {
+        io::ErrorKind::WouldBlock => Error::new(ErrorKind::NotReady, "reader has no bytes yet"),
+        io::ErrorKind::NotFound |
+        io::ErrorKind::PermissionDenied |
+        io::ErrorKind::Unsupported => Error::new(ErrorKind::Unavailable, "reader unavailable"),
+        _ => Error::new(ErrorKind::Unexpected, "reader failed"),
+    }
+}
+
+impl<R: Read> Rng for ReadRng<R> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        let mut filled = 0;
+        while filled < dest.len() {
+            match self.reader.read(&mut dest[filled..]) {
+                Ok(0) => return Err(Error::new(ErrorKind::Unavailable, "reader at end of input")),
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(ref e) => return Err(map_io_error(e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+// ——— test RNGs ———
+
+/// A reader giving bytes 0, 1, 2, ..., at most `chunk` per `read`, after
+/// failing with each of `errors` in turn.
+struct Scripted {
+    next: u8,
+    chunk: usize,
+    errors: Vec<io::ErrorKind>,
+}
+
+impl Scripted {
+    fn new(chunk: usize, errors: &[io::ErrorKind]) -> Scripted {
+        Scripted { next: 0, chunk, errors: errors.iter().rev().cloned().collect() }
+    }
+}
+
+impl Read for Scripted {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(kind) = self.errors.pop() {
+            return Err(io::Error::from(kind));
+        }
+        let n = buf.len().min(self.chunk);
+        for b in &mut buf[..n] {
+            *b = self.next;
+            self.next = self.next.wrapping_add(1);
+        }
+        Ok(n)
+    }
+}
+
+// ——— usage ———
+
+fn main() {
+    {
+        // A recording: its bytes, then the end
+        let recorded = [1u8, 2, 3, 4, 5, 6];
+        let mut rng = ReadRng::new(&recorded[..]);
+        let mut buf = [0u8; 4];
+        rng.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+        let e = rng.try_fill(&mut buf).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Unavailable);
+        assert_eq!(buf[..2], [5, 6]);
+        println!("recording, read past its end: {}", e);
+    }
+    {
+        // Short reads: one byte at a time still fills
+        let mut rng = ReadRng::new(Scripted::new(1, &[]));
+        let mut buf = [0u8; 5];
+        rng.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3, 4]);
+        println!("short reads: filled, {:?}", buf);
+    }
+    {
+        // Interrupted is retried; the rest are mapped, and the next fill
+        // continues
+        let mut rng = ReadRng::new(Scripted::new(3, &[io::ErrorKind::Interrupted]));
+        let mut buf = [0u8; 4];
+        rng.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [0, 1, 2, 3]);
+        let cases = [
+            (io::ErrorKind::WouldBlock, ErrorKind::NotReady),
+            (io::ErrorKind::NotFound, ErrorKind::Unavailable),
+            (io::ErrorKind::PermissionDenied, ErrorKind::Unavailable),
+            (io::ErrorKind::InvalidData, ErrorKind::Unexpected),
+        ];
+        for &(io_kind, kind) in cases.iter() {
+            let mut rng = ReadRng::new(Scripted::new(8, &[io_kind]));
+            let e = rng.try_fill(&mut buf).unwrap_err();
+            assert_eq!(e.kind, kind);
+            assert_eq!(e.should_retry(), kind == ErrorKind::NotReady);
+            rng.try_fill(&mut buf).unwrap();
+        }
+        println!("io errors: Interrupted retried, the rest mapped");
+    }
+    {
+        // The system's random device, where there is one
+        match std::fs::File::open("/dev/urandom") {
+            Ok(file) => {
+                let mut rng = ReadRng::new(file);
+                let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+                rng.fill(&mut a);
+                rng.fill(&mut b);
+                assert!(a != b);
+                println!("/dev/urandom: {:?}", &a[..8]);
+            }
+            Err(_) => println!("/dev/urandom: none here"),
+        }
+    }
+    {
+        // Through the trait, as a trait object
+        let mut rng = ReadRng::new(Scripted::new(2, &[]));
+        let r = &mut rng as &mut dyn Rng;
+        let mut buf = [0u8; 3];
+        r.fill(&mut buf);
+        assert_eq!(buf, [0, 1, 2]);
+        println!("ReadRng, dynamic dispatch: {:?}", buf);
+    }
+}
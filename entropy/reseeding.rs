@@ -0,0 +1,237 @@
+//! `ReseedingRng`: inline reseeding at an exact byte threshold
+//! 
+//! The synchronous counterpart of `background_reseeding.rs`: `ReseedingRng<R>`
+//! calls its entropy source itself, from whichever call crosses the threshold.
+//! Reseed points are exact, at byte granularity:
+//! 
+//! *   each state produces exactly `threshold` bytes of output, then is
+//!     replaced; output byte `i` (counting from construction) comes from
+//!     generation `i / threshold`, at offset `i % threshold` in its stream
+//! *   reseeding is lazy: it happens just before the first byte of the next
+//!     generation, so a fill ending exactly on the threshold does not reseed,
+//!     and zero-length fills never do
+//! *   a fill (or `next_u32`) crossing one or more thresholds is split there,
+//!     reseeding as many times as needed; no byte is dropped or repeated
+//! *   if the source fails, the current state is kept for another `threshold`
+//!     bytes, after which reseeding is tried again
+//! 
+//! So, without failures, after `n` bytes of output there have been
+//! `(n - 1) / threshold` reseeds (none for `n = 0`). `main` checks all of this
+//! with fills that hit, straddle and vastly exceed the threshold.
+//! 
+//! Thoughts: splitting a fill requires `R`'s byte stream not to depend on how
+//! requests are split (true of buffered block generators, not of generators
+//! which discard the tail of a word). The alternative, only reseeding between
+//! calls, lets one large fill run arbitrarily far past the threshold.
+
+use std::cmp::min;
+
+// ——— traits ———
+
+#[derive(Debug)]
+pub struct Error;
+
+pub trait Rng {
+    fn fill(&mut self, dest: &mut [u8]);
+    
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+}
+
+pub trait SeedableRng: Sized {
+    type Seed: Default + AsMut<[u8]>;
+    
+    fn from_seed(seed: Self::Seed) -> Self;
+}
+
+/// A (fallible) source of seeds.
+pub trait EntropySource {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+// ——— wrapper ———
+
+/// Reseeds `R` from `S` after every `threshold` bytes of output.
+pub struct ReseedingRng<R, S> {
+    rng: R,
+    source: S,
+    threshold: u64,
+    /// Bytes produced by the current state.
+    used: u64,
+    generation: u64,
+    failures: u64,
+}
+
+impl<R: SeedableRng, S: EntropySource> ReseedingRng<R, S> {
+    /// Panics if `threshold` is zero.
+    pub fn new(rng: R, source: S, threshold: u64) -> Self {
+        assert!(threshold > 0, "ReseedingRng: threshold must be positive");
+        ReseedingRng { rng, source, threshold, used: 0, generation: 0, failures: 0 }
+    }
+    
+    /// Number of completed reseeds.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    
+    /// Number of failed reseed attempts.
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+    
+    fn reseed(&mut self) {
+        let mut seed = R::Seed::default();
+        match self.source.try_fill(seed.as_mut()) {
+            Ok(()) => {
+                self.rng = R::from_seed(seed);
+                self.generation += 1;
+            }
+            Err(Error) => self.failures += 1,
+        }
+        self.used = 0;
+    }
+}
+
+impl<R: Rng+SeedableRng, S: EntropySource> Rng for ReseedingRng<R, S> {
+    fn fill(&mut self, dest: &mut [u8]) {
+        let mut pos = 0;
+        while pos < dest.len() {
+            if self.used == self.threshold {
+                self.reseed();
+            }
+            let n = min((dest.len() - pos) as u64, self.threshold - self.used) as usize;
+            self.rng.fill(&mut dest[pos..pos + n]);
+            self.used += n as u64;
+            pos += n;
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+/// Byte `n` of the stream seeded with `seed`: identifies both.
+fn stream_byte(seed: u64, n: u64) -> u8 {
+    // SplitMix64 finaliser
+    let mut z = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ n;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)) as u8
+}
+
+/// A byte-stream generator: output does not depend on how it is requested.
+#[derive(Debug)]
+struct StreamRng {
+    seed: u64,
+    n: u64,
+}
+
+impl Rng for StreamRng {
+    fn fill(&mut self, dest: &mut [u8]) {
+        for b in dest.iter_mut() {
+            *b = stream_byte(self.seed, self.n);
+            self.n += 1;
+        }
+    }
+}
+
+impl SeedableRng for StreamRng {
+    type Seed = [u8; 8];
+    
+    fn from_seed(seed: [u8; 8]) -> Self {
+        StreamRng { seed: u64::from_le_bytes(seed), n: 0 }
+    }
+}
+
+/// Yields seeds 1, 2, 3, ...; fails on the attempts listed in `fail`.
+struct CountingSource {
+    next: u64,
+    attempt: usize,
+    fail: Vec<usize>,
+}
+
+impl EntropySource for CountingSource {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.attempt += 1;
+        if self.fail.contains(&self.attempt) {
+            return Err(Error);
+        }
+        self.next += 1;
+        dest.copy_from_slice(&self.next.to_le_bytes());
+        Ok(())
+    }
+}
+
+// ——— usage ———
+
+fn reseeding(threshold: u64, fail: Vec<usize>) -> ReseedingRng<StreamRng, CountingSource> {
+    let source = CountingSource { next: 0, attempt: 0, fail };
+    ReseedingRng::new(StreamRng::from_seed([0; 8]), source, threshold)
+}
+
+/// The documented output: `len` bytes, generation `g` being seed `g` and
+/// producing exactly `threshold` bytes.
+fn expected(threshold: u64, len: usize) -> Vec<u8> {
+    (0..len as u64).map(|i| stream_byte(i / threshold, i % threshold)).collect()
+}
+
+/// Fill with the given lengths; check output and reseed count after each.
+fn check(threshold: u64, lengths: &[usize]) {
+    let mut rng = reseeding(threshold, vec![]);
+    let mut out = vec![];
+    for &len in lengths {
+        let mut buf = vec![0u8; len];
+        rng.fill(&mut buf);
+        out.extend_from_slice(&buf);
+        let n = out.len() as u64;
+        let reseeds = if n == 0 { 0 } else { (n - 1) / threshold };
+        assert_eq!(rng.generation(), reseeds, "threshold {}, lengths {:?}: after {} bytes",
+            threshold, lengths, n);
+    }
+    assert!(out == expected(threshold, out.len()), "threshold {}, lengths {:?}: output differs",
+        threshold, lengths);
+}
+
+fn main() {
+    for &t in [1u64, 2, 7, 64, 1000].iter() {
+        let t_ = t as usize;
+        check(t, &[t_]);                        // exactly hits: no reseed yet
+        check(t, &[t_, 1]);                     // ... until the next byte
+        check(t, &[t_, t_, t_, 0, t_]);         // repeatedly hits
+        check(t, &[t_ - 1, 2]);                 // straddles by one
+        check(t, &[t_ + 1, t_ - 1, t_ + 5]);    // straddles every time
+        check(t, &[10 * t_ + 3]);               // one fill over ten thresholds
+        check(t, &[0, 0, 1, 0]);                // zero-length fills do nothing
+        check(t, &vec![1; 3 * t_ + 2]);         // byte at a time
+        check(t, &[3, 5, 1, 0, 17, 64, 2, 999, 4096, 1, 1, 13]);
+    }
+    println!("fills hitting, straddling and exceeding the threshold: ok");
+    {
+        // Words straddle too: with threshold 6, the second next_u32 takes
+        // two bytes from each generation
+        let mut rng = reseeding(6, vec![]);
+        let words = [rng.next_u32(), rng.next_u32()];
+        let mut bytes = words[0].to_le_bytes().to_vec();
+        bytes.extend_from_slice(&words[1].to_le_bytes());
+        assert_eq!(bytes, expected(6, 8));
+        assert_eq!(rng.generation(), 1);
+        println!("next_u32 across the threshold: ok");
+    }
+    {
+        // A failed reseed keeps the current state for another threshold,
+        // continuing its stream; the next attempt uses the next seed
+        let t = 10;
+        let mut rng = reseeding(t, vec![2]);
+        let mut buf = vec![0u8; 45];
+        rng.fill(&mut buf);
+        assert_eq!((rng.generation(), rng.failures()), (3, 1));
+        let mut want = expected(t, 10);                             // seed 0
+        want.extend((0..20).map(|i| stream_byte(1, i)));            // seed 1, twice as long
+        want.extend((0..10).map(|i| stream_byte(2, i)));            // seed 2
+        want.extend((0..5).map(|i| stream_byte(3, i)));             // seed 3
+        assert!(buf == want);
+        println!("failed reseed: ok");
+    }
+}
@@ -0,0 +1,246 @@
+//! `thread_rng()`: a handle to a thread-local generator
+//! 
+//! Each thread gets its own generator: created on the thread's first call
+//! to `thread_rng()`, seeded then from `OsRng` (`traits/os.rs`), and
+//! reseeded from it every `RESEED_THRESHOLD` bytes of output. `thread_rng()`
+//! returns a `ThreadRng`, a handle to that generator, as rand's:
+//! 
+//! *   the generator lives in a `thread_local!`, as `Rc<RefCell<_>>`; each
+//!     handle is a clone of the `Rc`, so all the handles of a thread share
+//!     one stream, and none can leave it (`ThreadRng` is neither `Send` nor
+//!     `Sync`)
+//! *   the generator is a `BlockRng` (`traits/block.rs`) over a
+//!     `ReseedingCore`, which reseeds between blocks; the core inside is a
+//!     SplitMix stand-in for ChaCha, cheap but not a crypto generator
+//! *   a failed reseed keeps the state, and tries again after another
+//!     threshold; a failed first seed panics, there being no state to keep
+//! 
+//! Run with:
+//! 
+//!     rustc thread_rng.rs && ./thread_rng
+//! 
+//! Thoughts: the handle works in each design (`bin/compare.rs` checks it),
+//! as none bounds `CryptoRng` by `Send`. But `&mut self` promises exclusive
+//! access the handle can't give: the `RefCell` checks it at run time, so a
+//! generator calling `thread_rng()` inside its own `fill` panics rather
+//! than failing to compile. `&self` would be honest, but force interior
+//! mutability on every other generator. After its first seeding, the
+//! infallible `fill` is the right interface, reseed failures absorbed.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// ——— traits ———
+
+#[path = "../traits/error.rs"]
+#[allow(dead_code)]
+mod error;
+use self::error::{Error, ErrorKind};
+
+#[path = "../traits/os.rs"]
+#[allow(dead_code)]
+mod os;
+use self::os::OsRng;
+
+#[path = "../traits/block.rs"]
+#[allow(dead_code)]
+mod block;
+use self::block::{BlockRng, BlockRngCore};
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("Rng::fill failed: {}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+    
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+}
+
+pub trait CryptoRng: Rng {}
+
+// ——— the generator ———
+
+/// Bytes of output between reseeds (rand's is 64 KiB too).
+const RESEED_THRESHOLD: i64 = 64 * 1024;
+
+/// Seedings and reseedings from `OsRng`, by every thread.
+static OS_SEEDINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// A SplitMix64 block core, standing in for ChaCha.
+struct Core(u64);
+
+impl BlockRngCore for Core {
+    type Results = [u32; 16];
+    
+    fn generate(&mut self, results: &mut [u32; 16]) {
+        for pair in results.chunks_mut(2) {
+            self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^= z >> 31;
+            pair[0] = z as u32;
+            pair[1] = (z >> 32) as u32;
+        }
+    }
+}
+
+/// A seed from `OsRng`.
+fn os_seed() -> Result<u64, Error> {
+    let mut seed = [0; 8];
+    OsRng::new().try_fill(&mut seed)
+        .map_err(|_| Error::new(ErrorKind::Unavailable, "OsRng failed"))?;
+    OS_SEEDINGS.fetch_add(1, Ordering::Relaxed);
+    Ok(u64::from_le_bytes(seed))
+}
+
+/// `Core`, reseeded from `OsRng` before the block crossing the threshold.
+struct ReseedingCore {
+    inner: Core,
+    bytes_until_reseed: i64,
+    reseeds: u64,
+}
+
+impl ReseedingCore {
+    fn new() -> Result<ReseedingCore, Error> {
+        Ok(ReseedingCore { inner: Core(os_seed()?), bytes_until_reseed: RESEED_THRESHOLD, reseeds: 0 })
+    }
+}
+
+impl BlockRngCore for ReseedingCore {
+    type Results = [u32; 16];
+    
+    fn generate(&mut self, results: &mut [u32; 16]) {
+        if self.bytes_until_reseed <= 0 {
+            // On failure, keep the state for another threshold
+            if let Ok(seed) = os_seed() {
+                self.inner = Core(seed);
+                self.reseeds += 1;
+            }
+            self.bytes_until_reseed = RESEED_THRESHOLD;
+        }
+        self.bytes_until_reseed -= 64;
+        self.inner.generate(results);
+    }
+}
+
+thread_local!(
+    static THREAD_RNG: Rc<RefCell<BlockRng<ReseedingCore>>> = {
+        let core = ReseedingCore::new()
+            .unwrap_or_else(|e| panic!("could not seed thread_rng: {}", e));
+        Rc::new(RefCell::new(BlockRng::new(core)))
+    }
+);
+
+// ——— ThreadRng ———
+
+/// A handle to this thread's generator.
+#[derive(Clone)]
+pub struct ThreadRng {
+    rng: Rc<RefCell<BlockRng<ReseedingCore>>>,
+}
+
+/// This thread's generator, seeding it on the thread's first call.
+pub fn thread_rng() -> ThreadRng {
+    ThreadRng { rng: THREAD_RNG.with(|t| t.clone()) }
+}
+
+impl ThreadRng {
+    /// Reseeds of this thread's generator so far.
+    pub fn reseeds(&self) -> u64 {
+        self.rng.borrow().core().reseeds
+    }
+}
+
+impl Rng for ThreadRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.borrow_mut().fill(dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.borrow_mut().next_u64()
+    }
+}
+
+impl CryptoRng for ThreadRng {}
+
+// ——— consumers ———
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) -> [u8; 16] {
+    let mut key = [0; 16];
+    rng.fill(&mut key);
+    key
+}
+
+// ——— usage ———
+
+fn main() {
+    {
+        // Seeded lazily: a new thread, on its first call only
+        let before = OS_SEEDINGS.load(Ordering::Relaxed);
+        let (mid, after) = thread::spawn(move || {
+            let mid = OS_SEEDINGS.load(Ordering::Relaxed);
+            key(&mut thread_rng());
+            key(&mut thread_rng());
+            (mid, OS_SEEDINGS.load(Ordering::Relaxed))
+        }).join().unwrap();
+        assert_eq!((mid, after), (before, before + 1));
+        println!("thread_rng: seeded on first use, once");
+    }
+    {
+        // Handles share one generator: each takes the next words of it
+        let (mut a, mut b) = (thread_rng(), thread_rng());
+        let mut c = a.clone();
+        assert!(Rc::ptr_eq(&a.rng, &b.rng) && Rc::ptr_eq(&a.rng, &c.rng));
+        let index = a.rng.borrow().index() % 16;
+        let words = [a.next_u64(), b.next_u64(), c.next_u64()];
+        // Six words on, across a refill if need be
+        assert_eq!(c.rng.borrow().index(), (index + 6 - 1) % 16 + 1);
+        assert!(words[0] != words[1] && words[1] != words[2]);
+        println!("handles a, b, c: one stream, {:x?}", words);
+    }
+    {
+        // Threads have their own generators
+        let here = key(&mut thread_rng());
+        let there = thread::spawn(|| key(&mut thread_rng())).join().unwrap();
+        assert!(here != there);
+        println!("two threads: {:?} and {:?}", &here[..4], &there[..4]);
+    }
+    {
+        // Reseeded every RESEED_THRESHOLD bytes, between blocks
+        thread::spawn(|| {
+            let mut rng = thread_rng();
+            let mut buf = vec![0u8; 3 * RESEED_THRESHOLD as usize];
+            rng.fill(&mut buf);
+            assert_eq!(rng.reseeds(), 2);
+            rng.fill(&mut [0; 64]);
+            assert_eq!(rng.reseeds(), 3);
+            println!("reseeding: {} reseeds after {} bytes", rng.reseeds(), buf.len() + 64);
+        }).join().unwrap();
+    }
+    {
+        // The &mut self receiver is satisfied by the handle; the nested
+        // borrow a reentrant use would make is caught at run time only
+        let rng = thread_rng();
+        let held = rng.rng.borrow_mut();
+        assert!(thread_rng().rng.try_borrow_mut().is_err());
+        drop(held);
+        println!("reentrant use: a RefCell borrow error, not a compile error");
+    }
+    {
+        // Through the trait, as a trait object
+        let mut rng = thread_rng();
+        let r = &mut rng as &mut dyn CryptoRng;
+        println!("ThreadRng, dynamic dispatch, using CryptoRng: {:?}", &key(r)[..8]);
+    }
+}
@@ -0,0 +1,211 @@
+//! Throttled slow-entropy simulation
+//! 
+//! `Throttled<R>` wraps a source and makes it behave like a slow hardware
+//! module or an exhausted entropy pool: each call sleeps for a configurable
+//! latency and every `would_block_every`-th call fails with
+//! `ErrorKind::WouldBlock`. It is a test adaptor: it never changes output
+//! bytes, only timing and errors.
+//! 
+//! Two consumer designs are exercised against it here:
+//! 
+//! *   `fill_timeout`: retry `WouldBlock` until a deadline, then give up with
+//!     `ErrorKind::TimedOut`
+//! *   `Buffered<R>`: read the source in large blocks to amortise latency
+//! 
+//! Thoughts: the `WouldBlock` kind is what makes a timeout design possible at
+//! all; with a bare `Error` the caller can't tell "wait" from "give up". The
+//! buffered design hides latency well but turns one slow call into a rare,
+//! very slow one, which a timeout then has to accommodate.
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+// ——— traits ———
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// No entropy available yet; retry later.
+    WouldBlock,
+    /// A deadline passed before the request could be satisfied.
+    TimedOut,
+    /// The source cannot produce output; retrying will not help.
+    Unavailable,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+}
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("Rng::fill failed: {:?}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+// ——— adaptor ———
+
+/// Simulates a slow, sometimes-blocking source on top of `R`.
+#[derive(Debug)]
+pub struct Throttled<R> {
+    rng: R,
+    latency: Duration,
+    would_block_every: usize,
+    calls: usize,
+}
+
+impl<R: Rng> Throttled<R> {
+    /// Sleep for `latency` on each call; if `would_block_every` is non-zero,
+    /// every `would_block_every`-th call fails with `WouldBlock` (after
+    /// sleeping).
+    pub fn new(rng: R, latency: Duration, would_block_every: usize) -> Self {
+        Throttled { rng, latency, would_block_every, calls: 0 }
+    }
+    
+    /// Number of calls to `try_fill` so far.
+    pub fn calls(&self) -> usize {
+        self.calls
+    }
+}
+
+impl<R: Rng> Rng for Throttled<R> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.calls += 1;
+        sleep(self.latency);
+        if self.would_block_every != 0 && self.calls.is_multiple_of(self.would_block_every) {
+            return Err(Error { kind: ErrorKind::WouldBlock });
+        }
+        self.rng.try_fill(dest)
+    }
+}
+
+// ——— consumers ———
+
+/// Fill `dest`, retrying `WouldBlock` until `timeout` has elapsed.
+pub fn fill_timeout<R: Rng+?Sized>(rng: &mut R, dest: &mut [u8], timeout: Duration)
+    -> Result<(), Error>
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        match rng.try_fill(dest) {
+            Err(ref e) if e.kind == ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err(Error { kind: ErrorKind::TimedOut });
+                }
+            }
+            r => return r,
+        }
+    }
+}
+
+/// Reads the inner source `BLOCK` bytes at a time.
+#[derive(Debug)]
+pub struct Buffered<R> {
+    rng: R,
+    buf: [u8; BLOCK],
+    pos: usize,
+}
+
+const BLOCK: usize = 128;
+
+impl<R: Rng> Buffered<R> {
+    pub fn new(rng: R) -> Self {
+        Buffered { rng, buf: [0; BLOCK], pos: BLOCK }
+    }
+}
+
+impl<R: Rng> Rng for Buffered<R> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.pos == BLOCK {
+                // On error nothing is consumed, so a retry can resume here.
+                self.rng.try_fill(&mut self.buf)?;
+                self.pos = 0;
+            }
+            let n = (dest.len() - filled).min(BLOCK - self.pos);
+            dest[filled..filled + n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            filled += n;
+            self.pos += n;
+        }
+        Ok(())
+    }
+}
+
+// ——— test RNGs ———
+
+// Outputs 0, 1, 2, ... (wrapping)
+#[derive(Debug)]
+struct CountingRng(u8);
+
+impl Rng for CountingRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for b in dest {
+            *b = self.0;
+            self.0 = self.0.wrapping_add(1);
+        }
+        Ok(())
+    }
+}
+
+// ——— usage ———
+
+fn main() {
+    let ms = Duration::from_millis;
+    {
+        // The adaptor itself: latency and periodic WouldBlock, output unchanged
+        let mut r = Throttled::new(CountingRng(0), ms(2), 3);
+        let mut buf = [0u8; 4];
+        let start = Instant::now();
+        r.try_fill(&mut buf).unwrap();
+        r.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [4, 5, 6, 7]);
+        assert_eq!(r.try_fill(&mut buf).unwrap_err().kind, ErrorKind::WouldBlock);
+        assert!(start.elapsed() >= ms(6));
+        r.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [8, 9, 10, 11]);
+        println!("Throttled: {} calls, {:?}", r.calls(), start.elapsed());
+    }
+    {
+        // Timeout design: WouldBlock blips are retried within the deadline
+        let mut r = Throttled::new(CountingRng(0), ms(1), 2);
+        let mut buf = [0u8; 4];
+        for _ in 0..4 {
+            fill_timeout(&mut r, &mut buf, ms(50)).unwrap();
+        }
+        // A source which always blocks times out
+        let mut r = Throttled::new(CountingRng(0), ms(1), 1);
+        let start = Instant::now();
+        let e = fill_timeout(&mut r, &mut buf, ms(20)).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::TimedOut);
+        assert!(start.elapsed() >= ms(20));
+        println!("fill_timeout: timed out after {:?}, {} calls", start.elapsed(), r.calls());
+    }
+    {
+        // Buffered design: latency is paid once per block, not per call
+        let mut b = Buffered::new(Throttled::new(CountingRng(0), ms(5), 0));
+        let mut word = [0u8; 4];
+        let start = Instant::now();
+        for i in 0..64u32 {
+            b.fill(&mut word);
+            assert_eq!(word[0], (i * 4) as u8);
+        }
+        assert_eq!(b.rng.calls(), 2);
+        println!("Buffered: 64 reads, {} inner calls, {:?}", b.rng.calls(), start.elapsed());
+        
+        // A WouldBlock during refill is reported and the next call resumes
+        // the stream where the inner source left off
+        let mut b = Buffered::new(Throttled::new(CountingRng(0), ms(0), 2));
+        let mut block = [0u8; BLOCK];
+        b.fill(&mut block);
+        let mut next = [0u8; 1];
+        assert_eq!(b.try_fill(&mut next).unwrap_err().kind, ErrorKind::WouldBlock);
+        b.fill(&mut next);
+        assert_eq!(next[0], BLOCK as u8);
+        println!("Buffered: refill after WouldBlock resumes the stream");
+    }
+}
@@ -0,0 +1,164 @@
+//! `crypto.getRandomValues` as an entropy source on `wasm32-unknown-unknown`
+//! 
+//! Unlike the other experiments this is a Cargo crate, since `wasm-bindgen`
+//! and `wasm-pack` require one. `WasmRng` implements the fallible side of the
+//! designs (`try_fill`); `SeededRng::from_rng` shows seeding from it.
+//! 
+//! Run the tests in a headless browser with:
+//! 
+//!     wasm-pack test --headless --firefox    # or --chrome
+//! 
+//! Thoughts: the error path is real here — a worker without `crypto`, a
+//! sandboxed iframe, or a request above the 65536-byte quota all fail — so the
+//! fallible trait is needed even for a "secure, always available" source.
+//! The quota also means `try_fill` must chunk internally; callers shouldn't
+//! have to know.
+
+extern crate js_sys;
+extern crate wasm_bindgen;
+
+use std::fmt;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+// ——— error ———
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// No `crypto.getRandomValues` in this environment.
+    Unavailable,
+    /// `getRandomValues` threw.
+    Unexpected,
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// The exception's message, if any.
+    pub msg: Option<String>,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.msg {
+            Some(ref msg) => write!(f, "WasmRng: {:?}: {}", self.kind, msg),
+            None => write!(f, "WasmRng: {:?}", self.kind),
+        }
+    }
+}
+
+impl Error {
+    fn unavailable() -> Self {
+        Error { kind: ErrorKind::Unavailable, msg: None }
+    }
+    
+    fn from_js(e: JsValue) -> Self {
+        let msg = e.dyn_ref::<js_sys::Error>()
+            .map(|e| String::from(e.message()))
+            .or_else(|| e.as_string());
+        Error { kind: ErrorKind::Unexpected, msg }
+    }
+}
+
+// ——— traits ———
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("{}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+pub trait CryptoRng: Rng {}
+
+// ——— WasmRng ———
+
+#[wasm_bindgen]
+extern "C" {
+    /// A `Crypto` object (or anything pretending to be one).
+    pub type Crypto;
+    
+    #[wasm_bindgen(method, js_name = getRandomValues, catch)]
+    fn get_random_values(this: &Crypto, buf: &mut [u8]) -> Result<JsValue, JsValue>;
+}
+
+/// Maximum bytes per `getRandomValues` call (Web Crypto quota).
+pub const MAX_CHUNK: usize = 65536;
+
+/// Entropy from `crypto.getRandomValues`.
+pub struct WasmRng {
+    crypto: Crypto,
+}
+
+impl WasmRng {
+    /// Use the global `crypto` object.
+    pub fn new() -> Result<WasmRng, Error> {
+        let crypto = js_sys::Reflect::get(&js_sys::global(), &"crypto".into())
+            .map_err(|_| Error::unavailable())?;
+        WasmRng::from_crypto(crypto)
+    }
+    
+    /// Use the given object, which must have a `getRandomValues` method.
+    pub fn from_crypto(crypto: JsValue) -> Result<WasmRng, Error> {
+        if !crypto.is_object() {
+            return Err(Error::unavailable());
+        }
+        let method = js_sys::Reflect::get(&crypto, &"getRandomValues".into())
+            .map_err(|_| Error::unavailable())?;
+        if !method.is_function() {
+            return Err(Error::unavailable());
+        }
+        Ok(WasmRng { crypto: crypto.unchecked_into() })
+    }
+}
+
+impl Rng for WasmRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for chunk in dest.chunks_mut(MAX_CHUNK) {
+            self.crypto.get_random_values(chunk).map_err(Error::from_js)?;
+        }
+        Ok(())
+    }
+}
+
+impl CryptoRng for WasmRng {}
+
+// ——— seeding ———
+
+/// A small seeded generator (xorshift128), to exercise seeding from `WasmRng`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededRng {
+    s: [u32; 4],
+}
+
+impl SeededRng {
+    pub fn from_seed(seed: [u8; 16]) -> SeededRng {
+        let mut s = [0u32; 4];
+        for (x, b) in s.iter_mut().zip(seed.chunks(4)) {
+            *x = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        if s == [0; 4] {
+            s[0] = 1;   // all-zero state is a fixed point
+        }
+        SeededRng { s }
+    }
+    
+    /// Seed from another generator, propagating its errors.
+    pub fn from_rng<R: Rng+?Sized>(rng: &mut R) -> Result<SeededRng, Error> {
+        let mut seed = [0u8; 16];
+        rng.try_fill(&mut seed)?;
+        Ok(SeededRng::from_seed(seed))
+    }
+    
+    pub fn next_u32(&mut self) -> u32 {
+        let t = self.s[0] ^ (self.s[0] << 11);
+        self.s[0] = self.s[1];
+        self.s[1] = self.s[2];
+        self.s[2] = self.s[3];
+        self.s[3] ^= (self.s[3] >> 19) ^ t ^ (t >> 8);
+        self.s[3]
+    }
+}
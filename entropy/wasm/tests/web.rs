@@ -0,0 +1,88 @@
+//! Run with `wasm-pack test --headless --firefox` (or `--chrome`).
+#![cfg(target_arch = "wasm32")]
+
+extern crate js_sys;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+extern crate wasm_entropy;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::*;
+use wasm_entropy::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen(inline_js = "
+    export function throwing_crypto() {
+        return { getRandomValues: function(a) { throw new Error('quota exceeded'); } };
+    }
+")]
+extern "C" {
+    fn throwing_crypto() -> JsValue;
+}
+
+#[wasm_bindgen_test]
+fn fill() {
+    let mut rng = WasmRng::new().unwrap();
+    let mut a = [0u8; 32];
+    let mut b = [0u8; 32];
+    rng.try_fill(&mut a).unwrap();
+    rng.fill(&mut b);
+    assert!(a != [0; 32] && a != b);
+    rng.try_fill(&mut []).unwrap();
+}
+
+#[wasm_bindgen_test]
+fn fill_above_quota() {
+    // A single getRandomValues call would throw QuotaExceededError.
+    let mut rng = WasmRng::new().unwrap();
+    let mut buf = vec![0u8; 3 * MAX_CHUNK + 7];
+    rng.try_fill(&mut buf).unwrap();
+    // Every chunk, including the short tail, was written
+    for chunk in buf.chunks(MAX_CHUNK) {
+        assert!(chunk.iter().any(|&b| b != 0));
+    }
+}
+
+#[wasm_bindgen_test]
+fn dynamic_dispatch() {
+    let mut rng = WasmRng::new().unwrap();
+    let r = &mut rng as &mut dyn CryptoRng;
+    let mut buf = [0u8; 8];
+    r.try_fill(&mut buf).unwrap();
+}
+
+#[wasm_bindgen_test]
+fn seeding() {
+    let mut rng = WasmRng::new().unwrap();
+    let mut a = SeededRng::from_rng(&mut rng).unwrap();
+    let mut b = SeededRng::from_rng(&mut rng).unwrap();
+    assert!(a != b);
+    assert!((0..4).map(|_| a.next_u32()).collect::<Vec<_>>()
+        != (0..4).map(|_| b.next_u32()).collect::<Vec<_>>());
+    
+    // Seeding itself is deterministic
+    let mut c = SeededRng::from_seed([7; 16]);
+    let mut d = SeededRng::from_seed([7; 16]);
+    assert_eq!(c.next_u32(), d.next_u32());
+}
+
+#[wasm_bindgen_test]
+fn missing_crypto() {
+    let e = WasmRng::from_crypto(JsValue::UNDEFINED).err().unwrap();
+    assert_eq!(e.kind, ErrorKind::Unavailable);
+    let e = WasmRng::from_crypto(js_sys::Object::new().into()).err().unwrap();
+    assert_eq!(e.kind, ErrorKind::Unavailable);
+}
+
+#[wasm_bindgen_test]
+fn throwing_crypto_propagates() {
+    let mut rng = WasmRng::from_crypto(throwing_crypto()).unwrap();
+    let e = rng.try_fill(&mut [0u8; 4]).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::Unexpected);
+    assert_eq!(e.msg.as_ref().map(|s| &s[..]), Some("quota exceeded"));
+    
+    // ... including through seeding
+    let e = SeededRng::from_rng(&mut rng).unwrap_err();
+    assert_eq!(e.kind, ErrorKind::Unexpected);
+}
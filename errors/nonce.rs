@@ -0,0 +1,278 @@
+//! Unique nonces: a counter followed by random bytes from a `CryptoRng`
+//! 
+//! A realistic consumer where the crypto marker, fallibility and persisted
+//! state all matter. `NonceGen` produces 12-byte nonces (as for AES-GCM): a
+//! 4-byte big-endian counter, its high-water mark saved to a `CounterStore`
+//! before use so a restart never reuses one, then an 8-byte random tail,
+//! keeping nonces unique when the state is reused anyway (a restored backup
+//! or snapshot). The traits are with_policy's, with a `CryptoRng` marker on
+//! top. Compile-fail check: `fail_plain_rng`.
+//! 
+//! Thoughts: the three concerns stayed separate, but the traits cannot
+//! express the real requirement on the tail, independence from other
+//! instances: a `CryptoRng` cloned along with a snapshot satisfies the bound.
+
+#[path = "with_policy.rs"]
+#[allow(dead_code)]
+mod with_policy;
+
+pub use self::with_policy::{Error, ErrorKind, Rng};
+use self::with_policy::{ErrorInjectingRng, Injector};
+use std::collections::HashSet;
+
+// ——— traits ———
+
+/// Marks a generator as cryptographically secure.
+pub trait CryptoRng: Rng {}
+
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {}
+
+/// Durable storage for a `NonceGen`'s high-water mark.
+pub trait CounterStore {
+    /// The mark last saved, if any.
+    fn load(&mut self) -> Option<u64>;
+    
+    /// Record durably that counters below `mark` may have been used. Must not
+    /// return until the mark would survive a crash.
+    fn save(&mut self, mark: u64) -> Result<(), Error>;
+}
+
+impl<S: CounterStore+?Sized> CounterStore for &mut S {
+    fn load(&mut self) -> Option<u64> {
+        (**self).load()
+    }
+    
+    fn save(&mut self, mark: u64) -> Result<(), Error> {
+        (**self).save(mark)
+    }
+}
+
+// ——— impls ———
+
+pub const COUNTER_BYTES: usize = 4;
+pub const TAIL_BYTES: usize = 8;
+
+pub type Nonce = [u8; COUNTER_BYTES + TAIL_BYTES];
+
+/// Number of counters reserved by each save.
+pub const RESERVE: u64 = 1024;
+
+/// One past the last counter.
+const COUNTER_END: u64 = 1 << (8 * COUNTER_BYTES);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NonceError {
+    /// The generator failed; no counter was used.
+    Rng(Error),
+    /// The store failed; no counter was used.
+    Store(Error),
+    /// Every counter has been used (or skipped).
+    Exhausted,
+}
+
+/// Produces unique nonces: a counter, then random bytes.
+#[derive(Debug)]
+pub struct NonceGen<R, S> {
+    rng: R,
+    store: S,
+    /// The next counter; `COUNTER_END` once exhausted.
+    next: u64,
+    /// The saved mark: counters below it may be used without saving.
+    reserved: u64,
+}
+
+impl<R: CryptoRng, S: CounterStore> NonceGen<R, S> {
+    /// Continue from the store's mark, or start at zero if it has none.
+    pub fn new(rng: R, mut store: S) -> Self {
+        let next = store.load().unwrap_or(0).min(COUNTER_END);
+        NonceGen { rng, store, next, reserved: next }
+    }
+    
+    /// The next nonce. Errors from the generator or the store are returned,
+    /// not retried, and use up no counter; once the counter is exhausted,
+    /// every call returns `Exhausted`, never wrapping.
+    pub fn next_nonce(&mut self) -> Result<Nonce, NonceError> {
+        if self.next == COUNTER_END {
+            return Err(NonceError::Exhausted);
+        }
+        if self.next == self.reserved {
+            let mark = (self.next + RESERVE).min(COUNTER_END);
+            self.store.save(mark).map_err(NonceError::Store)?;
+            self.reserved = mark;
+        }
+        let mut nonce = [0; COUNTER_BYTES + TAIL_BYTES];
+        self.rng.try_fill(&mut nonce[COUNTER_BYTES..]).map_err(NonceError::Rng)?;
+        nonce[..COUNTER_BYTES].copy_from_slice(&(self.next as u32).to_be_bytes());
+        self.next += 1;
+        Ok(nonce)
+    }
+}
+
+// ——— test RNGs ———
+
+// SplitMix64 as bytes: a stand-in for a CryptoRng (it is not one)
+#[derive(Debug, Clone)]
+struct TestCRng(u64);
+
+impl Rng for TestCRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for chunk in dest.chunks_mut(8) {
+            self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+impl CryptoRng for TestCRng {}
+
+// A non-crypto Rng
+#[derive(Debug)]
+#[cfg_attr(not(fail_plain_rng), allow(dead_code))]
+struct TestRng(u8);
+
+impl Rng for TestRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for b in dest {
+            *b = self.0;
+        }
+        Ok(())
+    }
+}
+
+impl<R: CryptoRng> CryptoRng for ErrorInjectingRng<R> {}
+
+// In-memory store; counts saves, and fails them while `failing` is set.
+#[derive(Debug, Clone, Default)]
+struct MemStore {
+    mark: Option<u64>,
+    saves: usize,
+    failing: bool,
+}
+
+impl CounterStore for MemStore {
+    fn load(&mut self) -> Option<u64> {
+        self.mark
+    }
+    
+    fn save(&mut self, mark: u64) -> Result<(), Error> {
+        if self.failing {
+            return Err(Error::new(ErrorKind::Unavailable, "store failing"));
+        }
+        self.mark = Some(mark);
+        self.saves += 1;
+        Ok(())
+    }
+}
+
+#[cfg(fail_plain_rng)]
+fn plain_rng() {
+    // Error: `TestRng` does not implement `CryptoRng`
+    NonceGen::new(TestRng(0), MemStore::default()).next_nonce().unwrap();
+}
+
+// ——— usage ———
+
+fn counter(nonce: &Nonce) -> u32 {
+    u32::from_be_bytes([nonce[0], nonce[1], nonce[2], nonce[3]])
+}
+
+fn main() {
+    {
+        // Uniqueness across restarts (a crash every so often, losing all but
+        // the store) and generator errors: counters strictly increase, so no
+        // nonce repeats even without the tail
+        let mut store = MemStore::default();
+        let mut rng = ErrorInjectingRng {
+            rng: TestCRng(1), inject: Injector::every(7, ErrorKind::Transient),
+        };
+        let mut seen = HashSet::new();
+        let mut last = None;
+        let (mut issued, mut errors) = (0, 0);
+        for run in 0..50 {
+            let mut gen = NonceGen::new(&mut rng, &mut store);
+            for _ in 0..(run * 97) % 3000 {
+                match gen.next_nonce() {
+                    Ok(nonce) => {
+                        assert!(last < Some(counter(&nonce)), "counter reused or out of order");
+                        last = Some(counter(&nonce));
+                        assert!(seen.insert(nonce));
+                        issued += 1;
+                    }
+                    Err(e) => {
+                        assert!(matches!(e, NonceError::Rng(Error { kind: ErrorKind::Transient, .. })));
+                        errors += 1;
+                    }
+                }
+            }
+        }
+        // At most `RESERVE` counters skipped per restart
+        let skipped = last.unwrap() as usize + 1 - issued;
+        assert!(skipped <= 50 * RESERVE as usize);
+        assert!(store.saves <= issued / RESERVE as usize + 50);
+        println!("{} unique nonces over 50 restarts ({} generator errors, {} counters skipped, {} saves)",
+            issued, errors, skipped, store.saves);
+    }
+    {
+        // Reused state, from a snapshot: counters repeat, nonces do not
+        let snapshot = MemStore { mark: Some(5 * RESERVE), ..MemStore::default() };
+        let mut a = NonceGen::new(TestCRng(2), snapshot.clone());
+        let mut b = NonceGen::new(TestCRng(3), snapshot.clone());
+        for _ in 0..1000 {
+            let (x, y) = (a.next_nonce().unwrap(), b.next_nonce().unwrap());
+            assert_eq!(counter(&x), counter(&y));
+            assert!(x != y);
+        }
+        // ... unless the generator's state was captured too
+        let mut a = NonceGen::new(TestCRng(2), snapshot.clone());
+        let mut b = NonceGen::new(TestCRng(2), snapshot.clone());
+        assert_eq!(a.next_nonce(), b.next_nonce());
+        println!("restored from a snapshot: same counters, different tails");
+    }
+    {
+        // Overflow: the last counter is used, then `Exhausted`, for good
+        let mut store = MemStore { mark: Some(COUNTER_END - 3), ..MemStore::default() };
+        let mut gen = NonceGen::new(TestCRng(4), &mut store);
+        let counters: Vec<u32> = (0..3).map(|_| counter(&gen.next_nonce().unwrap())).collect();
+        assert_eq!(counters, [u32::MAX - 2, u32::MAX - 1, u32::MAX]);
+        for _ in 0..3 {
+            assert_eq!(gen.next_nonce(), Err(NonceError::Exhausted));
+        }
+        assert_eq!(store.mark, Some(COUNTER_END));
+        let mut gen = NonceGen::new(TestCRng(4), &mut store);
+        assert_eq!(gen.next_nonce(), Err(NonceError::Exhausted));
+        let mut gen = NonceGen::new(TestCRng(4), MemStore { mark: Some(u64::MAX), ..MemStore::default() });
+        assert_eq!(gen.next_nonce(), Err(NonceError::Exhausted));
+        println!("counter exhausted: Exhausted, never wraps, also after restart");
+    }
+    {
+        // Failures use up no counter: a failed save issues nothing, and a
+        // failed generator leaves the counter for the next call
+        let mut store = MemStore { failing: true, ..MemStore::default() };
+        let mut gen = NonceGen::new(TestCRng(5), &mut store);
+        assert!(matches!(gen.next_nonce(), Err(NonceError::Store(Error { kind: ErrorKind::Unavailable, .. }))));
+        assert_eq!(gen.store.mark, None);
+        gen.store.failing = false;
+        assert_eq!(counter(&gen.next_nonce().unwrap()), 0);
+        let rng = ErrorInjectingRng { rng: TestCRng(6), inject: Injector::every(2, ErrorKind::Unavailable) };
+        let mut gen = NonceGen::new(rng, MemStore::default());
+        assert_eq!(counter(&gen.next_nonce().unwrap()), 0);
+        assert!(matches!(gen.next_nonce(), Err(NonceError::Rng(Error { kind: ErrorKind::Unavailable, .. }))));
+        assert_eq!(counter(&gen.next_nonce().unwrap()), 1);
+        println!("store and generator errors: returned, no counter used");
+    }
+    {
+        // Through trait objects
+        let mut rng = TestCRng(7);
+        let mut store = MemStore::default();
+        let dyn_rng: &mut dyn CryptoRng = &mut rng;
+        let dyn_store: &mut dyn CounterStore = &mut store;
+        let mut gen = NonceGen::new(dyn_rng, dyn_store);
+        assert!(gen.next_nonce().is_ok());
+        println!("&mut dyn CryptoRng, &mut dyn CounterStore: ok");
+    }
+}
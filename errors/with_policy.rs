@@ -0,0 +1,211 @@
+//! Configurable on-error policy wrapper
+//! 
+//! Applications usually want *one* answer to "what happens when randomness
+//! fails?", not a decision at every call site. `WithPolicy<R, P>` wraps a
+//! fallible generator and lets the policy `P` decide, per error, whether to
+//! retry, substitute a fallback generator, or propagate/panic.
+//! 
+//! The `Rng` trait here is that of marker_only (fallible via `try_fill`, with
+//! a provided panicking `fill`), with the shared error of `traits/error.rs`.
+//! 
+//! Thoughts: easy to write against a trait where `try_fill` is the required
+//! method; the policy only needs to see the `Error` and the inner generator.
+//! Policies are generic over the generator so `Policy` isn't object safe, but
+//! `WithPolicy<R, P>` itself is an ordinary `Rng` and can be used as `&mut dyn
+//! Rng`, which is what matters. Note that a `Fallback` changes the output
+//! stream, so it must not be used where reproducibility is required; and a
+//! fallback should itself be at least as strong as the primary (nothing here
+//! checks that a `CryptoRng` isn't replaced by a weak generator).
+
+#[path = "../traits/error.rs"]
+#[allow(dead_code)]
+mod error;
+
+pub use self::error::{Error, ErrorKind, Injector};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// ——— traits ———
+
+pub trait Rng {
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("Rng::fill failed: {:?}", e))
+    }
+    
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+}
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        (**self).try_fill(dest)
+    }
+}
+
+// ——— policies ———
+
+/// Decides what to do when the wrapped generator returns an error.
+pub trait Policy {
+    /// Handle `err`, returned by `rng.try_fill(dest)`.
+    /// 
+    /// May retry on `rng`, fill `dest` some other way, return an error (which
+    /// `WithPolicy::try_fill` propagates) or panic.
+    fn handle<R: Rng+?Sized>(&mut self, rng: &mut R, dest: &mut [u8], err: Error)
+        -> Result<(), Error>;
+}
+
+/// Panic on any error, even from `try_fill`.
+#[derive(Debug)]
+pub struct Panic;
+
+impl Policy for Panic {
+    fn handle<R: Rng+?Sized>(&mut self, _: &mut R, _: &mut [u8], err: Error)
+        -> Result<(), Error>
+    {
+        panic!("random number generator failed: {:?}", err)
+    }
+}
+
+/// Retry transient errors up to `n` times; propagate others.
+#[derive(Debug)]
+pub struct Retry(pub usize);
+
+impl Policy for Retry {
+    fn handle<R: Rng+?Sized>(&mut self, rng: &mut R, dest: &mut [u8], mut err: Error)
+        -> Result<(), Error>
+    {
+        for _ in 0..self.0 {
+            if err.kind != ErrorKind::Transient {
+                break;
+            }
+            match rng.try_fill(dest) {
+                Ok(()) => return Ok(()),
+                Err(e) => err = e,
+            }
+        }
+        Err(err)
+    }
+}
+
+/// Fill from the generator `B` on any error.
+#[derive(Debug)]
+pub struct Fallback<B>(pub B);
+
+impl<B: Rng> Policy for Fallback<B> {
+    fn handle<R: Rng+?Sized>(&mut self, _: &mut R, dest: &mut [u8], _: Error)
+        -> Result<(), Error>
+    {
+        self.0.try_fill(dest)
+    }
+}
+
+// ——— adaptor ———
+
+/// Applies policy `P` to errors from generator `R`.
+#[derive(Debug)]
+pub struct WithPolicy<R, P> {
+    rng: R,
+    policy: P,
+}
+
+impl<R: Rng, P: Policy> WithPolicy<R, P> {
+    pub fn new(rng: R, policy: P) -> Self {
+        WithPolicy { rng, policy }
+    }
+}
+
+impl<R: Rng, P: Policy> Rng for WithPolicy<R, P> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        match self.rng.try_fill(dest) {
+            Ok(()) => Ok(()),
+            Err(e) => self.policy.handle(&mut self.rng, dest, e),
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+// Fills with a constant byte
+#[derive(Debug)]
+struct ConstRng(u8);
+
+impl Rng for ConstRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for b in dest {
+            *b = self.0;
+        }
+        Ok(())
+    }
+}
+
+// Wraps another generator, failing `try_fill` when `inject` says.
+#[derive(Debug)]
+pub struct ErrorInjectingRng<R> {
+    pub rng: R,
+    pub inject: Injector,
+}
+
+impl<R: Rng> Rng for ErrorInjectingRng<R> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.inject.check()?;
+        self.rng.try_fill(dest)
+    }
+}
+
+// The first `transient` calls fail; after them, all do if `persistent`.
+fn injecting(transient: usize, persistent: bool) -> ErrorInjectingRng<ConstRng> {
+    let inject = if persistent { Injector::persistent(transient) } else { Injector::transient(transient) };
+    ErrorInjectingRng { rng: ConstRng(1), inject }
+}
+
+// ——— usage ———
+
+fn main() {
+    let mut buf = [0u8; 4];
+    
+    {
+        // Panic: even try_fill panics
+        let mut r = WithPolicy::new(injecting(1, false), Panic);
+        assert!(catch_unwind(AssertUnwindSafe(|| r.try_fill(&mut buf))).is_err());
+        let mut r = WithPolicy::new(injecting(0, false), Panic);
+        r.fill(&mut buf);
+        assert_eq!(buf, [1; 4]);
+        println!("Panic: passes output through, panics on error");
+    }
+    {
+        // Retry(n): transient blips are absorbed
+        let mut r = WithPolicy::new(injecting(3, false), Retry(3));
+        r.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [1; 4]);
+        // ... but not too many
+        let mut r = WithPolicy::new(injecting(5, false), Retry(3));
+        assert_eq!(r.try_fill(&mut buf).unwrap_err().kind, ErrorKind::Transient);
+        // ... and persistent failures are propagated at once
+        let mut r = WithPolicy::new(injecting(1, true), Retry(3));
+        assert_eq!(r.try_fill(&mut buf).unwrap_err().kind, ErrorKind::Unavailable);
+        println!("Retry: absorbs up to n transient errors, propagates others");
+    }
+    {
+        // Fallback(B): output comes from B on error
+        let mut r = WithPolicy::new(injecting(1, true), Fallback(ConstRng(7)));
+        r.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [7; 4]);
+        r.fill(&mut buf);
+        assert_eq!(buf, [7; 4]);
+        // Policies compose: retry the primary, then fall back
+        let inner = WithPolicy::new(injecting(2, false), Retry(1));
+        let mut r = WithPolicy::new(inner, Fallback(ConstRng(9)));
+        r.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [9; 4]);
+        r.try_fill(&mut buf).unwrap();
+        assert_eq!(buf, [1; 4]);
+        println!("Fallback: substitutes the fallback generator on error");
+    }
+    {
+        // A wrapped generator is an ordinary Rng, usable via dynamic dispatch
+        let mut r = WithPolicy::new(injecting(2, false), Retry(2));
+        let dr = &mut r as &mut dyn Rng;
+        dr.fill(&mut buf);
+        println!("dynamic dispatch, using Rng: {:?}", buf);
+    }
+}
@@ -0,0 +1,339 @@
+//! ChaCha20 as a genuine `CryptoRng`, against RFC 7539
+//! 
+//! `traits/chacha.rs` is the cipher, a block core shared by every variant's
+//! test generators. Here it is checked and put to use:
+//! 
+//! *   the RFC's vectors: the block function (2.3.2, A.1 #1 and #2) and an
+//!     encryption (2.4.2)
+//! *   `ChaCha20Rng`, the core buffered by `BlockRng`: its output is the
+//!     keystream, in order, into a zeroed buffer or an uninitialised one
+//! *   the crypto side of four designs, each implemented for `ChaCha20Rng`
+//!     in its own `on_*` module, with a consumer drawing a key through the
+//!     design's crypto bound: `marker_only` (`CryptoRng: Rng`, a marker),
+//!     `extends_Rng` (`CryptoRng: Rng`, with fallible methods provided),
+//!     `separate_explicit_Rng` (an independent `CryptoRng` with `try_`
+//!     methods) and `raw_infallible` (`CryptoRng<E>: RawRng<E>`, here
+//!     `Infallible`)
+//! *   what the marker promises: "secure when correctly initialised"
+//! 
+//! Run with:
+//! 
+//!     rustc -O chacha20.rs && ./chacha20
+//! 
+//! Thoughts: the designs take a real cipher as easily as a stub: each
+//! crypto impl is a line or two over the buffered core. What it shows is
+//! the limit of the marker, a claim about the algorithm checked at the
+//! type: seeded from a 16-bit secret through `seed_from_u64`, `ChaCha20Rng`
+//! is recovered from eight bytes of output in under a second. So crypto
+//! generators should be seeded from a crypto source only (`from_rng.rs`'s
+//! `SeedFromCryptoRng`), with `seed_from_u64` documented as for tests.
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, bare_trait_objects, clippy::duplicate_mod)]
+mod marker_only;
+#[path = "../traits/extends_Rng.rs"]
+#[allow(dead_code, unused_imports, bare_trait_objects, clippy::duplicate_mod)]
+mod extends_rng;
+#[path = "../traits/separate_explicit_Rng.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+mod separate_explicit_rng;
+#[path = "../traits/raw_infallible.rs"]
+#[allow(dead_code, bare_trait_objects, clippy::duplicate_mod)]
+mod raw_infallible;
+
+use marker_only::test_rngs::block::BlockRng;
+use marker_only::test_rngs::chacha::{block, words_le, ChaCha20Core};
+use marker_only::test_rngs::seeding::SeedableRng;
+use std::time::Instant;
+
+// ——— the generator ———
+
+/// ChaCha20's keystream, a word at a time.
+#[derive(Debug, Clone)]
+pub struct ChaCha20Rng(BlockRng<ChaCha20Core>);
+
+impl SeedableRng for ChaCha20Rng {
+    type Seed = [u8; 32];
+    
+    fn from_seed(seed: [u8; 32]) -> Self {
+        ChaCha20Rng(BlockRng::new(ChaCha20Core::from_seed(seed)))
+    }
+}
+
+// ——— test vectors ———
+
+/// Bytes 0 to 31, the key of most of the RFC's vectors.
+const KEY_SEQ: [u8; 32] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+];
+
+/// One block of the RFC's block function.
+struct BlockVector {
+    section: &'static str,
+    key: [u8; 32],
+    counter: u32,
+    nonce: [u8; 12],
+    out: [u8; 64],
+}
+
+const BLOCKS: &[BlockVector] = &[
+    BlockVector {
+        section: "2.3.2",
+        key: KEY_SEQ,
+        counter: 1,
+        nonce: [0, 0, 0, 0x09, 0, 0, 0, 0x4a, 0, 0, 0, 0],
+        out: [
+            0x10, 0xf1, 0xe7, 0xe4, 0xd1, 0x3b, 0x59, 0x15, 0x50, 0x0f, 0xdd, 0x1f,
+            0xa3, 0x20, 0x71, 0xc4, 0xc7, 0xd1, 0xf4, 0xc7, 0x33, 0xc0, 0x68, 0x03,
+            0x04, 0x22, 0xaa, 0x9a, 0xc3, 0xd4, 0x6c, 0x4e, 0xd2, 0x82, 0x64, 0x46,
+            0x07, 0x9f, 0xaa, 0x09, 0x14, 0xc2, 0xd7, 0x05, 0xd9, 0x8b, 0x02, 0xa2,
+            0xb5, 0x12, 0x9c, 0xd1, 0xde, 0x16, 0x4e, 0xb9, 0xcb, 0xd0, 0x83, 0xe8,
+            0xa2, 0x50, 0x3c, 0x4e,
+        ],
+    },
+    BlockVector {
+        section: "A.1 #1",
+        key: [0; 32],
+        counter: 0,
+        nonce: [0; 12],
+        out: [
+            0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5,
+            0x53, 0x86, 0xbd, 0x28, 0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a,
+            0xa8, 0x36, 0xef, 0xcc, 0x8b, 0x77, 0x0d, 0xc7, 0xda, 0x41, 0x59, 0x7c,
+            0x51, 0x57, 0x48, 0x8d, 0x77, 0x24, 0xe0, 0x3f, 0xb8, 0xd8, 0x4a, 0x37,
+            0x6a, 0x43, 0xb8, 0xf4, 0x15, 0x18, 0xa1, 0x1c, 0xc3, 0x87, 0xb6, 0x69,
+            0xb2, 0xee, 0x65, 0x86,
+        ],
+    },
+    BlockVector {
+        section: "A.1 #2",
+        key: [0; 32],
+        counter: 1,
+        nonce: [0; 12],
+        out: [
+            0x9f, 0x07, 0xe7, 0xbe, 0x55, 0x51, 0x38, 0x7a, 0x98, 0xba, 0x97, 0x7c,
+            0x73, 0x2d, 0x08, 0x0d, 0xcb, 0x0f, 0x29, 0xa0, 0x48, 0xe3, 0x65, 0x69,
+            0x12, 0xc6, 0x53, 0x3e, 0x32, 0xee, 0x7a, 0xed, 0x29, 0xb7, 0x21, 0x76,
+            0x9c, 0xe6, 0x4e, 0x43, 0xd5, 0x71, 0x33, 0xb0, 0x74, 0xd8, 0x39, 0xd5,
+            0x31, 0xed, 0x1f, 0x28, 0x51, 0x0a, 0xfb, 0x45, 0xac, 0xe1, 0x0a, 0x1f,
+            0x4b, 0x79, 0x4d, 0x6f,
+        ],
+    },
+];
+
+/// The RFC's encryption example, 2.4.2: key 0 to 31, counter 1.
+const SUNSCREEN_NONCE: [u8; 12] = [0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+const SUNSCREEN: &[u8] = b"Ladies and Gentlemen of the class of '99: If I could offer you only one \
+    tip for the future, sunscreen would be it.";
+const SUNSCREEN_CIPHERTEXT: [u8; 114] = [
+    0x6e, 0x2e, 0x35, 0x9a, 0x25, 0x68, 0xf9, 0x80, 0x41, 0xba, 0x07, 0x28,
+    0xdd, 0x0d, 0x69, 0x81, 0xe9, 0x7e, 0x7a, 0xec, 0x1d, 0x43, 0x60, 0xc2,
+    0x0a, 0x27, 0xaf, 0xcc, 0xfd, 0x9f, 0xae, 0x0b, 0xf9, 0x1b, 0x65, 0xc5,
+    0x52, 0x47, 0x33, 0xab, 0x8f, 0x59, 0x3d, 0xab, 0xcd, 0x62, 0xb3, 0x57,
+    0x16, 0x39, 0xd6, 0x24, 0xe6, 0x51, 0x52, 0xab, 0x8f, 0x53, 0x0c, 0x35,
+    0x9f, 0x08, 0x61, 0xd8, 0x07, 0xca, 0x0d, 0xbf, 0x50, 0x0d, 0x6a, 0x61,
+    0x56, 0xa3, 0x8e, 0x08, 0x8a, 0x22, 0xb6, 0x5e, 0x52, 0xbc, 0x51, 0x4d,
+    0x16, 0xcc, 0xf8, 0x06, 0x81, 0x8c, 0xe9, 0x1a, 0xb7, 0x79, 0x37, 0x36,
+    0x5a, 0xf9, 0x0b, 0xbf, 0x74, 0xa3, 0x5b, 0xe6, 0xb4, 0x0b, 0x8e, 0xed,
+    0xf2, 0x78, 0x5e, 0x42, 0x87, 0x4d,
+];
+
+/// The RFC's block: its 32-bit counter and 96-bit nonce as the last words.
+fn rfc_block(key: &[u8; 32], counter: u32, nonce: &[u8; 12]) -> [u8; 64] {
+    let n: [u32; 3] = words_le(nonce);
+    let words = block(&words_le(key), [counter, n[0], n[1], n[2]]);
+    let mut out = [0u8; 64];
+    for (chunk, w) in out.chunks_exact_mut(4).zip(words.iter()) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    out
+}
+
+// ——— designs ———
+
+mod on_marker_only {
+    use super::marker_only::{impl_fill_from_u64, CryptoRng, Error, Rng};
+    use super::ChaCha20Rng;
+    use std::mem::MaybeUninit;
+    
+    impl Rng for ChaCha20Rng {
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.0.fill(dest);
+            Ok(())
+        }
+        
+        fn try_fill_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u8>])
+            -> Result<&'a mut [u8], Error>
+        {
+            Ok(self.0.fill_uninit(dest))
+        }
+        
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+    }
+    
+    impl CryptoRng for ChaCha20Rng {}
+    
+    pub fn key<R: CryptoRng>(rng: &mut R) -> [u8; 32] {
+        let mut key = [0; 32];
+        rng.fill(&mut key);
+        key
+    }
+    
+    /// `fill` through the words, to check they are the keystream's order.
+    pub fn key_by_words<R: CryptoRng>(rng: &mut R) -> [u8; 32] {
+        let mut key = [0; 32];
+        impl_fill_from_u64(rng, &mut key);
+        key
+    }
+    
+    /// `len` bytes, into a buffer never zeroed.
+    pub fn bytes<R: CryptoRng>(rng: &mut R, len: usize) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(len);
+        rng.try_fill_uninit(&mut buf.spare_capacity_mut()[..len]).unwrap();
+        // Sound: `try_fill_uninit` initialised the first `len` bytes
+        unsafe { buf.set_len(len) };
+        buf
+    }
+}
+
+mod on_extends_rng {
+    use super::extends_rng::{CryptoRng, Rng};
+    use super::ChaCha20Rng;
+    
+    impl Rng for ChaCha20Rng {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+    }
+    
+    impl CryptoRng for ChaCha20Rng {}
+    
+    pub fn key<R: CryptoRng>(rng: &mut R) -> [u8; 32] {
+        let mut key = [0; 32];
+        rng.fill(&mut key);
+        key
+    }
+}
+
+mod on_separate_explicit_rng {
+    use super::separate_explicit_rng::{CryptoError, CryptoRng};
+    use super::ChaCha20Rng;
+    
+    impl CryptoRng for ChaCha20Rng {
+        fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+            Ok(self.0.next_u32())
+        }
+    }
+    
+    pub fn key<R: CryptoRng>(rng: &mut R) -> [u8; 32] {
+        let mut key = [0; 32];
+        rng.try_fill(&mut key).unwrap();
+        key
+    }
+}
+
+mod on_raw_infallible {
+    use super::raw_infallible::{CryptoRng, Rng};
+    use super::ChaCha20Rng;
+    use std::convert::Infallible;
+    
+    impl Rng for ChaCha20Rng {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+    }
+    
+    impl CryptoRng<Infallible> for ChaCha20Rng {}
+    
+    pub fn key<R: CryptoRng<Infallible> + Rng>(rng: &mut R) -> [u8; 32] {
+        let mut key = [0; 32];
+        rng.fill(&mut key);
+        key
+    }
+}
+
+// ——— initialisation ———
+
+/// The 16-bit secret behind `seed_from_u64`, from eight bytes of output.
+fn recover(output: [u8; 8]) -> Option<u16> {
+    (0..=u16::MAX).find(|&secret| {
+        let mut buf = [0u8; 8];
+        ChaCha20Rng::seed_from_u64(secret.into()).0.fill(&mut buf);
+        buf == output
+    })
+}
+
+// ——— usage ———
+
+fn main() {
+    {
+        for v in BLOCKS {
+            assert_eq!(rfc_block(&v.key, v.counter, &v.nonce)[..], v.out[..], "{}", v.section);
+        }
+        let ciphertext: Vec<u8> = SUNSCREEN.chunks(64).enumerate().flat_map(|(i, chunk)| {
+            let ks = rfc_block(&KEY_SEQ, 1 + i as u32, &SUNSCREEN_NONCE);
+            chunk.iter().zip(ks.iter()).map(|(p, k)| p ^ k).collect::<Vec<u8>>()
+        }).collect();
+        assert_eq!(ciphertext[..], SUNSCREEN_CIPHERTEXT[..]);
+        println!("RFC 7539: block function (2.3.2, A.1 #1, #2) and encryption (2.4.2) ok");
+    }
+    {
+        // Under 2^32 blocks the 64-bit counter is the RFC's, the stream a
+        // zero nonce: the zero key's stream is A.1 #1 then #2
+        let mut rng = ChaCha20Rng::from_seed([0; 32]);
+        let mut out = [0u8; 128];
+        rng.0.fill(&mut out);
+        assert_eq!(out[..64], BLOCKS[1].out[..]);
+        assert_eq!(out[64..], BLOCKS[2].out[..]);
+        assert_eq!(rng.0.core().counter(), 2);
+        // Into an uninitialised buffer, the same bytes, the odd tail included
+        let mut rng = ChaCha20Rng::from_seed([0; 32]);
+        assert_eq!(on_marker_only::bytes(&mut rng, 127)[..], out[..127]);
+        println!("ChaCha20Rng: the keystream, in order");
+    }
+    {
+        // Each design's crypto bound gives the keystream; so does the shared
+        // crypto role over the core, in `marker_only`
+        let want = {
+            let mut key = [0u8; 32];
+            ChaCha20Rng::from_seed(KEY_SEQ).0.fill(&mut key);
+            key
+        };
+        let rng = || ChaCha20Rng::from_seed(KEY_SEQ);
+        assert_eq!(on_marker_only::key(&mut rng()), want);
+        assert_eq!(on_marker_only::key_by_words(&mut rng()), want);
+        assert_eq!(on_extends_rng::key(&mut rng()), want);
+        assert_eq!(on_separate_explicit_rng::key(&mut rng()), want);
+        assert_eq!(on_raw_infallible::key(&mut rng()), want);
+        let core = BlockRng::<ChaCha20Core>::from_seed(KEY_SEQ);
+        let mut role = marker_only::test_rngs::TestCRng(core);
+        assert_eq!(on_marker_only::key(&mut role), want);
+        println!("marker_only, extends_Rng, separate_explicit_Rng, raw_infallible: the keystream");
+    }
+    {
+        // The marker can't see the seed: from a 16-bit secret, and from the
+        // zero key, `ChaCha20Rng` is as `CryptoRng` as ever
+        let secret = 0xbeef;
+        let mut rng = ChaCha20Rng::seed_from_u64(secret);
+        let mut seen = [0u8; 8];
+        seen.copy_from_slice(&on_marker_only::key(&mut rng)[..8]);
+        let start = Instant::now();
+        assert_eq!(recover(seen), Some(secret as u16));
+        println!("seed_from_u64 from a 16-bit secret: recovered in {:?}", start.elapsed());
+        let mut zero = [0u8; 32];
+        ChaCha20Rng::from_seed([0; 32]).0.fill(&mut zero);
+        assert_eq!(zero[..], BLOCKS[1].out[..32]);
+        println!("the zero key: the RFC's published keystream");
+    }
+}
@@ -0,0 +1,899 @@
+//! `XorShift128` under every design: what implementing a generator costs
+//! 
+//! Marsaglia's xorshift128 (as `rand_xorshift`'s `XorShiftRng`) is written
+//! once, with `SeedableRng` from `seeding.rs`, and implemented under each
+//! design of `traits/` in its own `on_*` module: the *implementer's* part,
+//! the fewest methods that work, and the *caller's* part, `draw`, two `u64`
+//! and an 11-byte `fill` through the design's bound. `main` counts, from
+//! this file's own source, each implementer's lines, `impl` blocks and
+//! methods and each part's `unwrap`s, then checks every draw against the
+//! reference output. The nightly designs read `n/a` without the `nightly`
+//! cfg; their counts are from the source regardless.
+//! 
+//! Run with:
+//! 
+//!     rustc -O xorshift.rs && ./xorshift
+//!     rustc +nightly -O --cfg 'feature="nightly"' xorshift.rs && ./xorshift
+//! 
+//! Thoughts: the counts barely tell the designs apart: 22 of 29 cost one
+//! `impl` of one method. What costs is requiring bytes (`from_rng`,
+//! `marker_only`, `capabilities`), naming the other trait
+//! (`extends_CryptoRng`, `auto_trait_CryptoRng`, silent if forgotten), and
+//! impls which compile and are wrong: `traps` holds two, where word methods
+//! provided through each other recurse forever if one is left out.
+
+#![cfg_attr(feature = "nightly", feature(
+    auto_traits,
+    negative_bounds,
+    negative_impls,
+    never_type,
+    rustc_attrs,
+    specialization,
+    with_negative_coherence,
+))]
+#![cfg_attr(feature = "nightly", allow(incomplete_features, internal_features))]
+// Each variant includes the shared files itself, as its own crate root
+#![allow(clippy::duplicate_mod)]
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+#[path = "../traits/seeding.rs"]
+#[allow(dead_code)]
+mod seeding;
+
+use seeding::SeedableRng;
+
+// ——— the generator ———
+
+/// Marsaglia's xorshift128: four words of state, period 2^128 - 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorShift128 {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+}
+
+/// Marsaglia's initial state, from the paper.
+const MARSAGLIA: [u32; 4] = [123456789, 362436069, 521288629, 88675123];
+
+impl XorShift128 {
+    pub fn from_words(words: [u32; 4]) -> Self {
+        if words == [0; 4] {
+            return XorShift128::from_words(MARSAGLIA);
+        }
+        let [x, y, z, w] = words;
+        XorShift128 { x, y, z, w }
+    }
+    
+    #[inline]
+    fn next_word(&mut self) -> u32 {
+        let t = self.x ^ (self.x << 11);
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        self.w ^= (self.w >> 19) ^ t ^ (t >> 8);
+        self.w
+    }
+}
+
+impl SeedableRng for XorShift128 {
+    type Seed = [u8; 16];
+    
+    fn from_seed(seed: [u8; 16]) -> Self {
+        let mut words = [0u32; 4];
+        for (w, b) in words.iter_mut().zip(seed.chunks_exact(4)) {
+            *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        XorShift128::from_words(words)
+    }
+}
+
+/// What a caller draws: two `u64`, then 11 bytes, where the design can fill.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Draw {
+    u64s: [u64; 2],
+    fill: Option<[u8; 11]>,
+}
+
+// ——— designs ———
+
+#[path = "../traits/assoc_error.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod assoc_error;
+#[cfg(feature = "nightly")]
+#[path = "../traits/auto_trait_CryptoRng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod auto_trait_cryptorng;
+#[path = "../traits/batched_fallibility.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod batched_fallibility;
+#[path = "../traits/block_output.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod block_output;
+#[path = "../traits/capabilities.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod capabilities;
+#[path = "../traits/const_crypto.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod const_crypto;
+#[path = "../traits/downcast.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod downcast;
+#[path = "../traits/extends_CryptoRng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod extends_cryptorng;
+#[path = "../traits/extends_CryptoRng2.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod extends_cryptorng2;
+#[path = "../traits/extends_CryptoRng3.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod extends_cryptorng3;
+#[path = "../traits/extends_Rng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod extends_rng;
+#[path = "../traits/extends_Rng2.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod extends_rng2;
+#[path = "../traits/extends_Rng_infallible.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod extends_rng_infallible;
+#[path = "../traits/fallibility_param.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod fallibility_param;
+#[path = "../traits/from_rng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod from_rng;
+#[path = "../traits/kind_param.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod kind_param;
+#[path = "../traits/lending_block.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod lending_block;
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod marker_only;
+#[cfg(feature = "nightly")]
+#[path = "../traits/negative_impls.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod negative_impls;
+#[path = "../traits/raw_default.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_default;
+#[cfg(feature = "nightly")]
+#[path = "../traits/raw_explicit.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_explicit;
+#[path = "../traits/raw_infallible.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_infallible;
+#[cfg(feature = "nightly")]
+#[path = "../traits/raw_newtype.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_newtype;
+#[cfg(feature = "nightly")]
+#[path = "../traits/raw_rng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod raw_rng;
+#[path = "../traits/separate_explicit_Rng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod separate_explicit_rng;
+#[path = "../traits/separate_implicit_Rng.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod separate_implicit_rng;
+#[path = "../traits/separate_implicit_Rng2.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod separate_implicit_rng2;
+#[path = "../traits/separate_implicit_Rng3.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod separate_implicit_rng3;
+#[cfg(feature = "nightly")]
+#[path = "../traits/specialization.rs"]
+#[allow(dead_code, deprecated, unused_attributes, unused_imports, bare_trait_objects)]
+mod specialization;
+
+// ——— implementations ———
+
+mod on_assoc_error {
+    use super::assoc_error::{Rng, TryRng};
+    use super::{Draw, XorShift128};
+    use std::convert::Infallible;
+    
+    impl TryRng for XorShift128 {
+        type Error = Infallible;
+        
+        fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+            Ok(self.next_word())
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod on_auto_trait_cryptorng {
+    use super::auto_trait_cryptorng::{CryptoRng, Rng};
+    use super::{Draw, XorShift128};
+    
+    impl !CryptoRng for XorShift128 {}
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_batched_fallibility {
+    use super::batched_fallibility::{Error, TryRng};
+    use super::{Draw, XorShift128};
+    
+    impl TryRng for XorShift128 {
+        fn try_next_u32(&mut self) -> Result<u32, Error> {
+            Ok(self.next_word())
+        }
+    }
+    
+    pub fn draw<R: TryRng>(rng: &mut R) -> Draw {
+        let mut next = || u64::from(rng.try_next_u32().unwrap());
+        let mut u64 = || next() | next() << 32;
+        Draw { u64s: [u64(), u64()], fill: None }
+    }
+}
+
+mod on_block_output {
+    use super::block_output::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u64(&mut self) -> u64 {
+            let low = self.next_word();
+            u64::from(self.next_word()) << 32 | u64::from(low)
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        Draw { u64s: rng.next_block(), fill: None }
+    }
+}
+
+mod on_capabilities {
+    use super::capabilities::{Error, Rng};
+    use super::{bridge, Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            bridge::fill_via_u64(dest, || self.next_u64());
+            Ok(())
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            bridge::u64_from_u32(self.next_word(), self.next_word())
+        }
+        
+        fn is_fallible(&self) -> bool {
+            false
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.try_fill(&mut fill).unwrap();
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_const_crypto {
+    use super::const_crypto::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_downcast {
+    use super::downcast::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_extends_cryptorng {
+    use super::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use super::{Draw, XorShift128};
+    
+    impl CryptoRng for XorShift128 {
+        fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+            Ok(self.next_word())
+        }
+    }
+    
+    impl Rng for XorShift128 {}
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_extends_cryptorng2 {
+    use super::extends_cryptorng2::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_extends_cryptorng3 {
+    use super::extends_cryptorng3::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_extends_rng {
+    use super::extends_rng::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_extends_rng2 {
+    use super::extends_rng2::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_extends_rng_infallible {
+    use super::extends_rng_infallible::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_fallibility_param {
+    use super::fallibility_param::{Infallible, Rng};
+    use super::{Draw, XorShift128};
+    
+    impl Rng<Infallible> for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng<Infallible>>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_from_rng {
+    use super::from_rng::{Error, Rng};
+    use super::{bridge, Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            bridge::fill_via_u64(dest, || {
+                bridge::u64_from_u32(self.next_word(), self.next_word())
+            });
+            Ok(())
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_kind_param {
+    use super::kind_param::{Gen, Plain};
+    use super::{Draw, XorShift128};
+    
+    impl Gen<Plain> for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Gen<Plain>>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_lending_block {
+    use super::lending_block::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let mut u64 = || u64::from(rng.next_u32()) | u64::from(rng.next_u32()) << 32;
+        Draw { u64s: [u64(), u64()], fill: None }
+    }
+}
+
+mod on_marker_only {
+    use super::marker_only::{impl_fill_from_u64, impl_next_u64_via_u32, Error, Rng};
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            impl_fill_from_u64(self, dest);
+            Ok(())
+        }
+        
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            impl_next_u64_via_u32(self)
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod on_negative_impls {
+    use super::negative_impls::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_raw_default {
+    use super::raw_default::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod on_raw_explicit {
+    use super::raw_explicit::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_raw_infallible {
+    use super::raw_infallible::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod on_raw_newtype {
+    use super::raw_newtype::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod on_raw_rng {
+    use super::raw_rng::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_separate_explicit_rng {
+    use super::separate_explicit_rng::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_separate_implicit_rng {
+    use super::separate_implicit_rng::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_separate_implicit_rng2 {
+    use super::separate_implicit_rng2::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+mod on_separate_implicit_rng3 {
+    use super::separate_implicit_rng3::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+#[cfg(feature = "nightly")]
+mod on_specialization {
+    use super::specialization::Rng;
+    use super::{Draw, XorShift128};
+    
+    impl Rng for XorShift128 {
+        fn next_u32(&mut self) -> u32 {
+            self.next_word()
+        }
+    }
+    
+    pub fn draw<R: Rng>(rng: &mut R) -> Draw {
+        let u64s = [rng.next_u64(), rng.next_u64()];
+        let mut fill = [0; 11];
+        rng.fill(&mut fill);
+        Draw { u64s, fill: Some(fill) }
+    }
+}
+
+// ——— traps ———
+
+/// Impls the compiler accepts, whose methods recurse forever: each design
+/// provides the word method the impl left out in terms of the one it gave.
+#[allow(dead_code)]
+mod traps {
+    use super::{marker_only, raw_default};
+    use super::marker_only::{impl_next_u64_via_u32, Error};
+    
+    pub struct Forgetful;
+    
+    // `next_u32` is provided through `try_next_u32`, which the blanket
+    // `RawRng<Infallible>` impl forwards to `next_u32`
+    impl raw_default::Rng for Forgetful {}
+    
+    // `next_u32` is provided through `next_u64`, here through `next_u32`
+    impl marker_only::Rng for Forgetful {
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            marker_only::impl_fill_from_u64(self, dest);
+            Ok(())
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            impl_next_u64_via_u32(self)
+        }
+    }
+}
+
+// ——— counting ———
+
+/// Counts for one `on_*` module of `SOURCE`.
+#[derive(Debug, Default)]
+struct Counts {
+    lines: usize,
+    impls: usize,
+    methods: usize,
+    impl_unwraps: usize,
+    caller_unwraps: usize,
+}
+
+const SOURCE: &str = include_str!("xorshift.rs");
+
+/// Counts each `on_*` module's parts: the implementer's, up to `pub fn
+/// draw`, and the caller's, from it. Lines are code lines, without `use`s.
+fn count() -> Vec<(&'static str, Counts)> {
+    let mut modules = vec![];
+    let mut lines = SOURCE.lines();
+    while let Some(line) = lines.next() {
+        let name = match line.strip_prefix("mod on_") {
+            Some(rest) => rest.trim_end_matches(" {"),
+            None => continue,
+        };
+        let mut counts = Counts::default();
+        let mut caller = false;
+        for line in lines.by_ref().take_while(|&l| l != "}") {
+            let code = line.trim();
+            caller |= code.starts_with("pub fn draw");
+            let unwraps = code.matches(".unwrap()").count() + code.matches(".expect(").count();
+            if caller {
+                counts.caller_unwraps += unwraps;
+                continue;
+            }
+            counts.impl_unwraps += unwraps;
+            if !(code.is_empty() || code.starts_with("//") || code.starts_with("use ")) {
+                counts.lines += 1;
+            }
+            counts.impls += (code.starts_with("impl ") || code.starts_with("impl<")) as usize;
+            counts.methods += code.starts_with("fn ") as usize;
+        }
+        modules.push((name, counts));
+    }
+    modules
+}
+
+// ——— usage ———
+
+fn main() {
+    let seed = || XorShift128::from_words(MARSAGLIA);
+    let words: Vec<u32> = {
+        let mut rng = seed();
+        (0..8).map(|_| rng.next_word()).collect()
+    };
+    {
+        // Marsaglia's output from his initial state, and the zero seed
+        assert_eq!(words[..5], [3701687786, 458299110, 2500872618, 3633119408, 516391518]);
+        assert_eq!(XorShift128::from_seed([0; 16]), seed());
+        println!("xorshift128: reference output ok; the zero seed is Marsaglia's state");
+    }
+    
+    // Every design's draw: words low first, then the fill's bytes in order
+    let u64 = |i: usize| u64::from(words[i]) | u64::from(words[i + 1]) << 32;
+    let mut bytes = [0u8; 11];
+    bytes[..8].copy_from_slice(&u64(4).to_le_bytes());
+    bytes[8..].copy_from_slice(&words[6].to_le_bytes()[..3]);
+    let want = |fill: bool| Draw { u64s: [u64(0), u64(2)], fill: fill.then_some(bytes) };
+    
+    #[cfg_attr(not(feature = "nightly"), allow(unused_mut))]
+    let mut draws: Vec<(&str, Draw)> = vec![
+        ("assoc_error", on_assoc_error::draw(&mut seed())),
+        ("batched_fallibility", on_batched_fallibility::draw(&mut seed())),
+        ("block_output", on_block_output::draw(&mut seed())),
+        ("capabilities", on_capabilities::draw(&mut seed())),
+        ("const_crypto", on_const_crypto::draw(&mut seed())),
+        ("downcast", on_downcast::draw(&mut seed())),
+        ("extends_cryptorng", on_extends_cryptorng::draw(&mut seed())),
+        ("extends_cryptorng2", on_extends_cryptorng2::draw(&mut seed())),
+        ("extends_cryptorng3", on_extends_cryptorng3::draw(&mut seed())),
+        ("extends_rng", on_extends_rng::draw(&mut seed())),
+        ("extends_rng2", on_extends_rng2::draw(&mut seed())),
+        ("extends_rng_infallible", on_extends_rng_infallible::draw(&mut seed())),
+        ("fallibility_param", on_fallibility_param::draw(&mut seed())),
+        ("from_rng", on_from_rng::draw(&mut seed())),
+        ("kind_param", on_kind_param::draw(&mut seed())),
+        ("lending_block", on_lending_block::draw(&mut seed())),
+        ("marker_only", on_marker_only::draw(&mut seed())),
+        ("raw_default", on_raw_default::draw(&mut seed())),
+        ("raw_infallible", on_raw_infallible::draw(&mut seed())),
+        ("separate_explicit_rng", on_separate_explicit_rng::draw(&mut seed())),
+        ("separate_implicit_rng", on_separate_implicit_rng::draw(&mut seed())),
+        ("separate_implicit_rng2", on_separate_implicit_rng2::draw(&mut seed())),
+        ("separate_implicit_rng3", on_separate_implicit_rng3::draw(&mut seed())),
+    ];
+    #[cfg(feature = "nightly")]
+    draws.extend(vec![
+        ("auto_trait_cryptorng", on_auto_trait_cryptorng::draw(&mut seed())),
+        ("negative_impls", on_negative_impls::draw(&mut seed())),
+        ("raw_explicit", on_raw_explicit::draw(&mut seed())),
+        ("raw_newtype", on_raw_newtype::draw(&mut seed())),
+        ("raw_rng", on_raw_rng::draw(&mut seed())),
+        ("specialization", on_specialization::draw(&mut seed())),
+    ]);
+    
+    println!();
+    println!("{:<24} {:>5} {:>5} {:>7} {:>7} {:>8}  output", "design", "lines", "impls",
+        "methods", "unwraps", "(caller)");
+    for (name, c) in count() {
+        let output = match draws.iter().find(|d| d.0 == name) {
+            None => "n/a",
+            Some((_, d)) => {
+                assert_eq!(*d, want(d.fill.is_some()), "{}: differs from the reference", name);
+                if d.fill.is_some() { "ok" } else { "ok, no fill" }
+            }
+        };
+        println!("{:<24} {:>5} {:>5} {:>7} {:>7} {:>8}  {}", name, c.lines, c.impls, c.methods,
+            c.impl_unwraps, c.caller_unwraps, output);
+    }
+}
@@ -0,0 +1,35 @@
+//! Impl conflicts of each design, enforced
+//! 
+//! Where `compare` reports a design giving no type both roles, or accepting
+//! no plain generator where a crypto one is asked, the compiler's error is
+//! kept here: `impl_conflicts/fail` (and `impl_conflicts/nightly`, for the
+//! designs needing the `nightly` feature) has a `_dual_impl` case for each
+//! design whose blanket impls leave no room for a type implementing both
+//! traits, and a `_plain_as_crypto` case for each design refusing a plain
+//! `TestRng` to a function asking for its crypto trait. The designs
+//! accepting either are not here; `compare` shows them as `yes`.
+//! 
+//! Run with:
+//! 
+//!     cargo test --test impl_conflicts
+//!     cargo +nightly test --features nightly --test impl_conflicts
+//! 
+//! The `.stderr` files of `fail` hold stable's wording and those of `nightly`
+//! nightly's, which differ in the trait bound errors' notes (the `nightly`
+//! modules show up as similarly named traits), so each directory is checked
+//! on its own toolchain.
+//! 
+//! After a compiler upgrade changes the wording of an error, `TRYBUILD=overwrite`
+//! rewrites the `.stderr` files; review the diff before committing.
+
+extern crate trybuild;
+
+#[test]
+fn impl_conflicts() {
+    let t = trybuild::TestCases::new();
+    if cfg!(feature = "nightly") {
+        t.compile_fail("tests/impl_conflicts/nightly/*.rs");
+    } else {
+        t.compile_fail("tests/impl_conflicts/fail/*.rs");
+    }
+}
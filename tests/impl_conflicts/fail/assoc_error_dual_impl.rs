@@ -0,0 +1,31 @@
+//! `assoc_error`: a type can't be made both plain and crypto
+//! 
+//! `TryRng` is implemented once per type, so a type picks one `Error` and
+//! with it one role.
+
+extern crate rand_design;
+
+use std::convert::Infallible;
+
+use rand_design::assoc_error::{CryptoError, TryRng};
+
+struct TestBothRng(u32);
+
+impl TryRng for TestBothRng {
+    type Error = Infallible;
+    
+    fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+        Ok(self.0)
+    }
+}
+
+// Error: conflicts with the `TryRng` impl above
+impl TryRng for TestBothRng {
+    type Error = CryptoError;
+    
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        Ok(self.0)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+//! `assoc_error`: a plain generator isn't accepted where `TryRng<Error = CryptoError>` is asked
+
+extern crate rand_design;
+
+use rand_design::assoc_error::test_rngs::{Constant, TestRng};
+use rand_design::assoc_error::{CryptoError, TryRng};
+
+fn key<R: TryRng<Error = CryptoError> + ?Sized>(rng: &mut R) {
+    let _ = rng.try_next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
@@ -0,0 +1,25 @@
+//! `const_crypto`: a type can't be made both plain and crypto
+//! 
+//! `impl<R: Rng<true>+?Sized> Rng for R` already makes every crypto generator
+//! plain, so the impl marked below overlaps it.
+
+extern crate rand_design;
+
+use rand_design::const_crypto::{Rng};
+
+struct TestBothRng(u32);
+
+// Error: conflicts with `impl<R: Rng<true>+?Sized> Rng for R`
+impl Rng for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+impl Rng<true> for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+//! `const_crypto`: a plain generator isn't accepted where `Rng<true>` is asked
+
+extern crate rand_design;
+
+use rand_design::const_crypto::test_rngs::{Constant, TestRng};
+use rand_design::const_crypto::{Rng};
+
+fn key<R: Rng<true> + ?Sized>(rng: &mut R) {
+    let _ = rng.next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
@@ -0,0 +1,25 @@
+//! `extends_cryptorng2`: a type can't be made both plain and crypto
+//! 
+//! `impl<R: Rng+?Sized> CryptoRng for R` already makes every plain generator
+//! crypto, so the impl marked below overlaps it.
+
+extern crate rand_design;
+
+use rand_design::extends_cryptorng2::{CryptoError, CryptoRng, Rng};
+
+struct TestBothRng(u32);
+
+impl Rng for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+// Error: conflicts with `impl<R: Rng+?Sized> CryptoRng for R`
+impl CryptoRng for TestBothRng {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        Ok(self.0)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+//! `extends_rng2`: a plain generator isn't accepted where `CryptoRng` is asked
+
+extern crate rand_design;
+
+use rand_design::extends_rng2::test_rngs::{Constant, TestRng};
+use rand_design::extends_rng2::{CryptoRng};
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) {
+    let _ = rng.try_next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
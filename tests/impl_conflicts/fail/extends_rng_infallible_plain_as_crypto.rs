@@ -0,0 +1,14 @@
+//! `extends_rng_infallible`: a plain generator isn't accepted where `CryptoRng` is asked
+
+extern crate rand_design;
+
+use rand_design::extends_rng_infallible::test_rngs::{Constant, TestRng};
+use rand_design::extends_rng_infallible::{CryptoRng};
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) {
+    let _ = rng.next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
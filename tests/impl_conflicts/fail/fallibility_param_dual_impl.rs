@@ -0,0 +1,27 @@
+//! `fallibility_param`: a type can't be made both plain and crypto
+//! 
+//! `impl<R: Rng<Infallible>+?Sized> Rng<Fallible> for R` already makes every
+//! plain generator fallible, so the impl marked below overlaps it.
+
+extern crate rand_design;
+
+use rand_design::fallibility_param::{CryptoRng, Error, Fallible, Infallible, Rng};
+
+struct TestBothRng(u32);
+
+impl Rng<Infallible> for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+// Error: conflicts with `impl<R: Rng<Infallible>+?Sized> Rng<Fallible> for R`
+impl Rng<Fallible> for TestBothRng {
+    fn next_u32(&mut self) -> Result<u32, Error> {
+        Ok(self.0)
+    }
+}
+
+impl CryptoRng<Fallible> for TestBothRng {}
+
+fn main() {}
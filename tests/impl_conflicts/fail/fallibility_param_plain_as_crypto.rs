@@ -0,0 +1,14 @@
+//! `fallibility_param`: a plain generator isn't accepted where `CryptoRng<Fallible>` is asked
+
+extern crate rand_design;
+
+use rand_design::fallibility_param::test_rngs::{Constant, TestRng};
+use rand_design::fallibility_param::{CryptoRng, Fallible};
+
+fn key<R: CryptoRng<Fallible> + ?Sized>(rng: &mut R) {
+    let _ = rng.next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
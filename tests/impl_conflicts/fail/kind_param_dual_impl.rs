@@ -0,0 +1,25 @@
+//! `kind_param`: a type can't be made both plain and crypto
+//! 
+//! `impl<R: Gen<Crypto>+?Sized> Gen<Plain> for R` already makes every crypto
+//! generator plain, so the impl marked below overlaps it.
+
+extern crate rand_design;
+
+use rand_design::kind_param::{Crypto, Gen, Plain};
+
+struct TestBothRng(u32);
+
+// Error: conflicts with `impl<R: Gen<Crypto>+?Sized> Gen<Plain> for R`
+impl Gen<Plain> for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+impl Gen<Crypto> for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+//! `kind_param`: a plain generator isn't accepted where `Gen<Crypto>` is asked
+
+extern crate rand_design;
+
+use rand_design::kind_param::test_rngs::{Constant, TestRng};
+use rand_design::kind_param::{Crypto, Gen};
+
+fn key<R: Gen<Crypto> + ?Sized>(rng: &mut R) {
+    let _ = rng.next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
@@ -0,0 +1,14 @@
+//! `marker_only`: a plain generator isn't accepted where `CryptoRng` is asked
+
+extern crate rand_design;
+
+use rand_design::marker_only::test_rngs::{Constant, TestRng};
+use rand_design::marker_only::{CryptoRng};
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) {
+    let _ = rng.try_fill(&mut [0u8; 8]);
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
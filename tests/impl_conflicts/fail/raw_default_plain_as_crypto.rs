@@ -0,0 +1,14 @@
+//! `raw_default`: a plain generator isn't accepted where `CryptoRng` is asked
+
+extern crate rand_design;
+
+use rand_design::raw_default::test_rngs::{Constant, TestRng};
+use rand_design::raw_default::{CryptoRng};
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) {
+    let _ = rng.try_next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
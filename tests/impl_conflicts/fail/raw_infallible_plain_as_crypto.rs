@@ -0,0 +1,14 @@
+//! `raw_infallible`: a plain generator isn't accepted where `CryptoRng<Error>` is asked
+
+extern crate rand_design;
+
+use rand_design::raw_infallible::test_rngs::{Constant, TestRng};
+use rand_design::raw_infallible::{CryptoRng, Error};
+
+fn key<R: CryptoRng<Error> + ?Sized>(rng: &mut R) {
+    let _ = rng.try_next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
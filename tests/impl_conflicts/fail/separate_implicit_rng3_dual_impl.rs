@@ -0,0 +1,25 @@
+//! `separate_implicit_rng3`: a type can't be made both plain and crypto
+//! 
+//! `impl<CR: CryptoRng+?Sized> Rng for CR` already makes every crypto
+//! generator plain, so the impl marked below overlaps it.
+
+extern crate rand_design;
+
+use rand_design::separate_implicit_rng3::{CryptoError, CryptoRng, Rng};
+
+struct TestBothRng(u32);
+
+// Error: conflicts with `impl<CR: CryptoRng+?Sized> Rng for CR`
+impl Rng for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+impl CryptoRng for TestBothRng {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        Ok(self.0)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+//! `auto_trait_cryptorng`: a plain generator isn't accepted where `Rng + CryptoRng` is asked
+
+extern crate rand_design;
+
+use rand_design::auto_trait_cryptorng::test_rngs::{Constant, TestRng};
+use rand_design::auto_trait_cryptorng::{CryptoRng, Rng};
+
+fn key<R: Rng + CryptoRng + ?Sized>(rng: &mut R) {
+    let _ = rng.next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
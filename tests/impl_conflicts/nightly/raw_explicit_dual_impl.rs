@@ -0,0 +1,27 @@
+//! `raw_explicit`: a type can't be made both plain and crypto
+//! 
+//! `impl<R: RawRng<!>+?Sized> RawRng<Error> for R` already makes every
+//! plain generator fallible, so the impl marked below overlaps it.
+
+extern crate rand_design;
+
+use rand_design::raw_explicit::{CryptoRng, Error, RawRng, Rng};
+
+struct TestBothRng(u32);
+
+impl Rng for TestBothRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+// Error: conflicts with `impl<R: RawRng<!>+?Sized> RawRng<Error> for R`
+impl RawRng<Error> for TestBothRng {
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        Ok(self.0)
+    }
+}
+
+impl CryptoRng<Error> for TestBothRng {}
+
+fn main() {}
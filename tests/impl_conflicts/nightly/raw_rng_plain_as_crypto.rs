@@ -0,0 +1,14 @@
+//! `raw_rng`: a plain generator isn't accepted where `RawRng<CryptoError>` is asked
+
+extern crate rand_design;
+
+use rand_design::raw_rng::test_rngs::{Constant, TestRng};
+use rand_design::raw_rng::{CryptoError, RawRng};
+
+fn key<R: RawRng<CryptoError> + ?Sized>(rng: &mut R) {
+    let _ = rng.try_next_u32();
+}
+
+fn main() {
+    key(&mut TestRng(Constant(1)));
+}
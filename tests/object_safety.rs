@@ -0,0 +1,29 @@
+//! Object safety of each design, enforced
+//! 
+//! The variants' headers say which of their traits make objects. Here each
+//! design's traits are made into objects from its own test generators, in
+//! `object_safety/pass` (and `object_safety/nightly`, for the designs needing
+//! the `nightly` feature): `&mut dyn Rng`, `&mut dyn CryptoRng` under the
+//! design's names, and `Box<dyn RawRng<E>>` for each `E` of the `raw_`
+//! designs. The traits said to make no object are in `object_safety/fail`,
+//! with the compiler's error.
+//! 
+//! Run with:
+//! 
+//!     cargo test --test object_safety
+//!     cargo +nightly test --features nightly --test object_safety
+//! 
+//! After a compiler upgrade changes the wording of an error, `TRYBUILD=overwrite`
+//! rewrites the `.stderr` files; review the diff before committing.
+
+extern crate trybuild;
+
+#[test]
+fn object_safety() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/object_safety/pass/*.rs");
+    if cfg!(feature = "nightly") {
+        t.pass("tests/object_safety/nightly/*.rs");
+    }
+    t.compile_fail("tests/object_safety/fail/*.rs");
+}
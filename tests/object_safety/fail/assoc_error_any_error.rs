@@ -0,0 +1,10 @@
+//! `assoc_error`: no `dyn TryRng` for "any generator", the error type being
+//! part of the object type
+
+extern crate rand_design;
+
+use rand_design::assoc_error::TryRng;
+
+fn main() {
+    let _: Option<&mut dyn TryRng> = None;
+}
@@ -0,0 +1,9 @@
+//! `cloneable`: `CloneableRng` needs `Clone`, so `Sized`, and makes no object
+
+extern crate rand_design;
+
+use rand_design::cloneable::CloneableRng;
+
+fn main() {
+    let _: Option<Box<dyn CloneableRng>> = None;
+}
@@ -0,0 +1,10 @@
+//! `lending_block`: `LendingRng` makes no object, for its generic associated
+//! type
+
+extern crate rand_design;
+
+use rand_design::lending_block::LendingRng;
+
+fn main() {
+    let _: Option<&mut dyn LendingRng<Block<'static> = &'static [u32]>> = None;
+}
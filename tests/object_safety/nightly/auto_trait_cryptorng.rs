@@ -0,0 +1,13 @@
+//! `auto_trait_cryptorng`: `&mut dyn Rng` and `&mut dyn Rng + CryptoRng`
+
+extern crate rand_design;
+
+use rand_design::auto_trait_cryptorng::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::auto_trait_cryptorng::{CryptoRng, Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut (dyn Rng + CryptoRng) = &mut TestCRng(Constant(2));
+    let _ = c.next_u32();
+}
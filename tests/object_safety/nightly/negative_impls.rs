@@ -0,0 +1,13 @@
+//! `negative_impls`: `&mut dyn Rng` and `&mut dyn CryptoRng`
+
+extern crate rand_design;
+
+use rand_design::negative_impls::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::negative_impls::{CryptoRng, Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn CryptoRng = &mut TestCRng(Constant(2));
+    let _ = c.try_next_u32();
+}
@@ -0,0 +1,20 @@
+//! `raw_explicit`: `&mut dyn Rng`, `&mut dyn CryptoRng<Error>`,
+//! `Box<dyn RawRng<!>>` and `Box<dyn RawRng<Error>>`
+
+#![feature(never_type)]
+
+extern crate rand_design;
+
+use rand_design::raw_explicit::test_rngs::{Constant, TestFCRng, TestRng};
+use rand_design::raw_explicit::{CryptoRng, Error, RawRng, Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn CryptoRng<Error> = &mut TestFCRng(Constant(2));
+    let _ = c.try_next_u32();
+    let mut b0: Box<dyn RawRng<!>> = Box::new(TestRng(Constant(3)));
+    let _ = b0.try_next_u32();
+    let mut b1: Box<dyn RawRng<Error>> = Box::new(TestFCRng(Constant(4)));
+    let _ = b1.try_next_u32();
+}
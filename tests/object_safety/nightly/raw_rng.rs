@@ -0,0 +1,18 @@
+//! `raw_rng`: `&mut dyn Rng`, `&mut dyn RawRng<CryptoError>` and
+//! `Box<dyn RawRng<!>>`
+
+#![feature(never_type)]
+
+extern crate rand_design;
+
+use rand_design::raw_rng::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::raw_rng::{CryptoError, RawRng, Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn RawRng<CryptoError> = &mut TestCRng(Constant(2));
+    let _ = c.try_next_u32();
+    let mut b: Box<dyn RawRng<!>> = Box::new(TestRng(Constant(3)));
+    let _ = b.try_next_u32();
+}
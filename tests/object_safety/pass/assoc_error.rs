@@ -0,0 +1,13 @@
+//! `assoc_error`: `&mut dyn Rng` and `&mut dyn TryRng<Error = CryptoError>`
+
+extern crate rand_design;
+
+use rand_design::assoc_error::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::assoc_error::{CryptoError, Rng, TryRng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn TryRng<Error = CryptoError> = &mut TestCRng(Constant(2));
+    let _ = c.try_next_u32();
+}
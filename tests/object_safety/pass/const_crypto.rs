@@ -0,0 +1,13 @@
+//! `const_crypto`: `&mut dyn Rng` and `&mut dyn Rng<true>`
+
+extern crate rand_design;
+
+use rand_design::const_crypto::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::const_crypto::{Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn Rng<true> = &mut TestCRng(Constant(2));
+    let _ = c.next_u32();
+}
@@ -0,0 +1,13 @@
+//! `extends_rng_infallible`: `&mut dyn Rng` and `&mut dyn CryptoRng`
+
+extern crate rand_design;
+
+use rand_design::extends_rng_infallible::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::extends_rng_infallible::{CryptoRng, Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn CryptoRng = &mut TestCRng(Constant(2));
+    let _ = c.next_u32();
+}
@@ -0,0 +1,14 @@
+//! `fallibility_param`: `&mut dyn Rng<Infallible>` and
+//! `&mut dyn CryptoRng<Fallible>`
+
+extern crate rand_design;
+
+use rand_design::fallibility_param::test_rngs::{Constant, TestFCRng, TestRng};
+use rand_design::fallibility_param::{CryptoRng, Fallible, Infallible, Rng};
+
+fn main() {
+    let r: &mut dyn Rng<Infallible> = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn CryptoRng<Fallible> = &mut TestFCRng(Constant(2));
+    let _ = c.next_u32();
+}
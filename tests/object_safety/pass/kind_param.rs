@@ -0,0 +1,13 @@
+//! `kind_param`: `&mut dyn Gen<Plain>` and `&mut dyn Gen<Crypto>`
+
+extern crate rand_design;
+
+use rand_design::kind_param::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::kind_param::{Crypto, Gen, Plain};
+
+fn main() {
+    let r: &mut dyn Gen<Plain> = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn Gen<Crypto> = &mut TestCRng(Constant(2));
+    let _ = c.next_u32();
+}
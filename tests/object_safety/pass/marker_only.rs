@@ -0,0 +1,13 @@
+//! `marker_only`: `&mut dyn Rng` and `&mut dyn CryptoRng`
+
+extern crate rand_design;
+
+use rand_design::marker_only::test_rngs::{Constant, TestCRng, TestRng};
+use rand_design::marker_only::{CryptoRng, Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u64();
+    let c: &mut dyn CryptoRng = &mut TestCRng(Constant(2));
+    let _ = c.try_fill(&mut [0u8; 8]);
+}
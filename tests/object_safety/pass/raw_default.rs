@@ -0,0 +1,20 @@
+//! `raw_default`: `&mut dyn Rng`, `&mut dyn CryptoRng`,
+//! `Box<dyn RawRng<Infallible>>` and `Box<dyn RawRng>`
+
+extern crate rand_design;
+
+use std::convert::Infallible;
+
+use rand_design::raw_default::test_rngs::{Constant, TestFCRng, TestRng};
+use rand_design::raw_default::{CryptoRng, RawRng, Rng};
+
+fn main() {
+    let r: &mut dyn Rng = &mut TestRng(Constant(1));
+    let _ = r.next_u32();
+    let c: &mut dyn CryptoRng = &mut TestFCRng(Constant(2));
+    let _ = c.try_next_u32();
+    let mut b0: Box<dyn RawRng<Infallible>> = Box::new(TestRng(Constant(3)));
+    let _ = b0.try_next_u32();
+    let mut b1: Box<dyn RawRng> = Box::new(TestFCRng(Constant(4)));
+    let _ = b1.try_next_u32();
+}
@@ -0,0 +1,319 @@
+//! One fallible trait with the error type as an associated type, instead of a
+//! type parameter as in raw_rng: `TryRng { type Error; ... }`. `Rng` is the
+//! same trait where `Error = Infallible`, implemented for all such generators;
+//! a crypto generator is a `TryRng<Error = CryptoError>`. Stable Rust.
+//! 
+//! Methods: `TryRng` requires `try_next_u32` and provides `try_next_u64`,
+//! `try_next_u128` and `try_fill` on top of it (see `bridge.rs`); `Rng`
+//! provides `next_u32`, `next_u64`, `next_u128` and `fill`, each unwrapping
+//! its `try_` counterpart.
+//! 
+//! Thoughts: the associated type dodges raw_rng's overlaps: one `&mut R`
+//! impl, one adaptor, and `R: TryRng` consumers taking both kinds with
+//! nothing to infer; unwrapping a `Result<u32, Infallible>` is free. But
+//! coherence doesn't see `Error = Infallible` and `Error = CryptoError` as
+//! disjoint, so a type can't be both plain and crypto; the error type is
+//! part of the object type, so no `dyn TryRng` takes any generator; and an
+//! infallible crypto generator can't say so.
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_error_overlap assoc_error.rs
+//!     rustc --cfg fail_erased_object assoc_error.rs
+//!     rustc --cfg fail_plain_as_crypto assoc_error.rs
+
+use std::convert::Infallible;
+use std::fmt::Debug;
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+pub trait TryRng {
+    type Error;
+    
+    fn try_next_u32(&mut self) -> Result<u32, Self::Error>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Self::Error> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Self::Error> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
+}
+
+fn unwrap<T>(r: Result<T, Infallible>) -> T {
+    match r {
+        Ok(x) => x,
+        Err(e) => match e {},
+    }
+}
+
+pub trait Rng: TryRng<Error = Infallible> {
+    fn next_u32(&mut self) -> u32 {
+        unwrap(self.try_next_u32())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        unwrap(self.try_next_u64())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        unwrap(self.try_next_u128())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        unwrap(self.try_fill(dest))
+    }
+}
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
+
+pub type CryptoRng = TryRng<Error = CryptoError>;
+
+// ——— impls ———
+
+impl<R: TryRng<Error = Infallible>+?Sized> Rng for R {}
+
+// One impl for every error type, so `&mut R` is an `Rng` too
+impl<R: TryRng+?Sized> TryRng for &mut R {
+    type Error = R::Error;
+    
+    fn try_next_u32(&mut self) -> Result<u32, R::Error> {
+        (*self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, R::Error> {
+        (*self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, R::Error> {
+        (*self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), R::Error> {
+        (*self).try_fill(dest)
+    }
+}
+
+impl<R: TryRng+?Sized> TryRng for Box<R> {
+    type Error = R::Error;
+    
+    fn try_next_u32(&mut self) -> Result<u32, R::Error> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, R::Error> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, R::Error> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), R::Error> {
+        (**self).try_fill(dest)
+    }
+}
+
+#[cfg(fail_error_overlap)]
+mod overlap {
+    use super::*;
+    
+    trait Describe {
+        fn describe(&self) -> &'static str;
+    }
+    
+    impl<R: TryRng<Error = Infallible>> Describe for R {
+        fn describe(&self) -> &'static str {
+            "plain"
+        }
+    }
+    
+    // Error: conflicts with the impl above, although no type can match both
+    impl<R: TryRng<Error = CryptoError>> Describe for R {
+        fn describe(&self) -> &'static str {
+            "crypto"
+        }
+    }
+}
+
+// ——— adaptor ———
+
+// Given `rng` of type `T` where `T: TryRng` with any error type, this can
+// consume `rng` (`as_rng(rng)`), panicking on error
+fn as_rng<R: TryRng>(rng: R) -> AsRng<R> {
+    AsRng { rng }
+}
+
+struct AsRng<R: TryRng+?Sized> {
+    rng: R
+}
+
+impl<R: TryRng+?Sized> TryRng for AsRng<R> where R::Error: Debug {
+    type Error = Infallible;
+    
+    fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+        Ok(self.rng.try_next_u32().unwrap())
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Infallible> {
+        Ok(self.rng.try_next_u64().unwrap())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Infallible> {
+        Ok(self.rng.try_next_u128().unwrap())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Infallible> {
+        self.rng.try_fill(dest).unwrap();
+        Ok(())
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestCRng, TestRng};
+
+// A non-crypto Rng
+impl<S: Source> TryRng for TestRng<S> {
+    type Error = Infallible;
+    
+    fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+        Ok(self.0.word() as u32)
+    }
+}
+
+// A CryptoRng
+impl<S: Source> TryRng for TestCRng<S> {
+    type Error = CryptoError;
+    
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+// ——— consumers ———
+
+// Any generator: the error type comes with it
+fn first_word<R: TryRng+?Sized>(rng: &mut R) -> Result<u32, R::Error> {
+    rng.try_next_u32()
+}
+
+fn gen_key<R: TryRng<Error = CryptoError>+?Sized>(rng: &mut R) -> Result<u32, CryptoError> {
+    rng.try_next_u32()
+}
+
+#[cfg(fail_erased_object)]
+fn erased(rng: &mut dyn TryRng) {
+    // Error: the value of the associated type `Error` must be specified
+    let _ = rng.try_next_u32();
+}
+
+#[cfg(fail_plain_as_crypto)]
+fn plain_as_crypto() {
+    let _ = gen_key(&mut TestRng(Constant(1)));
+}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
+    println!("t: {:?} impls Rng", t);
+    println!("c: {:?} impls CryptoRng", c);
+    {
+        // Do both traits support both functions via static dispatch?
+        println!("t, static dispatch, using TryRng: {:?}", t.try_next_u32());
+        println!("t, static dispatch, using Rng: {:?}", t.next_u32());
+        println!("c, static dispatch, using CryptoRng: {:?}", gen_key(&mut c));
+        println!("c, static dispatch, using Rng: {:?}", as_rng(&mut c).next_u32());
+        println!("t and c, static dispatch, generic over TryRng: {:?}, {:?}",
+                first_word(&mut t), first_word(&mut c));
+    }
+    {
+        // Can both types be used via CryptoRng with dynamic dispatch?
+        let cr = &mut c as &mut CryptoRng;
+        println!("c, dynamic dispatch, using CryptoRng: {:?}", cr.try_next_u32());
+        // t can't: its error type is `Infallible`, and there's no adaptor
+        // from plain to crypto
+    }
+    {
+        // Can both types be used via Rng with dynamic dispatch?
+        let mut cr = as_rng(&mut c as &mut CryptoRng);
+        let tr = &mut t as &mut Rng;
+        println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
+        println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
+        // `&mut dyn Rng` is itself an `Rng`, through the one `&mut R` impl
+        let mut tr = tr;
+        println!("&mut t, dynamic dispatch, using Rng: {:?}", Rng::next_u32(&mut tr));
+    }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using CryptoRng: Ok(0), then Err");
+    }
+    {
+        // `Infallible` costs nothing in the return value
+        assert_eq!(std::mem::size_of::<Result<u32, Infallible>>(), std::mem::size_of::<u32>());
+        println!("Result<u32, Infallible> is as big as u32");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The adaptor: the try_ methods, unwrapped
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(as_rng(&mut c).next_u64(), 2 << 32 | 1);
+        println!("as_rng(c), provided methods: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: TryRng<Error = CryptoError>>(mut r: R, how: &str) {
+            println!("c, {}, using TryRng<Error = CryptoError>: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn TryRng<Error = CryptoError>, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn TryRng<Error = CryptoError>>, "by Box<dyn>");
+    }
+}
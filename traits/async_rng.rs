@@ -0,0 +1,348 @@
+//! Async generators: `poll_fill`, beside the synchronous traits
+//! 
+//! An async service wants entropy without blocking the thread its runtime
+//! polls tasks on, so an async trait, as `AsyncRead` is to `Read`, over
+//! `extends_CryptoRng`'s synchronous traits:
+//! 
+//! *   `AsyncRng::poll_fill(cx, dest)` fills all of `dest`, or returns
+//!     `Pending`, consuming nothing, having arranged for the task to be woken
+//! *   `AsyncRngExt::try_fill(dest)`, an `async fn` written by hand, as these
+//!     files are edition 2015
+//! *   `AsAsync` adapts a synchronous `CryptoRng`, a `NotReady` failure
+//!     being `Pending` with an immediate wake
+//! 
+//! Run with:
+//! 
+//!     rustc async_rng.rs && ./async_rng
+//! 
+//! Compile-fail check (must fail to compile):
+//! 
+//!     rustc --cfg fail_blanket_async async_rng.rs
+//! 
+//! Thoughts: the design extends to async as a trait beside it: `NotReady`
+//! is what `Pending` means. What the synchronous side can't supply is the
+//! waker: `AsAsync` over a pool which isn't ready spins (over 10^5 polls
+//! here, against two for the native `PoolRng`), so it suits generators
+//! which never wait, and an async `OsRng` needs a reactor or a thread.
+//! `poll_fill` is dyn compatible; a blanket impl for every `CryptoRng`
+//! would stop the OS generator being both (`fail_blanket_async`).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+pub trait CryptoRng {
+    fn try_next_u32(&mut self) -> Result<u32, Error>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
+}
+
+pub trait Rng: CryptoRng {
+    fn next_u32(&mut self) -> u32 {
+        self.try_next_u32().unwrap()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.try_next_u64().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap()
+    }
+}
+
+pub trait AsyncRng {
+    /// Fills `dest`, or returns `Pending` having arranged for `cx`'s waker to
+    /// be woken when a poll may succeed; a `Pending` call consumes nothing.
+    fn poll_fill(&mut self, cx: &mut Context, dest: &mut [u8]) -> Poll<Result<(), Error>>;
+}
+
+/// The future of `AsyncRngExt::try_fill`.
+pub struct TryFill<'a, R: ?Sized+'a> {
+    rng: &'a mut R,
+    dest: &'a mut [u8],
+}
+
+impl<'a, R: AsyncRng+?Sized> Future for TryFill<'a, R> {
+    type Output = Result<(), Error>;
+    
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        let this = self.get_mut();
+        this.rng.poll_fill(cx, this.dest)
+    }
+}
+
+pub trait AsyncRngExt: AsyncRng {
+    /// `async fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>`.
+    fn try_fill<'a>(&'a mut self, dest: &'a mut [u8]) -> TryFill<'a, Self> {
+        TryFill { rng: self, dest }
+    }
+}
+
+impl<R: AsyncRng+?Sized> AsyncRngExt for R {}
+
+// ——— impls ———
+
+impl<R: AsyncRng+?Sized> AsyncRng for &mut R {
+    fn poll_fill(&mut self, cx: &mut Context, dest: &mut [u8]) -> Poll<Result<(), Error>> {
+        (**self).poll_fill(cx, dest)
+    }
+}
+
+#[cfg(fail_blanket_async)]
+// Error: conflicts with the impl for `&mut R`, as a downstream crate may
+// make `&mut _` a `CryptoRng`; without that, with `PoolRng`'s own, since
+// `PoolRng` is a `CryptoRng` too. And each `try_fill` of a synchronous
+// generator is ambiguous
+impl<R: CryptoRng+?Sized> AsyncRng for R {
+    fn poll_fill(&mut self, _: &mut Context, dest: &mut [u8]) -> Poll<Result<(), Error>> {
+        Poll::Ready(self.try_fill(dest))
+    }
+}
+
+// ——— adaptor ———
+
+/// A synchronous generator as an async one. It never waits for anything:
+/// `NotReady` wakes the task at once, to be polled again, and a generator
+/// which blocks blocks the thread polling it.
+#[derive(Debug)]
+pub struct AsAsync<R>(pub R);
+
+impl<R: CryptoRng> AsyncRng for AsAsync<R> {
+    fn poll_fill(&mut self, cx: &mut Context, dest: &mut [u8]) -> Poll<Result<(), Error>> {
+        match self.0.try_fill(dest) {
+            Err(ref e) if e.kind.should_wait() => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Counting, FailAfter, Source, TestCRng, TestRng};
+
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+use std::thread;
+use std::time::Duration;
+
+// A non-crypto Rng
+impl<S: Source> CryptoRng for TestRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+impl<S: Source> Rng for TestRng<S> {}
+
+// A CryptoRng
+impl<S: Source> CryptoRng for TestCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+/// An entropy pool which isn't ready until `fill_up`, as the kernel's early
+/// in boot; then it wakes the task waiting on it.
+#[derive(Debug, Default)]
+pub struct Pool {
+    state: Mutex<(bool, Option<Waker>)>,
+}
+
+impl Pool {
+    pub fn fill_up(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.0 = true;
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+    }
+    
+    fn is_ready(&self) -> bool {
+        self.state.lock().unwrap().0
+    }
+}
+
+/// A generator behind a `Pool`: synchronously `NotReady` until the pool is,
+/// as `getrandom(2)` with `GRND_NONBLOCK`; asynchronously `Pending`, with its
+/// waker left with the pool.
+#[derive(Debug)]
+pub struct PoolRng<S> {
+    pub source: S,
+    pub pool: Arc<Pool>,
+}
+
+impl<S: Source> CryptoRng for PoolRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        if !self.pool.is_ready() {
+            return Err(Error::new(ErrorKind::NotReady, "pool not ready"));
+        }
+        self.source.try_word().map(|x| x as u32)
+            .map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+impl<S: Source> AsyncRng for PoolRng<S> {
+    fn poll_fill(&mut self, cx: &mut Context, dest: &mut [u8]) -> Poll<Result<(), Error>> {
+        {
+            let mut state = self.pool.state.lock().unwrap();
+            if !state.0 {
+                state.1 = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        Poll::Ready(CryptoRng::try_fill(self, dest))
+    }
+}
+
+// ——— consumers ———
+
+/// Unparks the thread blocked in `block_on`.
+struct ThreadWaker(thread::Thread);
+
+impl std::task::Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// The smallest executor: polls `fut` on this thread, parking it between
+/// wake-ups.
+fn block_on<F: Future+Unpin>(mut fut: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(x) = Pin::new(&mut fut).poll(&mut cx) {
+            return x;
+        }
+        thread::park();
+    }
+}
+
+/// Counts the polls of a future.
+struct Polls<F>(F, usize);
+
+impl<F: Future+Unpin> Future for Polls<F> {
+    type Output = (F::Output, usize);
+    
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<(F::Output, usize)> {
+        self.1 += 1;
+        let polls = self.1;
+        Pin::new(&mut self.0).poll(cx).map(|x| (x, polls))
+    }
+}
+
+/// An async generator as a synchronous one, blocking the thread until each
+/// fill is ready.
+#[derive(Debug)]
+pub struct Blocking<R>(pub R);
+
+impl<R: AsyncRng> CryptoRng for Blocking<R> {
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        let mut buf = [0u8; 4];
+        self.try_fill(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        block_on(AsyncRngExt::try_fill(&mut self.0, dest))
+    }
+}
+
+/// A pool filled from another thread after `delay`.
+fn pool_filled_after(delay: Duration) -> Arc<Pool> {
+    let pool = Arc::new(Pool::default());
+    let filler = pool.clone();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        filler.fill_up();
+    });
+    pool
+}
+
+// ——— usage ———
+
+pub fn main() {
+    {
+        // Adapted, a synchronous generator gives the same bytes, at once
+        let mut sync = [0u8; 20];
+        TestCRng(Counting(1)).try_fill(&mut sync).unwrap();
+        let mut adapted = [0u8; 20];
+        block_on(AsAsync(TestCRng(Counting(1))).try_fill(&mut adapted)).unwrap();
+        assert_eq!(adapted, sync);
+        let mut plain = [0u8; 20];
+        block_on(AsAsync(TestRng(Counting(1))).try_fill(&mut plain)).unwrap();
+        assert_eq!(plain, sync);
+        println!("AsAsync: the synchronous bytes, Ready at the first poll");
+    }
+    {
+        // Its failures pass through, but for `NotReady`
+        let mut dest = [0u8; 16];
+        let err = block_on(AsAsync(TestCRng(FailAfter::new(1))).try_fill(&mut dest)).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Unavailable);
+        println!("AsAsync over a failing generator: {}", err);
+    }
+    {
+        // Native: pending until the pool fills, then woken once
+        let pool = pool_filled_after(Duration::from_millis(20));
+        let mut rng = PoolRng { source: Counting(1), pool };
+        assert_eq!(CryptoRng::try_next_u32(&mut rng).unwrap_err().kind, ErrorKind::NotReady);
+        let mut dest = [0u8; 20];
+        let (result, polls) = block_on(Polls(AsyncRngExt::try_fill(&mut rng, &mut dest), 0));
+        result.unwrap();
+        let mut sync = [0u8; 20];
+        TestCRng(Counting(1)).try_fill(&mut sync).unwrap();
+        assert_eq!((dest, polls), (sync, 2));
+        println!("PoolRng: NotReady synchronously; asynchronously 2 polls, the bytes once ready");
+    }
+    {
+        // Adapted, the same generator is polled until the pool fills
+        let pool = pool_filled_after(Duration::from_millis(20));
+        let mut rng = AsAsync(PoolRng { source: Counting(1), pool });
+        let mut dest = [0u8; 20];
+        let (result, polls) = block_on(Polls(rng.try_fill(&mut dest), 0));
+        result.unwrap();
+        assert!(polls > 2);
+        println!("AsAsync(PoolRng): woken at once on NotReady, {} polls until ready", polls);
+    }
+    {
+        // Through a trait object, and back to synchronous
+        let mut rng = AsAsync(TestCRng(Counting(1)));
+        let r: &mut dyn AsyncRng = &mut rng;
+        let mut dest = [0u8; 8];
+        block_on(r.try_fill(&mut dest)).unwrap();
+        assert_eq!(u64::from_le_bytes(dest), 2 << 32 | 1);
+        let pool = pool_filled_after(Duration::from_millis(5));
+        let mut b = Blocking(PoolRng { source: Counting(7), pool });
+        let (mut blocked, mut sync) = ([0u8; 12], [0u8; 12]);
+        b.try_fill(&mut blocked).unwrap();
+        TestCRng(Counting(7)).try_fill(&mut sync).unwrap();
+        assert_eq!(blocked, sync);
+        println!("&mut dyn AsyncRng: try_fill; Blocking(PoolRng): try_fill, once ready");
+    }
+}
@@ -0,0 +1,267 @@
+//! Marker trait, as in marker_only, but `CryptoRng` is an (unsafe) auto trait
+//! (nightly only)
+//! 
+//! Every type is `CryptoRng` unless it, or one of its fields, opts out with
+//! `impl !CryptoRng for T {}`, so wrappers inherit the marker structurally
+//! and `&mut (dyn Rng + CryptoRng)` just works. An auto trait can't have
+//! methods or supertraits, so nothing here is fallible and bounds name both
+//! traits. Compile-fail checks (nightly): `fail_opted_out`,
+//! `fail_erased_object`, `fail_methods`, `fail_supertrait`.
+//! 
+//! Thoughts: the default is backwards for a security marker: a generator
+//! which forgets to opt out (`ForgetfulRng`, or any written before the trait
+//! existed) is silently "secure", as is `u32`. Auto traits are also
+//! perma-unstable for user code. Not viable.
+
+#![feature(auto_traits)]
+#![feature(negative_impls)]
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+/// Marker for cryptographically secure generators.
+/// 
+/// # Safety
+/// 
+/// Implementing it (re-opting in) makes a security claim: the output is
+/// unpredictable to anyone without the state.
+pub unsafe auto trait CryptoRng {}
+
+// ——— impls ———
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (*self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (*self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (*self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (*self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+// ——— adaptor ———
+
+// A generic wrapper: no CryptoRng impls needed.
+struct AsRng<R: Rng> {
+    rng: R
+}
+
+impl<R: Rng> Rng for AsRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.fill(dest)
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, Source, TestCRng, TestRng};
+
+// A non-crypto Rng
+impl<S> !CryptoRng for TestRng<S> {}
+
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// A CryptoRng: nothing to do
+impl<S: Source> Rng for TestCRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// A CryptoRng using a non-crypto generator internally (e.g. for timing
+// jitter); the structural result must be overridden.
+#[derive(Debug)]
+struct TestMixedCRng(u32, TestRng);
+
+unsafe impl CryptoRng for TestMixedCRng {}
+
+impl Rng for TestMixedCRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^ self.1.next_u32()
+    }
+}
+
+// A non-crypto Rng whose author forgot to opt out
+#[derive(Debug)]
+struct ForgetfulRng(u32);
+
+impl Rng for ForgetfulRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0
+    }
+}
+
+// ——— consumers ———
+
+// Compiles only if `T: CryptoRng`.
+fn assert_crypto<T: CryptoRng+?Sized>() {}
+
+fn gen_key<R: Rng+CryptoRng+?Sized>(rng: &mut R) -> u32 {
+    rng.next_u32()
+}
+
+// ——— compile-fail cases ———
+
+#[cfg(fail_opted_out)]
+fn opted_out() {
+    gen_key(&mut TestRng(Constant(1)));
+    gen_key(&mut AsRng { rng: TestRng(Constant(1)) });
+}
+
+#[cfg(fail_erased_object)]
+fn erased_object(r: &mut dyn Rng) {
+    gen_key(r);
+}
+
+#[cfg(fail_methods)]
+pub unsafe auto trait CryptoRngWithMethods {
+    fn try_next_u32(&mut self) -> Result<u32, ()>;
+}
+
+#[cfg(fail_supertrait)]
+pub unsafe auto trait CryptoRngExtendsRng: Rng {}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
+    let mut m = TestMixedCRng(1, TestRng(Constant(2)));
+    let mut f = ForgetfulRng(7);
+    println!("t: {:?} impls Rng", t);
+    println!("c: {:?} impls Rng + CryptoRng (automatically)", c);
+    println!("m: {:?} impls Rng + CryptoRng (re-opted in)", m);
+    println!("f: {:?} impls Rng + CryptoRng (by accident!)", f);
+    {
+        // Static dispatch with and without the CryptoRng requirement
+        println!("t, static dispatch, using Rng: {:?}", t.next_u32());
+        println!("c, static dispatch, using Rng+CryptoRng: {:?}", gen_key(&mut c));
+        println!("m, static dispatch, using Rng+CryptoRng: {:?}", gen_key(&mut m));
+        println!("f, static dispatch, using Rng+CryptoRng: {:?}", gen_key(&mut f));
+    }
+    {
+        // Wrappers inherit the marker structurally
+        let mut ac = AsRng { rng: &mut c };
+        println!("AsRng<&mut TestCRng>, using Rng+CryptoRng: {:?}", gen_key(&mut ac));
+        let mut bc: Box<TestCRng> = Box::new(TestCRng(Constant(42)));
+        println!("Box<TestCRng>, using Rng+CryptoRng: {:?}", gen_key(&mut bc));
+    }
+    {
+        // Dynamic dispatch: the marker is part of the object type
+        let cr = &mut c as &mut (dyn Rng + CryptoRng);
+        println!("c, dynamic dispatch, using Rng+CryptoRng: {:?}", gen_key(cr));
+        let tr = &mut t as &mut dyn Rng;
+        println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
+        let mut bc: Box<dyn Rng + CryptoRng> = Box::new(TestCRng(Constant(42)));
+        println!("Box<dyn Rng + CryptoRng>, using Rng+CryptoRng: {:?}", gen_key(&mut bc));
+    }
+    {
+        // Compile-pass checks, including the accidental ones
+        assert_crypto::<TestCRng>();
+        assert_crypto::<&mut TestCRng>();
+        assert_crypto::<AsRng<TestCRng>>();
+        assert_crypto::<Box<dyn Rng + CryptoRng>>();
+        assert_crypto::<TestMixedCRng>();
+        assert_crypto::<ForgetfulRng>();
+        assert_crypto::<u32>();
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // Box forwards each method
+        let mut b = Box::new(TestCRng(Counting(1))) as Box<dyn Rng + CryptoRng>;
+        assert_eq!(b.next_u64(), 2 << 32 | 1);
+        println!("Box<dyn Rng + CryptoRng>, provided methods: the same words");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: Rng + CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using Rng + CryptoRng: {:?}", how, r.next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut (dyn Rng + CryptoRng), "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn Rng + CryptoRng>, "by Box<dyn>");
+    }
+}
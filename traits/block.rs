@@ -0,0 +1,128 @@
+//! Block generators: a `BlockRngCore`, and the `BlockRng` buffering it
+//! 
+//! ChaCha, HC-128 and ISAAC don't make a word at a time: each step makes a
+//! block of 16 to 256 words, which the generator then hands out. The
+//! algorithm is the core, `BlockRngCore::generate` filling a buffer; the
+//! handing out is `BlockRng<R>`, the same for every core, which keeps the
+//! buffer and its position and refills when it runs dry.
+//! 
+//! `test_rngs.rs` includes this file and makes `BlockRng` a `Source`, so that
+//! each variant's roles over it (`TestRng<BlockRng<SplitMixCore>>` and the
+//! rest) are block generators implementing that variant's traits, through the
+//! impls it already has; `bin/compare.rs` checks that each variant accepts
+//! them. `SplitMixCore` there gives the words of `SplitMix`, eight steps per
+//! block, so the two can be compared word for word.
+//! 
+//! Words are handed out low first: `next_u64` takes two, straddling a refill
+//! if need be, and `fill` copies whole words little-endian, dropping the rest
+//! of the last (as rand's `fill_via_u32_chunks`). `fill_uninit` copies the
+//! same bytes into an uninitialised buffer, which it returns initialised, so
+//! megabytes of output are written once rather than zeroed first.
+//! 
+//! Thoughts: the designs needn't know about blocks. Buffering happens below
+//! the traits, so `BlockRng` fits each of them as any other generator does,
+//! and a core never implements `Rng` itself. What blocks do raise is a
+//! question for the trait methods: whether errors are per block or per word
+//! (batched_fallibility), and whether output can come a block at a time
+//! (block_output, lending_block); those files keep their own cores, as
+//! their wrappers add to this one.
+//! 
+//! This file has no `main`: the variants including it exercise it.
+
+use std::cmp::min;
+use std::mem::MaybeUninit;
+
+pub trait BlockRngCore {
+    /// One block of words.
+    type Results: AsRef<[u32]> + AsMut<[u32]> + Default;
+    
+    /// Generates the next block into `results`.
+    fn generate(&mut self, results: &mut Self::Results);
+}
+
+/// Buffers a `BlockRngCore`'s output, handing it out a word at a time.
+/// 
+/// With the `serde` feature it serializes as its core, buffer and position,
+/// so a restored generator goes on mid-block.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockRng<R: BlockRngCore> {
+    core: R,
+    results: R::Results,
+    index: usize,
+}
+
+impl<R: BlockRngCore> BlockRng<R> {
+    /// A generator with an empty buffer: the first word generates a block.
+    pub fn new(core: R) -> Self {
+        let results = R::Results::default();
+        let index = results.as_ref().len();
+        BlockRng { core, results, index }
+    }
+    
+    pub fn core(&self) -> &R {
+        &self.core
+    }
+    
+    /// The position of the next word in the buffer.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    
+    /// Drops the buffered words: the next word generates a block.
+    pub fn reset(&mut self) {
+        self.index = self.results.as_ref().len();
+    }
+    
+    fn refill_if_drained(&mut self) {
+        if self.index == self.results.as_ref().len() {
+            self.core.generate(&mut self.results);
+            self.index = 0;
+        }
+    }
+    
+    pub fn next_u32(&mut self) -> u32 {
+        self.refill_if_drained();
+        let x = self.results.as_ref()[self.index];
+        self.index += 1;
+        x
+    }
+    
+    pub fn next_u64(&mut self) -> u64 {
+        let lo = self.next_u32() as u64;
+        let hi = self.next_u32() as u64;
+        hi << 32 | lo
+    }
+    
+    /// Fills `dest` from whole words, little-endian.
+    pub fn fill(&mut self, dest: &mut [u8]) {
+        let mut filled = 0;
+        while filled < dest.len() {
+            self.refill_if_drained();
+            let words = &self.results.as_ref()[self.index..];
+            let n = min(dest.len() - filled, words.len() * 4);
+            for (chunk, word) in dest[filled..filled + n].chunks_mut(4).zip(words) {
+                chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+            }
+            self.index += n.div_ceil(4);
+            filled += n;
+        }
+    }
+    
+    /// As `fill`, into uninitialised `dest`.
+    pub fn fill_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u8>]) -> &'a mut [u8] {
+        let mut filled = 0;
+        while filled < dest.len() {
+            self.refill_if_drained();
+            let words = &self.results.as_ref()[self.index..];
+            let n = min(dest.len() - filled, words.len() * 4);
+            for (chunk, word) in dest[filled..filled + n].chunks_mut(4).zip(words) {
+                chunk.write_copy_of_slice(&word.to_le_bytes()[..chunk.len()]);
+            }
+            self.index += n.div_ceil(4);
+            filled += n;
+        }
+        // Sound: the loop wrote every byte
+        unsafe { dest.assume_init_mut() }
+    }
+}
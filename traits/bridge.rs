@@ -0,0 +1,53 @@
+//! Default-method bodies shared by the variants
+//! 
+//! Every variant models the same surface: a required word method (`next_u32`
+//! or `try_next_u32`), and `next_u64`, `next_u128` and `fill` (or their `try_`
+//! forms) provided on top of it. The provided methods are the same few lines
+//! in each variant, so they call these helpers:
+//! 
+//!     #[path = "bridge.rs"]
+//!     #[allow(dead_code)]
+//!     mod bridge;
+//! 
+//!     fn next_u64(&mut self) -> u64 {
+//!         bridge::u64_from_u32(self.next_u32(), self.next_u32())
+//!     }
+//! 
+//! Words are combined low first, and `fill` writes each `u64` little-endian,
+//! truncating the last; so every variant produces the same bytes from the same
+//! source. What differs between variants is where the defaults live and what
+//! the blanket impls and adaptors must forward.
+//! 
+//! This file has no `main`: the variants including it exercise it.
+
+/// A `u64` from two words, `lo` first.
+#[inline]
+pub fn u64_from_u32(lo: u32, hi: u32) -> u64 {
+    (hi as u64) << 32 | lo as u64
+}
+
+/// A `u128` from two words, `lo` first.
+#[inline]
+pub fn u128_from_u64(lo: u64, hi: u64) -> u128 {
+    (hi as u128) << 64 | lo as u128
+}
+
+/// Fills `dest` with `u64` words from `next`, little-endian.
+#[inline]
+pub fn fill_via_u64<F: FnMut() -> u64>(dest: &mut [u8], mut next: F) {
+    for chunk in dest.chunks_mut(8) {
+        let n = chunk.len();
+        chunk.copy_from_slice(&next().to_le_bytes()[..n]);
+    }
+}
+
+/// Fills `dest` with `u64` words from `next`, little-endian, stopping at the
+/// first error. The bytes before the error are written.
+#[inline]
+pub fn try_fill_via_u64<E, F: FnMut() -> Result<u64, E>>(dest: &mut [u8], mut next: F) -> Result<(), E> {
+    for chunk in dest.chunks_mut(8) {
+        let n = chunk.len();
+        chunk.copy_from_slice(&next()?.to_le_bytes()[..n]);
+    }
+    Ok(())
+}
@@ -0,0 +1,499 @@
+//! Runtime capability queries: `is_fallible`, `is_crypto`, `supports_jump`, `backend`
+//! 
+//! Marker traits (`CryptoRng`) and type parameters say nothing once a
+//! generator is behind `Box<dyn Rng>`. Here `Rng` itself carries query
+//! methods, so a framework holding only a trait object can still decide
+//! e.g. to refuse a non-crypto generator for key material, or to hand out
+//! jumped sub-streams only if jumping is supported:
+//! 
+//! *   `is_fallible()`: whether `try_fill` may return an error
+//! *   `is_crypto()`: whether output is meant to be cryptographically secure
+//! *   `supports_jump()`: whether `try_jump` works
+//! *   `backend()`: name of the underlying generator
+//! 
+//! Defaults are conservative (fallible, not crypto, no jump, the type's name),
+//! so a generator which says nothing is never over-trusted. Adaptors combine
+//! what they wrap: `Fallback<A, B>` is only crypto if both are and only
+//! fallible if both are; `ReseedingRng` cannot jump; `ErrorInjectingRng` is
+//! always fallible. Forwarding impls for `Box` and `&mut` must forward the
+//! queries too, or the conservative defaults leak through; `main` checks this.
+//! 
+//! Thoughts: `is_crypto()` duplicates the `CryptoRng` marker and nothing
+//! forces the two to agree (an associated const would, but makes `Rng` unusable
+//! as a trait object). Four methods on every vtable cost nothing measurable; a
+//! `Capabilities` struct is returned by `capabilities()` so callers need only
+//! one virtual call, while generators override individual methods.
+
+use std::any::type_name;
+
+// ——— traits ———
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind, Injector};
+
+/// Snapshot of a generator's capabilities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub fallible: bool,
+    pub crypto: bool,
+    pub jump: bool,
+    pub backend: &'static str,
+}
+
+pub trait Rng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+    
+    fn next_u64(&mut self) -> u64;
+    
+    /// Advance by a large, generator-specific number of steps, to get a
+    /// non-overlapping sub-stream; `Unavailable` if the generator can't.
+    fn try_jump(&mut self) -> Result<(), Error> {
+        Err(Error::new(ErrorKind::Unavailable, "jump not supported"))
+    }
+    
+    /// True unless `try_fill` never fails.
+    fn is_fallible(&self) -> bool {
+        true
+    }
+    
+    /// True if output is meant to be cryptographically secure.
+    fn is_crypto(&self) -> bool {
+        false
+    }
+    
+    /// True if `try_jump` is supported.
+    fn supports_jump(&self) -> bool {
+        false
+    }
+    
+    /// Name of the underlying generator.
+    fn backend(&self) -> &'static str {
+        type_name::<Self>()
+    }
+    
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            fallible: self.is_fallible(),
+            crypto: self.is_crypto(),
+            jump: self.supports_jump(),
+            backend: self.backend(),
+        }
+    }
+}
+
+/// Marker for crypto generators; implementors must also return true from
+/// `is_crypto`.
+pub trait CryptoRng: Rng {}
+
+// ——— impls ———
+
+macro_rules! forward_rng {
+    () => {
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            (**self).try_fill(dest)
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            (**self).next_u64()
+        }
+        
+        fn try_jump(&mut self) -> Result<(), Error> {
+            (**self).try_jump()
+        }
+        
+        fn is_fallible(&self) -> bool {
+            (**self).is_fallible()
+        }
+        
+        fn is_crypto(&self) -> bool {
+            (**self).is_crypto()
+        }
+        
+        fn supports_jump(&self) -> bool {
+            (**self).supports_jump()
+        }
+        
+        fn backend(&self) -> &'static str {
+            (**self).backend()
+        }
+    };
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    forward_rng!();
+}
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    forward_rng!();
+}
+
+// ——— adaptors ———
+
+/// Uses `fallback` whenever `primary` fails, and from then on if the failure
+/// was not transient.
+#[derive(Debug)]
+pub struct Fallback<A, B> {
+    primary: A,
+    fallback: B,
+    failed: bool,
+}
+
+impl<A: Rng, B: Rng> Fallback<A, B> {
+    pub fn new(primary: A, fallback: B) -> Self {
+        Fallback { primary, fallback, failed: false }
+    }
+}
+
+impl<A: Rng, B: Rng> Rng for Fallback<A, B> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        if !self.failed {
+            match self.primary.try_fill(dest) {
+                Ok(()) => return Ok(()),
+                Err(e) => self.failed = e.kind != ErrorKind::Transient,
+            }
+        }
+        self.fallback.try_fill(dest)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.try_fill(&mut buf).expect("Fallback: both generators failed");
+        u64::from_le_bytes(buf)
+    }
+    
+    /// Either may end up in use, so both must qualify.
+    fn is_fallible(&self) -> bool {
+        self.primary.is_fallible() && self.fallback.is_fallible()
+    }
+    
+    fn is_crypto(&self) -> bool {
+        self.primary.is_crypto() && self.fallback.is_crypto()
+    }
+    
+    // Jumping one generator does not give a sub-stream of the combination.
+    fn supports_jump(&self) -> bool {
+        false
+    }
+    
+    /// The generator currently in use.
+    fn backend(&self) -> &'static str {
+        if self.failed { self.fallback.backend() } else { self.primary.backend() }
+    }
+}
+
+/// Reseeds `rng` from `source` every `threshold` outputs (simplified; see
+/// `entropy/reseeding.rs`). Reseed failures are ignored.
+#[derive(Debug)]
+pub struct ReseedingRng<R, S> {
+    rng: R,
+    source: S,
+    threshold: u32,
+    used: u32,
+}
+
+impl<R: Rng+From<u64>, S: Rng> Rng for ReseedingRng<R, S> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.reseed_if_due();
+        self.rng.try_fill(dest)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.reseed_if_due();
+        self.rng.next_u64()
+    }
+    
+    fn is_fallible(&self) -> bool {
+        self.rng.is_fallible()
+    }
+    
+    /// Reseeding from a non-crypto source spoils a crypto generator.
+    fn is_crypto(&self) -> bool {
+        self.rng.is_crypto() && self.source.is_crypto()
+    }
+    
+    // Reseeding breaks the relation between jumped and un-jumped streams.
+    fn supports_jump(&self) -> bool {
+        false
+    }
+    
+    fn backend(&self) -> &'static str {
+        self.rng.backend()
+    }
+}
+
+impl<R: Rng+From<u64>, S: Rng> ReseedingRng<R, S> {
+    fn reseed_if_due(&mut self) {
+        self.used += 1;
+        if self.used > self.threshold {
+            let mut seed = [0u8; 8];
+            if self.source.try_fill(&mut seed).is_ok() {
+                self.rng = R::from(u64::from_le_bytes(seed));
+            }
+            self.used = 1;
+        }
+    }
+}
+
+/// Injects errors into `try_fill` as scheduled by `inject`.
+#[derive(Debug)]
+pub struct ErrorInjectingRng<R> {
+    rng: R,
+    inject: Injector,
+}
+
+impl<R: Rng> Rng for ErrorInjectingRng<R> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.inject.check()?;
+        self.rng.try_fill(dest)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+    
+    fn try_jump(&mut self) -> Result<(), Error> {
+        self.rng.try_jump()
+    }
+    
+    fn is_fallible(&self) -> bool {
+        true
+    }
+    
+    fn is_crypto(&self) -> bool {
+        self.rng.is_crypto()
+    }
+    
+    fn supports_jump(&self) -> bool {
+        self.rng.supports_jump()
+    }
+    
+    fn backend(&self) -> &'static str {
+        self.rng.backend()
+    }
+}
+
+// ——— test RNGs ———
+
+fn fill_from_u64<R: Rng+?Sized>(rng: &mut R, dest: &mut [u8]) {
+    for chunk in dest.chunks_mut(8) {
+        let x = rng.next_u64().to_le_bytes();
+        chunk.copy_from_slice(&x[..chunk.len()]);
+    }
+}
+
+/// Non-crypto, infallible, jumpable: a counter (jump adds 2^32).
+#[derive(Debug)]
+struct Counter(u64);
+
+impl From<u64> for Counter {
+    fn from(seed: u64) -> Self {
+        Counter(seed)
+    }
+}
+
+impl Rng for Counter {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(1);
+        self.0
+    }
+    
+    fn try_jump(&mut self) -> Result<(), Error> {
+        self.0 = self.0.wrapping_add(1 << 32);
+        Ok(())
+    }
+    
+    fn is_fallible(&self) -> bool {
+        false
+    }
+    
+    fn supports_jump(&self) -> bool {
+        true
+    }
+    
+    fn backend(&self) -> &'static str {
+        "Counter"
+    }
+}
+
+/// Crypto (for testing purposes!), infallible, no jump.
+#[derive(Debug)]
+struct TestCRng(u64);
+
+impl From<u64> for TestCRng {
+    fn from(seed: u64) -> Self {
+        TestCRng(seed)
+    }
+}
+
+impl Rng for TestCRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.rotate_left(13) ^ 0x5EED;
+        self.0
+    }
+    
+    fn is_fallible(&self) -> bool {
+        false
+    }
+    
+    fn is_crypto(&self) -> bool {
+        true
+    }
+    
+    fn backend(&self) -> &'static str {
+        "TestCRng"
+    }
+}
+
+impl CryptoRng for TestCRng {}
+
+/// Crypto and fallible, like an OS source; `available` false makes it fail.
+#[derive(Debug)]
+struct TestOsRng {
+    available: bool,
+}
+
+impl Rng for TestOsRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        if !self.available {
+            return Err(Error::new(ErrorKind::Unavailable, "OS generator unavailable"));
+        }
+        for b in dest.iter_mut() {
+            *b = 0xA5;
+        }
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.try_fill(&mut buf).expect("TestOsRng unavailable");
+        u64::from_le_bytes(buf)
+    }
+    
+    fn is_crypto(&self) -> bool {
+        true
+    }
+    
+    fn backend(&self) -> &'static str {
+        "TestOsRng"
+    }
+}
+
+impl CryptoRng for TestOsRng {}
+
+/// Says nothing about itself.
+#[derive(Debug)]
+struct Quiet;
+
+impl Rng for Quiet {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        0
+    }
+}
+
+// ——— usage ———
+
+/// Example policy: key material only from crypto generators.
+fn key_rng(rng: Box<dyn Rng>) -> Result<Box<dyn Rng>, &'static str> {
+    if rng.is_crypto() { Ok(rng) } else { Err(rng.backend()) }
+}
+
+/// Example policy: `n` non-overlapping streams, by jumping if supported.
+fn streams(mut rng: Box<dyn Rng>, n: usize) -> Option<Vec<u64>> {
+    if !rng.supports_jump() {
+        return None;
+    }
+    Some((0..n).map(|_| {
+        let x = rng.next_u64();
+        rng.try_jump().unwrap();
+        x
+    }).collect())
+}
+
+fn caps(fallible: bool, crypto: bool, jump: bool, backend: &'static str) -> Capabilities {
+    Capabilities { fallible, crypto, jump, backend }
+}
+
+pub fn main() {
+    {
+        // Plain generators, statically and through trait objects
+        let gens: Vec<(Box<dyn Rng>, Capabilities)> = vec![
+            (Box::new(Counter(0)), caps(false, false, true, "Counter")),
+            (Box::new(TestCRng(1)), caps(false, true, false, "TestCRng")),
+            (Box::new(TestOsRng { available: true }), caps(true, true, false, "TestOsRng")),
+            (Box::new(Quiet), caps(true, false, false, type_name::<Quiet>())),
+        ];
+        for (rng, expected) in gens {
+            assert_eq!(rng.capabilities(), expected);
+            // Box<Box<dyn Rng>> and &mut dyn Rng forward, not default
+            let mut boxed: Box<Box<dyn Rng>> = Box::new(rng);
+            assert_eq!(boxed.capabilities(), expected);
+            let r: &mut dyn Rng = &mut *boxed;
+            assert_eq!((&&mut *r).capabilities(), expected);
+        }
+        let e = Quiet.try_jump().unwrap_err();
+        assert_eq!((e.kind, e.msg), (ErrorKind::Unavailable, "jump not supported"));
+        println!("generators: capabilities visible through trait objects");
+    }
+    {
+        // Fallback: crypto/fallible only if both; backend follows use
+        let mut f = Fallback::new(TestOsRng { available: false }, TestCRng(7));
+        assert_eq!(f.capabilities(), caps(false, true, false, "TestOsRng"));
+        f.next_u64();
+        assert_eq!(f.backend(), "TestCRng");
+        
+        let f = Fallback::new(TestOsRng { available: true }, Counter(0));
+        assert_eq!(f.capabilities(), caps(false, false, false, "TestOsRng"));
+        let f = Fallback::new(TestOsRng { available: true }, TestOsRng { available: true });
+        assert_eq!(f.capabilities(), caps(true, true, false, "TestOsRng"));
+        
+        // Nested adaptors combine all the way down
+        let f = Fallback::new(ErrorInjectingRng { rng: TestCRng(1),
+            inject: Injector::transient(1) },
+            Fallback::new(TestOsRng { available: true }, TestCRng(2)));
+        assert_eq!(f.capabilities(), caps(false, true, false, "TestCRng"));
+        println!("Fallback: combined capabilities ok");
+    }
+    {
+        // Reseeding: crypto only with a crypto source; never jumps
+        let r = ReseedingRng { rng: TestCRng(1), source: TestOsRng { available: true }, threshold: 4, used: 0 };
+        assert_eq!(r.capabilities(), caps(false, true, false, "TestCRng"));
+        let r = ReseedingRng { rng: TestCRng(1), source: Counter(0), threshold: 4, used: 0 };
+        assert_eq!(r.capabilities(), caps(false, false, false, "TestCRng"));
+        let r = ReseedingRng { rng: Counter(1), source: TestOsRng { available: true }, threshold: 4, used: 0 };
+        assert_eq!(r.capabilities(), caps(false, false, false, "Counter"));
+        
+        // Error injection: always fallible, otherwise as the inner generator
+        let mut e = ErrorInjectingRng { rng: Counter(0), inject: Injector::transient(1) };
+        assert_eq!(e.capabilities(), caps(true, false, true, "Counter"));
+        assert!(e.try_jump().is_ok());
+        println!("ReseedingRng, ErrorInjectingRng: combined capabilities ok");
+    }
+    {
+        // Policy decisions from Box<dyn Rng> alone
+        assert!(key_rng(Box::new(TestCRng(1))).is_ok());
+        assert_eq!(key_rng(Box::new(Counter(1))).err(), Some("Counter"));
+        let r: Box<dyn Rng> = Box::new(ReseedingRng { rng: TestCRng(1), source: Counter(0), threshold: 4, used: 0 });
+        assert_eq!(key_rng(r).err(), Some("TestCRng"));
+        
+        assert_eq!(streams(Box::new(Counter(0)), 3), Some(vec![1, (1 << 32) + 2, (2 << 32) + 3]));
+        assert_eq!(streams(Box::new(TestCRng(0)), 3), None);
+        println!("policies: ok");
+    }
+}
@@ -0,0 +1,111 @@
+//! ChaCha20: a real cipher behind the crypto roles
+//! 
+//! The test generators are stubs: a crypto role over `Constant` claims a
+//! security it hasn't got, which is fine for checking dispatch and useless
+//! for checking what `CryptoRng` promises. `ChaCha20Core` is the cipher of
+//! RFC 7539 a block at a time: each block is the keystream of one counter
+//! value, sixteen words. `test_rngs.rs` includes this file and makes the core
+//! a `BlockRngCore` of `block.rs`, so `TestCRng<BlockRng<ChaCha20Core>>` is a
+//! genuine crypto generator in every variant, through the impls it already
+//! has; like the other shared files, this one needs nothing but `core`.
+//! 
+//! `block` is the RFC's block function, its last four input words given by
+//! the caller: the RFC puts a 32-bit counter and a 96-bit nonce there, and
+//! `ChaCha20Core` a 64-bit counter and a 64-bit stream, as Bernstein's
+//! original and `rand_chacha` do, so a stream can't run out. The seed is the
+//! key, 32 bytes; the counter and stream start at zero. Through the roles,
+//! whose `Source` gives `u64`s, a design's `u32` output is the low half of
+//! each pair of keystream words: still keystream, just not all of it.
+//! 
+//! This file has no `main`: `generators/chacha20.rs` checks it against the
+//! RFC's test vectors, and the variants including it exercise it.
+
+use std::fmt;
+
+/// "expand 32-byte k", the first four words of every block's input.
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+#[inline(always)]
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+/// The ChaCha20 block function: twenty rounds over the constants, `key` and
+/// `tail`, added to the input.
+pub fn block(key: &[u32; 8], tail: [u32; 4]) -> [u32; 16] {
+    let mut input = [0u32; 16];
+    input[..4].copy_from_slice(&CONSTANTS);
+    input[4..12].copy_from_slice(key);
+    input[12..].copy_from_slice(&tail);
+    let mut s = input;
+    for _ in 0..10 {
+        quarter_round(&mut s, 0, 4, 8, 12);
+        quarter_round(&mut s, 1, 5, 9, 13);
+        quarter_round(&mut s, 2, 6, 10, 14);
+        quarter_round(&mut s, 3, 7, 11, 15);
+        quarter_round(&mut s, 0, 5, 10, 15);
+        quarter_round(&mut s, 1, 6, 11, 12);
+        quarter_round(&mut s, 2, 7, 8, 13);
+        quarter_round(&mut s, 3, 4, 9, 14);
+    }
+    for (x, i) in s.iter_mut().zip(input.iter()) {
+        *x = x.wrapping_add(*i);
+    }
+    s
+}
+
+/// Words of `bytes`, little-endian.
+pub fn words_le<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    for (w, b) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    }
+    words
+}
+
+/// ChaCha20 with a 64-bit counter and stream, a block at a time.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChaCha20Core {
+    key: [u32; 8],
+    counter: u64,
+    stream: u64,
+}
+
+impl ChaCha20Core {
+    pub fn new(key: [u32; 8], stream: u64) -> Self {
+        ChaCha20Core { key, counter: 0, stream }
+    }
+    
+    /// The key, little-endian words; stream 0.
+    pub fn from_key(key: [u8; 32]) -> Self {
+        ChaCha20Core::new(words_le(&key), 0)
+    }
+    
+    /// The next block of keystream.
+    pub fn next_block(&mut self) -> [u32; 16] {
+        let (c, s) = (self.counter, self.stream);
+        self.counter = c.wrapping_add(1);
+        block(&self.key, [c as u32, (c >> 32) as u32, s as u32, (s >> 32) as u32])
+    }
+    
+    /// The counter of the next block.
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+}
+
+// Not derived: the key stays out of logs.
+impl fmt::Debug for ChaCha20Core {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChaCha20Core").field("counter", &self.counter)
+            .field("stream", &self.stream).finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,319 @@
+//! Clone policy: an opt-in `CloneableRng`
+//! 
+//! A clone of a generator replays its original's stream: right for a
+//! checkpoint, and silently wrong for `vec![rng.clone(); n]`. Here generic
+//! code which clones a generator bounds it by the marker `CloneableRng: Rng +
+//! Clone`, which an author implements to mean "cloning replays", and `fork`
+//! seeds a second, unrelated stream from the parent's output. Compile-fail
+//! checks: `fail_clone_borrowed`, `fail_clone_dyn`, `fail_reseeding_replay`.
+//! 
+//! Thoughts: the marker costs one line per generator and catches the
+//! commonest bug at compile time: code that wrote `R: Clone` to get
+//! independent streams no longer accepts generators whose clones replay,
+//! and `fork` is the fix. Crypto generators implement neither.
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+pub trait CryptoRng {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+}
+
+/// A generator whose clones replay its stream, deliberately.
+/// 
+/// Implement this only where a replay is what a clone should mean: for
+/// checkpoints, tests, reproducing a run. For a second stream, use `fork`.
+pub trait CloneableRng: Rng + Clone {}
+
+/// A new generator, seeded from `rng`'s output: a second stream, unrelated
+/// to the first.
+pub fn fork<R: Rng + SeedableRng>(rng: &mut R) -> R {
+    let mut seed = R::Seed::default();
+    rng.fill(seed.as_mut());
+    R::from_seed(seed)
+}
+
+// ——— impls ———
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+}
+
+impl<R: CloneableRng> CloneableRng for Box<R> {}
+
+// ——— adaptors ———
+
+/// A crypto generator as an `Rng`, owned. `Clone` when `CR` is, by derive;
+/// never `CloneableRng`.
+#[derive(Debug, Clone)]
+pub struct AsRng<CR>(pub CR);
+
+impl<CR: CryptoRng> Rng for AsRng<CR> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.try_next_u32().unwrap()
+    }
+}
+
+/// A crypto generator as an `Rng`, borrowed. Not `Clone`: it holds a
+/// `&mut`.
+#[derive(Debug)]
+pub struct AsRngMut<'a, CR: ?Sized + 'a>(pub &'a mut CR);
+
+impl<'a, CR: CryptoRng + ?Sized> Rng for AsRngMut<'a, CR> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.try_next_u32().unwrap()
+    }
+}
+
+/// A source of seeds.
+pub trait EntropySource {
+    fn fill_seed(&mut self, dest: &mut [u8]);
+}
+
+/// Reseeds `R` from `S` after every `threshold` words.
+#[derive(Debug)]
+pub struct ReseedingRng<R, S> {
+    rng: R,
+    source: S,
+    threshold: u64,
+    /// Words produced by the current state.
+    used: u64,
+}
+
+impl<R: SeedableRng, S: EntropySource> ReseedingRng<R, S> {
+    pub fn new(rng: R, source: S, threshold: u64) -> Self {
+        ReseedingRng { rng, source, threshold, used: 0 }
+    }
+}
+
+impl<R: Rng + SeedableRng, S: EntropySource> Rng for ReseedingRng<R, S> {
+    fn next_u32(&mut self) -> u32 {
+        if self.used >= self.threshold {
+            let mut seed = R::Seed::default();
+            self.source.fill_seed(seed.as_mut());
+            self.rng = R::from_seed(seed);
+            self.used = 0;
+        }
+        self.used += 1;
+        self.rng.next_u32()
+    }
+}
+
+// The clone reseeds before its first word, so it doesn't replay.
+impl<R: Clone, S: Clone> Clone for ReseedingRng<R, S> {
+    fn clone(&self) -> Self {
+        ReseedingRng {
+            rng: self.rng.clone(),
+            source: self.source.clone(),
+            threshold: self.threshold,
+            used: self.threshold,
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::seeding::SeedableRng;
+use self::test_rngs::{Constant, Source, SplitMix, TestCRng, TestRng};
+use std::cell::Cell;
+use std::rc::Rc;
+
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: Source> CryptoRng for TestCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        Ok(self.0.word() as u32)
+    }
+}
+
+/// SplitMix64 as a generator of both kinds; `Clone`, and so opted in.
+#[derive(Debug, Clone)]
+pub struct SplitMixRng(pub SplitMix);
+
+impl Rng for SplitMixRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl CryptoRng for SplitMixRng {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        Ok(self.0.word() as u32)
+    }
+}
+
+impl CloneableRng for SplitMixRng {}
+
+impl SeedableRng for SplitMixRng {
+    type Seed = [u8; 8];
+    
+    fn from_seed(seed: [u8; 8]) -> Self {
+        SplitMixRng(SplitMix(u64::from_le_bytes(seed)))
+    }
+}
+
+/// A handle to shared entropy, as to the OS: clones draw from the same
+/// counter, so no two seeds are alike.
+#[derive(Debug, Clone)]
+pub struct SharedEntropy(Rc<Cell<u64>>);
+
+impl EntropySource for SharedEntropy {
+    fn fill_seed(&mut self, dest: &mut [u8]) {
+        let n = self.0.get() + 1;
+        self.0.set(n);
+        bridge::fill_via_u64(dest, || n);
+    }
+}
+
+/// A deterministic source: its clones give the same seeds.
+#[derive(Debug, Clone)]
+pub struct CountingEntropy(u64);
+
+impl EntropySource for CountingEntropy {
+    fn fill_seed(&mut self, dest: &mut [u8]) {
+        self.0 += 1;
+        let n = self.0;
+        bridge::fill_via_u64(dest, || n);
+    }
+}
+
+// ——— compile-fail cases ———
+
+/// Draws from `rng` and from its replay, which must agree.
+fn replay<R: CloneableRng>(rng: &mut R, n: usize) -> (Vec<u32>, Vec<u32>) {
+    let mut copy = rng.clone();
+    ((0..n).map(|_| rng.next_u32()).collect(), (0..n).map(|_| copy.next_u32()).collect())
+}
+
+// `&mut CR` isn't `Clone`, so the borrowing adaptor can't be.
+#[cfg(fail_clone_borrowed)]
+fn clone_borrowed(rng: &mut SplitMixRng) {
+    let a = AsRngMut(rng);
+    let _b = a.clone();
+}
+
+// `Clone` needs `Sized`, so no trait object can be cloned.
+#[cfg(fail_clone_dyn)]
+fn clone_dyn(rng: Box<dyn Rng>) -> Box<dyn Rng> {
+    rng.clone()
+}
+
+// `ReseedingRng` is `Clone` but doesn't replay, so doesn't opt in.
+#[cfg(fail_reseeding_replay)]
+fn reseeding_replay(rng: &mut ReseedingRng<SplitMixRng, SharedEntropy>) {
+    replay(rng, 4);
+}
+
+// ——— consumers ———
+
+/// A random walk of `steps` steps; the end point.
+fn walk<R: Rng>(rng: &mut R, steps: usize) -> i64 {
+    (0..steps).map(|_| if rng.next_u32() >> 31 == 0 { -1 } else { 1 }).sum()
+}
+
+/// The bug: each particle gets a clone, and every walk is the same. Written
+/// with `R: Clone` this compiles for any `Clone` generator; with the marker
+/// it says what it does.
+fn walks_cloned<R: CloneableRng>(rng: &R, particles: usize) -> Vec<i64> {
+    (0..particles).map(|_| walk(&mut rng.clone(), 64)).collect()
+}
+
+/// The fix: each particle gets a fork.
+fn walks_forked<R: Rng + SeedableRng>(rng: &mut R, particles: usize) -> Vec<i64> {
+    (0..particles).map(|_| walk(&mut fork(rng), 64)).collect()
+}
+
+// ——— usage ———
+
+fn assert_clone<T: Clone>() {}
+fn assert_cloneable<T: CloneableRng>() {}
+
+pub fn main() {
+    {
+        // The hazard: clones walk in lockstep; forks don't
+        let mut rng = SplitMixRng(SplitMix(1));
+        let cloned = walks_cloned(&rng, 8);
+        assert!(cloned.iter().all(|&x| x == cloned[0]));
+        let forked = walks_forked(&mut rng, 8);
+        assert!(forked.iter().any(|&x| x != forked[0]));
+        println!("walks of clones: {:?}", cloned);
+        println!("walks of forks:  {:?}", forked);
+    }
+    {
+        // What can be `Clone`, and what opts in to replay
+        assert_clone::<SplitMixRng>();
+        assert_cloneable::<SplitMixRng>();
+        assert_cloneable::<Box<SplitMixRng>>();
+        assert_clone::<AsRng<SplitMixRng>>();
+        assert_clone::<ReseedingRng<SplitMixRng, SharedEntropy>>();
+        let (a, b) = replay(&mut Box::new(SplitMixRng(SplitMix(2))), 4);
+        assert_eq!(a, b);
+        // `TestRng` and `TestCRng` aren't `Clone`, so neither is an adaptor
+        // over them; the borrowing adaptor works, unclonable
+        let mut c = TestCRng(Constant(3));
+        assert_eq!(AsRngMut(&mut c).next_u32(), 3);
+        assert_eq!(AsRng(TestCRng(Constant(5))).next_u32(), 5);
+        assert_eq!(TestRng(Constant(7)).next_u32(), 7);
+        println!("Clone: SplitMixRng, Box, AsRng, ReseedingRng; CloneableRng: SplitMixRng, Box");
+    }
+    {
+        // `AsRng` over a cloneable crypto generator clones its keystream
+        let mut a = AsRng(SplitMixRng(SplitMix(4)));
+        let mut b = a.clone();
+        assert_eq!(a.next_u64(), b.next_u64());
+        println!("AsRng clone: the same keystream, so no CloneableRng");
+    }
+    {
+        // `ReseedingRng`'s clone reseeds at once: from shared entropy it
+        // diverges, from a deterministic source it replays the original's
+        // next generation
+        let shared = SharedEntropy(Rc::new(Cell::new(0)));
+        let mut r = ReseedingRng::new(SplitMixRng(SplitMix(5)), shared, 4);
+        let mut c = r.clone();
+        let (x, y): (Vec<u32>, Vec<u32>) = ((0..8).map(|_| r.next_u32()).collect(),
+            (0..8).map(|_| c.next_u32()).collect());
+        assert!(x[..4] != y[..4] && x[4..] != y[..4]);
+        
+        let mut r = ReseedingRng::new(SplitMixRng(SplitMix(5)), CountingEntropy(0), 4);
+        let mut c = r.clone();
+        let x: Vec<u32> = (0..8).map(|_| r.next_u32()).collect();
+        let y: Vec<u32> = (0..4).map(|_| c.next_u32()).collect();
+        assert_eq!(x[4..], y[..]);
+        println!("ReseedingRng clone: fresh with shared entropy, a replay without");
+    }
+}
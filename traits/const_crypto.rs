@@ -0,0 +1,344 @@
+//! Single trait with crypto-ness as a const parameter: `Rng<const CRYPTO: bool>`
+//! 
+//! Like kind_param, but the marker is a `bool` const generic instead of a
+//! marker type: `Rng<false>` is a plain generator, `Rng<true>` a crypto one.
+//! Every `Rng<true>` implicitly implements `Rng<false>`.
+//! 
+//! Methods: `next_u32` is required, and `next_u64`, `next_u128` and `fill`
+//! are provided on top of it (see `bridge.rs`); each `try_` method defaults
+//! to its counterpart, wrapped in `Ok`.
+//! 
+//! Thoughts: behaves exactly like kind_param (same blanket impl, same
+//! ambiguity on crypto generators, same dual-impl conflict); both object types
+//! `dyn Rng<false>` and `dyn Rng<true>` are fine since the parameter is fixed
+//! in the object type. The bound syntax is the real difference and it's worse:
+//! `R: Rng<true>` says nothing to a reader, whereas `R: Gen<Crypto>` or
+//! `R: CryptoRng` do; a `const CRYPTO: bool = false` default helps only for
+//! the common case. There's also no room to grow: a marker type can carry
+//! associated items (e.g. an error type), a `bool` cannot. No benefit over
+//! the two-trait designs; the bench shows no overhead either.
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_dual_impl const_crypto.rs
+//!     rustc --cfg fail_plain_as_crypto const_crypto.rs
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+pub trait Rng<const CRYPTO: bool = false> {
+    /// Generate a random number. Panics on error.
+    fn next_u32(&mut self) -> u32;
+    
+    /// Generate a random `u64`. Panics on error.
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    /// Generate a random `u128`. Panics on error.
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    /// Fill `dest` with random bytes. Panics on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+    
+    /// Generate a random number, reporting errors.
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        Ok(self.next_u32())
+    }
+    
+    /// Generate a random `u64`, reporting errors.
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        Ok(self.next_u64())
+    }
+    
+    /// Generate a random `u128`, reporting errors.
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        Ok(self.next_u128())
+    }
+    
+    /// Fill `dest` with random bytes, reporting errors.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill(dest);
+        Ok(())
+    }
+}
+
+// ——— impls ———
+
+impl<R: Rng<true>+?Sized> Rng<false> for R {
+    fn next_u32(&mut self) -> u32 {
+        Rng::<true>::next_u32(self)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        Rng::<true>::next_u64(self)
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        Rng::<true>::next_u128(self)
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        Rng::<true>::fill(self, dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        Rng::<true>::try_next_u32(self)
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        Rng::<true>::try_next_u64(self)
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        Rng::<true>::try_next_u128(self)
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        Rng::<true>::try_fill(self, dest)
+    }
+}
+
+impl<R: Rng<true>+?Sized> Rng<true> for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        (**self).try_fill(dest)
+    }
+}
+
+impl<R: Rng<true>+?Sized> Rng<true> for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        (**self).try_fill(dest)
+    }
+}
+
+// ——— adaptor ———
+
+// Given `rng` of type `T` where `T: Rng<false>`, treat it as crypto.
+fn assume_crypto<'a, R: Rng+?Sized+'a>(rng: &'a mut R) -> AssumeCrypto<'a, R> {
+    AssumeCrypto { rng }
+}
+
+struct AssumeCrypto<'a, R: Rng+?Sized+'a> {
+    rng: &'a mut R
+}
+
+impl<'a, R: Rng+?Sized> Rng<true> for AssumeCrypto<'a, R> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.fill(dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        self.rng.try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        self.rng.try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        self.rng.try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill(dest)
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, Source, TestCRng, TestRng};
+
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// A CryptoRng
+impl<S: Source> Rng<true> for TestCRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+#[cfg(fail_dual_impl)]
+mod dual {
+    use super::*;
+    
+    struct TestBothRng(u32);
+    
+    impl Rng<true> for TestBothRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+    }
+    
+    // Error: conflicts with `impl<R: Rng<true>+?Sized> Rng<false> for R`
+    impl Rng<false> for TestBothRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+    }
+}
+
+// ——— consumers ———
+
+fn next_pair<const C: bool, R: Rng<C>+?Sized>(rng: &mut R) -> (u32, u32) {
+    (rng.next_u32(), rng.next_u32())
+}
+
+fn gen_key<R: Rng<true>+?Sized>(rng: &mut R) -> u32 {
+    rng.next_u32()
+}
+
+#[cfg(fail_plain_as_crypto)]
+fn plain_as_crypto() {
+    gen_key(&mut TestRng(Constant(1)));
+}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
+    println!("t: {:?} impls Rng<false>", t);
+    println!("c: {:?} impls Rng<true>", c);
+    {
+        // Do both traits support both functions via static dispatch?
+        println!("t, static dispatch, using Rng<false>: {:?}", t.next_u32());
+        println!("t, static dispatch, using Rng<true>: {:?}", gen_key(&mut assume_crypto(&mut t)));
+        println!("c, static dispatch, using Rng<true>: {:?}", Rng::<true>::try_next_u32(&mut c));
+        println!("c, static dispatch, using Rng<false>: {:?}", Rng::<false>::next_u32(&mut c));
+        println!("c, static dispatch, generic over CRYPTO: {:?}", next_pair::<true, _>(&mut c));
+    }
+    {
+        // Can both types be used via Rng<true> with dynamic dispatch?
+        let cr = &mut c as &mut dyn Rng<true>;
+        println!("c, dynamic dispatch, using Rng<true>: {:?}", cr.try_next_u32());
+        let mut ta = assume_crypto(&mut t);
+        let tr = &mut ta as &mut dyn Rng<true>;
+        println!("t, dynamic dispatch, using Rng<true>: {:?}", tr.try_next_u32());
+    }
+    {
+        // Can both types be used via Rng<false> with dynamic dispatch?
+        let cr = &mut c as &mut dyn Rng;
+        println!("c, dynamic dispatch, using Rng<false>: {:?}", cr.next_u32());
+        let tr = &mut t as &mut dyn Rng;
+        println!("t, dynamic dispatch, using Rng<false>: {:?}", tr.next_u32());
+        let cr = &mut c as &mut dyn Rng<true>;
+        println!("c, dynamic dispatch, Rng<true> used as Rng<false>: {:?}", Rng::<false>::next_u32(cr));
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket Rng<false> impl forwards each method
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(Rng::<false>::next_u64(&mut c), 2 << 32 | 1);
+        println!("c, provided methods via Rng<false>: the same words");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        // No forwarding for `Rng<false>`: it would overlap the blanket impl
+        fn use_crng<R: Rng<true>>(mut r: R, how: &str) {
+            println!("c, {}, using Rng<true>: {:?}", how, r.next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn Rng<true>, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn Rng<true>>, "by Box<dyn>");
+    }
+}
@@ -0,0 +1,313 @@
+//! Downcasting a trait object: from `dyn Rng` back to the generator
+//! 
+//! A test harness hands the code under test a `&mut dyn Rng`, and afterwards
+//! wants the mock behind it: how many words it gave out, or to reset it.
+//! Marker traits and queries (`capabilities.rs`) can't give that; it needs
+//! `Any`'s downcast, which a trait object of `Rng` doesn't have. Three ways
+//! to give it:
+//! 
+//! *   a hidden `type_id` method on `Rng`, provided and bounded `where Self:
+//!     'static`, with `is`, `downcast_ref` and `downcast_mut` inherent to
+//!     `dyn Rng + 'static`, as `std::error::Error` does. This is the design
+//!     here; `dyn CryptoRng` gets them by upcasting to `dyn Rng`
+//! *   `Rng: Any` as a supertrait, upcasting `&dyn Rng` to `&dyn Any` (stable
+//!     since Rust 1.86): no method at all, but every generator must be
+//!     `'static` (module `any_supertrait`)
+//! *   a required `fn as_any(&self) -> &dyn Any`: it can't be provided, as
+//!     `self as &dyn Any` needs `Self: Sized`, and a method bounded so can't
+//!     be called on a trait object; every generator writes the same line
+//! 
+//! `Rng` is `extends_Rng_infallible`'s, `next_u32` with `next_u64` and `fill`
+//! provided; `MockRng` counts the words it gives out.
+//! 
+//! Run with:
+//! 
+//!     rustc downcast.rs && ./downcast
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_borrowed_any downcast.rs
+//!     rustc --cfg fail_elided_lifetime downcast.rs
+//! 
+//! Thoughts: the `std::error::Error` pattern works and costs implementors
+//! nothing, but must be got right: an override of `type_id` that lies is
+//! undefined behaviour (hence the `private::Internal` seal), and forwarding
+//! impls must not forward it. The catch for a harness is the lifetime: it
+//! must take `&mut (dyn Rng + 'static)` (`fail_elided_lifetime`). `Rng: Any`
+//! rules out `impl Rng for &mut R` (`fail_borrowed_any`). Downcasting suits
+//! tests; a framework should ask a generator through queries instead.
+
+use std::any::TypeId;
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+mod private {
+    // Other crates can't name this, so can't override `type_id`
+    #[derive(Debug)]
+    pub struct Internal;
+}
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+    
+    /// The implementing type's id; never override.
+    #[doc(hidden)]
+    fn type_id(&self, _: private::Internal) -> TypeId where Self: 'static {
+        TypeId::of::<Self>()
+    }
+}
+
+pub trait CryptoRng: Rng {}
+
+// ——— impls ———
+
+impl dyn Rng + 'static {
+    /// True if the generator is a `T`.
+    pub fn is<T: Rng + 'static>(&self) -> bool {
+        self.type_id(private::Internal) == TypeId::of::<T>()
+    }
+    
+    /// The generator, if it is a `T`.
+    pub fn downcast_ref<T: Rng + 'static>(&self) -> Option<&T> {
+        if self.is::<T>() {
+            // Safe: the object's type is `T`
+            Some(unsafe { &*(self as *const dyn Rng as *const T) })
+        } else {
+            None
+        }
+    }
+    
+    /// The generator, if it is a `T`.
+    pub fn downcast_mut<T: Rng + 'static>(&mut self) -> Option<&mut T> {
+        if self.is::<T>() {
+            // Safe: the object's type is `T`
+            Some(unsafe { &mut *(self as *mut dyn Rng as *mut T) })
+        } else {
+            None
+        }
+    }
+}
+
+impl dyn CryptoRng + 'static {
+    pub fn is<T: Rng + 'static>(&self) -> bool {
+        (self as &dyn Rng).is::<T>()
+    }
+    
+    pub fn downcast_ref<T: Rng + 'static>(&self) -> Option<&T> {
+        (self as &dyn Rng).downcast_ref()
+    }
+    
+    pub fn downcast_mut<T: Rng + 'static>(&mut self) -> Option<&mut T> {
+        (self as &mut dyn Rng).downcast_mut()
+    }
+}
+
+// Not forwarding `type_id`: `&mut R` and `Box<R>` are types of their own.
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {}
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Source, TestCRng, TestRng};
+
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: Source> Rng for TestCRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: Source> CryptoRng for TestCRng<S> {}
+
+/// A mock: a constant word, counting the words given out.
+#[derive(Debug)]
+pub struct MockRng {
+    pub value: u32,
+    pub words: usize,
+}
+
+impl MockRng {
+    pub fn new(value: u32) -> MockRng {
+        MockRng { value, words: 0 }
+    }
+}
+
+impl Rng for MockRng {
+    fn next_u32(&mut self) -> u32 {
+        self.words += 1;
+        self.value
+    }
+}
+
+// A crypto mock, for `dyn CryptoRng`.
+impl CryptoRng for MockRng {}
+
+// ——— alternative: `Any` as a supertrait ———
+
+mod any_supertrait {
+    use std::any::Any;
+    
+    pub trait Rng: Any {
+        fn next_u32(&mut self) -> u32;
+    }
+    
+    #[derive(Debug)]
+    pub struct MockRng(pub u32);
+    
+    impl Rng for MockRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+    }
+    
+    // Error: `&'a mut R` isn't `'static`, as `Any` requires.
+    #[cfg(fail_borrowed_any)]
+    impl<'a, R: Rng+?Sized> Rng for &'a mut R {
+        fn next_u32(&mut self) -> u32 {
+            (**self).next_u32()
+        }
+    }
+    
+    /// The generator behind `rng`, if it is a `MockRng`.
+    pub fn mock(rng: &dyn Rng) -> Option<&MockRng> {
+        (rng as &dyn Any).downcast_ref()
+    }
+}
+
+// ——— compile-fail cases ———
+
+// `&mut dyn Rng` in a signature is `&'a mut (dyn Rng + 'a)`, and the
+// downcast asks `'static`.
+#[cfg(fail_elided_lifetime)]
+fn words(rng: &mut dyn Rng) -> usize {
+    rng.downcast_ref::<MockRng>().unwrap().words
+}
+
+// ——— consumers ———
+
+/// The code under test: eight bytes, then a word.
+fn draw(rng: &mut dyn Rng) -> u64 {
+    let mut buf = [0; 8];
+    rng.fill(&mut buf);
+    rng.next_u32() as u64 ^ u64::from_le_bytes(buf)
+}
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) -> [u8; 16] {
+    let mut key = [0; 16];
+    rng.fill(&mut key);
+    key
+}
+
+// ——— usage ———
+
+pub fn main() {
+    {
+        // The harness: a mock in, its count out, through the trait object
+        let mut mock = MockRng::new(7);
+        let rng: &mut (dyn Rng + 'static) = &mut mock;
+        draw(rng);
+        assert!(rng.is::<MockRng>());
+        assert_eq!(rng.downcast_ref::<MockRng>().unwrap().words, 3);
+        rng.downcast_mut::<MockRng>().unwrap().words = 0;
+        assert_eq!(mock.words, 0);
+        println!("&mut dyn Rng: a MockRng, which gave out 3 words");
+    }
+    {
+        // The wrong type: `None`, and the generator is untouched
+        let mut t = TestRng(Constant(13));
+        let rng: &mut (dyn Rng + 'static) = &mut t;
+        assert!(!rng.is::<MockRng>());
+        assert!(rng.downcast_mut::<MockRng>().is_none());
+        assert!(rng.downcast_ref::<TestRng<Constant>>().is_some());
+        println!("&mut dyn Rng: a TestRng<Constant>, not a MockRng");
+    }
+    {
+        // `Box<dyn Rng>` is `'static` by default; the box itself is a
+        // generator of its own type, as `type_id` isn't forwarded
+        let mut b: Box<dyn Rng> = Box::new(MockRng::new(1));
+        draw(&mut *b);
+        assert_eq!(b.downcast_ref::<MockRng>().unwrap().words, 3);
+        let bb: Box<dyn Rng> = Box::new(b);
+        assert!(bb.downcast_ref::<MockRng>().is_none());
+        let b = bb.downcast_ref::<Box<dyn Rng>>().unwrap();
+        assert_eq!(b.downcast_ref::<MockRng>().unwrap().words, 3);
+        println!("Box<dyn Rng>: a MockRng; boxed again, a Box<dyn Rng>");
+    }
+    {
+        // `dyn CryptoRng`, through `dyn Rng`
+        let mut mock = MockRng::new(0x0101_0101);
+        let rng: &mut (dyn CryptoRng + 'static) = &mut mock;
+        assert_eq!(key(rng), [1; 16]);
+        assert_eq!(rng.downcast_ref::<MockRng>().unwrap().words, 4);
+        assert!(!rng.is::<TestCRng<Constant>>());
+        println!("&mut dyn CryptoRng: a MockRng, which gave out 4 words");
+    }
+    {
+        // Borrowed generators are still generators: `type_id` asks `'static`
+        // only of its callers
+        fn use_rng<R: Rng>(mut r: R) -> u32 {
+            r.next_u32()
+        }
+        let mut mock = MockRng::new(5);
+        assert_eq!(use_rng(&mut mock), 5);
+        assert_eq!(use_rng(&mut &mut mock), 5);
+        assert_eq!(mock.words, 2);
+        println!("&mut MockRng: an Rng, words counted by the MockRng");
+    }
+    {
+        // `Rng: Any`: upcast, then `Any`'s downcast
+        use self::any_supertrait::{mock, MockRng, Rng};
+        let mut m = MockRng(9);
+        assert_eq!(m.next_u32(), 9);
+        assert_eq!(mock(&m).unwrap().0, 9);
+        println!("Rng: Any, upcast to dyn Any: {:?}", mock(&m).unwrap());
+    }
+}
@@ -0,0 +1,141 @@
+//! The error type shared by the fallible variants
+//! 
+//! A failing generator says what kind of failure it is, so that the caller
+//! can tell "retry later" from "give up", and why, for the humans reading the
+//! log. The variants differ in where the error appears (`CryptoRng` only, a
+//! type parameter, an associated type), not in what it carries, so they
+//! include this file instead of each declaring an empty marker:
+//! 
+//!     #[path = "error.rs"]
+//!     #[allow(dead_code)]
+//!     mod error;
+//!     pub use self::error::{Error as CryptoError, ErrorKind};
+//! 
+//! The kinds are those of rand 0.4, and their advice:
+//! 
+//! *   `NotReady`: not ready *yet* (an OS entropy pool early in boot); wait,
+//!     then retry
+//! *   `Transient`: a passing failure (an interrupted read); retry now
+//! *   `Unavailable`: the source is gone or never existed; give up
+//! *   `Unexpected`: anything else, a bug or an unrecognised OS error; give up
+//! 
+//! The message is a `&'static str`, so the error needs no allocation and is
+//! `Copy`; a source with more to say (an OS error code) would add a field.
+//! marker_only's `fill` retries `Transient` errors, and its
+//! `fill_when_ready` waits out `NotReady` ones. `Injector` is the schedule
+//! of failures of the test generators that inject them.
+//! 
+//! This file has no `main`: the variants including it exercise it.
+
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The generator isn't ready yet; waiting, then retrying, may succeed.
+    NotReady,
+    /// A temporary failure; retrying soon may succeed.
+    Transient,
+    /// The generator cannot produce output; retrying will not help.
+    Unavailable,
+    /// An unexpected failure; retrying will not help.
+    Unexpected,
+}
+
+impl ErrorKind {
+    /// True if retrying may succeed.
+    pub fn should_retry(self) -> bool {
+        match self {
+            ErrorKind::NotReady | ErrorKind::Transient => true,
+            ErrorKind::Unavailable | ErrorKind::Unexpected => false,
+        }
+    }
+    
+    /// True if the retry should wait a while first.
+    pub fn should_wait(self) -> bool {
+        self == ErrorKind::NotReady
+    }
+    
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorKind::NotReady => "not ready yet",
+            ErrorKind::Transient => "transient failure",
+            ErrorKind::Unavailable => "unavailable",
+            ErrorKind::Unexpected => "unexpected failure",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// What failed, for humans.
+    pub msg: &'static str,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, msg: &'static str) -> Error {
+        Error { kind, msg }
+    }
+    
+    /// True if retrying may succeed.
+    pub fn should_retry(&self) -> bool {
+        self.kind.should_retry()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.msg, self.kind.description())
+    }
+}
+
+impl error::Error for Error {}
+
+// ——— injected failures ———
+
+/// When a test generator injecting errors fails: each design's
+/// `ErrorInjectingRng` asks before every fallible call, so all of them fail
+/// alike whatever their trait.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Injector {
+    calls: usize,
+    /// The first `transient` calls fail `Transient`.
+    transient: usize,
+    /// After those, every call fails `Unavailable`.
+    persistent: bool,
+    /// Otherwise every `period`-th call fails with `kind`; none if 0.
+    period: usize,
+    kind: ErrorKind,
+}
+
+impl Injector {
+    /// The first `n` calls fail `Transient`; later ones succeed.
+    pub fn transient(n: usize) -> Injector {
+        Injector { calls: 0, transient: n, persistent: false, period: 0, kind: ErrorKind::Transient }
+    }
+    
+    /// The first `n` calls fail `Transient`; later ones `Unavailable`.
+    pub fn persistent(n: usize) -> Injector {
+        Injector { persistent: true, ..Injector::transient(n) }
+    }
+    
+    /// Every `period`-th call fails with `kind`.
+    pub fn every(period: usize, kind: ErrorKind) -> Injector {
+        Injector { period, kind, ..Injector::transient(0) }
+    }
+    
+    /// How the next call goes: `Ok` to go ahead, or the error to fail with.
+    pub fn check(&mut self) -> Result<(), Error> {
+        self.calls += 1;
+        if self.calls <= self.transient {
+            Err(Error::new(ErrorKind::Transient, "injected transient error"))
+        } else if self.persistent {
+            Err(Error::new(ErrorKind::Unavailable, "injected persistent error"))
+        } else if self.period > 0 && self.calls.is_multiple_of(self.period) {
+            Err(Error::new(self.kind, "injected error"))
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -3,32 +3,134 @@
 //! This implements `Rng` for any `CryptoRng` implicitly.
 //! `Rng` has a default impl for `CryptoRng` but users may want to override
 //! 
-//! Note: this *only* considers the next_u32 member function
+//! Methods: `CryptoRng` requires `try_next_u32` and provides `try_next_u64`,
+//! `try_next_u128` and `try_fill` on top of it (see `bridge.rs`); each `Rng`
+//! method defaults to its `try_` counterpart, unwrapped.
 //! 
 //! Thoughts: works, but implementing Rng requires implementing CryptoRng
 //! manually too. Simpler but less convenient than extends_CryptoRng2.
 //! The default implementation of `Rng` may panic.
+//! 
+//! With the full method set, overriding an `Rng` method (as `TestRng` does
+//! with `next_u32`, for speed) does nothing for the others: `next_u64` goes
+//! to `try_next_u64`, and thence to `try_next_u32`. Each must be overridden.
 
 // ——— traits ———
 
-#[derive(Debug)]
-struct CryptoError;
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
 
-trait CryptoRng {
+pub trait CryptoRng {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
 }
 
-trait Rng: CryptoRng {
+pub trait Rng: CryptoRng {
     fn next_u32(&mut self) -> u32 {
         self.try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap()
+    }
 }
 
 // Required for `as_rng(&mut rng)`.
-impl<'a, CR: CryptoRng+?Sized> CryptoRng for &'a mut CR {
+impl<CR: CryptoRng+?Sized> CryptoRng for &mut CR {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         (*self).try_next_u32()
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (*self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (*self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (*self).try_fill(dest)
+    }
+}
+
+impl<CR: CryptoRng+?Sized> CryptoRng for Box<CR> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
 }
 
 // ——— adaptor ———
@@ -47,48 +149,109 @@ impl<CR: CryptoRng+?Sized> CryptoRng for AsRng<CR> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         self.rng.try_next_u32()
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        self.rng.try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        self.rng.try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.rng.try_fill(dest)
+    }
 }
 
 impl<CR: CryptoRng+?Sized> Rng for AsRng<CR> {
     fn next_u32(&mut self) -> u32 {
         self.rng.try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.try_fill(dest).unwrap()
+    }
 }
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, CountingRng, FailAfter, Source, TestCRng, TestRng};
 
-impl CryptoRng for TestRng {
+// A non-crypto Rng
+impl<S: Source> CryptoRng for TestRng<S> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
     }
 }
 
-impl Rng for TestRng {
+impl<S: Source> Rng for TestRng<S> {
     // This is optional, but should improve performance slightly:
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // A CryptoRng
-#[derive(Debug)]
-struct TestCRng(u32);
+impl<S: Source> CryptoRng for TestCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
 
-impl CryptoRng for TestCRng {
+// Any generator, metered
+impl<R: CryptoRng> CryptoRng for CountingRng<R> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+        let x = self.inner.try_next_u32()?;
+        Ok(self.u32(x))
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        let x = self.inner.try_next_u64()?;
+        Ok(self.u64(x))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.inner.try_fill(dest)?;
+        self.fill(dest.len());
+        Ok(())
+    }
+}
+
+impl<R: Rng> Rng for CountingRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        let x = self.inner.next_u32();
+        self.u32(x)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let x = self.inner.next_u64();
+        self.u64(x)
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.inner.fill(dest);
+        self.fill(dest.len());
     }
 }
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut c = TestCRng(42);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
     println!("t: {:?} impls Rng", t);
     println!("c: {:?} impls CryptoRng", c);
     {
@@ -112,4 +275,47 @@ fn main() {
         println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
         println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
     }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using CryptoRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill, via the try_ methods");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
 }
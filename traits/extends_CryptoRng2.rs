@@ -3,7 +3,9 @@
 //! This implements `Rng` for any `CryptoRng` implicitly.
 //! Each `Rng` automatically and safely implements its base `CryptoRng`.
 //! 
-//! Note: this *only* considers the next_u32 member function
+//! Methods: `CryptoRng` requires `try_next_u32`, `Rng` requires `next_u32`,
+//! and each provides the wider methods on top (see `bridge.rs`). The blanket
+//! `CryptoRng` impl forwards each method to its `Rng` counterpart.
 //! 
 //! Thoughts: works okay. Implementing each trait requires only one impl block.
 //! It's not possible to impl either trait with no function definition despite
@@ -15,15 +17,45 @@
 
 // ——— traits ———
 
-#[derive(Debug)]
-struct CryptoError;
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
 
-trait CryptoRng {
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
+
+pub trait CryptoRng {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
 }
 
-trait Rng: CryptoRng {
+pub trait Rng: CryptoRng {
     fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
 }
 
 // ——— impls ———
@@ -33,13 +65,56 @@ impl<R: Rng+?Sized> CryptoRng for R {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         Ok(self.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.fill(dest);
+        Ok(())
+    }
 }
 
 // Required for `as_rng(&mut rng)` and `as_rng_ref` definition.
-impl<'a, CR: CryptoRng+?Sized> Rng for &'a mut CR {
+impl<CR: CryptoRng+?Sized> Rng for &mut CR {
     fn next_u32(&mut self) -> u32 {
         (*self).try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        (*self).try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (*self).try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (*self).try_fill(dest).unwrap()
+    }
+}
+
+impl<CR: CryptoRng+?Sized> Rng for Box<CR> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).try_next_u32().unwrap()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).try_fill(dest).unwrap()
+    }
 }
 
 // ——— adaptor ———
@@ -58,35 +133,47 @@ impl<CR: CryptoRng+?Sized> Rng for AsRng<CR> {
     fn next_u32(&mut self) -> u32 {
         self.rng.try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.try_fill(dest).unwrap()
+    }
 }
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestCRng, TestRng};
 
-impl Rng for TestRng {
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // A CryptoRng
-#[derive(Debug)]
-struct TestCRng(u32);
-
-impl CryptoRng for TestCRng {
+impl<S: Source> CryptoRng for TestCRng<S> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
     }
 }
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut c = TestCRng(42);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
     println!("t: {:?} impls Rng", t);
     println!("c: {:?} impls CryptoRng", c);
     {
@@ -110,4 +197,53 @@ fn main() {
         println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
         println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
     }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using CryptoRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket CryptoRng impl: the Rng methods, wrapped in Ok
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.try_next_u64().unwrap(), 2 << 32 | 1);
+        println!("t, provided methods via CryptoRng: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
 }
@@ -2,34 +2,137 @@
 //! 
 //! This implements `Rng` for any `CryptoRng` implicitly.
 //! 
-//! Note: this *only* considers the next_u32 member function
+//! Methods: `Rng` requires `next_u32` and provides `next_u64`, `next_u128`
+//! and `fill` on top of it (see `bridge.rs`); each `try_` method of
+//! `CryptoRng` defaults to its `Rng` counterpart.
 //! 
 //! Thoughts: we probably don't want CryptoRng to depend on Rng. CryptoRng
 //! trait requires two implementations, one of which may have to panic.
 //! None of the "lib code" can panic, but this is moot due to above (user forced
 //! to write code which may panic).
+//! 
+//! With the full method set it gets worse: a fallible generator overriding
+//! `try_next_u32` must override all four `try_` methods, or `try_next_u64`
+//! and the rest quietly go through the infallible `Rng` methods instead.
 
 // ——— traits ———
 
-#[derive(Debug)]
-struct CryptoError;
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
 
-trait Rng {
+pub trait Rng {
     fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
 }
 
-trait CryptoRng: Rng {
+pub trait CryptoRng: Rng {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         Ok(self.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.fill(dest);
+        Ok(())
+    }
 }
 
 // ——— impls ———
 
-impl<'a, R: Rng+?Sized> Rng for &'a mut R {
+impl<R: Rng+?Sized> Rng for &mut R {
     fn next_u32(&mut self) -> u32 {
         (*self).next_u32()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        (*self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (*self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (*self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
 }
 
 // ——— adaptor ———
@@ -48,45 +151,105 @@ impl<R: Rng+?Sized> Rng for AsCRng<R> {
     fn next_u32(&mut self) -> u32 {
         self.rng.next_u32()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.fill(dest)
+    }
 }
 
 impl<R: Rng+?Sized> CryptoRng for AsCRng<R> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         Ok(self.rng.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.rng.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.rng.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.rng.fill(dest);
+        Ok(())
+    }
 }
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, CountingRng, Source, TestCRng, TestRng};
 
-impl Rng for TestRng {
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // A CryptoRng
-#[derive(Debug)]
-struct TestCRng(u32);
-
-impl Rng for TestCRng {
+impl<S: Source> Rng for TestCRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
-impl CryptoRng for TestCRng {
+impl<S: Source> CryptoRng for TestCRng<S> {
     // No function defintion needed *if* next_u32 version is sufficient
 }
 
+// Any generator, metered
+impl<R: Rng> Rng for CountingRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        let x = self.inner.next_u32();
+        self.u32(x)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let x = self.inner.next_u64();
+        self.u64(x)
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.inner.fill(dest);
+        self.fill(dest.len());
+    }
+}
+
+impl<R: CryptoRng> CryptoRng for CountingRng<R> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        let x = self.inner.try_next_u32()?;
+        Ok(self.u32(x))
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        let x = self.inner.try_next_u64()?;
+        Ok(self.u64(x))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.inner.try_fill(dest)?;
+        self.fill(dest.len());
+        Ok(())
+    }
+}
+
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut c = TestCRng(42);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
     println!("t: {:?} impls Rng", t);
     println!("c: {:?} impls CryptoRng", c);
     {
@@ -110,4 +273,39 @@ fn main() {
         println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
         println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
     }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // CryptoRng's defaults: the Rng methods, wrapped in Ok
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(c.try_next_u64().unwrap(), 2 << 32 | 1);
+        println!("c, provided methods via CryptoRng: the same words");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
 }
@@ -2,7 +2,9 @@
 //! 
 //! This implements `Rng` for any `CryptoRng` implicitly.
 //! 
-//! Note: this *only* considers the next_u32 member function
+//! Methods: `Rng` requires `next_u32` and provides `next_u64`, `next_u128`
+//! and `fill` on top of it (see `bridge.rs`). Nothing is fallible, so there is
+//! no `try_fill`.
 //! 
 //! Thoughts: works okay. Implementing each trait requires only one impl block.
 //! It's not possible to impl either trait with no function definition despite
@@ -12,21 +14,69 @@
 
 // ——— traits ———
 
-trait Rng {
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+pub trait Rng {
     fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
 }
 
-trait CryptoRng: Rng {}
+pub trait CryptoRng: Rng {}
 
 // ——— impls ———
 
-impl<'a, R: Rng+?Sized> Rng for &'a mut R {
+impl<R: Rng+?Sized> Rng for &mut R {
     fn next_u32(&mut self) -> u32 {
         (*self).next_u32()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        (*self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (*self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (*self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
 }
 
-impl<'a, R: CryptoRng+?Sized> CryptoRng for &'a mut R {}
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {}
+
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {}
 
 // ——— adaptor ———
 // Note: we *probably* don't need this, since the *only* reason to use
@@ -47,37 +97,48 @@ impl<R: Rng+?Sized> Rng for AsCRng<R> {
     fn next_u32(&mut self) -> u32 {
         self.rng.next_u32()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.fill(dest)
+    }
 }
 impl<R: Rng+?Sized> CryptoRng for AsCRng<R> {}
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, Source, TestCRng, TestRng};
 
-impl Rng for TestRng {
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // A CryptoRng
-#[derive(Debug)]
-struct TestCRng(u32);
-
-impl Rng for TestCRng {
+impl<S: Source> Rng for TestCRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
-impl CryptoRng for TestCRng {}
+impl<S: Source> CryptoRng for TestCRng<S> {}
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut c = TestCRng(42);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
     println!("t: {:?} impls Rng", t);
     println!("c: {:?} impls CryptoRng", c);
     {
@@ -100,4 +161,39 @@ fn main() {
         println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
         println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
     }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The adaptor forwards each method
+        let mut a = as_crng(TestRng(Counting(1)));
+        assert_eq!(a.next_u64(), 2 << 32 | 1);
+        println!("as_crng(t), provided methods: the same words");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
 }
@@ -0,0 +1,352 @@
+//! Tries to unify the two traits via a fallibility type parameter, without
+//! the never type: `Rng<F: Fallibility>`.
+//! 
+//! The `Fallibility` trait is a return-type constructor: `Infallible` maps
+//! `T` to the bare `T` and `Fallible` maps it to `Result<T, Error>`. One
+//! trait thus serves both worlds: `Rng<Infallible>::next_u32` returns `u32`,
+//! `Rng<Fallible>::next_u32` returns `Result<u32, Error>`. Every infallible
+//! generator implicitly implements the fallible trait. Crypto-ness is a
+//! separate marker, as in raw_explicit.
+//! 
+//! Methods: `next_u32` is required, and `next_u64`, `next_u128` and `fill`
+//! are provided on top of it, generic over `F`: `Rng<Fallible>::fill` is the
+//! `try_fill` of the other designs.
+//! 
+//! Thoughts: works on stable (generic associated types) and the bench shows no
+//! overhead. Implementers only write one impl block. The complexity moves to
+//! consumers: code generic over `F` can't use `?` or `unwrap`, so every
+//! combinator must be threaded through the `Fallibility` trait (`map`,
+//! `and_then` below; a real library would need many more). Meanwhile code
+//! which is not generic over `F` is just the two-trait design in disguise,
+//! with worse names, and infallible generators have two impls so method calls
+//! on them are ambiguous. The full method set's defaults are such generic
+//! code: `next_u64` needs `and_then`, and `fill`, a loop stopping at the first
+//! error, needs a combinator of its own (`for_each`). Same shape as raw_explicit, more machinery; no win.
+//! 
+//! Compile-fail check (must fail to compile):
+//! 
+//!     rustc --cfg fail_question_mark fallibility_param.rs
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+/// Fallibility of a generator: a constructor for its return types.
+pub trait Fallibility {
+    type Out<T>;
+    
+    fn map<T, U, G: FnOnce(T) -> U>(x: Self::Out<T>, f: G) -> Self::Out<U>;
+    fn and_then<T, U, G: FnOnce(T) -> Self::Out<U>>(x: Self::Out<T>, f: G) -> Self::Out<U>;
+    /// Calls `f` on each item in turn, stopping at the first error.
+    fn for_each<I: Iterator, G: FnMut(I::Item) -> Self::Out<()>>(iter: I, f: G) -> Self::Out<()>;
+}
+
+#[derive(Debug)]
+pub enum Infallible {}
+
+#[derive(Debug)]
+pub enum Fallible {}
+
+impl Fallibility for Infallible {
+    type Out<T> = T;
+    
+    fn map<T, U, G: FnOnce(T) -> U>(x: T, f: G) -> U { f(x) }
+    fn and_then<T, U, G: FnOnce(T) -> U>(x: T, f: G) -> U { f(x) }
+    fn for_each<I: Iterator, G: FnMut(I::Item)>(iter: I, f: G) { iter.for_each(f) }
+}
+
+impl Fallibility for Fallible {
+    type Out<T> = Result<T, Error>;
+    
+    fn map<T, U, G: FnOnce(T) -> U>(x: Result<T, Error>, f: G) -> Result<U, Error> {
+        x.map(f)
+    }
+    fn and_then<T, U, G: FnOnce(T) -> Result<U, Error>>(x: Result<T, Error>, f: G)
+        -> Result<U, Error>
+    {
+        x.and_then(f)
+    }
+    fn for_each<I: Iterator, G: FnMut(I::Item) -> Result<(), Error>>(mut iter: I, f: G)
+        -> Result<(), Error>
+    {
+        iter.try_for_each(f)
+    }
+}
+
+pub trait Rng<F: Fallibility> {
+    fn next_u32(&mut self) -> F::Out<u32>;
+    
+    fn next_u64(&mut self) -> F::Out<u64> {
+        let lo = self.next_u32();
+        F::and_then(lo, |lo| F::map(self.next_u32(), |hi| bridge::u64_from_u32(lo, hi)))
+    }
+    
+    fn next_u128(&mut self) -> F::Out<u128> {
+        let lo = self.next_u64();
+        F::and_then(lo, |lo| F::map(self.next_u64(), |hi| bridge::u128_from_u64(lo, hi)))
+    }
+    
+    // No `bridge::fill_via_u64` here: the loop must stop at an error, for
+    // either `F`, so it needs its own combinator.
+    fn fill(&mut self, dest: &mut [u8]) -> F::Out<()> {
+        F::for_each(dest.chunks_mut(8), |chunk| F::map(self.next_u64(), |x| {
+            let n = chunk.len();
+            chunk.copy_from_slice(&x.to_le_bytes()[..n]);
+        }))
+    }
+}
+
+pub trait CryptoRng<F: Fallibility>: Rng<F> {}
+
+// ——— impls ———
+
+// Every infallible generator is also a fallible one.
+impl<R: Rng<Infallible>+?Sized> Rng<Fallible> for R {
+    fn next_u32(&mut self) -> Result<u32, Error> {
+        Ok(Rng::<Infallible>::next_u32(self))
+    }
+    
+    fn next_u64(&mut self) -> Result<u64, Error> {
+        Ok(Rng::<Infallible>::next_u64(self))
+    }
+    
+    fn next_u128(&mut self) -> Result<u128, Error> {
+        Ok(Rng::<Infallible>::next_u128(self))
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        Rng::<Infallible>::fill(self, dest);
+        Ok(())
+    }
+}
+
+impl<R: CryptoRng<Infallible>+?Sized> CryptoRng<Fallible> for R {}
+
+impl<R: Rng<Infallible>+?Sized> Rng<Infallible> for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng<Infallible>+?Sized> Rng<Infallible> for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+// Fallible crypto generators can't forward: `Rng<Fallible> for &mut R` would
+// overlap the blanket impl, for an infallible `R`.
+impl<R: CryptoRng<Infallible>+?Sized> CryptoRng<Infallible> for &mut R {}
+impl<R: CryptoRng<Infallible>+?Sized> CryptoRng<Infallible> for Box<R> {}
+
+// ——— adaptor ———
+
+// Given `rng` of type `T` where `T: Rng<Fallible>`, this can consume
+// `&mut rng` (`as_rng_ref(rng)`), panicking on error
+pub fn as_rng_ref<'a, R: Rng<Fallible>+?Sized+'a>(rng: &'a mut R) -> AsRng<'a, R> {
+    AsRng { rng }
+}
+
+pub struct AsRng<'a, R: Rng<Fallible>+?Sized+'a> {
+    rng: &'a mut R
+}
+
+impl<'a, R: Rng<Fallible>+?Sized> Rng<Infallible> for AsRng<'a, R> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32().unwrap()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.fill(dest).unwrap()
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestFCRng, TestICRng, TestRng};
+
+// A non-crypto Rng
+impl<S: Source> Rng<Infallible> for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// An infallible CryptoRng
+impl<S: Source> Rng<Infallible> for TestICRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: Source> CryptoRng<Infallible> for TestICRng<S> {}
+
+// A fallible CryptoRng
+impl<S: Source> Rng<Fallible> for TestFCRng<S> {
+    fn next_u32(&mut self) -> Result<u32, Error> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+impl<S: Source> CryptoRng<Fallible> for TestFCRng<S> {}
+
+// ——— consumers ———
+
+// Generic over fallibility: returns `u64` or `Result<u64, Error>`. This is
+// just the default `Rng::next_u64`, written as a consumer would.
+fn next_u64<F: Fallibility, R: Rng<F>+?Sized>(rng: &mut R) -> F::Out<u64> {
+    let lo = rng.next_u32();
+    F::and_then(lo, |lo| {
+        F::map(rng.next_u32(), |hi| ((hi as u64) << 32) | lo as u64)
+    })
+}
+
+#[cfg(fail_question_mark)]
+fn next_u64_question_mark<F: Fallibility, R: Rng<F>+?Sized>(rng: &mut R) -> F::Out<u64> {
+    let lo = rng.next_u32()?;
+    let hi = rng.next_u32()?;
+    F::map(hi, |hi| ((hi as u64) << 32) | lo as u64)
+}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut ic = TestICRng(Constant(17));
+    let mut fc = TestFCRng(Constant(42));
+    println!("t: {:?} impls Rng<Infallible>", t);
+    println!("ic: {:?} impls CryptoRng<Infallible>", ic);
+    println!("fc: {:?} impls CryptoRng<Fallible>", fc);
+    {
+        // Do all generators support both fallibilities via static dispatch?
+        // Infallible generators (including adaptors!) have two impls so calls
+        // must be qualified.
+        println!("t, static dispatch, using Rng<Fallible>: {:?}", Rng::<Fallible>::next_u32(&mut t));
+        println!("t, static dispatch, using Rng<Infallible>: {:?}", Rng::<Infallible>::next_u32(&mut t));
+        println!("ic, static dispatch, using Rng<Fallible>: {:?}", Rng::<Fallible>::next_u32(&mut ic));
+        println!("ic, static dispatch, using Rng<Infallible>: {:?}", Rng::<Infallible>::next_u32(&mut ic));
+        println!("fc, static dispatch, using Rng<Fallible>: {:?}", fc.next_u32());
+        println!("fc, static dispatch, using Rng<Infallible>: {:?}", Rng::<Infallible>::next_u32(&mut as_rng_ref(&mut fc)));
+        println!("t, static dispatch, generic next_u64: {:?}", next_u64::<Infallible, _>(&mut t));
+        println!("fc, static dispatch, generic next_u64: {:?}", next_u64::<Fallible, _>(&mut fc));
+    }
+    {
+        // Can all types be used via Rng<Fallible> with dynamic dispatch?
+        let tr = &mut t as &mut dyn Rng<Fallible>;
+        println!("t, dynamic dispatch, using Rng<Fallible>: {:?}", tr.next_u32());
+        let ir = &mut ic as &mut dyn Rng<Fallible>;
+        println!("ic, dynamic dispatch, using Rng<Fallible>: {:?}", ir.next_u32());
+        let fr = &mut fc as &mut dyn Rng<Fallible>;
+        println!("fc, dynamic dispatch, using Rng<Fallible>: {:?}", fr.next_u32());
+        println!("fc, dynamic dispatch, generic next_u64: {:?}", next_u64::<Fallible, _>(fr));
+    }
+    {
+        // Can all types be used via Rng<Infallible> with dynamic dispatch?
+        let tr = &mut t as &mut dyn Rng<Infallible>;
+        println!("t, dynamic dispatch, using Rng<Infallible>: {:?}", tr.next_u32());
+        let ir = &mut ic as &mut dyn Rng<Infallible>;
+        println!("ic, dynamic dispatch, using Rng<Infallible>: {:?}", ir.next_u32());
+        let mut fr = as_rng_ref(&mut fc as &mut dyn Rng<Fallible>);
+        println!("fc, dynamic dispatch, using Rng<Infallible>: {:?}", Rng::<Infallible>::next_u32(&mut fr));
+    }
+    {
+        // Can both crypto RNGs be used via CryptoRng<Fallible> with dynamic dispatch?
+        let ir = &mut ic as &mut dyn CryptoRng<Fallible>;
+        println!("ic, dynamic dispatch, using CryptoRng<Fallible>: {:?}", ir.next_u32());
+        let fr = &mut fc as &mut dyn CryptoRng<Fallible>;
+        println!("fc, dynamic dispatch, using CryptoRng<Fallible>: {:?}", fr.next_u32());
+    }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestFCRng(FailAfter::new(1));
+        assert_eq!(f.next_u32().unwrap(), 0);
+        assert!(f.next_u32().is_err());
+        println!("f, failing after one word, using Rng<Fallible>: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(Rng::<Infallible>::next_u64(&mut t), 2 << 32 | 1);
+        assert_eq!(Rng::<Infallible>::next_u128(&mut t), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        Rng::<Infallible>::fill(&mut t, &mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket Rng<Fallible> impl: the infallible methods, wrapped in Ok
+        let mut t = TestRng(Counting(1));
+        assert_eq!(Rng::<Fallible>::next_u64(&mut t).unwrap(), 2 << 32 | 1);
+        println!("t, provided methods via Rng<Fallible>: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestFCRng(FailAfter::new(3));
+        assert_eq!(f.next_u64().unwrap(), 1 << 32);
+        assert!(f.fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: next_u64 Ok, then fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng<Infallible>>(mut r: R, how: &str) {
+            println!("t, {}, using Rng<Infallible>: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng<Infallible>, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng<Infallible>>, "by Box<dyn>");
+        // An infallible crypto generator: a fallible one can't be forwarded
+        fn use_crng<R: CryptoRng<Infallible>>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng<Infallible>: {:?}", how, r.next_u32());
+        }
+        let mut c = TestICRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng<Infallible>, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng<Infallible>>, "by Box<dyn>");
+    }
+}
@@ -0,0 +1,193 @@
+//! Seeding one generator from another: `from_rng`
+//! 
+//! `from_rng` fills a seed (the `SeedableRng` design of `seeding.rs`) from
+//! another generator, returning `Result<Self, Error>` with the source's
+//! error passed on by `?`. A crypto generator seeded from a plain one is no
+//! better than its seed, so the bound is chosen by the generator being
+//! seeded: `SeedFromRng::from_rng` takes any `R: Rng` and is implemented by
+//! plain generators, each opting in; `SeedFromCryptoRng::from_crypto_rng`
+//! takes `R: CryptoRng` and is implemented for every `SeedableRng`. The `Rng`
+//! here is that of marker_only.
+//! 
+//! Run with:
+//! 
+//!     rustc from_rng.rs && ./from_rng
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_crypto_from_plain from_rng.rs
+//!     rustc --cfg fail_plain_as_crypto from_rng.rs
+//! 
+//! Thoughts: one `from_rng` can't serve both: with `R: Rng` everywhere (rand
+//! 0.5), a crypto generator seeds from a plain one unseen; with `R:
+//! CryptoRng`, a simulation can't fork its small generators. The split
+//! costs an opt-in per plain generator (the blanket impl would need
+//! `!CryptoRng`), and relies on a crypto generator being usable as `Rng`.
+//! No retry happens here: a transient failure reaches the caller.
+
+// ——— traits ———
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+pub trait Rng {
+    /// Fill dest with random bytes.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+    
+    /// Fill dest with random bytes, panicking on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap_or_else(|e| panic!("Rng::fill failed: {:?}", e))
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+}
+
+pub trait CryptoRng: Rng {}
+
+/// A generator which may be seeded from any `Rng`: plain generators only.
+pub trait SeedFromRng: SeedableRng {
+    /// Seeds a generator from `rng`, passing on its error.
+    fn from_rng<R: Rng+?Sized>(rng: &mut R) -> Result<Self, Error> {
+        seed_via(rng)
+    }
+}
+
+/// A generator which may be seeded from a `CryptoRng`: any.
+pub trait SeedFromCryptoRng: SeedableRng {
+    /// Seeds a generator from `rng`, passing on its error.
+    fn from_crypto_rng<R: CryptoRng+?Sized>(rng: &mut R) -> Result<Self, Error> {
+        seed_via(rng)
+    }
+}
+
+fn seed_via<T: SeedableRng, R: Rng+?Sized>(rng: &mut R) -> Result<T, Error> {
+    let mut seed = T::Seed::default();
+    rng.try_fill(seed.as_mut())?;
+    Ok(T::from_seed(seed))
+}
+
+// ——— impls ———
+
+impl<T: SeedableRng> SeedFromCryptoRng for T {}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::seeding::SeedableRng;
+use self::test_rngs::{Counting, FailAfter, Source, SplitMix, TestCRng, TestRng};
+
+// Fills from the source's words; an exhausted source is unavailable.
+fn try_fill_from_source<S: Source>(source: &mut S, dest: &mut [u8]) -> Result<(), Error> {
+    for chunk in dest.chunks_mut(8) {
+        let x = source.try_word().map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))?;
+        chunk.copy_from_slice(&x.to_le_bytes()[..chunk.len()]);
+    }
+    Ok(())
+}
+
+// A non-crypto Rng, seedable from any Rng
+impl<S: Source> Rng for TestRng<S> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        try_fill_from_source(&mut self.0, dest)
+    }
+}
+
+impl<S: SeedableRng> SeedFromRng for TestRng<S> {}
+
+// A CryptoRng, seedable from a CryptoRng only
+impl<S: Source> Rng for TestCRng<S> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        try_fill_from_source(&mut self.0, dest)
+    }
+}
+
+impl<S: Source> CryptoRng for TestCRng<S> {}
+
+// ——— consumers ———
+
+/// `n` generators seeded from `master`, one after another.
+pub fn fork<T: SeedFromCryptoRng>(master: &mut dyn CryptoRng, n: usize) -> Result<Vec<T>, Error> {
+    (0..n).map(|_| T::from_crypto_rng(master)).collect()
+}
+
+#[cfg(fail_crypto_from_plain)]
+fn crypto_from_plain() {
+    // Error: `TestCRng<SplitMix>` doesn't implement `SeedFromRng`
+    let _ = TestCRng::<SplitMix>::from_rng(&mut TestRng(Counting(1)));
+}
+
+#[cfg(fail_plain_as_crypto)]
+fn plain_as_crypto() {
+    // Error: `TestRng<Counting>` doesn't implement `CryptoRng`
+    let _ = TestCRng::<SplitMix>::from_crypto_rng(&mut TestRng(Counting(1)));
+}
+
+// ——— usage ———
+
+pub fn main() {
+    // The seeded generators, over sources counting from 5: the seed is the
+    // first word, so each starts as `SplitMix(5)`
+    let want = TestRng(SplitMix(5)).next_u64();
+    {
+        let mut t = TestRng(Counting(5));
+        let mut a = TestRng::<SplitMix>::from_rng(&mut t).unwrap();
+        assert_eq!(a.next_u64(), want);
+        assert_eq!(t.next_u64(), 6);
+        println!("plain from plain, via SeedFromRng: seeded with the next word");
+    }
+    {
+        let mut c = TestCRng(Counting(5));
+        let mut a = TestRng::<SplitMix>::from_rng(&mut c).unwrap();
+        assert_eq!(a.next_u64(), want);
+        let mut c = TestCRng(Counting(5));
+        let mut b = TestRng::<SplitMix>::from_crypto_rng(&mut c).unwrap();
+        assert_eq!(b.next_u64(), want);
+        println!("plain from crypto, via SeedFromRng and SeedFromCryptoRng: the same");
+    }
+    {
+        let mut c = TestCRng(Counting(5));
+        let mut a = TestCRng::<SplitMix>::from_crypto_rng(&mut c).unwrap();
+        assert_eq!(a.next_u64(), want);
+        println!("crypto from crypto, via SeedFromCryptoRng: the same");
+    }
+    {
+        // Both take trait objects
+        let mut t = TestRng(Counting(5));
+        let d = &mut t as &mut dyn Rng;
+        assert_eq!(TestRng::<SplitMix>::from_rng(d).unwrap().next_u64(), want);
+        let mut c = TestCRng(Counting(5));
+        let d = &mut c as &mut dyn CryptoRng;
+        assert_eq!(TestCRng::<SplitMix>::from_crypto_rng(d).unwrap().next_u64(), want);
+        println!("from &mut dyn Rng and &mut dyn CryptoRng: the same");
+    }
+    {
+        // A failing source: its error is the constructor's
+        let mut f = TestCRng(FailAfter::new(0));
+        let e = TestCRng::<SplitMix>::from_crypto_rng(&mut f).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Unavailable);
+        let mut f = TestRng(FailAfter::new(0));
+        assert!(TestRng::<SplitMix>::from_rng(&mut f).is_err());
+        println!("from an exhausted source: Err({})", e);
+    }
+    {
+        // Generic code asks for a crypto source, and works for either kind
+        let mut master = TestCRng(Counting(5));
+        let plain: Vec<TestRng<SplitMix>> = fork(&mut master, 2).unwrap();
+        let crypto: Vec<TestCRng<SplitMix>> = fork(&mut master, 2).unwrap();
+        assert_eq!(plain[0].0, SplitMix(5));
+        assert_eq!(plain[1].0, SplitMix(6));
+        assert_eq!(crypto[0].0, SplitMix(7));
+        assert_eq!(crypto[1].0, SplitMix(8));
+        let mut f = TestCRng(FailAfter::new(1));
+        assert!(fork::<TestRng<SplitMix>>(&mut f, 2).is_err());
+        println!("fork: children seeded in turn; the first error ends it");
+    }
+}
@@ -0,0 +1,366 @@
+//! Single trait parameterised by a marker kind: `Gen<K: Kind>`
+//! 
+//! Instead of two traits, there is one trait `Gen<K>` where `K` is one of two
+//! zero-sized markers, `Plain` or `Crypto`. Every `Gen<Crypto>` is implicitly
+//! a `Gen<Plain>` (the conversion is safe in that direction); the reverse
+//! needs the explicit `assume_crypto` adaptor. As in marker_only, generators
+//! may be fallible but only `try_next_u32` allows error handling.
+//! 
+//! Methods: `next_u32` is required, and `next_u64`, `next_u128` and `fill`
+//! are provided on top of it (see `bridge.rs`); each `try_` method defaults
+//! to its counterpart, wrapped in `Ok`.
+//! 
+//! Thoughts: this is marker_only with the marker moved into a type
+//! parameter, with the same strengths (one impl block per generator, both
+//! object types). Code can be generic over the kind, but nothing here
+//! needed that. The blanket `Gen<Plain>` impl costs what
+//! separate_implicit_Rng3's do: no dual types, `&mut R` forwarding only
+//! `Gen<Crypto>`, and `c.next_u32()` ambiguous on a crypto generator.
+//! Kind-parameterisation does not beat the two-trait approaches.
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_dual_impl kind_param.rs
+//!     rustc --cfg fail_plain_as_crypto kind_param.rs
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+/// Kind of generator. Only `Plain` and `Crypto` exist.
+pub trait Kind {}
+
+/// Marker: a generator suitable for general use.
+#[derive(Debug)]
+pub enum Plain {}
+
+/// Marker: a generator which is secure when correctly initialised.
+#[derive(Debug)]
+pub enum Crypto {}
+
+impl Kind for Plain {}
+impl Kind for Crypto {}
+
+pub trait Gen<K: Kind> {
+    /// Generate a random number. Panics on error.
+    fn next_u32(&mut self) -> u32;
+    
+    /// Generate a random `u64`. Panics on error.
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    /// Generate a random `u128`. Panics on error.
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    /// Fill `dest` with random bytes. Panics on error.
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+    
+    /// Generate a random number, reporting errors.
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        Ok(self.next_u32())
+    }
+    
+    /// Generate a random `u64`, reporting errors.
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        Ok(self.next_u64())
+    }
+    
+    /// Generate a random `u128`, reporting errors.
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        Ok(self.next_u128())
+    }
+    
+    /// Fill `dest` with random bytes, reporting errors.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill(dest);
+        Ok(())
+    }
+}
+
+// ——— impls ———
+
+// Conversion: any crypto generator is also a plain one.
+impl<R: Gen<Crypto>+?Sized> Gen<Plain> for R {
+    fn next_u32(&mut self) -> u32 {
+        Gen::<Crypto>::next_u32(self)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        Gen::<Crypto>::next_u64(self)
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        Gen::<Crypto>::next_u128(self)
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        Gen::<Crypto>::fill(self, dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        Gen::<Crypto>::try_next_u32(self)
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        Gen::<Crypto>::try_next_u64(self)
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        Gen::<Crypto>::try_next_u128(self)
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        Gen::<Crypto>::try_fill(self, dest)
+    }
+}
+
+// Only `Gen<Crypto>` can be forwarded; forwarding `Gen<Plain>` would conflict
+// with the above for `&mut CR`.
+impl<R: Gen<Crypto>+?Sized> Gen<Crypto> for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        (**self).try_fill(dest)
+    }
+}
+
+impl<R: Gen<Crypto>+?Sized> Gen<Crypto> for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        (**self).try_fill(dest)
+    }
+}
+
+// ——— adaptor ———
+
+// Given `rng` of type `T` where `T: Gen<Plain>`, treat it as crypto. This is
+// a claim by the user, hence the name.
+fn assume_crypto<'a, R: Gen<Plain>+?Sized+'a>(rng: &'a mut R) -> AssumeCrypto<'a, R> {
+    AssumeCrypto { rng }
+}
+
+struct AssumeCrypto<'a, R: Gen<Plain>+?Sized+'a> {
+    rng: &'a mut R
+}
+
+impl<'a, R: Gen<Plain>+?Sized> Gen<Crypto> for AssumeCrypto<'a, R> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.fill(dest)
+    }
+    
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        self.rng.try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        self.rng.try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        self.rng.try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill(dest)
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, Source, TestCRng, TestRng};
+
+// A non-crypto Rng
+impl<S: Source> Gen<Plain> for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// A CryptoRng
+impl<S: Source> Gen<Crypto> for TestCRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// An RNG supporting both interfaces separately
+#[cfg(fail_dual_impl)]
+mod dual {
+    use super::*;
+    
+    struct TestBothRng(u32);
+    
+    impl Gen<Crypto> for TestBothRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+    }
+    
+    // Error: conflicts with `impl<R: Gen<Crypto>+?Sized> Gen<Plain> for R`
+    impl Gen<Plain> for TestBothRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+    }
+}
+
+// ——— consumers ———
+
+// Generic over the kind
+fn next_pair<K: Kind, R: Gen<K>+?Sized>(rng: &mut R) -> (u32, u32) {
+    (rng.next_u32(), rng.next_u32())
+}
+
+fn gen_key<R: Gen<Crypto>+?Sized>(rng: &mut R) -> u32 {
+    rng.next_u32()
+}
+
+#[cfg(fail_plain_as_crypto)]
+fn plain_as_crypto() {
+    gen_key(&mut TestRng(Constant(1)));
+}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
+    println!("t: {:?} impls Gen<Plain>", t);
+    println!("c: {:?} impls Gen<Crypto>", c);
+    {
+        // Do both kinds support both functions via static dispatch?
+        println!("t, static dispatch, using Gen<Plain>: {:?}", t.next_u32());
+        println!("t, static dispatch, using Gen<Crypto>: {:?}", gen_key(&mut assume_crypto(&mut t)));
+        // Method calls on `c` are ambiguous (two impls); name the kind:
+        println!("c, static dispatch, using Gen<Crypto>: {:?}", Gen::<Crypto>::try_next_u32(&mut c));
+        println!("c, static dispatch, using Gen<Plain>: {:?}", Gen::<Plain>::next_u32(&mut c));
+        println!("c, static dispatch, generic over kind: {:?}", next_pair::<Crypto, _>(&mut c));
+    }
+    {
+        // Can both types be used via Gen<Crypto> with dynamic dispatch?
+        let cr = &mut c as &mut dyn Gen<Crypto>;
+        println!("c, dynamic dispatch, using Gen<Crypto>: {:?}", cr.try_next_u32());
+        let mut ta = assume_crypto(&mut t);
+        let tr = &mut ta as &mut dyn Gen<Crypto>;
+        println!("t, dynamic dispatch, using Gen<Crypto>: {:?}", tr.try_next_u32());
+    }
+    {
+        // Can both types be used via Gen<Plain> with dynamic dispatch?
+        let cr = &mut c as &mut dyn Gen<Plain>;
+        println!("c, dynamic dispatch, using Gen<Plain>: {:?}", cr.next_u32());
+        let tr = &mut t as &mut dyn Gen<Plain>;
+        println!("t, dynamic dispatch, using Gen<Plain>: {:?}", tr.next_u32());
+        // `dyn Gen<Crypto>` is itself `Gen<Plain>`:
+        let cr = &mut c as &mut dyn Gen<Crypto>;
+        println!("c, dynamic dispatch, Gen<Crypto> used as Gen<Plain>: {:?}", Gen::<Plain>::next_u32(cr));
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket Gen<Plain> impl forwards each method
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(Gen::<Plain>::next_u64(&mut c), 2 << 32 | 1);
+        println!("c, provided methods via Gen<Plain>: the same words");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        // No forwarding for `Gen<Plain>`: it would overlap the blanket impl
+        fn use_crng<R: Gen<Crypto>>(mut r: R, how: &str) {
+            println!("c, {}, using Gen<Crypto>: {:?}", how, r.next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn Gen<Crypto>, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn Gen<Crypto>>, "by Box<dyn>");
+    }
+}
@@ -0,0 +1,347 @@
+//! A rand 0.4 bridge: adaptors each way between its `Rng` and three designs
+//! 
+//! Crates written against rand 0.4 take `R: rand::Rng`, which has no error
+//! channel and no crypto marker. Neither direction can be a blanket impl
+//! (`fail_blanket_to_legacy` is an orphan, `fail_blanket_from_legacy`
+//! overlaps), so `extends_Rng`, `extends_CryptoRng` and `raw_infallible`,
+//! each included whole, get a pair of newtypes: `FromLegacy(rng)`, a rand 0.4
+//! generator as the design's `Rng`, and `ToLegacy(rng)`, the reverse.
+//! 
+//! Thoughts: the bridge is small and mechanical; what it can't carry is what
+//! rand 0.4 never had: a crypto marker, and errors, which become panics. Word
+//! order is the subtle part: rand 0.4 builds a `u64` high word first, the
+//! designs low first.
+
+// marker_only.rs, included by the designs' test generators, refers to
+// `::core`, as its own crate root would
+extern crate core;
+extern crate rand04;
+
+use rand04::Rng as LegacyRng;
+
+#[path = "extends_Rng.rs"]
+#[allow(dead_code, unused_imports, bare_trait_objects)]
+mod extends_rng;
+
+#[path = "extends_CryptoRng.rs"]
+#[allow(dead_code, unused_imports, bare_trait_objects)]
+mod extends_cryptorng;
+
+#[path = "raw_infallible.rs"]
+#[allow(dead_code, bare_trait_objects)]
+mod raw_infallible;
+
+// ——— designs ———
+
+mod on_extends_rng {
+    use super::extends_rng::{CryptoRng, Rng};
+    use rand04::{ChaChaRng, OsRng, Rng as LegacyRng};
+    
+    /// A rand 0.4 generator as an `Rng`.
+    pub struct FromLegacy<R: ?Sized>(pub R);
+    
+    impl<R: LegacyRng + ?Sized> Rng for FromLegacy<R> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+        
+        fn fill(&mut self, dest: &mut [u8]) {
+            self.0.fill_bytes(dest)
+        }
+    }
+    
+    // Nothing in rand 0.4 says which generators are crypto: they are named
+    impl CryptoRng for FromLegacy<ChaChaRng> {}
+    impl CryptoRng for FromLegacy<OsRng> {}
+    
+    /// An `Rng` as a rand 0.4 generator; a `CryptoRng` is one too, but no
+    /// longer says so.
+    pub struct ToLegacy<R: ?Sized>(pub R);
+    
+    impl<R: Rng + ?Sized> LegacyRng for ToLegacy<R> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+        
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.0.fill(dest)
+        }
+    }
+}
+
+mod on_extends_cryptorng {
+    use super::extends_cryptorng::{CryptoError, CryptoRng, Rng};
+    use rand04::Rng as LegacyRng;
+    
+    /// A rand 0.4 generator as an `Rng`, and so as a `CryptoRng`, whatever
+    /// the generator.
+    pub struct FromLegacy<R: ?Sized>(pub R);
+    
+    impl<R: LegacyRng + ?Sized> CryptoRng for FromLegacy<R> {
+        fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+            Ok(self.0.next_u32())
+        }
+        
+        fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+            Ok(self.0.next_u64())
+        }
+        
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+            self.0.fill_bytes(dest);
+            Ok(())
+        }
+    }
+    
+    impl<R: LegacyRng + ?Sized> Rng for FromLegacy<R> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+        
+        fn fill(&mut self, dest: &mut [u8]) {
+            self.0.fill_bytes(dest)
+        }
+    }
+    
+    /// A `CryptoRng`, an `Rng` included, as a rand 0.4 generator; panics
+    /// where it fails.
+    pub struct ToLegacy<R: ?Sized>(pub R);
+    
+    impl<R: CryptoRng + ?Sized> LegacyRng for ToLegacy<R> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.try_next_u32().unwrap()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            self.0.try_next_u64().unwrap()
+        }
+        
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.0.try_fill(dest).unwrap()
+        }
+    }
+}
+
+mod on_raw_infallible {
+    use super::raw_infallible::{CryptoRng, Error, RawRng, Rng};
+    use rand04::{ChaChaRng, OsRng, Rng as LegacyRng};
+    use std::convert::Infallible;
+    
+    /// A rand 0.4 generator as an `Rng`, so `RawRng<Infallible>` and
+    /// `RawRng<Error>` too.
+    pub struct FromLegacy<R: ?Sized>(pub R);
+    
+    impl<R: LegacyRng + ?Sized> Rng for FromLegacy<R> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.next_u32()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            self.0.next_u64()
+        }
+        
+        fn fill(&mut self, dest: &mut [u8]) {
+            self.0.fill_bytes(dest)
+        }
+    }
+    
+    // As for `extends_Rng`, named; `CryptoRng<Error>` follows
+    impl CryptoRng<Infallible> for FromLegacy<ChaChaRng> {}
+    impl CryptoRng<Infallible> for FromLegacy<OsRng> {}
+    
+    /// A `RawRng<Error>`, a plain generator included, as a rand 0.4
+    /// generator; panics where it fails.
+    pub struct ToLegacy<R: ?Sized>(pub R);
+    
+    impl<R: RawRng<Error> + ?Sized> LegacyRng for ToLegacy<R> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.try_next_u32().unwrap()
+        }
+        
+        fn next_u64(&mut self) -> u64 {
+            self.0.try_next_u64().unwrap()
+        }
+        
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.0.try_fill(dest).unwrap()
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+/// A rand 0.4 generator of words 0, 1, 2..., from `next_u32` alone: the
+/// words of `TestRng(Counting(0))`, as rand 0.4 would have it.
+pub struct Counting04(pub u32);
+
+impl LegacyRng for Counting04 {
+    fn next_u32(&mut self) -> u32 {
+        let x = self.0;
+        self.0 = x.wrapping_add(1);
+        x
+    }
+}
+
+// ——— consumers ———
+
+/// Dice, as a crate written against rand 0.4 rolls them.
+fn legacy_dice<R: LegacyRng>(rng: &mut R, n: usize) -> Vec<u32> {
+    (0..n).map(|_| rng.gen_range(1, 7)).collect()
+}
+
+/// A key, as a crate written against `extends_Rng` makes one.
+fn key<R: extends_rng::CryptoRng + ?Sized>(rng: &mut R) -> [u8; 16] {
+    let mut key = [0; 16];
+    rng.try_fill(&mut key).unwrap();
+    key
+}
+
+// ——— compile-fail cases ———
+
+// A foreign trait for every `R`: an orphan, whatever the bound.
+#[cfg(fail_blanket_to_legacy)]
+impl<R: extends_rng::Rng> LegacyRng for R {
+    fn next_u32(&mut self) -> u32 {
+        extends_rng::Rng::next_u32(self)
+    }
+}
+
+// rand 0.4 implements its `Rng` for `&mut R`, so this overlaps the design's
+// own impl for `&mut R`.
+#[cfg(fail_blanket_from_legacy)]
+impl<R: LegacyRng + ?Sized> extends_rng::Rng for R {
+    fn next_u32(&mut self) -> u32 {
+        LegacyRng::next_u32(self)
+    }
+}
+
+// ——— usage ———
+
+pub fn main() {
+    use rand04::{ChaChaRng, XorShiftRng};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    
+    {
+        use self::extends_rng::test_rngs::{Counting, SplitMix, TestCRng, TestRng};
+        use self::extends_rng::Rng;
+        use self::on_extends_rng::ToLegacy;
+        
+        // The design's words, its `u64`s and its bytes, through rand 0.4
+        let mut t = ToLegacy(TestRng(Counting(0)));
+        assert_eq!(t.next_u32(), 0);
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        let mut bytes = [0; 12];
+        t.fill_bytes(&mut bytes);
+        let mut want = [0; 12];
+        TestRng(Counting(3)).fill(&mut want);
+        assert_eq!(bytes, want);
+        
+        // A rand 0.4 consumer, over a generator or lent one
+        let dice = legacy_dice(&mut ToLegacy(TestRng(SplitMix(1))), 6);
+        assert!(dice.iter().all(|d| (1..=6).contains(d)));
+        let mut c = TestCRng(SplitMix(1));
+        assert_eq!(legacy_dice(&mut ToLegacy(&mut c), 6), dice);
+        println!("extends_Rng, ToLegacy: the design's words; rand 0.4 dice {:?}", dice);
+    }
+    {
+        use self::extends_rng::test_rngs::{Counting, TestRng};
+        use self::extends_rng::Rng;
+        use self::on_extends_rng::{FromLegacy, ToLegacy};
+        
+        // rand 0.4's words, its `u64`s and its bytes, through the design;
+        // `next_u128` is two of its `u64`s, low first
+        let mut x = XorShiftRng::new_unseeded();
+        let mut f = FromLegacy(x.clone());
+        assert_eq!(f.next_u32(), x.next_u32());
+        assert_eq!(f.next_u64(), x.next_u64());
+        let (lo, hi) = (x.next_u64(), x.next_u64());
+        assert_eq!(f.next_u128(), (hi as u128) << 64 | lo as u128);
+        let mut bytes = [0; 12];
+        let mut want = [0; 12];
+        f.fill(&mut bytes);
+        x.fill_bytes(&mut want);
+        assert_eq!(bytes, want);
+        
+        // And back: the round trip changes nothing
+        let mut r = ToLegacy(FromLegacy(x.clone()));
+        assert_eq!((r.next_u32(), r.next_u64()), (x.next_u32(), x.next_u64()));
+        
+        // The same words, high first in rand 0.4 and low first here
+        assert_eq!(FromLegacy(Counting04(0)).next_u64(), 1);
+        assert_eq!(TestRng(Counting(0)).next_u64(), 1 << 32);
+        println!("extends_Rng, FromLegacy: XorShiftRng's words; words 0, 1 make 1, not 1 << 32");
+    }
+    {
+        use self::on_extends_rng::FromLegacy;
+        
+        // `ChaChaRng` is crypto because the bridge says so; `XorShiftRng`
+        // isn't, `key(&mut FromLegacy(XorShiftRng::new_unseeded()))` not
+        // compiling. (`ChaChaRng` is `Copy`: `FromLegacy(c)` starts where
+        // `c` does.)
+        let mut c = ChaChaRng::new_unseeded();
+        let k = key(&mut FromLegacy(c));
+        let mut want = [0; 16];
+        c.fill_bytes(&mut want);
+        assert_eq!(k, want);
+        println!("extends_Rng: FromLegacy<ChaChaRng> is a CryptoRng, as named");
+    }
+    {
+        use self::extends_cryptorng::test_rngs::{FailAfter, TestCRng, TestRng};
+        use self::extends_cryptorng::CryptoRng;
+        use self::on_extends_cryptorng::{FromLegacy, ToLegacy};
+        
+        // Every rand 0.4 generator is a `CryptoRng` here, and never fails
+        fn crypto_word<CR: CryptoRng + ?Sized>(rng: &mut CR) -> u64 {
+            rng.try_next_u64().unwrap()
+        }
+        let mut x = XorShiftRng::new_unseeded();
+        assert_eq!(crypto_word(&mut FromLegacy(x.clone())), x.next_u64());
+        
+        // A failure is a panic in the adaptor, plain generator or crypto.
+        // (The panics print: the hook is global, so it is left alone.)
+        let mut t = ToLegacy(TestRng(FailAfter::new(2)));
+        assert_eq!((t.next_u32(), t.next_u32()), (0, 1));
+        assert!(catch_unwind(AssertUnwindSafe(|| t.next_u32())).is_err());
+        let mut c = ToLegacy(TestCRng(FailAfter::new(2)));
+        assert_eq!(c.next_u64(), 1 << 32);
+        assert!(catch_unwind(AssertUnwindSafe(|| c.fill_bytes(&mut [0; 4]))).is_err());
+        println!("extends_CryptoRng: FromLegacy<XorShiftRng> is a CryptoRng; ToLegacy panics");
+    }
+    {
+        use self::on_raw_infallible::{FromLegacy, ToLegacy};
+        use self::raw_infallible::test_rngs::{FailAfter, SplitMix, TestFCRng, TestRng};
+        use self::raw_infallible::{CryptoRng, Error, RawRng};
+        
+        // A rand 0.4 generator is `RawRng<Error>` as any plain one is
+        fn raw_word<R: RawRng<Error> + ?Sized>(rng: &mut R) -> Result<u64, Error> {
+            rng.try_next_u64()
+        }
+        fn crypto_word<R: CryptoRng<Error> + ?Sized>(rng: &mut R) -> Result<u64, Error> {
+            rng.try_next_u64()
+        }
+        let mut x = XorShiftRng::new_unseeded();
+        assert_eq!(raw_word(&mut FromLegacy(x.clone())).unwrap(), x.next_u64());
+        let mut c = ChaChaRng::new_unseeded();
+        assert_eq!(crypto_word(&mut FromLegacy(c)).unwrap(), c.next_u64());
+        
+        // Plain generators go through the same unwrap, which never fires
+        let dice = legacy_dice(&mut ToLegacy(TestRng(SplitMix(1))), 6);
+        assert!(dice.iter().all(|d| (1..=6).contains(d)));
+        let mut f = ToLegacy(TestFCRng(FailAfter::new(3)));
+        assert_eq!(f.next_u64(), 1 << 32);
+        assert_eq!(f.next_u32(), 2);
+        assert!(catch_unwind(AssertUnwindSafe(|| f.next_u32())).is_err());
+        println!("raw_infallible: FromLegacy<ChaChaRng> is a CryptoRng<Error>; ToLegacy panics");
+    }
+}
@@ -0,0 +1,365 @@
+//! Lending block output: `next_block()` borrows the generator's buffer
+//! 
+//! A block generator (ChaCha and the like) produces a block of words into its
+//! own buffer, and `fill` then copies them into the caller's slice: every
+//! word is written twice before it's used. With a generic associated type,
+//! `LendingRng::next_block()` can instead hand out the buffer itself:
+//! 
+//! *   `BlockRng<C>`, the buffering wrapper for block cores, lends its unread
+//!     words as `&[u32]`, marking them read
+//! *   a generator with no buffer returns its words by value (`[u32; 2]` for
+//!     `SplitMix32`), which a plain `-> &[u32]` method couldn't do
+//! 
+//! Either way the words are exactly those `next_u32` calls would give, so the
+//! two can be mixed. `TryLendingRng` is the fallible form, over a core which
+//! can fail per block (as in batched_fallibility); a failed refill loses
+//! nothing, and the next call continues the stream.
+//! 
+//! Run with:
+//! 
+//!     rustc lending_block.rs && ./lending_block
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_erased_object lending_block.rs
+//!     rustc --cfg fail_retry_borrow lending_block.rs
+//! 
+//! Thoughts: it avoids the second copy only for consumers which use the
+//! words in place, as `xor_keystream` does; `fill` itself gains nothing. A
+//! trait with a generic associated type can't be an object, the block
+//! borrows the generator until dropped, and blocks can't be an `Iterator`.
+//! With errors, a wrapper which retries and returns the first attempt's
+//! block is rejected by today's borrow checker (`fail_retry_borrow`, a
+//! Polonius case), so `next_or_reseed` retries first and borrows once.
+
+use std::cmp::min;
+
+// ——— traits ———
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+}
+
+pub trait LendingRng: Rng {
+    /// Words owned by the generator, or by the block.
+    type Block<'a>: AsRef<[u32]> where Self: 'a;
+    
+    /// The next words, at least one: those as many `next_u32` calls would
+    /// give.
+    fn next_block(&mut self) -> Self::Block<'_>;
+}
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+pub trait TryLendingRng {
+    type Block<'a>: AsRef<[u32]> where Self: 'a;
+    
+    /// The next words, at least one, or the error of the refill.
+    fn try_next_block(&mut self) -> Result<Self::Block<'_>, Error>;
+}
+
+/// A generator producing a block of words at a time.
+pub trait BlockCore {
+    type Results: AsRef<[u32]> + AsMut<[u32]> + Default;
+    
+    fn generate(&mut self, results: &mut Self::Results);
+}
+
+/// A block core which can fail.
+pub trait TryBlockCore {
+    type Results: AsRef<[u32]> + AsMut<[u32]> + Default;
+    
+    fn try_generate(&mut self, results: &mut Self::Results) -> Result<(), Error>;
+}
+
+// ——— impls ———
+
+/// Buffers a `BlockCore`'s output.
+pub struct BlockRng<C: BlockCore> {
+    core: C,
+    results: C::Results,
+    index: usize,
+}
+
+impl<C: BlockCore> BlockRng<C> {
+    pub fn new(core: C) -> Self {
+        let results = C::Results::default();
+        let index = results.as_ref().len();
+        BlockRng { core, results, index }
+    }
+    
+    fn refill_if_drained(&mut self) {
+        if self.index == self.results.as_ref().len() {
+            self.core.generate(&mut self.results);
+            self.index = 0;
+        }
+    }
+    
+    /// Copies words into `dest`: the buffered way, for comparison.
+    pub fn fill_u32(&mut self, dest: &mut [u32]) {
+        let mut filled = 0;
+        while filled < dest.len() {
+            self.refill_if_drained();
+            let n = min(dest.len() - filled, self.results.as_ref().len() - self.index);
+            dest[filled..filled + n].copy_from_slice(&self.results.as_ref()[self.index..self.index + n]);
+            self.index += n;
+            filled += n;
+        }
+    }
+}
+
+impl<C: BlockCore> Rng for BlockRng<C> {
+    fn next_u32(&mut self) -> u32 {
+        self.refill_if_drained();
+        let x = self.results.as_ref()[self.index];
+        self.index += 1;
+        x
+    }
+}
+
+impl<C: BlockCore> LendingRng for BlockRng<C> {
+    type Block<'a> = &'a [u32] where C: 'a;
+    
+    fn next_block(&mut self) -> &[u32] {
+        self.refill_if_drained();
+        let start = self.index;
+        self.index = self.results.as_ref().len();
+        &self.results.as_ref()[start..]
+    }
+}
+
+/// Buffers a `TryBlockCore`'s output, with errors only at refills.
+pub struct TryBlockRng<C: TryBlockCore> {
+    core: C,
+    results: C::Results,
+    index: usize,
+}
+
+impl<C: TryBlockCore> TryBlockRng<C> {
+    pub fn new(core: C) -> Self {
+        let results = C::Results::default();
+        let index = results.as_ref().len();
+        TryBlockRng { core, results, index }
+    }
+    
+    pub fn core_mut(&mut self) -> &mut C {
+        &mut self.core
+    }
+    
+    /// Refills if drained; on error the buffer stays drained.
+    fn try_refill_if_drained(&mut self) -> Result<(), Error> {
+        if self.index == self.results.as_ref().len() {
+            self.core.try_generate(&mut self.results)?;
+            self.index = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<C: TryBlockCore> TryLendingRng for TryBlockRng<C> {
+    type Block<'a> = &'a [u32] where C: 'a;
+    
+    fn try_next_block(&mut self) -> Result<&[u32], Error> {
+        self.try_refill_if_drained()?;
+        let start = self.index;
+        self.index = self.results.as_ref().len();
+        Ok(&self.results.as_ref()[start..])
+    }
+}
+
+#[cfg(fail_erased_object)]
+// Error: `LendingRng` is not dyn compatible, for its generic associated type
+fn erased(rng: &mut dyn LendingRng<Block<'static> = &'static [u32]>) {}
+
+// ——— test RNGs ———
+
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const GOLDEN: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// SplitMix64, as two `u32` words per step, eight steps at a time: a block
+/// core.
+pub struct SplitMixCore(pub u64);
+
+impl BlockCore for SplitMixCore {
+    type Results = [u32; 16];
+    
+    fn generate(&mut self, results: &mut [u32; 16]) {
+        for pair in results.chunks_mut(2) {
+            self.0 = self.0.wrapping_add(GOLDEN);
+            let x = mix(self.0);
+            pair[0] = x as u32;
+            pair[1] = (x >> 32) as u32;
+        }
+    }
+}
+
+/// SplitMix64 one step at a time, with no buffer: its blocks are owned.
+pub struct SplitMix(pub u64);
+
+impl SplitMix {
+    fn step(&mut self) -> [u32; 2] {
+        self.0 = self.0.wrapping_add(GOLDEN);
+        let x = mix(self.0);
+        [x as u32, (x >> 32) as u32]
+    }
+}
+
+/// Holds back the high word of a step for the next call.
+pub struct SplitMix32 {
+    inner: SplitMix,
+    high: Option<u32>,
+}
+
+impl Rng for SplitMix32 {
+    fn next_u32(&mut self) -> u32 {
+        match self.high.take() {
+            Some(x) => x,
+            None => {
+                let [lo, hi] = self.inner.step();
+                self.high = Some(hi);
+                lo
+            }
+        }
+    }
+}
+
+impl LendingRng for SplitMix32 {
+    type Block<'a> = [u32; 2];
+    
+    // Only from a whole step; `next_u32` covers the half-read one
+    fn next_block(&mut self) -> [u32; 2] {
+        match self.high.take() {
+            Some(x) => [x, self.next_u32()],
+            None => self.inner.step(),
+        }
+    }
+}
+
+/// `SplitMixCore`, failing on refills while `failing` is set.
+pub struct FlakyCore {
+    inner: SplitMixCore,
+    pub failing: bool,
+}
+
+impl TryBlockCore for FlakyCore {
+    type Results = [u32; 16];
+    
+    fn try_generate(&mut self, results: &mut [u32; 16]) -> Result<(), Error> {
+        if self.failing {
+            return Err(Error::new(ErrorKind::Transient, "flaky core failed"));
+        }
+        self.inner.generate(results);
+        Ok(())
+    }
+}
+
+// ——— consumers ———
+
+/// XORs `data` with the keystream, reading each block in place.
+fn xor_keystream<R: LendingRng>(rng: &mut R, data: &mut [u8]) {
+    let mut done = 0;
+    while done < data.len() {
+        let block = rng.next_block();
+        for word in block.as_ref() {
+            for (d, k) in data[done..].iter_mut().zip(word.to_le_bytes().iter()) {
+                *d ^= k;
+            }
+            done = min(done + 4, data.len());
+        }
+    }
+}
+
+/// The same over `fill_u32`: the keystream needs a buffer of its own.
+fn xor_keystream_via_fill<C: BlockCore>(rng: &mut BlockRng<C>, data: &mut [u8]) {
+    let mut keystream = vec![0u32; data.len().div_ceil(4)];
+    rng.fill_u32(&mut keystream);
+    for (chunk, word) in data.chunks_mut(4).zip(keystream.iter()) {
+        for (d, k) in chunk.iter_mut().zip(word.to_le_bytes().iter()) {
+            *d ^= k;
+        }
+    }
+}
+
+/// Reseeds a failing core and retries, then borrows the block: the order
+/// the borrow checker accepts.
+fn next_or_reseed(rng: &mut TryBlockRng<FlakyCore>) -> Result<&[u32], Error> {
+    if rng.try_refill_if_drained().is_err() {
+        rng.core_mut().failing = false;
+    }
+    rng.try_next_block()
+}
+
+#[cfg(fail_retry_borrow)]
+fn next_or_reseed_lending(rng: &mut TryBlockRng<FlakyCore>) -> Result<&[u32], Error> {
+    match rng.try_next_block() {
+        Ok(block) => return Ok(block),
+        Err(_) => {}
+    }
+    // Error: `*rng` is still borrowed by the block returned above
+    rng.core_mut().failing = false;
+    rng.try_next_block()
+}
+
+// ——— usage ———
+
+pub fn main() {
+    // Blocks and words give the same stream, block generator or not, and can
+    // be mixed
+    for mixed in 0..3 {
+        let mut lent = BlockRng::new(SplitMixCore(0x5EED));
+        let mut owned = SplitMix32 { inner: SplitMix(0x5EED), high: None };
+        let mut words = BlockRng::new(SplitMixCore(0x5EED));
+        for _ in 0..mixed {
+            let x = lent.next_u32();
+            assert_eq!(x, words.next_u32());
+            assert_eq!(x, owned.next_u32());
+        }
+        for _ in 0..5 {
+            let a: Vec<u32> = lent.next_block().to_vec();
+            let b: Vec<u32> = (0..a.len()).map(|_| words.next_u32()).collect();
+            assert_eq!(a, b);
+        }
+        let mut got = vec![];
+        while got.len() < 40 {
+            got.extend_from_slice(owned.next_block().as_ref());
+        }
+        let mut check = BlockRng::new(SplitMixCore(0x5EED));
+        let want: Vec<u32> = (0..mixed + got.len()).map(|_| check.next_u32()).collect();
+        assert_eq!(&got[..], &want[mixed..]);
+    }
+    println!("next_block() matches next_u32() calls, lent or owned, mixed or not");
+    
+    {
+        // In place, or through a buffer: the same keystream
+        let mut a = *b"attack at dawn, with the whole block";
+        let mut b = a;
+        xor_keystream(&mut BlockRng::new(SplitMixCore(1)), &mut a);
+        xor_keystream_via_fill(&mut BlockRng::new(SplitMixCore(1)), &mut b);
+        assert_eq!(a, b);
+        // And twice is the identity
+        xor_keystream(&mut BlockRng::new(SplitMixCore(1)), &mut a);
+        assert_eq!(&a, b"attack at dawn, with the whole block");
+        println!("xor_keystream: in place, same as via fill_u32");
+    }
+    {
+        // A failed refill loses nothing: the stream continues after it
+        let core = FlakyCore { inner: SplitMixCore(9), failing: false };
+        let mut f = TryBlockRng::new(core);
+        let mut check = BlockRng::new(SplitMixCore(9));
+        let first = f.try_next_block().unwrap().to_vec();
+        assert_eq!(first, (0..16).map(|_| check.next_u32()).collect::<Vec<_>>());
+        f.core_mut().failing = true;
+        assert_eq!(f.try_next_block().unwrap_err().kind, ErrorKind::Transient);
+        let block = next_or_reseed(&mut f).unwrap().to_vec();
+        assert_eq!(block, (0..16).map(|_| check.next_u32()).collect::<Vec<_>>());
+        println!("f, failing once: Err, then reseeded and continued");
+    }
+}
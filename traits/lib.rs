@@ -0,0 +1,167 @@
+//! The trait designs, compiled together
+//! 
+//! Each design is still a standalone program (see `README.md`); this crate
+//! compiles them all as modules, one per variant, so that they can refer to
+//! each other and be checked in one run:
+//! 
+//!     cargo test
+//!     cargo +nightly test --features nightly
+//! 
+//! where each test runs one variant's `main`. The variants using unstable
+//! features (`auto_trait_cryptorng`, `negative_impls`, `specialization`, and
+//! the `raw_*` designs but `raw_default` and `raw_infallible`) need the
+//! `nightly` feature, `serializable` the `serde` feature, `parallel` the
+//! `rayon` feature and `legacy` the `rand04` feature (rand 0.4, under that
+//! name). The compile-fail checks still need `rustc --cfg` on the
+//! single file, as listed at the top of each, but for object safety:
+//! `tests/object_safety.rs` makes each design's traits into objects, and
+//! checks that the traits said to make none don't, and
+//! `tests/impl_conflicts.rs` that the designs giving no type both roles, or
+//! refusing plain generators as crypto, do.
+//! 
+//! `prelude` exports the `marker_only` design, the one most programs outside
+//! `traits/` build on. Those still include it by `#[path]`, each a standalone
+//! program, but `cargo test` runs them as well (the `[[test]]` targets in
+//! `Cargo.toml`): the distribution, generator, entropy and error checks.
+//! 
+//! The variants print, so they need the `std` feature (on by default).
+//! Without it the crate is `no_std`, with only the files the variants share
+//! (`block`, `bridge`, `chacha`, `error`, `os`, `pcg`, `seeding`); `cargo
+//! run --bin no_std` checks that each design builds on `core` alone:
+//! 
+//!     cargo build --no-default-features
+//! 
+//! Thoughts: the variants deliberately share names (`Rng`, `CryptoRng`,
+//! `TestRng`...) with different meanings, so nothing but the prelude is
+//! glob-exported; compare designs through their module paths.
+
+#![cfg_attr(feature = "nightly", feature(
+    auto_traits,
+    negative_bounds,
+    negative_impls,
+    never_type,
+    rustc_attrs,
+    specialization,
+    with_negative_coherence,
+))]
+#![cfg_attr(feature = "nightly", allow(incomplete_features, internal_features))]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// marker_only.rs refers to `::core`, as its own crate root would
+#[cfg(feature = "std")]
+extern crate core;
+// The shared files name `std::fmt` and the like, which `core` has too
+#[cfg(not(feature = "std"))]
+extern crate core as std;
+// As `serializable.rs`, `parallel.rs` and `legacy.rs` declare them at their own
+// crate root
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "rand04")]
+extern crate rand04;
+
+/// Designs relating separate `Rng` and `CryptoRng` traits.
+macro_rules! variants {
+    ($($(#[$attr:meta])* $name:ident = $file:expr;)*) => {
+        $(
+            $(#[$attr])*
+            #[cfg(feature = "std")]
+            #[path = $file]
+            #[allow(dead_code, deprecated, unused_attributes, bare_trait_objects)]
+            // Each includes the shared files itself, as its own crate root
+            #[allow(clippy::duplicate_mod)]
+            pub mod $name;
+        )*
+        
+        #[cfg(all(test, feature = "std"))]
+        mod tests {
+            $(
+                $(#[$attr])*
+                #[test]
+                fn $name() {
+                    super::$name::main();
+                }
+            )*
+        }
+    };
+}
+
+variants! {
+    assoc_error = "assoc_error.rs";
+    async_rng = "async_rng.rs";
+    #[cfg(feature = "nightly")]
+    auto_trait_cryptorng = "auto_trait_CryptoRng.rs";
+    batched_fallibility = "batched_fallibility.rs";
+    block_output = "block_output.rs";
+    capabilities = "capabilities.rs";
+    cloneable = "cloneable.rs";
+    const_crypto = "const_crypto.rs";
+    downcast = "downcast.rs";
+    extends_cryptorng = "extends_CryptoRng.rs";
+    extends_cryptorng2 = "extends_CryptoRng2.rs";
+    extends_cryptorng3 = "extends_CryptoRng3.rs";
+    extends_rng = "extends_Rng.rs";
+    extends_rng2 = "extends_Rng2.rs";
+    extends_rng_infallible = "extends_Rng_infallible.rs";
+    fallibility_param = "fallibility_param.rs";
+    from_rng = "from_rng.rs";
+    kind_param = "kind_param.rs";
+    #[cfg(feature = "rand04")]
+    legacy = "legacy.rs";
+    lending_block = "lending_block.rs";
+    locked = "locked.rs";
+    marker_only = "marker_only.rs";
+    #[cfg(feature = "nightly")]
+    negative_impls = "negative_impls.rs";
+    #[cfg(feature = "rayon")]
+    parallel = "parallel.rs";
+    raw_default = "raw_default.rs";
+    #[cfg(feature = "nightly")]
+    raw_explicit = "raw_explicit.rs";
+    raw_infallible = "raw_infallible.rs";
+    #[cfg(feature = "nightly")]
+    raw_newtype = "raw_newtype.rs";
+    #[cfg(feature = "nightly")]
+    raw_rng = "raw_rng.rs";
+    separate_explicit_rng = "separate_explicit_Rng.rs";
+    separate_implicit_rng = "separate_implicit_Rng.rs";
+    separate_implicit_rng2 = "separate_implicit_Rng2.rs";
+    separate_implicit_rng3 = "separate_implicit_Rng3.rs";
+    #[cfg(feature = "serde")]
+    serializable = "serializable.rs";
+    shared_ref = "shared_ref.rs";
+    #[cfg(feature = "nightly")]
+    specialization = "specialization.rs";
+    streams = "streams.rs";
+}
+
+/// The `marker_only` design: the traits and helpers shared by the rest of
+/// the tree.
+#[cfg(feature = "std")]
+pub mod prelude {
+    pub use marker_only::{fill_slice, impl_fill_from_u64, impl_fill_uninit_from_u64, AsByteSliceMut};
+    pub use marker_only::{impl_next_u128_from_fill, impl_next_u32_from_fill, impl_next_u64_from_fill};
+    pub use marker_only::{impl_next_u128_via_u64, impl_next_u32_via_u64, impl_next_u64_via_u32};
+    pub use marker_only::{f64_closed_open01, f64_closed_open01_exp, f64_open_closed01, f64_open_closed01_exp};
+    pub use marker_only::{fill_when_ready, CryptoRng, Error, ErrorKind, Rng, FILL_RETRIES, MAX_BACKOFF};
+}
+
+// The files shared by the variants, which need only `core`
+#[path = "block.rs"]
+pub mod block;
+#[path = "bridge.rs"]
+pub mod bridge;
+#[path = "chacha.rs"]
+pub mod chacha;
+#[path = "error.rs"]
+pub mod error;
+#[path = "os.rs"]
+pub mod os;
+#[path = "pcg.rs"]
+pub mod pcg;
+#[path = "seeding.rs"]
+pub mod seeding;
@@ -0,0 +1,351 @@
+//! `LockedRng`: one generator shared between threads, behind a `Mutex`
+//! 
+//! `shared_ref` makes sharing a property of the trait; this keeps the traits
+//! at `&mut self` and makes it a wrapper. `LockedRng<R>` holds a `Mutex<R>`:
+//! `Rng for LockedRng<R>` delegates through `get_mut` without locking, and
+//! `Rng for &LockedRng<R>` locks for each call (once for a whole `fill`), as
+//! does its `SharedRng`, `shared_ref`'s `&self` trait. A poisoned lock is
+//! taken over, the generator being in some valid state. The traits have
+//! neither `Send` nor `Sync` among their bounds.
+//! 
+//! Run with:
+//! 
+//!     rustc locked.rs && ./locked
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_locked_handle locked.rs
+//!     rustc --cfg fail_send_supertrait locked.rs
+//! 
+//! Thoughts: the traits should require neither. `LockedRng<R>` is `Sync`
+//! when `R` is `Send`, and a `Send` supertrait would shut out
+//! `thread_rng()`'s handle (`fail_send_supertrait`), so the bound goes where
+//! the thread is. Threads drawing from one `LockedRng` share one stream,
+//! each word once, at the cost of a contended lock per call: it suits one
+//! replayable stream, not throughput.
+
+#![allow(unused)]
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+    
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+pub trait CryptoRng: Rng {}
+
+/// `shared_ref`'s trait: the same methods, over `&self`.
+pub trait SharedRng {
+    fn next_u64(&self) -> u64;
+    
+    fn fill(&self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+// ——— impls ———
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {}
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {}
+
+#[cfg(fail_send_supertrait)]
+mod send_supertrait {
+    use super::test_rngs::SplitMix;
+    use super::Handle;
+    
+    pub trait Rng: Send {
+        fn next_u64(&mut self) -> u64;
+    }
+    
+    // Error: `Rc<RefCell<SplitMix>>` cannot be sent between threads safely
+    impl Rng for Handle {
+        fn next_u64(&mut self) -> u64 {
+            super::Rng::next_u64(self)
+        }
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Counting, Source, SplitMix, TestCRng, TestRng};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u64(&mut self) -> u64 {
+        self.0.word()
+    }
+}
+
+// A CryptoRng
+impl<S: Source> Rng for TestCRng<S> {
+    fn next_u64(&mut self) -> u64 {
+        self.0.word()
+    }
+}
+
+impl<S: Source> CryptoRng for TestCRng<S> {}
+
+/// A handle to a generator shared within one thread, as `thread_rng()`'s:
+/// neither `Send` nor `Sync`.
+#[derive(Debug, Clone)]
+pub struct Handle(Rc<RefCell<SplitMix>>);
+
+impl Rng for Handle {
+    fn next_u64(&mut self) -> u64 {
+        self.0.borrow_mut().word()
+    }
+}
+
+// ——— LockedRng ———
+
+/// A generator any number of threads may share, one call at a time.
+#[derive(Debug, Default)]
+pub struct LockedRng<R>(Mutex<R>);
+
+impl<R> LockedRng<R> {
+    pub fn new(rng: R) -> Self {
+        LockedRng(Mutex::new(rng))
+    }
+    
+    /// The generator, locked until the guard is dropped: several draws as
+    /// one run of the stream.
+    pub fn lock(&self) -> MutexGuard<'_, R> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    pub fn get_mut(&mut self) -> &mut R {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    pub fn into_inner(self) -> R {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Exclusive already: no lock
+impl<R: Rng> Rng for LockedRng<R> {
+    fn next_u64(&mut self) -> u64 {
+        self.get_mut().next_u64()
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        self.get_mut().next_u32()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.get_mut().next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.get_mut().fill(dest)
+    }
+}
+
+impl<R: CryptoRng> CryptoRng for LockedRng<R> {}
+
+// Shared: a lock per call
+impl<R: Rng> Rng for &LockedRng<R> {
+    fn next_u64(&mut self) -> u64 {
+        self.lock().next_u64()
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        self.lock().next_u32()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.lock().next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.lock().fill(dest)
+    }
+}
+
+impl<R: CryptoRng> CryptoRng for &LockedRng<R> {}
+
+impl<R: Rng> SharedRng for LockedRng<R> {
+    fn next_u64(&self) -> u64 {
+        self.lock().next_u64()
+    }
+    
+    fn fill(&self, dest: &mut [u8]) {
+        self.lock().fill(dest)
+    }
+}
+
+#[cfg(fail_locked_handle)]
+// Error: `Rc<RefCell<SplitMix>>` cannot be sent between threads safely, so
+// `LockedRng<Handle>` isn't `Sync`
+fn locked_handle() {
+    let rng = Arc::new(LockedRng::new(Handle(Rc::new(RefCell::new(SplitMix(1))))));
+    let other = rng.clone();
+    thread::spawn(move || (&*other).next_u64());
+}
+
+// ——— consumers ———
+
+fn key<R: CryptoRng + ?Sized>(rng: &mut R) -> [u8; 16] {
+    let mut key = [0; 16];
+    rng.fill(&mut key);
+    key
+}
+
+/// Draws on another thread, which is where the `Send` bound belongs.
+fn draw_elsewhere<R: Rng + Send + 'static>(mut rng: R) -> u64 {
+    thread::spawn(move || rng.next_u64()).join().unwrap()
+}
+
+/// `n` words from `rng`, taken by value as `&LockedRng` is.
+fn words<R: Rng>(mut rng: R, n: usize) -> Vec<u64> {
+    (0..n).map(|_| rng.next_u64()).collect()
+}
+
+fn sequential<S: Source>(mut source: S, n: usize) -> Vec<u64> {
+    (0..n).map(|_| source.word()).collect()
+}
+
+const THREADS: usize = 4;
+
+// ——— usage ———
+
+pub fn main() {
+    {
+        // Shared by threads, one call at a time: the stream, each word once
+        const WORDS: usize = 1000;
+        let rng = Arc::new(LockedRng::new(TestRng(SplitMix(1))));
+        let handles: Vec<_> = (0..THREADS).map(|_| {
+            let rng = rng.clone();
+            thread::spawn(move || words(&*rng, WORDS))
+        }).collect();
+        let mut drawn: Vec<u64> = handles.into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        drawn.sort_unstable();
+        let mut want = sequential(SplitMix(1), THREADS * WORDS);
+        want.sort_unstable();
+        assert_eq!(drawn, want);
+        // Back to one owner, who draws on without locking
+        let mut rng = Arc::try_unwrap(rng).unwrap();
+        let next = &sequential(SplitMix(1), THREADS * WORDS + 2)[THREADS * WORDS..];
+        assert_eq!([rng.next_u64(), rng.into_inner().next_u64()], next);
+        println!("LockedRng: {} threads drew the stream between them, each word once", THREADS);
+    }
+    {
+        // A `fill`, and a held guard, are one run of the stream
+        const FILLS: usize = 200;
+        let rng = Arc::new(LockedRng::new(TestRng(Counting(0))));
+        let handles: Vec<_> = (0..THREADS).map(|i| {
+            let rng = rng.clone();
+            thread::spawn(move || {
+                let mut runs = Vec::new();
+                for _ in 0..FILLS {
+                    if i % 2 == 0 {
+                        let mut buf = [0u8; 64];
+                        Rng::fill(&mut &*rng, &mut buf);
+                        runs.push(buf.chunks(8).map(|c| {
+                            let mut word = [0u8; 8];
+                            word.copy_from_slice(c);
+                            u64::from_le_bytes(word)
+                        }).collect::<Vec<_>>());
+                    } else {
+                        let mut guard = rng.lock();
+                        runs.push((0..8).map(|_| guard.next_u64()).collect());
+                    }
+                }
+                runs
+            })
+        }).collect();
+        for h in handles {
+            for run in h.join().unwrap() {
+                assert!(run.windows(2).all(|w| w[1] == w[0] + 1), "interleaved: {:?}", run);
+            }
+        }
+        println!("LockedRng: fills and guards never interleaved across {} threads", THREADS);
+    }
+    {
+        // `&self` through `SharedRng`, from any thread, with no `mut`
+        let rng = Arc::new(LockedRng::new(TestCRng(Counting(1))));
+        let other = rng.clone();
+        let there = thread::spawn(move || SharedRng::next_u64(&*other)).join().unwrap();
+        let here = SharedRng::next_u64(&*rng);
+        assert_eq!((there, here), (1, 2));
+        // Crypto, either way
+        let k = key(&mut &*rng);
+        assert_eq!(k[..8], 3u64.to_le_bytes());
+        let mut owned = LockedRng::new(TestCRng(Counting(10)));
+        assert_eq!(key(&mut owned)[..8], 10u64.to_le_bytes());
+        println!("SharedRng for LockedRng: no mut; CryptoRng through & and &mut");
+    }
+    {
+        // The `Send` bound at the spawn, met by a generator, a boxed one and
+        // a locked one; a `Handle` draws on its own thread only
+        assert_eq!(draw_elsewhere(TestRng(Counting(5))), 5);
+        let boxed: Box<dyn CryptoRng + Send> = Box::new(TestCRng(Counting(6)));
+        assert_eq!(draw_elsewhere(boxed), 6);
+        assert_eq!(draw_elsewhere(LockedRng::new(TestRng(Counting(7)))), 7);
+        let mut handle = Handle(Rc::new(RefCell::new(SplitMix(1))));
+        assert_eq!(handle.next_u64(), sequential(SplitMix(1), 1)[0]);
+        println!("Send at the spawn: plain, boxed and locked generators; the handle stays home");
+    }
+}
@@ -1,7 +1,30 @@
+//! Single `Rng` trait, with `CryptoRng` as a marker only
+//! 
+//! Unlike the baseline, `try_fill` is the required method and `fill` is
+//! provided on top of it: `fill` retries `Transient` errors up to
+//! `FILL_RETRIES` times before panicking, which needs the error kinds of
+//! `error.rs`. The other variants keep a unit `Error`, so the retry lives
+//! here alone.
+//! 
+//! Thoughts: with `fill` provided, an infallible generator must still write
+//! `try_fill`, returning `Ok` always; the price of a retry policy in one place.
+
 extern crate core;
 
-#[derive(Debug)]
-pub struct Error;
+use core::cmp::min;
+use core::mem::MaybeUninit;
+use core::time::Duration;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind, Injector};
+
+/// Number of times `fill` retries a transient error before panicking.
+pub const FILL_RETRIES: usize = 8;
+
+/// Longest single wait of `fill_when_ready`.
+pub const MAX_BACKOFF: Duration = Duration::from_millis(100);
 
 /// Trait governing random number generation.
 /// 
@@ -10,19 +33,50 @@ pub struct Error;
 pub trait Rng {
     /// Fill dest with random bytes.
     /// 
-    /// Panics if the underlying generator has an error; use `try_fill` if you
-    /// wish to handle errors.
-    fn fill(&mut self, dest: &mut [u8]);
+    /// Transient errors are retried up to `FILL_RETRIES` times. Panics if the
+    /// underlying generator has any other error or keeps failing; use
+    /// `try_fill` if you wish to handle errors. `NotReady` panics too, since
+    /// waiting needs a clock: `fill_when_ready` waits.
+    fn fill(&mut self, dest: &mut [u8]) {
+        let mut retries = 0;
+        loop {
+            match self.try_fill(dest) {
+                Ok(()) => return,
+                Err(ref e) if e.kind == ErrorKind::Transient && retries < FILL_RETRIES => {
+                    retries += 1;
+                }
+                Err(e) => panic!("Rng::fill failed: {:?}", e),
+            }
+        }
+    }
     
     /// Fill dest with random bytes.
     /// 
-    /// In infallible generators this is identical to `fill`; in fallible
-    /// generators this function may return an `Error`, and `fill` simply
-    /// unwraps the `Result`.
-    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        // we asume infallable generator in default impl
-        self.fill(dest);
-        Ok(())
+    /// In infallible generators this never fails; in fallible generators this
+    /// function may return an `Error`, and `fill` retries transient errors
+    /// before panicking.
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error>;
+    
+    /// Fill uninitialised `dest` with random bytes, returning it initialised.
+    /// 
+    /// The default zeroes `dest`, then calls `try_fill`; a generator which
+    /// can write its output straight in should override it (as with
+    /// `impl_fill_uninit_from_u64`), so that a large buffer is written once.
+    fn try_fill_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u8>]) -> Result<&'a mut [u8], Error> {
+        for b in dest.iter_mut() {
+            b.write(0);
+        }
+        // Sound: every byte was written just above
+        let dest = unsafe { dest.assume_init_mut() };
+        self.try_fill(dest)?;
+        Ok(dest)
+    }
+    
+    /// Generate a random number.
+    /// 
+    /// Panics if the underlying generator has an error.
+    fn next_u32(&mut self) -> u32 {
+        impl_next_u32_via_u64(self)
     }
     
     /// Generate a random number.
@@ -30,7 +84,12 @@ pub trait Rng {
     /// Panics if the underlying generator has an error.
     fn next_u64(&mut self) -> u64;
     
-    // also next_u32, next_u128
+    /// Generate a random number.
+    /// 
+    /// Panics if the underlying generator has an error.
+    fn next_u128(&mut self) -> u128 {
+        impl_next_u128_via_u64(self)
+    }
 }
 
 /// Extension trait marking a generator as "cryptographically secure".
@@ -45,12 +104,187 @@ pub trait Rng {
 /// compile time, therefore this trait is more of a guideline than a guarantee).
 pub trait CryptoRng: Rng {}
 
+// ——— impls ———
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        (**self).try_fill(dest)
+    }
+    
+    fn try_fill_uninit<'b>(&mut self, dest: &'b mut [MaybeUninit<u8>]) -> Result<&'b mut [u8], Error> {
+        (**self).try_fill_uninit(dest)
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        (**self).try_fill(dest)
+    }
+    
+    fn try_fill_uninit<'b>(&mut self, dest: &'b mut [MaybeUninit<u8>]) -> Result<&'b mut [u8], Error> {
+        (**self).try_fill_uninit(dest)
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {}
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {}
+
 // ——— utility functions ———
 
+/// Fills `dest`, waiting until the generator is ready, as `getrandom(2)`
+/// blocks until the kernel's pool is initialised: a `NotReady` failure is
+/// retried after `wait(d)`, `d` doubling from 1ms up to `MAX_BACKOFF`.
+/// 
+/// Once the waits add up to `timeout`, the last `NotReady` is returned;
+/// other errors are returned at once, after up to `FILL_RETRIES` transient
+/// ones, as `fill` retries them. `wait` is `std::thread::sleep` but in tests,
+/// and keeps this free of `std`.
+pub fn fill_when_ready<R, W>(rng: &mut R, dest: &mut [u8], timeout: Duration, mut wait: W)
+    -> Result<(), Error>
+    where R: Rng+?Sized, W: FnMut(Duration)
+{
+    let (mut backoff, mut waited, mut retries) = (Duration::from_millis(1), Duration::ZERO, 0);
+    loop {
+        match rng.try_fill(dest) {
+            Ok(()) => return Ok(()),
+            Err(ref e) if e.kind == ErrorKind::Transient && retries < FILL_RETRIES => retries += 1,
+            Err(ref e) if e.kind.should_wait() && waited < timeout => {
+                let d = min(backoff, timeout - waited);
+                wait(d);
+                waited += d;
+                backoff = min(backoff * 2, MAX_BACKOFF);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// With the `secure-helpers` feature, overwrite a stack temporary holding
+/// generator output, so that it does not outlive the helper using it.
+/// 
+/// The stores are dead as far as the optimiser knows, hence volatile; see
+/// `verify/helpers_zeroize.rs` for a check that they survive optimisation.
+macro_rules! wipe_temporary {
+    ($x:expr) => {
+        #[cfg(feature = "secure-helpers")]
+        unsafe {
+            let n = ::core::mem::size_of_val(&$x);
+            let p = &mut $x as *mut _ as *mut u8;
+            for i in 0..n {
+                ::core::ptr::write_volatile(p.add(i), 0);
+            }
+            ::core::sync::atomic::compiler_fence(::core::sync::atomic::Ordering::SeqCst);
+        }
+    };
+}
+
+/// Convenient implementation of `next_u32` in terms of `next_u64`: the low
+/// half of a word, the other half discarded.
+/// 
+/// This is `Rng`'s default; a generator whose native output is 32 bits
+/// should implement `next_u32` itself, and `next_u64` with
+/// `impl_next_u64_via_u32`.
+pub fn impl_next_u32_via_u64<R: Rng+?Sized>(rng: &mut R) -> u32 {
+    rng.next_u64() as u32
+}
+
+/// Convenient implementation of `next_u64` in terms of `next_u32`: two
+/// words, the first low.
+pub fn impl_next_u64_via_u32<R: Rng+?Sized>(rng: &mut R) -> u64 {
+    let lo = rng.next_u32() as u64;
+    let hi = rng.next_u32() as u64;
+    hi << 32 | lo
+}
+
+/// Convenient implementation of `next_u128` in terms of `next_u64`: two
+/// words, the first low. This is `Rng`'s default.
+pub fn impl_next_u128_via_u64<R: Rng+?Sized>(rng: &mut R) -> u128 {
+    let lo = rng.next_u64() as u128;
+    let hi = rng.next_u64() as u128;
+    hi << 64 | lo
+}
+
 /// Convenient implementation for `fill` in terms of `next_u64`.
-// TODO: Also for u32, u128 via macro internals.
 pub fn impl_fill_from_u64<R: Rng+?Sized>(rng: &mut R, dest: &mut [u8]) {
-    use core::cmp::min;
+    let mut chunks = dest.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        #[cfg_attr(not(feature = "secure-helpers"), allow(unused_mut))]
+        let mut x = rng.next_u64();
+        le_bytes(chunk, &mut x);
+        wipe_temporary!(x);
+    }
+    let rest = chunks.into_remainder();
+    if !rest.is_empty() {
+        #[cfg_attr(not(feature = "secure-helpers"), allow(unused_mut))]
+        let mut x = rng.next_u64();
+        le_bytes(rest, &mut x);
+        wipe_temporary!(x);
+    }
+}
+
+/// Convenient implementation for `try_fill_uninit` in terms of `next_u64`:
+/// the bytes of `impl_fill_from_u64`, written once.
+pub fn impl_fill_uninit_from_u64<'a, R: Rng+?Sized>(rng: &mut R, dest: &'a mut [MaybeUninit<u8>]) -> &'a mut [u8] {
+    for chunk in dest.chunks_mut(8) {
+        #[cfg_attr(not(feature = "secure-helpers"), allow(unused_mut))]
+        let mut x = rng.next_u64();
+        for b in chunk {
+            b.write(x as u8);
+            x >>= 8;
+        }
+        wipe_temporary!(x);
+    }
+    // Sound: every chunk was written above
+    unsafe { dest.assume_init_mut() }
+}
+
+// Writes `x`'s low bytes, least significant first, shifting each out of `x`.
+// Not `to_le_bytes`: an unoptimised build keeps copies of the array that
+// `wipe_temporary!` can't reach.
+#[inline(always)]
+fn le_bytes(dest: &mut [u8], x: &mut u64) {
+    for b in dest {
+        *b = *x as u8;
+        *x >>= 8;
+    }
+}
+
+/// `impl_fill_from_u64` as it was, copying through raw pointers; kept for
+/// `benches/fill_helpers.rs` to compare against.
+#[cfg(feature = "unsafe-fill")]
+pub fn impl_fill_from_u64_ptr<R: Rng+?Sized>(rng: &mut R, dest: &mut [u8]) {
     use core::intrinsics::copy_nonoverlapping;
     use core::mem::size_of;
     
@@ -59,21 +293,98 @@ pub fn impl_fill_from_u64<R: Rng+?Sized>(rng: &mut R, dest: &mut [u8]) {
     while len > pos {
         // Cast pointer, effectively to `&[u8; 8]`, and copy as many bytes
         // as required. Byte-swap x on BE architectures.
-        let x = rng.next_u64().to_le();
+        #[cfg_attr(not(feature = "secure-helpers"), allow(unused_mut))]
+        let mut x = rng.next_u64().to_le();
         let xp = &x as *const u64 as *const u8;
         let p: *mut u8 = unsafe{ dest.as_mut_ptr().offset(pos as isize) };
         let n = min(len - pos, size_of::<u64>());
         unsafe{ copy_nonoverlapping(xp, p, n); }
+        wipe_temporary!(x);
         pos += n;
     }
 }
 
+/// Integer slices `fill_slice` can write: as bytes, then converted from
+/// little-endian, so the integers are the same on every platform.
+pub trait AsByteSliceMut {
+    /// The slice's memory, as bytes.
+    fn as_byte_slice_mut(&mut self) -> &mut [u8];
+    
+    /// Converts each integer from little-endian; nothing on little-endian
+    /// targets.
+    fn convert_from_le(&mut self);
+}
+
+impl AsByteSliceMut for [u8] {
+    fn as_byte_slice_mut(&mut self) -> &mut [u8] {
+        self
+    }
+    
+    fn convert_from_le(&mut self) {}
+}
+
+macro_rules! impl_as_byte_slice {
+    ($($ty:ty),*) => {$(
+        impl AsByteSliceMut for [$ty] {
+            fn as_byte_slice_mut(&mut self) -> &mut [u8] {
+                let len = self.len() * ::core::mem::size_of::<$ty>();
+                // Safe: any bytes are a valid integer, and a `u8` needs no
+                // alignment
+                unsafe { ::core::slice::from_raw_parts_mut(self.as_mut_ptr() as *mut u8, len) }
+            }
+            
+            fn convert_from_le(&mut self) {
+                for x in self {
+                    *x = <$ty>::from_le(*x);
+                }
+            }
+        }
+    )*};
+}
+
+impl_as_byte_slice!(u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Fill an integer slice, the same integers on every platform: one `fill`
+/// of its bytes, then a byte swap of each integer on big-endian targets.
+/// 
+/// Not a method of `Rng`: a generic method would need `Self: Sized`, and so
+/// be unavailable on `dyn Rng`.
+pub fn fill_slice<R: Rng+?Sized, T: AsByteSliceMut+?Sized>(rng: &mut R, dest: &mut T) {
+    rng.fill(dest.as_byte_slice_mut());
+    dest.convert_from_le();
+}
+
+/// A float in `[0, 1)` from the top 53 bits of `x`, by multiplying: each
+/// multiple of 2^-53 equally likely.
+pub fn f64_closed_open01(x: u64) -> f64 {
+    (x >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// A float in `(0, 1]`: as `f64_closed_open01`, plus 2^-53.
+pub fn f64_open_closed01(x: u64) -> f64 {
+    ((x >> 11) + 1) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// A float in `[0, 1)` from the top 52 bits of `x`, by the exponent trick:
+/// the bits as the mantissa of a float in `[1, 2)`, less 1. No conversion or
+/// multiply, but multiples of 2^-52 only.
+pub fn f64_closed_open01_exp(x: u64) -> f64 {
+    f64::from_bits(0x3FF0_0000_0000_0000 | x >> 12) - 1.0
+}
+
+/// A float in `(0, 1]`, by the exponent trick: 2 less the float in `[1, 2)`.
+pub fn f64_open_closed01_exp(x: u64) -> f64 {
+    2.0 - f64::from_bits(0x3FF0_0000_0000_0000 | x >> 12)
+}
+
 macro_rules! impl_uint_from_fill {
     ($ty:ty, $N:expr, $rng:expr) => ({
         assert_eq!($N, ::core::mem::size_of::<$ty>());
         let mut buf = [0u8; $N];
         $rng.fill(&mut buf);
-        unsafe{ *(&buf[0] as *const u8 as *const $ty) }.to_le()
+        let x = <$ty>::from_le_bytes(buf);
+        wipe_temporary!(buf);
+        x
     });
 }
 
@@ -86,43 +397,174 @@ pub fn impl_next_u64_from_fill<R: Rng+?Sized>(rng: &mut R) -> u64 {
     impl_uint_from_fill!(u64, 8, rng)
 }
 
+/// Convenient implementation of `next_u32` in terms of `fill`.
+pub fn impl_next_u32_from_fill<R: Rng+?Sized>(rng: &mut R) -> u32 {
+    impl_uint_from_fill!(u32, 4, rng)
+}
+
+/// Convenient implementation of `next_u128` in terms of `fill`.
+pub fn impl_next_u128_from_fill<R: Rng+?Sized>(rng: &mut R) -> u128 {
+    impl_uint_from_fill!(u128, 16, rng)
+}
+
 // ——— test RNGs ———
 
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, FaultyRng, Source, SplitMix};
+use self::test_rngs::{TestCRng, TestRng};
+use self::test_rngs::os::{OsError, OsRng};
+
+// Fills from the source's words; an exhausted source is unavailable.
+fn try_fill_from_source<S: Source>(source: &mut S, dest: &mut [u8]) -> Result<(), Error> {
+    for chunk in dest.chunks_mut(8) {
+        let x = source.try_word().map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))?;
+        chunk.copy_from_slice(&x.to_le_bytes()[..chunk.len()]);
+    }
+    Ok(())
+}
+
+// The same, into uninitialised bytes.
+fn try_fill_uninit_from_source<'a, S: Source>(source: &mut S, dest: &'a mut [MaybeUninit<u8>])
+    -> Result<&'a mut [u8], Error>
+{
+    for chunk in dest.chunks_mut(8) {
+        let x = source.try_word().map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))?;
+        chunk.write_copy_of_slice(&x.to_le_bytes()[..chunk.len()]);
+    }
+    // Sound: every chunk was written above
+    Ok(unsafe { dest.assume_init_mut() })
+}
+
 // A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        try_fill_from_source(&mut self.0, dest)
+    }
+    
+    fn try_fill_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u8>]) -> Result<&'a mut [u8], Error> {
+        try_fill_uninit_from_source(&mut self.0, dest)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0.word()
+    }
+}
+
+// A CryptoRng
+impl<S: Source> Rng for TestCRng<S> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        try_fill_from_source(&mut self.0, dest)
+    }
+    
+    fn try_fill_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u8>]) -> Result<&'a mut [u8], Error> {
+        try_fill_uninit_from_source(&mut self.0, dest)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0.word()
+    }
+}
+
+impl<S: Source> CryptoRng for TestCRng<S> {}
+
+/// A generator whose native output is 32 bits: `next_u32` is its own, and
+/// `next_u64` is built on it.
 #[derive(Debug)]
-struct TestRng(u64);
+pub struct Word32Rng<S = Constant>(pub S);
 
-impl Rng for TestRng {
-    fn fill(&mut self, dest: &mut [u8]) {
-        impl_fill_from_u64(self, dest)
+impl<S: Source> Rng for Word32Rng<S> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn try_fill_uninit<'a>(&mut self, dest: &'a mut [MaybeUninit<u8>]) -> Result<&'a mut [u8], Error> {
+        Ok(impl_fill_uninit_from_u64(self, dest))
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
     }
     
     fn next_u64(&mut self) -> u64 {
-        self.0
+        impl_next_u64_via_u32(self)
     }
 }
 
-// A CryptoRng
+// Wraps another generator, failing `try_fill` when `inject` says.
 #[derive(Debug)]
-struct TestCRng(u64);
+struct ErrorInjectingRng<R> {
+    rng: R,
+    inject: Injector,
+}
 
-impl Rng for TestCRng {
-    fn fill(&mut self, dest: &mut [u8]) {
-        impl_fill_from_u64(self, dest)
+impl<R: Rng> Rng for ErrorInjectingRng<R> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.inject.check()?;
+        self.rng.try_fill(dest)
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        impl_next_u32_from_fill(self)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        impl_next_u64_from_fill(self)
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        impl_next_u128_from_fill(self)
+    }
+}
+
+// Wraps another generator, `NotReady` for its first `not_ready` calls, as
+// `getrandom(2)` with `GRND_NONBLOCK` is before the kernel's pool is
+// initialised.
+#[derive(Debug)]
+struct BootingRng<R> {
+    rng: R,
+    not_ready: usize,
+}
+
+impl<R: Rng> Rng for BootingRng<R> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        if self.not_ready > 0 {
+            self.not_ready -= 1;
+            return Err(Error::new(ErrorKind::NotReady, "pool not ready"));
+        }
+        self.rng.try_fill(dest)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        impl_next_u64_from_fill(self)
+    }
+}
+
+// The operating system's generator, with its kinds: not through a role,
+// whose source fails `Exhausted` whatever the cause
+impl Rng for OsRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        OsRng::try_fill(self, dest).map_err(|e| match e {
+            OsError::NotReady => Error::new(ErrorKind::NotReady, "OS pool not ready"),
+            OsError::Unavailable => Error::new(ErrorKind::Unavailable, "no OS generator"),
+            OsError::Code(_) => Error::new(ErrorKind::Unexpected, "OS generator failed"),
+        })
     }
     
     fn next_u64(&mut self) -> u64 {
-        self.0
+        impl_next_u64_from_fill(self)
     }
 }
 
-impl CryptoRng for TestCRng {}
+impl CryptoRng for OsRng {}
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(0x20216F6C6C6548);
-    let mut c = TestCRng(0x3F556572416F6857);
+pub fn main() {
+    let mut t = TestRng(Constant(0x20216F6C6C6548));
+    let mut c = TestCRng(Constant(0x3F556572416F6857));
     let mut buf = [0u8; 16];
     t.fill(&mut buf);
     println!("t: {:?} says: {}", t, String::from_utf8_lossy(&buf));
@@ -145,4 +587,139 @@ fn main() {
         let cr = &mut c as &mut Rng;
         println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u64());
     }
+    {
+        // Transient errors are retried by `fill`, but visible to `try_fill`
+        let mut e = ErrorInjectingRng { rng: TestRng(Constant(0x20216F6C6C6548)),
+            inject: Injector::transient(3) };
+        assert_eq!(e.try_fill(&mut buf).unwrap_err().kind, ErrorKind::Transient);
+        e.fill(&mut buf);
+        println!("e: {:?} says: {}", e, String::from_utf8_lossy(&buf));
+        
+        let mut e = ErrorInjectingRng { rng: TestRng(Constant(1)),
+            inject: Injector::transient(FILL_RETRIES) };
+        e.fill(&mut buf);
+        
+        // Too many transient errors panic, as do persistent ones. (The hook
+        // is left alone, printing the panics: it is global, and `cargo test`
+        // runs the other variants on other threads meanwhile.)
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        let mut e = ErrorInjectingRng { rng: TestRng(Constant(1)),
+            inject: Injector::transient(FILL_RETRIES + 1) };
+        assert!(catch_unwind(AssertUnwindSafe(|| e.fill(&mut buf))).is_err());
+        let mut e = ErrorInjectingRng { rng: TestRng(Constant(1)),
+            inject: Injector::persistent(1) };
+        assert!(catch_unwind(AssertUnwindSafe(|| e.fill(&mut buf))).is_err());
+        assert_eq!(e.try_fill(&mut buf).unwrap_err().kind, ErrorKind::Unavailable);
+        println!("e: persistent failures still panic in fill");
+    }
+    {
+        // A source failing after one word: unavailable, visible to `try_fill`
+        let mut f = TestCRng(FailAfter::new(1));
+        assert!(f.try_fill(&mut buf[..8]).is_ok());
+        assert_eq!(f.try_fill(&mut buf[..8]).unwrap_err().kind, ErrorKind::Unavailable);
+        println!("f, failing after one word, using try_fill: Ok, then Unavailable");
+        // The same fault injected into a real generator
+        let mut g = TestCRng(FaultyRng::after(SplitMix(1), 2));
+        assert!(g.try_fill(&mut buf[..16]).is_ok());
+        assert_eq!(g.try_fill(&mut buf[..8]).unwrap_err().kind, ErrorKind::Unavailable);
+        assert_eq!(g.0.calls, 3);
+        println!("g, SplitMix faulty after two words, using try_fill: Ok, then Unavailable");
+    }
+    {
+        // Early boot: `NotReady`, which `fill_when_ready` waits out, backing
+        // off; past its timeout, the last `NotReady`
+        let mut b = BootingRng { rng: TestCRng(Constant(1)), not_ready: 4 };
+        assert_eq!(b.try_fill(&mut buf).unwrap_err().kind, ErrorKind::NotReady);
+        let mut waits = vec![];
+        let second = Duration::from_secs(1);
+        fill_when_ready(&mut b, &mut buf, second, |d| waits.push(d.as_millis())).unwrap();
+        assert_eq!(waits, [1, 2, 4]);
+        let mut b = BootingRng { rng: TestCRng(Constant(1)), not_ready: 100 };
+        let (mut waited, mut n) = (Duration::ZERO, 0);
+        let e = fill_when_ready(&mut b, &mut buf, Duration::from_millis(500), |d| {
+            waited += d;
+            n += 1;
+        }).unwrap_err();
+        // 1 + 2 + ... + 64 = 127ms, three of 100ms, then the 73ms left
+        assert_eq!((e.kind, waited, n), (ErrorKind::NotReady, Duration::from_millis(500), 11));
+        // Other errors at once, transient ones retried first
+        let mut e = ErrorInjectingRng { rng: TestRng(Constant(1)),
+            inject: Injector::persistent(2) };
+        let e = fill_when_ready(&mut e, &mut buf, Duration::from_secs(1), |_| panic!("waited"));
+        assert_eq!(e.unwrap_err().kind, ErrorKind::Unavailable);
+        // And the OS generator, ready long since
+        let mut os = OsRng::nonblocking();
+        fill_when_ready(&mut os, &mut buf, Duration::from_secs(10), std::thread::sleep).unwrap();
+        println!("b, NotReady 4 times: waited 1, 2 and 4ms; OsRng::nonblocking: ready");
+    }
+    {
+        // Native and derived methods: `TestRng` makes 64-bit words, so its
+        // `next_u32` takes a whole word; `Word32Rng` makes 32-bit words, so
+        // its `next_u64` takes two, and `next_u128` four
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u32(), 1);
+        assert_eq!(t.next_u128(), 3 << 64 | 2);
+        let mut w = Word32Rng(Counting(1));
+        assert_eq!(w.next_u64(), 2 << 32 | 1);
+        assert_eq!(w.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        assert_eq!(w.next_u32(), 7);
+        println!("w, 32-bit native, next_u128 of four words: {:#x}", Word32Rng(Counting(1)).next_u128());
+    }
+    {
+        // Integer slices: the words' little-endian bytes, as integers
+        let mut t = TestRng(Constant(0x0807_0605_0403_0201));
+        let mut words = [0u32; 3];
+        fill_slice(&mut t, &mut words[..]);
+        assert_eq!(words, [0x0403_0201, 0x0807_0605, 0x0403_0201]);
+        let mut halves = [0i16; 2];
+        fill_slice(&mut t as &mut dyn Rng, &mut halves[..]);
+        assert_eq!(halves, [0x0201, 0x0403]);
+        println!("t, fill_slice of [u32; 3]: {:x?}", words);
+    }
+    {
+        // Uninitialised buffers: each override writes the bytes `try_fill`
+        // would, odd tail included; the default zeroes, then fills; and a
+        // failing source is an error, with no bytes to read
+        let mut want = [0u8; 13];
+        let mut buf = [MaybeUninit::uninit(); 13];
+        TestRng(Counting(1)).try_fill(&mut want).unwrap();
+        assert_eq!(TestRng(Counting(1)).try_fill_uninit(&mut buf).unwrap(), &want);
+        Word32Rng(Counting(1)).try_fill(&mut want).unwrap();
+        assert_eq!(Word32Rng(Counting(1)).try_fill_uninit(&mut buf).unwrap(), &want);
+        let mut b = BootingRng { rng: TestRng(Counting(1)), not_ready: 1 };
+        assert!(b.try_fill_uninit(&mut buf).unwrap_err().kind == ErrorKind::NotReady);
+        TestRng(Counting(1)).try_fill(&mut want).unwrap();
+        assert_eq!((&mut b as &mut dyn Rng).try_fill_uninit(&mut buf).unwrap(), &want);
+        let e = TestCRng(FailAfter::new(1)).try_fill_uninit(&mut buf).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Unavailable);
+        println!("t, try_fill_uninit of 13 bytes: {:x?}", want);
+    }
+    {
+        // Floats from words: the ends of each interval, from the ends of the
+        // word's range
+        let (top53, top52) = (1.0 - 2f64.powi(-53), 1.0 - 2f64.powi(-52));
+        assert_eq!((f64_closed_open01(0), f64_closed_open01(u64::MAX)), (0.0, top53));
+        assert_eq!((f64_open_closed01(0), f64_open_closed01(u64::MAX)), (2f64.powi(-53), 1.0));
+        assert_eq!((f64_closed_open01_exp(0), f64_closed_open01_exp(u64::MAX)), (0.0, top52));
+        assert_eq!((f64_open_closed01_exp(0), f64_open_closed01_exp(u64::MAX)), (1.0, 2f64.powi(-52)));
+        println!("t, floats: {} and {}", f64_closed_open01(t.next_u64()), f64_open_closed01_exp(t.next_u64()));
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u64());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.next_u64());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
 }
@@ -0,0 +1,424 @@
+//! Independent traits, using negative reasoning (nightly only)
+//! 
+//! Several variants (separate_implicit_Rng3, extends_CryptoRng2, …) hit the
+//! same coherence wall: a blanket `impl<CR: CryptoRng> Rng for CR` forbids any
+//! type from also implementing `Rng` directly. What we really want is
+//! "implement `Rng` for all `CryptoRng` except those that already implement
+//! `Rng`". That exact bound is self-referential and can't be written, but two
+//! nightly features get close:
+//! 
+//! 1.  `negative_bounds`: the blanket impl requires `CR: !NativeRng`, where
+//!     `NativeRng` is a marker implemented by types providing their own `Rng`
+//!     impl. This allows dual-purpose types (`TestBothRng`).
+//! 2.  `with_negative_coherence`: non-crypto generators write
+//!     `impl !CryptoRng for T`, proving to coherence that the blanket impl
+//!     can't apply. See the `negative_coherence` module below.
+//! 
+//! Methods: `Rng` requires `next_u32`, `CryptoRng` requires `try_next_u32`,
+//! and each provides the wider methods on top (see `bridge.rs`). The blanket
+//! `Rng` impl forwards each method to its `try_` counterpart.
+//! 
+//! Thoughts: (1) works: `TestBothRng` implements both traits optimally. The
+//! catch is that `T: !NativeRng` needs an explicit `impl !NativeRng for T`,
+//! so every crypto-only generator needs an opt-out line or silently isn't
+//! an `Rng`; wrappers opt in and forward. (2) needs an opt-out on every
+//! non-crypto generator instead, and still allows no dual type. Both
+//! features are internal to the compiler, so this shows where the wall is,
+//! not a usable design.
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc +nightly --cfg fail_missing_opt_out negative_impls.rs
+//!     rustc +nightly --cfg fail_native_and_opt_out negative_impls.rs
+//!     rustc +nightly --cfg fail_dual_with_coherence negative_impls.rs
+
+#![feature(negative_bounds)]
+#![feature(negative_impls)]
+#![feature(with_negative_coherence)]
+#![feature(rustc_attrs)]
+#![allow(internal_features)]
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
+
+pub trait CryptoRng {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
+}
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+/// Marker for types which implement `Rng` themselves.
+/// 
+/// Crypto generators must instead opt out with `impl !NativeRng for T {}`.
+pub trait NativeRng {}
+
+// ——— impls ———
+
+impl<CR: CryptoRng+!NativeRng+?Sized> Rng for CR {
+    fn next_u32(&mut self) -> u32 {
+        self.try_next_u32().unwrap()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap()
+    }
+}
+
+impl<CR: CryptoRng+?Sized> CryptoRng for &mut CR {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (*self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (*self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (*self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (*self).try_fill(dest)
+    }
+}
+
+impl<CR: CryptoRng+?Sized> CryptoRng for Box<CR> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
+}
+
+// Wrappers opt in and forward instead of taking the blanket impl: `&mut R`
+// is then an `Rng` whenever `R` is, natively or through the blanket impl
+// (which negative coherence can tell apart from this one, `&mut R` being
+// `NativeRng`).
+impl<R: ?Sized> NativeRng for &mut R {}
+impl<R: ?Sized> NativeRng for Box<R> {}
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+// Opt-out required for trait objects to get the blanket impl.
+impl !NativeRng for dyn CryptoRng {}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestBothRng, TestCRng, TestRng};
+
+// A non-crypto Rng
+impl<S: Source> NativeRng for TestRng<S> {}
+
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// A CryptoRng
+impl<S> !NativeRng for TestCRng<S> {}
+
+impl<S: Source> CryptoRng for TestCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+// An RNG supporting both interfaces: this is what separate_implicit_Rng3
+// could not express.
+impl<S: Source> NativeRng for TestBothRng<S> {}
+
+impl<S: Source> CryptoRng for TestBothRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+impl<S: Source> Rng for TestBothRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32 + 1
+    }
+}
+
+// ——— compile-fail cases ———
+
+// A crypto generator which forgot to opt out: it is *not* an `Rng`.
+#[cfg(fail_missing_opt_out)]
+mod missing_opt_out {
+    use super::*;
+    
+    struct ForgetfulCRng(u32);
+    
+    impl CryptoRng for ForgetfulCRng {
+        fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+            Ok(self.0)
+        }
+    }
+    
+    fn use_rng<R: Rng>(_: R) {}
+    
+    fn f() {
+        use_rng(ForgetfulCRng(0));
+    }
+}
+
+// A type can't both claim to implement `Rng` and opt out.
+#[cfg(fail_native_and_opt_out)]
+mod native_and_opt_out {
+    use super::*;
+    
+    struct Confused;
+    impl NativeRng for Confused {}
+    impl !NativeRng for Confused {}
+}
+
+// ——— alternative: negative coherence ———
+
+mod negative_coherence {
+    use super::CryptoError;
+    
+    #[rustc_strict_coherence]
+    pub trait CryptoRng {
+        fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+    }
+    
+    pub trait Rng {
+        fn next_u32(&mut self) -> u32;
+    }
+    
+    impl<CR: CryptoRng> Rng for CR {
+        fn next_u32(&mut self) -> u32 {
+            self.try_next_u32().unwrap()
+        }
+    }
+    
+    // A non-crypto Rng must promise never to be a CryptoRng.
+    #[derive(Debug)]
+    pub struct TestRng(pub u32);
+    
+    impl !CryptoRng for TestRng {}
+    
+    impl Rng for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0
+        }
+    }
+    
+    #[derive(Debug)]
+    pub struct TestCRng(pub u32);
+    
+    impl CryptoRng for TestCRng {
+        fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+            Ok(self.0)
+        }
+    }
+    
+    // Dual-purpose types remain impossible: conflicts with the blanket impl.
+    #[cfg(fail_dual_with_coherence)]
+    mod dual {
+        use super::*;
+        
+        struct TestBothRng(u32);
+        
+        impl CryptoRng for TestBothRng {
+            fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+                Ok(self.0)
+            }
+        }
+        
+        impl Rng for TestBothRng {
+            fn next_u32(&mut self) -> u32 {
+                self.0
+            }
+        }
+    }
+}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
+    let mut b = TestBothRng(Constant(3651));
+    println!("t: {:?} impls Rng", t);
+    println!("c: {:?} impls CryptoRng", c);
+    println!("b: {:?} impls both", b);
+    {
+        // Does each type support each trait it claims via static dispatch?
+        println!("t, static dispatch, using Rng: {:?}", t.next_u32());
+        println!("c, static dispatch, using CryptoRng: {:?}", c.try_next_u32());
+        println!("c, static dispatch, using Rng: {:?}", c.next_u32());
+        println!("b, static dispatch, using CryptoRng: {:?}", b.try_next_u32());
+        println!("b, static dispatch, using Rng: {:?}", b.next_u32());
+        // b's own `Rng` impl is used, not the blanket impl:
+        assert_eq!(b.next_u32(), 3652);
+        assert_eq!(c.next_u32(), 42);
+    }
+    {
+        // Can the crypto types be used via CryptoRng with dynamic dispatch?
+        let cr = &mut c as &mut dyn CryptoRng;
+        println!("c, dynamic dispatch, using CryptoRng: {:?}", cr.try_next_u32());
+        let br = &mut b as &mut dyn CryptoRng;
+        println!("b, dynamic dispatch, using CryptoRng: {:?}", br.try_next_u32());
+    }
+    {
+        // Can all types be used via Rng with dynamic dispatch?
+        let tr = &mut t as &mut dyn Rng;
+        println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
+        let cr = &mut c as &mut dyn Rng;
+        println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
+        let br = &mut b as &mut dyn Rng;
+        println!("b, dynamic dispatch, using Rng: {:?}", br.next_u32());
+        // A `dyn CryptoRng` is itself an `Rng`:
+        let ccr = &mut c as &mut dyn CryptoRng;
+        println!("c, dynamic dispatch, CryptoRng used as Rng: {:?}", ccr.next_u32());
+    }
+    {
+        use self::negative_coherence::{Rng, CryptoRng};
+        let mut t = negative_coherence::TestRng(13);
+        let mut c = negative_coherence::TestCRng(42);
+        println!("t, negative coherence, using Rng: {:?}", t.next_u32());
+        println!("c, negative coherence, using CryptoRng: {:?}", c.try_next_u32());
+        println!("c, negative coherence, using Rng: {:?}", c.next_u32());
+    }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using CryptoRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket Rng impl: the try_ methods, unwrapped
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(c.next_u64(), 2 << 32 | 1);
+        println!("c, provided methods via Rng: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
+}
@@ -0,0 +1,76 @@
+//! The operating system's generator, by system call: `OsRng`
+//! 
+//! A thin wrapper over the system calls of `entropy/os_backends/sys.rs`
+//! (`getrandom(2)`, `getentropy(2)`, `RtlGenRandom`), on `core` alone, with
+//! their errno values classified as the shared error's kinds: `EAGAIN` is
+//! `NotReady`, `ENOSYS` and `EPERM` are `Unavailable`. `test_rngs.rs` makes
+//! it a `Source` for every variant's roles; `entropy/os_rng.rs` is the full
+//! façade over the same calls.
+//! 
+//! Thoughts: blocking is the generator's choice, made when it's built (`new`
+//! or `nonblocking`); no design needs a per-call "don't block", since
+//! `NotReady` is the kind for it.
+
+#[path = "../entropy/os_backends/sys.rs"]
+#[allow(dead_code)]
+mod sys;
+
+/// Why a call failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OsError {
+    /// The pool isn't initialised yet, and the call wasn't to block.
+    NotReady,
+    /// No such call here: an old kernel, a filter, another platform.
+    Unavailable,
+    /// Any other error, by its code (errno, or 0 where there is none).
+    Code(i32),
+}
+
+impl OsError {
+    /// The kind of an errno value of the platform's call.
+    pub fn from_errno(code: i32) -> OsError {
+        match code {
+            sys::EAGAIN => OsError::NotReady,
+            sys::ENOSYS | sys::EPERM => OsError::Unavailable,
+            _ => OsError::Code(code),
+        }
+    }
+}
+
+/// The operating system's generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OsRng {
+    nonblocking: bool,
+}
+
+impl OsRng {
+    /// A generator waiting for the pool in early boot.
+    pub fn new() -> OsRng {
+        OsRng { nonblocking: false }
+    }
+    
+    /// A generator failing `NotReady` in early boot, where the platform can
+    /// tell (Linux); elsewhere the same as `new`.
+    pub fn nonblocking() -> OsRng {
+        OsRng { nonblocking: true }
+    }
+    
+    /// The platform's call.
+    pub fn backend() -> &'static str {
+        sys::NAME
+    }
+    
+    /// Fills `dest`; a zero-length `dest` always succeeds.
+    pub fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), OsError> {
+        if dest.is_empty() {
+            return Ok(());
+        }
+        sys::fill(dest, self.nonblocking).map_err(OsError::from_errno)
+    }
+}
+
+impl Default for OsRng {
+    fn default() -> OsRng {
+        OsRng::new()
+    }
+}
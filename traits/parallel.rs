@@ -0,0 +1,224 @@
+//! Data-parallel sampling: a generator per task, over a rayon pool
+//! 
+//! A Monte Carlo run split across threads must not share one generator: a
+//! lock (`locked.rs`) serialises every draw, and the words each task gets
+//! depend on the scheduling, so the result does too. The alternative is a
+//! generator per task, derived before the work starts, in one of two ways:
+//! 
+//! *   `fork_from_rng`: each task's generator seeded, in order, from the
+//!     master's output (`SeedableRng::from_seed` over `try_fill`, as
+//!     `from_rng.rs` does); any `SeedableRng`, fallible as the master is
+//! *   `fork_by_jump`: each a copy of the master, which then jumps ahead,
+//!     so the tasks take disjoint stretches of one stream; generators with
+//!     a jump only (`JumpRng`), here `Xoshiro256pp`, 2^128 words a jump
+//! 
+//! `par_sample(rngs, sample)` then runs `sample(i, &mut rng)` for each
+//! task's generator on the current rayon pool, the results in task order.
+//! It asks `R: Rng + Send`: a task owns its generator, so nothing is shared
+//! and nothing need be `Sync`. The traits are `marker_only`'s, included
+//! whole; the tasks, not the threads, own the generators, so the result is
+//! the same in a pool of any size.
+//! 
+//! Run with:
+//! 
+//!     cargo test --features rayon parallel
+//! 
+//! or alone, given `rayon` built, as for `serializable.rs`:
+//! 
+//!     rustc --extern rayon=PATH/librayon.rlib -L PATH parallel.rs && ./parallel
+//! 
+//! Thoughts: the design supports it with nothing added: the bound `R: Send`
+//! is on `par_sample`, where the threads are. `main` estimates pi in pools
+//! of 1 to 8 threads, by both derivations, with the same count each time;
+//! rayon's `map_init`, a generator per thread, loses that. Seeding works for
+//! every `SeedableRng`, its children as independent as the master's output
+//! is random; a jump promises disjoint streams, but like `set_stream`
+//! (`streams.rs`) belongs to the concrete types, so `JumpRng` is no proposal.
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+extern crate rayon;
+
+use rayon::prelude::*;
+
+#[path = "marker_only.rs"]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+
+use self::marker_only::test_rngs::seeding::SeedableRng;
+use self::marker_only::test_rngs::{FailAfter, SplitMix, TestRng};
+use self::marker_only::{f64_closed_open01, impl_fill_from_u64, Error, Rng};
+
+// ——— helpers ———
+
+/// A generator which can skip ahead far enough that the stretches between
+/// jumps never overlap.
+pub trait JumpRng: Rng {
+    fn jump(&mut self);
+}
+
+/// `n` generators, each seeded in turn from `master`'s output.
+pub fn fork_from_rng<R: SeedableRng, M: Rng + ?Sized>(master: &mut M, n: usize)
+    -> Result<Vec<R>, Error>
+{
+    (0..n).map(|_| {
+        let mut seed = R::Seed::default();
+        master.try_fill(seed.as_mut())?;
+        Ok(R::from_seed(seed))
+    }).collect()
+}
+
+/// `n` generators, each `master` as it was before its next jump.
+pub fn fork_by_jump<R: JumpRng + Clone>(master: &mut R, n: usize) -> Vec<R> {
+    (0..n).map(|_| {
+        let rng = master.clone();
+        master.jump();
+        rng
+    }).collect()
+}
+
+/// `sample(i, rng)` for each task `i` and its generator, on the current
+/// rayon pool; the results in task order.
+pub fn par_sample<R, T, F>(rngs: Vec<R>, sample: F) -> Vec<T>
+    where R: Rng + Send, T: Send, F: Fn(usize, &mut R) -> T + Sync
+{
+    rngs.into_par_iter()
+        .enumerate()
+        .map(|(i, mut rng)| sample(i, &mut rng))
+        .collect()
+}
+
+// ——— Xoshiro256pp ———
+
+/// xoshiro256++ (Blackman and Vigna), with its jump of 2^128 words.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xoshiro256pp {
+    s: [u64; 4],
+}
+
+const JUMP: [u64; 4] = [
+    0x180ec6d33cfd0aba, 0xd5a61266f0c9392c, 0xa9582618e03fc9aa, 0x39abdc4529b1661c,
+];
+
+impl Rng for Xoshiro256pp {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+}
+
+impl JumpRng for Xoshiro256pp {
+    fn jump(&mut self) {
+        let mut s = [0u64; 4];
+        for &word in JUMP.iter() {
+            for b in 0..64 {
+                if word & 1 << b != 0 {
+                    for (x, y) in s.iter_mut().zip(self.s.iter()) {
+                        *x ^= *y;
+                    }
+                }
+                self.next_u64();
+            }
+        }
+        self.s = s;
+    }
+}
+
+impl SeedableRng for Xoshiro256pp {
+    type Seed = [u8; 32];
+    
+    /// Four words, little-endian; the all-zero seed, a fixed point, is
+    /// replaced by `seed_from_u64(0)`'s.
+    fn from_seed(seed: [u8; 32]) -> Self {
+        if seed == [0; 32] {
+            return Self::seed_from_u64(0);
+        }
+        let mut s = [0u64; 4];
+        for (x, chunk) in s.iter_mut().zip(seed.chunks(8)) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            *x = u64::from_le_bytes(word);
+        }
+        Xoshiro256pp { s }
+    }
+}
+
+// ——— consumers ———
+
+const TASKS: usize = 32;
+const SAMPLES: usize = 1 << 14;
+
+/// Points of the unit square falling in the quarter circle.
+fn hits<R: Rng + ?Sized>(rng: &mut R) -> u64 {
+    (0..SAMPLES).filter(|_| {
+        let x = f64_closed_open01(rng.next_u64());
+        let y = f64_closed_open01(rng.next_u64());
+        x * x + y * y < 1.0
+    }).count() as u64
+}
+
+/// Each task's hits, in a pool of `threads` threads.
+fn run<R: Rng + Send>(threads: usize, rngs: Vec<R>) -> Vec<u64> {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    pool.install(|| par_sample(rngs, |_, rng| hits(rng)))
+}
+
+// ——— usage ———
+
+pub fn main() {
+    {
+        // Seeded from a master: the same hits at any pool size
+        let forks = || {
+            let mut master = TestRng(SplitMix(0x5EED));
+            fork_from_rng::<Xoshiro256pp, _>(&mut master, TASKS).unwrap()
+        };
+        let want = run(1, forks());
+        for &threads in [2, 4, 8].iter() {
+            assert_eq!(run(threads, forks()), want);
+        }
+        let total: u64 = want.iter().sum();
+        let pi = 4.0 * total as f64 / (TASKS * SAMPLES) as f64;
+        assert!((pi - std::f64::consts::PI).abs() < 0.02, "pi = {}", pi);
+        println!("fork_from_rng: pi ~ {:.4}, the same in pools of 1-8 threads", pi);
+    }
+    {
+        // Jumped: each task a stretch of one stream, likewise
+        let forks = || fork_by_jump(&mut Xoshiro256pp::seed_from_u64(0x5EED), TASKS);
+        let want = run(1, forks());
+        for &threads in [2, 4, 8].iter() {
+            assert_eq!(run(threads, forks()), want);
+        }
+        let total: u64 = want.iter().sum();
+        let pi = 4.0 * total as f64 / (TASKS * SAMPLES) as f64;
+        assert!((pi - std::f64::consts::PI).abs() < 0.02, "pi = {}", pi);
+        // The stretches start apart: no two tasks begin with the same word
+        let mut firsts: Vec<u64> = forks().iter_mut().map(|r| r.next_u64()).collect();
+        firsts.sort_unstable();
+        firsts.dedup();
+        assert_eq!(firsts.len(), TASKS);
+        println!("fork_by_jump: pi ~ {:.4}, the same in pools of 1-8 threads", pi);
+    }
+    {
+        // Any seedable generator will do, and the task index is passed on
+        let rngs = fork_from_rng::<TestRng<SplitMix>, _>(&mut TestRng(SplitMix(1)), 4).unwrap();
+        let out = par_sample(rngs, |i, rng| (i, rng.next_u64() != 0));
+        assert_eq!(out, vec![(0, true), (1, true), (2, true), (3, true)]);
+        // A failing master fails the fork, with its own error
+        let err = fork_from_rng::<TestRng<SplitMix>, _>(&mut TestRng(FailAfter::new(3)), 8);
+        assert!(err.is_err());
+        println!("fork_from_rng over a failing master: {:?}", err.unwrap_err());
+    }
+}
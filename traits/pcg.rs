@@ -0,0 +1,57 @@
+//! PCG32: the small, fast generator
+//! 
+//! `SplitMix` is cheap, but it is a test source, and the designs are for
+//! generators like `rand_pcg`'s: a few bytes of state, a nanosecond a word,
+//! so that a call's overhead is a fair share of its cost. `Pcg32` is that
+//! generator, PCG-XSH-RR 64/32 (O'Neill): a 64-bit LCG, its increment
+//! selecting a stream, with a 32-bit output permutation. `new` is the
+//! reference `pcg32_srandom`, as `streams.rs`'s own copy, which studies the
+//! streams.
+//! 
+//! Its native output is a `u32`. `test_rngs.rs` includes this file and makes
+//! it a `Source` whose words are two outputs, low first, as `rand_pcg`'s
+//! `next_u64`; so a role's `next_u32`, which truncates a word, steps it
+//! twice, and the native `next_u32` here is the floor to compare with. It
+//! seeds from 16 bytes, the state then the stream, and from a `u64` as the
+//! state on stream 0. Like the other shared files, it needs nothing but
+//! `core`.
+//! 
+//! This file has no `main`: `verify/golden_vectors.rs` checks it against the
+//! reference output, and `benches/call_overhead.rs` times it.
+
+const MUL: u64 = 6364136223846793005;
+
+/// PCG-XSH-RR 64/32.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pcg32 {
+    state: u64,
+    /// Always odd: `stream << 1 | 1`.
+    inc: u64,
+}
+
+impl Pcg32 {
+    /// As the reference `pcg32_srandom(state, stream)`; 63 bits of `stream`
+    /// are kept.
+    pub fn new(state: u64, stream: u64) -> Self {
+        let mut rng = Pcg32 { state: 0, inc: stream << 1 | 1 };
+        rng.step();
+        rng.state = rng.state.wrapping_add(state);
+        rng.step();
+        rng
+    }
+    
+    #[inline]
+    fn step(&mut self) {
+        self.state = self.state.wrapping_mul(MUL).wrapping_add(self.inc);
+    }
+    
+    /// The next output, native.
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
@@ -0,0 +1,382 @@
+//! raw_infallible with a default for the error parameter: `RawRng<E = Error>`
+//! (and `CryptoRng<E = Error>`), so that the common case needn't name it.
+//! A fallible generator with the common `Error` writes `impl RawRng`, and
+//! consumers write `R: RawRng` and `&mut dyn RawRng`, which every plain
+//! generator is too through the blanket impl; a generator with its own error
+//! type overrides it, as `RawRng<Exhausted>`. Stable Rust.
+//! 
+//! Methods: as raw_infallible. `RawRng` requires `try_next_u32` and provides
+//! `try_next_u64`, `try_next_u128` and `try_fill` on top of it; `Rng`
+//! provides its wider methods on top of `next_u32` (see `bridge.rs`), not of
+//! `RawRng<Infallible>`.
+//! 
+//! Thoughts: the default works wherever a type is written (impls, bounds,
+//! `dyn RawRng`), and nowhere else: it is not used for inference, so a call
+//! on a plain generator is as ambiguous as in raw_infallible. A generator
+//! with its own error type still can't be a `dyn RawRng` without an
+//! adaptor. So the default saves typing at the edges but changes nothing
+//! the design can do: the capability matrix row is raw_infallible's.
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_default_inference raw_default.rs
+//!     rustc --cfg fail_second_blanket raw_default.rs
+
+use std::convert::Infallible;
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+// What `!` gets from the compiler, written out: `Infallible` doesn't coerce
+impl From<Infallible> for Error {
+    fn from(e: Infallible) -> Error {
+        match e {}
+    }
+}
+
+pub trait RawRng<E = Error> {
+    fn try_next_u32(&mut self) -> Result<u32, E>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, E> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, E> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), E> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
+}
+
+pub trait Rng: RawRng<Infallible> {
+    fn next_u32(&mut self) -> u32 {
+        self.try_next_u32().unwrap_or_else(|e| match e {})
+    }
+    
+    // Not through `self.try_next_u64()` like `next_u32`: the blanket
+    // `RawRng<Infallible>` impl forwards `try_next_u64` here, so that would
+    // recurse forever. (As does `next_u32`, if an impl leaves it out.)
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+pub trait CryptoRng<E = Error>: RawRng<E> {}
+
+// ——— impls ———
+
+impl<R: Rng+?Sized> RawRng<Infallible> for R {
+    fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+        Ok(self.next_u32())
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Infallible> {
+        Ok(self.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Infallible> {
+        Ok(self.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Infallible> {
+        self.fill(dest);
+        Ok(())
+    }
+}
+
+// This impl allows infallible generators to use the fallible trait, but means
+// `t.try_next_u32()` resolves to two functions (conflict); the default
+// doesn't choose between them (see `fail_default_inference`)
+impl<R: RawRng<Infallible>+?Sized> RawRng for R {
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        self.try_next_u32().map_err(Error::from)
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        self.try_next_u64().map_err(Error::from)
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        self.try_next_u128().map_err(Error::from)
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.try_fill(dest).map_err(Error::from)
+    }
+}
+
+impl<R: CryptoRng<Infallible>+?Sized> CryptoRng for R {}
+
+#[cfg(fail_second_blanket)]
+mod second_blanket {
+    use super::*;
+    use super::test_rngs::Exhausted;
+    
+    // Error: conflicts with the impl above, for a type implementing both
+    // `RawRng<Infallible>` and `RawRng<Exhausted>`: a generator with its own
+    // error type can't join the default
+    impl<R: RawRng<Exhausted>+?Sized> RawRng for R {
+        fn try_next_u32(&mut self) -> Result<u32, Error> {
+            RawRng::<Exhausted>::try_next_u32(self)
+                .map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))
+        }
+    }
+}
+
+// The blanket impls above make these `RawRng<Infallible>` and `RawRng` too.
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+// Fallible crypto generators can't forward: `RawRng for &mut R` would
+// overlap the blanket impl, for an infallible `R`.
+impl<R: CryptoRng<Infallible>+Rng+?Sized> CryptoRng<Infallible> for &mut R {}
+impl<R: CryptoRng<Infallible>+Rng+?Sized> CryptoRng<Infallible> for Box<R> {}
+
+// ——— adaptor ———
+
+// Given `rng` of type `T` where `T: CryptoRng`, this can consume
+// `&mut rng` (`as_rng(rng)`)
+pub fn as_rng_ref<'a, CR: RawRng+?Sized+'a>(rng: &'a mut CR) -> AsRng<'a, CR> {
+    AsRng { rng }
+}
+
+pub struct AsRng<'a, CR: RawRng+?Sized+'a> {
+    rng: &'a mut CR
+}
+
+impl<'a, CR: RawRng+?Sized> Rng for AsRng<'a, CR> {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.try_next_u32().unwrap()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.try_fill(dest).unwrap()
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, Exhausted, FailAfter, Source, TestCRng, TestFCRng, TestICRng, TestRng};
+
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// An infallible CryptoRng
+impl<S: Source> Rng for TestICRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+impl<S: Source> CryptoRng<Infallible> for TestICRng<S> {}
+
+// A fallible CryptoRng, with the default error type
+impl<S: Source> RawRng for TestFCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, Error> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+impl<S: Source> CryptoRng for TestFCRng<S> {}
+
+// A fallible CryptoRng with its own error type, overriding the default
+impl<S: Source> RawRng<Exhausted> for TestCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, Exhausted> {
+        self.0.try_word().map(|x| x as u32)
+    }
+}
+
+impl<S: Source> CryptoRng<Exhausted> for TestCRng<S> {}
+
+// ——— consumers ———
+
+// `RawRng` here is `RawRng<Error>`: any generator with the default error
+fn first_word<R: RawRng+?Sized>(rng: &mut R) -> Result<u32, Error> {
+    rng.try_next_u32()
+}
+
+#[cfg(fail_default_inference)]
+fn default_inference() {
+    // Error: type annotations needed; `t` implements `RawRng<Infallible>` and
+    // `RawRng<Error>`, and the default is not used to choose
+    let _ = TestRng(Constant(1)).try_next_u32();
+}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut ic = TestICRng(Constant(17));
+    let mut fc = TestFCRng(Constant(42));
+    let mut c = TestCRng(Constant(7));
+    println!("t: {:?} impls Rng", t);
+    println!("ic: {:?} impls CryptoRng<Infallible>", ic);
+    println!("fc: {:?} impls CryptoRng", fc);
+    println!("c: {:?} impls CryptoRng<Exhausted>", c);
+    {
+        // Do all traits support all functions via static dispatch? The
+        // default applies to `<T as RawRng>`, not to a method call
+        println!("t, static dispatch, using RawRng: {:?}", <TestRng as RawRng>::try_next_u32(&mut t));
+        println!("t, static dispatch, using Rng: {:?}", t.next_u32());
+        println!("ic, static dispatch, using RawRng: {:?}", <TestICRng as RawRng>::try_next_u32(&mut ic));
+        println!("ic, static dispatch, using Rng: {:?}", as_rng_ref(&mut ic).next_u32());
+        println!("fc, static dispatch, using RawRng: {:?}", fc.try_next_u32());
+        println!("fc, static dispatch, using Rng: {:?}", as_rng_ref(&mut fc).next_u32());
+        println!("c, static dispatch, using RawRng<Exhausted>: {:?}", c.try_next_u32());
+    }
+    {
+        // A generic consumer over the default takes t, ic and fc unannotated
+        println!("t, ic, fc, static dispatch, generic over RawRng: {:?}, {:?}, {:?}",
+                first_word(&mut t), first_word(&mut ic), first_word(&mut fc));
+    }
+    {
+        // Can all types be used via RawRng with dynamic dispatch?
+        let ir = &mut ic as &mut RawRng;
+        println!("ic, dynamic dispatch, using RawRng: {:?}", ir.try_next_u32());
+        let fr = &mut fc as &mut RawRng;
+        println!("fc, dynamic dispatch, using RawRng: {:?}", fr.try_next_u32());
+        let tr = &mut t as &mut RawRng;
+        println!("t, dynamic dispatch, using RawRng: {:?}", tr.try_next_u32());
+        // c only with its own error type
+        let cr = &mut c as &mut RawRng<Exhausted>;
+        println!("c, dynamic dispatch, using RawRng<Exhausted>: {:?}", cr.try_next_u32());
+    }
+    {
+        // Can all types be used via Rng with dynamic dispatch?
+        let ir = &mut ic as &mut Rng;
+        let mut fr = as_rng_ref(&mut fc as &mut CryptoRng);
+        let tr = &mut t as &mut Rng;
+        println!("ic, dynamic dispatch, using Rng: {:?}", ir.next_u32());
+        println!("fc, dynamic dispatch, using Rng: {:?}", fr.next_u32());
+        println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
+    }
+    {
+        // Can both crypto RNGs be used via CryptoRng with dynamic dispatch?
+        let ir = &mut ic as &mut CryptoRng;
+        let fr = &mut fc as &mut CryptoRng;
+        println!("ic, dynamic dispatch, using CryptoRng: {:?}", ir.try_next_u32());
+        println!("fc, dynamic dispatch, using CryptoRng: {:?}", fr.try_next_u32());
+    }
+    {
+        // A source failing after one word: the error reaches the caller,
+        // with either error type
+        let mut f = TestFCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32(), Ok(0));
+        assert_eq!(f.try_next_u32(), Err(Exhausted));
+        println!("f, failing after one word, using RawRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket RawRng impl forwards each method
+        let mut t = TestRng(Counting(1));
+        assert_eq!(<TestRng<Counting> as RawRng>::try_next_u64(&mut t).unwrap(), 2 << 32 | 1);
+        println!("t, provided methods via RawRng: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestFCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        // An infallible crypto generator, and not as a trait object: a
+        // fallible one can't be forwarded, and `dyn CryptoRng<Infallible>` isn't
+        // an `Rng`
+        fn use_crng<R: CryptoRng<Infallible>>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng<Infallible>: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestICRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(Box::new(c), "by Box");
+    }
+}
@@ -1,31 +1,66 @@
 //! Tries to unify the two traits via templating on the error type and using
 //! the never type (still experimental). Tests have shown no performance
-//! overhead.
+//! overhead (see `benches/call_overhead.rs`).
 //! 
 //! Rng exists as a separate trait only so that users don't have to unwrap
 //! the `Result<T, !>` type themselves.
 //! 
-//! Note: this *only* considers the next_u32 member function.
+//! Methods: `RawRng` requires `try_next_u32` and provides `try_next_u64`,
+//! `try_next_u128` and `try_fill` on top of it; `Rng` provides its wider
+//! methods on top of `next_u32` (see `bridge.rs`), not of `RawRng<!>`.
 //! 
 //! Thoughts: better than I had expected. A little complex. Might be workable.
 #![feature(never_type)]
 
 // ——— traits ———
 
-#[derive(Debug)]
-struct Error;
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
 
-trait RawRng<E> {
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error, ErrorKind};
+
+pub trait RawRng<E> {
     fn try_next_u32(&mut self) -> Result<u32, E>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, E> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, E> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), E> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
 }
 
-trait Rng: RawRng<!> {
+pub trait Rng: RawRng<!> {
     fn next_u32(&mut self) -> u32 {
         self.try_next_u32().unwrap_or_else(|e| e)
     }
+    
+    // Not `self.try_next_u64().unwrap_or_else(|e| e)` like `next_u32`: the
+    // blanket `RawRng<!>` impl forwards `try_next_u64` here, so that would
+    // recurse forever. (As does `next_u32`, if an impl leaves it out.)
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
 }
 
-trait CryptoRng<E>: RawRng<E> {}
+pub trait CryptoRng<E>: RawRng<E> {}
 
 // ——— impls ———
 
@@ -33,6 +68,19 @@ impl<R: Rng+?Sized> RawRng<!> for R {
     fn try_next_u32(&mut self) -> Result<u32, !> {
         Ok(self.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, !> {
+        Ok(self.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, !> {
+        Ok(self.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), !> {
+        self.fill(dest);
+        Ok(())
+    }
 }
 
 // This impl allows infallible generators to use the fallible trait, but means
@@ -41,19 +89,73 @@ impl<R: RawRng<!>+?Sized> RawRng<Error> for R {
     fn try_next_u32(&mut self) -> Result<u32, Error> {
         self.try_next_u32().map_err(|e| e)
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        self.try_next_u64().map_err(|e| e)
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        self.try_next_u128().map_err(|e| e)
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.try_fill(dest).map_err(|e| e)
+    }
 }
 
 impl<R: CryptoRng<!>+?Sized> CryptoRng<Error> for R {}
 
+// The blanket impls above make these `RawRng<!>` and `RawRng<Error>` too.
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+// Fallible crypto generators can't forward: `RawRng<Error> for &mut R` would
+// overlap the blanket impl, for an infallible `R`.
+impl<R: CryptoRng<!>+Rng+?Sized> CryptoRng<!> for &mut R {}
+impl<R: CryptoRng<!>+Rng+?Sized> CryptoRng<!> for Box<R> {}
+
 // ——— adaptor ———
 
 // Given `rng` of type `T` where `T: CryptoRng`, this can consume
 // `&mut rng` (`as_rng(rng)`)
-fn as_rng_ref<'a, CR: RawRng<Error>+?Sized+'a>(rng: &'a mut CR) -> AsRng<'a, CR> {
+pub fn as_rng_ref<'a, CR: RawRng<Error>+?Sized+'a>(rng: &'a mut CR) -> AsRng<'a, CR> {
     AsRng { rng }
 }
 
-struct AsRng<'a, CR: RawRng<Error>+?Sized+'a> {
+pub struct AsRng<'a, CR: RawRng<Error>+?Sized+'a> {
     rng: &'a mut CR
 }
 
@@ -61,50 +163,59 @@ impl<'a, CR: RawRng<Error>+?Sized> Rng for AsRng<'a, CR> {
     fn next_u32(&mut self) -> u32 {
         self.rng.try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.try_fill(dest).unwrap()
+    }
 }
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestFCRng, TestICRng, TestRng};
 
-impl Rng for TestRng {
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // An infallible CryptoRng
-#[derive(Debug)]
-struct TestICRng(u32);
-
-impl Rng for TestICRng {
+impl<S: Source> Rng for TestICRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
-impl CryptoRng<!> for TestICRng {}
+impl<S: Source> CryptoRng<!> for TestICRng<S> {}
 
 // A fallible CryptoRng
-#[derive(Debug)]
-struct TestFCRng(u32);
-
-impl RawRng<Error> for TestFCRng {
+impl<S: Source> RawRng<Error> for TestFCRng<S> {
     fn try_next_u32(&mut self) -> Result<u32, Error> {
-        Ok(self.0)
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| Error::new(ErrorKind::Unavailable, "test source exhausted"))
     }
 }
 
-impl CryptoRng<Error> for TestFCRng {}
+impl<S: Source> CryptoRng<Error> for TestFCRng<S> {}
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut ic = TestICRng(17);
-    let mut fc = TestFCRng(42);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut ic = TestICRng(Constant(17));
+    let mut fc = TestFCRng(Constant(42));
     println!("t: {:?} impls Rng", t);
     println!("ic: {:?} impls CryptoRng", ic);
     println!("fc: {:?} impls CryptoRng", fc);
@@ -143,4 +254,55 @@ fn main() {
         println!("fc, dynamic dispatch, using CryptoRng: {:?}", fr.try_next_u32());
         
     }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestFCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using RawRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket RawRng<Error> impl forwards each method
+        let mut t = TestRng(Counting(1));
+        assert_eq!(RawRng::<Error>::try_next_u64(&mut t).unwrap(), 2 << 32 | 1);
+        println!("t, provided methods via RawRng<Error>: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestFCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        // An infallible crypto generator, and not as a trait object: a
+        // fallible one can't be forwarded, and `dyn CryptoRng<!>` isn't
+        // an `Rng`
+        fn use_crng<R: CryptoRng<!>>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng<!>: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestICRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(Box::new(c), "by Box");
+    }
 }
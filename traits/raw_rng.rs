@@ -1,35 +1,74 @@
 //! Tries to unify the two traits via templating on the error type and using
 //! the never type (still experimental). Tests have shown no performance
-//! overhead.
+//! overhead (see `benches/call_overhead.rs`).
 //! 
 //! Rng exists as a separate trait only so that users don't have to unwrap
 //! the `Result<T, !>` type themselves.
 //! 
-//! Note: this *only* considers the next_u32 member function.
+//! Methods: `RawRng` requires `try_next_u32` and provides `try_next_u64`,
+//! `try_next_u128` and `try_fill` on top of it; `Rng` provides its wider
+//! methods on top of `next_u32` (see `bridge.rs`), not of `RawRng<!>`.
 //! 
 //! Thoughts: a common super-trait which is not object safe doesn't really help
 //! anything(?). At the same time, it's no longer possible to make one version
 //! implement the other, so IMO this is strictly worse than extends_CryptoRng2.
 //! And don't forget, this also depends on an unstable language feature.
+//! 
+//! The full method set shows a trap: the blanket `RawRng<!>` impl forwards to
+//! `Rng`, so `Rng`'s defaults can't go back through `RawRng<!>` (as
+//! `next_u32`'s does: an `Rng` leaving it out recurses forever).
 
 #![feature(never_type)]
 
 // ——— traits ———
 
-trait RawRng<Error> {
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+pub trait RawRng<Error> {
     fn try_next_u32(&mut self) -> Result<u32, Error>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, Error> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, Error> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
 }
 
-trait Rng: RawRng<!> {
+pub trait Rng: RawRng<!> {
     fn next_u32(&mut self) -> u32 {
         self.try_next_u32().unwrap_or_else(|e| e)
     }
+    
+    // Not `self.try_next_u64().unwrap_or_else(|e| e)` like `next_u32`: the
+    // blanket `RawRng<!>` impl forwards `try_next_u64` here, so that would
+    // recurse forever. (As does `next_u32`, if an impl leaves it out.)
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
 }
 
-#[derive(Debug)]
-struct CryptoError;
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
 
-type CryptoRng = RawRng<CryptoError>;
+pub type CryptoRng = RawRng<CryptoError>;
 
 // ——— impls ———
 
@@ -37,24 +76,104 @@ impl<R: Rng+?Sized> RawRng<!> for R {
     fn try_next_u32(&mut self) -> Result<u32, !> {
         Ok(self.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, !> {
+        Ok(self.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, !> {
+        Ok(self.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), !> {
+        self.fill(dest);
+        Ok(())
+    }
 }
 
 // Required for `as_rng(&mut rng)` and `as_rng_ref` definition.
-impl<'a, CR: RawRng<CryptoError>+?Sized> RawRng<CryptoError> for &'a mut CR {
+impl<CR: RawRng<CryptoError>+?Sized> RawRng<CryptoError> for &mut CR {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         (*self).try_next_u32()
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (*self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (*self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (*self).try_fill(dest)
+    }
+}
+
+impl<CR: RawRng<CryptoError>+?Sized> RawRng<CryptoError> for Box<CR> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
+}
+
+// The blanket impl above makes these `RawRng<!>` too.
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
 }
 
 // ——— adaptor ———
 
 // Given `rng` of type `T` where `T: CryptoRng`, this can consume
 // `rng` (`as_rng(rng)`)
-fn as_rng<CR: RawRng<CryptoError>>(rng: CR) -> AsRng<CR> {
+pub fn as_rng<CR: RawRng<CryptoError>>(rng: CR) -> AsRng<CR> {
     AsRng { rng }
 }
 
-struct AsRng<CR: RawRng<CryptoError>+?Sized> {
+pub struct AsRng<CR: RawRng<CryptoError>+?Sized> {
     rng: CR
 }
 
@@ -62,35 +181,47 @@ impl<CR: RawRng<CryptoError>+?Sized> Rng for AsRng<CR> {
     fn next_u32(&mut self) -> u32 {
         self.rng.try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.try_fill(dest).unwrap()
+    }
 }
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestCRng, TestRng};
 
-impl Rng for TestRng {
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // A CryptoRng
-#[derive(Debug)]
-struct TestCRng(u32);
-
-impl RawRng<CryptoError> for TestCRng {
+impl<S: Source> RawRng<CryptoError> for TestCRng<S> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
     }
 }
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut c = TestCRng(42);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
     println!("t: {:?} impls Rng", t);
     println!("c: {:?} impls CryptoRng", c);
     {
@@ -117,4 +248,53 @@ fn main() {
         println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
         println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
     }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using RawRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The adaptor: the try_ methods, unwrapped
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(as_rng(&mut c).next_u64(), 2 << 32 | 1);
+        println!("as_rng(c), provided methods: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: RawRng<CryptoError>>(mut r: R, how: &str) {
+            println!("c, {}, using RawRng<CryptoError>: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn RawRng<CryptoError>, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn RawRng<CryptoError>>, "by Box<dyn>");
+    }
 }
@@ -0,0 +1,100 @@
+//! Seeding designs, shared by the variants
+//! 
+//! Three ways to construct a generator from a seed:
+//! 
+//! *   `SeedableRng`: the seed is an associated type, a byte array in
+//!     practice (`type Seed: AsMut<[u8]> + Default`), so its length is known
+//!     at compile time, and generic code can make one (`seed_from_u64`)
+//! *   `FromSeedSlice`: the seed is any `&[u8]`, checked at run time against
+//!     `SEED_LEN`
+//! *   `SeedFrom<S>`: one impl per seed type, so a generator can take both a
+//!     byte array and, say, a `u64`
+//! 
+//! None of them mention `Rng` or `CryptoRng`: seeding is a constructor, and
+//! doesn't depend on how the generator is then used. `test_rngs.rs` includes
+//! this file and implements all three for its sources (the seed is the
+//! source's state, little-endian) and, forwarding, for its roles; so every
+//! variant's test generators are seedable, and `bin/compare.rs` checks that
+//! each variant's traits accept them.
+//! 
+//!     #[path = "seeding.rs"]
+//!     pub mod seeding;
+//! 
+//! Thoughts: `SeedableRng` is the one to keep. Its seed can't be the wrong
+//! length, `Default` and `AsMut<[u8]>` are all generic code needs to build
+//! one (from a `u64`, another generator or the OS), and the bound reads
+//! well (`R: SeedableRng`). Its cost is that the length is fixed per type: a
+//! generator accepting 16 or 32 byte keys needs a type for each.
+//! `FromSeedSlice` takes any length, but moves the check to run time, and
+//! every caller must handle a `SeedLenError` which a correct program never
+//! sees. `SeedFrom<S>` is the most flexible and the least usable generically:
+//! `R: SeedFrom<S>` says nothing about how to make an `S`, so generic code
+//! must name the seed type itself, at which point it is `SeedableRng` with
+//! more typing. It suits concrete convenience constructors only.
+//! 
+//! This file has no `main`: the variants including it exercise it.
+
+use std::fmt;
+
+// ——— SeedableRng ———
+
+pub trait SeedableRng: Sized {
+    /// The seed: a byte array of the generator's seed length.
+    type Seed: AsMut<[u8]> + Default;
+    
+    fn from_seed(seed: Self::Seed) -> Self;
+    
+    /// A seed expanded from `state` (by SplitMix64), for tests and
+    /// reproducible simulations; not for anything needing security.
+    fn seed_from_u64(mut state: u64) -> Self {
+        let mut seed = Self::Seed::default();
+        for chunk in seed.as_mut().chunks_mut(8) {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^= z >> 31;
+            let n = chunk.len();
+            chunk.copy_from_slice(&z.to_le_bytes()[..n]);
+        }
+        Self::from_seed(seed)
+    }
+}
+
+// ——— seed slices ———
+
+/// The seed slice had the wrong length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedLenError {
+    pub expected: usize,
+    pub got: usize,
+}
+
+impl fmt::Display for SeedLenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "seed of {} bytes, expected {}", self.got, self.expected)
+    }
+}
+
+pub trait FromSeedSlice: Sized {
+    const SEED_LEN: usize;
+    
+    /// Fails unless `seed` is `SEED_LEN` bytes long.
+    fn from_seed_slice(seed: &[u8]) -> Result<Self, SeedLenError>;
+}
+
+/// Checks `seed` is `N` bytes long, as an array.
+pub fn seed_array<const N: usize>(seed: &[u8]) -> Result<[u8; N], SeedLenError> {
+    let mut array = [0; N];
+    if seed.len() != N {
+        return Err(SeedLenError { expected: N, got: seed.len() });
+    }
+    array.copy_from_slice(seed);
+    Ok(array)
+}
+
+// ——— generic seed types ———
+
+pub trait SeedFrom<S>: Sized {
+    fn seed_from(seed: S) -> Self;
+}
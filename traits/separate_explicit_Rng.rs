@@ -3,7 +3,9 @@
 //! This uses a wrapper, `AsRng`, implementing `Rng` for any `CryptoRng`.
 //! (This wrapper can be used with other variants.)
 //! 
-//! Note: this *only* considers the next_u32 member function
+//! Methods: `Rng` requires `next_u32`, `CryptoRng` requires `try_next_u32`,
+//! and each provides the wider methods on top (see `bridge.rs`). The adaptors
+//! forward each method.
 //! 
 //! Thoughts: works fine; relatively simple surprise-free code.
 //! Adaptors needed in both directions. This (and `raw_rng` which also needs
@@ -12,42 +14,132 @@
 
 // ——— traits ———
 
-#[derive(Debug)]
-struct CryptoError;
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
 
-trait CryptoRng {
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
+
+pub trait CryptoRng {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
 }
 
-trait Rng {
+pub trait Rng {
     fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
 }
 
 // ——— impl ———
 
 // Required for `as_rng(&mut rng)`
-impl<'a, CR: CryptoRng+?Sized> CryptoRng for &'a mut CR {
+impl<CR: CryptoRng+?Sized> CryptoRng for &mut CR {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         (*self).try_next_u32()
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (*self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (*self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (*self).try_fill(dest)
+    }
+}
+
+impl<CR: CryptoRng+?Sized> CryptoRng for Box<CR> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
 }
 
 // Required for `as_crng(&mut rng)`
-impl<'a, R: Rng+?Sized> Rng for &'a mut R {
+impl<R: Rng+?Sized> Rng for &mut R {
     fn next_u32(&mut self) -> u32 {
         (*self).next_u32()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        (*self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (*self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (*self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
 }
 
 // ——— adaptor 1 ———
 
 // Given `rng` of type `T` where `T: CryptoRng`, this can consume
 // `rng` (`as_rng(rng)`) or use a reference (`as_rng(&mut rng)`).
-fn as_rng<CR: CryptoRng>(rng: CR) -> AsRng<CR> {
+pub fn as_rng<CR: CryptoRng>(rng: CR) -> AsRng<CR> {
     AsRng { rng }
 }
 
-struct AsRng<CR: CryptoRng+?Sized> {
+pub struct AsRng<CR: CryptoRng+?Sized> {
     rng: CR
 }
 
@@ -55,6 +147,18 @@ impl<CR: CryptoRng+?Sized> Rng for AsRng<CR> {
     fn next_u32(&mut self) -> u32 {
         self.rng.try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.rng.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.rng.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.rng.try_fill(dest).unwrap()
+    }
 }
 
 // ——— adaptor 2 ———
@@ -73,53 +177,64 @@ impl<R: Rng+?Sized> CryptoRng for AsCRng<R> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         Ok(self.rng.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.rng.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.rng.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.rng.fill(dest);
+        Ok(())
+    }
 }
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestBothRng, TestCRng, TestRng};
 
-impl Rng for TestRng {
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // A CryptoRng
-#[derive(Debug)]
-struct TestCRng(u32);
-
-impl CryptoRng for TestCRng {
+impl<S: Source> CryptoRng for TestCRng<S> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
     }
 }
 
 // A dual-purpose Rng
-#[derive(Debug)]
-struct TestBothRng(u32);
-
-impl Rng for TestBothRng {
+impl<S: Source> Rng for TestBothRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
-impl CryptoRng for TestBothRng {
+impl<S: Source> CryptoRng for TestBothRng<S> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
     }
 }
 
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut c = TestCRng(42);
-    let mut b = TestBothRng(3651);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
+    let mut b = TestBothRng(Constant(3651));
     println!("t: {:?} impls Rng", t);
     println!("c: {:?} impls CryptoRng", c);
     println!("b: {:?} impls both", b);
@@ -150,4 +265,53 @@ fn main() {
         println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
         println!("b, dynamic dispatch, using Rng: {:?}", br.next_u32());
     }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using CryptoRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The adaptor: the try_ methods, unwrapped
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(as_rng(&mut c).next_u64(), 2 << 32 | 1);
+        println!("as_rng(c), provided methods: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        fn use_rng<R: Rng>(mut r: R, how: &str) {
+            println!("t, {}, using Rng: {:?}", how, r.next_u32());
+        }
+        let mut t = TestRng(Constant(13));
+        use_rng(&mut t, "by &mut");
+        use_rng(&mut t as &mut dyn Rng, "by &mut dyn");
+        use_rng(Box::new(t) as Box<dyn Rng>, "by Box<dyn>");
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
 }
@@ -2,24 +2,62 @@
 //! 
 //! This implements `Rng` for any `CryptoRng` implicitly.
 //! 
-//! Note: this *only* considers the next_u32 member function
+//! Methods: `Rng` requires `next_u32`, `CryptoRng` requires `try_next_u32`,
+//! and each provides the wider methods on top (see `bridge.rs`). The blanket
+//! `Rng` impl forwards each method to its `try_` counterpart.
 //! 
 //! Thoughts: it's impossible for any one type to implement both traits
-//! optimally. It's also impossible to impl Rng for &mut Rng.
+//! optimally. It's also impossible to impl Rng for &mut Rng. The full method
+//! set makes the first worse: a crypto generator gets every `Rng` method from
+//! the blanket impl, so none of them can be overridden.
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc --cfg fail_dual_impl separate_implicit_Rng3.rs
 
 #![allow(unused)]
 
 // ——— traits ———
 
-#[derive(Debug)]
-struct CryptoError;
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
 
-trait CryptoRng {
+pub trait CryptoRng {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
 }
 
-trait Rng {
+pub trait Rng {
     fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
 }
 
 // ——— impls ———
@@ -28,12 +66,54 @@ impl<CR: CryptoRng+?Sized> Rng for CR {
     fn next_u32(&mut self) -> u32 {
         self.try_next_u32().unwrap()
     }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.try_next_u64().unwrap()
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        self.try_next_u128().unwrap()
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap()
+    }
 }
 
-impl<'a, R: CryptoRng+?Sized> CryptoRng for &'a mut R {
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         (*self).try_next_u32()
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (*self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (*self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (*self).try_fill(dest)
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
 }
 
 // ——— adaptor ———
@@ -52,6 +132,19 @@ impl<R: Rng+?Sized> CryptoRng for AsCRng<R> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         Ok(self.rng.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.rng.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.rng.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.rng.fill(dest);
+        Ok(())
+    }
 }
 
 // Given `rng` of type `T` where `T: Rng`, this can consume
@@ -68,55 +161,70 @@ impl<'a, R: Rng+?Sized> CryptoRng for AsCRngRef<'a, R> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
         Ok(self.rng.next_u32())
     }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.rng.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.rng.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.rng.fill(dest);
+        Ok(())
+    }
 }
 
 // ——— test RNGs ———
 
-// A non-crypto Rng
-#[derive(Debug)]
-struct TestRng(u32);
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestCRng, TestRng};
 
-impl Rng for TestRng {
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
     fn next_u32(&mut self) -> u32 {
-        self.0
+        self.0.word() as u32
     }
 }
 
 // A CryptoRng
-#[derive(Debug)]
-struct TestCRng(u32);
-
-impl CryptoRng for TestCRng {
+impl<S: Source> CryptoRng for TestCRng<S> {
     fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
     }
 }
 
-/*
 // An RNG supporting both interfaces
-#[derive(Debug)]
-struct TestBothRng(u32);
-
-impl CryptoRng for TestBothRng {
-    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
-        Ok(self.0)
+#[cfg(fail_dual_impl)]
+mod dual {
+    use super::*;
+    use super::test_rngs::TestBothRng;
+    
+    impl<S: Source> CryptoRng for TestBothRng<S> {
+        fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+            self.0.try_word().map(|x| x as u32)
+                .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
+        }
     }
-}
-
-// Error: conflicts with `impl<CR: CryptoRng+?Sized> Rng for CR`
-impl Rng for TestBothRng {
-    fn next_u32(&mut self) -> u32 {
-        self.0
+    
+    // Error: conflicts with `impl<CR: CryptoRng+?Sized> Rng for CR`
+    impl<S: Source> Rng for TestBothRng<S> {
+        fn next_u32(&mut self) -> u32 {
+            self.0.word() as u32
+        }
     }
 }
-*/
 
 
 // ——— usage ———
 
-fn main() {
-    let mut t = TestRng(13);
-    let mut c = TestCRng(42);
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
     println!("t: {:?} impls Rng", t);
     println!("c: {:?} impls CryptoRng", c);
     {
@@ -140,4 +248,47 @@ fn main() {
         println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
         println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
     }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using CryptoRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket Rng impl: the try_ methods, unwrapped
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(c.next_u64(), 2 << 32 | 1);
+        println!("c, provided methods via Rng: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        // No forwarding for `Rng`: it would overlap the blanket impl
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
 }
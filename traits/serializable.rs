@@ -0,0 +1,267 @@
+//! Checkpointing: a `SerializableRng` bound, over four designs
+//! 
+//! A simulation saves its generators' state mid-run and restores it later,
+//! going on with the same stream. With serde that is a bound, `Serialize +
+//! DeserializeOwned`, on the generator; with the `serde` feature the test
+//! generators derive both. Each of `marker_only`, `extends_Rng`,
+//! `separate_explicit_Rng` and `raw_infallible` is included whole, with a
+//! `SerializableRng` over its traits. Compile-fail check:
+//! `fail_dyn_serializable`.
+//! 
+//! Thoughts: no design precludes checkpointing, as state is below the traits.
+//! What costs is in every design: a generator behind `dyn` needs a trait of
+//! its own (`CheckpointRng`), restoring only into its own type.
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+extern crate serde;
+extern crate serde_json;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[path = "marker_only.rs"]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+
+#[path = "extends_Rng.rs"]
+#[allow(dead_code, unused_imports)]
+mod extends_rng;
+
+#[path = "separate_explicit_Rng.rs"]
+#[allow(dead_code)]
+mod separate_explicit_rng;
+
+#[path = "raw_infallible.rs"]
+#[allow(dead_code)]
+mod raw_infallible;
+
+// ——— checkpoints ———
+
+/// A generator's state, as JSON.
+pub fn checkpoint<R: Serialize + ?Sized>(rng: &R) -> Result<String, serde_json::Error> {
+    serde_json::to_string(rng)
+}
+
+/// A generator in the state `checkpoint` saved.
+pub fn restore<R: DeserializeOwned>(state: &str) -> Result<R, serde_json::Error> {
+    serde_json::from_str(state)
+}
+
+/// `n` outputs of `next` from `rng`, and `n` from the generator restored
+/// from its state before them.
+fn replay<R, T, F>(rng: &mut R, n: usize, mut next: F) -> (Vec<T>, Vec<T>)
+    where R: Serialize + DeserializeOwned, F: FnMut(&mut R) -> T
+{
+    let state = checkpoint(rng).unwrap();
+    let words = (0..n).map(|_| next(rng)).collect();
+    let mut restored: R = restore(&state).unwrap();
+    let again = (0..n).map(|_| next(&mut restored)).collect();
+    (words, again)
+}
+
+// ——— designs ———
+
+mod on_marker_only {
+    use super::marker_only::{CryptoRng, Rng};
+    use super::replay;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    
+    pub trait SerializableRng: Rng + Serialize + DeserializeOwned {}
+    
+    impl<R: Rng + Serialize + DeserializeOwned> SerializableRng for R {}
+    
+    /// Words after a checkpoint, from the generator and from the restored one.
+    pub fn replay_words<R: SerializableRng>(rng: &mut R, n: usize) -> (Vec<u64>, Vec<u64>) {
+        replay(rng, n, |r| r.next_u64())
+    }
+    
+    /// Keys drawn in turn, `None` from the first failure.
+    pub type Keys = Vec<Option<[u8; 8]>>;
+    
+    /// Keys after a checkpoint, from the generator and from the restored
+    /// one.
+    pub fn replay_keys<R: SerializableRng + CryptoRng>(rng: &mut R, n: usize) -> (Keys, Keys) {
+        replay(rng, n, |r| {
+            let mut key = [0; 8];
+            r.try_fill(&mut key).ok().map(|()| key)
+        })
+    }
+    
+    /// Checkpointing behind `dyn`: saving needs no type, and restoring is
+    /// into a generator of the saved type, in place.
+    pub trait CheckpointRng: Rng {
+        fn checkpoint(&self) -> Result<String, ::serde_json::Error>;
+        
+        fn restore(&mut self, state: &str) -> Result<(), ::serde_json::Error>;
+    }
+    
+    impl<R: SerializableRng> CheckpointRng for R {
+        fn checkpoint(&self) -> Result<String, ::serde_json::Error> {
+            super::checkpoint(self)
+        }
+        
+        fn restore(&mut self, state: &str) -> Result<(), ::serde_json::Error> {
+            *self = super::restore(state)?;
+            Ok(())
+        }
+    }
+}
+
+mod on_extends_rng {
+    use super::extends_rng::{CryptoRng, Rng};
+    use super::replay;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    
+    pub trait SerializableRng: Rng + Serialize + DeserializeOwned {}
+    
+    impl<R: Rng + Serialize + DeserializeOwned> SerializableRng for R {}
+    
+    pub fn replay_words<R: SerializableRng>(rng: &mut R, n: usize) -> (Vec<u64>, Vec<u64>) {
+        replay(rng, n, |r| r.next_u64())
+    }
+    
+    // A crypto generator is an `Rng`, so the one bound covers it
+    pub fn replay_crypto<R: SerializableRng + CryptoRng>(rng: &mut R, n: usize) -> (Vec<u64>, Vec<u64>) {
+        replay(rng, n, |r| r.try_next_u64().unwrap())
+    }
+}
+
+mod on_separate_explicit_rng {
+    use super::replay;
+    use super::separate_explicit_rng::{CryptoRng, Rng};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    
+    pub trait SerializableRng: Rng + Serialize + DeserializeOwned {}
+    
+    impl<R: Rng + Serialize + DeserializeOwned> SerializableRng for R {}
+    
+    // Independent traits: a second bound, for the second trait
+    pub trait SerializableCryptoRng: CryptoRng + Serialize + DeserializeOwned {}
+    
+    impl<R: CryptoRng + Serialize + DeserializeOwned> SerializableCryptoRng for R {}
+    
+    pub fn replay_words<R: SerializableRng>(rng: &mut R, n: usize) -> (Vec<u64>, Vec<u64>) {
+        replay(rng, n, |r| r.next_u64())
+    }
+    
+    pub fn replay_crypto<R: SerializableCryptoRng>(rng: &mut R, n: usize) -> (Vec<u64>, Vec<u64>) {
+        replay(rng, n, |r| r.try_next_u64().unwrap())
+    }
+}
+
+mod on_raw_infallible {
+    use super::raw_infallible::RawRng;
+    use super::replay;
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    
+    pub trait SerializableRng<E>: RawRng<E> + Serialize + DeserializeOwned {}
+    
+    impl<E, R: RawRng<E> + Serialize + DeserializeOwned> SerializableRng<E> for R {}
+    
+    /// Words after a checkpoint, or `None` from the first failure; from the
+    /// generator and from the restored one.
+    pub fn replay_words<E, R: SerializableRng<E>>(rng: &mut R, n: usize)
+        -> (Vec<Option<u64>>, Vec<Option<u64>>)
+    {
+        replay(rng, n, |r| r.try_next_u64().ok())
+    }
+}
+
+// ——— compile-fail cases ———
+
+// `Serialize::serialize` is generic, and `DeserializeOwned` needs `Sized`:
+// the trait can't be made into an object.
+#[cfg(fail_dyn_serializable)]
+fn save(rng: &dyn on_marker_only::SerializableRng) -> String {
+    checkpoint(rng).unwrap()
+}
+
+// ——— usage ———
+
+pub fn main() {
+    {
+        // A role over a source serializes as the source's state, bare
+        use self::marker_only::test_rngs::{FailAfter, SplitMix, TestCRng, TestRng};
+        let t = TestRng(SplitMix(5));
+        assert_eq!(checkpoint(&t).unwrap(), "5");
+        let f = TestCRng(FailAfter::new(3));
+        assert_eq!(checkpoint(&f).unwrap(), r#"{"next":0,"left":3}"#);
+        println!("marker_only: TestRng(SplitMix(5)) saves as {}", checkpoint(&t).unwrap());
+    }
+    {
+        use self::marker_only::test_rngs::block::BlockRng;
+        use self::marker_only::test_rngs::{FailAfter, SplitMix, SplitMixCore, TestCRng, TestRng};
+        use self::marker_only::Rng;
+        use self::on_marker_only::{replay_keys, replay_words};
+        
+        let mut t = TestRng(SplitMix(1));
+        t.next_u64();
+        let (a, b) = replay_words(&mut t, 4);
+        assert_eq!(a, b);
+        
+        // Mid-block: the buffer and its position are state too, and the
+        // replay runs over a refill
+        let mut bt = TestRng(BlockRng::new(SplitMixCore(1)));
+        bt.next_u64();
+        let (a, b) = replay_words(&mut bt, 12);
+        assert_eq!(a, b);
+        assert_eq!(a[..4], replay_words(&mut TestRng(SplitMix(1)), 5).0[1..]);
+        
+        // A failing source, restored before its failure, fails again
+        let (a, b) = replay_keys(&mut TestCRng(FailAfter::new(2)), 3);
+        assert_eq!(a, b);
+        assert_eq!(a, [Some([0; 8]), Some([1, 0, 0, 0, 0, 0, 0, 0]), None]);
+        println!("marker_only: restored streams match, mid-block and before a failure");
+    }
+    {
+        // Behind `dyn`: save, draw, restore in place, draw the same again
+        use self::marker_only::test_rngs::{SplitMix, TestRng};
+        use self::on_marker_only::CheckpointRng;
+        let mut t = TestRng(SplitMix(9));
+        let r: &mut dyn CheckpointRng = &mut t;
+        let state = r.checkpoint().unwrap();
+        let x = r.next_u64();
+        r.restore(&state).unwrap();
+        assert_eq!(r.next_u64(), x);
+        assert!(r.restore("\"not a state\"").is_err());
+        println!("marker_only, dyn CheckpointRng: restored in place, {:#x} again", x);
+    }
+    {
+        use self::extends_rng::test_rngs::{Counting, TestCRng, TestRng};
+        use self::on_extends_rng::{replay_crypto, replay_words};
+        let (a, b) = replay_words(&mut TestRng(Counting(10)), 3);
+        assert_eq!(a, b);
+        let (a, b) = replay_crypto(&mut TestCRng(Counting(10)), 3);
+        assert_eq!(a, b);
+        println!("extends_Rng: restored streams match, plain and crypto: {:?}", a);
+    }
+    {
+        use self::on_separate_explicit_rng::{replay_crypto, replay_words};
+        use self::separate_explicit_rng::test_rngs::{SplitMix, TestBothRng};
+        // One generator, both bounds
+        let mut both = TestBothRng(SplitMix(3));
+        let (a, b) = replay_words(&mut both, 3);
+        assert_eq!(a, b);
+        let (c, d) = replay_crypto(&mut both, 3);
+        assert_eq!(c, d);
+        println!("separate_explicit_Rng: restored streams match, under each trait");
+    }
+    {
+        use self::on_raw_infallible::replay_words;
+        use self::raw_infallible::test_rngs::{FailAfter, SplitMix, TestFCRng, TestRng};
+        use self::raw_infallible::Error;
+        use std::convert::Infallible;
+        // A plain generator is `RawRng<Error>` too, so `E` must be named
+        let (a, b) = replay_words::<Infallible, _>(&mut TestRng(SplitMix(4)), 3);
+        assert_eq!(a, b);
+        let (a, b) = replay_words::<Error, _>(&mut TestFCRng(FailAfter::new(5)), 3);
+        assert_eq!(a, b);
+        assert_eq!(a, [Some(1 << 32), Some(3 << 32 | 2), None]);
+        println!("raw_infallible: restored streams match, failure included: {:?}", a);
+    }
+}
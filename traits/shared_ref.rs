@@ -0,0 +1,325 @@
+//! `&self` generators: interior mutability instead of `&mut`
+//! 
+//! Here the methods take `&self`, the state living in a `Cell`, a `RefCell`
+//! or an atomic, so any number of shared references draw from one stream:
+//! 
+//! *   `Rng` requires `next_u64(&self)` and provides `next_u32`, `next_u128`
+//!     and `fill` on top of it (see `bridge.rs`); `CryptoRng: Rng` is a
+//!     marker, as in `marker_only`. `&R`, `&mut R` and `Box<R>` forward
+//! *   `CellSplitMix` keeps its state in a `Cell<u64>`, `AtomicSplitMix` in
+//!     an `AtomicU64`; the roles over a `Source` hold it in a `RefCell`
+//! *   `thread_rng()`, and a `static` `AtomicSplitMix` seeding it
+//! 
+//! Run with:
+//! 
+//!     rustc shared_ref.rs && ./shared_ref
+//! 
+//! Compile-fail check (must fail to compile):
+//! 
+//!     rustc --cfg fail_share_cell shared_ref.rs
+//! 
+//! Thoughts: the handles win, the generators pay. A handle needs no `mut`,
+//! and a global needs no lock; but only a counter like SplitMix's is cheap
+//! to share atomically, and a buffered generator needs a lock (`locked.rs`).
+//! Every generator pays the wrapping, two holders of `&rng` interleave
+//! silently, and lending a buffer (`lending_block`) needs a guard. So `&mut
+//! self` stays right for the core traits; a handle can offer `&self`
+//! methods of its own.
+
+#![allow(unused)]
+
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+pub trait Rng {
+    fn next_u64(&self) -> u64;
+    
+    fn next_u32(&self) -> u32 {
+        self.next_u64() as u32
+    }
+    
+    fn next_u128(&self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+pub trait CryptoRng: Rng {}
+
+// ——— impls ———
+
+impl<R: Rng+?Sized> Rng for &R {
+    fn next_u64(&self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u32(&self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u128(&self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for &mut R {
+    fn next_u64(&self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u32(&self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u128(&self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: Rng+?Sized> Rng for Box<R> {
+    fn next_u64(&self) -> u64 {
+        (**self).next_u64()
+    }
+    
+    fn next_u32(&self) -> u32 {
+        (**self).next_u32()
+    }
+    
+    fn next_u128(&self) -> u128 {
+        (**self).next_u128()
+    }
+    
+    fn fill(&self, dest: &mut [u8]) {
+        (**self).fill(dest)
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for &R {}
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {}
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {}
+
+// ——— generators ———
+
+const GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+#[inline]
+fn mix(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// SplitMix64 for one thread: the state is a `Cell`, so `&self` suffices,
+/// and the generator isn't `Sync`.
+#[derive(Debug, Clone)]
+pub struct CellSplitMix(Cell<u64>);
+
+impl CellSplitMix {
+    pub const fn new(seed: u64) -> Self {
+        CellSplitMix(Cell::new(seed))
+    }
+}
+
+impl Rng for CellSplitMix {
+    #[inline]
+    fn next_u64(&self) -> u64 {
+        let state = self.0.get().wrapping_add(GAMMA);
+        self.0.set(state);
+        mix(state)
+    }
+}
+
+/// SplitMix64 for any number of threads: the state is a counter, so one
+/// `fetch_add` hands each caller its own step of the stream.
+#[derive(Debug)]
+pub struct AtomicSplitMix(AtomicU64);
+
+impl AtomicSplitMix {
+    pub const fn new(seed: u64) -> Self {
+        AtomicSplitMix(AtomicU64::new(seed))
+    }
+}
+
+impl Rng for AtomicSplitMix {
+    #[inline]
+    fn next_u64(&self) -> u64 {
+        mix(self.0.fetch_add(GAMMA, Ordering::Relaxed).wrapping_add(GAMMA))
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Counting, Source, SplitMix, TestCRng, TestRng};
+
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+
+// The roles hold their `&mut` source in a `RefCell`
+impl<S: Source> Rng for TestRng<RefCell<S>> {
+    fn next_u64(&self) -> u64 {
+        self.0.borrow_mut().word()
+    }
+}
+
+impl<S: Source> Rng for TestCRng<RefCell<S>> {
+    fn next_u64(&self) -> u64 {
+        self.0.borrow_mut().word()
+    }
+}
+
+impl<S: Source> CryptoRng for TestCRng<RefCell<S>> {}
+
+// ——— handles ———
+
+/// The global generator, a `static` with no lock; it seeds each thread's.
+pub static GLOBAL: AtomicSplitMix = AtomicSplitMix::new(0x5EED);
+
+thread_local!(
+    static THREAD_RNG: CellSplitMix = CellSplitMix::new(GLOBAL.next_u64())
+);
+
+/// A handle to this thread's generator. It holds nothing, so it is `Copy`;
+/// the marker keeps it on its thread.
+#[derive(Debug, Clone, Copy)]
+pub struct ThreadRng(PhantomData<*const ()>);
+
+/// This thread's generator, seeded from `GLOBAL` on the thread's first call.
+pub fn thread_rng() -> ThreadRng {
+    ThreadRng(PhantomData)
+}
+
+impl Rng for ThreadRng {
+    fn next_u64(&self) -> u64 {
+        THREAD_RNG.with(|r| r.next_u64())
+    }
+    
+    fn fill(&self, dest: &mut [u8]) {
+        THREAD_RNG.with(|r| r.fill(dest))
+    }
+}
+
+#[cfg(fail_share_cell)]
+// Error: `Cell<u64>` cannot be shared between threads safely
+fn share_cell() {
+    let rng = Arc::new(CellSplitMix::new(1));
+    let other = rng.clone();
+    thread::spawn(move || other.next_u64());
+}
+
+// ——— consumers ———
+
+fn draw<R: Rng + ?Sized>(rng: &R) -> u64 {
+    rng.next_u64()
+}
+
+fn by_value<R: Rng>(rng: R) -> u64 {
+    rng.next_u64()
+}
+
+fn key<R: CryptoRng + ?Sized>(rng: &R) -> [u8; 16] {
+    let mut key = [0; 16];
+    rng.fill(&mut key);
+    key
+}
+
+/// The first `n` words of SplitMix from `seed`, drawn through `&mut`.
+fn sequential(seed: u64, n: usize) -> Vec<u64> {
+    let mut source = SplitMix(seed);
+    (0..n).map(|_| source.word()).collect()
+}
+
+// ——— usage ———
+
+pub fn main() {
+    {
+        // Shared references draw one stream, in the order of the calls
+        let rng = CellSplitMix::new(1);
+        let (a, b) = (&rng, &rng);
+        let words = [a.next_u64(), b.next_u64(), a.next_u64(), draw(b)];
+        assert_eq!(words.to_vec(), sequential(1, 4));
+        // Two closures over one generator, where `&mut` would conflict
+        let rng = CellSplitMix::new(1);
+        let pairs: Vec<(u64, u64)> = (0..2).map(|_| rng.next_u64())
+            .zip((0..2).map(|_| rng.next_u64()))
+            .collect();
+        let s = sequential(1, 4);
+        assert_eq!(pairs, vec![(s[0], s[1]), (s[2], s[3])]);
+        println!("CellSplitMix: two references, two closures, one stream");
+    }
+    {
+        // A `&mut` source held in a `RefCell` gives the same words
+        let shared = TestRng(RefCell::new(SplitMix(1)));
+        let native = CellSplitMix::new(1);
+        for _ in 0..8 {
+            assert_eq!(shared.next_u64(), native.next_u64());
+        }
+        let r: &dyn Rng = &shared;
+        assert_eq!(r.next_u64(), native.next_u64());
+        let key = key(&TestCRng(RefCell::new(Counting(1))));
+        assert_eq!(key[..8], 1u64.to_le_bytes());
+        println!("TestRng(RefCell<SplitMix>): the same words, through &dyn Rng too");
+    }
+    {
+        // Handles are generators by `Deref`, and go by value as `&*handle`
+        let rc = Rc::new(CellSplitMix::new(1));
+        let boxed: Box<dyn Rng> = Box::new(CellSplitMix::new(1));
+        let (x, y) = (rc.next_u64(), by_value(&*rc));
+        assert_eq!([x, y].to_vec(), sequential(1, 2));
+        assert_eq!(by_value(boxed), x);
+        println!("Rc<CellSplitMix>: called through Deref; Box<dyn Rng> by value");
+    }
+    {
+        // Threads sharing an `AtomicSplitMix` split the sequential stream
+        const THREADS: usize = 4;
+        const WORDS: usize = 1000;
+        let rng = Arc::new(AtomicSplitMix::new(7));
+        let handles: Vec<_> = (0..THREADS).map(|_| {
+            let rng = rng.clone();
+            thread::spawn(move || (0..WORDS).map(|_| rng.next_u64()).collect::<Vec<_>>())
+        }).collect();
+        let mut drawn: Vec<u64> = handles.into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        drawn.sort_unstable();
+        let mut want = sequential(7, THREADS * WORDS);
+        want.sort_unstable();
+        assert_eq!(drawn, want);
+        println!("Arc<AtomicSplitMix>: {} threads drew the stream between them, each word once",
+            THREADS);
+    }
+    {
+        // Handles need no `mut`; each thread's generator is its own
+        let rng = thread_rng();
+        let here = (rng.next_u64(), thread_rng().next_u64());
+        assert_ne!(here.0, here.1);
+        let there = thread::spawn(|| thread_rng().next_u64()).join().unwrap();
+        assert!(there != here.0 && there != here.1);
+        let mut buf = [0u8; 12];
+        rng.fill(&mut buf);
+        assert!(buf != [0; 12] && draw(&GLOBAL) != 0);
+        println!("thread_rng(): no mut, a stream per thread; GLOBAL: no lock");
+    }
+}
@@ -0,0 +1,356 @@
+//! Independent traits, using specialization (nightly only)
+//! 
+//! separate_implicit_Rng3 with the blanket `impl<CR: CryptoRng> Rng for CR`
+//! made specializable: each of its methods is a `default fn`, so a type
+//! implementing `CryptoRng` may also implement `Rng` itself, overriding them.
+//! That is what the other variants can't do because of their blanket impls
+//! (see negative_impls for the negative-reasoning approach).
+//! 
+//! Methods: `Rng` requires `next_u32`, `CryptoRng` requires `try_next_u32`,
+//! and each provides the wider methods on top (see `bridge.rs`). The blanket
+//! `Rng` impl forwards each method to its `try_` counterpart.
+//! 
+//! Thoughts: it works: `TestBothRng` implements both traits, each
+//! optimally, with no opt-out lines (unlike negative_impls). But an
+//! overriding impl inherits what it leaves out from the blanket impl, so a
+//! dual type must override all four methods; the `&mut R` impl still
+//! conflicts; and `specialization` is incomplete and unsound, while
+//! `min_specialization` rejects a generic `TestBothRng<S: Source>`. As for
+//! negative_impls, this shows what the language would need to provide.
+//! 
+//! Compile-fail checks (each of these must fail to compile):
+//! 
+//!     rustc +nightly --cfg fail_mut_forwarding specialization.rs
+
+#![feature(specialization)]
+#![allow(incomplete_features)]
+#![allow(unused)]
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "error.rs"]
+#[allow(dead_code)]
+mod error;
+pub use self::error::{Error as CryptoError, ErrorKind};
+
+pub trait CryptoRng {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError>;
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(bridge::u64_from_u32(self.try_next_u32()?, self.try_next_u32()?))
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(bridge::u128_from_u64(self.try_next_u64()?, self.try_next_u64()?))
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        bridge::try_fill_via_u64(dest, || self.try_next_u64())
+    }
+}
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+// ——— impls ———
+
+// Every method `default`: a type implementing both traits overrides them
+impl<CR: CryptoRng+?Sized> Rng for CR {
+    default fn next_u32(&mut self) -> u32 {
+        self.try_next_u32().unwrap()
+    }
+    
+    default fn next_u64(&mut self) -> u64 {
+        self.try_next_u64().unwrap()
+    }
+    
+    default fn next_u128(&mut self) -> u128 {
+        self.try_next_u128().unwrap()
+    }
+    
+    default fn fill(&mut self, dest: &mut [u8]) {
+        self.try_fill(dest).unwrap()
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for &mut R {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (*self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (*self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (*self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (*self).try_fill(dest)
+    }
+}
+
+impl<R: CryptoRng+?Sized> CryptoRng for Box<R> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        (**self).try_next_u32()
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        (**self).try_next_u64()
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        (**self).try_next_u128()
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        (**self).try_fill(dest)
+    }
+}
+
+#[cfg(fail_mut_forwarding)]
+// Error: conflicts with the blanket impl, for `&mut R` where `R: CryptoRng`
+// (as would `Box<R>`, for the same reason).
+// Neither impl is more specific than the other, so neither specializes.
+impl<'a, R: Rng+?Sized> Rng for &'a mut R {
+    fn next_u32(&mut self) -> u32 {
+        (*self).next_u32()
+    }
+}
+
+// ——— adaptor ———
+
+// Given `rng` of type `T` where `T: Rng`, this can consume
+// `rng` (`as_rng(rng)`)
+fn as_crng<R: Rng>(rng: R) -> AsCRng<R> {
+    AsCRng { rng }
+}
+
+struct AsCRng<R: Rng+?Sized> {
+    rng: R
+}
+
+impl<R: Rng+?Sized> CryptoRng for AsCRng<R> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        Ok(self.rng.next_u32())
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.rng.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.rng.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.rng.fill(dest);
+        Ok(())
+    }
+}
+
+// Given `rng` of type `T` where `T: Rng`, this can consume
+// `&mut rng` (`as_rng(&mut rng)`)
+fn as_crng_ref<'a, R: Rng+?Sized+'a>(rng: &'a mut R) -> AsCRngRef<'a, R> {
+    AsCRngRef { rng }
+}
+
+struct AsCRngRef<'a, R: Rng+?Sized+'a> {
+    rng: &'a mut R
+}
+
+impl<'a, R: Rng+?Sized> CryptoRng for AsCRngRef<'a, R> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        Ok(self.rng.next_u32())
+    }
+    
+    fn try_next_u64(&mut self) -> Result<u64, CryptoError> {
+        Ok(self.rng.next_u64())
+    }
+    
+    fn try_next_u128(&mut self) -> Result<u128, CryptoError> {
+        Ok(self.rng.next_u128())
+    }
+    
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        self.rng.fill(dest);
+        Ok(())
+    }
+}
+
+// ——— test RNGs ———
+
+#[path = "test_rngs.rs"]
+#[allow(dead_code)]
+pub mod test_rngs;
+use self::test_rngs::{Constant, Counting, FailAfter, Source, TestBothRng, TestCRng, TestRng};
+
+// A non-crypto Rng
+impl<S: Source> Rng for TestRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+}
+
+// A CryptoRng
+impl<S: Source> CryptoRng for TestCRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+// An RNG supporting both interfaces: the `Rng` impl specializes the blanket
+// one. (`S: Source` is why this needs `specialization`: with
+// `min_specialization`, "cannot specialize on trait `Source`".)
+impl<S: Source> CryptoRng for TestBothRng<S> {
+    fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+        self.0.try_word().map(|x| x as u32)
+            .map_err(|_| CryptoError::new(ErrorKind::Unavailable, "test source exhausted"))
+    }
+}
+
+impl<S: Source> Rng for TestBothRng<S> {
+    fn next_u32(&mut self) -> u32 {
+        self.0.word() as u32
+    }
+    
+    // Not left out: these would come from the blanket impl, not from the
+    // `Rng` defaults over `next_u32` (see `main`)
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn next_u128(&mut self) -> u128 {
+        bridge::u128_from_u64(self.next_u64(), self.next_u64())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+// ——— usage ———
+
+pub fn main() {
+    let mut t = TestRng(Constant(13));
+    let mut c = TestCRng(Constant(42));
+    let mut b = TestBothRng(Constant(7));
+    println!("t: {:?} impls Rng", t);
+    println!("c: {:?} impls CryptoRng", c);
+    println!("b: {:?} impls Rng and CryptoRng", b);
+    {
+        // Do both traits support both functions via static dispatch?
+        println!("t, static dispatch, using CryptoRng: {:?}", as_crng_ref(&mut t).try_next_u32());
+        println!("t, static dispatch, using Rng: {:?}", t.next_u32());
+        println!("c, static dispatch, using CryptoRng: {:?}", c.try_next_u32());
+        println!("c, static dispatch, using Rng: {:?}", c.next_u32());
+        println!("b, static dispatch, using CryptoRng: {:?}", b.try_next_u32());
+        println!("b, static dispatch, using Rng: {:?}", b.next_u32());
+    }
+    {
+        // Can both types be used via CryptoRng with dynamic dispatch?
+        let cr = &mut c as &mut CryptoRng;
+        println!("c, dynamic dispatch, using CryptoRng: {:?}", cr.try_next_u32());
+        let mut tr = as_crng_ref(&mut t as &mut Rng);
+        println!("t, dynamic dispatch, using CryptoRng: {:?}", tr.try_next_u32());
+        let br = &mut b as &mut CryptoRng;
+        println!("b, dynamic dispatch, using CryptoRng: {:?}", br.try_next_u32());
+    }
+    {
+        // Can both types be used via Rng with dynamic dispatch?
+        let cr = &mut c as &mut Rng;
+        let tr = &mut t as &mut Rng;
+        println!("c, dynamic dispatch, using Rng: {:?}", cr.next_u32());
+        println!("t, dynamic dispatch, using Rng: {:?}", tr.next_u32());
+        let br = &mut b as &mut Rng;
+        println!("b, dynamic dispatch, using Rng: {:?}", br.next_u32());
+    }
+    {
+        // The dual-impl type's own `Rng` methods, and its `CryptoRng` ones
+        let mut b = TestBothRng(Counting(1));
+        assert_eq!(b.next_u64(), 2 << 32 | 1);
+        assert_eq!(b.try_next_u64().unwrap(), 4 << 32 | 3);
+        println!("b, provided methods via both traits: the same words");
+    }
+    {
+        // An override of `next_u32` alone: `next_u64` is inherited from the
+        // blanket impl, through `try_next_u64`, not built on the override
+        struct Partial;
+        impl CryptoRng for Partial {
+            fn try_next_u32(&mut self) -> Result<u32, CryptoError> {
+                Ok(1)
+            }
+        }
+        impl Rng for Partial {
+            fn next_u32(&mut self) -> u32 {
+                2
+            }
+        }
+        assert_eq!(Partial.next_u32(), 2);
+        assert_eq!(Partial.next_u64(), 1 << 32 | 1);
+        println!("Partial: next_u32 overridden, next_u64 still from try_next_u32");
+    }
+    {
+        // A source failing after one word: the error reaches the caller
+        let mut f = TestCRng(FailAfter::new(1));
+        assert_eq!(f.try_next_u32().unwrap(), 0);
+        assert!(f.try_next_u32().is_err());
+        println!("f, failing after one word, using CryptoRng: Ok(0), then Err");
+    }
+    {
+        // The provided methods, over words 1, 2, 3, ...: low word first, and
+        // `fill` little-endian
+        let mut t = TestRng(Counting(1));
+        assert_eq!(t.next_u64(), 2 << 32 | 1);
+        assert_eq!(t.next_u128(), (6 << 32 | 5) << 64 | (4 << 32 | 3));
+        let mut buf = [0u8; 12];
+        t.fill(&mut buf);
+        assert_eq!(buf, [7, 0, 0, 0, 8, 0, 0, 0, 9, 0, 0, 0]);
+        println!("t, provided methods: next_u64, next_u128 and fill bridge to next_u32");
+    }
+    {
+        // The blanket Rng impl: the try_ methods, unwrapped
+        let mut c = TestCRng(Counting(1));
+        assert_eq!(c.next_u64(), 2 << 32 | 1);
+        println!("c, provided methods via Rng: the same words");
+    }
+    {
+        // The provided methods pass on the first error
+        let mut f = TestCRng(FailAfter::new(3));
+        assert_eq!(f.try_next_u64().unwrap(), 1 << 32);
+        assert!(f.try_fill(&mut [0; 8]).is_err());
+        println!("f, failing after three words: try_next_u64 Ok, then try_fill Err");
+    }
+    {
+        // Forwarding: `&mut R` and `Box<R>`, of generators and of trait
+        // objects, passed by value to generic code
+        // No forwarding for `Rng`: it would overlap the blanket impl (see
+        // `fail_mut_forwarding`)
+        fn use_crng<R: CryptoRng>(mut r: R, how: &str) {
+            println!("c, {}, using CryptoRng: {:?}", how, r.try_next_u32());
+        }
+        let mut c = TestCRng(Constant(42));
+        use_crng(&mut c, "by &mut");
+        use_crng(&mut c as &mut dyn CryptoRng, "by &mut dyn");
+        use_crng(Box::new(c) as Box<dyn CryptoRng>, "by Box<dyn>");
+    }
+}
@@ -0,0 +1,276 @@
+//! Multiple streams: `set_stream`, on a trait or on the type
+//! 
+//! A parallel simulation wants worker `i` to build its own generator from
+//! one seed, the same at any thread count. Some generators take a stream
+//! parameter for this (PCG's increment, ChaCha's nonce); here `Pcg32` has
+//! `set_stream(u64)`, and the question is whether generic code should reach
+//! it, through `StreamableRng: Rng` (`by_stream::<R>(seed, i)`), or only the
+//! concrete type does, generic code deriving worker `i`'s seed from `(seed,
+//! i)` (`by_index`) or forking children in order (`by_fork`). `CounterRng`,
+//! counter-based, is a second streamable generator to compare with.
+//! 
+//! Run with:
+//! 
+//!     rustc streams.rs && ./streams
+//! 
+//! Thoughts: keep stream selection on the concrete types. `set_stream`
+//! means different things: `CounterRng` keeps its position, `Pcg32` its
+//! state; `Pcg32` has 2^63 streams, ChaCha 2^64; and `Pcg32`'s streams from
+//! one state are correlated, a counter's are not. `by_index` gives parallel
+//! code what it needs for every `SeedableRng`. A later trait should be a
+//! constructor, `from_seed_and_stream`, with the stream count a constant.
+
+// ——— traits ———
+
+#[path = "bridge.rs"]
+#[allow(dead_code)]
+mod bridge;
+
+#[path = "seeding.rs"]
+#[allow(dead_code)]
+pub mod seeding;
+use self::seeding::SeedableRng;
+
+pub trait Rng {
+    fn next_u32(&mut self) -> u32;
+    
+    fn next_u64(&mut self) -> u64 {
+        bridge::u64_from_u32(self.next_u32(), self.next_u32())
+    }
+    
+    fn fill(&mut self, dest: &mut [u8]) {
+        bridge::fill_via_u64(dest, || self.next_u64())
+    }
+}
+
+/// A generator with selectable streams.
+pub trait StreamableRng: Rng {
+    /// Switches to stream `stream`; what is kept of the current one is the
+    /// generator's choice.
+    fn set_stream(&mut self, stream: u64);
+    
+    fn stream(&self) -> u64;
+}
+
+// ——— test RNGs ———
+
+/// PCG-XSH-RR 64/32: a 64-bit LCG, its increment the stream, with a 32-bit
+/// output permutation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pcg32 {
+    state: u64,
+    /// Always odd: `stream << 1 | 1`.
+    inc: u64,
+}
+
+const PCG_MUL: u64 = 6364136223846793005;
+
+impl Pcg32 {
+    /// As the reference `pcg32_srandom(state, stream)`.
+    pub fn new(state: u64, stream: u64) -> Self {
+        let mut rng = Pcg32 { state: 0, inc: stream << 1 | 1 };
+        rng.step();
+        rng.state = rng.state.wrapping_add(state);
+        rng.step();
+        rng
+    }
+    
+    /// Switches stream, keeping the state.
+    pub fn set_stream(&mut self, stream: u64) {
+        self.inc = stream << 1 | 1;
+    }
+    
+    /// The stream: only 63 bits of it are kept.
+    pub fn stream(&self) -> u64 {
+        self.inc >> 1
+    }
+    
+    fn step(&mut self) {
+        self.state = self.state.wrapping_mul(PCG_MUL).wrapping_add(self.inc);
+    }
+}
+
+impl Rng for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
+
+impl StreamableRng for Pcg32 {
+    fn set_stream(&mut self, stream: u64) {
+        Pcg32::set_stream(self, stream)
+    }
+    
+    fn stream(&self) -> u64 {
+        Pcg32::stream(self)
+    }
+}
+
+impl SeedableRng for Pcg32 {
+    type Seed = [u8; 16];
+    
+    /// The state then the stream, little-endian.
+    fn from_seed(seed: [u8; 16]) -> Self {
+        let (state, stream) = split_seed(seed);
+        Pcg32::new(state, stream)
+    }
+}
+
+/// A counter-based generator: word `n` of stream `s` is a hash of `(n, s)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterRng {
+    counter: u64,
+    stream: u64,
+}
+
+impl CounterRng {
+    pub fn new(counter: u64, stream: u64) -> Self {
+        CounterRng { counter, stream }
+    }
+}
+
+/// SplitMix64's output function.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl Rng for CounterRng {
+    fn next_u32(&mut self) -> u32 {
+        self.counter = self.counter.wrapping_add(1);
+        mix(self.counter ^ mix(self.stream)) as u32
+    }
+}
+
+impl StreamableRng for CounterRng {
+    /// Switches stream, keeping the position.
+    fn set_stream(&mut self, stream: u64) {
+        self.stream = stream;
+    }
+    
+    fn stream(&self) -> u64 {
+        self.stream
+    }
+}
+
+impl SeedableRng for CounterRng {
+    type Seed = [u8; 16];
+    
+    /// The position then the stream, little-endian.
+    fn from_seed(seed: [u8; 16]) -> Self {
+        let (counter, stream) = split_seed(seed);
+        CounterRng::new(counter, stream)
+    }
+}
+
+fn split_seed(seed: [u8; 16]) -> (u64, u64) {
+    let mut a = [0u8; 8];
+    let mut b = [0u8; 8];
+    a.copy_from_slice(&seed[..8]);
+    b.copy_from_slice(&seed[8..]);
+    (u64::from_le_bytes(a), u64::from_le_bytes(b))
+}
+
+// ——— consumers ———
+
+/// Worker `i`'s generator: `seed`'s, on stream `i`.
+pub fn by_stream<R: StreamableRng + SeedableRng>(seed: u64, i: u64) -> R {
+    let mut rng = R::seed_from_u64(seed);
+    rng.set_stream(i);
+    rng
+}
+
+/// Worker `i`'s generator: seeded from a hash of `(seed, i)`.
+pub fn by_index<R: SeedableRng>(seed: u64, i: u64) -> R {
+    R::seed_from_u64(mix(seed ^ mix(i)))
+}
+
+/// All `n` workers' generators, seeded from a master in turn.
+pub fn by_fork<R: SeedableRng, M: Rng>(master: &mut M, n: usize) -> Vec<R> {
+    (0..n).map(|_| {
+        let mut seed = R::Seed::default();
+        master.fill(seed.as_mut());
+        R::from_seed(seed)
+    }).collect()
+}
+
+/// The first `n` words of each generator.
+fn outputs<R: Rng>(rngs: &mut [R], n: usize) -> Vec<Vec<u32>> {
+    rngs.iter_mut().map(|r| (0..n).map(|_| r.next_u32()).collect()).collect()
+}
+
+fn all_distinct(streams: &[Vec<u32>]) -> bool {
+    streams.iter().enumerate().all(|(i, a)| streams[..i].iter().all(|b| a != b))
+}
+
+// ——— usage ———
+
+pub fn main() {
+    {
+        // The reference output of `pcg32_srandom(42, 54)`
+        let mut rng = Pcg32::new(42, 54);
+        let want = [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e];
+        assert!((0..6).map(|_| rng.next_u32()).eq(want.iter().cloned()));
+        println!("Pcg32: matches the reference output");
+    }
+    {
+        // Each way gives distinct workers
+        let mut s: Vec<Pcg32> = (0..4).map(|i| by_stream(7, i)).collect();
+        let mut c: Vec<CounterRng> = (0..4).map(|i| by_stream(7, i)).collect();
+        let mut x: Vec<Pcg32> = (0..4).map(|i| by_index(7, i)).collect();
+        let mut f: Vec<Pcg32> = by_fork(&mut Pcg32::new(7, 0), 4);
+        for out in [outputs(&mut s, 4), outputs(&mut x, 4), outputs(&mut f, 4)].iter() {
+            assert!(all_distinct(out));
+        }
+        assert!(all_distinct(&outputs(&mut c, 4)));
+        println!("by_stream, by_index, by_fork: distinct workers");
+    }
+    {
+        // What `set_stream` keeps: `CounterRng`'s position, so it continues
+        // the new stream in step; `Pcg32`'s state, off its new stream's path
+        let mut c = CounterRng::new(0, 1);
+        let mut p = Pcg32::new(0, 1);
+        for _ in 0..10 {
+            c.next_u32();
+            p.next_u32();
+        }
+        c.set_stream(2);
+        p.set_stream(2);
+        let mut c2 = CounterRng::new(0, 2);
+        let mut p2 = Pcg32::new(0, 2);
+        for _ in 0..10 {
+            c2.next_u32();
+            p2.next_u32();
+        }
+        assert_eq!(c.next_u32(), c2.next_u32());
+        assert_ne!(p.next_u32(), p2.next_u32());
+        println!("set_stream mid-way: CounterRng continues in step, Pcg32 doesn't");
+    }
+    {
+        // `Pcg32` keeps 63 bits of the stream
+        let mut a = Pcg32::new(7, 5);
+        let mut b = Pcg32::new(7, 5 | 1 << 63);
+        assert_eq!(b.stream(), 5);
+        assert!((0..8).all(|_| a.next_u32() == b.next_u32()));
+        println!("Pcg32: streams s and s + 2^63 are one");
+    }
+    {
+        // Two `Pcg32` streams from one state differ by a sequence which
+        // doesn't depend on the state
+        let diffs = |state: u64| {
+            let (mut a, mut b) = (Pcg32::new(state, 1), Pcg32::new(state, 2));
+            b.state = a.state;
+            (0..8).map(|_| {
+                a.next_u32();
+                b.next_u32();
+                b.state.wrapping_sub(a.state)
+            }).collect::<Vec<u64>>()
+        };
+        assert_eq!(diffs(7), diffs(0x0123_4567_89ab_cdef));
+        println!("Pcg32: streams from one state differ by a seed-independent sequence");
+    }
+}
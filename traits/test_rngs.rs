@@ -0,0 +1,440 @@
+//! Canonical test generators, shared by the variants
+//! 
+//! A test generator is a *source* of `u64` words (`Constant`, `Counting`,
+//! `SplitMix`, a `BlockRng`, `FaultyRng` injecting failures into another...)
+//! inside a *role* naming what it claims to be: `TestRng`, `TestCRng`,
+//! `TestBothRng`, `TestICRng` or `TestFCRng`. Each variant implements its own
+//! traits for the roles it needs; `u32` designs take the low half of a word.
+//! 
+//! Thoughts: every variant runs the same generators, so a difference in
+//! behaviour between variants is the trait design's.
+
+#[path = "block.rs"]
+pub mod block;
+#[path = "chacha.rs"]
+pub mod chacha;
+#[path = "os.rs"]
+pub mod os;
+#[path = "pcg.rs"]
+pub mod pcg;
+#[path = "seeding.rs"]
+pub mod seeding;
+
+use self::block::{BlockRng, BlockRngCore};
+use self::chacha::ChaCha20Core;
+use self::os::OsRng;
+use self::pcg::Pcg32;
+use self::seeding::{seed_array, FromSeedSlice, SeedFrom, SeedLenError, SeedableRng};
+
+/// The source failed this call. Most sources then fail every later call too;
+/// `FaultyRng`'s random faults don't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exhausted;
+
+// ——— sources ———
+
+/// The words behind a test generator.
+pub trait Source {
+    /// The next word, or `Exhausted` if the source failed.
+    fn try_word(&mut self) -> Result<u64, Exhausted>;
+    
+    /// The next word, for infallible roles. Panics if exhausted.
+    fn word(&mut self) -> u64 {
+        self.try_word().expect("test source exhausted")
+    }
+}
+
+/// The same word forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Constant(pub u64);
+
+impl Source for Constant {
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        Ok(self.0)
+    }
+}
+
+/// Words `n`, `n + 1`, ..., wrapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Counting(pub u64);
+
+impl Source for Counting {
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        let x = self.0;
+        self.0 = x.wrapping_add(1);
+        Ok(x)
+    }
+}
+
+/// Words `state`, `state + increment`, ..., wrapping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub state: u64,
+    pub increment: u64,
+}
+
+impl Source for Step {
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        let x = self.state;
+        self.state = x.wrapping_add(self.increment);
+        Ok(x)
+    }
+}
+
+/// Words 0, 1, 2, ... `left` of them; then exhausted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailAfter {
+    pub next: u64,
+    pub left: usize,
+}
+
+impl FailAfter {
+    pub fn new(n: usize) -> Self {
+        FailAfter { next: 0, left: n }
+    }
+}
+
+impl Source for FailAfter {
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        if self.left == 0 {
+            return Err(Exhausted);
+        }
+        self.left -= 1;
+        self.next += 1;
+        Ok(self.next - 1)
+    }
+}
+
+/// Any source, with faults injected: every call from the `n`th on, or each
+/// call at random with probability `p`, reproducibly from a seed. A faulty
+/// call leaves the inner source as it was, so a retry gets the word the fault
+/// withheld; the inner source's own failures pass through. The roles report
+/// every failure as the design's `Unavailable`, even one a retry gets past.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaultyRng<S> {
+    pub inner: S,
+    fault: Fault,
+    /// Calls so far, faulty or not.
+    pub calls: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Fault {
+    After(usize),
+    Random { p: f64, coin: SplitMix },
+}
+
+impl<S: Source> FaultyRng<S> {
+    /// `n` words of `inner`; then every call fails.
+    pub fn after(inner: S, n: usize) -> Self {
+        FaultyRng { inner, fault: Fault::After(n), calls: 0 }
+    }
+    
+    /// Each call fails with probability `p`, as drawn from `SplitMix(seed)`.
+    pub fn with_probability(inner: S, p: f64, seed: u64) -> Self {
+        FaultyRng { inner, fault: Fault::Random { p, coin: SplitMix(seed) }, calls: 0 }
+    }
+}
+
+impl<S: Source> Source for FaultyRng<S> {
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        self.calls += 1;
+        let faulty = match self.fault {
+            Fault::After(n) => self.calls > n,
+            // The top 53 bits, as a float in [0, 1)
+            Fault::Random { p, ref mut coin } => {
+                ((coin.word() >> 11) as f64) < p * (1u64 << 53) as f64
+            }
+        };
+        if faulty {
+            return Err(Exhausted);
+        }
+        self.inner.try_word()
+    }
+}
+
+/// SplitMix64: cheap, but a real generator, so the words are not known at
+/// compile time. For the benches, where a constant would be folded away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitMix(pub u64);
+
+impl Source for SplitMix {
+    #[inline]
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        Ok(z ^ (z >> 31))
+    }
+}
+
+/// PCG32: two outputs per word, low first.
+impl Source for Pcg32 {
+    #[inline]
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        let low = self.next_u32();
+        Ok(u64::from(self.next_u32()) << 32 | u64::from(low))
+    }
+}
+
+/// A block generator, over any core.
+impl<R: BlockRngCore> Source for BlockRng<R> {
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        Ok(self.next_u64())
+    }
+}
+
+/// SplitMix64 as a block core: eight steps per block, each as two words, low
+/// first; so `BlockRng<SplitMixCore>` gives the words of `SplitMix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitMixCore(pub u64);
+
+impl BlockRngCore for SplitMixCore {
+    type Results = [u32; 16];
+    
+    fn generate(&mut self, results: &mut [u32; 16]) {
+        let mut source = SplitMix(self.0);
+        for pair in results.chunks_mut(2) {
+            let x = source.word();
+            pair[0] = x as u32;
+            pair[1] = (x >> 32) as u32;
+        }
+        self.0 = source.0;
+    }
+}
+
+/// The operating system's generator; any failure exhausts it for the call.
+impl Source for OsRng {
+    fn try_word(&mut self) -> Result<u64, Exhausted> {
+        let mut buf = [0; 8];
+        self.try_fill(&mut buf).map_err(|_| Exhausted)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+}
+
+// ——— seeding ———
+
+macro_rules! seedable_sources {
+    ($($source:ident)*) => {$(
+        impl SeedableRng for $source {
+            type Seed = [u8; 8];
+            
+            fn from_seed(seed: [u8; 8]) -> Self {
+                $source(u64::from_le_bytes(seed))
+            }
+        }
+        
+        impl FromSeedSlice for $source {
+            const SEED_LEN: usize = 8;
+            
+            fn from_seed_slice(seed: &[u8]) -> Result<Self, SeedLenError> {
+                seed_array(seed).map(Self::from_seed)
+            }
+        }
+        
+        impl SeedFrom<[u8; 8]> for $source {
+            fn seed_from(seed: [u8; 8]) -> Self {
+                Self::from_seed(seed)
+            }
+        }
+        
+        impl SeedFrom<u64> for $source {
+            fn seed_from(seed: u64) -> Self {
+                $source(seed)
+            }
+        }
+    )*};
+}
+
+seedable_sources!(Constant Counting SplitMix);
+
+impl SeedableRng for Pcg32 {
+    type Seed = [u8; 16];
+    
+    /// The state then the stream, little-endian.
+    fn from_seed(seed: [u8; 16]) -> Self {
+        let mut state = [0u8; 8];
+        let mut stream = [0u8; 8];
+        state.copy_from_slice(&seed[..8]);
+        stream.copy_from_slice(&seed[8..]);
+        Pcg32::new(u64::from_le_bytes(state), u64::from_le_bytes(stream))
+    }
+}
+
+impl FromSeedSlice for Pcg32 {
+    const SEED_LEN: usize = 16;
+    
+    fn from_seed_slice(seed: &[u8]) -> Result<Self, SeedLenError> {
+        seed_array(seed).map(Self::from_seed)
+    }
+}
+
+/// The state, on stream 0.
+impl SeedFrom<u64> for Pcg32 {
+    fn seed_from(seed: u64) -> Self {
+        Pcg32::new(seed, 0)
+    }
+}
+
+impl BlockRngCore for ChaCha20Core {
+    type Results = [u32; 16];
+    
+    fn generate(&mut self, results: &mut [u32; 16]) {
+        *results = self.next_block();
+    }
+}
+
+impl SeedableRng for ChaCha20Core {
+    type Seed = [u8; 32];
+    
+    /// The key, little-endian words; stream 0.
+    fn from_seed(seed: [u8; 32]) -> Self {
+        ChaCha20Core::from_key(seed)
+    }
+}
+
+/// A block generator seeds as its core, with an empty buffer.
+impl<R: BlockRngCore + SeedableRng> SeedableRng for BlockRng<R> {
+    type Seed = R::Seed;
+    
+    fn from_seed(seed: R::Seed) -> Self {
+        BlockRng::new(R::from_seed(seed))
+    }
+}
+
+macro_rules! seedable_roles {
+    ($($role:ident)*) => {$(
+        impl<S: SeedableRng> SeedableRng for $role<S> {
+            type Seed = S::Seed;
+            
+            fn from_seed(seed: S::Seed) -> Self {
+                $role(S::from_seed(seed))
+            }
+        }
+        
+        impl<S: FromSeedSlice> FromSeedSlice for $role<S> {
+            const SEED_LEN: usize = S::SEED_LEN;
+            
+            fn from_seed_slice(seed: &[u8]) -> Result<Self, SeedLenError> {
+                S::from_seed_slice(seed).map($role)
+            }
+        }
+        
+        impl<T, S: SeedFrom<T>> SeedFrom<T> for $role<S> {
+            fn seed_from(seed: T) -> Self {
+                $role(S::seed_from(seed))
+            }
+        }
+    )*};
+}
+
+// ——— roles ———
+
+/// A non-crypto generator.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestRng<S = Constant>(pub S);
+
+/// A crypto generator.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestCRng<S = Constant>(pub S);
+
+/// A generator implementing both interfaces, where the design allows it.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestBothRng<S = Constant>(pub S);
+
+/// An infallible crypto generator, for designs distinguishing fallibility.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestICRng<S = Constant>(pub S);
+
+/// A fallible crypto generator, for designs distinguishing fallibility.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestFCRng<S = Constant>(pub S);
+
+seedable_roles!(TestRng TestCRng TestBothRng TestICRng TestFCRng);
+
+// ——— mocks ———
+
+/// rand's `StepRng`: a plain generator of words `state`, `state +
+/// increment`, ...
+pub type StepRng = TestRng<Step>;
+
+/// A plain generator of one word, forever: a `StepRng` with no step.
+pub type ConstRng = TestRng<Constant>;
+
+impl StepRng {
+    pub fn new(state: u64, increment: u64) -> Self {
+        TestRng(Step { state, increment })
+    }
+}
+
+impl ConstRng {
+    pub fn new(word: u64) -> Self {
+        TestRng(Constant(word))
+    }
+}
+
+// ——— metering ———
+
+/// What a generator gave out: calls of each method, and their bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Consumed {
+    pub u32s: usize,
+    pub u64s: usize,
+    pub fills: usize,
+    /// Four a `u32`, eight a `u64`, and the length of each fill.
+    pub bytes: usize,
+}
+
+/// Any generator, counting what an algorithm draws from it. A variant
+/// implements its traits for it by delegation, calling `u32`, `u64` or
+/// `fill` on what the inner generator returned; a call that failed gave out
+/// nothing, and isn't counted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountingRng<R> {
+    pub inner: R,
+    pub consumed: Consumed,
+}
+
+impl<R> CountingRng<R> {
+    pub fn new(inner: R) -> Self {
+        CountingRng { inner, consumed: Consumed::default() }
+    }
+    
+    /// The count so far, starting again from zero.
+    pub fn take(&mut self) -> Consumed {
+        let consumed = self.consumed;
+        self.consumed = Consumed::default();
+        consumed
+    }
+    
+    pub fn u32(&mut self, x: u32) -> u32 {
+        self.consumed.u32s += 1;
+        self.consumed.bytes += 4;
+        x
+    }
+    
+    pub fn u64(&mut self, x: u64) -> u64 {
+        self.consumed.u64s += 1;
+        self.consumed.bytes += 8;
+        x
+    }
+    
+    pub fn fill(&mut self, len: usize) {
+        self.consumed.fills += 1;
+        self.consumed.bytes += len;
+    }
+}
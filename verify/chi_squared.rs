@@ -0,0 +1,35 @@
+//! Pearson's chi-squared test, shared by the statistical checks
+//! 
+//! The distribution and generator programs include this file rather than
+//! each carrying a copy:
+//! 
+//!     #[path = "../verify/chi_squared.rs"]
+//!     #[allow(dead_code)]
+//!     mod chi_squared;
+//!     use self::chi_squared::{chi_squared, chi_squared_limit};
+//! 
+//! Thoughts: the limit is deliberately generous, so that a check only fails
+//! on a real bias, never on an unlucky seed.
+
+/// Pearson's chi-squared statistic for `counts` against a uniform expectation.
+pub fn chi_squared(counts: &[u64]) -> f64 {
+    let total: u64 = counts.iter().sum();
+    let expected = total as f64 / counts.len() as f64;
+    counts.iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum()
+}
+
+/// Pearson's chi-squared statistic for `counts` against proportions
+/// `weights` (cells of weight zero must be empty).
+pub fn chi_squared_weighted(counts: &[u64], weights: &[u32]) -> f64 {
+    let n: u64 = counts.iter().sum();
+    let total: u64 = weights.iter().map(|&w| w as u64).sum();
+    counts.iter().zip(weights).map(|(&c, &w)| {
+        let expected = n as f64 * w as f64 / total as f64;
+        if w == 0 { assert_eq!(c, 0); 0.0 } else { (c as f64 - expected).powi(2) / expected }
+    }).sum()
+}
+
+/// Generous critical value for `df` degrees of freedom (far beyond p = 0.001).
+pub fn chi_squared_limit(df: usize) -> f64 {
+    df as f64 + 5.0 * (2.0 * df as f64).sqrt()
+}
@@ -0,0 +1,307 @@
+//! Codegen check: do `Result<u32, !>` and the adaptors compile away?
+//! 
+//! The `raw_*` designs claim no overhead from unifying the traits, and
+//! `bench/never_type_overhead.rs` supports that with timings. This file
+//! checks the machine code itself. Each function below is `#[no_mangle]` and
+//! `#[inline(never)]`, so it appears in the assembly under its own name, and
+//! calls `next_u32` on `SplitMix` one way: through a design's `Rng`, its
+//! `RawRng<!>` (or `RawRng<Infallible>`) with the never-failing `Result`
+//! taken apart, the blanket `RawRng<Error>` over an infallible generator
+//! with `unwrap`, the newtype, or the `AsRng` adaptor. `baseline_next_u32`
+//! calls the source directly. `main` reads the assembly, strips labels and
+//! directives, and compares each function's instructions with the baseline.
+//! 
+//! Run with (`--emit asm,link` writes `codegen.s` beside the program, which
+//! then reads it: the code checked is the code built):
+//! 
+//!     rustc +nightly -O --emit asm,link codegen.rs && ./codegen [ASM]
+//! 
+//! Without `-O` nothing is inlined and every layer is a call, so the check
+//! is only meaningful optimised; `main` refuses to judge a debug build. The
+//! functions also give a diff by hand: `grep -A20 '^raw_rng_as_rng_next_u32:'
+//! codegen.s`.
+//! 
+//! Thoughts: the claims hold to the instruction. Built with `-O`, all 21
+//! functions are the baseline's 17 instructions, so alike that LLVM emits
+//! each as an alias of `baseline_next_u32`; with merging off
+//! (`-Z merge-functions=disabled`) each body is the baseline's, and so at
+//! `-C opt-level=1`. Nothing is left of `!` or `Infallible`, of the blanket
+//! `RawRng<Error>`, of `raw_newtype`'s wrapper, or of `AsRng`'s `unwrap`:
+//! the `Err` arm is unreachable once the source's `Ok` is inlined. What
+//! does stay is real failure: over `FailAfter`, a source which can run out,
+//! `AsRng` keeps a test and a call to `unwrap_failed`, as it must. So the
+//! cost of the raw designs is in their types and coherence, never in the
+//! code; `bench/never_type_overhead.rs` times the same.
+
+#![feature(never_type)]
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+#[path = "../traits/extends_Rng.rs"]
+#[allow(dead_code, unused_imports)]
+mod extends_rng;
+#[path = "../traits/raw_default.rs"]
+#[allow(dead_code)]
+mod raw_default;
+#[path = "../traits/raw_explicit.rs"]
+#[allow(dead_code, unused_attributes)]
+mod raw_explicit;
+#[path = "../traits/raw_infallible.rs"]
+#[allow(dead_code)]
+mod raw_infallible;
+#[path = "../traits/raw_newtype.rs"]
+#[allow(dead_code, unused_attributes)]
+mod raw_newtype;
+#[path = "../traits/raw_rng.rs"]
+#[allow(dead_code, unused_attributes)]
+mod raw_rng;
+
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::{env, fs};
+
+// ——— functions ———
+
+#[no_mangle]
+#[inline(never)]
+pub fn baseline_next_u32(s: &mut marker_only::test_rngs::SplitMix) -> u32 {
+    use marker_only::test_rngs::Source;
+    s.word() as u32
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn marker_only_next_u32(
+    r: &mut marker_only::test_rngs::TestRng<marker_only::test_rngs::SplitMix>,
+) -> u32 {
+    use marker_only::Rng;
+    r.next_u32()
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn extends_rng_try_next_u32(
+    r: &mut extends_rng::test_rngs::TestCRng<extends_rng::test_rngs::SplitMix>,
+) -> u32 {
+    use extends_rng::CryptoRng;
+    r.try_next_u32().unwrap()
+}
+
+macro_rules! raw_designs {
+    ($($name:ident: $never:ty, $rng:ident, $raw:ident, $error:ident, $as_rng:ident;)*) => {$(
+        #[no_mangle]
+        #[inline(never)]
+        pub fn $rng(r: &mut $name::test_rngs::TestRng<$name::test_rngs::SplitMix>) -> u32 {
+            use $name::Rng;
+            r.next_u32()
+        }
+        
+        #[no_mangle]
+        #[inline(never)]
+        pub fn $raw(r: &mut $name::test_rngs::TestRng<$name::test_rngs::SplitMix>) -> u32 {
+            use $name::RawRng;
+            match RawRng::<$never>::try_next_u32(r) {
+                Ok(x) => x,
+                Err(e) => match e {},
+            }
+        }
+        
+        #[no_mangle]
+        #[inline(never)]
+        pub fn $error(r: &mut $name::test_rngs::TestICRng<$name::test_rngs::SplitMix>) -> u32 {
+            use $name::{Error, RawRng};
+            RawRng::<Error>::try_next_u32(r).unwrap()
+        }
+        
+        #[no_mangle]
+        #[inline(never)]
+        pub fn $as_rng(r: &mut $name::test_rngs::TestICRng<$name::test_rngs::SplitMix>) -> u32 {
+            use $name::Rng;
+            $name::as_rng_ref(r).next_u32()
+        }
+    )*};
+}
+
+raw_designs! {
+    raw_default: Infallible, raw_default_next_u32, raw_default_try_next_u32,
+        raw_default_error_next_u32, raw_default_as_rng_next_u32;
+    raw_explicit: !, raw_explicit_next_u32, raw_explicit_try_next_u32,
+        raw_explicit_error_next_u32, raw_explicit_as_rng_next_u32;
+    raw_infallible: Infallible, raw_infallible_next_u32, raw_infallible_try_next_u32,
+        raw_infallible_error_next_u32, raw_infallible_as_rng_next_u32;
+}
+
+// `raw_newtype` gets `RawRng<Error>` from the newtype, not a blanket impl
+#[no_mangle]
+#[inline(never)]
+pub fn raw_newtype_next_u32(
+    r: &mut raw_newtype::test_rngs::TestRng<raw_newtype::test_rngs::SplitMix>,
+) -> u32 {
+    use raw_newtype::Rng;
+    r.next_u32()
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn raw_newtype_try_next_u32(
+    r: &mut raw_newtype::test_rngs::TestRng<raw_newtype::test_rngs::SplitMix>,
+) -> u32 {
+    use raw_newtype::RawRng;
+    RawRng::<!>::try_next_u32(r).unwrap_or_else(|e| e)
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn raw_newtype_error_next_u32(
+    r: &mut raw_newtype::test_rngs::TestICRng<raw_newtype::test_rngs::SplitMix>,
+) -> u32 {
+    use raw_newtype::{Error, RawRng};
+    RawRng::<Error>::try_next_u32(&mut raw_newtype::Infallible(r)).unwrap()
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn raw_newtype_as_rng_next_u32(
+    r: &mut raw_newtype::test_rngs::TestICRng<raw_newtype::test_rngs::SplitMix>,
+) -> u32 {
+    use raw_newtype::Rng;
+    raw_newtype::as_rng_ref(&mut raw_newtype::Infallible(r)).next_u32()
+}
+
+// `raw_rng` has no `RawRng<Error>` for plain generators: its adaptor is over
+// a crypto generator, whose `CryptoError` the source never gives
+#[no_mangle]
+#[inline(never)]
+pub fn raw_rng_next_u32(r: &mut raw_rng::test_rngs::TestRng<raw_rng::test_rngs::SplitMix>) -> u32 {
+    use raw_rng::Rng;
+    r.next_u32()
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn raw_rng_try_next_u32(
+    r: &mut raw_rng::test_rngs::TestRng<raw_rng::test_rngs::SplitMix>,
+) -> u32 {
+    use raw_rng::RawRng;
+    RawRng::<!>::try_next_u32(r).unwrap_or_else(|e| e)
+}
+
+#[no_mangle]
+#[inline(never)]
+pub fn raw_rng_as_rng_next_u32(
+    r: &mut raw_rng::test_rngs::TestCRng<raw_rng::test_rngs::SplitMix>,
+) -> u32 {
+    use raw_rng::Rng;
+    raw_rng::as_rng(r).next_u32()
+}
+
+// ——— assembly ———
+
+/// The instructions of `name` in `asm`, local labels renamed; or the function
+/// it was merged into, as `Err`. Panics if `asm` hasn't got it.
+fn instructions(asm: &str, name: &str) -> Result<Vec<String>, String> {
+    let start = format!("{}:", name);
+    let mut lines = asm.lines().map(str::trim);
+    let mut found = false;
+    for line in lines.by_ref() {
+        if line == start {
+            found = true;
+            break;
+        }
+        // Identical functions may be emitted once, the others as aliases
+        for alias in [format!(".set {}, ", name), format!("{} = ", name)] {
+            if let Some(target) = line.strip_prefix(&alias) {
+                return Err(target.to_string());
+            }
+        }
+    }
+    assert!(found, "{} not in the assembly", name);
+    let mut body = Vec::new();
+    for line in lines {
+        if line.starts_with(".cfi_endproc") || line.starts_with(".Lfunc_end") {
+            break;
+        }
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() || line.ends_with(':') || line.starts_with('.') {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace()
+            .map(|w| if w.starts_with(".L") { ".L" } else { w })
+            .collect();
+        body.push(words.join(" "));
+    }
+    Ok(body)
+}
+
+const FUNCTIONS: [&str; 21] = [
+    "marker_only_next_u32",
+    "extends_rng_try_next_u32",
+    "raw_default_next_u32",
+    "raw_default_try_next_u32",
+    "raw_default_error_next_u32",
+    "raw_default_as_rng_next_u32",
+    "raw_explicit_next_u32",
+    "raw_explicit_try_next_u32",
+    "raw_explicit_error_next_u32",
+    "raw_explicit_as_rng_next_u32",
+    "raw_infallible_next_u32",
+    "raw_infallible_try_next_u32",
+    "raw_infallible_error_next_u32",
+    "raw_infallible_as_rng_next_u32",
+    "raw_newtype_next_u32",
+    "raw_newtype_try_next_u32",
+    "raw_newtype_error_next_u32",
+    "raw_newtype_as_rng_next_u32",
+    "raw_rng_next_u32",
+    "raw_rng_try_next_u32",
+    "raw_rng_as_rng_next_u32",
+];
+
+// ——— usage ———
+
+fn main() {
+    if cfg!(debug_assertions) {
+        panic!("build with -O: unoptimised, every layer is a call");
+    }
+    // `--emit asm,link` puts the assembly beside the program
+    let path = env::args_os().nth(1).map(PathBuf::from)
+        .unwrap_or_else(|| env::current_exe().unwrap().with_extension("s"));
+    let asm = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {} (build with --emit asm,link)", path.display(), e));
+    let baseline = instructions(&asm, "baseline_next_u32").expect("baseline merged away");
+    println!("baseline_next_u32: {} instructions", baseline.len());
+    
+    let mut differ = Vec::new();
+    for name in FUNCTIONS.iter() {
+        match instructions(&asm, name) {
+            Err(ref target) if target == "baseline_next_u32" => {
+                println!("{:32} the baseline (merged)", name);
+            }
+            Err(target) => panic!("{}: merged into {}", name, target),
+            Ok(ref body) if *body == baseline => println!("{:32} the baseline", name),
+            Ok(body) => {
+                println!("{:32} {} instructions, not the baseline's:", name, body.len());
+                for line in &body {
+                    println!("    {}", line);
+                }
+                differ.push(*name);
+            }
+        }
+    }
+    
+    // Use the functions, so the program checks them against each other too
+    let mut s = marker_only::test_rngs::SplitMix(1);
+    let want = baseline_next_u32(&mut s);
+    let mut r = raw_explicit::test_rngs::TestICRng(raw_explicit::test_rngs::SplitMix(1));
+    assert_eq!(raw_explicit_as_rng_next_u32(&mut r), want);
+    let mut r = raw_rng::test_rngs::TestCRng(raw_rng::test_rngs::SplitMix(1));
+    assert_eq!(raw_rng_as_rng_next_u32(&mut r), want);
+    
+    assert!(differ.is_empty(), "not compiled away: {:?}", differ);
+    println!("every path: the baseline's instructions");
+}
@@ -0,0 +1,314 @@
+//! Golden vectors: committed output digests over a matrix of seeds
+//! 
+//! For each backend, generates output from every seed in a matrix (all
+//! zeros, all ones, and every single-bit seed) through every derivation path:
+//! 
+//! *   `next_u32`, `next_u64`, `fill`: the backend's own methods
+//! *   `next_u32 via u64`: `impl_next_u32_via_u64`, the low half of `next_u64`
+//! *   `fill via words`: `impl_fill_from_u64` from `traits/marker_only.rs`
+//! *   `next_u64 via fill`: `impl_next_u64_from_fill`
+//! 
+//! and compares an FNV-1a digest of each path's output over all seeds with
+//! the committed value in `GOLDEN`. Any change to a stream, e.g. by
+//! refactoring a default method or helper, fails the run. Seeds a backend
+//! rejects (xoshiro's all-zero seed) are part of the digest too.
+//! 
+//! Backends: `Xoshiro128` (from `distributions/simd.rs`) and `Pcg32` (from
+//! `traits/pcg.rs`, checked against the reference output first) always;
+//! `ChaCha20Rng` and `Pcg64` with the `rand_chacha` / `rand_pcg` features,
+//! passed as in `bench/never_type_overhead.rs`:
+//! 
+//!     rustc golden_vectors.rs && ./golden_vectors
+//!     rustc --cfg 'feature="rand_chacha"' --cfg 'feature="rand_pcg"' \
+//!         --extern rand_core=PATH/librand_core.rlib \
+//!         --extern rand_chacha=PATH/librand_chacha.rlib \
+//!         --extern rand_pcg=PATH/librand_pcg.rlib -L PATH golden_vectors.rs && ./golden_vectors
+//! 
+//! After an *intended* change of stream, `./golden_vectors print` prints the
+//! new `GOLDEN` entries to commit.
+//! 
+//! Thoughts: digests keep the committed data small enough to review, at the
+//! cost of saying only which backend and path changed, not which seed; for
+//! that, compare `print` output before and after.
+
+#[cfg(any(feature = "rand_chacha", feature = "rand_pcg"))]
+extern crate rand_core;
+#[cfg(feature = "rand_chacha")]
+extern crate rand_chacha;
+#[cfg(feature = "rand_pcg")]
+extern crate rand_pcg;
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+
+#[path = "../distributions/simd.rs"]
+#[allow(dead_code, unused_attributes, unused_imports)]
+mod simd;
+
+use marker_only::test_rngs::pcg;
+use marker_only::test_rngs::seeding::SeedableRng;
+use marker_only::{impl_fill_from_u64, impl_next_u32_via_u64, impl_next_u64_from_fill, Error, Rng};
+use std::env;
+
+/// Committed digests: (backend, path, digest).
+const GOLDEN: &[(&str, &str, u64)] = &[
+    ("Xoshiro128", "next_u32", 0x20f2faf409e4e8df),
+    ("Xoshiro128", "next_u64", 0x04f11f88fbfce8d6),
+    ("Xoshiro128", "next_u32 via u64", 0xeb5c3c04749fb50d),
+    ("Xoshiro128", "fill", 0x202dae60bd4f5b84),
+    ("Xoshiro128", "fill via words", 0x202dae60bd4f5b84),
+    ("Xoshiro128", "next_u64 via fill", 0x04f11f88fbfce8d6),
+    ("Pcg32", "next_u32", 0x3060233e4e52b2a7),
+    ("Pcg32", "next_u64", 0x961491930c778edf),
+    ("Pcg32", "next_u32 via u64", 0xe53de778106f5343),
+    ("Pcg32", "fill", 0xf857562e589940ad),
+    ("Pcg32", "fill via words", 0xf857562e589940ad),
+    ("Pcg32", "next_u64 via fill", 0x961491930c778edf),
+    ("ChaCha20Rng", "next_u32", 0xa3790f7c7b8f25b1),
+    ("ChaCha20Rng", "next_u64", 0x20f39ca74ccb1aa0),
+    ("ChaCha20Rng", "next_u32 via u64", 0xbda69c8f5b49ac12),
+    ("ChaCha20Rng", "fill", 0xd4fd47bf24ad5316),
+    ("ChaCha20Rng", "fill via words", 0xd4fd47bf24ad5316),
+    ("ChaCha20Rng", "next_u64 via fill", 0x20f39ca74ccb1aa0),
+    ("Pcg64", "next_u32", 0x2fab571df4e693f8),
+    ("Pcg64", "next_u64", 0x6964c4675302489e),
+    ("Pcg64", "next_u32 via u64", 0x2fab571df4e693f8),
+    ("Pcg64", "fill", 0xbbc31b69431a8443),
+    ("Pcg64", "fill via words", 0xbbc31b69431a8443),
+    ("Pcg64", "next_u64 via fill", 0x6964c4675302489e),
+];
+
+/// Words per path and seed (bytes for `fill` paths: `FILL_LEN`).
+const WORDS: usize = 4;
+/// Covers whole words and a partial one.
+const FILL_LEN: usize = 37;
+
+// ——— backends ———
+
+/// A backend under test, adapted to `marker_only::Rng`.
+trait Backend: Rng + Sized {
+    const NAME: &'static str;
+    const SEED_LEN: usize;
+    
+    /// `None` if the generator rejects this seed.
+    fn from_seed(seed: &[u8]) -> Option<Self>;
+}
+
+struct Xoshiro(simd::Xoshiro128);
+
+impl Rng for Xoshiro {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        simd::Rng::next_u32(&mut self.0)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        simd::Rng::next_u64(&mut self.0)
+    }
+}
+
+impl Backend for Xoshiro {
+    const NAME: &'static str = "Xoshiro128";
+    const SEED_LEN: usize = 16;
+    
+    fn from_seed(seed: &[u8]) -> Option<Self> {
+        let mut words = [0u32; 4];
+        for (w, b) in words.iter_mut().zip(seed.chunks(4)) {
+            *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        if words == [0; 4] {
+            return None;
+        }
+        Some(Xoshiro(simd::Xoshiro128::new(words)))
+    }
+}
+
+struct Pcg32(pcg::Pcg32);
+
+impl Rng for Pcg32 {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        let low = self.0.next_u32();
+        u64::from(self.0.next_u32()) << 32 | u64::from(low)
+    }
+}
+
+impl Backend for Pcg32 {
+    const NAME: &'static str = "Pcg32";
+    const SEED_LEN: usize = 16;
+    
+    fn from_seed(seed: &[u8]) -> Option<Self> {
+        let mut s = [0u8; 16];
+        s.copy_from_slice(seed);
+        Some(Pcg32(pcg::Pcg32::from_seed(s)))
+    }
+}
+
+/// Backends from other crates, via `rand_core::RngCore`.
+#[allow(unused_macros)]
+macro_rules! rng_core_backend {
+    ($name:ident, $ty:ty) => {
+        struct $name($ty);
+        
+        impl Rng for $name {
+            fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+                rand_core::RngCore::fill_bytes(&mut self.0, dest);
+                Ok(())
+            }
+            
+            fn next_u32(&mut self) -> u32 {
+                rand_core::RngCore::next_u32(&mut self.0)
+            }
+            
+            fn next_u64(&mut self) -> u64 {
+                rand_core::RngCore::next_u64(&mut self.0)
+            }
+        }
+        
+        impl Backend for $name {
+            const NAME: &'static str = stringify!($name);
+            const SEED_LEN: usize = 32;
+            
+            fn from_seed(seed: &[u8]) -> Option<Self> {
+                let mut s = [0u8; 32];
+                s.copy_from_slice(seed);
+                Some($name(rand_core::SeedableRng::from_seed(s)))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "rand_chacha")]
+rng_core_backend!(ChaCha20Rng, rand_chacha::ChaCha20Rng);
+#[cfg(feature = "rand_pcg")]
+rng_core_backend!(Pcg64, rand_pcg::Pcg64);
+
+// ——— paths ———
+
+fn words<B, F: Fn(&mut B) -> u64>(rng: &mut B, f: F, bytes: usize) -> Vec<u8> {
+    (0..WORDS).flat_map(|_| f(rng).to_le_bytes()[..bytes].to_vec()).collect()
+}
+
+fn paths<B: Backend>() -> Vec<(&'static str, fn(&mut B) -> Vec<u8>)> {
+    vec![
+        ("next_u32", |r: &mut B| words(r, |r: &mut B| r.next_u32() as u64, 4)),
+        ("next_u64", |r: &mut B| words(r, |r: &mut B| r.next_u64(), 8)),
+        ("next_u32 via u64", |r: &mut B| words(r, |r: &mut B| impl_next_u32_via_u64(r) as u64, 4)),
+        ("fill", |r: &mut B| {
+            let mut buf = vec![0u8; FILL_LEN];
+            r.try_fill(&mut buf).unwrap();
+            buf
+        }),
+        ("fill via words", |r: &mut B| {
+            let mut buf = vec![0u8; FILL_LEN];
+            impl_fill_from_u64(r, &mut buf);
+            buf
+        }),
+        ("next_u64 via fill", |r: &mut B| words(r, |r: &mut B| impl_next_u64_from_fill(r), 8)),
+    ]
+}
+
+// ——— matrix ———
+
+/// All zeros, all ones, then each single bit (bit `i` is bit `i % 8` of byte
+/// `i / 8`).
+fn seeds(len: usize) -> Vec<Vec<u8>> {
+    let mut seeds = vec![vec![0u8; len], vec![!0u8; len]];
+    for bit in 0..8 * len {
+        let mut seed = vec![0u8; len];
+        seed[bit / 8] = 1 << (bit % 8);
+        seeds.push(seed);
+    }
+    seeds
+}
+
+fn fnv1a(hash: &mut u64, bytes: &[u8]) {
+    for &b in bytes {
+        *hash ^= b as u64;
+        *hash = hash.wrapping_mul(0x100_0000_01B3);
+    }
+}
+
+/// Digest of each path over all seeds.
+fn digests<B: Backend>() -> Vec<(&'static str, u64)> {
+    paths::<B>().into_iter().map(|(path, f)| {
+        let mut hash = 0xCBF2_9CE4_8422_2325;
+        for seed in seeds(B::SEED_LEN) {
+            match B::from_seed(&seed) {
+                Some(mut rng) => fnv1a(&mut hash, &f(&mut rng)),
+                None => fnv1a(&mut hash, b"rejected"),
+            }
+        }
+        (path, hash)
+    }).collect()
+}
+
+/// Check (or print) one backend; returns the number of mismatches.
+fn check<B: Backend>(print: bool) -> usize {
+    let mut failures = 0;
+    for (path, digest) in digests::<B>() {
+        if print {
+            println!("    (\"{}\", \"{}\", {:#018x}),", B::NAME, path, digest);
+            continue;
+        }
+        match GOLDEN.iter().find(|g| g.0 == B::NAME && g.1 == path) {
+            Some(g) if g.2 == digest => println!("{}, {}: ok", B::NAME, path),
+            Some(g) => {
+                println!("{}, {}: CHANGED (committed {:#018x}, now {:#018x})",
+                    B::NAME, path, g.2, digest);
+                failures += 1;
+            }
+            None => {
+                println!("{}, {}: no committed digest", B::NAME, path);
+                failures += 1;
+            }
+        }
+    }
+    failures
+}
+
+// ——— usage ———
+
+fn main() {
+    let print = env::args().nth(1).map_or(false, |a| a == "print");
+    let mut failures = 0;
+    {
+        // Sanity: the matrix and digest are what the header says
+        assert_eq!(seeds(16).len(), 2 + 128);
+        assert_eq!(seeds(16)[2 + 9][1], 2);
+        let mut h = 0xCBF2_9CE4_8422_2325;
+        fnv1a(&mut h, b"a");
+        assert_eq!(h, 0xAF63_DC4C_8601_EC8C);
+        assert!(Xoshiro::from_seed(&[0; 16]).is_none());
+        // The reference output of `pcg32_srandom(42, 54)`
+        let mut p = pcg::Pcg32::new(42, 54);
+        let want = [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e];
+        assert!((0..6).map(|_| p.next_u32()).eq(want.iter().cloned()));
+    }
+    failures += check::<Xoshiro>(print);
+    failures += check::<Pcg32>(print);
+    #[cfg(feature = "rand_chacha")] {
+        failures += check::<ChaCha20Rng>(print);
+    }
+    #[cfg(feature = "rand_pcg")] {
+        failures += check::<Pcg64>(print);
+    }
+    assert_eq!(failures, 0, "{} stream(s) changed; see above", failures);
+}
@@ -0,0 +1,327 @@
+//! Differential tests: fill/conversion helpers vs naive references
+//! 
+//! Some of the helpers in `traits/marker_only.rs` use raw pointers for speed.
+//! This file includes that module as-is and compares each helper against a
+//! slow, obviously-correct reference written with byte shifts only:
+//! 
+//! *   `impl_fill_from_u64` vs `ref_fill_from_u64`: random lengths, into
+//!     random offsets of a larger buffer (bytes outside the target must be
+//!     untouched)
+//! *   `impl_fill_uninit_from_u64`, and `BlockRng::fill_uninit` of
+//!     `traits/block.rs` (from a random position in its buffer), vs `fill`
+//!     of the same generator, likewise
+//! *   `impl_uint_from_fill!` vs `ref_uint_from_fill`, for u8 to u128
+//! *   `impl_next_u32_from_fill`, `impl_next_u64_from_fill` and
+//!     `impl_next_u128_from_fill` vs `ref_uint_from_fill`
+//! *   `impl_next_u32_via_u64`, `impl_next_u64_via_u32` and
+//!     `impl_next_u128_via_u64` vs `ref_uint_from_fill`, on a generator
+//!     filling from its words
+//! *   `fill_slice` vs `ref_fill_slice`, decoding a byte fill with
+//!     `from_le_bytes`, for `u16` to `u128`: the same integers on any target
+//! *   the float helpers (`f64_closed_open01` and the rest) vs `ref_float`, a
+//!     division of the kept bits by a power of two
+//! 
+//! Besides the output, both sides must leave the source generator in the same
+//! state, i.e. consume the same number of words or bytes.
+//! 
+//! Run with:
+//! 
+//!     rustc -O helpers_differential.rs && ./helpers_differential [SEED]
+//! 
+//! A failure prints the seed and the failing input, and is reproducible by
+//! passing that seed.
+//! 
+//! Thoughts: the references define the semantics (little-endian words, the
+//! tail of a partial word discarded), so an optimisation which changes them
+//! fails here even if it is memory-safe.
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+
+use marker_only::test_rngs::block::BlockRng;
+use marker_only::test_rngs::{Source, SplitMix, SplitMixCore};
+use marker_only::{Error, Rng};
+use std::env;
+use std::mem::MaybeUninit;
+
+/// Cases per helper and word size.
+const CASES: usize = 10_000;
+/// Longest fill tested; several words, plus every partial-word tail.
+const MAX_LEN: usize = 67;
+
+// ——— references ———
+
+/// Fill `dest` with the little-endian bytes of successive `next_u64` values,
+/// discarding the unused tail of the last one.
+fn ref_fill_from_u64<R: Rng+?Sized>(rng: &mut R, dest: &mut [u8]) {
+    let mut i = 0;
+    while i < dest.len() {
+        let x = rng.next_u64();
+        let mut j = 0;
+        while j < 8 && i < dest.len() {
+            dest[i] = (x >> (8 * j)) as u8;
+            i += 1;
+            j += 1;
+        }
+    }
+}
+
+/// Read an integer of `n` bytes, little-endian, via `fill`.
+fn ref_uint_from_fill<R: Rng+?Sized>(rng: &mut R, n: usize) -> u128 {
+    let mut buf = vec![0u8; n];
+    rng.fill(&mut buf);
+    let mut x = 0u128;
+    for (i, &b) in buf.iter().enumerate() {
+        x |= (b as u128) << (8 * i);
+    }
+    x
+}
+
+/// Fill `dest` as `ref_fill_from_u64` would its bytes, decoding each
+/// integer with `from_le_bytes`.
+macro_rules! ref_fill_slice {
+    ($rng:expr, $dest:expr, $ty:ty) => {{
+        const N: usize = ::std::mem::size_of::<$ty>();
+        let mut buf = vec![0u8; $dest.len() * N];
+        ref_fill_from_u64($rng, &mut buf);
+        for (x, b) in $dest.iter_mut().zip(buf.chunks(N)) {
+            let mut le = [0u8; N];
+            le.copy_from_slice(b);
+            *x = <$ty>::from_le_bytes(le);
+        }
+    }};
+}
+
+/// The float of `bits` significant bits of `x`: the top `bits`, plus
+/// `offset`, over 2^`bits`. Exact: the numerator fits a float's mantissa.
+fn ref_float(x: u64, bits: u32, offset: u64) -> f64 {
+    ((x >> (64 - bits)) + offset) as f64 / 2f64.powi(bits as i32)
+}
+
+// ——— test RNGs ———
+
+// `SplitMix` of `test_rngs.rs` (through `marker_only`) both generates the
+// inputs and is the generator under test.
+
+/// An input below `n`.
+fn below(input: &mut SplitMix, n: usize) -> usize {
+    (input.word() % n as u64) as usize
+}
+
+/// Word-oriented source: `next_u64` is primary; `try_fill` uses the reference.
+#[derive(Debug, Clone, PartialEq)]
+struct WordRng(SplitMix);
+
+impl Rng for WordRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        ref_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0.word()
+    }
+}
+
+/// Byte-oriented source: `try_fill` is primary, one byte per step.
+#[derive(Debug, Clone, PartialEq)]
+struct ByteRng(SplitMix);
+
+impl Rng for ByteRng {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for b in dest.iter_mut() {
+            *b = self.0.word() as u8;
+        }
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        ref_uint_from_fill(self, 8) as u64
+    }
+}
+
+// ——— differential tests ———
+
+fn fill_from_u64(input: &mut SplitMix) {
+    for _ in 0..CASES {
+        let len = below(input, MAX_LEN + 1);
+        let offset = below(input, 16);
+        let mut a = vec![0xA5u8; offset + len + 16];
+        let mut b = a.clone();
+        let mut ra = WordRng(SplitMix(input.word()));
+        let mut rb = ra.clone();
+        
+        marker_only::impl_fill_from_u64(&mut ra, &mut a[offset..offset + len]);
+        ref_fill_from_u64(&mut rb, &mut b[offset..offset + len]);
+        assert!(a == b && ra == rb,
+            "impl_fill_from_u64: len {}, offset {}, rng {:?}", len, offset, rb);
+    }
+}
+
+fn fill_uninit_from_u64(input: &mut SplitMix) {
+    for _ in 0..CASES {
+        let len = below(input, MAX_LEN + 1);
+        let offset = below(input, 16);
+        let mut a = vec![MaybeUninit::new(0xA5u8); offset + len + 16];
+        let mut b = vec![0xA5u8; offset + len + 16];
+        let mut ra = WordRng(SplitMix(input.word()));
+        let mut rb = ra.clone();
+        
+        marker_only::impl_fill_uninit_from_u64(&mut ra, &mut a[offset..offset + len]);
+        rb.fill(&mut b[offset..offset + len]);
+        let a: Vec<u8> = a.into_iter().map(|x| unsafe { x.assume_init() }).collect();
+        assert!(a == b && ra == rb,
+            "impl_fill_uninit_from_u64: len {}, offset {}, rng {:?}", len, offset, rb);
+    }
+}
+
+fn block_fill_uninit(input: &mut SplitMix) {
+    for _ in 0..CASES {
+        // Below two blocks, from any position in the buffer
+        let len = below(input, 2 * 64);
+        let offset = below(input, 16);
+        let mut a = vec![MaybeUninit::new(0xA5u8); offset + len + 16];
+        let mut b = vec![0xA5u8; offset + len + 16];
+        let mut ra = BlockRng::new(SplitMixCore(input.word()));
+        for _ in 0..below(input, 17) {
+            ra.next_u32();
+        }
+        let mut rb = ra.clone();
+        
+        ra.fill_uninit(&mut a[offset..offset + len]);
+        rb.fill(&mut b[offset..offset + len]);
+        let a: Vec<u8> = a.into_iter().map(|x| unsafe { x.assume_init() }).collect();
+        assert!(a == b && (ra.index(), ra.core()) == (rb.index(), rb.core()),
+            "BlockRng::fill_uninit: len {}, offset {}, rng {:?}", len, offset, rb);
+    }
+}
+
+macro_rules! uint_from_fill {
+    ($input:expr, $ty:ty, $N:expr) => {{
+        for _ in 0..CASES {
+            let mut ra = ByteRng(SplitMix($input.word()));
+            let mut rb = ra.clone();
+            let x: $ty = impl_uint_from_fill!($ty, $N, &mut ra);
+            let y = ref_uint_from_fill(&mut rb, $N) as $ty;
+            assert!(x == y && ra == rb,
+                "impl_uint_from_fill!({}): rng {:?}", stringify!($ty), rb);
+        }
+    }};
+}
+
+macro_rules! next_from_fill {
+    ($input:expr, $f:ident, $ty:ty, $N:expr) => {{
+        for _ in 0..CASES {
+            let mut ra = ByteRng(SplitMix($input.word()));
+            let mut rb = ra.clone();
+            let x = marker_only::$f(&mut ra);
+            let y = ref_uint_from_fill(&mut rb, $N) as $ty;
+            assert!(x == y && ra == rb, "{}: rng {:?}", stringify!($f), rb);
+        }
+    }};
+}
+
+// `WordRng` fills from its words, so the word helpers must read the same
+// integers as `fill` does: the low half of a word, and words low first.
+fn via_words(input: &mut SplitMix) {
+    for _ in 0..CASES {
+        let mut ra = WordRng(SplitMix(input.word()));
+        let mut rb = ra.clone();
+        let x = marker_only::impl_next_u32_via_u64(&mut ra);
+        let y = ref_uint_from_fill(&mut rb, 4) as u32;
+        assert!(x == y && ra == rb, "impl_next_u32_via_u64: rng {:?}", rb);
+        let x = marker_only::impl_next_u64_via_u32(&mut ra);
+        let y = ref_uint_from_fill(&mut rb, 4) | ref_uint_from_fill(&mut rb, 4) << 32;
+        assert!(x as u128 == y && ra == rb, "impl_next_u64_via_u32: rng {:?}", rb);
+        let x = marker_only::impl_next_u128_via_u64(&mut ra);
+        let y = ref_uint_from_fill(&mut rb, 16);
+        assert!(x == y && ra == rb, "impl_next_u128_via_u64: rng {:?}", rb);
+    }
+}
+
+macro_rules! fill_slice {
+    ($input:expr, $ty:ty) => {{
+        for _ in 0..CASES {
+            let len = below(&mut $input, MAX_LEN / ::std::mem::size_of::<$ty>() + 1);
+            let mut a = vec![0 as $ty; len];
+            let mut b = a.clone();
+            let mut ra = WordRng(SplitMix($input.word()));
+            let mut rb = ra.clone();
+            marker_only::fill_slice(&mut ra, &mut a[..]);
+            ref_fill_slice!(&mut rb, b, $ty);
+            assert!(a == b && ra == rb, "fill_slice({}): len {}, rng {:?}", stringify!($ty), len, rb);
+        }
+    }};
+}
+
+fn floats(input: &mut SplitMix) {
+    for _ in 0..CASES {
+        let x = input.word();
+        assert!(marker_only::f64_closed_open01(x) == ref_float(x, 53, 0)
+            && marker_only::f64_open_closed01(x) == ref_float(x, 53, 1)
+            && marker_only::f64_closed_open01_exp(x) == ref_float(x, 52, 0)
+            && marker_only::f64_open_closed01_exp(x) == 1.0 - ref_float(x, 52, 0),
+            "float helpers: word {:#x}", x);
+    }
+}
+
+// ——— usage ———
+
+fn main() {
+    let seed = match env::args().nth(1) {
+        Some(s) => s.parse().expect("SEED must be a u64"),
+        None => {
+            use std::time::{SystemTime, UNIX_EPOCH};
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+        }
+    };
+    println!("seed: {}", seed);
+    let mut input = SplitMix(seed);
+    
+    // Sanity check of the references themselves, on a known word
+    struct Fixed;
+    impl Rng for Fixed {
+        fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            ref_fill_from_u64(self, dest);
+            Ok(())
+        }
+        fn next_u64(&mut self) -> u64 {
+            0x0807_0605_0403_0201
+        }
+    }
+    let mut buf = [0u8; 10];
+    ref_fill_from_u64(&mut Fixed, &mut buf);
+    assert_eq!(buf, [1, 2, 3, 4, 5, 6, 7, 8, 1, 2]);
+    assert_eq!(ref_uint_from_fill(&mut Fixed, 3), 0x03_0201);
+    
+    fill_from_u64(&mut input);
+    println!("impl_fill_from_u64: {} cases ok", CASES);
+    fill_uninit_from_u64(&mut input);
+    println!("impl_fill_uninit_from_u64: {} cases ok", CASES);
+    block_fill_uninit(&mut input);
+    println!("BlockRng::fill_uninit: {} cases ok", CASES);
+    uint_from_fill!(input, u8, 1);
+    uint_from_fill!(input, u16, 2);
+    uint_from_fill!(input, u32, 4);
+    uint_from_fill!(input, u64, 8);
+    uint_from_fill!(input, u128, 16);
+    println!("impl_uint_from_fill!: {} cases ok for each of u8 to u128", CASES);
+    next_from_fill!(input, impl_next_u32_from_fill, u32, 4);
+    next_from_fill!(input, impl_next_u64_from_fill, u64, 8);
+    next_from_fill!(input, impl_next_u128_from_fill, u128, 16);
+    println!("impl_next_u*_from_fill: {} cases ok for each of u32 to u128", CASES);
+    via_words(&mut input);
+    println!("impl_next_*_via_*: {} cases ok", CASES);
+    fill_slice!(input, u16);
+    fill_slice!(input, u32);
+    fill_slice!(input, u64);
+    fill_slice!(input, u128);
+    println!("fill_slice: {} cases ok for each of u16 to u128", CASES);
+    floats(&mut input);
+    println!("float helpers: {} cases ok", CASES);
+}
@@ -0,0 +1,282 @@
+//! Kani proof harnesses for the fill helpers' memory safety
+//! 
+//! For every destination length up to `MAX_LEN`, every offset and every
+//! generator output, proves that the helpers in `traits/marker_only.rs`, and
+//! `BlockRng::fill_uninit` of `traits/block.rs` (for every length below two
+//! blocks, from every position in the buffer):
+//! 
+//! *   never access memory out of bounds: Kani checks every pointer operation,
+//!     and the guard bytes around the destination must be unchanged
+//! *   initialise every destination byte: the result must not depend on the
+//!     destination's previous contents (for the `fill_uninit` helpers, which
+//!     return the bytes as initialised, it must be exactly what `fill` gives)
+//! *   (for `impl_uint_from_fill!`) read exactly the bytes written by `fill`
+//! 
+//! Run with [Kani](https://model-checking.github.io/kani/):
+//! 
+//!     kani helpers_kani.rs
+//! 
+//! Without Kani, `kani::any` is emulated with pseudo-random values, so
+//! 
+//!     rustc helpers_kani.rs && ./helpers_kani
+//! 
+//! runs each harness on random inputs instead: a smoke test, not a proof.
+//! 
+//! Thoughts: writing these showed that `impl_uint_from_fill!` dereferenced a
+//! `*const u64` cast from a `[u8; 8]`, which need not be aligned (Kani checks
+//! alignment; a debug build of `helpers_differential.rs` also panicked, but
+//! only because the buffer happened to land misaligned). It now uses
+//! `from_le_bytes`, with no pointer at all. Lengths are bounded because Kani
+//! unrolls loops, but two words plus every partial-word tail covers every
+//! path through the helpers.
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+
+#[path = "../traits/block.rs"]
+#[allow(dead_code)]
+mod block;
+
+use block::{BlockRng, BlockRngCore};
+use marker_only::{Error, Rng};
+use std::mem::MaybeUninit;
+
+/// Longest destination checked: two words and a partial one.
+const MAX_LEN: usize = 23;
+/// Guard bytes either side of the destination.
+const GUARD: usize = 8;
+/// Destination buffer: guards, up to 7 bytes of offset, destination.
+const BUF: usize = MAX_LEN + 2 * GUARD + 8;
+/// Words per block of `Blocks`.
+const BLOCK: usize = 4;
+/// Longest `BlockRng` destination checked: just under two blocks.
+const BLOCK_MAX_LEN: usize = 2 * BLOCK * 4 - 1;
+/// The same buffer, for `BlockRng` destinations.
+const BLOCK_BUF: usize = BLOCK_MAX_LEN + 2 * GUARD + 8;
+
+// ——— kani stand-in ———
+
+/// Random stand-in for Kani's API, used when not running under Kani.
+#[cfg(not(kani))]
+mod kani {
+    use std::cell::Cell;
+    
+    thread_local!(static STATE: Cell<u64> = Cell::new(0x2545_F491_4F6C_DD1D));
+    
+    fn next() -> u64 {
+        STATE.with(|s| {
+            let mut x = s.get();
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            s.set(x);
+            x
+        })
+    }
+    
+    pub trait Arbitrary {
+        fn any() -> Self;
+    }
+    
+    impl Arbitrary for u8 {
+        fn any() -> Self { next() as u8 }
+    }
+    
+    impl Arbitrary for u32 {
+        fn any() -> Self { next() as u32 }
+    }
+    
+    impl Arbitrary for u64 {
+        fn any() -> Self { next() }
+    }
+    
+    impl<T: Arbitrary, const N: usize> Arbitrary for [T; N] {
+        fn any() -> Self { [(); N].map(|_| T::any()) }
+    }
+    
+    pub fn any<T: Arbitrary>() -> T {
+        T::any()
+    }
+}
+
+// ——— test RNGs ———
+
+/// Yields the given words from `next_u64`; using more is an error.
+struct Words {
+    words: [u64; 3],
+    used: usize,
+}
+
+impl Rng for Words {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        marker_only::impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        assert!(self.used < self.words.len(), "too many words consumed");
+        self.used += 1;
+        self.words[self.used - 1]
+    }
+}
+
+/// Fills from the given bytes; requesting more is an error.
+struct Bytes([u8; 16]);
+
+impl Rng for Bytes {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        assert!(dest.len() <= self.0.len(), "too many bytes requested");
+        dest.copy_from_slice(&self.0[..dest.len()]);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        marker_only::impl_next_u64_from_fill(self)
+    }
+}
+
+/// Generates blocks from the given words; a fourth block is an error.
+#[derive(Clone)]
+struct Blocks {
+    words: [u32; 3 * BLOCK],
+    blocks: usize,
+}
+
+impl BlockRngCore for Blocks {
+    type Results = [u32; BLOCK];
+    
+    fn generate(&mut self, results: &mut [u32; BLOCK]) {
+        assert!(self.blocks < 3, "too many blocks generated");
+        results.copy_from_slice(&self.words[self.blocks * BLOCK..][..BLOCK]);
+        self.blocks += 1;
+    }
+}
+
+// ——— harnesses ———
+
+#[cfg_attr(kani, kani::proof)]
+#[cfg_attr(kani, kani::unwind(48))]
+fn fill_from_u64() {
+    let len = usize::from(kani::any::<u8>()) % (MAX_LEN + 1);
+    let start = GUARD + usize::from(kani::any::<u8>()) % 8;
+    let end = start + len;
+    let words: [u64; 3] = kani::any();
+    let mut a: [u8; BUF] = kani::any();
+    let mut b: [u8; BUF] = kani::any();
+    let (old_a, old_b) = (a, b);
+    
+    marker_only::impl_fill_from_u64(&mut Words { words, used: 0 }, &mut a[start..end]);
+    marker_only::impl_fill_from_u64(&mut Words { words, used: 0 }, &mut b[start..end]);
+    
+    // In bounds: nothing outside the destination changed
+    assert!(a[..start] == old_a[..start] && a[end..] == old_a[end..]);
+    assert!(b[..start] == old_b[..start] && b[end..] == old_b[end..]);
+    // Initialised: the destination's old contents made no difference
+    assert!(a[start..end] == b[start..end]);
+}
+
+#[cfg_attr(kani, kani::proof)]
+#[cfg_attr(kani, kani::unwind(48))]
+fn fill_uninit_from_u64() {
+    let len = usize::from(kani::any::<u8>()) % (MAX_LEN + 1);
+    let start = GUARD + usize::from(kani::any::<u8>()) % 8;
+    let end = start + len;
+    let words: [u64; 3] = kani::any();
+    let old: [u8; BUF] = kani::any();
+    let mut buf = old.map(MaybeUninit::new);
+    let mut want = [0u8; MAX_LEN];
+    
+    let mut rng = Words { words, used: 0 };
+    let got = marker_only::impl_fill_uninit_from_u64(&mut rng, &mut buf[start..end]);
+    let mut reference = Words { words, used: 0 };
+    reference.fill(&mut want[..len]);
+    
+    // Initialised: the bytes of `fill`, whatever was there before
+    assert!(*got == want[..len] && rng.used == reference.used);
+    // In bounds: nothing outside the destination changed
+    let buf = buf.map(|b| unsafe { b.assume_init() });
+    assert!(buf[..start] == old[..start] && buf[end..] == old[end..]);
+}
+
+#[cfg_attr(kani, kani::proof)]
+#[cfg_attr(kani, kani::unwind(56))]
+fn block_fill_uninit() {
+    let len = usize::from(kani::any::<u8>()) % (BLOCK_MAX_LEN + 1);
+    let start = GUARD + usize::from(kani::any::<u8>()) % 8;
+    let end = start + len;
+    let old: [u8; BLOCK_BUF] = kani::any();
+    let mut buf = old.map(MaybeUninit::new);
+    let mut want = [0u8; BLOCK_MAX_LEN];
+    
+    // From every position: a fresh buffer, part used, or drained
+    let mut rng = BlockRng::new(Blocks { words: kani::any(), blocks: 0 });
+    for _ in 0..usize::from(kani::any::<u8>()) % (BLOCK + 1) {
+        rng.next_u32();
+    }
+    let mut reference = rng.clone();
+    let got = rng.fill_uninit(&mut buf[start..end]);
+    reference.fill(&mut want[..len]);
+    
+    // Initialised: the bytes of `fill`, whatever was there before, leaving
+    // the generator where `fill` does
+    assert!(*got == want[..len]);
+    assert!(rng.index() == reference.index() && rng.core().blocks == reference.core().blocks);
+    // In bounds: nothing outside the destination changed
+    let buf = buf.map(|b| unsafe { b.assume_init() });
+    assert!(buf[..start] == old[..start] && buf[end..] == old[end..]);
+}
+
+macro_rules! uint_from_fill {
+    ($name:ident, $ty:ty, $N:expr) => {
+        #[cfg_attr(kani, kani::proof)]
+        #[cfg_attr(kani, kani::unwind(17))]
+        fn $name() {
+            let bytes: [u8; 16] = kani::any();
+            let x = impl_uint_from_fill!($ty, $N, &mut Bytes(bytes));
+            let mut le = [0u8; $N];
+            le.copy_from_slice(&bytes[..$N]);
+            assert!(x == <$ty>::from_le_bytes(le));
+        }
+    };
+}
+
+uint_from_fill!(u16_from_fill, u16, 2);
+uint_from_fill!(u32_from_fill, u32, 4);
+uint_from_fill!(u64_from_fill, u64, 8);
+uint_from_fill!(u128_from_fill, u128, 16);
+
+#[cfg_attr(kani, kani::proof)]
+#[cfg_attr(kani, kani::unwind(17))]
+fn next_u64_from_fill() {
+    let bytes: [u8; 16] = kani::any();
+    let mut le = [0u8; 8];
+    le.copy_from_slice(&bytes[..8]);
+    assert!(Bytes(bytes).next_u64() == u64::from_le_bytes(le));
+}
+
+// ——— usage ———
+
+fn main() {
+    const ROUNDS: usize = 10_000;
+    let harnesses: [(&str, fn()); 8] = [
+        ("fill_from_u64", fill_from_u64),
+        ("fill_uninit_from_u64", fill_uninit_from_u64),
+        ("block_fill_uninit", block_fill_uninit),
+        ("u16_from_fill", u16_from_fill),
+        ("u32_from_fill", u32_from_fill),
+        ("u64_from_fill", u64_from_fill),
+        ("u128_from_fill", u128_from_fill),
+        ("next_u64_from_fill", next_u64_from_fill),
+    ];
+    for &(name, harness) in harnesses.iter() {
+        for _ in 0..ROUNDS {
+            harness();
+        }
+        println!("{}: {} random rounds ok (not a proof; run under kani)", name, ROUNDS);
+    }
+}
@@ -0,0 +1,151 @@
+//! Stack wiping in the fill helpers (`secure-helpers` feature)
+//! 
+//! With `secure-helpers`, `impl_fill_from_u64` and `impl_uint_from_fill!`
+//! (from `traits/marker_only.rs`) overwrite their stack copies of generator
+//! output with volatile stores, dead to the optimiser. This checks that they
+//! survive by scanning the stack after the helpers return. Without the
+//! feature the run is a control, failing unless output *is* found.
+//! 
+//! Thoughts: reading memory which holds no live value is undefined in Rust;
+//! volatile reads of mapped stack do what is meant, enough for a controlled
+//! harness but not for Miri. The wipe only covers the helpers' own frames,
+//! which is why this is a feature for helpers and not a guarantee.
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+
+use marker_only::{Error, Rng};
+use std::hint::black_box;
+use std::ptr::read_volatile;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// The generator's output, every word: an atomic, as `black_box` would leave
+/// copies in the generator's own frame.
+static OUTPUT: AtomicU64 = AtomicU64::new(0xA5C3_96E1_D2B4_F078);
+/// Address of the last buffer passed to `try_fill`.
+static FILLED: AtomicUsize = AtomicUsize::new(0);
+/// Stack position of the last `next_u64` call.
+static CALLED_AT: AtomicUsize = AtomicUsize::new(0);
+
+/// Bytes of stack scanned, upwards from `CALLED_AT`: the frames of
+/// `impl_fill_from_u64` and its caller.
+const REGION: usize = 1024;
+
+// ——— test RNGs ———
+
+/// Outputs `OUTPUT`, recording where it writes.
+struct Spy;
+
+impl Rng for Spy {
+    #[inline(never)]
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        FILLED.store(dest.as_ptr() as usize, Ordering::SeqCst);
+        let x = OUTPUT.load(Ordering::Relaxed);
+        for (i, b) in dest.iter_mut().enumerate() {
+            *b = (x >> (8 * (i % 8))) as u8;
+        }
+        Ok(())
+    }
+    
+    #[inline(never)]
+    fn next_u64(&mut self) -> u64 {
+        let here = 0u8;
+        CALLED_AT.store(&here as *const u8 as usize, Ordering::SeqCst);
+        OUTPUT.load(Ordering::Relaxed)
+    }
+}
+
+// ——— harness ———
+
+/// Stack used below a harness function before calling a helper, so that
+/// calls made while reading back (all of them, in debug builds) stay clear of
+/// the helper's frame.
+const PAD: usize = 1024;
+
+#[inline(never)]
+fn next_u64_via_fill() -> u64 {
+    let pad = [0u8; PAD];
+    black_box(&pad);
+    marker_only::impl_next_u64_from_fill(&mut Spy)
+}
+
+#[inline(never)]
+fn fill_via_words(dest: &mut [u8]) {
+    let pad = [0u8; PAD];
+    black_box(&pad);
+    marker_only::impl_fill_from_u64(&mut Spy, dest)
+}
+
+#[cfg(feature = "unsafe-fill")]
+#[inline(never)]
+fn fill_via_words_ptr(dest: &mut [u8]) {
+    let pad = [0u8; PAD];
+    black_box(&pad);
+    marker_only::impl_fill_from_u64_ptr(&mut Spy, dest)
+}
+
+/// Offset of the output word in the `REGION` bytes of stack above where
+/// `next_u64` was last called from, if there. Only valid straight after the
+/// helper returns.
+#[inline(always)]
+fn scan_stack() -> Option<usize> {
+    let p = CALLED_AT.load(Ordering::SeqCst) as *const u8;
+    let x = OUTPUT.load(Ordering::Relaxed).to_le_bytes();
+    let mut region = [0u8; REGION];
+    for i in 0..REGION {
+        region[i] = unsafe { read_volatile(p.add(i)) };
+    }
+    (0..REGION - 8).find(|&i| region[i..i + 8] == x)
+}
+
+// ——— usage ———
+
+fn main() {
+    let secure = cfg!(feature = "secure-helpers");
+    let mode = if cfg!(debug_assertions) { "debug" } else { "optimised" };
+    let x = OUTPUT.load(Ordering::Relaxed);
+    
+    // impl_uint_from_fill!: the buffer itself
+    let y = next_u64_via_fill();
+    let buf = FILLED.load(Ordering::SeqCst) as *const u8;
+    let mut left = [0u8; 8];
+    for i in 0..8 {
+        left[i] = unsafe { read_volatile(buf.add(i)) };
+    }
+    assert_eq!(y, x);
+    let uint_left = left == x.to_le_bytes();
+    println!("impl_uint_from_fill! ({}): buffer after return {:02x?}", mode, left);
+    
+    // impl_fill_from_u64: anywhere in its frame
+    // (a length known to the optimiser lets it skip the temporary)
+    let mut dest = vec![0u8; black_box(13)];
+    fill_via_words(&mut dest);
+    let found = scan_stack();
+    let fill_left = found.is_some();
+    assert!(dest[..8] == x.to_le_bytes() && dest[8..] == x.to_le_bytes()[..5]);
+    println!("impl_fill_from_u64 ({}): output word {:?} on the stack", mode, found);
+    
+    assert!(!fill_left, "impl_fill_from_u64 left output on the stack");
+    
+    if secure {
+        assert!(left == [0; 8], "impl_uint_from_fill! left output on the stack");
+        println!("secure-helpers: temporaries wiped");
+    } else {
+        assert!(uint_left, "control: harness did not see impl_uint_from_fill!'s buffer");
+        #[cfg(feature = "unsafe-fill")] {
+            let mut dest = vec![0u8; black_box(13)];
+            fill_via_words_ptr(&mut dest);
+            let found = scan_stack();
+            println!("impl_fill_from_u64_ptr ({}): output word {:?} on the stack", mode, found);
+            assert!(found.is_some(), "control: harness did not see impl_fill_from_u64_ptr's temporary");
+        }
+        #[cfg(not(feature = "unsafe-fill"))]
+        println!("(no fill control: needs the unsafe-fill feature)");
+        println!("control: temporaries left behind, and seen");
+    }
+}
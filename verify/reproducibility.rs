@@ -0,0 +1,445 @@
+//! Reproducibility: committed output vectors, under both byte orders
+//! 
+//! Each concrete generator has a committed vector in `VECTORS` (a seed and
+//! the first words and bytes), written out to compare by eye with published
+//! reference output. Each is checked under both byte orders a host may have:
+//! `NativeFill<O>` emulates a generator filling with `to_ne_bytes` on an `O`
+//! host, and for the other order must fail `fill` and `fill_slice`, and only
+//! those.
+//! 
+//! Thoughts: every generator here passes under both orders, as
+//! `marker_only`'s helpers name their byte order; a design which leaves
+//! `fill` to each generator invites `NativeFill`, which passes every test on
+//! the machine it was written on.
+
+#[cfg(any(feature = "rand_chacha", feature = "rand_pcg"))]
+extern crate rand_core;
+#[cfg(feature = "rand_chacha")]
+extern crate rand_chacha;
+#[cfg(feature = "rand_pcg")]
+extern crate rand_pcg;
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[macro_use]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+mod marker_only;
+
+#[path = "../distributions/simd.rs"]
+#[allow(dead_code, unused_attributes, unused_imports)]
+mod simd;
+
+use marker_only::test_rngs::{SplitMix, TestRng};
+use marker_only::{fill_slice, impl_fill_from_u64, Error, Rng, Word32Rng};
+use std::env;
+use std::marker::PhantomData;
+
+/// Bytes of `fill` per vector: two words and a partial one.
+const FILL_LEN: usize = 19;
+
+/// A generator's committed output.
+struct Vector {
+    backend: &'static str,
+    seed: &'static [u8],
+    u32s: [u32; 4],
+    u64s: [u64; 4],
+    fill: [u8; FILL_LEN],
+}
+
+/// Committed vectors, one per backend.
+const VECTORS: &[Vector] = &[
+    Vector {
+        backend: "Xoshiro128",
+        seed: &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16],
+        u32s: [0x0d0b090b, 0x41b06eb7, 0xb99ba82f, 0x05800fc1],
+        u64s: [
+            0x41b06eb70d0b090b,
+            0x05800fc1b99ba82f,
+            0xea190e79deb64fc3,
+            0x7d4616b8b40d2a9e,
+        ],
+        fill: [
+            0x0b, 0x09, 0x0b, 0x0d, 0xb7, 0x6e, 0xb0, 0x41,
+            0x2f, 0xa8, 0x9b, 0xb9, 0xc1, 0x0f, 0x80, 0x05,
+            0xc3, 0x4f, 0xb6,
+        ],
+    },
+    Vector {
+        backend: "SplitMix64",
+        seed: &[239, 205, 171, 137, 103, 69, 35, 1],
+        u32s: [0xa48faa9d, 0x34a1d093, 0x996dccbe, 0x4c4667ec],
+        u64s: [
+            0x157a3807a48faa9d,
+            0xd573529b34a1d093,
+            0x2f90b72e996dccbe,
+            0xa2d419334c4667ec,
+        ],
+        fill: [
+            0x9d, 0xaa, 0x8f, 0xa4, 0x07, 0x38, 0x7a, 0x15,
+            0x93, 0xd0, 0xa1, 0x34, 0x9b, 0x52, 0x73, 0xd5,
+            0xbe, 0xcc, 0x6d,
+        ],
+    },
+    Vector {
+        backend: "SplitMix64, 32-bit words",
+        seed: &[239, 205, 171, 137, 103, 69, 35, 1],
+        u32s: [0xa48faa9d, 0x34a1d093, 0x996dccbe, 0x4c4667ec],
+        u64s: [
+            0x34a1d093a48faa9d,
+            0x4c4667ec996dccbe,
+            0x2a2b4c7214938008,
+            0x4f9ea65160708c05,
+        ],
+        fill: [
+            0x9d, 0xaa, 0x8f, 0xa4, 0x93, 0xd0, 0xa1, 0x34,
+            0xbe, 0xcc, 0x6d, 0x99, 0xec, 0x67, 0x46, 0x4c,
+            0x08, 0x80, 0x93,
+        ],
+    },
+    Vector {
+        backend: "ChaCha20Rng",
+        seed: &[
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+            16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+        ],
+        u32s: [0x7d2bfd39, 0x6a19c5d9, 0x7703bd8d, 0x494adcb8],
+        u64s: [
+            0x6a19c5d97d2bfd39,
+            0x494adcb87703bd8d,
+            0xcc6adebc6fd8358a,
+            0x9224ead84c7dccb2,
+        ],
+        fill: [
+            0x39, 0xfd, 0x2b, 0x7d, 0xd9, 0xc5, 0x19, 0x6a,
+            0x8d, 0xbd, 0x03, 0x77, 0xb8, 0xdc, 0x4a, 0x49,
+            0x8a, 0x35, 0xd8,
+        ],
+    },
+    Vector {
+        backend: "Pcg64",
+        seed: &[
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+            16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+        ],
+        u32s: [0x1213c156, 0xe90f621b, 0xd65bde5c, 0x4c709d0c],
+        u64s: [
+            0x8e0e8a7d1213c156,
+            0x62cc7147e90f621b,
+            0xdd223c84d65bde5c,
+            0xc964f8394c709d0c,
+        ],
+        fill: [
+            0x56, 0xc1, 0x13, 0x12, 0x7d, 0x8a, 0x0e, 0x8e,
+            0x1b, 0x62, 0x0f, 0xe9, 0x47, 0x71, 0xcc, 0x62,
+            0x5c, 0xde, 0x5b,
+        ],
+    },
+];
+
+// ——— byte orders ———
+
+/// A host's byte order: how it converts words natively.
+trait ByteOrder {
+    const NAME: &'static str;
+    
+    /// `x.to_ne_bytes()` on such a host.
+    fn bytes(x: u64) -> [u8; 8];
+    
+    /// `u64::from_ne_bytes(b)` on such a host.
+    fn word(b: [u8; 8]) -> u64;
+}
+
+enum Little {}
+
+impl ByteOrder for Little {
+    const NAME: &'static str = "little-endian";
+    
+    fn bytes(x: u64) -> [u8; 8] {
+        x.to_le_bytes()
+    }
+    
+    fn word(b: [u8; 8]) -> u64 {
+        u64::from_le_bytes(b)
+    }
+}
+
+enum Big {}
+
+impl ByteOrder for Big {
+    const NAME: &'static str = "big-endian";
+    
+    fn bytes(x: u64) -> [u8; 8] {
+        x.to_be_bytes()
+    }
+    
+    fn word(b: [u8; 8]) -> u64 {
+        u64::from_be_bytes(b)
+    }
+}
+
+/// The byte order of the host running this file.
+#[cfg(target_endian = "little")]
+type Host = Little;
+#[cfg(target_endian = "big")]
+type Host = Big;
+
+// ——— backends ———
+
+/// A backend under test, adapted to `marker_only::Rng`.
+trait Backend: Rng + Sized {
+    /// The backend's entry in `VECTORS`.
+    const NAME: &'static str;
+    const SEED: &'static [u8];
+    
+    fn from_seed(seed: &[u8]) -> Self;
+}
+
+struct Xoshiro(simd::Xoshiro128);
+
+impl Rng for Xoshiro {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u32(&mut self) -> u32 {
+        simd::Rng::next_u32(&mut self.0)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        simd::Rng::next_u64(&mut self.0)
+    }
+}
+
+impl Backend for Xoshiro {
+    const NAME: &'static str = "Xoshiro128";
+    const SEED: &'static [u8] = &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+    
+    fn from_seed(seed: &[u8]) -> Self {
+        let mut words = [0u32; 4];
+        for (w, b) in words.iter_mut().zip(seed.chunks(4)) {
+            *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        Xoshiro(simd::Xoshiro128::new(words))
+    }
+}
+
+/// The 8-byte seed, little-endian, as SplitMix64's state.
+fn split_mix(seed: &[u8]) -> SplitMix {
+    let mut s = [0u8; 8];
+    s.copy_from_slice(seed);
+    SplitMix(u64::from_le_bytes(s))
+}
+
+const SPLIT_MIX_SEED: &[u8] = &[0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01];
+
+impl Backend for TestRng<SplitMix> {
+    const NAME: &'static str = "SplitMix64";
+    const SEED: &'static [u8] = SPLIT_MIX_SEED;
+    
+    fn from_seed(seed: &[u8]) -> Self {
+        TestRng(split_mix(seed))
+    }
+}
+
+impl Backend for Word32Rng<SplitMix> {
+    const NAME: &'static str = "SplitMix64, 32-bit words";
+    const SEED: &'static [u8] = SPLIT_MIX_SEED;
+    
+    fn from_seed(seed: &[u8]) -> Self {
+        Word32Rng(split_mix(seed))
+    }
+}
+
+/// Backends from other crates, via `rand_core::RngCore`.
+#[allow(unused_macros)]
+macro_rules! rng_core_backend {
+    ($name:ident, $ty:ty) => {
+        struct $name($ty);
+        
+        impl Rng for $name {
+            fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+                rand_core::RngCore::fill_bytes(&mut self.0, dest);
+                Ok(())
+            }
+            
+            fn next_u32(&mut self) -> u32 {
+                rand_core::RngCore::next_u32(&mut self.0)
+            }
+            
+            fn next_u64(&mut self) -> u64 {
+                rand_core::RngCore::next_u64(&mut self.0)
+            }
+        }
+        
+        impl Backend for $name {
+            const NAME: &'static str = stringify!($name);
+            const SEED: &'static [u8] = &[
+                0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+                16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+            ];
+            
+            fn from_seed(seed: &[u8]) -> Self {
+                let mut s = [0u8; 32];
+                s.copy_from_slice(seed);
+                $name(rand_core::SeedableRng::from_seed(s))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "rand_chacha")]
+rng_core_backend!(ChaCha20Rng, rand_chacha::ChaCha20Rng);
+#[cfg(feature = "rand_pcg")]
+rng_core_backend!(Pcg64, rand_pcg::Pcg64);
+
+// ——— broken backends ———
+
+/// SplitMix64 filling with native-order words, as on an `O` host.
+struct NativeFill<O>(SplitMix, PhantomData<O>);
+
+impl<O: ByteOrder> Rng for NativeFill<O> {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for chunk in dest.chunks_mut(8) {
+            let n = chunk.len();
+            chunk.copy_from_slice(&O::bytes(self.next_u64())[..n]);
+        }
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        use marker_only::test_rngs::Source;
+        self.0.word()
+    }
+}
+
+impl<O: ByteOrder> Backend for NativeFill<O> {
+    const NAME: &'static str = "SplitMix64";
+    const SEED: &'static [u8] = SPLIT_MIX_SEED;
+    
+    fn from_seed(seed: &[u8]) -> Self {
+        NativeFill(split_mix(seed), PhantomData)
+    }
+}
+
+// ——— checks ———
+
+/// Checks `B` against its vector, on a host of order `O`; returns the names
+/// of the failed checks.
+fn check<B: Backend, O: ByteOrder>() -> Vec<&'static str> {
+    let v = VECTORS.iter().find(|v| v.backend == B::NAME)
+        .unwrap_or_else(|| panic!("{}: no committed vector", B::NAME));
+    assert_eq!(v.seed, B::SEED, "{}: seed differs from the committed one", B::NAME);
+    let rng = || B::from_seed(v.seed);
+    let mut failed = vec![];
+    
+    let mut r = rng();
+    if (0..4).map(|_| r.next_u32()).ne(v.u32s.iter().cloned()) {
+        failed.push("next_u32");
+    }
+    let mut r = rng();
+    if (0..4).map(|_| r.next_u64()).ne(v.u64s.iter().cloned()) {
+        failed.push("next_u64");
+    }
+    let mut r = rng();
+    let x = r.next_u128();
+    if x != (v.u64s[1] as u128) << 64 | v.u64s[0] as u128 {
+        failed.push("next_u128");
+    }
+    let mut buf = [0u8; FILL_LEN];
+    rng().fill(&mut buf);
+    if buf != v.fill {
+        failed.push("fill");
+    }
+    
+    // `fill_slice` agrees with `fill` read little-endian, which for these
+    // generators is the words
+    let (mut a, mut b) = ([0u32; 4], [0u64; 2]);
+    fill_slice(&mut rng(), &mut a[..]);
+    fill_slice(&mut rng(), &mut b[..]);
+    let lo = |x: u64| [x as u32, (x >> 32) as u32];
+    let words32 = [lo(v.u64s[0]), lo(v.u64s[1])].concat();
+    if a[..] != words32[..] || b[..] != v.u64s[..2] {
+        failed.push("fill_slice");
+    }
+    
+    // The committed bytes, as an `O` host reads them: the words, in `O`'s
+    // terms. Little-endian hosts see them as they are
+    let mut read_back = true;
+    for (i, chunk) in v.fill.chunks_exact(8).enumerate() {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(chunk);
+        read_back &= O::word(b) == O::word(v.u64s[i].to_le_bytes());
+    }
+    if !read_back {
+        failed.push("read back");
+    }
+    
+    println!("{}, {}: {}", B::NAME, O::NAME,
+        if failed.is_empty() { "ok".to_string() } else { format!("FAILED {:?}", failed) });
+    failed
+}
+
+/// Checks `B` on both byte orders, or prints its vector.
+fn check_both<B: Backend>(print: bool) -> usize {
+    if print {
+        let rng = || B::from_seed(B::SEED);
+        let mut r = rng();
+        let u32s: Vec<u32> = (0..4).map(|_| r.next_u32()).collect();
+        let mut r = rng();
+        let u64s: Vec<u64> = (0..4).map(|_| r.next_u64()).collect();
+        let mut fill = [0u8; FILL_LEN];
+        rng().fill(&mut fill);
+        println!("    Vector {{");
+        println!("        backend: {:?},", B::NAME);
+        println!("        seed: &{:?},", B::SEED);
+        let u32s: Vec<String> = u32s.iter().map(|x| format!("{:#010x}", x)).collect();
+        println!("        u32s: [{}],", u32s.join(", "));
+        println!("        u64s: [");
+        for x in u64s {
+            println!("            {:#018x},", x);
+        }
+        println!("        ],");
+        println!("        fill: [");
+        for chunk in fill.chunks(8) {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("{:#04x}", b)).collect();
+            println!("            {},", bytes.join(", "));
+        }
+        println!("        ],");
+        println!("    }},");
+        return 0;
+    }
+    check::<B, Little>().len() + check::<B, Big>().len()
+}
+
+// ——— usage ———
+
+fn main() {
+    let print = env::args().nth(1).as_deref() == Some("print");
+    let mut failures = 0;
+    failures += check_both::<Xoshiro>(print);
+    failures += check_both::<TestRng<SplitMix>>(print);
+    failures += check_both::<Word32Rng<SplitMix>>(print);
+    #[cfg(feature = "rand_chacha")] {
+        failures += check_both::<ChaCha20Rng>(print);
+    }
+    #[cfg(feature = "rand_pcg")] {
+        failures += check_both::<Pcg64>(print);
+    }
+    if print {
+        return;
+    }
+    assert_eq!(failures, 0, "{} check(s) failed; see above", failures);
+    println!();
+    
+    // Native-order fill: fine on its own host, caught on the other
+    assert!(check::<NativeFill<Host>, Host>().is_empty());
+    #[cfg(target_endian = "little")]
+    let other = check::<NativeFill<Big>, Big>();
+    #[cfg(target_endian = "big")]
+    let other = check::<NativeFill<Little>, Little>();
+    assert_eq!(other, ["fill", "fill_slice"]);
+    println!("native-order fill: reproduces on its own host only, and is caught");
+}
@@ -0,0 +1,377 @@
+//! `rng_test_suite!`: one conformance battery for every backend
+//! 
+//! `rng_test_suite!(MyBackend)` runs a standard battery against a backend
+//! implementing `marker_only::Rng` and the `SeedableRng` below: seed
+//! determinism and sensitivity, fill/next consistency, adaptor transparency
+//! and statistical smoke tests. It prints a line per check and returns the
+//! names of those failed; `rng_test_suite!(T: CryptoRng, Send)` also asserts
+//! capabilities at compile time. `main` runs it over the backends here, and
+//! over three broken ones, each of which must fail exactly its check.
+//! 
+//! Thoughts: the battery is conformance, not quality: its statistics catch a
+//! stuck bit or a counter, not a weak generator. Fill/next consistency is a
+//! convention of these designs, not a law, so a backend may fail it.
+
+// marker_only.rs refers to `::core`, as its own crate root would
+extern crate core;
+
+#[path = "../traits/marker_only.rs"]
+#[allow(dead_code, deprecated, bare_trait_objects)]
+pub mod marker_only;
+
+#[path = "chi_squared.rs"]
+#[allow(dead_code)]
+mod chi_squared;
+
+#[path = "../distributions/simd.rs"]
+#[allow(dead_code, unused_attributes, unused_imports)]
+mod simd;
+
+#[cfg(any(feature = "rand_chacha", feature = "rand_pcg"))]
+extern crate rand_core;
+#[cfg(feature = "rand_chacha")]
+extern crate rand_chacha;
+#[cfg(feature = "rand_pcg")]
+extern crate rand_pcg;
+
+pub use self::marker_only::{CryptoRng, Error, Rng};
+use self::marker_only::test_rngs::{Source, SplitMix};
+
+// ——— traits ———
+
+pub trait SeedableRng: Sized {
+    type Seed: Default + AsMut<[u8]> + Clone;
+    
+    fn from_seed(seed: Self::Seed) -> Self;
+}
+
+// ——— battery ———
+
+/// Runs the battery against `$ty`, asserting any capabilities listed after
+/// a colon at compile time; evaluates to the names of the failed checks.
+macro_rules! rng_test_suite {
+    ($ty:ty $(: $($cap:path),+)?) => {{
+        fn capabilities<T: ?Sized $($(+ $cap)+)?>() {}
+        capabilities::<$ty>();
+        $crate::suite::run::<$ty>(stringify!($ty))
+    }};
+}
+
+pub mod suite {
+    use super::chi_squared::{chi_squared, chi_squared_limit};
+    use super::marker_only::{impl_fill_from_u64, impl_next_u64_from_fill, Rng};
+    use super::SeedableRng;
+    
+    /// A check: `Err` describes the first failure.
+    type Check = fn() -> Result<(), String>;
+    
+    /// Runs every check on `R`, printing the results; returns the names of
+    /// those which failed.
+    pub fn run<R: Rng + SeedableRng + 'static>(name: &str) -> Vec<&'static str> {
+        let checks: [(&'static str, Check); 5] = [
+            ("seed determinism", seed_determinism::<R>),
+            ("seed sensitivity", seed_sensitivity::<R>),
+            ("fill/next consistency", fill_next_consistency::<R>),
+            ("adaptor transparency", adaptor_transparency::<R>),
+            ("statistical smoke tests", statistical_smoke::<R>),
+        ];
+        let mut failed = vec![];
+        for &(check, f) in checks.iter() {
+            match f() {
+                Ok(()) => println!("{}: {}: ok", name, check),
+                Err(e) => {
+                    println!("{}: {}: FAILED: {}", name, check, e);
+                    failed.push(check);
+                }
+            }
+        }
+        failed
+    }
+    
+    /// Bytes 1, 2, 3, ...: never the all-zero seed some backends reject.
+    fn base_seed<R: SeedableRng>() -> R::Seed {
+        let mut seed = R::Seed::default();
+        for (i, b) in seed.as_mut().iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        seed
+    }
+    
+    fn backend<R: SeedableRng>() -> R {
+        R::from_seed(base_seed::<R>())
+    }
+    
+    fn words<R: Rng + ?Sized>(rng: &mut R, n: usize) -> Vec<u64> {
+        (0..n).map(|_| rng.next_u64()).collect()
+    }
+    
+    fn bytes<R: Rng + ?Sized>(rng: &mut R, n: usize) -> Vec<u8> {
+        let mut buf = vec![0; n];
+        rng.fill(&mut buf);
+        buf
+    }
+    
+    fn seed_determinism<R: Rng + SeedableRng>() -> Result<(), String> {
+        let (mut a, mut b) = (backend::<R>(), backend::<R>());
+        if words(&mut a, 64) != words(&mut b, 64) || bytes(&mut a, 100) != bytes(&mut b, 100) {
+            return Err("two instances from one seed differ".into());
+        }
+        Ok(())
+    }
+    
+    fn seed_sensitivity<R: Rng + SeedableRng>() -> Result<(), String> {
+        let base = words(&mut backend::<R>(), 4);
+        let len = base_seed::<R>().as_mut().len();
+        for bit in 0..8 * len {
+            let mut seed = base_seed::<R>();
+            seed.as_mut()[bit / 8] ^= 1 << (bit % 8);
+            if words(&mut R::from_seed(seed), 4) == base {
+                return Err(format!("seed bit {} of {} does not change the output", bit, 8 * len));
+            }
+        }
+        Ok(())
+    }
+    
+    fn fill_next_consistency<R: Rng + SeedableRng>() -> Result<(), String> {
+        let (mut a, mut b) = (backend::<R>(), backend::<R>());
+        for &len in &[0, 1, 7, 8, 13, 64] {
+            let mut helper = vec![0; len];
+            impl_fill_from_u64(&mut b, &mut helper);
+            if bytes(&mut a, len) != helper {
+                return Err(format!("fill of {} bytes differs from little-endian next_u64 words", len));
+            }
+            if a.next_u64() != b.next_u64() {
+                return Err(format!("fill of {} bytes consumed the wrong number of words", len));
+            }
+        }
+        let (mut buf, mut try_buf) = ([0; 37], [0; 37]);
+        a.fill(&mut buf);
+        b.try_fill(&mut try_buf).map_err(|e| format!("try_fill failed: {:?}", e))?;
+        if buf != try_buf {
+            return Err("try_fill differs from fill".into());
+        }
+        if (0..8).any(|_| a.next_u64() != impl_next_u64_from_fill(&mut b)) {
+            return Err("impl_next_u64_from_fill differs from next_u64".into());
+        }
+        Ok(())
+    }
+    
+    fn adaptor_transparency<R: Rng + SeedableRng + 'static>() -> Result<(), String> {
+        let expected = {
+            let mut rng = backend::<R>();
+            (words(&mut rng, 16), bytes(&mut rng, 37))
+        };
+        let mut rng = backend::<R>();
+        let by_ref: &mut dyn Rng = &mut rng;
+        if (words(by_ref, 16), bytes(by_ref, 37)) != expected {
+            return Err("&mut dyn Rng changes the stream".into());
+        }
+        let mut boxed: Box<dyn Rng> = Box::new(backend::<R>());
+        if (words(&mut *boxed, 16), bytes(&mut *boxed, 37)) != expected {
+            return Err("Box<dyn Rng> changes the stream".into());
+        }
+        Ok(())
+    }
+    
+    fn statistical_smoke<R: Rng + SeedableRng>() -> Result<(), String> {
+        let mut rng = backend::<R>();
+        // Each bit set in half of 2^14 words, within five standard deviations
+        let xs = words(&mut rng, 1 << 14);
+        for bit in 0..64 {
+            let ones = xs.iter().filter(|&&x| x >> bit & 1 == 1).count() as f64;
+            if (ones - 8192.0).abs() > 5.0 * 64.0 {
+                return Err(format!("bit {} set in {} of 16384 words", bit, ones));
+            }
+        }
+        let mut counts = [0u64; 256];
+        for b in bytes(&mut rng, 1 << 16) {
+            counts[b as usize] += 1;
+        }
+        if chi_squared(&counts) >= chi_squared_limit(255) {
+            return Err(format!("byte chi-squared {:.0}", chi_squared(&counts)));
+        }
+        let mut xs = words(&mut rng, 4096);
+        xs.sort();
+        if xs.windows(2).any(|w| w[0] == w[1]) {
+            return Err("a word repeats within 4096".into());
+        }
+        Ok(())
+    }
+}
+
+// ——— backends ———
+
+/// xoshiro128++ from `simd.rs`, which implements another trait copy.
+struct Xoshiro(simd::Xoshiro128);
+
+impl Rng for Xoshiro {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        marker_only::impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        simd::Rng::next_u64(&mut self.0)
+    }
+}
+
+impl SeedableRng for Xoshiro {
+    type Seed = [u8; 16];
+    
+    /// Panics on the all-zero seed, as `Xoshiro128::new` does.
+    fn from_seed(seed: [u8; 16]) -> Self {
+        let mut words = [0u32; 4];
+        for (w, b) in words.iter_mut().zip(seed.chunks(4)) {
+            *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        Xoshiro(simd::Xoshiro128::new(words))
+    }
+}
+
+/// SplitMix64 of `test_rngs.rs`.
+impl Rng for SplitMix {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        marker_only::impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.word()
+    }
+}
+
+impl SeedableRng for SplitMix {
+    type Seed = [u8; 8];
+    
+    fn from_seed(seed: [u8; 8]) -> Self {
+        SplitMix(u64::from_le_bytes(seed))
+    }
+}
+
+/// Backends from other crates, via `rand_core::RngCore`.
+#[allow(unused_macros)]
+macro_rules! rng_core_backend {
+    ($name:ident, $ty:ty) => {
+        struct $name($ty);
+        
+        impl Rng for $name {
+            fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+                rand_core::RngCore::fill_bytes(&mut self.0, dest);
+                Ok(())
+            }
+            
+            fn next_u64(&mut self) -> u64 {
+                rand_core::RngCore::next_u64(&mut self.0)
+            }
+        }
+        
+        impl SeedableRng for $name {
+            type Seed = [u8; 32];
+            
+            fn from_seed(seed: [u8; 32]) -> Self {
+                $name(rand_core::SeedableRng::from_seed(seed))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "rand_chacha")]
+rng_core_backend!(ChaCha20Rng, rand_chacha::ChaCha20Rng);
+#[cfg(feature = "rand_chacha")]
+impl CryptoRng for ChaCha20Rng {}
+#[cfg(feature = "rand_pcg")]
+rng_core_backend!(Pcg64, rand_pcg::Pcg64);
+
+// ——— broken backends ———
+
+/// SplitMix64 seeded from only the first half of its 16-byte seed.
+struct HalfSeed(SplitMix);
+
+impl Rng for HalfSeed {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.0.try_fill(dest)
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+impl SeedableRng for HalfSeed {
+    type Seed = [u8; 16];
+    
+    fn from_seed(seed: [u8; 16]) -> Self {
+        let mut half = [0; 8];
+        half.copy_from_slice(&seed[..8]);
+        HalfSeed(SplitMix::from_seed(half))
+    }
+}
+
+/// SplitMix64 filling with big-endian words.
+struct BigEndianFill(SplitMix);
+
+impl Rng for BigEndianFill {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.0.next_u64().to_be_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+}
+
+impl SeedableRng for BigEndianFill {
+    type Seed = [u8; 8];
+    
+    fn from_seed(seed: [u8; 8]) -> Self {
+        BigEndianFill(SplitMix::from_seed(seed))
+    }
+}
+
+/// SplitMix64 with the low bit stuck at 1.
+struct StuckBit(SplitMix);
+
+impl Rng for StuckBit {
+    fn try_fill(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        marker_only::impl_fill_from_u64(self, dest);
+        Ok(())
+    }
+    
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64() | 1
+    }
+}
+
+impl SeedableRng for StuckBit {
+    type Seed = [u8; 8];
+    
+    fn from_seed(seed: [u8; 8]) -> Self {
+        StuckBit(SplitMix::from_seed(seed))
+    }
+}
+
+// ——— usage ———
+
+fn main() {
+    let mut failed = vec![];
+    failed.extend(rng_test_suite!(Xoshiro: Rng, Send));
+    failed.extend(rng_test_suite!(SplitMix: Rng, Send));
+    #[cfg(feature = "rand_chacha")] {
+        failed.extend(rng_test_suite!(ChaCha20Rng: CryptoRng, Send));
+    }
+    #[cfg(feature = "rand_pcg")] {
+        failed.extend(rng_test_suite!(Pcg64: Rng, Send));
+    }
+    assert!(failed.is_empty(), "failed: {:?}", failed);
+    println!();
+    
+    // Each broken backend fails its check, and only that
+    assert_eq!(rng_test_suite!(HalfSeed), ["seed sensitivity"]);
+    assert_eq!(rng_test_suite!(BigEndianFill), ["fill/next consistency"]);
+    assert_eq!(rng_test_suite!(StuckBit), ["statistical smoke tests"]);
+    println!("broken backends: each caught by its check");
+}